@@ -7,20 +7,29 @@ use lsp_types::request::{
     CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls, CallHierarchyPrepare,
     CodeActionRequest, CodeActionResolveRequest, CodeLensRequest, Completion,
     DocumentHighlightRequest, DocumentLinkRequest, DocumentSymbolRequest, ExecuteCommand,
-    FoldingRangeRequest, GotoDefinition, GotoImplementation, GotoImplementationParams,
+    FoldingRangeRequest, Formatting, GotoDefinition, GotoImplementation, GotoImplementationParams,
     GotoTypeDefinition, GotoTypeDefinitionParams, HoverRequest, InlayHintRequest,
-    InlayHintResolveRequest, References, ResolveCompletionItem, SelectionRangeRequest,
-    SemanticTokensFullRequest, SignatureHelpRequest, WillRenameFiles, WorkspaceSymbol,
+    InlayHintResolveRequest, OnTypeFormatting, RangeFormatting, References, ResolveCompletionItem,
+    SelectionRangeRequest, SemanticTokensFullDeltaRequest, SemanticTokensFullRequest,
+    SemanticTokensRangeRequest, SignatureHelpRequest, WillRenameFiles, WorkspaceSymbol,
 };
 use lsp_types::{
     CallHierarchyIncomingCallsParams, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams,
     CodeAction, CodeActionParams, CodeLensParams, CompletionItem, CompletionParams,
-    DocumentHighlightParams, DocumentLinkParams, DocumentSymbolParams, ExecuteCommandParams,
-    FoldingRangeParams, GotoDefinitionParams, HoverParams, InlayHint, InlayHintParams,
-    ReferenceParams, RenameFilesParams, SelectionRangeParams, SemanticTokensParams,
+    DocumentFormattingParams, DocumentHighlightParams, DocumentLinkParams,
+    DocumentOnTypeFormattingParams, DocumentRangeFormattingParams, DocumentSymbolParams,
+    ExecuteCommandParams, FoldingRangeParams, GotoDefinitionParams, HoverParams, InlayHint,
+    InlayHintParams, ReferenceParams, RenameFilesParams, SelectionRangeParams,
+    SemanticTokensDeltaParams, SemanticTokensParams, SemanticTokensRangeParams,
     SignatureHelpParams, WorkspaceSymbolParams,
 };
 
+use crate::message::{
+    DocumentDiagnosticParams, DocumentDiagnosticRequest, TypeHierarchyPrepare,
+    TypeHierarchyPrepareParams, TypeHierarchySubtypes, TypeHierarchySubtypesParams,
+    TypeHierarchySupertypes, TypeHierarchySupertypesParams, WorkspaceDiagnosticParams,
+    WorkspaceDiagnosticRequest,
+};
 use crate::server::Server;
 
 #[derive(Debug, Clone)]
@@ -43,6 +52,8 @@ pub struct SendChannels {
     goto_type_definition: mpsc::Sender<WorkerMessage<GotoTypeDefinitionParams>>,
     goto_implementation: mpsc::Sender<WorkerMessage<GotoImplementationParams>>,
     semantic_tokens_full: mpsc::Sender<WorkerMessage<SemanticTokensParams>>,
+    semantic_tokens_full_delta: mpsc::Sender<WorkerMessage<SemanticTokensDeltaParams>>,
+    semantic_tokens_range: mpsc::Sender<WorkerMessage<SemanticTokensRangeParams>>,
     inlay_hint: mpsc::Sender<WorkerMessage<InlayHintParams>>,
     inlay_hint_resolve: mpsc::Sender<WorkerMessage<InlayHint>>,
     hover: mpsc::Sender<WorkerMessage<HoverParams>>,
@@ -62,6 +73,14 @@ pub struct SendChannels {
     selection_range: mpsc::Sender<WorkerMessage<SelectionRangeParams>>,
     document_highlight: mpsc::Sender<WorkerMessage<DocumentHighlightParams>>,
     document_link: mpsc::Sender<WorkerMessage<DocumentLinkParams>>,
+    formatting: mpsc::Sender<WorkerMessage<DocumentFormattingParams>>,
+    range_formatting: mpsc::Sender<WorkerMessage<DocumentRangeFormattingParams>>,
+    on_type_formatting: mpsc::Sender<WorkerMessage<DocumentOnTypeFormattingParams>>,
+    type_hierarchy_prepare: mpsc::Sender<WorkerMessage<TypeHierarchyPrepareParams>>,
+    type_hierarchy_supertypes: mpsc::Sender<WorkerMessage<TypeHierarchySupertypesParams>>,
+    type_hierarchy_subtypes: mpsc::Sender<WorkerMessage<TypeHierarchySubtypesParams>>,
+    document_diagnostic: mpsc::Sender<WorkerMessage<DocumentDiagnosticParams>>,
+    workspace_diagnostic: mpsc::Sender<WorkerMessage<WorkspaceDiagnosticParams>>,
     pub(crate) health_check: mpsc::Sender<WorkerMessage<()>>,
 }
 
@@ -73,6 +92,8 @@ impl SendChannels {
         let (tx_goto_type_definition, rx_goto_type_definition) = mpsc::channel();
         let (tx_goto_implementation, rx_goto_implementation) = mpsc::channel();
         let (tx_semantic_tokens_full, rx_semantic_tokens_full) = mpsc::channel();
+        let (tx_semantic_tokens_full_delta, rx_semantic_tokens_full_delta) = mpsc::channel();
+        let (tx_semantic_tokens_range, rx_semantic_tokens_range) = mpsc::channel();
         let (tx_inlay_hint, rx_inlay_hint) = mpsc::channel();
         let (tx_inlay_hint_resolve, rx_inlay_hint_resolve) = mpsc::channel();
         let (tx_hover, rx_hover) = mpsc::channel();
@@ -92,6 +113,14 @@ impl SendChannels {
         let (tx_selection_range, rx_selection_range) = mpsc::channel();
         let (tx_document_highlight, rx_document_highlight) = mpsc::channel();
         let (tx_document_link, rx_document_link) = mpsc::channel();
+        let (tx_formatting, rx_formatting) = mpsc::channel();
+        let (tx_range_formatting, rx_range_formatting) = mpsc::channel();
+        let (tx_on_type_formatting, rx_on_type_formatting) = mpsc::channel();
+        let (tx_type_hierarchy_prepare, rx_type_hierarchy_prepare) = mpsc::channel();
+        let (tx_type_hierarchy_supertypes, rx_type_hierarchy_supertypes) = mpsc::channel();
+        let (tx_type_hierarchy_subtypes, rx_type_hierarchy_subtypes) = mpsc::channel();
+        let (tx_document_diagnostic, rx_document_diagnostic) = mpsc::channel();
+        let (tx_workspace_diagnostic, rx_workspace_diagnostic) = mpsc::channel();
         let (tx_health_check, rx_health_check) = mpsc::channel();
         (
             Self {
@@ -101,6 +130,8 @@ impl SendChannels {
                 goto_type_definition: tx_goto_type_definition,
                 goto_implementation: tx_goto_implementation,
                 semantic_tokens_full: tx_semantic_tokens_full,
+                semantic_tokens_full_delta: tx_semantic_tokens_full_delta,
+                semantic_tokens_range: tx_semantic_tokens_range,
                 inlay_hint: tx_inlay_hint,
                 inlay_hint_resolve: tx_inlay_hint_resolve,
                 hover: tx_hover,
@@ -120,6 +151,14 @@ impl SendChannels {
                 selection_range: tx_selection_range,
                 document_highlight: tx_document_highlight,
                 document_link: tx_document_link,
+                formatting: tx_formatting,
+                range_formatting: tx_range_formatting,
+                on_type_formatting: tx_on_type_formatting,
+                type_hierarchy_prepare: tx_type_hierarchy_prepare,
+                type_hierarchy_supertypes: tx_type_hierarchy_supertypes,
+                type_hierarchy_subtypes: tx_type_hierarchy_subtypes,
+                document_diagnostic: tx_document_diagnostic,
+                workspace_diagnostic: tx_workspace_diagnostic,
                 health_check: tx_health_check,
             },
             ReceiveChannels {
@@ -129,6 +168,8 @@ impl SendChannels {
                 goto_type_definition: rx_goto_type_definition,
                 goto_implementation: rx_goto_implementation,
                 semantic_tokens_full: rx_semantic_tokens_full,
+                semantic_tokens_full_delta: rx_semantic_tokens_full_delta,
+                semantic_tokens_range: rx_semantic_tokens_range,
                 inlay_hint: rx_inlay_hint,
                 inlay_hint_resolve: rx_inlay_hint_resolve,
                 hover: rx_hover,
@@ -148,6 +189,14 @@ impl SendChannels {
                 selection_range: rx_selection_range,
                 document_highlight: rx_document_highlight,
                 document_link: rx_document_link,
+                formatting: rx_formatting,
+                range_formatting: rx_range_formatting,
+                on_type_formatting: rx_on_type_formatting,
+                type_hierarchy_prepare: rx_type_hierarchy_prepare,
+                type_hierarchy_supertypes: rx_type_hierarchy_supertypes,
+                type_hierarchy_subtypes: rx_type_hierarchy_subtypes,
+                document_diagnostic: rx_document_diagnostic,
+                workspace_diagnostic: rx_workspace_diagnostic,
                 health_check: rx_health_check,
             },
         )
@@ -160,6 +209,8 @@ impl SendChannels {
         let _ = self.goto_type_definition.send(WorkerMessage::Kill);
         let _ = self.goto_implementation.send(WorkerMessage::Kill);
         let _ = self.semantic_tokens_full.send(WorkerMessage::Kill);
+        let _ = self.semantic_tokens_full_delta.send(WorkerMessage::Kill);
+        let _ = self.semantic_tokens_range.send(WorkerMessage::Kill);
         let _ = self.inlay_hint.send(WorkerMessage::Kill);
         let _ = self.inlay_hint_resolve.send(WorkerMessage::Kill);
         let _ = self.hover.send(WorkerMessage::Kill);
@@ -178,6 +229,14 @@ impl SendChannels {
         let _ = self.folding_range.send(WorkerMessage::Kill);
         let _ = self.selection_range.send(WorkerMessage::Kill);
         let _ = self.document_highlight.send(WorkerMessage::Kill);
+        let _ = self.formatting.send(WorkerMessage::Kill);
+        let _ = self.range_formatting.send(WorkerMessage::Kill);
+        let _ = self.on_type_formatting.send(WorkerMessage::Kill);
+        let _ = self.type_hierarchy_prepare.send(WorkerMessage::Kill);
+        let _ = self.type_hierarchy_supertypes.send(WorkerMessage::Kill);
+        let _ = self.type_hierarchy_subtypes.send(WorkerMessage::Kill);
+        let _ = self.document_diagnostic.send(WorkerMessage::Kill);
+        let _ = self.workspace_diagnostic.send(WorkerMessage::Kill);
         let _ = self.health_check.send(WorkerMessage::Kill);
     }
 }
@@ -190,6 +249,8 @@ pub struct ReceiveChannels {
     pub(crate) goto_type_definition: mpsc::Receiver<WorkerMessage<GotoTypeDefinitionParams>>,
     pub(crate) goto_implementation: mpsc::Receiver<WorkerMessage<GotoImplementationParams>>,
     pub(crate) semantic_tokens_full: mpsc::Receiver<WorkerMessage<SemanticTokensParams>>,
+    pub(crate) semantic_tokens_full_delta: mpsc::Receiver<WorkerMessage<SemanticTokensDeltaParams>>,
+    pub(crate) semantic_tokens_range: mpsc::Receiver<WorkerMessage<SemanticTokensRangeParams>>,
     pub(crate) inlay_hint: mpsc::Receiver<WorkerMessage<InlayHintParams>>,
     pub(crate) inlay_hint_resolve: mpsc::Receiver<WorkerMessage<InlayHint>>,
     pub(crate) hover: mpsc::Receiver<WorkerMessage<HoverParams>>,
@@ -211,6 +272,15 @@ pub struct ReceiveChannels {
     pub(crate) selection_range: mpsc::Receiver<WorkerMessage<SelectionRangeParams>>,
     pub(crate) document_highlight: mpsc::Receiver<WorkerMessage<DocumentHighlightParams>>,
     pub(crate) document_link: mpsc::Receiver<WorkerMessage<DocumentLinkParams>>,
+    pub(crate) formatting: mpsc::Receiver<WorkerMessage<DocumentFormattingParams>>,
+    pub(crate) range_formatting: mpsc::Receiver<WorkerMessage<DocumentRangeFormattingParams>>,
+    pub(crate) on_type_formatting: mpsc::Receiver<WorkerMessage<DocumentOnTypeFormattingParams>>,
+    pub(crate) type_hierarchy_prepare: mpsc::Receiver<WorkerMessage<TypeHierarchyPrepareParams>>,
+    pub(crate) type_hierarchy_supertypes:
+        mpsc::Receiver<WorkerMessage<TypeHierarchySupertypesParams>>,
+    pub(crate) type_hierarchy_subtypes: mpsc::Receiver<WorkerMessage<TypeHierarchySubtypesParams>>,
+    pub(crate) document_diagnostic: mpsc::Receiver<WorkerMessage<DocumentDiagnosticParams>>,
+    pub(crate) workspace_diagnostic: mpsc::Receiver<WorkerMessage<WorkspaceDiagnosticParams>>,
     pub(crate) health_check: mpsc::Receiver<WorkerMessage<()>>,
 }
 
@@ -263,6 +333,16 @@ impl_sendable!(
     SemanticTokensParams,
     semantic_tokens_full
 );
+impl_sendable!(
+    SemanticTokensFullDeltaRequest,
+    SemanticTokensDeltaParams,
+    semantic_tokens_full_delta
+);
+impl_sendable!(
+    SemanticTokensRangeRequest,
+    SemanticTokensRangeParams,
+    semantic_tokens_range
+);
 impl_sendable!(InlayHintRequest, InlayHintParams, inlay_hint);
 impl_sendable!(InlayHintResolveRequest, InlayHint, inlay_hint_resolve);
 impl_sendable!(HoverRequest, HoverParams, hover);
@@ -298,3 +378,39 @@ impl_sendable!(
     document_highlight
 );
 impl_sendable!(DocumentLinkRequest, DocumentLinkParams, document_link);
+impl_sendable!(Formatting, DocumentFormattingParams, formatting);
+impl_sendable!(
+    RangeFormatting,
+    DocumentRangeFormattingParams,
+    range_formatting
+);
+impl_sendable!(
+    OnTypeFormatting,
+    DocumentOnTypeFormattingParams,
+    on_type_formatting
+);
+impl_sendable!(
+    TypeHierarchyPrepare,
+    TypeHierarchyPrepareParams,
+    type_hierarchy_prepare
+);
+impl_sendable!(
+    TypeHierarchySupertypes,
+    TypeHierarchySupertypesParams,
+    type_hierarchy_supertypes
+);
+impl_sendable!(
+    TypeHierarchySubtypes,
+    TypeHierarchySubtypesParams,
+    type_hierarchy_subtypes
+);
+impl_sendable!(
+    DocumentDiagnosticRequest,
+    DocumentDiagnosticParams,
+    document_diagnostic
+);
+impl_sendable!(
+    WorkspaceDiagnosticRequest,
+    WorkspaceDiagnosticParams,
+    workspace_diagnostic
+);
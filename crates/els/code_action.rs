@@ -18,21 +18,17 @@ use crate::server::{ELSResult, RedirectableStdout, Server};
 use crate::util::{self, NormalizedUrl};
 
 impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
-    fn gen_eliminate_unused_vars_action(
+    /// Removes the unused variables (and renames the unused parameters to `_`) in `uri`.
+    pub(crate) fn gen_eliminate_unused_vars_edit(
         &self,
-        params: &CodeActionParams,
-    ) -> ELSResult<Option<CodeAction>> {
-        let uri = NormalizedUrl::new(params.text_document.uri.clone());
-        let diags = &params.context.diagnostics;
-        let Some(diag) = diags.first().cloned() else {
-            return Ok(None);
-        };
+        uri: &NormalizedUrl,
+    ) -> ELSResult<Option<WorkspaceEdit>> {
         let mut map = HashMap::new();
-        let Some(visitor) = self.get_visitor(&uri) else {
+        let Some(visitor) = self.get_visitor(uri) else {
             self.send_log("visitor not found")?;
             return Ok(None);
         };
-        let Some(warns) = self.get_warns(&uri) else {
+        let Some(warns) = self.get_warns(uri) else {
             self.send_log("artifact not found")?;
             return Ok(None);
         };
@@ -78,20 +74,37 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                 }
                 Some(_) => {}
                 None => {
-                    let Some(token) = self.file_cache.get_token(&uri, diag.range.start) else {
+                    let Some(range) = util::loc_to_range(warn.core.loc) else {
+                        continue;
+                    };
+                    let Some(token) = self.file_cache.get_token(&uri, range.start) else {
                         continue;
                     };
                     let Some(vi) = visitor.get_info(&token) else {
                         continue;
                     };
                     if vi.kind.is_parameter() {
-                        let edit = TextEdit::new(diag.range, "_".to_string());
+                        let edit = TextEdit::new(range, "_".to_string());
                         map.entry(uri.clone().raw()).or_insert(vec![]).push(edit);
                     }
                 }
             }
         }
-        let edit = WorkspaceEdit::new(map);
+        Ok(Some(WorkspaceEdit::new(map)))
+    }
+
+    fn gen_eliminate_unused_vars_action(
+        &self,
+        params: &CodeActionParams,
+    ) -> ELSResult<Option<CodeAction>> {
+        let uri = NormalizedUrl::new(params.text_document.uri.clone());
+        let diags = &params.context.diagnostics;
+        let Some(diag) = diags.first().cloned() else {
+            return Ok(None);
+        };
+        let Some(edit) = self.gen_eliminate_unused_vars_edit(&uri)? else {
+            return Ok(None);
+        };
         let action = CodeAction {
             title: "Eliminate unused variables".to_string(),
             kind: Some(CodeActionKind::QUICKFIX),
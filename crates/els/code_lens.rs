@@ -48,7 +48,32 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
         Ok(result)
     }
 
-    fn send_class_inherits_lens(&mut self, _uri: &NormalizedUrl) -> ELSResult<Vec<CodeLens>> {
-        Ok(vec![])
+    fn send_class_inherits_lens(&mut self, uri: &NormalizedUrl) -> ELSResult<Vec<CodeLens>> {
+        let mut result = vec![];
+        if let Some(hir) = self.get_hir(uri) {
+            for chunk in hir.module.iter() {
+                let Expr::ClassDef(class_def) = chunk else {
+                    continue;
+                };
+                let class_loc = &class_def.sig.ident().vi.def_loc;
+                let Some(range) = util::loc_to_range(class_loc.loc) else {
+                    continue;
+                };
+                let command = self.gen_show_subclasses_command(class_loc.clone())?;
+                result.push(CodeLens {
+                    range,
+                    command,
+                    data: None,
+                });
+                if let Some(command) = self.gen_show_superclass_command(class_def)? {
+                    result.push(CodeLens {
+                        range,
+                        command: Some(command),
+                        data: None,
+                    });
+                }
+            }
+        }
+        Ok(result)
     }
 }
@@ -1,24 +1,98 @@
+use std::fmt::Write;
+
+use erg_common::set::Set;
+use erg_common::traits::Locational;
 use erg_compiler::erg_parser::parse::Parsable;
 use erg_compiler::varinfo::AbsLocation;
-use serde_json::Value;
+use serde_json::{json, Value};
 
 use erg_compiler::artifact::BuildRunnable;
-use erg_compiler::hir::Expr;
+use erg_compiler::hir::{Accessor, ClassDef, Expr, HIR};
+use erg_compiler::ty::constructors::{mono, poly};
+use erg_compiler::ty::value::GenTypeObj;
+use erg_compiler::ty::Type;
 
-use lsp_types::{Command, ExecuteCommandParams, Location, Url};
+use lsp_types::{ApplyWorkspaceEditParams, Command, ExecuteCommandParams, Location, Url};
 
 use crate::_log;
-use crate::server::{ELSResult, RedirectableStdout, Server};
+use crate::server::{ELSResult, RedirectableStdout, Server, APPLY_EDIT_ID};
 use crate::util::{self, NormalizedUrl};
 
+/// Commands advertised in `ServerCapabilities` (prefixed with `erg.` or `pylyzer.`)
+pub(crate) const COMMANDS: [&str; 7] = [
+    "eliminate_unused_vars",
+    "restart_analysis",
+    "clear_module_cache",
+    "show_hir",
+    "show_desugared_ast",
+    "show_module_decl",
+    "lint_workspace",
+];
+
 impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
     pub(crate) fn handle_execute_command(
         &mut self,
         params: ExecuteCommandParams,
     ) -> ELSResult<Option<Value>> {
         _log!(self, "command requested: {}", params.command);
-        #[allow(clippy::match_single_binding)]
-        match &params.command[..] {
+        let prefix = format!("{}.", self.mode());
+        let command = params
+            .command
+            .strip_prefix(&prefix)
+            .unwrap_or(&params.command);
+        match command {
+            "eliminate_unused_vars" => {
+                let Some(uri) = uri_arg(&params) else {
+                    return Ok(None);
+                };
+                if let Some(edit) = self.gen_eliminate_unused_vars_edit(&uri)? {
+                    let params = ApplyWorkspaceEditParams {
+                        label: Some("Eliminate unused variables".to_string()),
+                        edit,
+                    };
+                    self.send_stdout(&json!({
+                        "jsonrpc": "2.0",
+                        "id": APPLY_EDIT_ID,
+                        "method": "workspace/applyEdit",
+                        "params": params,
+                    }))?;
+                }
+                Ok(None)
+            }
+            "restart_analysis" => {
+                self.clear_module_cache();
+                self.recheck_files(self.file_cache.entries())?;
+                Ok(None)
+            }
+            "clear_module_cache" => {
+                self.clear_module_cache();
+                Ok(None)
+            }
+            "show_hir" => {
+                let Some(uri) = uri_arg(&params) else {
+                    return Ok(None);
+                };
+                let hir = self.get_hir(&uri).map(|hir| hir.to_string());
+                Ok(hir.map(Value::from))
+            }
+            "show_desugared_ast" => {
+                let Some(uri) = uri_arg(&params) else {
+                    return Ok(None);
+                };
+                let ast = self.get_ast(&uri).map(|ast| ast.to_string());
+                Ok(ast.map(Value::from))
+            }
+            "show_module_decl" => {
+                let Some(uri) = uri_arg(&params) else {
+                    return Ok(None);
+                };
+                let decl = self.get_hir(&uri).map(|hir| module_decl(&hir));
+                Ok(decl.map(Value::from))
+            }
+            "lint_workspace" => {
+                self.lint_workspace()?;
+                Ok(None)
+            }
             other => {
                 _log!(self, "unknown command {other}: {params:?}");
                 Ok(None)
@@ -26,6 +100,45 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
         }
     }
 
+    /// The next request will re-analyze the modules.
+    fn clear_module_cache(&mut self) {
+        self.comp_cache.clear();
+        self.shared.clear_all();
+    }
+
+    fn recheck_files(&mut self, uris: impl IntoIterator<Item = NormalizedUrl>) -> ELSResult<()> {
+        let mut checked = Set::new();
+        for uri in uris {
+            let code = self.file_cache.get_entire_code(&uri)?;
+            self.check_file(uri, code, &mut checked)?;
+        }
+        self.send_empty_diagnostics(checked)
+    }
+
+    /// Lints all the `.er` files under the project root (or the working directory).
+    #[cfg(feature = "lint")]
+    fn lint_workspace(&mut self) -> ELSResult<()> {
+        use crate::server::OptionalFeatures;
+        use erg_common::pathutil::project_entry_dir_of;
+        let root = project_entry_dir_of(&self.home).unwrap_or(self.home.clone());
+        let uris = Self::project_files(root);
+        let enabled = self.opt_features.contains(&OptionalFeatures::Lint);
+        if !enabled {
+            self.opt_features.push(OptionalFeatures::Lint);
+        }
+        let res = self.recheck_files(uris);
+        if !enabled {
+            self.opt_features
+                .retain(|feat| feat != &OptionalFeatures::Lint);
+        }
+        res
+    }
+
+    #[cfg(not(feature = "lint"))]
+    fn lint_workspace(&mut self) -> ELSResult<()> {
+        self.send_error_info("ELS is built without the `lint` feature")
+    }
+
     pub(crate) fn gen_show_trait_impls_command(
         &self,
         trait_loc: AbsLocation,
@@ -57,4 +170,175 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
             arguments: Some(vec![uri, position, locations]),
         }))
     }
+
+    /// Returns `true` if `loc` points to the superclass of a class defined in the module
+    fn is_superclass_ref(&self, loc: &Location) -> bool {
+        let uri = NormalizedUrl::new(loc.uri.clone());
+        let Some(hir) = self.get_hir(&uri) else {
+            return false;
+        };
+        hir.module.iter().any(|chunk| match chunk {
+            Expr::ClassDef(class_def) => superclass_of(class_def).is_some_and(|sup| {
+                util::loc_to_range(sup.loc()).is_some_and(|range| range == loc.range)
+            }),
+            _ => false,
+        })
+    }
+
+    pub(crate) fn gen_show_subclasses_command(
+        &self,
+        class_loc: AbsLocation,
+    ) -> ELSResult<Option<Command>> {
+        let refs = self.get_refs_from_abs_loc(&class_loc);
+        let subclasses = refs
+            .into_iter()
+            .filter(|loc| self.is_superclass_ref(loc))
+            .collect::<Vec<_>>();
+        let subclass_len = subclasses.len();
+        let locations = serde_json::to_value(subclasses)?;
+        let Ok(uri) = class_loc.module.ok_or(()).and_then(Url::from_file_path) else {
+            return Ok(None);
+        };
+        let uri = serde_json::to_value(uri)?;
+        let Some(position) = util::loc_to_pos(class_loc.loc) else {
+            return Ok(None);
+        };
+        let position = serde_json::to_value(position)?;
+        Ok(Some(Command {
+            title: format!("{subclass_len} subclasses"),
+            command: "erg.showReferences".to_string(),
+            arguments: Some(vec![uri, position, locations]),
+        }))
+    }
+
+    pub(crate) fn gen_show_superclass_command(
+        &self,
+        class_def: &ClassDef,
+    ) -> ELSResult<Option<Command>> {
+        let Some(sup) = superclass_of(class_def) else {
+            return Ok(None);
+        };
+        let sup_loc = &sup.var_info().def_loc;
+        let Some(location) = sup_loc
+            .module
+            .as_ref()
+            .and_then(|path| Url::from_file_path(path).ok())
+            .zip(util::loc_to_range(sup_loc.loc))
+            .map(|(uri, range)| Location::new(uri, range))
+        else {
+            return Ok(None);
+        };
+        let locations = serde_json::to_value(vec![location])?;
+        let class_loc = &class_def.sig.ident().vi.def_loc;
+        let Ok(uri) = class_loc
+            .module
+            .clone()
+            .ok_or(())
+            .and_then(Url::from_file_path)
+        else {
+            return Ok(None);
+        };
+        let uri = serde_json::to_value(uri)?;
+        let Some(position) = util::loc_to_pos(class_loc.loc) else {
+            return Ok(None);
+        };
+        let position = serde_json::to_value(position)?;
+        Ok(Some(Command {
+            title: format!("inherits from {}", sup.show()),
+            command: "erg.showReferences".to_string(),
+            arguments: Some(vec![uri, position, locations]),
+        }))
+    }
+}
+
+/// `D = Inherit C` -> `C`
+fn superclass_of(class_def: &ClassDef) -> Option<&Accessor> {
+    if !matches!(class_def.obj.as_ref(), GenTypeObj::Subclass(_)) {
+        return None;
+    }
+    match class_def.require_or_sup.as_deref() {
+        Some(Expr::Accessor(acc)) => Some(acc),
+        _ => None,
+    }
+}
+
+fn uri_arg(params: &ExecuteCommandParams) -> Option<NormalizedUrl> {
+    let uri = params.arguments.first()?.as_str()?;
+    Url::parse(uri).ok().map(NormalizedUrl::new)
+}
+
+/// The types defined in the module are referred to by their local names (`foo::C` -> `C`).
+fn decl_of(t: &Type, namespace: &str) -> String {
+    let mut decl = t.clone();
+    for contained in t.contained_ts().into_iter().chain([t.clone()]) {
+        if &contained.namespace()[..] != namespace {
+            continue;
+        }
+        let local = match &contained {
+            Type::Mono(_) => mono(contained.local_name()),
+            Type::Poly { params, .. } => poly(contained.local_name(), params.clone()),
+            _ => continue,
+        };
+        decl = decl.replace(&contained, &local);
+    }
+    decl.to_string()
+}
+
+/// Generates the declaration (`.d.er`) of the module from the inferred types.
+fn module_decl(hir: &HIR) -> String {
+    let mut decl = String::new();
+    for chunk in hir.module.iter() {
+        match chunk {
+            Expr::Def(def) => {
+                let ns = &def.sig.ident().vi.vis.def_namespace;
+                let vis = if def.sig.vis().is_public() { "." } else { "" };
+                let name = def.sig.inspect();
+                let t = if def.def_kind().is_trait() {
+                    "TraitType".to_string()
+                } else if def.def_kind().is_class() {
+                    "ClassType".to_string()
+                } else {
+                    decl_of(&def.sig.ident().vi.t, ns)
+                };
+                let _ = writeln!(decl, "{vis}{name}: {t}");
+            }
+            Expr::ClassDef(class_def) => {
+                let ns = &class_def.sig.ident().vi.vis.def_namespace;
+                let vis = if class_def.sig.vis().is_public() {
+                    "."
+                } else {
+                    ""
+                };
+                let name = format!("{vis}{}", class_def.sig.inspect());
+                let _ = writeln!(decl, "{name}: ClassType");
+                if let GenTypeObj::Subclass(sub) = class_def.obj.as_ref() {
+                    let _ = writeln!(decl, "{name} <: {}", decl_of(sub.sup.typ(), ns));
+                }
+                for methods in class_def.methods_list.iter() {
+                    if let Some(impl_trait) = methods.impl_trait.as_ref() {
+                        let _ = writeln!(decl, "{name} <: {}", decl_of(impl_trait, ns));
+                    }
+                }
+                let _ = writeln!(decl, "{name}.");
+                if let Some(Type::Record(fields)) = class_def.obj.base_or_sup().map(|t| t.typ()) {
+                    for (field, t) in fields.iter() {
+                        let _ = writeln!(decl, "    {}: {}", field.symbol, decl_of(t, ns));
+                    }
+                }
+                let _ = writeln!(
+                    decl,
+                    "    __call__: {}",
+                    decl_of(&class_def.constructor, ns)
+                );
+                for method in class_def.all_methods() {
+                    if let Expr::Def(def) = method {
+                        let t = decl_of(&def.sig.ident().vi.t, ns);
+                        let _ = writeln!(decl, "    {}: {t}", def.sig.inspect());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    decl
 }
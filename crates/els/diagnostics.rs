@@ -19,7 +19,8 @@ use erg_common::pathutil::{project_entry_dir_of, project_entry_file_of, Normaliz
 use erg_common::set::Set;
 use erg_common::spawn::{safe_yield, spawn_new_thread};
 use erg_common::style::*;
-use erg_common::{fn_name, lsp_log};
+use erg_common::traits::Stream;
+use erg_common::{fn_name, get_hash, lsp_log};
 use erg_compiler::artifact::BuildRunnable;
 use erg_compiler::build_package::CheckStatus;
 use erg_compiler::erg_parser::ast::Module;
@@ -30,8 +31,13 @@ use erg_compiler::error::CompileErrors;
 use crate::_log;
 use crate::channels::WorkerMessage;
 use crate::diff::{ASTDiff, HIRDiff};
+use crate::message::{
+    DocumentDiagnosticParams, DocumentDiagnosticReport, FullDocumentDiagnosticReport,
+    UnchangedDocumentDiagnosticReport, WorkspaceDiagnosticParams, WorkspaceDiagnosticReport,
+    WorkspaceDocumentDiagnosticReport,
+};
 use crate::server::{DefaultFeatures, ELSResult, RedirectableStdout, Server};
-use crate::server::{ASK_AUTO_SAVE_ID, HEALTH_CHECKER_ID};
+use crate::server::{ASK_AUTO_SAVE_ID, DIAGNOSTIC_REFRESH_ID, HEALTH_CHECKER_ID};
 use crate::util::{self, NormalizedUrl};
 
 #[cfg(unix)]
@@ -151,8 +157,19 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
         } else {
             "exec"
         };
+        let normalized = NormalizedPathBuf::from(path.clone());
+        // `get_checker` removes the references from the dependents too
+        let external_refs = self.shared.index.external_refs(&normalized);
+        // otherwise, the diagnostics of the dependents would be collected into the artifact of this module
+        let dependent_diags = self.take_dependent_diags(&normalized);
         let mut checker = self.get_checker(path.clone());
-        let (artifact, status) = match checker.build(code.into(), mode) {
+        // the stored diagnostics of the other files would also be collected into the artifact
+        let stored_errors = self.shared.errors.take();
+        let stored_warns = self.shared.warns.take();
+        let result = checker.build(code.into(), mode);
+        self.shared.errors.extend(stored_errors);
+        self.shared.warns.extend(stored_warns);
+        let (artifact, status) = match result {
             Ok(artifact) => {
                 #[cfg(feature = "lint")]
                 let mut artifact = artifact;
@@ -161,7 +178,6 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                     .opt_features
                     .contains(&crate::server::OptionalFeatures::Lint)
                 {
-                    use erg_common::traits::Stream;
                     let mut linter = erg_linter::Linter::new(self.cfg.inherit(path.clone()));
                     let warns = linter.lint(&artifact.object);
                     artifact.warns.extend(warns);
@@ -171,10 +187,9 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                     "checking {uri} passed, found warns: {}",
                     artifact.warns.len()
                 );
-                self.shared.errors.clear();
-                if artifact.warns.is_empty() {
-                    self.shared.warns.clear();
-                }
+                // discard the previous results (they will be replaced with `artifact.warns`)
+                self.shared.errors.remove(&normalized);
+                self.shared.warns.remove(&normalized);
                 let uri_and_diags = self.make_uri_and_diags(artifact.warns.clone());
                 // clear previous diagnostics
                 self.send_diagnostics(uri.clone().raw(), vec![])?;
@@ -187,9 +202,9 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
             Err(artifact) => {
                 _log!(self, "found errors: {}", artifact.errors.len());
                 _log!(self, "found warns: {}", artifact.warns.len());
-                if artifact.warns.is_empty() {
-                    self.shared.warns.clear();
-                }
+                // discard the previous results (they will be replaced with `artifact.errors`)
+                self.shared.errors.remove(&normalized);
+                self.shared.warns.remove(&normalized);
                 let diags = artifact
                     .errors
                     .clone()
@@ -223,8 +238,18 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
         };
         let Some(ctx) = checker.pop_context() else {
             _log!(self, "context not found");
+            self.restore_dependent_diags(dependent_diags, checked);
             return Ok(());
         };
+        // the interface of a module with errors is not reliable, so the dependents are always rechecked
+        let interface_changed = if status == CheckStatus::Succeed {
+            self.shared
+                .graph
+                .update_interface_hash(&normalized, ctx.interface_hash())
+        } else {
+            self.shared.graph.remove_interface_hash(&normalized);
+            true
+        };
         if mode == "declare" {
             self.shared
                 .py_mod_cache
@@ -234,17 +259,63 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                 .mod_cache
                 .register(path, ast, artifact.object, ctx, status);
         }
-        let dependents = self.dependents_of(&uri);
+        // the references from the dependents to be rechecked will be replaced
+        self.shared.index.restore_external_refs(external_refs);
+        let mut dependents = self.dependents_of(&uri);
+        if !interface_changed && !dependents.is_empty() {
+            // the dependents whose caches have been removed need to be checked anyway
+            dependents.retain(|dep| self.shared.mod_cache.get(&util::uri_to_path(dep)).is_none());
+            _log!(
+                self,
+                "the interface of {uri} is unchanged, rechecking dependents: {}",
+                dependents.len()
+            );
+        }
         for dep in dependents {
             // _log!(self, "dep: {dep}");
             let code = self.file_cache.get_entire_code(&dep)?.to_string();
             self.check_file(dep, code, checked)?;
         }
+        self.restore_dependent_diags(dependent_diags, checked);
         self.shared.errors.extend(artifact.errors);
         self.shared.warns.extend(artifact.warns);
         Ok(())
     }
 
+    /// Takes the errors and warnings of the modules which (indirectly) depend on `path`.
+    fn take_dependent_diags(&self, path: &NormalizedPathBuf) -> (CompileErrors, CompileErrors) {
+        let graph = self.shared.graph.ref_inner();
+        let mut errors = CompileErrors::empty();
+        let mut warns = CompileErrors::empty();
+        for node in graph.iter() {
+            if graph.deep_depends_on(&node.id, path) {
+                errors.extend(self.shared.errors.get(&node.id));
+                warns.extend(self.shared.warns.get(&node.id));
+                self.shared.errors.remove(&node.id);
+                self.shared.warns.remove(&node.id);
+            }
+        }
+        (errors, warns)
+    }
+
+    /// Restores the diagnostics of the dependents which have not been rechecked.
+    fn restore_dependent_diags(
+        &self,
+        (errors, warns): (CompileErrors, CompileErrors),
+        checked: &Set<NormalizedUrl>,
+    ) {
+        let is_unchecked = |err: &erg_compiler::error::CompileError| {
+            NormalizedUrl::from_file_path(err.input.path())
+                .map_or(true, |uri| !checked.contains(&uri))
+        };
+        self.shared
+            .errors
+            .extend(errors.into_iter().filter(is_unchecked).collect());
+        self.shared
+            .warns
+            .extend(warns.into_iter().filter(is_unchecked).collect());
+    }
+
     pub(crate) fn send_empty_diagnostics(&self, checked: Set<NormalizedUrl>) -> ELSResult<()> {
         for checked in checked {
             let Ok(path) = checked.to_file_path() else {
@@ -255,9 +326,24 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                 self.send_diagnostics(checked.raw(), vec![])?;
             }
         }
+        if self.flags.pull_diagnostics() {
+            self.request_diagnostic_refresh()?;
+        }
         Ok(())
     }
 
+    /// Ask the client to pull diagnostics again (`workspace/diagnostic/refresh`)
+    fn request_diagnostic_refresh(&self) -> ELSResult<()> {
+        if !self.flags.diagnostic_refresh() {
+            return Ok(());
+        }
+        self.send_stdout(&json!({
+            "jsonrpc": "2.0",
+            "id": DIAGNOSTIC_REFRESH_ID,
+            "method": "workspace/diagnostic/refresh",
+        }))
+    }
+
     // TODO: reset mutable dependent types
     pub(crate) fn quick_check_file(&mut self, uri: NormalizedUrl) -> ELSResult<()> {
         if self.file_cache.editing.borrow().contains(&uri) {
@@ -368,6 +454,10 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
         {
             return Ok(());
         }
+        // the client will request diagnostics with `textDocument/diagnostic`
+        if self.flags.pull_diagnostics() {
+            return Ok(());
+        }
         let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
         if self
             .init_params
@@ -388,6 +478,79 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
         Ok(())
     }
 
+    /// Diagnostics of `path` stored in the shared compiler resource
+    fn stored_diagnostics(&mut self, path: &NormalizedPathBuf) -> Vec<Diagnostic> {
+        let mut errors = self.shared.errors.get(path);
+        errors.extend(self.shared.warns.get(path));
+        self.make_uri_and_diags(errors)
+            .into_iter()
+            .flat_map(|(_, diags)| diags)
+            .collect()
+    }
+
+    fn diagnostic_report(
+        &mut self,
+        path: &NormalizedPathBuf,
+        previous_result_id: Option<&str>,
+    ) -> ELSResult<DocumentDiagnosticReport> {
+        let items = self.stored_diagnostics(path);
+        let result_id = get_hash(&serde_json::to_string(&items)?).to_string();
+        if previous_result_id == Some(&result_id[..]) {
+            Ok(DocumentDiagnosticReport::Unchanged(
+                UnchangedDocumentDiagnosticReport { result_id },
+            ))
+        } else {
+            Ok(DocumentDiagnosticReport::Full(
+                FullDocumentDiagnosticReport {
+                    result_id: Some(result_id),
+                    items,
+                },
+            ))
+        }
+    }
+
+    pub(crate) fn handle_document_diagnostic(
+        &mut self,
+        params: DocumentDiagnosticParams,
+    ) -> ELSResult<DocumentDiagnosticReport> {
+        _log!(self, "document diagnostic requested: {params:?}");
+        let uri = NormalizedUrl::new(params.text_document.uri);
+        let path = NormalizedPathBuf::from(util::uri_to_path(&uri));
+        self.diagnostic_report(&path, params.previous_result_id.as_deref())
+    }
+
+    pub(crate) fn handle_workspace_diagnostic(
+        &mut self,
+        params: WorkspaceDiagnosticParams,
+    ) -> ELSResult<WorkspaceDiagnosticReport> {
+        _log!(self, "workspace diagnostic requested: {params:?}");
+        let mut previous = Dict::new();
+        for prev in params.previous_result_ids {
+            previous.insert(NormalizedUrl::new(prev.uri), prev.value);
+        }
+        // files with diagnostics, opened files and files the client has results for
+        let mut uris = self
+            .shared
+            .errors
+            .raw_iter()
+            .chain(self.shared.warns.raw_iter())
+            .filter_map(|err| NormalizedUrl::from_file_path(err.input.path()).ok())
+            .collect::<Set<_>>();
+        uris.extend(self.file_cache.entries());
+        uris.extend(previous.keys().cloned());
+        let mut items = vec![];
+        for uri in uris {
+            let path = NormalizedPathBuf::from(util::uri_to_path(&uri));
+            let report = self.diagnostic_report(&path, previous.get(&uri).map(|s| &s[..]))?;
+            items.push(WorkspaceDocumentDiagnosticReport {
+                version: self.file_cache.get_ver(&uri),
+                uri: uri.raw(),
+                report,
+            });
+        }
+        Ok(WorkspaceDiagnosticReport { items })
+    }
+
     /// Periodically send diagnostics without a request from the server.
     /// This is necessary to perform reactive error highlighting in editors such as Vim, where no action is taken until the buffer is saved.
     pub(crate) fn start_auto_diagnostics(&mut self) {
@@ -435,7 +598,7 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
         );
     }
 
-    fn project_files(dir: PathBuf) -> Vec<NormalizedUrl> {
+    pub(crate) fn project_files(dir: PathBuf) -> Vec<NormalizedUrl> {
         let mut uris = vec![];
         let Ok(read_dir) = dir.read_dir() else {
             return uris;
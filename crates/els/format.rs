@@ -0,0 +1,122 @@
+use erg_compiler::artifact::BuildRunnable;
+use erg_compiler::erg_parser::format::Formatter;
+use erg_compiler::erg_parser::parse::Parsable;
+
+use lsp_types::{
+    DocumentFormattingParams, DocumentOnTypeFormattingParams, DocumentRangeFormattingParams,
+    FormattingOptions, Position, Range, TextEdit,
+};
+
+use crate::_log;
+use crate::server::{ELSResult, RedirectableStdout, Server};
+use crate::util::NormalizedUrl;
+
+fn utf16_len(s: &str) -> u32 {
+    s.chars().map(|c| c.len_utf16() as u32).sum()
+}
+
+/// Returns a single edit that replaces only the lines that differ.
+fn minimal_edit(old: &str, new: &str) -> Option<TextEdit> {
+    if old == new {
+        return None;
+    }
+    let old_lines = old.split_inclusive('\n').collect::<Vec<_>>();
+    let new_lines = new.split_inclusive('\n').collect::<Vec<_>>();
+    let prefix = old_lines
+        .iter()
+        .zip(new_lines.iter())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let pos = |line: usize| {
+        if line < old_lines.len() || old.is_empty() || old.ends_with('\n') {
+            Position::new(line as u32, 0)
+        } else {
+            // the end of the last line without a line break
+            let last = old_lines.len() - 1;
+            Position::new(last as u32, utf16_len(old_lines[last]))
+        }
+    };
+    let range = Range::new(pos(prefix), pos(old_lines.len() - suffix));
+    let new_text = new_lines[prefix..new_lines.len() - suffix].concat();
+    Some(TextEdit::new(range, new_text))
+}
+
+impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
+    /// `range`: 1-origin, inclusive
+    fn format_code(
+        &self,
+        uri: &NormalizedUrl,
+        range: Option<(usize, usize)>,
+        options: &FormattingOptions,
+    ) -> ELSResult<Option<Vec<TextEdit>>> {
+        let code = self.file_cache.get_entire_code(uri)?;
+        if !options.insert_spaces {
+            _log!(
+                self,
+                "tabs cannot be used for indentation, spaces are inserted instead"
+            );
+        }
+        let mut formatter =
+            Formatter::new(code.clone()).with_indent_width(options.tab_size as usize);
+        if let Some((ln_begin, ln_end)) = range {
+            formatter = formatter.with_range(ln_begin, ln_end);
+        }
+        match formatter.format() {
+            Ok(formatted) => Ok(Some(minimal_edit(&code, &formatted).into_iter().collect())),
+            Err(err) => {
+                _log!(self, "failed to format {uri}: {err}");
+                Ok(None)
+            }
+        }
+    }
+
+    pub(crate) fn handle_formatting(
+        &mut self,
+        params: DocumentFormattingParams,
+    ) -> ELSResult<Option<Vec<TextEdit>>> {
+        _log!(self, "formatting requested: {params:?}");
+        let uri = NormalizedUrl::new(params.text_document.uri);
+        self.format_code(&uri, None, &params.options)
+    }
+
+    pub(crate) fn handle_range_formatting(
+        &mut self,
+        params: DocumentRangeFormattingParams,
+    ) -> ELSResult<Option<Vec<TextEdit>>> {
+        _log!(self, "range formatting requested: {params:?}");
+        let uri = NormalizedUrl::new(params.text_document.uri);
+        let Range { start, end } = params.range;
+        // a selection that ends at the beginning of a line does not include the line
+        let ln_end = if end.character == 0 && end.line > start.line {
+            end.line
+        } else {
+            end.line + 1
+        };
+        self.format_code(
+            &uri,
+            Some((start.line as usize + 1, ln_end as usize)),
+            &params.options,
+        )
+    }
+
+    pub(crate) fn handle_on_type_formatting(
+        &mut self,
+        params: DocumentOnTypeFormattingParams,
+    ) -> ELSResult<Option<Vec<TextEdit>>> {
+        _log!(self, "on type formatting requested: {params:?}");
+        let uri = NormalizedUrl::new(params.text_document_position.text_document.uri);
+        let pos = params.text_document_position.position;
+        if params.ch != "\n" || pos.line == 0 {
+            return Ok(None);
+        }
+        // format the line just finished
+        let line = pos.line as usize;
+        self.format_code(&uri, Some((line, line)), &params.options)
+    }
+}
@@ -11,6 +11,7 @@ mod doc_highlight;
 mod doc_link;
 mod file_cache;
 mod folding_range;
+mod format;
 mod hir_visitor;
 mod hover;
 mod implementation;
@@ -25,6 +26,15 @@ mod server;
 mod sig_help;
 mod symbol;
 mod type_definition;
+mod type_hierarchy;
 mod util;
+pub use message::{
+    DocumentDiagnosticParams, DocumentDiagnosticReport, DocumentDiagnosticRequest,
+    FullDocumentDiagnosticReport, PreviousResultId, TypeHierarchyItem, TypeHierarchyPrepare,
+    TypeHierarchyPrepareParams, TypeHierarchySubtypes, TypeHierarchySubtypesParams,
+    TypeHierarchySupertypes, TypeHierarchySupertypesParams, UnchangedDocumentDiagnosticReport,
+    WorkspaceDiagnosticParams, WorkspaceDiagnosticReport, WorkspaceDiagnosticRequest,
+    WorkspaceDocumentDiagnosticReport,
+};
 pub use server::*;
 pub use util::*;
@@ -11,6 +11,7 @@ mod doc_highlight;
 mod doc_link;
 mod file_cache;
 mod folding_range;
+mod format;
 mod hir_visitor;
 mod hover;
 mod implementation;
@@ -25,6 +26,7 @@ mod server;
 mod sig_help;
 mod symbol;
 mod type_definition;
+mod type_hierarchy;
 mod util;
 
 use erg_common::config::ErgConfig;
@@ -1,5 +1,6 @@
 use erg_common::dict::Dict;
 use erg_common::error::Location;
+use erg_common::get_hash;
 use erg_common::traits::Locational;
 
 use erg_compiler::artifact::BuildRunnable;
@@ -12,9 +13,13 @@ use erg_compiler::erg_parser::token::TokenKind;
 use erg_compiler::ASTBuilder;
 
 use lsp_types::{
-    SemanticToken, SemanticTokenType, SemanticTokens, SemanticTokensParams, SemanticTokensResult,
+    Range, SemanticToken, SemanticTokenType, SemanticTokens, SemanticTokensDelta,
+    SemanticTokensDeltaParams, SemanticTokensEdit, SemanticTokensFullDeltaResult,
+    SemanticTokensParams, SemanticTokensRangeParams, SemanticTokensRangeResult,
+    SemanticTokensResult,
 };
 
+use crate::_log;
 use crate::server::{ELSResult, RedirectableStdout, Server};
 use crate::util::{self, NormalizedUrl};
 
@@ -284,24 +289,150 @@ impl ASTSemanticState {
     }
 }
 
+fn tokens_result_id(tokens: &[SemanticToken]) -> String {
+    let raw = tokens
+        .iter()
+        .flat_map(|t| {
+            [
+                t.delta_line,
+                t.delta_start,
+                t.length,
+                t.token_type,
+                t.token_modifiers_bitset,
+            ]
+        })
+        .collect::<Vec<_>>();
+    get_hash(&raw).to_string()
+}
+
+/// Computes a single edit that replaces the differing part of `old` with that of `new`.
+/// Note that `start` and `delete_count` are indices of the flattened `u32` array.
+fn tokens_edits(old: &[SemanticToken], new: &[SemanticToken]) -> Vec<SemanticTokensEdit> {
+    let prefix = old.iter().zip(new).take_while(|(l, r)| l == r).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(l, r)| l == r)
+        .count();
+    let deleted = old.len() - prefix - suffix;
+    let inserted = &new[prefix..new.len() - suffix];
+    if deleted == 0 && inserted.is_empty() {
+        return vec![];
+    }
+    vec![SemanticTokensEdit {
+        start: (prefix * 5) as u32,
+        delete_count: (deleted * 5) as u32,
+        data: Some(inserted.to_vec()),
+    }]
+}
+
+/// Extracts the tokens within `range` and re-encodes their relative positions
+fn tokens_in_range(tokens: Vec<SemanticToken>, range: Range) -> Vec<SemanticToken> {
+    let (mut line, mut col) = (0, 0);
+    let (mut prev_line, mut prev_col) = (0, 0);
+    let mut result = vec![];
+    for mut token in tokens {
+        line += token.delta_line;
+        col = if token.delta_line == 0 {
+            col + token.delta_start
+        } else {
+            token.delta_start
+        };
+        let before_start = line < range.start.line
+            || (line == range.start.line && col + token.length <= range.start.character);
+        let after_end =
+            line > range.end.line || (line == range.end.line && col >= range.end.character);
+        if before_start || after_end {
+            continue;
+        }
+        token.delta_line = line - prev_line;
+        token.delta_start = if token.delta_line == 0 {
+            col - prev_col
+        } else {
+            col
+        };
+        (prev_line, prev_col) = (line, col);
+        result.push(token);
+    }
+    result
+}
+
 impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
+    fn get_semantic_tokens(&self, uri: &NormalizedUrl) -> ELSResult<Option<SemanticTokens>> {
+        let path = util::uri_to_path(uri);
+        let src = self.file_cache.get_entire_code(uri)?;
+        let mut builder = ASTBuilder::new(self.cfg.inherit(path));
+        match builder.build_without_desugaring(src) {
+            Ok(artifact) => {
+                let mut state = ASTSemanticState::new();
+                Ok(Some(state.enumerate_tokens(artifact.ast)))
+            }
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Assigns a result id to `tokens` and remembers them for subsequent delta requests
+    fn register_semantic_tokens(&self, uri: NormalizedUrl, tokens: &mut SemanticTokens) {
+        tokens.result_id = Some(tokens_result_id(&tokens.data));
+        self.semantic_tokens
+            .borrow_mut()
+            .insert(uri, tokens.clone());
+    }
+
     pub(crate) fn handle_semantic_tokens_full(
         &mut self,
         params: SemanticTokensParams,
     ) -> ELSResult<Option<SemanticTokensResult>> {
         self.send_log(format!("full semantic tokens request: {params:?}"))?;
         let uri = NormalizedUrl::new(params.text_document.uri);
-        let path = util::uri_to_path(&uri);
-        let src = self.file_cache.get_entire_code(&uri)?;
-        let mut builder = ASTBuilder::new(self.cfg.inherit(path));
-        let result = match builder.build_without_desugaring(src) {
-            Ok(artifact) => {
-                let mut state = ASTSemanticState::new();
-                let tokens = state.enumerate_tokens(artifact.ast);
-                Some(SemanticTokensResult::Tokens(tokens))
-            }
-            Err(_) => None,
+        let Some(mut tokens) = self.get_semantic_tokens(&uri)? else {
+            return Ok(None);
         };
-        Ok(result)
+        self.register_semantic_tokens(uri, &mut tokens);
+        Ok(Some(SemanticTokensResult::Tokens(tokens)))
+    }
+
+    pub(crate) fn handle_semantic_tokens_full_delta(
+        &mut self,
+        params: SemanticTokensDeltaParams,
+    ) -> ELSResult<Option<SemanticTokensFullDeltaResult>> {
+        _log!(self, "semantic tokens delta requested: {params:?}");
+        let uri = NormalizedUrl::new(params.text_document.uri);
+        let Some(mut tokens) = self.get_semantic_tokens(&uri)? else {
+            return Ok(None);
+        };
+        let edits = self
+            .semantic_tokens
+            .borrow()
+            .get(&uri)
+            .filter(|prev| prev.result_id.as_ref() == Some(&params.previous_result_id))
+            .map(|prev| tokens_edits(&prev.data, &tokens.data));
+        self.register_semantic_tokens(uri, &mut tokens);
+        let result = match edits {
+            Some(edits) => SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+                result_id: tokens.result_id,
+                edits,
+            }),
+            // the previous result is unknown, so the client needs all tokens
+            None => SemanticTokensFullDeltaResult::Tokens(tokens),
+        };
+        Ok(Some(result))
+    }
+
+    pub(crate) fn handle_semantic_tokens_range(
+        &mut self,
+        params: SemanticTokensRangeParams,
+    ) -> ELSResult<Option<SemanticTokensRangeResult>> {
+        _log!(self, "semantic tokens range requested: {params:?}");
+        let uri = NormalizedUrl::new(params.text_document.uri);
+        let Some(tokens) = self.get_semantic_tokens(&uri)? else {
+            return Ok(None);
+        };
+        let data = tokens_in_range(tokens.data, params.range);
+        Ok(Some(SemanticTokensRangeResult::Tokens(SemanticTokens {
+            result_id: None,
+            data,
+        })))
     }
 }
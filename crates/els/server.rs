@@ -39,20 +39,22 @@ use lsp_types::request::{
     CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls, CallHierarchyPrepare,
     CodeActionRequest, CodeActionResolveRequest, CodeLensRequest, Completion,
     DocumentHighlightRequest, DocumentLinkRequest, DocumentSymbolRequest, ExecuteCommand,
-    FoldingRangeRequest, GotoDefinition, GotoImplementation, GotoTypeDefinition, HoverRequest,
-    InlayHintRequest, InlayHintResolveRequest, References, Rename, Request, ResolveCompletionItem,
-    SelectionRangeRequest, SemanticTokensFullRequest, SignatureHelpRequest, WillRenameFiles,
-    WorkspaceSymbol,
+    FoldingRangeRequest, Formatting, GotoDefinition, GotoImplementation, GotoTypeDefinition,
+    HoverRequest, InlayHintRequest, InlayHintResolveRequest, OnTypeFormatting, RangeFormatting,
+    References, Rename, Request, ResolveCompletionItem, SelectionRangeRequest,
+    SemanticTokensFullDeltaRequest, SemanticTokensFullRequest, SemanticTokensRangeRequest,
+    SignatureHelpRequest, WillRenameFiles, WorkspaceSymbol,
 };
 use lsp_types::{
     CallHierarchyServerCapability, CodeActionKind, CodeActionOptions, CodeActionProviderCapability,
     CodeLensOptions, CompletionOptions, ConfigurationItem, ConfigurationParams,
     DidChangeTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams,
-    DocumentLinkOptions, ExecuteCommandOptions, FoldingRangeProviderCapability,
-    HoverProviderCapability, ImplementationProviderCapability, InitializeParams, InitializeResult,
-    InlayHintOptions, InlayHintServerCapabilities, NumberOrString, OneOf, Position, ProgressParams,
-    ProgressParamsValue, SelectionRangeProviderCapability, SemanticTokenModifier,
-    SemanticTokenType, SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions,
+    DocumentLinkOptions, DocumentOnTypeFormattingOptions, ExecuteCommandOptions,
+    FoldingRangeProviderCapability, HoverProviderCapability, ImplementationProviderCapability,
+    InitializeParams, InitializeResult, InlayHintOptions, InlayHintServerCapabilities,
+    NumberOrString, OneOf, Position, ProgressParams, ProgressParamsValue,
+    SelectionRangeProviderCapability, SemanticTokenModifier, SemanticTokenType, SemanticTokens,
+    SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions,
     SemanticTokensServerCapabilities, ServerCapabilities, SignatureHelpOptions,
     TypeDefinitionProviderCapability, WorkDoneProgress, WorkDoneProgressBegin,
     WorkDoneProgressCreateParams, WorkDoneProgressEnd, WorkDoneProgressOptions,
@@ -63,15 +65,21 @@ use serde_json::json;
 use serde_json::Value;
 
 use crate::channels::{SendChannels, Sendable, WorkerMessage};
+use crate::command::COMMANDS;
 use crate::completion::CompletionCache;
 use crate::file_cache::FileCache;
 use crate::hir_visitor::{ExprKind, HIRVisitor};
-use crate::message::{ErrorMessage, LSPResult};
+use crate::message::{
+    DocumentDiagnosticRequest, ErrorMessage, LSPResult, TypeHierarchyPrepare,
+    TypeHierarchySubtypes, TypeHierarchySupertypes, WorkspaceDiagnosticRequest,
+};
 use crate::scheduler::Scheduler;
 use crate::util::{self, loc_to_pos, NormalizedUrl};
 
 pub const HEALTH_CHECKER_ID: i64 = 10000;
 pub const ASK_AUTO_SAVE_ID: i64 = 10001;
+pub const DIAGNOSTIC_REFRESH_ID: i64 = 10002;
+pub const APPLY_EDIT_ID: i64 = 10003;
 
 pub type ELSResult<T> = Result<T, Box<dyn std::error::Error>>;
 
@@ -98,6 +106,7 @@ pub enum DefaultFeatures {
     DocumentLink,
     FoldingRange,
     SelectionRange,
+    Formatting,
     /* ELS specific features */
     SmartCompletion,
     DeepCompletion,
@@ -136,6 +145,7 @@ impl From<&str> for DefaultFeatures {
             "selectionrange" | "selectionRange" | "selection-range" => {
                 DefaultFeatures::SelectionRange
             }
+            "formatting" | "format" => DefaultFeatures::Formatting,
             "smartcompletion" | "smartCompletion" | "smart-completion" => {
                 DefaultFeatures::SmartCompletion
             }
@@ -178,6 +188,8 @@ pub struct Flags {
     pub(crate) client_initialized: Arc<AtomicBool>,
     pub(crate) workspace_checked: Arc<AtomicBool>,
     pub(crate) builtin_modules_loaded: Arc<AtomicBool>,
+    pub(crate) pull_diagnostics: Arc<AtomicBool>,
+    pub(crate) diagnostic_refresh: Arc<AtomicBool>,
 }
 
 impl Flags {
@@ -193,6 +205,15 @@ impl Flags {
     pub fn builtin_modules_loaded(&self) -> bool {
         self.builtin_modules_loaded.load(Ordering::Relaxed)
     }
+
+    /// The client requests diagnostics by itself (`textDocument/diagnostic`)
+    pub fn pull_diagnostics(&self) -> bool {
+        self.pull_diagnostics.load(Ordering::Relaxed)
+    }
+
+    pub fn diagnostic_refresh(&self) -> bool {
+        self.diagnostic_refresh.load(Ordering::Relaxed)
+    }
 }
 
 /// A Language Server, which can be used any object implementing `BuildRunnable` internally by passing it as a generic parameter.
@@ -207,6 +228,8 @@ pub struct Server<Checker: BuildRunnable = PackageBuilder, Parser: Parsable = Si
     pub(crate) opt_features: Vec<OptionalFeatures>,
     pub(crate) file_cache: FileCache,
     pub(crate) comp_cache: CompletionCache,
+    /// the latest semantic tokens of each file (for `textDocument/semanticTokens/full/delta`)
+    pub(crate) semantic_tokens: Shared<Dict<NormalizedUrl, SemanticTokens>>,
     pub flags: Flags,
     pub(crate) shared: SharedCompilerResource,
     pub(crate) channels: Option<SendChannels>,
@@ -252,6 +275,7 @@ impl<C: BuildRunnable, P: Parsable> Clone for Server<C, P> {
             opt_features: self.opt_features.clone(),
             file_cache: self.file_cache.clone(),
             comp_cache: self.comp_cache.clone(),
+            semantic_tokens: self.semantic_tokens.clone(),
             shared: self.shared.clone(),
             channels: self.channels.clone(),
             flags: self.flags.clone(),
@@ -296,6 +320,7 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                 shared.clone(),
                 external_items,
             ),
+            semantic_tokens: Shared::new(Dict::new()),
             shared,
             cfg,
             home: normalize_path(std::env::current_dir().unwrap_or_default()),
@@ -366,12 +391,7 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                     None
                 }
             });
-            let package = erg_common::config::Package::new(
-                name.leak(),
-                as_name.leak(),
-                version.leak(),
-                path.map(|p| &*p.leak()),
-            );
+            let package = erg_common::config::Package::new(name, as_name, version, path);
             packages.push(package);
         }
         cfg.packages = Arc::from(packages);
@@ -465,10 +485,34 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
         self.send_log("initializing ELS")?;
         if msg.get("params").is_some() && msg["params"].get("capabilities").is_some() {
             self.init_params = InitializeParams::deserialize(&msg["params"])?;
+            // `ClientCapabilities` of lsp-types 0.93 does not have these fields
+            let capas = &msg["params"]["capabilities"];
+            self.flags.pull_diagnostics.store(
+                capas["textDocument"]["diagnostic"].is_object(),
+                Ordering::Relaxed,
+            );
+            self.flags.diagnostic_refresh.store(
+                capas["workspace"]["diagnostics"]["refreshSupport"]
+                    .as_bool()
+                    .unwrap_or(false),
+                Ordering::Relaxed,
+            );
             // self.send_log(format!("set client capabilities: {:?}", self.client_capas))?;
         }
         let mut result = InitializeResult::default();
         result.capabilities = self.init_capabilities();
+        let mut result = serde_json::to_value(result)?;
+        // `ServerCapabilities` of lsp-types 0.93 does not have this field
+        result["capabilities"]["typeHierarchyProvider"] = json!(true);
+        if !self
+            .disabled_features
+            .contains(&DefaultFeatures::Diagnostics)
+        {
+            result["capabilities"]["diagnosticProvider"] = json!({
+                "interFileDependencies": true,
+                "workspaceDiagnostics": true,
+            });
+        }
         self.init_services();
         self.send_stdout(&json!({
             "jsonrpc": "2.0",
@@ -514,8 +558,8 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                 },
             )));
         let mut sema_options = SemanticTokensOptions::default();
-        sema_options.range = Some(false);
-        sema_options.full = Some(SemanticTokensFullOptions::Bool(true));
+        sema_options.range = Some(true);
+        sema_options.full = Some(SemanticTokensFullOptions::Delta { delta: Some(true) });
         sema_options.legend = SemanticTokensLegend {
             token_types: vec![
                 SemanticTokenType::NAMESPACE,
@@ -566,7 +610,10 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
             Some(options)
         };
         capabilities.execute_command_provider = Some(ExecuteCommandOptions {
-            commands: vec![format!("{}.eliminate_unused_vars", self.mode())],
+            commands: COMMANDS
+                .iter()
+                .map(|command| format!("{}.{command}", self.mode()))
+                .collect(),
             work_done_progress_options: WorkDoneProgressOptions::default(),
         });
         capabilities.signature_help_provider = self
@@ -614,6 +661,17 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                 .contains(&DefaultFeatures::DocumentHighlight)
                 .not(),
         ));
+        let formatting = self
+            .disabled_features
+            .contains(&DefaultFeatures::Formatting)
+            .not();
+        capabilities.document_formatting_provider = Some(OneOf::Left(formatting));
+        capabilities.document_range_formatting_provider = Some(OneOf::Left(formatting));
+        capabilities.document_on_type_formatting_provider =
+            formatting.then(|| DocumentOnTypeFormattingOptions {
+                first_trigger_character: "\n".to_string(),
+                more_trigger_character: None,
+            });
         capabilities
     }
 
@@ -656,6 +714,14 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
             receivers.semantic_tokens_full,
             Self::handle_semantic_tokens_full,
         );
+        self.start_service::<SemanticTokensFullDeltaRequest>(
+            receivers.semantic_tokens_full_delta,
+            Self::handle_semantic_tokens_full_delta,
+        );
+        self.start_service::<SemanticTokensRangeRequest>(
+            receivers.semantic_tokens_range,
+            Self::handle_semantic_tokens_range,
+        );
         self.start_service::<InlayHintRequest>(receivers.inlay_hint, Self::handle_inlay_hint);
         self.start_service::<InlayHintResolveRequest>(
             receivers.inlay_hint_resolve,
@@ -717,6 +783,35 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
             receivers.document_link,
             Self::handle_document_link,
         );
+        self.start_service::<Formatting>(receivers.formatting, Self::handle_formatting);
+        self.start_service::<RangeFormatting>(
+            receivers.range_formatting,
+            Self::handle_range_formatting,
+        );
+        self.start_service::<OnTypeFormatting>(
+            receivers.on_type_formatting,
+            Self::handle_on_type_formatting,
+        );
+        self.start_service::<TypeHierarchyPrepare>(
+            receivers.type_hierarchy_prepare,
+            Self::handle_type_hierarchy_prepare,
+        );
+        self.start_service::<TypeHierarchySupertypes>(
+            receivers.type_hierarchy_supertypes,
+            Self::handle_type_hierarchy_supertypes,
+        );
+        self.start_service::<TypeHierarchySubtypes>(
+            receivers.type_hierarchy_subtypes,
+            Self::handle_type_hierarchy_subtypes,
+        );
+        self.start_service::<DocumentDiagnosticRequest>(
+            receivers.document_diagnostic,
+            Self::handle_document_diagnostic,
+        );
+        self.start_service::<WorkspaceDiagnosticRequest>(
+            receivers.workspace_diagnostic,
+            Self::handle_workspace_diagnostic,
+        );
         self.start_client_health_checker(receivers.health_check);
     }
 
@@ -952,6 +1047,12 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
             SemanticTokensFullRequest::METHOD => {
                 self.parse_send::<SemanticTokensFullRequest>(id, msg)
             }
+            SemanticTokensFullDeltaRequest::METHOD => {
+                self.parse_send::<SemanticTokensFullDeltaRequest>(id, msg)
+            }
+            SemanticTokensRangeRequest::METHOD => {
+                self.parse_send::<SemanticTokensRangeRequest>(id, msg)
+            }
             InlayHintRequest::METHOD => self.parse_send::<InlayHintRequest>(id, msg),
             InlayHintResolveRequest::METHOD => self.parse_send::<InlayHintResolveRequest>(id, msg),
             CodeActionRequest::METHOD => self.parse_send::<CodeActionRequest>(id, msg),
@@ -977,6 +1078,18 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
                 self.parse_send::<DocumentHighlightRequest>(id, msg)
             }
             DocumentLinkRequest::METHOD => self.parse_send::<DocumentLinkRequest>(id, msg),
+            Formatting::METHOD => self.parse_send::<Formatting>(id, msg),
+            RangeFormatting::METHOD => self.parse_send::<RangeFormatting>(id, msg),
+            OnTypeFormatting::METHOD => self.parse_send::<OnTypeFormatting>(id, msg),
+            TypeHierarchyPrepare::METHOD => self.parse_send::<TypeHierarchyPrepare>(id, msg),
+            TypeHierarchySupertypes::METHOD => self.parse_send::<TypeHierarchySupertypes>(id, msg),
+            TypeHierarchySubtypes::METHOD => self.parse_send::<TypeHierarchySubtypes>(id, msg),
+            DocumentDiagnosticRequest::METHOD => {
+                self.parse_send::<DocumentDiagnosticRequest>(id, msg)
+            }
+            WorkspaceDiagnosticRequest::METHOD => {
+                self.parse_send::<WorkspaceDiagnosticRequest>(id, msg)
+            }
             other => self.send_error(Some(id), -32600, format!("{other} is not supported")),
         }
     }
@@ -1084,9 +1197,10 @@ impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
     /// TODO: Reuse cache.
     /// Because of the difficulty of caching "transitional types" such as assert casting and mutable dependent types,
     /// the cache is deleted after each analysis.
+    /// The caches of the dependents are kept; they are rechecked (and cleared) only if the interface of the module changes.
     pub(crate) fn get_checker(&self, path: PathBuf) -> Checker {
         let shared = self.shared.clone();
-        shared.clear(&NormalizedPathBuf::from(&path));
+        shared.clear_path(&NormalizedPathBuf::from(&path));
         Checker::inherit(self.cfg.inherit(path), shared)
     }
 
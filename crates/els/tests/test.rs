@@ -1,21 +1,61 @@
 use std::path::Path;
 
 use erg_common::spawn::safe_yield;
+use lsp_types::request::{
+    ExecuteCommand, Formatting, RangeFormatting, SemanticTokensFullDeltaRequest,
+    SemanticTokensFullRequest, SemanticTokensRangeRequest,
+};
 use lsp_types::{
-    CompletionResponse, DiagnosticSeverity, DocumentSymbolResponse, FoldingRange, FoldingRangeKind,
-    GotoDefinitionResponse, HoverContents, InlayHintLabel, MarkedString,
+    CompletionResponse, DiagnosticSeverity, DocumentFormattingParams,
+    DocumentRangeFormattingParams, DocumentSymbolResponse, ExecuteCommandParams, FoldingRange,
+    FoldingRangeKind, FormattingOptions, GotoDefinitionResponse, HoverContents, InlayHintLabel,
+    MarkedString, Position, Range, SemanticToken, SemanticTokensDeltaParams,
+    SemanticTokensFullDeltaResult, SemanticTokensParams, SemanticTokensRangeParams,
+    SemanticTokensRangeResult, SemanticTokensResult, TextDocumentIdentifier,
+    TextDocumentPositionParams, TextEdit,
 };
 const FILE_A: &str = "tests/a.er";
 const FILE_B: &str = "tests/b.er";
 const FILE_C: &str = "tests/c.er";
 const FILE_IMPORTS: &str = "tests/imports.er";
+const FILE_INDENT: &str = "tests/indent.er";
+const FILE_INHERITS: &str = "tests/inherits.er";
 const FILE_INVALID_SYNTAX: &str = "tests/invalid_syntax.er";
 const FILE_RETRIGGER: &str = "tests/retrigger.er";
 const FILE_TOLERANT_COMPLETION: &str = "tests/tolerant_completion.er";
+const FILE_UNFORMATTED: &str = "tests/unformatted.er";
 
-use els::{NormalizedUrl, Server};
+use els::{
+    DocumentDiagnosticParams, DocumentDiagnosticReport, DocumentDiagnosticRequest, NormalizedUrl,
+    PreviousResultId, Server, TypeHierarchyPrepare, TypeHierarchyPrepareParams,
+    TypeHierarchySubtypes, TypeHierarchySubtypesParams, TypeHierarchySupertypes,
+    TypeHierarchySupertypesParams, WorkspaceDiagnosticParams, WorkspaceDiagnosticRequest,
+};
 use erg_proc_macros::exec_new_thread;
-use molc::{add_char, delete_line, oneline_range};
+use molc::{add_char, delete_line, oneline_range, FakeClient};
+
+/// Files are checked only on save
+/// (otherwise the auto-diagnostics thread would recheck them concurrently).
+fn bind_save_only_client() -> FakeClient<Server> {
+    let mut client = Server::bind_fake_client();
+    client.add_handler("workspace/configuration", |msg, server| {
+        server.dispatch(
+            serde_json::json!({ "jsonrpc": "2.0", "id": msg["id"], "result": ["afterDelay"] }),
+        )
+    });
+    client
+}
+
+/// Waits for the end of the current checking (all the diagnostics have been published).
+fn wait_checking_done(client: &mut FakeClient<Server>) -> Result<(), Box<dyn std::error::Error>> {
+    loop {
+        client.wait_messages(1)?;
+        let msg = client.responses.last().unwrap();
+        if msg["method"] == "$/progress" && msg["params"]["value"]["message"] == "checking done" {
+            return Ok(());
+        }
+    }
+}
 
 #[test]
 fn test_open() -> Result<(), Box<dyn std::error::Error>> {
@@ -315,6 +355,40 @@ fn test_goto_definition() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn test_goto_definition_across_modules() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = bind_save_only_client();
+    client.request_initialize()?;
+    client.notify_initialized()?;
+    client.wait_messages(3)?;
+    let uri_b = NormalizedUrl::from_file_path(Path::new(FILE_B).canonicalize()?)?;
+    let uri_c = NormalizedUrl::from_file_path(Path::new(FILE_C).canonicalize()?)?;
+    client.notify_open(FILE_B)?;
+    while NormalizedUrl::new(client.wait_diagnostics()?.uri) != uri_b {}
+    client.notify_open(FILE_C)?;
+    while NormalizedUrl::new(client.wait_diagnostics()?.uri) != uri_c {}
+    // c.er:4 `b.x` -> b.er:3 `.x`
+    let Some(GotoDefinitionResponse::Scalar(location)) =
+        client.request_goto_definition(uri_c.clone().raw(), 3, 19)?
+    else {
+        todo!()
+    };
+    assert_eq!(NormalizedUrl::new(location.uri), uri_b);
+    assert_eq!(&location.range, &oneline_range(2, 1, 2));
+    // insert a line to b.er:1, `.x` is moved to b.er:4
+    client.notify_change(uri_b.clone().raw(), add_char(0, 0, "z = 0\n"))?;
+    client.notify_save(uri_b.clone().raw())?;
+    while NormalizedUrl::new(client.wait_diagnostics()?.uri) != uri_c {}
+    let Some(GotoDefinitionResponse::Scalar(location)) =
+        client.request_goto_definition(uri_c.clone().raw(), 3, 19)?
+    else {
+        todo!()
+    };
+    assert_eq!(NormalizedUrl::new(location.uri), uri_b);
+    assert_eq!(&location.range, &oneline_range(3, 1, 2));
+    Ok(())
+}
+
 #[test]
 #[exec_new_thread]
 fn test_folding_range() -> Result<(), Box<dyn std::error::Error>> {
@@ -338,6 +412,293 @@ fn test_folding_range() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn test_code_lens() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = Server::bind_fake_client();
+    client.request_initialize()?;
+    client.notify_initialized()?;
+    let uri = NormalizedUrl::from_file_path(Path::new(FILE_INHERITS).canonicalize()?)?;
+    client.notify_open(FILE_INHERITS)?;
+    client.wait_messages(2)?;
+    let lenses = client.request_code_lens(uri.raw())?.unwrap();
+    let titles = lenses
+        .iter()
+        .filter(|lens| lens.range.start.line < 6)
+        .map(|lens| {
+            (
+                lens.range.start.line,
+                lens.command.as_ref().unwrap().title.as_str(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        titles,
+        vec![
+            (1, "2 subclasses"),
+            (3, "0 subclasses"),
+            (3, "inherits from C"),
+            (4, "0 subclasses"),
+            (4, "inherits from C"),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_type_hierarchy() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = Server::bind_fake_client();
+    client.request_initialize()?;
+    client.notify_initialized()?;
+    let uri = NormalizedUrl::from_file_path(Path::new(FILE_INHERITS).canonicalize()?)?;
+    client.notify_open(FILE_INHERITS)?;
+    client.wait_messages(2)?;
+    let items = client
+        .request::<TypeHierarchyPrepare>(TypeHierarchyPrepareParams {
+            text_document_position_params: TextDocumentPositionParams::new(
+                TextDocumentIdentifier::new(uri.raw()),
+                Position::new(3, 0),
+            ),
+            work_done_progress_params: Default::default(),
+        })?
+        .unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].name, "D");
+    let sups = client
+        .request::<TypeHierarchySupertypes>(TypeHierarchySupertypesParams {
+            item: items[0].clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })?
+        .unwrap();
+    let class_c = sups.into_iter().find(|item| item.name == "C").unwrap();
+    let mut subs = client
+        .request::<TypeHierarchySubtypes>(TypeHierarchySubtypesParams {
+            item: class_c.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })?
+        .unwrap()
+        .into_iter()
+        .map(|item| item.name)
+        .collect::<Vec<_>>();
+    subs.sort();
+    assert_eq!(subs, vec!["D", "E"]);
+    let sups = client
+        .request::<TypeHierarchySupertypes>(TypeHierarchySupertypesParams {
+            item: class_c,
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })?
+        .unwrap();
+    assert!(sups.iter().any(|item| item.name == "T"));
+    let subs = client
+        .request::<TypeHierarchySubtypes>(TypeHierarchySubtypesParams {
+            item: sups.into_iter().find(|item| item.name == "T").unwrap(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })?
+        .unwrap();
+    assert!(subs.iter().any(|item| item.name == "C"));
+    Ok(())
+}
+
+#[test]
+fn test_execute_command() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = Server::bind_fake_client();
+    client.request_initialize()?;
+    client.notify_initialized()?;
+    let uri = NormalizedUrl::from_file_path(Path::new(FILE_INHERITS).canonicalize()?)?;
+    client.notify_open(FILE_INHERITS)?;
+    client.wait_messages(2)?;
+    let decl = client
+        .request::<ExecuteCommand>(ExecuteCommandParams {
+            command: "erg.show_module_decl".to_string(),
+            arguments: vec![uri.clone().raw().to_string().into()],
+            work_done_progress_params: Default::default(),
+        })?
+        .unwrap();
+    let decl = decl.as_str().unwrap();
+    assert!(decl.contains("C: ClassType\nC <: T\nC.\n    x: Int\n"));
+    assert!(decl.contains("D <: C\n"));
+    assert!(decl.contains("T: TraitType\n"));
+    assert!(!decl.contains("inherits::"), "{decl}");
+    let mut command = |command: &str| {
+        client.request::<ExecuteCommand>(ExecuteCommandParams {
+            command: command.to_string(),
+            arguments: vec![uri.clone().raw().to_string().into()],
+            work_done_progress_params: Default::default(),
+        })
+    };
+    assert!(command("erg.show_hir")?.is_some());
+    assert!(command("erg.clear_module_cache")?.is_none());
+    assert!(command("erg.show_hir")?.is_none());
+    command("erg.restart_analysis")?;
+    assert!(command("erg.show_desugared_ast")?.is_some());
+    Ok(())
+}
+
+#[test]
+fn test_restart_analysis() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = bind_save_only_client();
+    client.request_initialize()?;
+    client.notify_initialized()?;
+    let uri = NormalizedUrl::from_file_path(Path::new(FILE_INHERITS).canonicalize()?)?;
+    client.notify_open(FILE_INHERITS)?;
+    while NormalizedUrl::new(client.wait_diagnostics()?.uri) != uri {}
+    let command = |client: &mut FakeClient<Server>, command: &str| {
+        client.request::<ExecuteCommand>(ExecuteCommandParams {
+            command: command.to_string(),
+            arguments: vec![uri.clone().raw().to_string().into()],
+            work_done_progress_params: Default::default(),
+        })
+    };
+    let published = |client: &FakeClient<Server>| {
+        client
+            .responses
+            .iter()
+            .filter(|msg| {
+                msg["method"] == "textDocument/publishDiagnostics"
+                    && msg["params"]["uri"] == uri.clone().raw().as_str()
+            })
+            .count()
+    };
+    assert!(command(&mut client, "erg.clear_module_cache")?.is_none());
+    assert!(command(&mut client, "erg.show_hir")?.is_none());
+    let before = published(&client);
+    assert!(command(&mut client, "erg.restart_analysis")?.is_none());
+    // the open files are analyzed again
+    assert!(published(&client) > before);
+    assert!(command(&mut client, "erg.show_hir")?.is_some());
+    Ok(())
+}
+
+#[test]
+fn test_lint_workspace() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = bind_save_only_client();
+    client.request_initialize()?;
+    client.notify_initialized()?;
+    let res = client.request::<ExecuteCommand>(ExecuteCommandParams {
+        command: "erg.lint_workspace".to_string(),
+        arguments: vec![],
+        work_done_progress_params: Default::default(),
+    })?;
+    assert!(res.is_none());
+    if cfg!(feature = "lint") {
+        // the files in the workspace are checked without being opened
+        let uri_b = NormalizedUrl::from_file_path(Path::new(FILE_B).canonicalize()?)?;
+        assert!(client.responses.iter().any(|msg| {
+            msg["method"] == "textDocument/publishDiagnostics"
+                && msg["params"]["uri"] == uri_b.clone().raw().as_str()
+        }));
+    } else {
+        assert!(client.responses.iter().any(|msg| {
+            msg["method"] == "window/showMessage"
+                && msg["params"]["message"]
+                    .as_str()
+                    .is_some_and(|msg| msg.contains("`lint` feature"))
+        }));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_eliminate_unused_vars_command() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = bind_save_only_client();
+    client.request_initialize()?;
+    client.notify_initialized()?;
+    let uri = NormalizedUrl::from_file_path(Path::new(FILE_IMPORTS).canonicalize()?)?;
+    client.notify_open(FILE_IMPORTS)?;
+    while NormalizedUrl::new(client.wait_diagnostics()?.uri) != uri {}
+    client.request::<ExecuteCommand>(ExecuteCommandParams {
+        command: "erg.eliminate_unused_vars".to_string(),
+        arguments: vec![uri.clone().raw().to_string().into()],
+        work_done_progress_params: Default::default(),
+    })?;
+    let apply_edit = client
+        .responses
+        .iter()
+        .find(|msg| msg["method"] == "workspace/applyEdit")
+        .unwrap();
+    let edits = apply_edit["params"]["edit"]["changes"][uri.clone().raw().as_str()]
+        .as_array()
+        .unwrap();
+    // glob, importlib, io, math
+    assert_eq!(edits.len(), 4);
+    Ok(())
+}
+
+#[test]
+fn test_formatting() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = Server::bind_fake_client();
+    client.request_initialize()?;
+    client.notify_initialized()?;
+    let uri = NormalizedUrl::from_file_path(Path::new(FILE_UNFORMATTED).canonicalize()?)?;
+    client.notify_open(FILE_UNFORMATTED)?;
+    let options = FormattingOptions {
+        tab_size: 4,
+        insert_spaces: true,
+        ..Default::default()
+    };
+    let edits = client
+        .request::<Formatting>(DocumentFormattingParams {
+            text_document: TextDocumentIdentifier::new(uri.clone().raw()),
+            options: options.clone(),
+            work_done_progress_params: Default::default(),
+        })?
+        .unwrap();
+    assert_eq!(
+        edits,
+        vec![TextEdit::new(
+            Range::new(Position::new(0, 0), Position::new(2, 0)),
+            "f(x, y := 1) = x + y # add\nprint! f(1, y := 2)\n".to_string(),
+        )]
+    );
+    let edits = client
+        .request::<RangeFormatting>(DocumentRangeFormattingParams {
+            text_document: TextDocumentIdentifier::new(uri.raw()),
+            range: oneline_range(1, 0, 16),
+            options,
+            work_done_progress_params: Default::default(),
+        })?
+        .unwrap();
+    assert_eq!(
+        edits,
+        vec![TextEdit::new(
+            Range::new(Position::new(1, 0), Position::new(2, 0)),
+            "print! f(1, y := 2)\n".to_string(),
+        )]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_formatting_options() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = Server::bind_fake_client();
+    client.request_initialize()?;
+    client.notify_initialized()?;
+    let uri = NormalizedUrl::from_file_path(Path::new(FILE_INDENT).canonicalize()?)?;
+    client.notify_open(FILE_INDENT)?;
+    let edits = client
+        .request::<Formatting>(DocumentFormattingParams {
+            text_document: TextDocumentIdentifier::new(uri.raw()),
+            options: FormattingOptions {
+                tab_size: 2,
+                insert_spaces: true,
+                ..Default::default()
+            },
+            work_done_progress_params: Default::default(),
+        })?
+        .unwrap();
+    assert_eq!(
+        edits,
+        vec![TextEdit::new(
+            Range::new(Position::new(1, 0), Position::new(2, 0)),
+            "  x\n".to_string(),
+        )]
+    );
+    Ok(())
+}
+
 #[test]
 fn test_document_symbol() -> Result<(), Box<dyn std::error::Error>> {
     let mut client = Server::bind_fake_client();
@@ -378,18 +739,18 @@ fn test_inlay_hint() -> Result<(), Box<dyn std::error::Error>> {
 #[test]
 #[exec_new_thread]
 fn test_dependents_check() -> Result<(), Box<dyn std::error::Error>> {
-    let mut client = Server::bind_fake_client();
+    let mut client = bind_save_only_client();
     client.request_initialize()?;
     client.notify_initialized()?;
     client.wait_messages(3)?;
+    let uri_b = NormalizedUrl::from_file_path(Path::new(FILE_B).canonicalize()?)?;
+    let uri_c = NormalizedUrl::from_file_path(Path::new(FILE_C).canonicalize()?)?;
     client.notify_open(FILE_B)?;
-    client.wait_messages(6)?;
+    while NormalizedUrl::new(client.wait_diagnostics()?.uri) != uri_b {}
     client.notify_open(FILE_C)?;
-    client.wait_messages(6)?;
-    let uri_b = NormalizedUrl::from_file_path(Path::new(FILE_B).canonicalize()?)?;
+    while NormalizedUrl::new(client.wait_diagnostics()?.uri) != uri_c {}
     // delete b.er:3, causing an error in c.er
     client.notify_change(uri_b.clone().raw(), delete_line(2))?;
-    client.wait_messages(2)?;
     client.responses.clear();
     client.notify_save(uri_b.clone().raw())?;
     let b_diags = client.wait_diagnostics()?;
@@ -406,9 +767,9 @@ fn test_dependents_check() -> Result<(), Box<dyn std::error::Error>> {
         c_diags.diagnostics[0].severity,
         Some(DiagnosticSeverity::ERROR)
     );
+    wait_checking_done(&mut client)?;
     // insert invalid code to b.er:8, causing a syntax error in b.er but not c.er
     client.notify_change(uri_b.clone().raw(), add_char(7, 0, "a.\n"))?;
-    client.wait_messages(1)?;
     client.responses.clear();
     client.notify_save(uri_b.clone().raw())?;
     let b_diags = client.wait_diagnostics()?;
@@ -421,6 +782,70 @@ fn test_dependents_check() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+#[exec_new_thread]
+fn test_skip_dependents_check() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = bind_save_only_client();
+    client.request_initialize()?;
+    client.notify_initialized()?;
+    client.wait_messages(3)?;
+    let uri_b = NormalizedUrl::from_file_path(Path::new(FILE_B).canonicalize()?)?;
+    let uri_c = NormalizedUrl::from_file_path(Path::new(FILE_C).canonicalize()?)?;
+    client.notify_open(FILE_B)?;
+    while NormalizedUrl::new(client.wait_diagnostics()?.uri) != uri_b {}
+    client.notify_open(FILE_C)?;
+    while NormalizedUrl::new(client.wait_diagnostics()?.uri) != uri_c {}
+    let diagnosed = |responses: &[serde_json::Value], uri: &NormalizedUrl| {
+        responses.iter().any(|msg| {
+            msg["method"] == "textDocument/publishDiagnostics"
+                && msg["params"]["uri"] == uri.clone().raw().as_str()
+        })
+    };
+    // add a private variable to b.er:9, the public interface is unchanged
+    client.notify_change(uri_b.clone().raw(), add_char(8, 0, "y = 1\n"))?;
+    client.responses.clear();
+    client.notify_save(uri_b.clone().raw())?;
+    let b_diags = client.wait_diagnostics()?;
+    assert!(b_diags.diagnostics.is_empty(), "{:?}", b_diags.diagnostics);
+    // the request is handled after the checking is done
+    client.request_hover(uri_b.clone().raw(), 2, 1)?;
+    assert!(!diagnosed(&client.responses, &uri_c));
+    // insert a blank line to b.er:1, the AST is unchanged so nothing is rechecked
+    client.notify_change(uri_b.clone().raw(), add_char(0, 0, "\n"))?;
+    client.responses.clear();
+    client.notify_save(uri_b.clone().raw())?;
+    client.request_hover(uri_b.clone().raw(), 3, 1)?;
+    assert!(!diagnosed(&client.responses, &uri_b));
+    assert!(!diagnosed(&client.responses, &uri_c));
+    // add a private variable to b.er:1, all the public definitions are moved so c.er needs to be rechecked
+    client.notify_change(uri_b.clone().raw(), add_char(0, 0, "z = 0\n"))?;
+    client.responses.clear();
+    client.notify_save(uri_b.clone().raw())?;
+    while NormalizedUrl::new(client.wait_diagnostics()?.uri) != uri_c {}
+    // the references from c.er follow the moved definition (b.er:5 `.x`)
+    let locations = client
+        .request_references(uri_b.clone().raw(), 4, 1)?
+        .unwrap();
+    assert_eq!(locations.len(), 1, "{locations:?}");
+    assert_eq!(NormalizedUrl::new(locations[0].uri.clone()), uri_c);
+    assert_eq!(&locations[0].range, &oneline_range(3, 19, 20));
+    // b.er:10 `aplus self, x = self.a + x` -> `aplus self, x = self.a + x + 0`, only the body changes
+    client.notify_change(uri_b.clone().raw(), add_char(9, 30, " + 0"))?;
+    client.responses.clear();
+    client.notify_save(uri_b.clone().raw())?;
+    client.wait_diagnostics()?;
+    client.request_hover(uri_b.clone().raw(), 4, 1)?;
+    assert!(!diagnosed(&client.responses, &uri_c));
+    // b.er:5 `.x = 1` -> `.x = 1.0`, c.er needs to be rechecked
+    client.notify_change(uri_b.clone().raw(), add_char(4, 6, ".0"))?;
+    client.responses.clear();
+    client.notify_save(uri_b.clone().raw())?;
+    client.wait_diagnostics()?;
+    client.request_hover(uri_b.clone().raw(), 4, 1)?;
+    assert!(diagnosed(&client.responses, &uri_c));
+    Ok(())
+}
+
 #[test]
 fn test_fix_error() -> Result<(), Box<dyn std::error::Error>> {
     let mut client = Server::bind_fake_client();
@@ -440,3 +865,181 @@ fn test_fix_error() -> Result<(), Box<dyn std::error::Error>> {
     assert_eq!(diags.diagnostics.len(), 0);
     Ok(())
 }
+
+#[test]
+fn test_pull_diagnostics() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = Server::bind_fake_client();
+    client.request_initialize()?;
+    client.notify_initialized()?;
+    client.notify_open(FILE_INVALID_SYNTAX)?;
+    client.wait_diagnostics()?;
+    let uri = NormalizedUrl::from_file_path(Path::new(FILE_INVALID_SYNTAX).canonicalize()?)?;
+    let params = |previous_result_id| DocumentDiagnosticParams {
+        text_document: TextDocumentIdentifier::new(uri.clone().raw()),
+        identifier: None,
+        previous_result_id,
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+    let DocumentDiagnosticReport::Full(report) =
+        client.request::<DocumentDiagnosticRequest>(params(None))?
+    else {
+        panic!("expected a full report");
+    };
+    assert_eq!(report.items.len(), 1);
+    assert_eq!(report.items[0].severity, Some(DiagnosticSeverity::ERROR));
+    let result_id = report.result_id.unwrap();
+    let report = client.request::<DocumentDiagnosticRequest>(params(Some(result_id.clone())))?;
+    assert!(matches!(report, DocumentDiagnosticReport::Unchanged(_)));
+    let report = client.request::<WorkspaceDiagnosticRequest>(WorkspaceDiagnosticParams {
+        identifier: None,
+        previous_result_ids: vec![PreviousResultId {
+            uri: uri.clone().raw(),
+            value: result_id.clone(),
+        }],
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    })?;
+    let item = report
+        .items
+        .iter()
+        .find(|item| item.uri == uri.clone().raw())
+        .unwrap();
+    assert!(matches!(
+        item.report,
+        DocumentDiagnosticReport::Unchanged(_)
+    ));
+    client.notify_change(uri.clone().raw(), add_char(0, 10, " 1"))?;
+    client.notify_save(uri.clone().raw())?;
+    client.wait_diagnostics()?;
+    let DocumentDiagnosticReport::Full(report) =
+        client.request::<DocumentDiagnosticRequest>(params(Some(result_id)))?
+    else {
+        panic!("expected a full report");
+    };
+    assert!(report.items.is_empty(), "{:?}", report.items);
+    Ok(())
+}
+
+#[test]
+fn test_pull_diagnostics_multiple_files() -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = bind_save_only_client();
+    client.request_initialize()?;
+    client.notify_initialized()?;
+    let uri_err = NormalizedUrl::from_file_path(Path::new(FILE_INVALID_SYNTAX).canonicalize()?)?;
+    let uri_a = NormalizedUrl::from_file_path(Path::new(FILE_A).canonicalize()?)?;
+    client.notify_open(FILE_INVALID_SYNTAX)?;
+    while NormalizedUrl::new(client.wait_diagnostics()?.uri) != uri_err {}
+    client.notify_open(FILE_A)?;
+    while NormalizedUrl::new(client.wait_diagnostics()?.uri) != uri_a {}
+    let workspace_diags = |client: &mut FakeClient<Server>, uri: &NormalizedUrl| {
+        let report = client.request::<WorkspaceDiagnosticRequest>(WorkspaceDiagnosticParams {
+            identifier: None,
+            previous_result_ids: vec![],
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })?;
+        let item = report
+            .items
+            .into_iter()
+            .find(|item| item.uri == uri.clone().raw())
+            .unwrap();
+        let DocumentDiagnosticReport::Full(report) = item.report else {
+            panic!("expected a full report");
+        };
+        Ok::<_, Box<dyn std::error::Error>>(report.items)
+    };
+    // `a.er` has a warning (`y` is not used)
+    client.notify_change(uri_a.clone().raw(), add_char(2, 0, "y = 1\n"))?;
+    client.notify_save(uri_a.clone().raw())?;
+    while NormalizedUrl::new(client.wait_diagnostics()?.uri) != uri_a {}
+    assert_eq!(workspace_diags(&mut client, &uri_a)?.len(), 1);
+    // `a.er` is rechecked successfully without warnings
+    client.notify_change(uri_a.clone().raw(), delete_line(2))?;
+    client.notify_save(uri_a.clone().raw())?;
+    while NormalizedUrl::new(client.wait_diagnostics()?.uri) != uri_a {}
+    assert!(workspace_diags(&mut client, &uri_a)?.is_empty());
+    // the errors of the other file are kept
+    let items = workspace_diags(&mut client, &uri_err)?;
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].severity, Some(DiagnosticSeverity::ERROR));
+    Ok(())
+}
+
+#[test]
+fn test_semantic_tokens() -> Result<(), Box<dyn std::error::Error>> {
+    fn flatten(tokens: &[SemanticToken]) -> Vec<u32> {
+        tokens
+            .iter()
+            .flat_map(|t| {
+                [
+                    t.delta_line,
+                    t.delta_start,
+                    t.length,
+                    t.token_type,
+                    t.token_modifiers_bitset,
+                ]
+            })
+            .collect()
+    }
+    let mut client = Server::bind_fake_client();
+    client.request_initialize()?;
+    client.notify_initialized()?;
+    client.notify_open(FILE_A)?;
+    let uri = NormalizedUrl::from_file_path(Path::new(FILE_A).canonicalize()?)?;
+    let doc = TextDocumentIdentifier::new(uri.clone().raw());
+    let Some(SemanticTokensResult::Tokens(full)) =
+        client.request::<SemanticTokensFullRequest>(SemanticTokensParams {
+            text_document: doc.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })?
+    else {
+        panic!("expected full tokens");
+    };
+    let mut data = flatten(&full.data);
+    // `_ = x + 1` -> `_ = x + 1 + x`
+    client.notify_change(uri.clone().raw(), add_char(1, 9, " + x"))?;
+    let delta_params = SemanticTokensDeltaParams {
+        text_document: doc.clone(),
+        previous_result_id: full.result_id.unwrap(),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    };
+    let Some(SemanticTokensFullDeltaResult::TokensDelta(delta)) =
+        client.request::<SemanticTokensFullDeltaRequest>(delta_params)?
+    else {
+        panic!("expected delta");
+    };
+    assert_eq!(delta.edits.len(), 1);
+    for edit in delta.edits.iter().rev() {
+        let start = edit.start as usize;
+        let inserted = flatten(edit.data.as_deref().unwrap_or_default());
+        data.splice(start..start + edit.delete_count as usize, inserted);
+    }
+    let Some(SemanticTokensResult::Tokens(new_full)) = client
+        .request::<SemanticTokensFullRequest>(SemanticTokensParams {
+            text_document: doc.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })?
+    else {
+        panic!("expected full tokens");
+    };
+    assert_eq!(data, flatten(&new_full.data));
+    let Some(SemanticTokensRangeResult::Tokens(ranged)) = client
+        .request::<SemanticTokensRangeRequest>(SemanticTokensRangeParams {
+            text_document: doc,
+            range: Range::new(Position::new(1, 0), Position::new(2, 0)),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })?
+    else {
+        panic!("expected ranged tokens");
+    };
+    assert!(!ranged.data.is_empty());
+    assert_eq!(ranged.data[0].delta_line, 1);
+    assert!(ranged.data[1..].iter().all(|t| t.delta_line == 0));
+    assert!(ranged.data.len() < new_full.data.len());
+    Ok(())
+}
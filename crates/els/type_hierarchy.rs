@@ -0,0 +1,189 @@
+use std::str::FromStr;
+
+use erg_common::set::Set;
+use erg_common::Str;
+use erg_compiler::artifact::BuildRunnable;
+use erg_compiler::context::{Context, ContextKind};
+use erg_compiler::erg_parser::parse::Parsable;
+use erg_compiler::ty::Type;
+use erg_compiler::varinfo::{AbsLocation, VarInfo};
+
+use crate::_log;
+use crate::message::{
+    TypeHierarchyItem, TypeHierarchyPrepareParams, TypeHierarchySubtypesParams,
+    TypeHierarchySupertypesParams,
+};
+use crate::server::{ELSResult, RedirectableStdout, Server};
+use crate::symbol::symbol_kind;
+use crate::util::{abs_loc_to_lsp_loc, NormalizedUrl};
+
+/// A class, trait or patch registered in the shared compiler resource
+struct TypeEntry<'a> {
+    name: Str,
+    qual_name: Str,
+    vi: &'a VarInfo,
+    ctx: &'a Context,
+}
+
+impl TypeEntry<'_> {
+    fn super_types(&self) -> Vec<&Type> {
+        let mut sups = self
+            .ctx
+            .super_classes()
+            .chain(self.ctx.super_traits())
+            .collect::<Vec<_>>();
+        match &self.ctx.kind {
+            ContextKind::Patch(base) | ContextKind::StructuralPatch(base) => sups.push(base),
+            ContextKind::GluePatch(tr_impl) => {
+                sups.push(&tr_impl.sub_type);
+                sups.push(&tr_impl.sup_trait);
+            }
+            _ => {}
+        }
+        sups
+    }
+
+    fn hierarchy_item(&self) -> Option<TypeHierarchyItem> {
+        let loc = abs_loc_to_lsp_loc(&self.vi.def_loc)?;
+        Some(TypeHierarchyItem {
+            name: self.name.to_string(),
+            kind: symbol_kind(self.vi),
+            tags: None,
+            detail: Some(self.qual_name.to_string()),
+            uri: loc.uri,
+            range: loc.range,
+            selection_range: loc.range,
+            data: Some(self.vi.def_loc.to_string().into()),
+        })
+    }
+}
+
+impl<Checker: BuildRunnable, Parser: Parsable> Server<Checker, Parser> {
+    /// Classes, traits and patches of all modules (including builtins and `.d.er` files)
+    fn get_type_entries(&self) -> Vec<TypeEntry<'_>> {
+        let mut entries = vec![];
+        for ent in self.shared.raw_modules() {
+            let module = &ent.module.context;
+            for type_ctx in module.local_type_ctxs() {
+                let Some((_, vi)) = module.get_var_info(&type_ctx.typ.local_name()) else {
+                    continue;
+                };
+                entries.push(TypeEntry {
+                    name: type_ctx.typ.local_name(),
+                    qual_name: type_ctx.typ.qual_name(),
+                    vi,
+                    ctx: &type_ctx.ctx,
+                });
+            }
+            for (name, patch) in module.local_patches() {
+                let Some((_, vi)) = module.get_var_info(name.inspect()) else {
+                    continue;
+                };
+                entries.push(TypeEntry {
+                    name: name.inspect().clone(),
+                    qual_name: patch.name.clone(),
+                    vi,
+                    ctx: patch,
+                });
+            }
+        }
+        entries
+    }
+
+    fn collect_items<'e>(
+        entries: impl Iterator<Item = &'e TypeEntry<'e>>,
+    ) -> Vec<TypeHierarchyItem> {
+        let mut visited = Set::new();
+        let mut items = vec![];
+        for entry in entries {
+            if !visited.insert(&entry.vi.def_loc) {
+                continue;
+            }
+            items.extend(entry.hierarchy_item());
+        }
+        items
+    }
+
+    pub(crate) fn handle_type_hierarchy_prepare(
+        &mut self,
+        params: TypeHierarchyPrepareParams,
+    ) -> ELSResult<Option<Vec<TypeHierarchyItem>>> {
+        _log!(self, "type hierarchy prepare requested: {params:?}");
+        let uri = NormalizedUrl::new(params.text_document_position_params.text_document.uri);
+        let pos = params.text_document_position_params.position;
+        let Some(token) = self.file_cache.get_symbol(&uri, pos) else {
+            return Ok(None);
+        };
+        let Some(vi) = self.get_definition(&uri, &token)? else {
+            return Ok(None);
+        };
+        let entries = self.get_type_entries();
+        let found = entries
+            .iter()
+            .filter(|entry| entry.vi.def_loc == vi.def_loc);
+        Ok(Some(Self::collect_items(found)))
+    }
+
+    pub(crate) fn handle_type_hierarchy_supertypes(
+        &mut self,
+        params: TypeHierarchySupertypesParams,
+    ) -> ELSResult<Option<Vec<TypeHierarchyItem>>> {
+        _log!(self, "type hierarchy supertypes requested: {params:?}");
+        let Some(data) = params.item.data.as_ref().and_then(|d| d.as_str()) else {
+            return Ok(None);
+        };
+        let Ok(loc) = AbsLocation::from_str(data) else {
+            return Ok(None);
+        };
+        let entries = self.get_type_entries();
+        let Some(target) = entries.iter().find(|entry| entry.vi.def_loc == loc) else {
+            return Ok(None);
+        };
+        let sup_names = target
+            .super_types()
+            .into_iter()
+            .map(|t| t.qual_name())
+            .collect::<Set<_>>();
+        let sups = entries
+            .iter()
+            .filter(|entry| sup_names.contains(&entry.qual_name));
+        Ok(Some(Self::collect_items(sups)))
+    }
+
+    pub(crate) fn handle_type_hierarchy_subtypes(
+        &mut self,
+        params: TypeHierarchySubtypesParams,
+    ) -> ELSResult<Option<Vec<TypeHierarchyItem>>> {
+        _log!(self, "type hierarchy subtypes requested: {params:?}");
+        let Some(data) = params.item.data.as_ref().and_then(|d| d.as_str()) else {
+            return Ok(None);
+        };
+        let Ok(loc) = AbsLocation::from_str(data) else {
+            return Ok(None);
+        };
+        let entries = self.get_type_entries();
+        let Some(target) = entries.iter().find(|entry| entry.vi.def_loc == loc) else {
+            return Ok(None);
+        };
+        // types that implement the trait outside of their definitions (e.g. `C|<: T|.`)
+        let impl_names = self
+            .shared
+            .trait_impls
+            .get(&target.qual_name)
+            .map(|impls| {
+                impls
+                    .iter()
+                    .map(|tr_impl| tr_impl.sub_type.qual_name())
+                    .collect::<Set<_>>()
+            })
+            .unwrap_or_default();
+        let subs = entries.iter().filter(|entry| {
+            impl_names.contains(&entry.qual_name)
+                || entry
+                    .super_types()
+                    .iter()
+                    .any(|sup| sup.qual_name() == target.qual_name)
+        });
+        Ok(Some(Self::collect_items(subs)))
+    }
+}
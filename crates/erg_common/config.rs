@@ -31,10 +31,13 @@ pub enum ErgMode {
     Compile,
     Transpile,
     Execute,
+    Interpret,
     LanguageServer,
     Lint,
     Read,
     Pack,
+    Format,
+    DebugAdapter,
 }
 
 impl TryFrom<&str> for ErgMode {
@@ -49,10 +52,13 @@ impl TryFrom<&str> for ErgMode {
             "comp" | "compile" | "compiler" => Ok(Self::Compile),
             "trans" | "transpile" | "transpiler" => Ok(Self::Transpile),
             "run" | "execute" => Ok(Self::Execute),
+            "interpret" | "interp" | "dyne" => Ok(Self::Interpret),
             "server" | "language-server" => Ok(Self::LanguageServer),
             "lint" | "linter" => Ok(Self::Lint),
             "byteread" | "read" | "reader" | "dis" => Ok(Self::Read),
             "pack" | "package" => Ok(Self::Pack),
+            "fmt" | "format" | "formatter" => Ok(Self::Format),
+            "dap" | "debug-adapter" => Ok(Self::DebugAdapter),
             _ => Err(()),
         }
     }
@@ -69,10 +75,13 @@ impl From<ErgMode> for &str {
             ErgMode::Compile => "compile",
             ErgMode::Transpile => "transpile",
             ErgMode::Execute => "execute",
+            ErgMode::Interpret => "interpret",
             ErgMode::LanguageServer => "language-server",
             ErgMode::Lint => "lint",
             ErgMode::Read => "read",
             ErgMode::Pack => "pack",
+            ErgMode::Format => "fmt",
+            ErgMode::DebugAdapter => "debug-adapter",
         }
     }
 }
@@ -94,6 +103,11 @@ pub enum TranspileTarget {
     Python,
     Json,
     Toml,
+    Yaml,
+    /// Python stub files (`.pyi`)
+    PyStub,
+    /// WebAssembly modules (`.wasm` and `.wat`)
+    Wasm,
 }
 
 impl From<&str> for TranspileTarget {
@@ -102,27 +116,25 @@ impl From<&str> for TranspileTarget {
             "python" | "py" => Self::Python,
             "json" => Self::Json,
             "toml" => Self::Toml,
+            "yaml" | "yml" => Self::Yaml,
+            "pyi" | "stub" => Self::PyStub,
+            "wasm" | "wat" => Self::Wasm,
             _ => panic!("unsupported transpile target: {s}"),
         }
     }
 }
 
 #[pyclass]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Package {
-    pub name: &'static str,
-    pub as_name: &'static str,
-    pub version: &'static str,
-    pub path: Option<&'static str>,
+    pub name: String,
+    pub as_name: String,
+    pub version: String,
+    pub path: Option<String>,
 }
 
 impl Package {
-    pub const fn new(
-        name: &'static str,
-        as_name: &'static str,
-        version: &'static str,
-        path: Option<&'static str>,
-    ) -> Self {
+    pub fn new(name: String, as_name: String, version: String, path: Option<String>) -> Self {
         Self {
             name,
             as_name,
@@ -136,12 +148,7 @@ impl Package {
 impl Package {
     #[new]
     fn _new(name: String, as_name: String, version: String, path: Option<String>) -> Self {
-        Self {
-            name: Box::leak(name.into_boxed_str()),
-            as_name: Box::leak(as_name.into_boxed_str()),
-            version: Box::leak(version.into_boxed_str()),
-            path: path.map(|s| Box::leak(s.into_boxed_str()) as &'static str),
-        }
+        Self::new(name, as_name, version, path)
     }
 }
 
@@ -334,22 +341,14 @@ impl ErgConfig {
                 "--use-package" => {
                     let name = args
                         .next()
-                        .expect("`name` of `--use-package` is not passed")
-                        .into_boxed_str();
+                        .expect("`name` of `--use-package` is not passed");
                     let as_name = args
                         .next()
-                        .expect("`as_name` of `--use-package` is not passed")
-                        .into_boxed_str();
+                        .expect("`as_name` of `--use-package` is not passed");
                     let version = args
                         .next()
-                        .expect("`version` of `--use-package` is not passed")
-                        .into_boxed_str();
-                    packages.push(Package::new(
-                        Box::leak(name),
-                        Box::leak(as_name),
-                        Box::leak(version),
-                        None,
-                    ));
+                        .expect("`version` of `--use-package` is not passed");
+                    packages.push(Package::new(name, as_name, version, None));
                 }
                 "--use-pylyzer" => {
                     cfg.use_pylyzer = true;
@@ -357,26 +356,17 @@ impl ErgConfig {
                 "--use-local-package" => {
                     let name = args
                         .next()
-                        .expect("`name` of `--use-package` is not passed")
-                        .into_boxed_str();
+                        .expect("`name` of `--use-package` is not passed");
                     let as_name = args
                         .next()
-                        .expect("`as_name` of `--use-package` is not passed")
-                        .into_boxed_str();
+                        .expect("`as_name` of `--use-package` is not passed");
                     let version = args
                         .next()
-                        .expect("`version` of `--use-package` is not passed")
-                        .into_boxed_str();
+                        .expect("`version` of `--use-package` is not passed");
                     let path = args
                         .next()
-                        .expect("`path` of `--use-package` is not passed")
-                        .into_boxed_str();
-                    packages.push(Package::new(
-                        Box::leak(name),
-                        Box::leak(as_name),
-                        Box::leak(version),
-                        Some(Box::leak(path)),
-                    ));
+                        .expect("`path` of `--use-package` is not passed");
+                    packages.push(Package::new(name, as_name, version, Some(path)));
                 }
                 "--ping" => {
                     println!("pong");
@@ -532,7 +522,7 @@ USAGE:
                 _ => {
                     if let Ok(mode) = ErgMode::try_from(&arg[..]) {
                         cfg.mode = mode;
-                        if cfg.mode == ErgMode::Pack {
+                        if matches!(cfg.mode, ErgMode::Pack | ErgMode::Format) {
                             for arg in args {
                                 runtime_args.push(Box::leak(arg.into_boxed_str()));
                             }
@@ -561,7 +551,12 @@ USAGE:
                 }
             }
         }
-        if cfg.input.is_repl() && cfg.mode != ErgMode::LanguageServer {
+        if cfg.input.is_repl()
+            && !matches!(
+                cfg.mode,
+                ErgMode::LanguageServer | ErgMode::Pack | ErgMode::Format | ErgMode::DebugAdapter
+            )
+        {
             let is_stdin_piped = !stdin().is_terminal();
             let input = if is_stdin_piped {
                 let mut buffer = String::new();
@@ -49,6 +49,9 @@ pub enum ErrorKind {
     MethodError = 21,
     DummyError = 22,
     ExpectNextLine = 23,
+    CycleError = 24,
+    FFIError = 25,
+    MutationError = 26,
     /* compile warnings */
     AttributeWarning = 60,
     CastWarning = 61,
@@ -157,6 +160,9 @@ impl From<&str> for ErrorKind {
             "HasEffect" => Self::HasEffect,
             "PurityError" => Self::PurityError,
             "MoveError" => Self::MoveError,
+            "CycleError" => Self::CycleError,
+            "FFIError" => Self::FFIError,
+            "MutationError" => Self::MutationError,
             "AttributeWarning" => Self::AttributeWarning,
             "CastWarning" => Self::CastWarning,
             "DeprecationWarning" => Self::DeprecationWarning,
@@ -1049,8 +1055,10 @@ pub trait ErrorDisplay {
             msg += &sub_msg.format_code_and_pointer(self, color, gutter_color, mark, chars);
         }
         msg += &core.kind.to_string();
-        msg += ": ";
-        msg += &core.main_message;
+        if !core.main_message.is_empty() {
+            msg += ": ";
+            msg += &core.main_message;
+        }
         msg += "\n\n";
         msg
     }
@@ -32,9 +32,12 @@ COMMAND
     compile                              コンパイル
     transpile                            トランスパイル
     run|exec                             実行(デフォルト)
+    interpret|dyne                       Pythonを使わずに実行(Dyneインタプリタ)
     server                               言語サーバーを起動
     lint                                 Lintを実行
-    pack                                 パッケージング管理",
+    pack                                 パッケージング管理
+    fmt                                  コードフォーマット
+    dap                                  デバッグアダプタを起動",
 
     "simplified_chinese" =>
     "\
@@ -65,9 +68,12 @@ COMMAND
     compile                              编译
     transpile                            转译
     run|exec                             执行(默认模式)
+    interpret|dyne                       不使用Python执行(Dyne解释器)
     server                               执行语言服务器
     lint                                 执行 Lint
-    pack                                 执行打包管理",
+    pack                                 执行打包管理
+    fmt                                  格式化代码
+    dap                                  启动调试适配器",
 
     "traditional_chinese" =>
         "\
@@ -98,9 +104,12 @@ COMMAND
     compile                              編譯
     transpile                            轉譯
     run|exec                             執行(預設模式)
+    interpret|dyne                       不使用Python執行(Dyne直譯器)
     server                               執行語言伺服器
     lint                                 執行 Lint
-    pack                                 執行打包管理",
+    pack                                 執行打包管理
+    fmt                                  格式化程式碼
+    dap                                  啟動偵錯配接器",
 
     "english" =>
         "\
@@ -131,9 +140,12 @@ COMMAND
     compile                              compile
     transpile                            transpile
     run|exec                             execute (default mode)
+    interpret|dyne                       execute without Python (Dyne interpreter)
     server                               start Erg language server
     lint                                 lint
-    pack                                 run package manager",
+    pack                                 run package manager
+    fmt                                  format source code
+    dap                                  start Erg debug adapter",
     )
 }
 
@@ -142,7 +154,7 @@ pub fn mode_message<'a>() -> &'a str {
         "japanese" =>
         "\
 USAGE:
-    erg --mode [lex | parse | lower | check | compile | exec | read | lint | pack] [SUBCOMMAND] [ARGS]...
+    erg --mode [lex | parse | lower | check | compile | exec | read | lint | pack | fmt] [SUBCOMMAND] [ARGS]...
 
 lex
     <filename>.erやREPLなどから入力を受け取り、字句を解析
@@ -178,7 +190,10 @@ lint
     プログラムをLintする
 
 pack
-    パッケージ管理",
+    パッケージ管理
+
+fmt
+    <filename>.erをフォーマットする(--checkで差分の有無のみ確認)",
 
     "simplified_chinese" =>
     "\
@@ -220,7 +235,10 @@ lint
     Lint 程序
 
 pack
-    包管理",
+    包管理
+
+fmt
+    格式化 <filename>.er (使用 --check 仅检查是否已格式化)",
 
     "traditional_chinese" =>
     "\
@@ -262,7 +280,10 @@ lint
     Lint 程式
 
 pack
-    封裝管理",
+    封裝管理
+
+fmt
+    格式化 <檔名>.er (使用 --check 僅檢查是否已格式化)",
 
     "english" =>
     "\
@@ -303,7 +324,10 @@ lint
     Lint the program
 
 pack
-    Package management",
+    Package management
+
+fmt
+    Format <filename>.er (with --check, only check if it is formatted)",
     )
 }
 
@@ -79,6 +79,10 @@ impl InputKind {
         matches!(self, Self::REPL | Self::DummyREPL(_))
     }
 
+    pub const fn is_file(&self) -> bool {
+        matches!(self, Self::File { .. })
+    }
+
     pub fn path(&self) -> &Path {
         match self {
             Self::File { path, .. } => path.as_path(),
@@ -189,6 +193,10 @@ impl Input {
         self.kind.is_repl()
     }
 
+    pub const fn is_file(&self) -> bool {
+        self.kind.is_file()
+    }
+
     pub const fn id(&self) -> u64 {
         self.id
     }
@@ -586,11 +594,11 @@ impl Input {
         decl: bool,
     ) -> Option<PathBuf> {
         let name = path.components().next()?.as_os_str();
-        let pkg = cfg.packages.iter().find(|p| p.as_name == name)?;
-        let root_path = if let Some(path) = pkg.path {
+        let pkg = cfg.packages.iter().find(|p| name == p.as_name.as_str())?;
+        let root_path = if let Some(path) = &pkg.path {
             PathBuf::from(path).canonicalize().ok()?
         } else {
-            erg_pkgs_path().join(pkg.name).join(pkg.version)
+            erg_pkgs_path().join(&pkg.name).join(&pkg.version)
         };
         if path.components().count() <= 1 {
             let full_path = if decl {
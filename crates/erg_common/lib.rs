@@ -22,6 +22,8 @@ pub mod opcode308;
 pub mod opcode309;
 pub mod opcode310;
 pub mod opcode311;
+pub mod opcode312;
+pub mod opcode313;
 pub mod pathutil;
 pub mod python_util;
 pub mod random;
@@ -32,6 +34,7 @@ pub mod spawn;
 pub mod stdin;
 pub mod str;
 pub mod style;
+pub mod traceback;
 pub mod traits;
 pub mod triple;
 pub mod tsort;
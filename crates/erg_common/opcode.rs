@@ -157,6 +157,12 @@ impl CommonOpcode {
     pub fn is_jump_op(op: u8) -> bool {
         [93, 110, 111, 112, 113, 114, 115, 140, 143, 175, 176].contains(&op)
     }
+
+    /// Since 3.13, the opcode numbers of jump instructions are different.
+    pub fn is_jump_op_313(op: u8) -> bool {
+        // FOR_ITER, JUMP_BACKWARD, JUMP_BACKWARD_NO_INTERRUPT, JUMP_FORWARD, POP_JUMP_IF_*, SEND
+        [72, 77, 78, 79, 97, 98, 99, 100, 104].contains(&op)
+    }
 }
 
 impl_u8_enum! {CompareOp;
@@ -0,0 +1,339 @@
+//! defines `Opcode` (represents Python bytecode opcodes).
+//!
+//! Opcode(Pythonバイトコードオペコードを表す)を定義する
+
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+use crate::impl_u8_enum;
+use crate::opcode312::Opcode312;
+
+impl_u8_enum! {Opcode313;
+    CACHE = 0,
+    BEFORE_ASYNC_WITH = 1,
+    BEFORE_WITH = 2,
+    BINARY_SLICE = 4,
+    BINARY_SUBSCR = 5,
+    CHECK_EG_MATCH = 6,
+    CHECK_EXC_MATCH = 7,
+    CLEANUP_THROW = 8,
+    DELETE_SUBSCR = 9,
+    END_ASYNC_FOR = 10,
+    END_FOR = 11,
+    END_SEND = 12,
+    EXIT_INIT_CHECK = 13,
+    FORMAT_SIMPLE = 14,
+    FORMAT_WITH_SPEC = 15,
+    GET_AITER = 16,
+    RESERVED = 17,
+    GET_ANEXT = 18,
+    GET_ITER = 19,
+    GET_LEN = 20,
+    GET_YIELD_FROM_ITER = 21,
+    INTERPRETER_EXIT = 22,
+    LOAD_ASSERTION_ERROR = 23,
+    LOAD_BUILD_CLASS = 24,
+    LOAD_LOCALS = 25,
+    MAKE_FUNCTION = 26,
+    MATCH_KEYS = 27,
+    MATCH_MAPPING = 28,
+    MATCH_SEQUENCE = 29,
+    NOP = 30,
+    POP_EXCEPT = 31,
+    POP_TOP = 32,
+    PUSH_EXC_INFO = 33,
+    PUSH_NULL = 34,
+    RETURN_GENERATOR = 35,
+    RETURN_VALUE = 36,
+    SETUP_ANNOTATIONS = 37,
+    STORE_SLICE = 38,
+    STORE_SUBSCR = 39,
+    TO_BOOL = 40,
+    UNARY_INVERT = 41,
+    UNARY_NEGATIVE = 42,
+    UNARY_NOT = 43,
+    /* ↓ These opcodes take an arg */
+    WITH_EXCEPT_START = 44,
+    BINARY_OP = 45,
+    BUILD_CONST_KEY_MAP = 46,
+    BUILD_LIST = 47,
+    BUILD_MAP = 48, // build a Dict object
+    BUILD_SET = 49,
+    BUILD_SLICE = 50,
+    BUILD_STRING = 51,
+    BUILD_TUPLE = 52,
+    CALL = 53,
+    CALL_FUNCTION_EX = 54,
+    CALL_INTRINSIC_1 = 55,
+    CALL_INTRINSIC_2 = 56,
+    CALL_KW = 57,
+    COMPARE_OP = 58,
+    CONTAINS_OP = 59,
+    CONVERT_VALUE = 60,
+    COPY = 61,
+    COPY_FREE_VARS = 62,
+    DELETE_ATTR = 63,
+    DELETE_DEREF = 64,
+    DELETE_FAST = 65,
+    DELETE_GLOBAL = 66,
+    DELETE_NAME = 67,
+    DICT_MERGE = 68,
+    DICT_UPDATE = 69,
+    ENTER_EXECUTOR = 70,
+    EXTENDED_ARG = 71,
+    FOR_ITER = 72,
+    GET_AWAITABLE = 73,
+    IMPORT_FROM = 74,
+    IMPORT_NAME = 75,
+    IS_OP = 76,
+    JUMP_BACKWARD = 77,
+    JUMP_BACKWARD_NO_INTERRUPT = 78,
+    JUMP_FORWARD = 79,
+    LIST_APPEND = 80,
+    LIST_EXTEND = 81,
+    LOAD_ATTR = 82,
+    LOAD_CONST = 83,
+    LOAD_DEREF = 84,
+    LOAD_FAST = 85,
+    LOAD_FAST_AND_CLEAR = 86,
+    LOAD_FAST_CHECK = 87,
+    LOAD_FAST_LOAD_FAST = 88,
+    LOAD_FROM_DICT_OR_DEREF = 89,
+    LOAD_FROM_DICT_OR_GLOBALS = 90,
+    LOAD_GLOBAL = 91,
+    LOAD_NAME = 92,
+    LOAD_SUPER_ATTR = 93,
+    MAKE_CELL = 94,
+    MAP_ADD = 95,
+    MATCH_CLASS = 96,
+    POP_JUMP_IF_FALSE = 97,
+    POP_JUMP_IF_NONE = 98,
+    POP_JUMP_IF_NOT_NONE = 99,
+    POP_JUMP_IF_TRUE = 100,
+    RAISE_VARARGS = 101,
+    RERAISE = 102,
+    RETURN_CONST = 103,
+    SEND = 104,
+    SET_ADD = 105,
+    SET_FUNCTION_ATTRIBUTE = 106,
+    SET_UPDATE = 107,
+    STORE_ATTR = 108,
+    STORE_DEREF = 109,
+    STORE_FAST = 110,
+    STORE_FAST_LOAD_FAST = 111,
+    STORE_FAST_STORE_FAST = 112,
+    STORE_GLOBAL = 113,
+    STORE_NAME = 114,
+    SWAP = 115,
+    UNPACK_EX = 116,
+    UNPACK_SEQUENCE = 117,
+    YIELD_VALUE = 118,
+    RESUME = 149,
+    // Erg-specific opcodes (must have a unary `ERG_`)
+    // Define in descending order from 219, 255
+    ERG_POP_NTH = 196,
+    ERG_PEEK_NTH = 197, // get ref to the arg-th element from TOS
+    ERG_INC = 198,      // name += 1; arg: typecode
+    ERG_DEC = 199,      // name -= 1
+    ERG_LOAD_FAST_IMMUT = 200,
+    ERG_STORE_FAST_IMMUT = 201,
+    ERG_MOVE_FAST = 202,
+    ERG_CLONE_FAST = 203,
+    ERG_COPY_FAST = 204,
+    ERG_REF_FAST = 205,
+    ERG_REF_MUT_FAST = 206,
+    ERG_MOVE_OUTER = 207,
+    ERG_CLONE_OUTER = 208,
+    ERG_COPY_OUTER = 209,
+    ERG_REF_OUTER = 210,
+    ERG_REF_MUT_OUTER = 211,
+    ERG_LESS_THAN = 212,
+    ERG_LESS_EQUAL = 213,
+    ERG_EQUAL = 214,
+    ERG_NOT_EQUAL = 215,
+    ERG_MAKE_SLOT = 216,
+    ERG_MAKE_TYPE = 217,
+    ERG_MAKE_PURE_FUNCTION = 218,
+    ERG_CALL_PURE_FUNCTION = 219,
+    /* ↑ These opcodes take an arg ↑ */
+    /* ↓ These opcodes take no arg ↓ */
+    // ... = 220,
+    ERG_LOAD_EMPTY_SLOT = 242,
+    ERG_LOAD_EMPTY_STR = 243,
+    ERG_LOAD_1_NAT = 244,
+    ERG_LOAD_1_INT = 245,
+    ERG_LOAD_1_REAL = 246,
+    ERG_LOAD_NONE = 247,
+    ERG_MUTATE = 248, // !x
+    ERG_STORE_SUBSCR = 249, // `[] =` (it doesn't cause any exceptions)
+    // ... = 250,
+    ERG_BINARY_SUBSCR = 251, // `= []` (it doesn't cause any exceptions)
+    ERG_BINARY_RANGE = 252,
+    // `/?` (rhs may be 0, it may cause a runtime panic)
+    ERG_TRY_BINARY_DIVIDE = 253,
+    // `/` (rhs could not be 0, it doesn't cause any exceptions)
+    ERG_BINARY_TRUE_DIVIDE = 254,
+    NOT_IMPLEMENTED = 255,
+}
+
+impl Opcode313 {
+    /// In 3.13, the opcode numbers were completely reassigned.
+    /// Returns `None` if the instruction was removed in 3.13 (`FORMAT_VALUE`, `KW_NAMES`).
+    pub const fn from_312(op: Opcode312) -> Option<Self> {
+        Some(match op {
+            // `LOAD_CLOSURE` is now a pseudo instruction and is compiled to `LOAD_FAST`
+            Opcode312::LOAD_CLOSURE => Self::LOAD_FAST,
+            Opcode312::CACHE => Self::CACHE,
+            Opcode312::POP_TOP => Self::POP_TOP,
+            Opcode312::PUSH_NULL => Self::PUSH_NULL,
+            Opcode312::INTERPRETER_EXIT => Self::INTERPRETER_EXIT,
+            Opcode312::END_FOR => Self::END_FOR,
+            Opcode312::END_SEND => Self::END_SEND,
+            Opcode312::NOP => Self::NOP,
+            Opcode312::UNARY_NEGATIVE => Self::UNARY_NEGATIVE,
+            Opcode312::UNARY_NOT => Self::UNARY_NOT,
+            Opcode312::UNARY_INVERT => Self::UNARY_INVERT,
+            Opcode312::RESERVED => Self::RESERVED,
+            Opcode312::BINARY_SUBSCR => Self::BINARY_SUBSCR,
+            Opcode312::BINARY_SLICE => Self::BINARY_SLICE,
+            Opcode312::STORE_SLICE => Self::STORE_SLICE,
+            Opcode312::GET_LEN => Self::GET_LEN,
+            Opcode312::MATCH_MAPPING => Self::MATCH_MAPPING,
+            Opcode312::MATCH_SEQUENCE => Self::MATCH_SEQUENCE,
+            Opcode312::MATCH_KEYS => Self::MATCH_KEYS,
+            Opcode312::PUSH_EXC_INFO => Self::PUSH_EXC_INFO,
+            Opcode312::CHECK_EXC_MATCH => Self::CHECK_EXC_MATCH,
+            Opcode312::CHECK_EG_MATCH => Self::CHECK_EG_MATCH,
+            Opcode312::WITH_EXCEPT_START => Self::WITH_EXCEPT_START,
+            Opcode312::GET_AITER => Self::GET_AITER,
+            Opcode312::GET_ANEXT => Self::GET_ANEXT,
+            Opcode312::BEFORE_ASYNC_WITH => Self::BEFORE_ASYNC_WITH,
+            Opcode312::BEFORE_WITH => Self::BEFORE_WITH,
+            Opcode312::END_ASYNC_FOR => Self::END_ASYNC_FOR,
+            Opcode312::CLEANUP_THROW => Self::CLEANUP_THROW,
+            Opcode312::STORE_SUBSCR => Self::STORE_SUBSCR,
+            Opcode312::DELETE_SUBSCR => Self::DELETE_SUBSCR,
+            Opcode312::GET_ITER => Self::GET_ITER,
+            Opcode312::GET_YIELD_FROM_ITER => Self::GET_YIELD_FROM_ITER,
+            Opcode312::LOAD_BUILD_CLASS => Self::LOAD_BUILD_CLASS,
+            Opcode312::LOAD_ASSERTION_ERROR => Self::LOAD_ASSERTION_ERROR,
+            Opcode312::RETURN_GENERATOR => Self::RETURN_GENERATOR,
+            Opcode312::RETURN_VALUE => Self::RETURN_VALUE,
+            Opcode312::SETUP_ANNOTATIONS => Self::SETUP_ANNOTATIONS,
+            Opcode312::LOAD_LOCALS => Self::LOAD_LOCALS,
+            Opcode312::POP_EXCEPT => Self::POP_EXCEPT,
+            Opcode312::STORE_NAME => Self::STORE_NAME,
+            Opcode312::DELETE_NAME => Self::DELETE_NAME,
+            Opcode312::UNPACK_SEQUENCE => Self::UNPACK_SEQUENCE,
+            Opcode312::FOR_ITER => Self::FOR_ITER,
+            Opcode312::UNPACK_EX => Self::UNPACK_EX,
+            Opcode312::STORE_ATTR => Self::STORE_ATTR,
+            Opcode312::DELETE_ATTR => Self::DELETE_ATTR,
+            Opcode312::STORE_GLOBAL => Self::STORE_GLOBAL,
+            Opcode312::DELETE_GLOBAL => Self::DELETE_GLOBAL,
+            Opcode312::SWAP => Self::SWAP,
+            Opcode312::LOAD_CONST => Self::LOAD_CONST,
+            Opcode312::LOAD_NAME => Self::LOAD_NAME,
+            Opcode312::BUILD_TUPLE => Self::BUILD_TUPLE,
+            Opcode312::BUILD_LIST => Self::BUILD_LIST,
+            Opcode312::BUILD_SET => Self::BUILD_SET,
+            Opcode312::BUILD_MAP => Self::BUILD_MAP,
+            Opcode312::LOAD_ATTR => Self::LOAD_ATTR,
+            Opcode312::COMPARE_OP => Self::COMPARE_OP,
+            Opcode312::IMPORT_NAME => Self::IMPORT_NAME,
+            Opcode312::IMPORT_FROM => Self::IMPORT_FROM,
+            Opcode312::JUMP_FORWARD => Self::JUMP_FORWARD,
+            Opcode312::POP_JUMP_IF_FALSE => Self::POP_JUMP_IF_FALSE,
+            Opcode312::POP_JUMP_IF_TRUE => Self::POP_JUMP_IF_TRUE,
+            Opcode312::LOAD_GLOBAL => Self::LOAD_GLOBAL,
+            Opcode312::IS_OP => Self::IS_OP,
+            Opcode312::CONTAINS_OP => Self::CONTAINS_OP,
+            Opcode312::RERAISE => Self::RERAISE,
+            Opcode312::COPY => Self::COPY,
+            Opcode312::RETURN_CONST => Self::RETURN_CONST,
+            Opcode312::BINARY_OP => Self::BINARY_OP,
+            Opcode312::SEND => Self::SEND,
+            Opcode312::LOAD_FAST => Self::LOAD_FAST,
+            Opcode312::STORE_FAST => Self::STORE_FAST,
+            Opcode312::DELETE_FAST => Self::DELETE_FAST,
+            Opcode312::LOAD_FAST_CHECK => Self::LOAD_FAST_CHECK,
+            Opcode312::POP_JUMP_IF_NOT_NONE => Self::POP_JUMP_IF_NOT_NONE,
+            Opcode312::POP_JUMP_IF_NONE => Self::POP_JUMP_IF_NONE,
+            Opcode312::RAISE_VARARGS => Self::RAISE_VARARGS,
+            Opcode312::GET_AWAITABLE => Self::GET_AWAITABLE,
+            Opcode312::MAKE_FUNCTION => Self::MAKE_FUNCTION,
+            Opcode312::BUILD_SLICE => Self::BUILD_SLICE,
+            Opcode312::JUMP_BACKWARD_NO_INTERRUPT => Self::JUMP_BACKWARD_NO_INTERRUPT,
+            Opcode312::MAKE_CELL => Self::MAKE_CELL,
+            Opcode312::LOAD_DEREF => Self::LOAD_DEREF,
+            Opcode312::STORE_DEREF => Self::STORE_DEREF,
+            Opcode312::DELETE_DEREF => Self::DELETE_DEREF,
+            Opcode312::JUMP_BACKWARD => Self::JUMP_BACKWARD,
+            Opcode312::LOAD_SUPER_ATTR => Self::LOAD_SUPER_ATTR,
+            Opcode312::CALL_FUNCTION_EX => Self::CALL_FUNCTION_EX,
+            Opcode312::LOAD_FAST_AND_CLEAR => Self::LOAD_FAST_AND_CLEAR,
+            Opcode312::EXTENDED_ARG => Self::EXTENDED_ARG,
+            Opcode312::LIST_APPEND => Self::LIST_APPEND,
+            Opcode312::SET_ADD => Self::SET_ADD,
+            Opcode312::MAP_ADD => Self::MAP_ADD,
+            Opcode312::COPY_FREE_VARS => Self::COPY_FREE_VARS,
+            Opcode312::YIELD_VALUE => Self::YIELD_VALUE,
+            Opcode312::RESUME => Self::RESUME,
+            Opcode312::MATCH_CLASS => Self::MATCH_CLASS,
+            Opcode312::BUILD_CONST_KEY_MAP => Self::BUILD_CONST_KEY_MAP,
+            Opcode312::BUILD_STRING => Self::BUILD_STRING,
+            Opcode312::LIST_EXTEND => Self::LIST_EXTEND,
+            Opcode312::SET_UPDATE => Self::SET_UPDATE,
+            Opcode312::DICT_MERGE => Self::DICT_MERGE,
+            Opcode312::DICT_UPDATE => Self::DICT_UPDATE,
+            Opcode312::CALL => Self::CALL,
+            Opcode312::CALL_INTRINSIC_1 => Self::CALL_INTRINSIC_1,
+            Opcode312::CALL_INTRINSIC_2 => Self::CALL_INTRINSIC_2,
+            Opcode312::LOAD_FROM_DICT_OR_GLOBALS => Self::LOAD_FROM_DICT_OR_GLOBALS,
+            Opcode312::LOAD_FROM_DICT_OR_DEREF => Self::LOAD_FROM_DICT_OR_DEREF,
+            Opcode312::ERG_POP_NTH => Self::ERG_POP_NTH,
+            Opcode312::ERG_PEEK_NTH => Self::ERG_PEEK_NTH,
+            Opcode312::ERG_INC => Self::ERG_INC,
+            Opcode312::ERG_DEC => Self::ERG_DEC,
+            Opcode312::ERG_LOAD_FAST_IMMUT => Self::ERG_LOAD_FAST_IMMUT,
+            Opcode312::ERG_STORE_FAST_IMMUT => Self::ERG_STORE_FAST_IMMUT,
+            Opcode312::ERG_MOVE_FAST => Self::ERG_MOVE_FAST,
+            Opcode312::ERG_CLONE_FAST => Self::ERG_CLONE_FAST,
+            Opcode312::ERG_COPY_FAST => Self::ERG_COPY_FAST,
+            Opcode312::ERG_REF_FAST => Self::ERG_REF_FAST,
+            Opcode312::ERG_REF_MUT_FAST => Self::ERG_REF_MUT_FAST,
+            Opcode312::ERG_MOVE_OUTER => Self::ERG_MOVE_OUTER,
+            Opcode312::ERG_CLONE_OUTER => Self::ERG_CLONE_OUTER,
+            Opcode312::ERG_COPY_OUTER => Self::ERG_COPY_OUTER,
+            Opcode312::ERG_REF_OUTER => Self::ERG_REF_OUTER,
+            Opcode312::ERG_REF_MUT_OUTER => Self::ERG_REF_MUT_OUTER,
+            Opcode312::ERG_LESS_THAN => Self::ERG_LESS_THAN,
+            Opcode312::ERG_LESS_EQUAL => Self::ERG_LESS_EQUAL,
+            Opcode312::ERG_EQUAL => Self::ERG_EQUAL,
+            Opcode312::ERG_NOT_EQUAL => Self::ERG_NOT_EQUAL,
+            Opcode312::ERG_MAKE_SLOT => Self::ERG_MAKE_SLOT,
+            Opcode312::ERG_MAKE_TYPE => Self::ERG_MAKE_TYPE,
+            Opcode312::ERG_MAKE_PURE_FUNCTION => Self::ERG_MAKE_PURE_FUNCTION,
+            Opcode312::ERG_CALL_PURE_FUNCTION => Self::ERG_CALL_PURE_FUNCTION,
+            Opcode312::ERG_LOAD_EMPTY_SLOT => Self::ERG_LOAD_EMPTY_SLOT,
+            Opcode312::ERG_LOAD_EMPTY_STR => Self::ERG_LOAD_EMPTY_STR,
+            Opcode312::ERG_LOAD_1_NAT => Self::ERG_LOAD_1_NAT,
+            Opcode312::ERG_LOAD_1_INT => Self::ERG_LOAD_1_INT,
+            Opcode312::ERG_LOAD_1_REAL => Self::ERG_LOAD_1_REAL,
+            Opcode312::ERG_LOAD_NONE => Self::ERG_LOAD_NONE,
+            Opcode312::ERG_MUTATE => Self::ERG_MUTATE,
+            Opcode312::ERG_STORE_SUBSCR => Self::ERG_STORE_SUBSCR,
+            Opcode312::ERG_BINARY_SUBSCR => Self::ERG_BINARY_SUBSCR,
+            Opcode312::ERG_BINARY_RANGE => Self::ERG_BINARY_RANGE,
+            Opcode312::ERG_TRY_BINARY_DIVIDE => Self::ERG_TRY_BINARY_DIVIDE,
+            Opcode312::ERG_BINARY_TRUE_DIVIDE => Self::ERG_BINARY_TRUE_DIVIDE,
+            Opcode312::NOT_IMPLEMENTED => Self::NOT_IMPLEMENTED,
+            _ => return None,
+        })
+    }
+
+    /// Instructions whose opcode is less than `HAVE_ARGUMENT` (44) ignore their argument.
+    pub const fn has_arg(&self) -> bool {
+        (*self as u8) >= 44
+    }
+}
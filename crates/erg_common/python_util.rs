@@ -3,16 +3,18 @@
 //! CPythonを呼び出すためのユーティリティー
 use std::env::{current_dir, set_current_dir, temp_dir};
 use std::fs::{canonicalize, remove_file, File};
-use std::io::Write;
+use std::io::{stderr, BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
 use std::process::{Child, Command, ExitStatus, Stdio};
 
 use crate::env::opt_which_python;
+use crate::error::ErrorDisplay;
 use crate::fn_name_full;
 use crate::io::Output;
 use crate::pathutil::remove_verbatim;
 use crate::random::random;
 use crate::serialize::get_magic_num_from_bytes;
+use crate::traceback::{PyTraceback, TracebackError, TRACEBACK_HEADER};
 
 #[cfg(unix)]
 pub const BUILTIN_PYTHON_MODS: [&str; 178] = [
@@ -719,6 +721,8 @@ impl PythonVersion {
     pub const V3_09: Self = Self::new(3, Some(9), Some(0));
     pub const V3_10: Self = Self::new(3, Some(10), Some(0));
     pub const V3_11: Self = Self::new(3, Some(11), Some(0));
+    pub const V3_12: Self = Self::new(3, Some(12), Some(0));
+    pub const V3_13: Self = Self::new(3, Some(13), Some(0));
 
     pub const fn new(major: u8, minor: Option<u8>, micro: Option<u8>) -> Self {
         Self {
@@ -831,6 +835,7 @@ fn exec_pyc_in(
     working_dir: impl AsRef<Path>,
     args: &[&str],
     stdout: impl Into<Stdio>,
+    erg_traceback: bool,
 ) -> std::io::Result<ExitStatus> {
     let current_dir = current_dir()?;
     set_current_dir(working_dir.as_ref())?;
@@ -839,7 +844,7 @@ fn exec_pyc_in(
         file.as_ref().display()
     );
     let command = py_command.map_or_else(|| which_python().to_string(), ToString::to_string);
-    let mut out = if cfg!(windows) {
+    let out = if cfg!(windows) {
         Command::new("cmd")
             .arg("/C")
             .arg(command)
@@ -847,6 +852,7 @@ fn exec_pyc_in(
             .arg(code)
             .args(args)
             .stdout(stdout)
+            .stderr(traceback_stderr(erg_traceback))
             .spawn()
             .expect("cannot execute python")
     } else {
@@ -859,33 +865,38 @@ fn exec_pyc_in(
             .arg("-c")
             .arg(exec_command)
             .stdout(stdout)
+            .stderr(traceback_stderr(erg_traceback))
             .spawn()
             .expect("cannot execute python")
     };
-    let res = out.wait();
+    let res = wait_with_erg_traceback(out);
     set_current_dir(current_dir)?;
     res
 }
 
 /// executes over a shell, cause `python` may not exist as an executable file (like pyenv)
+///
+/// If `erg_traceback` is true, the traceback of an uncaught exception is displayed as an Erg error.
 pub fn exec_pyc(
     file: impl AsRef<Path>,
     py_command: Option<&str>,
     working_dir: Option<impl AsRef<Path>>,
     args: &[&str],
     stdout: impl Into<Stdio>,
+    erg_traceback: bool,
 ) -> std::io::Result<ExitStatus> {
     if let Some(working_dir) = working_dir {
-        return exec_pyc_in(file, py_command, working_dir, args, stdout);
+        return exec_pyc_in(file, py_command, working_dir, args, stdout, erg_traceback);
     }
     let command = py_command.map_or_else(|| which_python().to_string(), ToString::to_string);
-    let mut out = if cfg!(windows) {
+    let out = if cfg!(windows) {
         Command::new("cmd")
             .arg("/C")
             .arg(command)
             .arg(file.as_ref())
             .args(args)
             .stdout(stdout)
+            .stderr(traceback_stderr(erg_traceback))
             .spawn()
             .expect("cannot execute python")
     } else {
@@ -894,10 +905,57 @@ pub fn exec_pyc(
             .arg("-c")
             .arg(exec_command)
             .stdout(stdout)
+            .stderr(traceback_stderr(erg_traceback))
             .spawn()
             .expect("cannot execute python")
     };
-    out.wait()
+    wait_with_erg_traceback(out)
+}
+
+/// The stderr is piped only if it needs to be rewritten.
+fn traceback_stderr(erg_traceback: bool) -> Stdio {
+    if erg_traceback {
+        Stdio::piped()
+    } else {
+        Stdio::inherit()
+    }
+}
+
+/// Waits for the process while forwarding its stderr (if piped).
+/// If the process is terminated by an uncaught exception raised in Erg code,
+/// the traceback is displayed as an Erg error instead of the raw one.
+fn wait_with_erg_traceback(mut child: Child) -> std::io::Result<ExitStatus> {
+    let Some(child_stderr) = child.stderr.take() else {
+        return child.wait();
+    };
+    let mut reader = BufReader::new(child_stderr);
+    let mut line = vec![];
+    // the stderr after a traceback begins (this may contain multiple tracebacks)
+    let mut buffered = String::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        let text = String::from_utf8_lossy(&line);
+        if buffered.is_empty() && text.trim_end() != TRACEBACK_HEADER {
+            let mut stderr = stderr();
+            stderr.write_all(&line)?;
+            stderr.flush()?;
+        } else {
+            buffered += &text;
+        }
+    }
+    let status = child.wait()?;
+    if let Some(idx) = buffered.rfind(TRACEBACK_HEADER) {
+        let (before, last) = buffered.split_at(idx);
+        stderr().write_all(before.as_bytes())?;
+        match PyTraceback::parse(last).and_then(|tb| TracebackError::from_erg_traceback(&tb)) {
+            Some(err) => err.write_to_stderr(),
+            None => stderr().write_all(last.as_bytes())?,
+        }
+    }
+    Ok(status)
 }
 
 /// evaluates over a shell, cause `python` may not exist as an executable file (like pyenv)
@@ -983,11 +1041,41 @@ pub fn spawn_py(py_command: Option<&str>, code: &str) -> Child {
     }
 }
 
-pub fn exec_pyc_code(code: &[u8], args: &[&str], output: Output) -> std::io::Result<ExitStatus> {
+/// Spawn a Python process running `code` with `args` (`sys.argv[1:]`).
+/// The stdout and stderr of the process are piped.
+pub fn spawn_py_piped(
+    py_command: Option<&str>,
+    code: &str,
+    args: &[&str],
+) -> std::io::Result<Child> {
+    Command::new(py_command.unwrap_or(which_python()))
+        .arg("-c")
+        .arg(code)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+}
+
+pub fn exec_pyc_code(
+    code: &[u8],
+    py_command: Option<&str>,
+    args: &[&str],
+    output: Output,
+    erg_traceback: bool,
+) -> std::io::Result<ExitStatus> {
     let tmp_dir = temp_dir();
     let tmp_file = tmp_dir.join(format!("{}.pyc", random()));
     File::create(&tmp_file).unwrap().write_all(code).unwrap();
-    let res = exec_pyc(&tmp_file, None, current_dir().ok(), args, output);
+    let res = exec_pyc(
+        &tmp_file,
+        py_command,
+        current_dir().ok(),
+        args,
+        output,
+        erg_traceback,
+    );
     remove_file(tmp_file)?;
     res
 }
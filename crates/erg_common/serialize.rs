@@ -32,8 +32,9 @@ pub const fn get_ver_from_magic_num(magic_num: u32) -> PythonVersion {
         3400..=3413 => PythonVersion::new(3, Some(8), Some(0)),
         3420..=3425 => PythonVersion::new(3, Some(9), Some(0)),
         3430..=3439 => PythonVersion::new(3, Some(10), Some(0)), // main: 3439
-        3495 => PythonVersion::new(3, Some(11), Some(0)),
-        3531 => PythonVersion::new(3, Some(12), Some(0)),
+        3450..=3495 => PythonVersion::new(3, Some(11), Some(0)), // main: 3495
+        3500..=3531 => PythonVersion::new(3, Some(12), Some(0)), // main: 3531
+        3550..=3571 => PythonVersion::new(3, Some(13), Some(0)), // main: 3571
         _ => panic!("unknown magic number (unsupported Python version)"),
     }
 }
@@ -262,9 +262,13 @@ impl Characters {
     }
 
     // kind[padded error number]
+    // the number is omitted if it is 0 (e.g. runtime errors)
     #[cfg(not(feature = "pretty"))]
     pub fn error_kind_format(&self, kind: &str, err_num: usize) -> String {
         const PADDING: usize = 4;
+        if err_num == 0 {
+            return kind.to_string();
+        }
         format!("{kind}{}#{err_num:>0PADDING$}{}", self.lbrac, self.rbrac,)
     }
 
@@ -278,6 +282,9 @@ impl Characters {
         } else {
             "😱"
         };
+        if err_num == 0 {
+            return format!("{emoji} {kind}");
+        }
         format!(
             "{emoji} {kind}{}#{err_num:>0PADDING$}{}",
             self.lbrac, self.rbrac,
@@ -0,0 +1,335 @@
+//! Converts Python tracebacks of compiled Erg programs into Erg errors.
+//!
+//! Since the code objects generated by Erg have the `.er` file as `co_filename` and Erg line numbers in the line tables,
+//! the frames of a traceback already point to the Erg source; only the identifiers need to be demangled.
+use std::path::Path;
+
+use crate::error::{ErrorCore, ErrorDisplay, ErrorKind, Location, SubMessage};
+use crate::io::{Input, InputKind};
+use crate::{impl_display_and_error, switch_lang};
+
+pub const TRACEBACK_HEADER: &str = "Traceback (most recent call last):";
+
+/// Restores the Erg name of a variable mangled by the code generator (e.g. `::f__erg_proc___L2` -> `f!`).
+/// Returns `None` for the variables generated by the compiler.
+pub fn demangle(py_name: &str) -> Option<String> {
+    let name = if let Some(private) = py_name.strip_prefix("::") {
+        strip_line_mangling(private)
+    } else {
+        py_name
+    };
+    if name.starts_with('#') || name.starts_with('%') {
+        return None;
+    }
+    Some(
+        name.replace("__erg_proc__", "!")
+            .replace("__erg_shared__", "$"),
+    )
+}
+
+/// `x_L1_C4` -> `x`
+fn strip_line_mangling(name: &str) -> &str {
+    fn strip<'a>(name: &'a str, marker: &str) -> &'a str {
+        match name.rfind(marker) {
+            Some(idx)
+                if idx > 0
+                    && idx + marker.len() < name.len()
+                    && name[idx + marker.len()..]
+                        .chars()
+                        .all(|c| c.is_ascii_digit()) =>
+            {
+                &name[..idx]
+            }
+            _ => name,
+        }
+    }
+    strip(strip(name, "_C"), "_L")
+}
+
+/// Demangles all the identifiers in an exception message.
+/// e.g. `name '::x_L1' is not defined` -> `name 'x' is not defined`
+pub fn demangle_message(msg: &str) -> String {
+    let mut demangled = String::with_capacity(msg.len());
+    let mut rest = msg;
+    while let Some(idx) = rest.find("::") {
+        demangled += &rest[..idx];
+        let end = rest[idx + 2..]
+            .find(|c: char| !(c.is_alphanumeric() || "_!$#%".contains(c)))
+            .map_or(rest.len(), |end| idx + 2 + end);
+        let mangled = &rest[idx..end];
+        demangled += &demangle(mangled).unwrap_or_else(|| mangled.to_string());
+        rest = &rest[end..];
+    }
+    demangled += rest;
+    demangled
+        .replace("__erg_proc__", "!")
+        .replace("__erg_shared__", "$")
+}
+
+/// Returns the `Input` of `file` if it is an existing Erg script.
+pub fn erg_script_input(file: &str) -> Option<Input> {
+    let path = Path::new(file);
+    (path.extension().is_some_and(|ext| ext == "er") && path.exists())
+        .then(|| Input::file(path.to_path_buf()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyFrame {
+    pub file: String,
+    /// 1-origin
+    pub line: u32,
+    pub name: String,
+}
+
+impl PyFrame {
+    /// `  File "foo.er", line 3, in f` => `PyFrame { file: "foo.er", line: 3, name: "f" }`
+    fn parse(line: &str) -> Option<Self> {
+        let rest = line.trim_start().strip_prefix("File \"")?;
+        let (file, rest) = rest.split_once("\", line ")?;
+        let (line, name) = rest.split_once(", in ").unwrap_or((rest, "<module>"));
+        Some(Self {
+            file: file.to_string(),
+            line: line.trim().parse().ok()?,
+            name: name.trim().to_string(),
+        })
+    }
+}
+
+/// A traceback printed by Python (`traceback.print_exc()`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyTraceback {
+    /// the outermost frame comes first
+    pub frames: Vec<PyFrame>,
+    /// e.g. `ZeroDivisionError`
+    pub exception: String,
+    pub message: String,
+}
+
+impl PyTraceback {
+    /// Parses the last traceback in `text`.
+    /// For chained exceptions, only the last one (which terminated the program) is taken.
+    pub fn parse(text: &str) -> Option<Self> {
+        let start = text.rfind(TRACEBACK_HEADER)? + TRACEBACK_HEADER.len();
+        let mut frames = vec![];
+        let mut lines = text[start..].lines().skip(1).peekable();
+        while let Some(line) = lines.peek() {
+            if !line.starts_with(' ') {
+                break;
+            }
+            if let Some(frame) = PyFrame::parse(line) {
+                frames.push(frame);
+            }
+            // the source code and the `^^^^` markers are skipped
+            lines.next();
+        }
+        let last = lines.next()?;
+        let (exception, first_line) = last.split_once(':').unwrap_or((last, ""));
+        let mut message = first_line.trim_start().to_string();
+        for line in lines {
+            message.push('\n');
+            message += line;
+        }
+        Some(Self {
+            frames,
+            // e.g. `module.MyError` -> `MyError`
+            exception: exception
+                .rsplit('.')
+                .next()
+                .unwrap_or(exception)
+                .to_string(),
+            message: message.trim_end().to_string(),
+        })
+    }
+}
+
+/// An uncaught Python exception, displayed in the same way as the compile errors.
+#[derive(Debug)]
+pub struct TracebackError {
+    pub core: Box<ErrorCore>,
+    pub input: Input,
+    pub caused_by: String,
+}
+
+impl_display_and_error!(TracebackError);
+
+impl ErrorDisplay for TracebackError {
+    fn core(&self) -> &ErrorCore {
+        &self.core
+    }
+    fn input(&self) -> &Input {
+        &self.input
+    }
+    fn caused_by(&self) -> &str {
+        &self.caused_by
+    }
+    fn ref_inner(&self) -> Option<&Self> {
+        None
+    }
+}
+
+impl TracebackError {
+    /// `resolve` returns the `Input` of a frame's file if it is an Erg script.
+    /// Returns `None` if no frames belong to Erg scripts.
+    pub fn from_traceback(
+        traceback: &PyTraceback,
+        resolve: impl Fn(&str) -> Option<Input>,
+    ) -> Option<Self> {
+        let erg_frames = traceback
+            .frames
+            .iter()
+            .filter_map(|frame| resolve(&frame.file).map(|input| (frame, input)))
+            // the code of a dummy input cannot be displayed
+            .filter(|(_, input)| !matches!(input.kind, InputKind::Dummy))
+            .collect::<Vec<_>>();
+        let (innermost, input) = erg_frames.last()?;
+        let mut sub_messages = vec![];
+        let mut others = vec![];
+        for (frame, _) in erg_frames.iter() {
+            let name = demangle(&frame.name).unwrap_or_else(|| frame.name.clone());
+            if frame.file == innermost.file {
+                let loc = Self::line_loc(input, frame.line);
+                let msg = switch_lang!(
+                    "japanese" => format!("{name}内"),
+                    "simplified_chinese" => format!("在{name}中"),
+                    "traditional_chinese" => format!("在{name}中"),
+                    "english" => format!("in {name}"),
+                );
+                sub_messages.push(SubMessage::ambiguous_new(loc, vec![msg], None));
+            } else {
+                others.push(format!(
+                    "File {}, line {}, in {name}",
+                    frame.file, frame.line
+                ));
+            }
+        }
+        if !others.is_empty() {
+            if let Some(last) = sub_messages.last_mut() {
+                let hint = switch_lang!(
+                    "japanese" => "他のファイルのフレーム:",
+                    "simplified_chinese" => "其他文件中的帧:",
+                    "traditional_chinese" => "其他文件中的幀:",
+                    "english" => "frames in other files:",
+                );
+                last.hint = Some(format!("{hint}\n{}", others.join("\n")));
+            }
+        }
+        let kind = ErrorKind::from(&traceback.exception[..]);
+        let message = demangle_message(&traceback.message);
+        // user-defined exceptions are not in `ErrorKind`
+        let main_message = if kind == ErrorKind::UserError && traceback.exception != "UserError" {
+            if message.is_empty() {
+                traceback.exception.clone()
+            } else {
+                format!("{}: {message}", traceback.exception)
+            }
+        } else {
+            message
+        };
+        let loc = sub_messages.last().map_or(Location::Unknown, |sub| sub.loc);
+        let core = ErrorCore::new(sub_messages, main_message, 0, kind, loc);
+        Some(Self {
+            core: Box::new(core),
+            input: input.clone(),
+            caused_by: demangle(&innermost.name).unwrap_or_else(|| innermost.name.clone()),
+        })
+    }
+
+    /// Resolves the `.er` files of the frames.
+    pub fn from_erg_traceback(traceback: &PyTraceback) -> Option<Self> {
+        Self::from_traceback(traceback, erg_script_input)
+    }
+
+    /// Points the whole code of the line (except the indentation).
+    fn line_loc(input: &Input, line: u32) -> Location {
+        let code = input.reread_lines(line as usize, line as usize);
+        let Some(code) = code.first() else {
+            return Location::Line(line);
+        };
+        let trimmed = code.trim();
+        if trimmed.is_empty() {
+            return Location::Line(line);
+        }
+        let col_begin = code.find(trimmed).unwrap_or(0);
+        let col_end = col_begin + trimmed.chars().count();
+        Location::range(line, col_begin as u32, line, col_end as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demangle() {
+        assert_eq!(demangle("::x_L1").as_deref(), Some("x"));
+        assert_eq!(demangle("::z_L3_C4").as_deref(), Some("z"));
+        assert_eq!(demangle("::f__erg_proc___L2").as_deref(), Some("f!"));
+        assert_eq!(demangle("g__erg_proc__").as_deref(), Some("g!"));
+        assert_eq!(demangle("pub").as_deref(), Some("pub"));
+        assert_eq!(demangle("x_L").as_deref(), Some("x_L"));
+        assert_eq!(demangle("::#NamedTuple"), None);
+        assert_eq!(demangle("%1"), None);
+        assert_eq!(
+            demangle_message("name '::x_L1' is not defined"),
+            "name 'x' is not defined"
+        );
+        assert_eq!(
+            demangle_message("'C' object has no attribute '::f__erg_proc__'"),
+            "'C' object has no attribute 'f!'"
+        );
+    }
+
+    #[test]
+    fn test_parse_traceback() {
+        let traceback = PyTraceback::parse(
+            "1
+Traceback (most recent call last):
+  File \"<string>\", line 1, in <module>
+  File \"t.er\", line 7, in <module>
+    g! 0
+  File \"t.er\", line 3, in f
+    y / x
+    ~~^~~
+ZeroDivisionError: division by zero
+",
+        )
+        .unwrap();
+        assert_eq!(traceback.exception, "ZeroDivisionError");
+        assert_eq!(traceback.message, "division by zero");
+        assert_eq!(traceback.frames.len(), 3);
+        assert_eq!(
+            traceback.frames[2],
+            PyFrame {
+                file: "t.er".into(),
+                line: 3,
+                name: "f".into()
+            }
+        );
+        let err = TracebackError::from_traceback(&traceback, |file| {
+            (file == "t.er").then(|| Input::str("f x = 10 / x\ng! x = f x\n  y / x\n".into()))
+        })
+        .unwrap();
+        assert_eq!(err.core.kind, ErrorKind::ZeroDivisionError);
+        assert_eq!(err.core.loc, Location::range(3, 2, 3, 7));
+        assert_eq!(err.core.sub_messages.len(), 2);
+        assert_eq!(err.caused_by, "f");
+    }
+
+    #[test]
+    fn test_show_traceback_error() {
+        let traceback = PyTraceback::parse(
+            "Traceback (most recent call last):
+  File \"t.er\", line 1, in <module>
+AssertionError
+",
+        )
+        .unwrap();
+        let err = TracebackError::from_traceback(&traceback, |file| {
+            (file == "t.er").then(|| Input::str("assert 1 == 2\n".into()))
+        })
+        .unwrap();
+        let shown = err.show();
+        assert!(!shown.contains("#0000"), "{shown}");
+        assert!(shown.trim_end().ends_with("AssertionError"), "{shown}");
+    }
+}
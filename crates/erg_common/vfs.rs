@@ -35,6 +35,10 @@ impl VirtualFileSystem {
         self.path_cache.borrow().get(&(input, path)).cloned()
     }
 
+    pub fn remove_cached_path(&self, input: Input, path: PathBuf) {
+        self.path_cache.borrow_mut().remove(&(input, path));
+    }
+
     pub fn remove(&self, path: impl AsRef<Path>) {
         let path = NormalizedPathBuf::from(path.as_ref());
         self.cache.borrow_mut().remove(&path);
@@ -92,6 +96,12 @@ impl SharedVFS {
             .get_or_init(VirtualFileSystem::new)
             .get_cached_path(input, path)
     }
+
+    pub fn remove_cached_path(&self, input: Input, path: PathBuf) {
+        self.0
+            .get_or_init(VirtualFileSystem::new)
+            .remove_cached_path(input, path)
+    }
 }
 
 pub static VFS: SharedVFS = SharedVFS(OnceLock::new());
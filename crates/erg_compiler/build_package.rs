@@ -1,6 +1,6 @@
 use std::ffi::OsStr;
 use std::fmt;
-use std::fs::{metadata, remove_file, File};
+use std::fs::{create_dir_all, metadata, remove_file, File};
 use std::io::{stdout, BufRead, BufReader, Write};
 use std::marker::PhantomData;
 use std::option::Option;
@@ -22,6 +22,7 @@ use erg_common::io::Input;
 #[allow(unused)]
 use erg_common::log;
 use erg_common::pathutil::{mod_name, project_entry_dir_of, NormalizedPathBuf};
+use erg_common::python_util::spawn_py_piped;
 use erg_common::set::Set;
 use erg_common::spawn::spawn_new_thread;
 use erg_common::str::Str;
@@ -606,56 +607,97 @@ impl<ASTBuilder: ASTBuildable, HIRBuilder: Buildable>
             return Availability::NotFound;
         };
         let dummy_hash = meta.len();
-        if status.hash != dummy_hash {
+        // the size can be the same even if the script is modified
+        // (the timestamp is recorded in seconds)
+        let secs = |time: SystemTime| {
+            time.duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |dur| dur.as_secs())
+        };
+        let modified = meta
+            .modified()
+            .is_ok_and(|modified| secs(modified) > secs(status.timestamp));
+        if status.hash != dummy_hash || modified {
             Availability::OutOfDate
         } else {
             Availability::Available
         }
     }
 
-    fn try_gen_py_decl_file(&self, __name__: &Str) -> Result<PathBuf, ()> {
-        if let Ok(path) = self.cfg.input.resolve_py(Path::new(&__name__[..])) {
-            if self.cfg.input.path() == path.as_path() {
-                return Ok(path);
-            }
-            if self.cfg.use_pylyzer {
-                let (out, err) = if self.cfg.mode == ErgMode::LanguageServer || self.cfg.quiet_repl
-                {
-                    (Stdio::null(), Stdio::null())
-                } else {
-                    (Stdio::inherit(), Stdio::inherit())
-                };
-                // pylyzer is a static analysis tool for Python (https://github.com/mtshiba/pylyzer).
-                // It can convert a Python script to an Erg AST for code analysis.
-                // There is also an option to output the analysis result as `d.er`. Use this if the system have pylyzer installed.
-                // A type definition file may be generated even if not all type checks succeed.
-                if let Ok(status) = Command::new("pylyzer")
-                    .arg("--dump-decl")
-                    .arg(path.to_str().unwrap_or_default())
-                    .stdout(out)
-                    .stderr(err)
-                    .spawn()
-                    .and_then(|mut child| child.wait())
-                {
-                    if let Some(path) = self
-                        .cfg
-                        .input
-                        .resolve_decl_path(Path::new(&__name__[..]), &self.cfg)
-                    {
-                        let size = metadata(&path).or(Err(()))?.len();
-                        // if pylyzer crashed
-                        if !status.success() && size == 0 {
-                            // The presence of the decl file indicates that the analysis is in progress or completed,
-                            // so if pylyzer crashes in the middle of the analysis, delete the file.
-                            remove_file(&path).unwrap();
-                        } else {
-                            return Ok(path);
-                        }
+    fn try_gen_py_decl_file(&self, __name__: &Str, cfg: &ErgConfig) -> Result<PathBuf, ()> {
+        let mod_path = Path::new(&__name__[..]);
+        // `resolve_py` and `resolve_decl_path` share the cache, and the failed decl resolution is cached
+        VFS.remove_cached_path(cfg.input.clone(), mod_path.to_path_buf());
+        let resolved = cfg.input.resolve_py(mod_path);
+        VFS.remove_cached_path(cfg.input.clone(), mod_path.to_path_buf());
+        let Ok(path) = resolved else {
+            return Err(());
+        };
+        if cfg.input.path() == path.as_path() {
+            return Ok(path);
+        }
+        if self.cfg.use_pylyzer {
+            let (out, err) = if self.cfg.mode == ErgMode::LanguageServer || self.cfg.quiet_repl {
+                (Stdio::null(), Stdio::null())
+            } else {
+                (Stdio::inherit(), Stdio::inherit())
+            };
+            // pylyzer is a static analysis tool for Python (https://github.com/mtshiba/pylyzer).
+            // It can convert a Python script to an Erg AST for code analysis.
+            // There is also an option to output the analysis result as `d.er`. Use this if the system have pylyzer installed.
+            // A type definition file may be generated even if not all type checks succeed.
+            if let Ok(status) = Command::new("pylyzer")
+                .arg("--dump-decl")
+                .arg(path.to_str().unwrap_or_default())
+                .stdout(out)
+                .stderr(err)
+                .spawn()
+                .and_then(|mut child| child.wait())
+            {
+                if let Some(decl_path) = cfg.input.resolve_decl_path(mod_path, cfg) {
+                    let size = metadata(&decl_path).or(Err(()))?.len();
+                    // if pylyzer crashed
+                    if !status.success() && size == 0 {
+                        // The presence of the decl file indicates that the analysis is in progress or completed,
+                        // so if pylyzer crashes in the middle of the analysis, delete the file.
+                        remove_file(&decl_path).unwrap();
+                    } else {
+                        return Ok(decl_path);
                     }
                 }
+                VFS.remove_cached_path(cfg.input.clone(), mod_path.to_path_buf());
             }
         }
-        Err(())
+        let res = self.gen_py_decl_file(&path);
+        VFS.cache_path(cfg.input.clone(), mod_path.to_path_buf(), res.clone().ok());
+        res
+    }
+
+    /// Generates `{dir}/__pycache__/{name}.d.er` from the Python script `{dir}/{name}.py` (without pylyzer).
+    /// The declarations are derived from function signatures, type annotations, class bodies and `__all__`,
+    /// and anything else is declared as `Obj`.
+    fn gen_py_decl_file(&self, py_path: &Path) -> Result<PathBuf, ()> {
+        let code = include_str!("scripts/gen_decl.py");
+        let output = spawn_py_piped(self.cfg.py_command, code, &[py_path.to_str().ok_or(())?])
+            .and_then(|child| child.wait_with_output())
+            .or(Err(()))?;
+        if !output.status.success() {
+            log!(err "failed to generate the declarations of {}", py_path.display());
+            return Err(());
+        }
+        let decls = String::from_utf8(output.stdout).or(Err(()))?;
+        let stem = py_path.file_stem().ok_or(())?.to_string_lossy();
+        let cache_dir = py_path.parent().ok_or(())?.join("__pycache__");
+        create_dir_all(&cache_dir).or(Err(()))?;
+        let decl_path = cache_dir.join(format!("{stem}.d.er"));
+        let status = PylyzerStatus {
+            status: CheckStatus::Succeed,
+            file: py_path.to_path_buf(),
+            timestamp: SystemTime::now(),
+            hash: metadata(py_path).or(Err(()))?.len(),
+        };
+        let mut file = File::create(&decl_path).or(Err(()))?;
+        write!(file, "{status}\n{decls}").or(Err(()))?;
+        Ok(decl_path)
     }
 
     fn register(&mut self, expr: &mut Expr, cfg: &ErgConfig) -> ResolveResult<()> {
@@ -687,9 +729,21 @@ impl<ASTBuilder: ASTBuildable, HIRBuilder: Buildable>
                 .resolve_real_path(path, cfg)
                 .or_else(|| cfg.input.resolve_decl_path(path, cfg))
         } else {
-            cfg.input
+            match cfg
+                .input
                 .resolve_decl_path(path, cfg)
                 .or_else(|| cfg.input.resolve_real_path(path, cfg))
+            {
+                // the Python script has been modified since the declarations were generated
+                Some(decl_path)
+                    if ERG_MODE && matches!(Self::availability(&decl_path), OutOfDate) =>
+                {
+                    self.try_gen_py_decl_file(__name__, cfg)
+                        .ok()
+                        .or(Some(decl_path))
+                }
+                resolved => resolved,
+            }
         };
         VFS.cache_path(cfg.input.clone(), path.to_path_buf(), resolved.clone());
         let import_path = match resolved {
@@ -702,7 +756,7 @@ impl<ASTBuilder: ASTBuildable, HIRBuilder: Buildable>
                     sleep(Duration::from_millis(100));
                 }
                 if matches!(Self::availability(path), OutOfDate | NotFound | Unreadable) {
-                    if let Ok(path) = self.try_gen_py_decl_file(__name__) {
+                    if let Ok(path) = self.try_gen_py_decl_file(__name__, cfg) {
                         path
                     } else {
                         return Ok(());
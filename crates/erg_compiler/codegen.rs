@@ -15,6 +15,8 @@ use erg_common::opcode308::Opcode308;
 use erg_common::opcode309::Opcode309;
 use erg_common::opcode310::Opcode310;
 use erg_common::opcode311::{BinOpCode, Opcode311};
+use erg_common::opcode312::{Intrinsic1, Opcode312};
+use erg_common::opcode313::Opcode313;
 use erg_common::option_enum_unwrap;
 use erg_common::python_util::{env_python_version, PythonVersion};
 use erg_common::traits::{Locational, Stream};
@@ -43,7 +45,7 @@ use crate::hir::{
     Identifier, Lambda, List, Literal, NonDefaultParamSignature, Params, PatchDef, PosArg, ReDef,
     Record, Set, Signature, SubrSignature, Tuple, UnaryOp, VarSignature, HIR,
 };
-use crate::ty::codeobj::{CodeObj, CodeObjFlags, MakeFunctionFlags};
+use crate::ty::codeobj::{CodeObj, CodeObjFlags, ExceptionTableEntry, MakeFunctionFlags};
 use crate::ty::value::{GenTypeObj, ValueObj};
 use crate::ty::SubrType;
 use crate::ty::{HasType, Type, TypeCode, TypePair, VisibilityModifier};
@@ -158,6 +160,7 @@ pub struct PyCodeGenUnit {
     pub(crate) prev_lineno: u32,
     pub(crate) lasti: usize,
     pub(crate) prev_lasti: usize,
+    pub(crate) exception_entries: Vec<ExceptionTableEntry>,
     pub(crate) _refs: Vec<ValueObj>, // ref-counted objects
 }
 
@@ -200,6 +203,7 @@ impl PyCodeGenUnit {
             prev_lineno: firstlineno,
             lasti: 0,
             prev_lasti: 0,
+            exception_entries: vec![],
             _refs: vec![],
         }
     }
@@ -382,17 +386,50 @@ impl PyCodeGenerator {
     #[inline]
     #[allow(dead_code)]
     fn emit_print_expr(&mut self) {
-        self.write_instr(Opcode311::PRINT_EXPR);
-        self.write_arg(0);
-        self.stack_dec();
+        if self.py_version.minor >= Some(12) {
+            self.emit_call_intrinsic_1(Intrinsic1::PRINT);
+            self.emit_pop_top();
+        } else {
+            self.write_instr(Opcode311::PRINT_EXPR);
+            self.write_arg(0);
+            self.stack_dec();
+        }
+    }
+
+    /// Since 3.12, some instructions (`PRINT_EXPR`, `IMPORT_STAR`, etc.) are replaced with `CALL_INTRINSIC_1`.
+    fn emit_call_intrinsic_1(&mut self, intrinsic: Intrinsic1) {
+        self.write_instr(Opcode312::CALL_INTRINSIC_1);
+        self.write_arg(intrinsic as usize);
     }
 
     fn _emit_compare_op(&mut self, op: CompareOp) {
         self.write_instr(Opcode311::COMPARE_OP);
-        self.write_arg(op as usize);
+        self.write_arg(self.compare_op_arg(op as usize));
         self.stack_dec();
-        if self.py_version.minor >= Some(11) {
-            self.write_bytes(&[0; 4]);
+        match self.py_version.minor {
+            Some(11) => self.write_bytes(&[0; 4]),
+            Some(12..) => self.write_bytes(&[0; 2]),
+            _ => {}
+        }
+    }
+
+    /// Since 3.12, the comparison mask is stored in the lower bits of the `COMPARE_OP` arg.
+    fn compare_op_arg(&self, op: usize) -> usize {
+        // <, <=, ==, !=, >, >=
+        const MASKS: [usize; 6] = [2, 10, 8, 7, 4, 12];
+        match self.py_version.minor {
+            Some(12) => (op << 4) | MASKS[op],
+            Some(13..) => (op << 5) | MASKS[op],
+            _ => op,
+        }
+    }
+
+    /// Since 3.13, `POP_JUMP_IF_*` and `UNARY_NOT` require a bool value.
+    fn emit_to_bool(&mut self) {
+        if self.py_version.minor >= Some(13) {
+            self.write_instr_313(Opcode313::TO_BOOL);
+            self.write_arg(0);
+            self.write_bytes(&[0; 6]);
         }
     }
 
@@ -401,6 +438,7 @@ impl PyCodeGenerator {
     fn terminate(&mut self) {
         self.emit_push_null();
         self.emit_load_name_instr(Identifier::static_public("exit"));
+        self.emit_push_null_after();
         self.emit_load_const(1);
         if self.py_version.minor >= Some(11) {
             self.emit_precall_and_call(1);
@@ -412,7 +450,6 @@ impl PyCodeGenerator {
     }
 
     /// swap TOS and TOS1
-    #[allow(unused)]
     fn rot2(&mut self) {
         if self.py_version.minor >= Some(11) {
             self.write_instr(Opcode311::SWAP);
@@ -423,7 +460,6 @@ impl PyCodeGenerator {
         }
     }
 
-    #[allow(unused)]
     fn dup_top(&mut self) {
         if self.py_version.minor >= Some(11) {
             self.write_instr(Opcode311::COPY);
@@ -475,9 +511,7 @@ impl PyCodeGenerator {
         } else {
             jump_to
         };
-        if idx == 0
-            || !CommonOpcode::is_jump_op(*self.cur_block_codeobj().code.get(idx - 1).unwrap())
-        {
+        if idx == 0 || !self.is_jump_op(*self.cur_block_codeobj().code.get(idx - 1).unwrap()) {
             self.crash(&format!("calc_edit_jump: not jump op: {idx} {jump_to}"));
         }
         self.edit_code(idx, arg)
@@ -514,13 +548,14 @@ impl PyCodeGenerator {
     #[inline]
     fn extend_arg(&mut self, before_instr: usize, bytes: &[u8]) -> usize {
         let mut shift_bytes = 0;
+        let extended_arg = self.convert_opcode(EXTENDED_ARG as u8);
         for byte in bytes.iter().rev().skip(1) {
             self.mut_cur_block_codeobj()
                 .code
                 .insert(before_instr, *byte);
             self.mut_cur_block_codeobj()
                 .code
-                .insert(before_instr, CommonOpcode::EXTENDED_ARG as u8);
+                .insert(before_instr, extended_arg);
             self.mut_cur_block().lasti += 2;
             shift_bytes += 2;
         }
@@ -528,11 +563,38 @@ impl PyCodeGenerator {
     }
 
     fn write_instr<C: Into<u8>>(&mut self, code: C) {
-        self.mut_cur_block_codeobj().code.push(code.into());
+        let code = self.convert_opcode(code.into());
+        self.mut_cur_block_codeobj().code.push(code);
         self.mut_cur_block().lasti += 1;
         // log!(info "wrote: {}", code);
     }
 
+    /// write an instruction that does not exist before 3.13
+    fn write_instr_313(&mut self, code: Opcode313) {
+        self.mut_cur_block_codeobj().code.push(code as u8);
+        self.mut_cur_block().lasti += 1;
+    }
+
+    /// In 3.13, all opcodes are renumbered.
+    /// Instructions are written with the numbers up to 3.12 and converted here.
+    fn convert_opcode(&mut self, code: u8) -> u8 {
+        if self.py_version.minor < Some(13) {
+            return code;
+        }
+        match Opcode312::try_from(code).ok().and_then(Opcode313::from_312) {
+            Some(op) => op as u8,
+            None => self.crash(&format!("convert_opcode: not supported in 3.13: {code}")),
+        }
+    }
+
+    fn is_jump_op(&self, op: u8) -> bool {
+        if self.py_version.minor >= Some(13) {
+            CommonOpcode::is_jump_op_313(op)
+        } else {
+            CommonOpcode::is_jump_op(op)
+        }
+    }
+
     /// returns: shift bytes
     fn write_arg(&mut self, code: usize) -> usize {
         match u8::try_from(code) {
@@ -543,13 +605,11 @@ impl PyCodeGenerator {
             }
             Err(_) => match u16::try_from(code) {
                 Ok(_) => {
-                    let delta =
-                        if CommonOpcode::is_jump_op(*self.cur_block_codeobj().code.last().unwrap())
-                        {
-                            2
-                        } else {
-                            0
-                        };
+                    let delta = if self.is_jump_op(*self.cur_block_codeobj().code.last().unwrap()) {
+                        2
+                    } else {
+                        0
+                    };
                     let arg = code + delta;
                     let bytes = u16::try_from(arg).unwrap().to_be_bytes(); // [u8; 2]
                     let before_instr = self.lasti().saturating_sub(1);
@@ -559,7 +619,7 @@ impl PyCodeGenerator {
                 }
                 Err(_) => {
                     let delta = 0;
-                    if CommonOpcode::is_jump_op(*self.cur_block_codeobj().code.last().unwrap()) {
+                    if self.is_jump_op(*self.cur_block_codeobj().code.last().unwrap()) {
                         6
                     } else {
                         0
@@ -796,6 +856,8 @@ impl PyCodeGenerator {
             StoreLoadKind::Local | StoreLoadKind::LocalConst => match acc_kind {
                 Name => LOAD_NAME as u8,
                 UnboundAttr => LOAD_ATTR as u8,
+                // `LOAD_METHOD` is merged into `LOAD_ATTR` in 3.12
+                BoundAttr if self.py_version.minor >= Some(12) => LOAD_ATTR as u8,
                 BoundAttr => LOAD_METHOD as u8,
             },
         }
@@ -859,6 +921,7 @@ impl PyCodeGenerator {
             "NoneType" => {
                 self.emit_push_null();
                 self.emit_load_name_instr(Identifier::static_public("type"));
+                self.emit_push_null_after();
                 let none = Expr::Literal(Literal::new(ValueObj::None, Token::DUMMY));
                 let args = Args::single(PosArg::new(none));
                 self.emit_args_311(args, AccessKind::Name);
@@ -920,7 +983,9 @@ impl PyCodeGenerator {
         self.stack_inc();
         self.mut_cur_block_codeobj().stacksize += 2;
         if instr == LOAD_GLOBAL as u8 && self.py_version.minor >= Some(11) {
-            self.write_bytes(&[0; 2]);
+            if self.py_version.minor == Some(11) {
+                self.write_bytes(&[0; 2]);
+            }
             self.write_bytes(&[0; 8]);
         }
     }
@@ -971,9 +1036,15 @@ impl PyCodeGenerator {
         self.write_instr(IMPORT_NAME);
         self.write_arg(name.idx);
         self.stack_inc();
-        self.write_instr(IMPORT_STAR);
-        self.write_arg(0);
-        self.stack_dec_n(3);
+        if self.py_version.minor >= Some(12) {
+            self.emit_call_intrinsic_1(Intrinsic1::IMPORT_STAR);
+            self.emit_pop_top();
+            self.stack_dec_n(2);
+        } else {
+            self.write_instr(IMPORT_STAR);
+            self.write_arg(0);
+            self.stack_dec_n(3);
+        }
     }
 
     /// item: (name, renamed)
@@ -1010,9 +1081,14 @@ impl PyCodeGenerator {
             .unwrap_or_else(|| self.register_attr(escaped));
         let instr = self.select_load_instr(name.kind, UnboundAttr);
         self.write_instr(instr);
-        self.write_arg(name.idx);
-        if self.py_version.minor >= Some(11) {
-            self.write_bytes(&[0; 8]);
+        if self.py_version.minor >= Some(12) {
+            self.write_arg(name.idx << 1);
+            self.write_bytes(&[0; 18]);
+        } else {
+            self.write_arg(name.idx);
+            if self.py_version.minor >= Some(11) {
+                self.write_bytes(&[0; 8]);
+            }
         }
     }
 
@@ -1024,10 +1100,17 @@ impl PyCodeGenerator {
             .unwrap_or_else(|| self.register_method(escaped));
         let instr = self.select_load_instr(name.kind, acc_kind);
         self.write_instr(instr);
-        self.write_arg(name.idx);
-        if self.py_version.minor >= Some(11) {
+        if self.py_version.minor >= Some(12) {
+            // the lowest bit means that this is a method load
+            self.write_arg((name.idx << 1) | 1);
             self.stack_inc(); // instead of PUSH_NULL
-            self.write_bytes(&[0; 20]);
+            self.write_bytes(&[0; 18]);
+        } else {
+            self.write_arg(name.idx);
+            if self.py_version.minor >= Some(11) {
+                self.stack_inc(); // instead of PUSH_NULL
+                self.write_bytes(&[0; 20]);
+            }
         }
     }
 
@@ -1096,7 +1179,8 @@ impl PyCodeGenerator {
             return;
         }
         let lasop_t_idx = self.cur_block_codeobj().code.len() - 2;
-        if self.cur_block_codeobj().code.get(lasop_t_idx) == Some(&(POP_TOP as u8)) {
+        let pop_top = self.convert_opcode(POP_TOP as u8);
+        if self.cur_block_codeobj().code.get(lasop_t_idx) == Some(&pop_top) {
             self.mut_cur_block_codeobj().code.pop();
             self.mut_cur_block_codeobj().code.pop();
             self.mut_cur_block().lasti -= 2;
@@ -1209,25 +1293,63 @@ impl PyCodeGenerator {
         }
     }
 
+    /// In 3.11 and 3.12, `NULL` is pushed before the callable object.
     fn emit_push_null(&mut self) {
-        if self.py_version.minor >= Some(11) {
+        if matches!(self.py_version.minor, Some(11 | 12)) {
             self.write_instr(Opcode311::PUSH_NULL);
             self.write_arg(0);
             self.stack_inc();
         }
     }
 
+    /// Since 3.13, `NULL` is pushed after the callable object.
+    fn emit_push_null_after(&mut self) {
+        if self.py_version.minor >= Some(13) {
+            self.write_instr(Opcode311::PUSH_NULL);
+            self.write_arg(0);
+            self.stack_inc();
+        }
+    }
+
+    /// `PRECALL` is removed in 3.12
     fn emit_precall_and_call(&mut self, argc: usize) {
-        self.write_instr(Opcode311::PRECALL);
-        self.write_arg(argc);
-        self.write_arg(0);
-        self.write_arg(0);
+        if self.py_version.minor < Some(12) {
+            self.write_instr(Opcode311::PRECALL);
+            self.write_arg(argc);
+            self.write_arg(0);
+            self.write_arg(0);
+        }
         self.write_instr(Opcode311::CALL);
         self.write_arg(argc);
-        self.write_bytes(&[0; 8]);
+        if self.py_version.minor >= Some(12) {
+            self.write_bytes(&[0; 6]);
+        } else {
+            self.write_bytes(&[0; 8]);
+        }
         self.stack_dec();
     }
 
+    /// Since 3.13, the flags of `MAKE_FUNCTION` are set by `SET_FUNCTION_ATTRIBUTE`.
+    fn write_make_function(&mut self, flag: usize) {
+        self.write_instr(MAKE_FUNCTION);
+        if self.py_version.minor >= Some(13) {
+            self.write_arg(0);
+            for attr in [
+                MakeFunctionFlags::Closure,
+                MakeFunctionFlags::Annotations,
+                MakeFunctionFlags::KwDefaults,
+                MakeFunctionFlags::Defaults,
+            ] {
+                if flag & attr as usize != 0 {
+                    self.write_instr_313(Opcode313::SET_FUNCTION_ATTRIBUTE);
+                    self.write_arg(attr as usize);
+                }
+            }
+        } else {
+            self.write_arg(flag);
+        }
+    }
+
     fn emit_call_instr(&mut self, argc: usize, kind: AccessKind) {
         if self.py_version.minor >= Some(11) {
             self.emit_precall_and_call(argc);
@@ -1241,7 +1363,13 @@ impl PyCodeGenerator {
     }
 
     fn emit_call_kw_instr(&mut self, argc: usize, kws: Vec<ValueObj>) {
-        if self.py_version.minor >= Some(11) {
+        if self.py_version.minor >= Some(13) {
+            // `KW_NAMES` is replaced with `CALL_KW`
+            self.emit_load_const(kws);
+            self.write_instr_313(Opcode313::CALL_KW);
+            self.write_arg(argc);
+            self.stack_dec_n(2);
+        } else if self.py_version.minor >= Some(11) {
             let idx = self.register_const(kws);
             self.write_instr(Opcode311::KW_NAMES);
             self.write_arg(idx);
@@ -1262,6 +1390,7 @@ impl PyCodeGenerator {
         self.write_instr(LOAD_BUILD_CLASS);
         self.write_arg(0);
         self.stack_inc();
+        self.emit_push_null_after();
         let kind = def.def_kind();
         let code = self.emit_trait_block(kind, &def.sig, def.body.block);
         self.emit_load_const(code);
@@ -1270,8 +1399,7 @@ impl PyCodeGenerator {
         } else {
             self.stack_inc();
         }
-        self.write_instr(MAKE_FUNCTION);
-        self.write_arg(0);
+        self.write_make_function(0);
         self.emit_load_const(def.sig.ident().inspect().clone());
         self.emit_load_name_instr(Identifier::private("#ABCMeta"));
         let subclasses_len = 1;
@@ -1355,15 +1483,6 @@ impl PyCodeGenerator {
         }
         // end of flagging
         let unit = self.units.pop().unwrap();
-        if !self.units.is_empty() {
-            let ld = unit.prev_lineno - self.cur_block().prev_lineno;
-            if ld != 0 {
-                if let Some(l) = self.mut_cur_block_codeobj().lnotab.last_mut() {
-                    *l += u8::try_from(ld).unwrap();
-                }
-                self.mut_cur_block().prev_lineno += ld;
-            }
-        }
         unit.codeobj
     }
 
@@ -1374,10 +1493,11 @@ impl PyCodeGenerator {
         deco: Option<Identifier>,
     ) {
         log!(info "entered {} ({ident})", fn_name!());
-        self.emit_push_null();
         let deco_is_some = deco.is_some();
         if let Some(deco) = deco {
+            self.emit_push_null();
             self.emit_load_name_instr(deco);
+            self.emit_push_null_after();
         }
         let code = {
             self.unit_size += 1;
@@ -1395,17 +1515,6 @@ impl PyCodeGenerator {
             self.write_instr(RETURN_VALUE);
             self.write_arg(0);
             let unit = self.units.pop().unwrap();
-            if !self.units.is_empty() {
-                let ld = unit
-                    .prev_lineno
-                    .saturating_sub(self.cur_block().prev_lineno);
-                if ld != 0 {
-                    if let Some(l) = self.mut_cur_block_codeobj().lnotab.last_mut() {
-                        *l += u8::try_from(ld).unwrap();
-                    }
-                    self.mut_cur_block().prev_lineno += ld;
-                }
-            }
             unit.codeobj
         };
         self.emit_load_const(code);
@@ -1418,8 +1527,7 @@ impl PyCodeGenerator {
         } else {
             self.stack_inc();
         }
-        self.write_instr(MAKE_FUNCTION);
-        self.write_arg(0);
+        self.write_make_function(0);
         if deco_is_some {
             self.emit_call_instr(1, Name);
             self.stack_dec();
@@ -1438,6 +1546,7 @@ impl PyCodeGenerator {
         self.write_instr(LOAD_BUILD_CLASS);
         self.write_arg(0);
         self.stack_inc();
+        self.emit_push_null_after();
         let code = self.emit_class_block(class_def);
         self.emit_load_const(code);
         if self.py_version.minor < Some(11) {
@@ -1445,8 +1554,7 @@ impl PyCodeGenerator {
         } else {
             self.stack_inc();
         }
-        self.write_instr(MAKE_FUNCTION);
-        self.write_arg(0);
+        self.write_make_function(0);
         self.emit_load_const(ident.inspect().clone());
         // LOAD subclasses
         let subclasses_len = self.emit_require_type(obj, require_or_sup);
@@ -1613,8 +1721,7 @@ impl PyCodeGenerator {
         } else {
             self.stack_inc();
         }
-        self.write_instr(MAKE_FUNCTION);
-        self.write_arg(make_function_flag);
+        self.write_make_function(make_function_flag);
         for _ in 0..n_decos {
             let argc = if self.py_version.minor >= Some(11) {
                 0
@@ -1650,11 +1757,13 @@ impl PyCodeGenerator {
             lambda.params.defaults,
             &mut make_function_flag,
         );
-        let flags = if lambda.params.var_params.is_some() {
-            CodeObjFlags::VarArgs as u32
-        } else {
-            0
-        };
+        let mut flags = 0;
+        if lambda.params.var_params.is_some() {
+            flags += CodeObjFlags::VarArgs as u32;
+        }
+        if lambda.params.kw_var_params.is_some() {
+            flags += CodeObjFlags::VarKeywords as u32;
+        }
         let code = self.emit_block(
             lambda.body,
             lambda.params.guards,
@@ -1672,11 +1781,13 @@ impl PyCodeGenerator {
         } else {
             self.stack_inc();
         }
-        self.write_instr(MAKE_FUNCTION);
-        self.write_arg(make_function_flag);
+        self.write_make_function(make_function_flag);
         // stack_dec: <lambda code obj> + <name "<lambda>"> -> <function>
         self.stack_dec();
-        if make_function_flag & MakeFunctionFlags::Defaults as usize != 0 {
+        if make_function_flag
+            & (MakeFunctionFlags::Defaults as usize | MakeFunctionFlags::KwDefaults as usize)
+            != 0
+        {
             self.stack_dec();
         }
         debug_assert_eq!(self.stack_len(), init_stack_len + 1);
@@ -1774,6 +1885,9 @@ impl PyCodeGenerator {
 
     fn emit_unaryop(&mut self, unary: UnaryOp) {
         log!(info "entered {} ({unary})", fn_name!());
+        if unary.op.is(TokenKind::Try) {
+            return self.emit_try(unary);
+        }
         let init_stack_len = self.stack_len();
         let val_t = unary
             .info
@@ -1791,10 +1905,9 @@ impl PyCodeGenerator {
                 if !self.mutate_op_loaded {
                     self.load_mutate_op();
                 }
-                if self.py_version.minor >= Some(11) {
-                    self.emit_push_null();
-                }
+                self.emit_push_null();
                 self.emit_load_name_instr(Identifier::private("#mutate_operator"));
+                self.emit_push_null_after();
                 NOP // ERG_MUTATE,
             }
             _ => {
@@ -1810,7 +1923,9 @@ impl PyCodeGenerator {
             }
         };
         self.emit_expr(*unary.expr);
-        if instr != NOP {
+        if instr == UNARY_POSITIVE && self.py_version.minor >= Some(12) {
+            self.emit_call_intrinsic_1(Intrinsic1::UNARY_POSITIVE);
+        } else if instr != NOP {
             self.write_instr(instr);
             self.write_arg(tycode as usize);
         } else {
@@ -1835,18 +1950,22 @@ impl PyCodeGenerator {
             TokenKind::RightOpen => {
                 self.emit_push_null();
                 self.emit_load_name_instr(Identifier::static_public("RightOpenRange"));
+                self.emit_push_null_after();
             }
             TokenKind::LeftOpen => {
                 self.emit_push_null();
                 self.emit_load_name_instr(Identifier::static_public("LeftOpenRange"));
+                self.emit_push_null_after();
             }
             TokenKind::Closed => {
                 self.emit_push_null();
                 self.emit_load_name_instr(Identifier::static_public("ClosedRange"));
+                self.emit_push_null_after();
             }
             TokenKind::Open => {
                 self.emit_push_null();
                 self.emit_load_name_instr(Identifier::static_public("OpenRange"));
+                self.emit_push_null_after();
             }
             // From 3.10, `or` can be used for types.
             // But Erg supports Python 3.7~, so we should use `typing.Union`.
@@ -1855,10 +1974,15 @@ impl PyCodeGenerator {
                 let args = Args::pos_only(vec![PosArg::new(*bin.lhs), PosArg::new(*bin.rhs)], None);
                 self.emit_push_null();
                 self.emit_load_name_instr(Identifier::private("#UnionType"));
+                self.emit_push_null_after();
                 self.emit_args_311(args, Name);
                 return;
             }
             // short circuiting
+            TokenKind::OrOp if self.py_version.minor >= Some(12) => {
+                self.emit_short_circuit(*bin.lhs, *bin.rhs, true);
+                return;
+            }
             TokenKind::OrOp => {
                 self.emit_expr(*bin.lhs);
                 let idx = self.lasti();
@@ -1875,6 +1999,10 @@ impl PyCodeGenerator {
                 self.stack_dec();
                 return;
             }
+            TokenKind::AndOp if self.py_version.minor >= Some(12) => {
+                self.emit_short_circuit(*bin.lhs, *bin.rhs, false);
+                return;
+            }
             TokenKind::AndOp => {
                 self.emit_expr(*bin.lhs);
                 let idx = self.lasti();
@@ -1902,6 +2030,7 @@ impl PyCodeGenerator {
                 }
                 self.emit_push_null();
                 self.emit_load_name_instr(Identifier::private("#contains_operator"));
+                self.emit_push_null_after();
             }
             _ => {}
         }
@@ -1924,6 +2053,93 @@ impl PyCodeGenerator {
         debug_assert_eq!(self.stack_len(), init_stack_len + 1);
     }
 
+    /// `JUMP_IF_TRUE_OR_POP` and `JUMP_IF_FALSE_OR_POP` are removed in 3.12
+    fn emit_short_circuit(&mut self, lhs: Expr, rhs: Expr, jump_if: bool) {
+        self.emit_expr(lhs);
+        self.copy(1);
+        self.emit_to_bool();
+        let idx = self.lasti();
+        self.write_instr(EXTENDED_ARG);
+        self.write_arg(0);
+        if jump_if {
+            self.write_instr(Opcode310::POP_JUMP_IF_TRUE);
+        } else {
+            self.write_instr(Opcode310::POP_JUMP_IF_FALSE);
+        }
+        self.write_arg(0);
+        self.stack_dec();
+        if self.py_version.minor >= Some(13) {
+            self.write_bytes(&[0; 2]);
+        }
+        self.emit_pop_top();
+        self.emit_expr(rhs);
+        let arg = self.lasti() - self.cur_jump_base(idx);
+        self.fill_jump(idx + 1, arg);
+    }
+
+    /// `idx`: the index of `EXTENDED_ARG` before a conditional jump instruction.
+    /// Returns the index of the next instruction (the base of the relative jump).
+    fn cur_jump_base(&self, idx: usize) -> usize {
+        if self.py_version.minor >= Some(13) {
+            idx + 6
+        } else {
+            idx + 4
+        }
+    }
+
+    /// ```python
+    /// x?
+    /// # =>
+    /// if isinstance(x, BaseException):
+    ///     return x # `raise x` at the top level
+    /// x
+    /// ```
+    fn emit_try(&mut self, unary: UnaryOp) {
+        log!(info "entered {} ({unary})", fn_name!());
+        self.emit_expr(*unary.expr);
+        // isinstance(x, BaseException)
+        if self.py_version.minor >= Some(11) {
+            self.emit_push_null();
+            self.emit_load_name_instr(Identifier::static_public("isinstance"));
+            self.emit_push_null_after();
+            self.copy(3);
+        } else {
+            self.dup_top();
+            self.emit_load_name_instr(Identifier::static_public("isinstance"));
+            self.rot2();
+        }
+        self.emit_load_name_instr(Identifier::static_public("BaseException"));
+        if self.py_version.minor >= Some(11) {
+            self.emit_precall_and_call(2);
+        } else {
+            self.write_instr(Opcode310::CALL_FUNCTION);
+            self.write_arg(2);
+        }
+        self.stack_dec_n(2);
+        let idx_pop_jump_if_false = self.lasti();
+        self.write_instr(EXTENDED_ARG);
+        self.write_arg(0);
+        self.write_instr(Opcode310::POP_JUMP_IF_FALSE);
+        self.write_arg(0);
+        if self.py_version.minor >= Some(13) {
+            self.write_bytes(&[0; 2]);
+        }
+        self.stack_dec();
+        if self.is_toplevel() {
+            self.write_instr(RAISE_VARARGS);
+            self.write_arg(1);
+        } else {
+            self.write_instr(RETURN_VALUE);
+            self.write_arg(0);
+        }
+        let idx_end = if self.py_version.minor >= Some(11) {
+            self.lasti() - self.cur_jump_base(idx_pop_jump_if_false)
+        } else {
+            self.lasti()
+        };
+        self.fill_jump(idx_pop_jump_if_false + 1, idx_end);
+    }
+
     fn emit_binop_instr(&mut self, binop: Token, type_pair: TypePair) {
         if self.py_version.minor >= Some(11) {
             self.emit_binop_instr_311(binop, type_pair);
@@ -2099,10 +2315,12 @@ impl PyCodeGenerator {
             | TokenKind::Closed
             | TokenKind::Open
             | TokenKind::ContainsOp => {
-                self.write_instr(Opcode311::PRECALL);
-                self.write_arg(2);
-                self.write_arg(0);
-                self.write_arg(0);
+                if self.py_version.minor < Some(12) {
+                    self.write_instr(Opcode311::PRECALL);
+                    self.write_arg(2);
+                    self.write_arg(0);
+                    self.write_arg(0);
+                }
                 Opcode311::CALL
             }
             _ => {
@@ -2128,12 +2346,12 @@ impl PyCodeGenerator {
             TokenKind::AndOp | TokenKind::BitAnd => BinOpCode::And as usize,
             TokenKind::OrOp | TokenKind::BitOr => BinOpCode::Or as usize,
             TokenKind::BitXor => BinOpCode::Xor as usize,
-            TokenKind::Less => 0,
-            TokenKind::LessEq => 1,
-            TokenKind::DblEq => 2,
-            TokenKind::NotEq => 3,
-            TokenKind::Gre => 4,
-            TokenKind::GreEq => 5,
+            TokenKind::Less => self.compare_op_arg(0),
+            TokenKind::LessEq => self.compare_op_arg(1),
+            TokenKind::DblEq => self.compare_op_arg(2),
+            TokenKind::NotEq => self.compare_op_arg(3),
+            TokenKind::Gre => self.compare_op_arg(4),
+            TokenKind::GreEq => self.compare_op_arg(5),
             TokenKind::IsOp => 0,
             TokenKind::IsNotOp => 1,
             TokenKind::LeftOpen
@@ -2145,13 +2363,20 @@ impl PyCodeGenerator {
         };
         self.write_instr(instr);
         self.write_arg(arg);
+        let since_312 = self.py_version.minor >= Some(12);
         match instr {
+            Opcode311::CALL if since_312 => {
+                self.write_bytes(&[0; 6]);
+            }
             Opcode311::CALL => {
                 self.write_bytes(&[0; 8]);
             }
             Opcode311::BINARY_OP => {
                 self.write_bytes(&[0; 2]);
             }
+            Opcode311::COMPARE_OP if since_312 => {
+                self.write_bytes(&[0; 2]);
+            }
             Opcode311::COMPARE_OP => {
                 self.write_bytes(&[0; 4]);
             }
@@ -2192,6 +2417,7 @@ impl PyCodeGenerator {
         log!(info "entered {}", fn_name!());
         let expr = args.remove_left_or_key("b").unwrap();
         self.emit_expr(expr);
+        self.emit_to_bool();
         self.write_instr(UNARY_NOT);
         self.write_arg(0);
     }
@@ -2226,6 +2452,7 @@ impl PyCodeGenerator {
         let init_stack_len = self.stack_len();
         let cond = args.remove(0);
         self.emit_expr(cond);
+        self.emit_to_bool();
         let idx_pop_jump_if_false = self.lasti();
         self.write_instr(EXTENDED_ARG);
         self.write_arg(0);
@@ -2233,6 +2460,9 @@ impl PyCodeGenerator {
         self.write_instr(Opcode310::POP_JUMP_IF_FALSE);
         // cannot detect where to jump to at this moment, so put as 0
         self.write_arg(0);
+        if self.py_version.minor >= Some(13) {
+            self.write_bytes(&[0; 2]);
+        }
         match args.remove(0) {
             // then block
             Expr::Lambda(lambda) => {
@@ -2251,7 +2481,7 @@ impl PyCodeGenerator {
             self.write_arg(0);
             // else block
             let idx_else_begin = match self.py_version.minor {
-                Some(11) => self.lasti() - idx_pop_jump_if_false - 4,
+                Some(11..) => self.lasti() - self.cur_jump_base(idx_pop_jump_if_false),
                 Some(7..=10) => self.lasti(),
                 _ => self.lasti(),
             };
@@ -2274,13 +2504,13 @@ impl PyCodeGenerator {
         } else {
             self.write_instr(Opcode311::JUMP_FORWARD);
             let jump_to = match self.py_version.minor {
-                Some(11 | 10) => 1,
+                Some(10..) => 1,
                 _ => 2,
             };
             self.write_arg(jump_to);
             // no else block
             let idx_end = if self.py_version.minor >= Some(11) {
-                self.lasti() - idx_pop_jump_if_false - 3
+                self.lasti() - self.cur_jump_base(idx_pop_jump_if_false)
             } else {
                 self.lasti()
             };
@@ -2312,6 +2542,9 @@ impl PyCodeGenerator {
         // but after executing this instruction, stack_len should be 1
         // cannot detect where to jump to at this moment, so put as 0
         self.write_arg(0);
+        if self.py_version.minor >= Some(12) {
+            self.write_bytes(&[0; 2]);
+        }
         let Expr::Lambda(lambda) = args.remove(0) else {
             unreachable!()
         };
@@ -2327,9 +2560,12 @@ impl PyCodeGenerator {
         self.write_instr(EXTENDED_ARG);
         self.write_arg(0);
         match self.py_version.minor {
-            Some(11) => {
+            Some(11..) => {
                 self.write_instr(Opcode311::JUMP_BACKWARD);
                 self.write_arg(0);
+                if self.py_version.minor >= Some(13) {
+                    self.write_bytes(&[0; 2]);
+                }
                 self.fill_jump(idx + 1, self.lasti() - idx_for_iter);
             }
             Some(7..=10) => {
@@ -2340,7 +2576,18 @@ impl PyCodeGenerator {
             _ => todo!("not supported Python version"),
         }
         let idx_end = self.lasti();
-        self.fill_jump(idx_for_iter + 1, idx_end - idx_for_iter - 2 - 2);
+        if self.py_version.minor >= Some(12) {
+            // FOR_ITER jumps to END_FOR and skips it (and POP_TOP in 3.13)
+            self.write_instr(Opcode312::END_FOR);
+            self.write_arg(0);
+            if self.py_version.minor >= Some(13) {
+                self.write_instr(POP_TOP);
+                self.write_arg(0);
+            }
+            self.fill_jump(idx_for_iter + 1, idx_end - idx_for_iter - 2 - 2 - 2);
+        } else {
+            self.fill_jump(idx_for_iter + 1, idx_end - idx_for_iter - 2 - 2);
+        }
         self.stack_dec();
         self.emit_load_const(ValueObj::None);
         debug_assert_eq!(self.stack_len(), _init_stack_len + 1);
@@ -2359,6 +2606,9 @@ impl PyCodeGenerator {
             Expr::Accessor(acc) => Expr::Accessor(acc).call_expr(Args::empty()),
             _ => todo!(),
         };
+        if self.py_version.minor >= Some(12) {
+            return self.emit_while_instr_312(cond, args);
+        }
         // Evaluate again at the end of the loop
         self.emit_expr(cond.clone());
         let idx_while = self.lasti();
@@ -2400,6 +2650,44 @@ impl PyCodeGenerator {
         debug_assert_eq!(self.stack_len(), _init_stack_len + 1);
     }
 
+    /// `POP_JUMP_BACKWARD_IF_TRUE` is removed in 3.12
+    fn emit_while_instr_312(&mut self, cond: Expr, mut args: Args) {
+        let _init_stack_len = self.stack_len();
+        let idx_cond = self.lasti();
+        self.emit_expr(cond);
+        self.emit_to_bool();
+        let idx_while = self.lasti();
+        self.write_instr(EXTENDED_ARG);
+        self.write_arg(0);
+        self.write_instr(Opcode310::POP_JUMP_IF_FALSE);
+        self.write_arg(0);
+        if self.py_version.minor >= Some(13) {
+            self.write_bytes(&[0; 2]);
+        }
+        self.stack_dec();
+        let Expr::Lambda(lambda) = args.remove(0) else {
+            unreachable!()
+        };
+        let init_stack_len = self.stack_len();
+        self.emit_control_block(lambda.body, lambda.params);
+        if self.stack_len() > init_stack_len {
+            self.emit_pop_top();
+        }
+        let idx = self.lasti();
+        self.write_instr(EXTENDED_ARG);
+        self.write_arg(0);
+        self.write_instr(Opcode311::JUMP_BACKWARD);
+        self.write_arg(0);
+        if self.py_version.minor >= Some(13) {
+            self.write_bytes(&[0; 2]);
+        }
+        self.fill_jump(idx + 1, self.lasti() - idx_cond);
+        let idx_end = self.lasti() - self.cur_jump_base(idx_while);
+        self.fill_jump(idx_while + 1, idx_end);
+        self.emit_load_const(ValueObj::None);
+        debug_assert_eq!(self.stack_len(), _init_stack_len + 1);
+    }
+
     fn emit_match_instr(&mut self, mut args: Args, _use_erg_specific: bool) {
         log!(info "entered {}", fn_name!());
         let init_stack_len = self.stack_len();
@@ -2426,7 +2714,8 @@ impl PyCodeGenerator {
             self.stack_dec();
             for pop_jump_point in pop_jump_points {
                 let idx = match self.py_version.minor {
-                    Some(11) => self.lasti() - pop_jump_point,
+                    Some(13..) => self.lasti() - pop_jump_point - 2,
+                    Some(11 | 12) => self.lasti() - pop_jump_point,
                     Some(10) => self.lasti() + 4,
                     _ => self.lasti() + 4,
                 };
@@ -2466,7 +2755,12 @@ impl PyCodeGenerator {
             _other => unreachable!(),
         }
         let mut pop_jump_points = vec![];
-        let last = params.guards.len().saturating_sub(1);
+        // the original value must be popped after the last condition (not binding) is satisfied
+        let last = params
+            .guards
+            .iter()
+            .rposition(|guard| matches!(guard, GuardClause::Condition(_)))
+            .unwrap_or(0);
         for (i, mut guard) in params.guards.into_iter().enumerate() {
             if let GuardClause::Condition(Expr::BinOp(BinOp {
                 op:
@@ -2499,6 +2793,7 @@ impl PyCodeGenerator {
                     if is_last_arm {
                         self.emit_pop_top();
                     } else {
+                        self.emit_to_bool();
                         pop_jump_points.push(self.lasti());
                         // HACK: match branches often jump very far (beyond the u8 range),
                         // so the jump destination should be reserved as the u16 range.
@@ -2509,6 +2804,9 @@ impl PyCodeGenerator {
                         // but the numbers are the same, only the way the jumping points are calculated is different.
                         self.write_instr(Opcode310::POP_JUMP_IF_FALSE); // jump to the next case
                         self.write_arg(0);
+                        if self.py_version.minor >= Some(13) {
+                            self.write_bytes(&[0; 2]);
+                        }
                         // if matched, pop original
                         if i == last {
                             self.emit_pop_top();
@@ -2572,6 +2870,105 @@ impl PyCodeGenerator {
         self.emit_load_name_instr(stash);
     }
 
+    /// Since 3.11, the exception handler is specified by the exception table
+    fn emit_with_instr_312(&mut self, mut args: Args) {
+        log!(info "entered {}", fn_name!());
+        if !matches!(args.get(1).unwrap(), Expr::Lambda(_)) {
+            return self.deopt_instr(ControlKind::With, args);
+        }
+        let expr = args.remove(0);
+        let Expr::Lambda(lambda) = args.remove(0) else {
+            unreachable!()
+        };
+        let params = self.gen_param_names(&lambda.params);
+        let init_stack_len = self.stack_len() as usize;
+        self.emit_expr(expr);
+        self.write_instr(Opcode311::BEFORE_WITH);
+        self.write_arg(0);
+        // push __exit__, __enter__() to the stack
+        self.stack_inc_n(2);
+        let idx_body_begin = self.lasti();
+        let lambda_line = lambda.body.last().unwrap().ln_begin().unwrap_or(0);
+        self.emit_with_block(lambda.body, params);
+        let stash = Identifier::private_with_line(self.fresh_gen.fresh_varname(), lambda_line);
+        self.emit_store_instr(stash.clone(), Name);
+        let idx_body_end = self.lasti();
+        self.emit_load_const(ValueObj::None);
+        self.emit_load_const(ValueObj::None);
+        self.emit_load_const(ValueObj::None);
+        self.emit_precall_and_call(2);
+        self.emit_pop_top();
+        let idx_jump_forward = self.lasti();
+        self.write_instr(EXTENDED_ARG);
+        self.write_arg(0);
+        self.write_instr(Opcode311::JUMP_FORWARD);
+        self.write_arg(0);
+        // exception handler: [__exit__, lasti, exc] on the stack
+        let idx_handler = self.lasti();
+        self.write_instr(Opcode311::PUSH_EXC_INFO);
+        self.write_arg(0);
+        self.write_instr(Opcode309::WITH_EXCEPT_START);
+        self.write_arg(0);
+        self.stack_inc_n(2);
+        self.emit_to_bool();
+        // skip RERAISE and the cleanup block
+        self.write_instr(Opcode311::POP_JUMP_FORWARD_IF_TRUE);
+        self.write_arg(4);
+        if self.py_version.minor >= Some(13) {
+            self.write_bytes(&[0; 2]);
+        }
+        self.stack_dec();
+        self.write_instr(Opcode311::RERAISE);
+        self.write_arg(2);
+        // cleanup block: [__exit__, lasti, prev_exc, lasti, exc] on the stack
+        let idx_cleanup = self.lasti();
+        self.stack_inc_n(2);
+        self.write_instr(Opcode311::COPY);
+        self.write_arg(3);
+        self.write_instr(Opcode311::POP_EXCEPT);
+        self.write_arg(0);
+        self.write_instr(Opcode311::RERAISE);
+        self.write_arg(1);
+        self.stack_dec_n(2);
+        // the exception is suppressed by __exit__
+        self.emit_pop_top();
+        self.write_instr(Opcode311::POP_EXCEPT);
+        self.write_arg(0);
+        self.stack_dec();
+        self.emit_pop_top();
+        self.emit_pop_top();
+        self.emit_load_const(ValueObj::None);
+        self.emit_store_instr(stash.clone(), Name);
+        let arg = self.lasti() - idx_jump_forward - 4;
+        self.fill_jump(idx_jump_forward + 1, arg);
+        self.register_exception_entries([
+            ExceptionTableEntry::new(
+                idx_body_begin,
+                idx_body_end,
+                idx_handler,
+                init_stack_len + 1,
+                true,
+            ),
+            ExceptionTableEntry::new(
+                idx_handler,
+                idx_cleanup,
+                idx_cleanup,
+                init_stack_len + 3,
+                true,
+            ),
+        ]);
+        self.emit_load_name_instr(stash);
+    }
+
+    fn register_exception_entries(
+        &mut self,
+        entries: impl IntoIterator<Item = ExceptionTableEntry>,
+    ) {
+        self.mut_cur_block().exception_entries.extend(entries);
+        let table = ExceptionTableEntry::table_into_bytes(&self.cur_block().exception_entries);
+        self.mut_cur_block_codeobj().exceptiontable = table;
+    }
+
     fn emit_with_instr_310(&mut self, mut args: Args) {
         log!(info "entered {}", fn_name!());
         if !matches!(args.get(1).unwrap(), Expr::Lambda(_)) {
@@ -2768,6 +3165,7 @@ impl PyCodeGenerator {
                 other => {
                     self.emit_push_null();
                     self.emit_expr(other);
+                    self.emit_push_null_after();
                     self.emit_args_311(call.args, Name);
                 }
             }
@@ -2787,6 +3185,7 @@ impl PyCodeGenerator {
             "if" | "if!" => self.emit_if_instr(args),
             "match" | "match!" => self.emit_match_instr(args, true),
             "with!" => match self.py_version.minor {
+                Some(12 | 13) => self.emit_with_instr_312(args),
                 Some(11) => self.emit_with_instr_311(args),
                 Some(10) => self.emit_with_instr_310(args),
                 Some(9) => self.emit_with_instr_309(args),
@@ -2855,6 +3254,7 @@ impl PyCodeGenerator {
             _ => {
                 self.emit_push_null();
                 self.emit_load_name_instr(local);
+                self.emit_push_null_after();
                 self.emit_args_311(args, Name);
             }
         }
@@ -2901,8 +3301,12 @@ impl PyCodeGenerator {
         if pos_len > 0 {
             self.write_instr(Opcode310::LIST_EXTEND);
             self.write_arg(1);
-            self.write_instr(Opcode310::LIST_TO_TUPLE);
-            self.write_arg(0);
+            if self.py_version.minor >= Some(12) {
+                self.emit_call_intrinsic_1(Intrinsic1::LIST_TO_TUPLE);
+            } else {
+                self.write_instr(Opcode310::LIST_TO_TUPLE);
+                self.write_arg(0);
+            }
         }
         self.stack_dec();
     }
@@ -3006,7 +3410,9 @@ impl PyCodeGenerator {
         }
         self.write_instr(Opcode311::BINARY_SUBSCR);
         self.write_arg(0);
-        if self.py_version.minor >= Some(11) {
+        if self.py_version.minor >= Some(12) {
+            self.write_bytes(&[0; 2]);
+        } else if self.py_version.minor >= Some(11) {
             self.write_bytes(&[0; 8]);
         }
         // (1 (subroutine) + argc) input objects -> 1 return object
@@ -3032,7 +3438,11 @@ impl PyCodeGenerator {
         } else {
             self.emit_expr(args.remove(0));
         }
-        self.write_instr(YIELD_VALUE);
+        if self.py_version.minor >= Some(12) {
+            self.write_instr(Opcode312::YIELD_VALUE);
+        } else {
+            self.write_instr(YIELD_VALUE);
+        }
         self.write_arg(0);
     }
 
@@ -3049,6 +3459,7 @@ impl PyCodeGenerator {
         method_name.vi.py_name = Some(func_name);
         self.emit_push_null();
         self.emit_load_name_instr(method_name);
+        self.emit_push_null_after();
         args.insert_pos(0, PosArg::new(obj));
         self.emit_args_311(args, Name);
     }
@@ -3058,11 +3469,15 @@ impl PyCodeGenerator {
         log!(info "entered {}", fn_name!());
         let init_stack_len = self.stack_len();
         self.emit_expr(args.remove(0));
+        self.emit_to_bool();
         let pop_jump_point = self.lasti();
         self.write_instr(EXTENDED_ARG);
         self.write_arg(0);
         self.write_instr(Opcode310::POP_JUMP_IF_TRUE);
         self.write_arg(0);
+        if self.py_version.minor >= Some(13) {
+            self.write_bytes(&[0; 2]);
+        }
         self.stack_dec();
         if self.py_version.minor >= Some(10) {
             self.write_instr(Opcode310::LOAD_ASSERTION_ERROR);
@@ -3085,7 +3500,7 @@ impl PyCodeGenerator {
         self.write_arg(1);
         self.stack_dec();
         let idx = match self.py_version.minor {
-            Some(11) => self.lasti() - pop_jump_point - 4,
+            Some(11..) => self.lasti() - self.cur_jump_base(pop_jump_point),
             Some(10) => self.lasti(),
             Some(_) => self.lasti(),
             _ => todo!(),
@@ -3105,6 +3520,7 @@ impl PyCodeGenerator {
             } else {
                 self.emit_load_name_instr(Identifier::static_public("List"));
             }
+            self.emit_push_null_after();
         }
         match list {
             List::Normal(mut lis) => {
@@ -3200,6 +3616,7 @@ impl PyCodeGenerator {
         if !self.cfg.no_std {
             self.emit_push_null();
             self.emit_load_name_instr(Identifier::static_public("Dict"));
+            self.emit_push_null_after();
         }
         match dict {
             crate::hir::Dict::Normal(dic) => {
@@ -3234,6 +3651,7 @@ impl PyCodeGenerator {
         // making record type
         let ident = Identifier::private("#NamedTuple");
         self.emit_load_name_instr(ident);
+        self.emit_push_null_after();
         // record name, let it be anonymous
         self.emit_load_const("Record");
         for field in rec.attrs.iter() {
@@ -3255,6 +3673,7 @@ impl PyCodeGenerator {
         let ident = Identifier::private("#rec");
         self.emit_push_null();
         self.emit_load_name_instr(ident);
+        self.emit_push_null_after();
         for field in rec.attrs.into_iter() {
             self.emit_simple_block(field.body.block);
         }
@@ -3418,11 +3837,13 @@ impl PyCodeGenerator {
                 v @ (Bool | Nat | Int | Float | Str) => {
                     self.emit_push_null();
                     self.emit_load_name_instr(Identifier::public(&v.qual_name()));
+                    self.emit_push_null_after();
                 }
                 other => match &other.qual_name()[..] {
                     t @ ("Bytes" | "List" | "Dict" | "Set") => {
                         self.emit_push_null();
                         self.emit_load_name_instr(Identifier::public(t));
+                        self.emit_push_null_after();
                     }
                     _ => {
                         wrapped = false;
@@ -3586,15 +4007,6 @@ impl PyCodeGenerator {
         }
         // end of flagging
         let unit = self.units.pop().unwrap();
-        if !self.units.is_empty() {
-            let ld = unit.prev_lineno - self.cur_block().prev_lineno;
-            if ld != 0 {
-                if let Some(l) = self.mut_cur_block_codeobj().lnotab.last_mut() {
-                    *l += u8::try_from(ld).unwrap();
-                }
-                self.mut_cur_block().prev_lineno += ld;
-            }
-        }
         unit.codeobj
     }
 
@@ -3857,9 +4269,11 @@ impl PyCodeGenerator {
             }
         } else if self.py_version.minor >= Some(11) {
             // cancel copying
+            let copy_free_vars = self.convert_opcode(Opcode311::COPY_FREE_VARS as u8);
             let code = self.cur_block_codeobj().code.get(idx_copy_free_vars);
-            debug_assert_eq!(code, Some(&(Opcode311::COPY_FREE_VARS as u8)));
-            self.edit_code(idx_copy_free_vars, CommonOpcode::NOP as usize);
+            debug_assert_eq!(code, Some(&copy_free_vars));
+            let nop = self.convert_opcode(CommonOpcode::NOP as u8);
+            self.edit_code(idx_copy_free_vars, nop as usize);
         }
         for (cell, placeholder) in cells {
             let name = escape_ident(cell);
@@ -3875,18 +4289,6 @@ impl PyCodeGenerator {
         }
         // end of flagging
         let unit = self.units.pop().unwrap();
-        // increase lineno
-        if !self.units.is_empty() {
-            let ld = unit
-                .prev_lineno
-                .saturating_sub(self.cur_block().prev_lineno);
-            if ld != 0 {
-                if let Some(l) = self.mut_cur_block_codeobj().lnotab.last_mut() {
-                    *l += u8::try_from(ld).unwrap();
-                }
-                self.mut_cur_block().prev_lineno += ld;
-            }
-        }
         unit.codeobj
     }
 
@@ -4099,15 +4501,6 @@ impl PyCodeGenerator {
         }
         // end of flagging
         let unit = self.units.pop().unwrap();
-        if !self.units.is_empty() {
-            let ld = unit.prev_lineno - self.cur_block().prev_lineno;
-            if ld != 0 {
-                if let Some(l) = self.mut_cur_block_codeobj().lnotab.last_mut() {
-                    *l += u8::try_from(ld).unwrap();
-                }
-                self.mut_cur_block().prev_lineno += ld;
-            }
-        }
         log!(info "the code-generating process has completed.{RESET}");
         unit.codeobj
     }
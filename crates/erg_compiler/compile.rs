@@ -18,10 +18,11 @@ use crate::codegen::PyCodeGenerator;
 use crate::context::{Context, ContextProvider};
 use crate::desugar_hir::HIRDesugarer;
 use crate::error::{CompileError, CompileErrors, CompileWarnings};
-use crate::hir::Expr;
+use crate::hir::{Expr, HIR};
 use crate::link_hir::HIRLinker;
 use crate::module::SharedCompilerResource;
 use crate::optimize::HIROptimizer;
+use crate::peephole::PeepholeOptimizer;
 use crate::ty::codeobj::CodeObj;
 use crate::varinfo::VarInfo;
 
@@ -280,7 +281,7 @@ impl Compiler {
     ) -> Result<CompleteArtifact<CodeObj>, ErrorArtifact> {
         log!(info "the compiling process has started.");
         let arti = self.build_link_desugar_optimize(src, mode)?;
-        let codeobj = self.code_generator.emit(arti.object);
+        let codeobj = self.emit_optimize(arti.object);
         log!(info "code object:\n{}", codeobj.code_info(Some(self.code_generator.py_version)));
         log!(info "the compiling process has completed");
         Ok(CompleteArtifact::new(codeobj, arti.warns))
@@ -293,7 +294,7 @@ impl Compiler {
     ) -> Result<CompleteArtifact<CodeObj>, ErrorArtifact> {
         log!(info "the compiling process has started.");
         let arti = self.build_link_desugar_optimize_ast(ast, mode)?;
-        let codeobj = self.code_generator.emit(arti.object);
+        let codeobj = self.emit_optimize(arti.object);
         log!(info "code object:\n{}", codeobj.code_info(Some(self.code_generator.py_version)));
         log!(info "the compiling process has completed");
         Ok(CompleteArtifact::new(codeobj, arti.warns))
@@ -312,7 +313,7 @@ impl Compiler {
         log!(info "the compiling process has started.");
         let arti = self.build_link_desugar_optimize(src, mode)?;
         let last = arti.object.module.last().cloned();
-        let codeobj = self.code_generator.emit(arti.object);
+        let codeobj = self.emit_optimize(arti.object);
         log!(info "code object:\n{}", codeobj.code_info(Some(self.code_generator.py_version)));
         log!(info "the compiling process has completed");
         Ok(CompleteArtifact::new((codeobj, last), arti.warns))
@@ -327,7 +328,12 @@ impl Compiler {
         let linker = HIRLinker::new(&self.cfg, &self.shared.mod_cache);
         let hir = linker.link(artifact.object);
         let hir = HIRDesugarer::desugar(hir);
-        let hir = HIROptimizer::optimize(self.cfg.clone(), self.shared.clone(), hir);
+        let hir = HIROptimizer::optimize(
+            self.cfg.clone(),
+            self.shared.clone(),
+            self.builder.get_context(),
+            hir,
+        );
         Ok(CompleteArtifact::new(hir, artifact.warns))
     }
 
@@ -340,10 +346,20 @@ impl Compiler {
         let linker = HIRLinker::new(&self.cfg, &self.shared.mod_cache);
         let hir = linker.link(artifact.object);
         let hir = HIRDesugarer::desugar(hir);
-        let hir = HIROptimizer::optimize(self.cfg.clone(), self.shared.clone(), hir);
+        let hir = HIROptimizer::optimize(
+            self.cfg.clone(),
+            self.shared.clone(),
+            self.builder.get_context(),
+            hir,
+        );
         Ok(CompleteArtifact::new(hir, artifact.warns))
     }
 
+    fn emit_optimize(&mut self, hir: HIR) -> CodeObj {
+        let codeobj = self.code_generator.emit(hir);
+        PeepholeOptimizer::optimize(&self.cfg, self.code_generator.py_version, codeobj)
+    }
+
     pub fn initialize_generator(&mut self) {
         self.code_generator.initialize();
     }
@@ -523,7 +523,7 @@ impl<'c> Substituter<'c> {
 }
 
 impl Context {
-    fn try_get_op_kind_from_token(&self, token: &Token) -> EvalResult<OpKind> {
+    pub(crate) fn try_get_op_kind_from_token(&self, token: &Token) -> EvalResult<OpKind> {
         match token.kind {
             TokenKind::Plus => Ok(OpKind::Add),
             TokenKind::Minus => Ok(OpKind::Sub),
@@ -1206,6 +1206,17 @@ impl Context {
                     elems.push(elem);
                 }
             }
+            Tuple::Comprehension(_) => {
+                return Err((
+                    ValueObj::Failure,
+                    EvalErrors::from(EvalError::not_const_expr(
+                        self.cfg.input.clone(),
+                        line!() as usize,
+                        tuple.loc(),
+                        self.caused_by(),
+                    )),
+                ));
+            }
         }
         let tuple = ValueObj::Tuple(ArcArray::from(elems));
         if errs.is_empty() {
@@ -1544,7 +1555,12 @@ impl Context {
         self.eval_const_chunk(block.last().unwrap())
     }
 
-    fn eval_bin(&self, op: OpKind, lhs: ValueObj, rhs: ValueObj) -> EvalResult<ValueObj> {
+    pub(crate) fn eval_bin(
+        &self,
+        op: OpKind,
+        lhs: ValueObj,
+        rhs: ValueObj,
+    ) -> EvalResult<ValueObj> {
         match op {
             Add => lhs.try_add(rhs).ok_or_else(|| {
                 EvalErrors::from(EvalError::unreachable(
@@ -1867,7 +1883,7 @@ impl Context {
         }
     }
 
-    fn eval_unary_val(&self, op: OpKind, val: ValueObj) -> EvalResult<ValueObj> {
+    pub(crate) fn eval_unary_val(&self, op: OpKind, val: ValueObj) -> EvalResult<ValueObj> {
         match op {
             Pos => match val {
                 ValueObj::Nat(_)
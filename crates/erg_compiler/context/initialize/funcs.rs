@@ -422,6 +422,12 @@ impl Context {
             t_sum.clone(),
             None,
         )));
+        let t_tuple = no_var_func(
+            vec![],
+            vec![kw(KW_ITERABLE, poly(ITERABLE, vec![ty_tp(T.clone())]))],
+            homo_tuple_t(T.clone()),
+        )
+        .quantify();
         let t_unreachable = d_func(vec![kw(KW_MSG, Obj)], Never);
         let t_vars = no_var_func(
             vec![],
@@ -746,6 +752,7 @@ impl Context {
             Some(FUNC_SUM),
             None,
         );
+        self.register_py_builtin(FUNC_TUPLE, t_tuple, Some(FUNC_TUPLE), 242);
         self.register_builtin_py_impl(FUNC_VARS, t_vars, Immutable, vis.clone(), Some(FUNC_VARS));
         self.register_py_builtin_const(
             FUNC_ZIP,
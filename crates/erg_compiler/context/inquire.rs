@@ -20,6 +20,7 @@ use erg_parser::token::Token;
 
 use crate::ty::constructors::{anon, fn_met, free_var, func, mono, poly, proc, proj, ref_, subr_t};
 use crate::ty::free::{Constraint, FreeTyParam, FreeTyVar};
+use crate::ty::predicate::IntRanges;
 use crate::ty::typaram::TyParam;
 use crate::ty::value::{GenTypeObj, TypeObj, ValueObj};
 use crate::ty::{
@@ -547,6 +548,28 @@ impl Context {
             if DEBUG_MODE {
                 eprintln!("match error: {err}");
             }
+            // Exhaustiveness of refinement patterns (e.g. `{I: Int | I >= 0}`) cannot be decided in general,
+            // so only warn unless they are shown to cover the target
+            // (if the target is out of the base types of the patterns, it is an error)
+            let base_union = arm_ts.iter().fold(Type::Never, |acc, t| {
+                self.union(&acc, &self.refinement_base(t))
+            });
+            if arm_ts.iter().any(|t| self.is_predicate_refinement(t))
+                && self.subtype_of(match_target_expr_t, &base_union)
+            {
+                if !self.covers_int_values(match_target_expr_t, &arm_ts) {
+                    let warn = TyCheckError::non_exhaustive_match_warning(
+                        self.cfg.input.clone(),
+                        line!() as usize,
+                        pos_args[0].loc(),
+                        self.caused_by(),
+                        match_target_expr_t,
+                        arm_ts,
+                    );
+                    self.shared().warns.push(warn);
+                }
+                return self.get_match_branches_t(kind, match_target_expr_t, pos_args, errs);
+            }
             errs.push(TyCheckError::match_error(
                 self.cfg.input.clone(),
                 line!() as usize,
@@ -558,6 +581,16 @@ impl Context {
             ));
             return Err((None, errs));
         }
+        self.get_match_branches_t(kind, match_target_expr_t, pos_args, errs)
+    }
+
+    fn get_match_branches_t(
+        &self,
+        kind: SubrKind,
+        match_target_expr_t: &Type,
+        pos_args: &[hir::PosArg],
+        mut errs: TyCheckErrors,
+    ) -> FailableOption<VarInfo> {
         let branch_ts = pos_args
             .iter()
             .skip(1)
@@ -598,6 +631,52 @@ impl Context {
         }
     }
 
+    /// e.g. `{I: Int | I >= 0}` (`{0, 1}` is not)
+    fn is_predicate_refinement(&self, t: &Type) -> bool {
+        match t {
+            Type::FreeVar(fv) if fv.is_linked() => self.is_predicate_refinement(&fv.crack()),
+            Type::Refinement(refine) => !refine.pred.consist_of_equal(),
+            _ => false,
+        }
+    }
+
+    /// `{I: Int | I >= 0}` => `Int`
+    fn refinement_base(&self, t: &Type) -> Type {
+        match t {
+            Type::FreeVar(fv) if fv.is_linked() => self.refinement_base(&fv.crack()),
+            Type::Refinement(refine) if !refine.pred.consist_of_equal() => {
+                self.refinement_base(&refine.t)
+            }
+            _ => t.clone(),
+        }
+    }
+
+    /// Returns the set of integers that `t` contains.
+    fn int_ranges(&self, t: &Type) -> Option<IntRanges> {
+        if self.subtype_of(&Type::Int, t) {
+            return Some(IntRanges::full());
+        }
+        let refine = t.clone().into_refinement();
+        if refine.t.as_ref() == t || !self.subtype_of(&refine.t, &Type::Int) {
+            return None;
+        }
+        Some(
+            self.int_ranges(&refine.t)?
+                .intersection(&refine.pred.int_ranges()?),
+        )
+    }
+
+    /// Check if the integer values of `target` are covered by `arm_ts`.
+    fn covers_int_values(&self, target: &Type, arm_ts: &[Type]) -> bool {
+        let Some(target) = self.int_ranges(target) else {
+            return false;
+        };
+        let covered = arm_ts.iter().try_fold(IntRanges::empty(), |acc, t| {
+            Some(acc.union(&self.int_ranges(t)?))
+        });
+        covered.is_some_and(|covered| target.is_subset_of(&covered))
+    }
+
     pub(crate) fn rec_get_var_info(
         &self,
         ident: &Identifier,
@@ -31,7 +31,7 @@ use erg_common::impl_display_from_debug;
 use erg_common::pathutil::NormalizedPathBuf;
 use erg_common::traits::{Locational, Stream};
 use erg_common::Str;
-use erg_common::{fmt_option, fn_name, get_hash, log};
+use erg_common::{fmt_option, fmt_vec, fn_name, get_hash, log};
 
 use ast::{DefId, DefKind, VarName};
 use erg_parser::ast;
@@ -760,6 +760,26 @@ impl Context {
         ctx.get_var_info(&typ.local_name())
     }
 
+    /// If `self` is a patch, the patched classes are returned.
+    pub fn super_classes(&self) -> impl Iterator<Item = &Type> {
+        self.super_classes.iter()
+    }
+
+    /// If `self` is not a trait, the implemented traits are returned.
+    pub fn super_traits(&self) -> impl Iterator<Item = &Type> {
+        self.super_traits.iter()
+    }
+
+    /// Type contexts defined in this scope (not recursive)
+    pub fn local_type_ctxs(&self) -> impl Iterator<Item = &TypeContext> {
+        self.mono_types.values().chain(self.poly_types.values())
+    }
+
+    /// Patches defined in this scope (not recursive)
+    pub fn local_patches(&self) -> impl Iterator<Item = (&VarName, &Context)> {
+        self.patches.iter()
+    }
+
     pub fn unregister(&mut self, name: &str) -> Option<VarInfo> {
         self.mono_types.remove(name);
         self.poly_types.remove(name);
@@ -1257,6 +1277,66 @@ impl Context {
             && self.locals.len() <= default_locals
     }
 
+    /// Collects the signatures of the public variables (including the members of the types defined here).
+    /// The `DefId`s are not included because they change every time the module is checked.
+    fn collect_public_signatures(&self, namespace: &str, sigs: &mut Vec<String>) {
+        for (name, vi) in self.locals.iter().chain(self.decls.iter()) {
+            if !vi.vis.is_public() {
+                continue;
+            }
+            let sig = format!(
+                "{namespace}{name}: {} ({:?}, {:?}, {:?}) @ {}",
+                vi.t, vi.muty, vi.vis.modifier, vi.py_name, vi.def_loc.loc
+            );
+            sigs.push(Self::renumber_free_vars(&sig));
+            if let Some(value) = self.consts.get(name) {
+                sigs.push(format!("{namespace}{name} = {value}"));
+            }
+        }
+        for (name, ty_ctx) in self.mono_types.iter().chain(self.poly_types.iter()) {
+            let namespace = format!("{namespace}{name}.");
+            sigs.push(format!(
+                "{namespace} <: {} {}",
+                fmt_vec(&ty_ctx.super_classes),
+                fmt_vec(&ty_ctx.super_traits)
+            ));
+            ty_ctx.collect_public_signatures(&namespace, sigs);
+            for methods in ty_ctx.methods_list.iter() {
+                methods.collect_public_signatures(&namespace, sigs);
+            }
+        }
+        for (name, patch) in self.patches.iter() {
+            patch.collect_public_signatures(&format!("{namespace}{name}."), sigs);
+        }
+    }
+
+    /// The unnamed free type variables (`%123`) are numbered in order of appearance,
+    /// since their ids depend on how many variables have been generated before.
+    /// e.g. `|%127 <: C|(self: %127) -> %127` -> `|%0 <: C|(self: %0) -> %0`
+    fn renumber_free_vars(sig: &str) -> String {
+        let mut ids = vec![];
+        let mut renumbered = String::with_capacity(sig.len());
+        let mut rest = sig;
+        while let Some(idx) = rest.find('%') {
+            renumbered += &rest[..=idx];
+            rest = &rest[idx + 1..];
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            if end == 0 {
+                continue;
+            }
+            let id = &rest[..end];
+            let n = ids.iter().position(|i| i == &id).unwrap_or_else(|| {
+                ids.push(id);
+                ids.len() - 1
+            });
+            renumbered += &n.to_string();
+            rest = &rest[end..];
+        }
+        renumbered + rest
+    }
+
     pub(crate) fn path(&self) -> Str {
         // NOTE: maybe this need to be changed if we want to support nested classes/traits
         if self.kind == ContextKind::Module {
@@ -1556,6 +1636,17 @@ impl Context {
         }
     }
 
+    /// The name and the registered return type of the subroutine that `return` exits from.
+    pub(crate) fn current_return_t(&self) -> Option<(Str, Type)> {
+        let ctx = self.current_true_function_ctx()?;
+        // e.g. `<module>::f` => `f`, `<module>::C.f` => `f`
+        let name = ctx.name.rsplit([':', '.']).next()?;
+        let name = VarName::from_str(Str::rc(name));
+        let vi = ctx.get_outer_scope()?.get_current_scope_non_param(&name)?;
+        let return_t = vi.t.return_t()?.clone();
+        Some((name.inspect().clone(), return_t))
+    }
+
     pub(crate) fn check_types(&self) {
         if DEBUG_MODE {
             for (_, ctx) in self.poly_types.iter() {
@@ -1625,4 +1716,15 @@ impl ModuleContext {
     pub fn is_empty(&self) -> bool {
         self.context.is_empty()
     }
+
+    /// The hash of the public interface of the module,
+    /// i.e. the signatures and the definition locations of the public variables, types and methods.
+    /// The locations are included because the dependents keep them (e.g. for go-to-definition).
+    /// The modules which depend on this module do not need to be rechecked unless this changes.
+    pub fn interface_hash(&self) -> usize {
+        let mut sigs = vec![];
+        self.context.collect_public_signatures("", &mut sigs);
+        sigs.sort();
+        get_hash(&sigs)
+    }
 }
@@ -348,6 +348,12 @@ impl<A: ASTBuildable> GenericASTLowerer<A> {
                 let elems = hir::Args::pos_only(elems, paren);
                 Ok(hir::Tuple::Normal(hir::NormalTuple::new(elems)))
             }
+            other => Err(LowerErrors::from(LowerError::declare_error(
+                self.cfg().input.clone(),
+                line!() as usize,
+                other.loc(),
+                self.module.context.caused_by(),
+            ))),
         }
     }
 
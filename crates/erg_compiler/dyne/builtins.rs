@@ -0,0 +1,2483 @@
+//! Builtin functions, types and methods of Dyne.
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use erg_common::error::{ErrorKind, Location};
+use erg_common::{switch_lang, Str};
+
+use erg_parser::token::{Token, TokenKind};
+
+use crate::ty::constructors::mono;
+use crate::ty::value::{TypeObj, ValueObj};
+use crate::ty::Type;
+
+use super::value::{Builtin, Class, Instance, Value};
+use super::{EvalResult, Interpreter};
+
+pub type BuiltinFn =
+    fn(&mut Interpreter, Vec<Value>, Vec<(Str, Value)>, Location) -> EvalResult<Value>;
+
+fn builtin(name: &str, func: BuiltinFn) -> Builtin {
+    Builtin {
+        name: Str::rc(name),
+        func,
+    }
+}
+
+/// Builtin functions and types that can be referred to by name.
+pub fn builtin_value(name: &str) -> Option<Value> {
+    let t = match name {
+        "Obj" => Type::Obj,
+        "Int" => Type::Int,
+        "Nat" => Type::Nat,
+        "Float" => Type::Float,
+        "Str" => Type::Str,
+        "Bool" => Type::Bool,
+        "NoneType" => Type::NoneType,
+        "Type" => Type::Type,
+        "Never" => Type::Never,
+        "List" | "List!" | "Tuple" | "Set" | "Set!" | "Dict" | "Dict!" | "Record" | "Range" => {
+            mono(name.trim_end_matches('!').to_string())
+        }
+        // comprehensions are desugared into `list(map(...))`, `dict(map(...))`, etc.
+        "list" => mono("List"),
+        "tuple" => mono("Tuple"),
+        "set" => mono("Set"),
+        "dict" => mono("Dict"),
+        _ => {
+            if let Some(func) = dunder_fn(name) {
+                return Some(Value::Builtin(builtin(name, func)));
+            }
+            let func: BuiltinFn = match name {
+                "print!" | "log" => print,
+                "str" => str,
+                "repr" => repr,
+                "len" => len,
+                "abs" => abs,
+                "int" => int,
+                "nat" => nat,
+                "float" => float,
+                "bool" => bool,
+                "not" => not,
+                "isinstance" => isinstance,
+                "hasattr" => hasattr,
+                "contains" => contains_func,
+                "min" => min,
+                "max" => max,
+                "sum" => sum,
+                "all" => all,
+                "any" => any,
+                "sorted" => sorted,
+                "reversed" => reversed,
+                "range" => range,
+                "enumerate" => enumerate,
+                "zip" => zip,
+                "map" => map,
+                "filter" => filter,
+                "round" => round,
+                "chr" => chr,
+                "ord" => ord,
+                "bin" => bin,
+                "iter" => iter_func,
+                "if" | "if!" => if_func,
+                "for" | "for!" => for_func,
+                "while" | "while!" => while_func,
+                "discard" => discard,
+                "exit" | "todo" | "unreachable" => exit,
+                "panic" => panic,
+                _ => return None,
+            };
+            return Some(Value::Builtin(builtin(name, func)));
+        }
+    };
+    Some(Value::builtin_type(t))
+}
+
+/// Methods of builtin values. The receiver is passed as the first argument.
+pub fn method(recv: &Value, name: &str) -> Option<Builtin> {
+    // `x.__add__(y)` is `__add__(x, y)`
+    if let Some(func) = dunder_fn(name) {
+        return Some(builtin(name, func));
+    }
+    let func: BuiltinFn = match (recv, name) {
+        (Value::Mut(_), "inc!") => mut_inc,
+        (Value::Mut(_), "dec!") => mut_dec,
+        (Value::Mut(_), "update!") => mut_update,
+        (Value::Mut(value), "push!") if value.borrow().as_str().is_some() => mut_str_push,
+        (Value::Mut(value), "invert!")
+            if value
+                .borrow()
+                .as_obj()
+                .is_some_and(|obj| matches!(obj, ValueObj::Bool(_))) =>
+        {
+            mut_invert
+        }
+        (Value::Mut(value), _) => return method(&value.borrow().clone(), name),
+        (Value::List(_), "push!" | "append!") => list_push,
+        (Value::List(_), "pop!") => list_pop,
+        (Value::List(_), "extend!") => list_extend,
+        (Value::List(_), "insert!") => list_insert,
+        (Value::List(_), "remove!") => list_remove,
+        (Value::List(_), "clear!") => list_clear,
+        (Value::List(_), "reverse!") => list_reverse,
+        (Value::List(_), "sort!") => list_sort,
+        (Value::List(_), "update!") => list_update,
+        (Value::List(_), "__setitem__" | "set!") => list_setitem,
+        (Value::List(_), "update_nth!") => list_update_nth,
+        (Value::List(_), "concat") => list_concat,
+        (Value::List(_), "from") => list_from,
+        (Value::List(_), "repeat") => list_repeat,
+        (Value::List(_), "dedup") => list_dedup,
+        (Value::List(_), "partition") => list_partition,
+        (Value::List(_) | Value::Tuple(_) | Value::Range(..), "__getitem__") => seq_getitem,
+        (Value::Tuple(_), "__Tuple_getitem__") => seq_getitem,
+        (Value::List(_) | Value::Tuple(_), "index") => seq_index,
+        (Value::List(_) | Value::Tuple(_), "count") => seq_count,
+        (Value::List(_) | Value::Tuple(_) | Value::Set(_) | Value::Range(..), "map") => iter_map,
+        (Value::List(_) | Value::Tuple(_) | Value::Set(_) | Value::Range(..), "filter") => {
+            iter_filter
+        }
+        (Value::List(_) | Value::Tuple(_) | Value::Set(_) | Value::Range(..), "to_list") => {
+            iter_to_list
+        }
+        (Value::List(_) | Value::Set(_), "copy") => collection_copy,
+        (Value::Set(_), "add!" | "insert!") => set_add,
+        (Value::Set(_), "remove!" | "discard!") => set_remove,
+        (Value::Dict(_), "__getitem__") => dict_getitem,
+        (Value::Dict(_), "__setitem__" | "insert!") => dict_insert,
+        (Value::Dict(_), "remove!" | "pop!") => dict_remove,
+        (Value::Dict(_), "get") => dict_get,
+        (Value::Dict(_), "keys") => dict_keys,
+        (Value::Dict(_), "values") => dict_values,
+        (Value::Dict(_), "items") => dict_items,
+        (Value::Dict(_), "copy") => collection_copy,
+        (Value::Dict(_), "concat") => dict_concat,
+        (Value::Dict(_), "diff") => dict_diff,
+        (Value::Dict(_), "merge!" | "update!") => dict_merge,
+        (Value::Dict(_), "as_record") => dict_as_record,
+        (Value::Obj(ValueObj::Str(_)), _) => match name {
+            "__getitem__" => str_getitem,
+            "upper" => str_upper,
+            "lower" => str_lower,
+            "capitalize" => str_capitalize,
+            "strip" => str_strip,
+            "split" => str_split,
+            "splitlines" => str_splitlines,
+            "join" => str_join,
+            "replace" => str_replace,
+            "startswith" => str_startswith,
+            "endswith" => str_endswith,
+            "find" => str_find,
+            "count" => str_count,
+            "isdigit" | "isnumeric" | "isdecimal" => str_isdigit,
+            "isalpha" => str_isalpha,
+            "to_int" => str_to_int,
+            "format" => str_format,
+            _ => return None,
+        },
+        (Value::Obj(ValueObj::Int(_) | ValueObj::Nat(_) | ValueObj::Bool(_)), _) => match name {
+            "times!" => int_times,
+            "succ" => int_succ,
+            "pred" => int_pred,
+            "abs" => abs,
+            "bit_count" => int_bit_count,
+            "bit_length" => int_bit_length,
+            _ => return None,
+        },
+        (Value::Obj(ValueObj::Float(_)), "abs") => abs,
+        (Value::Obj(ValueObj::Float(_)), "is_integer") => float_is_integer,
+        _ => return None,
+    };
+    Some(builtin(name, func))
+}
+
+/// The `new` method of user-defined classes.
+pub fn constructor() -> Value {
+    fn new(
+        interp: &mut Interpreter,
+        args: Vec<Value>,
+        _kw_args: Vec<(Str, Value)>,
+        loc: Location,
+    ) -> EvalResult<Value> {
+        let mut args = args.into_iter();
+        let Some(Value::Class(class)) = args.next() else {
+            return Err(interp.type_error("new must be called with a class", loc));
+        };
+        construct(interp, class, args.collect(), loc)
+    }
+    Value::Builtin(builtin("new", new))
+}
+
+/// `C.new {x = 1; y = 2}`
+pub fn construct(
+    interp: &mut Interpreter,
+    class: Rc<Class>,
+    args: Vec<Value>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let fields = match <[Value; 1]>::try_from(args) {
+        Ok([Value::Record(fields)]) => fields.to_vec(),
+        // `Class Str`, `Class 1..12`, etc. (referred to as `self::base`)
+        Ok([base]) => vec![(Str::ever("base"), base)],
+        Err(args) if args.is_empty() => vec![],
+        Err(args) => {
+            return Err(interp.type_error(
+                format!(
+                    "{}.new takes at most 1 argument, but got {}",
+                    class.name,
+                    args.len()
+                ),
+                loc,
+            ))
+        }
+    };
+    Ok(Value::Instance(Rc::new(Instance {
+        class,
+        fields: fields.into(),
+    })))
+}
+
+/// Attributes of builtin types (e.g. `Float.EPSILON`)
+pub fn type_attr(t: &Value, name: &str) -> Option<Value> {
+    let Value::Obj(ValueObj::Type(TypeObj::Builtin { t: ty, .. })) = t else {
+        return None;
+    };
+    match (ty, name) {
+        (_, "__call__") => Some(t.clone()),
+        (Type::Float, "EPSILON") => Some(Value::from(f64::EPSILON)),
+        _ => None,
+    }
+}
+
+/// The name of the special method that overloads the operator.
+pub fn dunder_name(kind: TokenKind) -> Option<&'static str> {
+    let name = match kind {
+        TokenKind::Plus => "__add__",
+        TokenKind::Minus => "__sub__",
+        TokenKind::Star => "__mul__",
+        TokenKind::Slash => "__truediv__",
+        TokenKind::FloorDiv => "__floordiv__",
+        TokenKind::Mod => "__mod__",
+        TokenKind::Pow => "__pow__",
+        TokenKind::Less => "__lt__",
+        TokenKind::LessEq => "__le__",
+        TokenKind::Gre => "__gt__",
+        TokenKind::GreEq => "__ge__",
+        TokenKind::DblEq => "__eq__",
+        TokenKind::NotEq => "__ne__",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Special methods of builtin values (e.g. `__add__(1, 2)`, `1.__add__(2)`)
+fn dunder_fn(name: &str) -> Option<BuiltinFn> {
+    macro_rules! binop {
+        ($name: literal, $kind: ident, $symbol: literal) => {{
+            fn op(
+                interp: &mut Interpreter,
+                args: Vec<Value>,
+                _kw_args: Vec<(Str, Value)>,
+                loc: Location,
+            ) -> EvalResult<Value> {
+                let [lhs, rhs] = take_args(interp, $name, args, loc)?;
+                let op = Token::from_str(TokenKind::$kind, $symbol);
+                interp.binary_op(&op, lhs, rhs, loc)
+            }
+            op
+        }};
+    }
+    let func: BuiltinFn = match name {
+        "__add__" => binop!("__add__", Plus, "+"),
+        "__sub__" => binop!("__sub__", Minus, "-"),
+        "__mul__" => binop!("__mul__", Star, "*"),
+        "__truediv__" | "__div__" => binop!("__truediv__", Slash, "/"),
+        "__floordiv__" => binop!("__floordiv__", FloorDiv, "//"),
+        "__mod__" => binop!("__mod__", Mod, "%"),
+        "__pow__" => binop!("__pow__", Pow, "**"),
+        "__lt__" => binop!("__lt__", Less, "<"),
+        "__le__" => binop!("__le__", LessEq, "<="),
+        "__gt__" => binop!("__gt__", Gre, ">"),
+        "__ge__" => binop!("__ge__", GreEq, ">="),
+        "__eq__" => binop!("__eq__", DblEq, "=="),
+        "__ne__" => binop!("__ne__", NotEq, "!="),
+        "__len__" => len,
+        "__str__" => str,
+        "__repr__" => repr,
+        _ => return None,
+    };
+    Some(func)
+}
+
+/// Calls of builtin types (e.g. `Int("1")`, `Str(1)`)
+pub fn convert(
+    interp: &mut Interpreter,
+    t: &Value,
+    args: Vec<Value>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let Value::Obj(ValueObj::Type(TypeObj::Builtin { t: ty, .. })) = t else {
+        return Err(interp.type_error("not a builtin type", loc));
+    };
+    // `List(Str)` (type arguments are not checked at runtime)
+    if args.first().is_some_and(|arg| arg.is_type()) {
+        return Ok(t.clone());
+    }
+    match ty {
+        Type::Int => int(interp, args, vec![], loc),
+        Type::Nat => {
+            let int = int(interp, args, vec![], loc)?;
+            if int.as_int().is_some_and(|i| i < 0) {
+                return Err(interp.error(
+                    ErrorKind::ValueError,
+                    format!("{} is not a Nat", int.repr()),
+                    loc,
+                ));
+            }
+            Ok(int)
+        }
+        Type::Float => float(interp, args, vec![], loc),
+        Type::Str => str(interp, args, vec![], loc),
+        Type::Bool => bool(interp, args, vec![], loc),
+        _ => {
+            let name = type_name(ty);
+            if &name[..] == "Dict" {
+                return dict_from(interp, args.first(), loc);
+            }
+            let elems = match args.first() {
+                Some(iterable) => iter(interp, iterable, loc)?,
+                None => vec![],
+            };
+            match &name[..] {
+                "List" => Ok(Value::list(elems)),
+                "Set" => Ok(Value::set(elems)),
+                "Tuple" => Ok(Value::Tuple(elems.into())),
+                _ => Err(interp.type_error(format!("'{name}' cannot be instantiated"), loc)),
+            }
+        }
+    }
+}
+
+/// `Dict(dict)` or `Dict(pairs)` (e.g. `Dict([("a", 1)])`)
+fn dict_from(interp: &Interpreter, arg: Option<&Value>, loc: Location) -> EvalResult<Value> {
+    match arg {
+        Some(Value::Dict(kvs)) => Ok(Value::dict(kvs.borrow().clone())),
+        Some(iterable) => {
+            let mut kvs = vec![];
+            for elem in iter(interp, iterable, loc)? {
+                let Value::Tuple(pair) = &elem else {
+                    return Err(interp.type_error(
+                        format!(
+                            "cannot convert '{}' object to a key-value pair",
+                            elem.class_name()
+                        ),
+                        loc,
+                    ));
+                };
+                let [key, value] = &pair[..] else {
+                    return Err(interp.error(
+                        ErrorKind::ValueError,
+                        format!(
+                            "a key-value pair must have 2 elements, but found {}",
+                            pair.len()
+                        ),
+                        loc,
+                    ));
+                };
+                kvs.push((key.clone(), value.clone()));
+            }
+            Ok(Value::dict(kvs))
+        }
+        None => Ok(Value::dict(vec![])),
+    }
+}
+
+fn type_name(t: &Type) -> Str {
+    match t {
+        Type::Mono(name) | Type::Poly { name, .. } => name.trim_end_matches('!').to_string().into(),
+        other => other.local_name(),
+    }
+}
+
+/// Same as `isinstance(value, t)`, but builtin types follow the Erg semantics
+/// (e.g. negative integers are not `Nat`s).
+pub fn is_instance(value: &Value, t: &Value) -> Option<bool> {
+    let value = value.deref();
+    match t {
+        Value::Class(class) => Some(match value {
+            Value::Instance(instance) => instance.class.is_subclass_of(class),
+            _ => false,
+        }),
+        Value::Union(types) => types
+            .iter()
+            .try_fold(false, |acc, t| Some(acc || is_instance(&value, t)?)),
+        Value::Obj(ValueObj::Type(TypeObj::Builtin { t, .. })) => {
+            let obj = value.as_obj();
+            Some(match t {
+                Type::Obj => true,
+                Type::Never => false,
+                Type::Int => matches!(
+                    obj,
+                    Some(ValueObj::Int(_) | ValueObj::Nat(_) | ValueObj::Bool(_))
+                ),
+                Type::Nat => value.as_int().is_some_and(|i| i >= 0),
+                Type::Float => matches!(obj, Some(ValueObj::Float(_))),
+                Type::Str => matches!(obj, Some(ValueObj::Str(_))),
+                Type::Bool => matches!(obj, Some(ValueObj::Bool(_))),
+                Type::NoneType => matches!(obj, Some(ValueObj::None)),
+                Type::Type => matches!(value, Value::Obj(ValueObj::Type(_)) | Value::Class(_)),
+                _ => match &type_name(t)[..] {
+                    "List" => matches!(value, Value::List(_)),
+                    "Tuple" => matches!(value, Value::Tuple(_)),
+                    "Set" => matches!(value, Value::Set(_)),
+                    "Dict" => matches!(value, Value::Dict(_)),
+                    "Record" => matches!(value, Value::Record(_)),
+                    "Range" => matches!(value, Value::Range(..)),
+                    _ => return None,
+                },
+            })
+        }
+        _ => None,
+    }
+}
+
+pub fn is_same(lhs: &Value, rhs: &Value) -> bool {
+    match (lhs, rhs) {
+        (Value::Obj(_), Value::Obj(_)) => lhs == rhs,
+        (Value::Mut(l), Value::Mut(r)) => Rc::ptr_eq(l, r),
+        (Value::List(l), Value::List(r)) | (Value::Set(l), Value::Set(r)) => Rc::ptr_eq(l, r),
+        (Value::Dict(l), Value::Dict(r)) => Rc::ptr_eq(l, r),
+        (Value::Tuple(l), Value::Tuple(r)) => Rc::ptr_eq(l, r),
+        (Value::Record(l), Value::Record(r)) => Rc::ptr_eq(l, r),
+        _ => {
+            matches!(
+                (lhs, rhs),
+                (Value::Closure(_), Value::Closure(_))
+                    | (Value::Class(_), Value::Class(_))
+                    | (Value::Instance(_), Value::Instance(_))
+                    | (Value::Module(_), Value::Module(_))
+            ) && lhs == rhs
+        }
+    }
+}
+
+/// `elem in container`
+pub fn contains(
+    interp: &mut Interpreter,
+    container: &Value,
+    elem: &Value,
+    loc: Location,
+) -> EvalResult<bool> {
+    if let Some(is_instance) = is_instance(elem, container) {
+        return Ok(is_instance);
+    }
+    if let Some(matches) = matches_type_pattern(interp, container, elem, loc) {
+        return matches;
+    }
+    match container.deref() {
+        Value::Obj(ValueObj::Str(s)) => match elem.as_str() {
+            Some(sub) => Ok(s.contains(&sub[..])),
+            None => Err(interp.type_error("'in <string>' requires a string as left operand", loc)),
+        },
+        Value::Dict(kvs) => Ok(kvs.borrow().iter().any(|(k, _)| k == elem)),
+        Value::Range(start, end, step) => Ok(elem.as_int().is_some_and(|i| {
+            let in_bounds = if step > 0 {
+                start <= i && i < end
+            } else {
+                end < i && i <= start
+            };
+            in_bounds && (i - start) % step == 0
+        })),
+        other => match other.iter_values() {
+            Some(elems) => Ok(elems.contains(elem)),
+            None => Err(interp.type_error(
+                format!(
+                    "argument of type '{}' is not a container",
+                    other.class_name()
+                ),
+                loc,
+            )),
+        },
+    }
+}
+
+/// `[1] in [Int]`, `(1, "a") in (Int, Str)`, `{"a": 1} in {Str: Int}`.
+/// Returns `None` if `pattern` is not a container of types.
+fn matches_type_pattern(
+    interp: &mut Interpreter,
+    pattern: &Value,
+    elem: &Value,
+    loc: Location,
+) -> Option<EvalResult<bool>> {
+    let is_type_like = |t: &Value| t.is_type() || matches!(t, Value::Range(..));
+    let elementwise = |interp: &mut Interpreter, ts: &[Value], elems: &[Value]| {
+        for (t, elem) in ts.iter().zip(elems) {
+            if !contains(interp, t, elem, loc)? {
+                return Ok(false);
+            }
+        }
+        Ok(elems.len() <= ts.len())
+    };
+    match (pattern.deref(), elem.deref()) {
+        (Value::List(ts), Value::List(elems)) => {
+            let ts = ts.borrow().clone();
+            ts.first()
+                .is_none_or(is_type_like)
+                .then(|| elementwise(interp, &ts, &elems.borrow().clone()))
+        }
+        (Value::Tuple(ts), Value::Tuple(elems)) => ts
+            .first()
+            .is_none_or(is_type_like)
+            .then(|| elementwise(interp, &ts, &elems)),
+        (Value::Dict(kts), Value::Dict(kvs)) => {
+            let kts = kts.borrow().clone();
+            if !kts.first().is_none_or(|(k, _)| k.is_type()) {
+                return None;
+            }
+            // patterns with several keys are not checked (same as `contains_operator`)
+            let [(key_t, value_t)] = &kts[..] else {
+                return Some(Ok(true));
+            };
+            let kvs = kvs.borrow().clone();
+            let res = (|| {
+                for (key, value) in kvs.iter() {
+                    if !contains(interp, key_t, key, loc)?
+                        || !contains(interp, value_t, value, loc)?
+                    {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            })();
+            Some(res)
+        }
+        // `{ foo; bar } -> ...` (the fields are checked by `hasattr`)
+        (Value::Record(ts), Value::Record(_) | Value::Instance(_)) => {
+            if !ts.iter().all(|(_, t)| is_type_like(t)) {
+                return None;
+            }
+            let res = (|| {
+                for (field, t) in ts.iter() {
+                    let value = match elem.deref() {
+                        Value::Record(fields) => fields
+                            .iter()
+                            .find(|(name, _)| name == field)
+                            .map(|(_, value)| value.clone()),
+                        Value::Instance(instance) => instance.get_field(field),
+                        _ => None,
+                    };
+                    match value {
+                        Some(value) if contains(interp, t, &value, loc)? => {}
+                        _ => return Ok(false),
+                    }
+                }
+                Ok(true)
+            })();
+            Some(res)
+        }
+        _ => None,
+    }
+}
+
+/// Converts the result of integer arithmetic into a value.
+/// Arbitrary-precision integers are not supported (see the module document of `dyne`).
+pub fn int_value(interp: &Interpreter, i: i128, loc: Location) -> EvalResult<Value> {
+    if let Ok(nat) = u64::try_from(i) {
+        Ok(Value::Obj(ValueObj::Nat(nat)))
+    } else if let Ok(int) = i32::try_from(i) {
+        Ok(Value::Obj(ValueObj::Int(int)))
+    } else {
+        // `i` may be saturated, so it is not shown
+        let (min, max) = (i32::MIN, u64::MAX);
+        let msg = switch_lang!(
+            "japanese" => format!("{min}..={max}の範囲外の整数はDyneでサポートされていません"),
+            "simplified_chinese" => format!("Dyne不支持{min}..={max}范围之外的整数"),
+            "traditional_chinese" => format!("Dyne不支援{min}..={max}範圍之外的整數"),
+            "english" => format!("integers out of the range {min}..={max} are not supported by Dyne"),
+        );
+        Err(interp.error(ErrorKind::FeatureError, msg, loc))
+    }
+}
+
+fn as_i128(value: &Value) -> Option<i128> {
+    match value.as_obj()? {
+        ValueObj::Int(i) => Some(i as i128),
+        ValueObj::Nat(n) => Some(n as i128),
+        ValueObj::Bool(b) => Some(b as i128),
+        _ => None,
+    }
+}
+
+fn zero_division(interp: &Interpreter, loc: Location) -> super::EvalError {
+    interp.error(ErrorKind::ZeroDivisionError, "division by zero", loc)
+}
+
+/// Arithmetic and comparison operators
+pub fn binary(
+    interp: &mut Interpreter,
+    op: &Token,
+    lhs: Value,
+    rhs: Value,
+    loc: Location,
+) -> EvalResult<Value> {
+    let (lhs, rhs) = (lhs.deref(), rhs.deref());
+    if let (Some(l), Some(r)) = (as_i128(&lhs), as_i128(&rhs)) {
+        let res = match op.kind {
+            TokenKind::Plus => l + r,
+            TokenKind::Minus => l - r,
+            TokenKind::Star => l.checked_mul(r).unwrap_or(i128::MAX),
+            TokenKind::Slash if r == 0 => return Err(zero_division(interp, loc)),
+            TokenKind::Slash => return Ok(Value::from(l as f64 / r as f64)),
+            TokenKind::FloorDiv | TokenKind::Mod if r == 0 => {
+                return Err(zero_division(interp, loc))
+            }
+            TokenKind::FloorDiv => l.div_euclid(r) - (r < 0 && l.rem_euclid(r) != 0) as i128,
+            TokenKind::Mod => {
+                let m = l.rem_euclid(r);
+                if r < 0 && m != 0 {
+                    m + r
+                } else {
+                    m
+                }
+            }
+            TokenKind::Pow if r < 0 => return Ok(Value::from((l as f64).powf(r as f64))),
+            TokenKind::Pow => u32::try_from(r)
+                .ok()
+                .and_then(|r| l.checked_pow(r))
+                .unwrap_or(i128::MAX),
+            TokenKind::BitAnd => l & r,
+            TokenKind::BitOr => l | r,
+            TokenKind::BitXor => l ^ r,
+            TokenKind::Shl => u32::try_from(r)
+                .ok()
+                .and_then(|r| l.checked_shl(r))
+                .unwrap_or(i128::MAX),
+            TokenKind::Shr => l >> r.min(127),
+            _ => return compare(interp, op, &lhs, &rhs, loc),
+        };
+        return int_value(interp, res, loc);
+    }
+    if let (Some(l), Some(r)) = (lhs.as_float(), rhs.as_float()) {
+        let res = match op.kind {
+            TokenKind::Plus => l + r,
+            TokenKind::Minus => l - r,
+            TokenKind::Star => l * r,
+            TokenKind::Slash | TokenKind::FloorDiv | TokenKind::Mod if r == 0.0 => {
+                return Err(zero_division(interp, loc))
+            }
+            TokenKind::Slash => l / r,
+            TokenKind::FloorDiv => (l / r).floor(),
+            TokenKind::Mod => l - r * (l / r).floor(),
+            TokenKind::Pow => l.powf(r),
+            _ => return compare(interp, op, &lhs, &rhs, loc),
+        };
+        return Ok(Value::from(res));
+    }
+    match (op.kind, &lhs, &rhs) {
+        (TokenKind::Plus, Value::Obj(ValueObj::Str(l)), Value::Obj(ValueObj::Str(r))) => {
+            Ok(Value::str(format!("{l}{r}")))
+        }
+        (TokenKind::Star, Value::Obj(ValueObj::Str(s)), n)
+        | (TokenKind::Star, n, Value::Obj(ValueObj::Str(s)))
+            if n.as_int().is_some() =>
+        {
+            let n = n.as_int().unwrap_or(0).max(0) as usize;
+            Ok(Value::str(s.repeat(n)))
+        }
+        (TokenKind::Star, Value::List(elems), n) | (TokenKind::Star, n, Value::List(elems))
+            if n.as_int().is_some() =>
+        {
+            let n = n.as_int().unwrap_or(0).max(0) as usize;
+            let elems = elems.borrow();
+            Ok(Value::list(
+                (0..n).flat_map(|_| elems.iter().cloned()).collect(),
+            ))
+        }
+        (TokenKind::Plus, Value::List(l), Value::List(r)) => {
+            let mut elems = l.borrow().clone();
+            elems.extend(r.borrow().iter().cloned());
+            Ok(Value::list(elems))
+        }
+        (TokenKind::Plus, Value::Tuple(l), Value::Tuple(r)) => {
+            Ok(Value::Tuple(l.iter().chain(r.iter()).cloned().collect()))
+        }
+        (TokenKind::BitOr, Value::Set(l), Value::Set(r)) => {
+            let mut elems = l.borrow().clone();
+            elems.extend(r.borrow().iter().cloned());
+            Ok(Value::set(elems))
+        }
+        (TokenKind::BitAnd, Value::Set(l), Value::Set(r)) => {
+            let r = r.borrow();
+            let elems = l
+                .borrow()
+                .iter()
+                .filter(|e| r.contains(e))
+                .cloned()
+                .collect();
+            Ok(Value::set(elems))
+        }
+        (TokenKind::Minus, Value::Set(l), Value::Set(r)) => {
+            let r = r.borrow();
+            let elems = l
+                .borrow()
+                .iter()
+                .filter(|e| !r.contains(e))
+                .cloned()
+                .collect();
+            Ok(Value::set(elems))
+        }
+        _ => compare(interp, op, &lhs, &rhs, loc),
+    }
+}
+
+fn compare(
+    interp: &mut Interpreter,
+    op: &Token,
+    lhs: &Value,
+    rhs: &Value,
+    loc: Location,
+) -> EvalResult<Value> {
+    let expect = |ord: Ordering| match op.kind {
+        TokenKind::Less => Some(ord.is_lt()),
+        TokenKind::Gre => Some(ord.is_gt()),
+        TokenKind::LessEq => Some(ord.is_le()),
+        TokenKind::GreEq => Some(ord.is_ge()),
+        _ => None,
+    };
+    if let Some(ord) = partial_cmp(lhs, rhs) {
+        if let Some(res) = expect(ord) {
+            return Ok(Value::from(res));
+        }
+    }
+    Err(interp.type_error(
+        format!(
+            "unsupported operand type(s) for {}: '{}' and '{}'",
+            op.content,
+            lhs.class_name(),
+            rhs.class_name()
+        ),
+        loc,
+    ))
+}
+
+fn partial_cmp(lhs: &Value, rhs: &Value) -> Option<Ordering> {
+    let (lhs, rhs) = (lhs.deref(), rhs.deref());
+    if let (Some(l), Some(r)) = (as_i128(&lhs), as_i128(&rhs)) {
+        return Some(l.cmp(&r));
+    }
+    if let (Some(l), Some(r)) = (lhs.as_float(), rhs.as_float()) {
+        return l.partial_cmp(&r);
+    }
+    match (&lhs, &rhs) {
+        (Value::Obj(ValueObj::Str(l)), Value::Obj(ValueObj::Str(r))) => Some(l.cmp(r)),
+        (Value::List(l), Value::List(r)) => cmp_seq(&l.borrow(), &r.borrow()),
+        (Value::Tuple(l), Value::Tuple(r)) => cmp_seq(l, r),
+        _ => None,
+    }
+}
+
+fn cmp_seq(lhs: &[Value], rhs: &[Value]) -> Option<Ordering> {
+    for (l, r) in lhs.iter().zip(rhs.iter()) {
+        if l != r {
+            return partial_cmp(l, r);
+        }
+    }
+    Some(lhs.len().cmp(&rhs.len()))
+}
+
+fn iter(interp: &Interpreter, value: &Value, loc: Location) -> EvalResult<Vec<Value>> {
+    value.iter_values().ok_or_else(|| {
+        interp.type_error(
+            format!("'{}' object is not iterable", value.class_name()),
+            loc,
+        )
+    })
+}
+
+fn take_args<const N: usize>(
+    interp: &Interpreter,
+    name: &str,
+    args: Vec<Value>,
+    loc: Location,
+) -> EvalResult<[Value; N]> {
+    let len = args.len();
+    <[Value; N]>::try_from(args).map_err(|_| {
+        interp.type_error(
+            format!("{name}() takes {N} arguments, but {len} were given"),
+            loc,
+        )
+    })
+}
+
+fn expect_int(interp: &Interpreter, value: &Value, loc: Location) -> EvalResult<i64> {
+    value.as_int().ok_or_else(|| {
+        interp.type_error(
+            format!("expected an Int, but found '{}'", value.class_name()),
+            loc,
+        )
+    })
+}
+
+fn expect_str(interp: &Interpreter, value: &Value, loc: Location) -> EvalResult<Str> {
+    value.as_str().ok_or_else(|| {
+        interp.type_error(
+            format!("expected a Str, but found '{}'", value.class_name()),
+            loc,
+        )
+    })
+}
+
+fn kw_arg(kw_args: &[(Str, Value)], name: &str) -> Option<Value> {
+    kw_args
+        .iter()
+        .find(|(kw, _)| &kw[..] == name)
+        .map(|(_, value)| value.clone())
+}
+
+fn print(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    kw_args: Vec<(Str, Value)>,
+    _loc: Location,
+) -> EvalResult<Value> {
+    let sep = kw_arg(&kw_args, "sep").map_or(" ".to_string(), |sep| sep.to_string());
+    let end = kw_arg(&kw_args, "end").map_or("\n".to_string(), |end| end.to_string());
+    let out = args
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect::<Vec<_>>()
+        .join(&sep);
+    interp.write_output(&(out + &end));
+    Ok(Value::NONE)
+}
+
+fn str(
+    _interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    _loc: Location,
+) -> EvalResult<Value> {
+    Ok(Value::str(
+        args.first().map(|arg| arg.to_string()).unwrap_or_default(),
+    ))
+}
+
+fn repr(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [value] = take_args(interp, "repr", args, loc)?;
+    Ok(Value::str(value.repr()))
+}
+
+fn len(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [value] = take_args(interp, "len", args, loc)?;
+    let len = match value.deref() {
+        Value::Obj(ValueObj::Str(s)) => s.chars().count(),
+        Value::Dict(kvs) => kvs.borrow().len(),
+        Value::Record(fields) => fields.len(),
+        other => iter(interp, &other, loc)?.len(),
+    };
+    Ok(Value::from(len as i64))
+}
+
+fn abs(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [value] = take_args(interp, "abs", args, loc)?;
+    match value.as_obj() {
+        Some(ValueObj::Float(f)) => Ok(Value::from(f.abs())),
+        _ => int_value(
+            interp,
+            (expect_int(interp, &value, loc)? as i128).abs(),
+            loc,
+        ),
+    }
+}
+
+fn int(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [value] = take_args(interp, "int", args, loc)?;
+    match value.as_obj() {
+        Some(ValueObj::Float(f)) if f.is_finite() => int_value(interp, f.trunc() as i128, loc),
+        Some(ValueObj::Str(s)) => match s.trim().parse::<i128>() {
+            Ok(i) => int_value(interp, i, loc),
+            Err(_) => Err(interp.error(
+                ErrorKind::ValueError,
+                format!("invalid literal for int(): {}", value.repr()),
+                loc,
+            )),
+        },
+        _ => int_value(interp, expect_int(interp, &value, loc)? as i128, loc),
+    }
+}
+
+fn nat(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    convert(interp, &Value::builtin_type(Type::Nat), args, loc)
+}
+
+fn float(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [value] = take_args(interp, "float", args, loc)?;
+    if let Some(s) = value.as_str() {
+        return s.trim().parse::<f64>().map(Value::from).map_err(|_| {
+            interp.error(
+                ErrorKind::ValueError,
+                format!("could not convert string to float: {}", value.repr()),
+                loc,
+            )
+        });
+    }
+    value.as_float().map(Value::from).ok_or_else(|| {
+        interp.type_error(
+            format!(
+                "float() argument must be a number, not '{}'",
+                value.class_name()
+            ),
+            loc,
+        )
+    })
+}
+
+fn bool(
+    _interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    _loc: Location,
+) -> EvalResult<Value> {
+    Ok(Value::from(args.first().is_some_and(Value::is_truthy)))
+}
+
+fn not(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [value] = take_args(interp, "not", args, loc)?;
+    Ok(Value::from(!value.is_truthy()))
+}
+
+fn isinstance(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [value, t] = take_args(interp, "isinstance", args, loc)?;
+    // `isinstance(1, (Int, Str))`
+    let res = match &t {
+        Value::Tuple(types) => types
+            .iter()
+            .try_fold(false, |acc, t| Some(acc || is_instance(&value, t)?)),
+        _ => is_instance(&value, &t),
+    };
+    res.map(Value::from).ok_or_else(|| {
+        interp.type_error(
+            format!(
+                "isinstance() arg 2 must be a type, not '{}'",
+                t.class_name()
+            ),
+            loc,
+        )
+    })
+}
+
+fn hasattr(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [obj, name] = take_args(interp, "hasattr", args, loc)?;
+    let name = expect_str(interp, &name, loc)?;
+    Ok(Value::from(interp.get_attr(&obj, &name, loc).is_ok()))
+}
+
+fn contains_func(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [container, elem] = take_args(interp, "contains", args, loc)?;
+    contains(interp, &container, &elem, loc).map(Value::from)
+}
+
+fn extremum(
+    interp: &mut Interpreter,
+    name: &str,
+    args: Vec<Value>,
+    loc: Location,
+    ord: Ordering,
+) -> EvalResult<Value> {
+    let elems = if args.len() == 1 {
+        iter(interp, &args[0], loc)?
+    } else {
+        args
+    };
+    let mut elems = elems.into_iter();
+    let Some(mut res) = elems.next() else {
+        return Err(interp.error(
+            ErrorKind::ValueError,
+            format!("{name}() arg is an empty sequence"),
+            loc,
+        ));
+    };
+    for elem in elems {
+        match partial_cmp(&elem, &res) {
+            Some(o) if o == ord => res = elem,
+            Some(_) => {}
+            None => {
+                return Err(interp.type_error(
+                    format!(
+                        "'{}' and '{}' cannot be compared",
+                        elem.class_name(),
+                        res.class_name()
+                    ),
+                    loc,
+                ))
+            }
+        }
+    }
+    Ok(res)
+}
+
+fn min(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    extremum(interp, "min", args, loc, Ordering::Less)
+}
+
+fn max(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    extremum(interp, "max", args, loc, Ordering::Greater)
+}
+
+fn sum(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let Some(iterable) = args.first() else {
+        return Err(interp.type_error("sum() takes at least 1 argument", loc));
+    };
+    let start = args
+        .get(1)
+        .cloned()
+        .or_else(|| kw_arg(&kw_args, "start"))
+        .unwrap_or(Value::from(0));
+    let plus = Token::from_str(TokenKind::Plus, "+");
+    let mut res = start;
+    for elem in iter(interp, iterable, loc)? {
+        res = binary(interp, &plus, res, elem, loc)?;
+    }
+    Ok(res)
+}
+
+fn all(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [iterable] = take_args(interp, "all", args, loc)?;
+    Ok(Value::from(
+        iter(interp, &iterable, loc)?.iter().all(Value::is_truthy),
+    ))
+}
+
+fn any(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [iterable] = take_args(interp, "any", args, loc)?;
+    Ok(Value::from(
+        iter(interp, &iterable, loc)?.iter().any(Value::is_truthy),
+    ))
+}
+
+fn sort_values(
+    interp: &mut Interpreter,
+    elems: &mut [Value],
+    kw_args: &[(Str, Value)],
+    loc: Location,
+) -> EvalResult<()> {
+    let key = kw_arg(kw_args, "key");
+    let mut keyed = Vec::with_capacity(elems.len());
+    for elem in elems.iter() {
+        let k = match &key {
+            Some(key) => interp.call(key, vec![elem.clone()], vec![], loc)?,
+            None => elem.clone(),
+        };
+        keyed.push((k, elem.clone()));
+    }
+    let mut incomparable = false;
+    keyed.sort_by(|(l, _), (r, _)| {
+        partial_cmp(l, r).unwrap_or_else(|| {
+            incomparable = true;
+            Ordering::Equal
+        })
+    });
+    if incomparable {
+        return Err(interp.type_error("the elements cannot be compared", loc));
+    }
+    if kw_arg(kw_args, "reverse").is_some_and(|rev| rev.is_truthy()) {
+        keyed.reverse();
+    }
+    for (dst, (_, elem)) in elems.iter_mut().zip(keyed) {
+        *dst = elem;
+    }
+    Ok(())
+}
+
+fn sorted(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [iterable] = take_args(interp, "sorted", args, loc)?;
+    let mut elems = iter(interp, &iterable, loc)?;
+    sort_values(interp, &mut elems, &kw_args, loc)?;
+    Ok(Value::list(elems))
+}
+
+fn reversed(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [iterable] = take_args(interp, "reversed", args, loc)?;
+    let mut elems = iter(interp, &iterable, loc)?;
+    elems.reverse();
+    Ok(Value::list(elems))
+}
+
+fn range(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let ints = args
+        .iter()
+        .map(|arg| expect_int(interp, arg, loc))
+        .collect::<EvalResult<Vec<_>>>()?;
+    match ints[..] {
+        [end] => Ok(Value::Range(0, end, 1)),
+        [start, end] => Ok(Value::Range(start, end, 1)),
+        [_, _, 0] => {
+            Err(interp.error(ErrorKind::ValueError, "range() arg 3 must not be zero", loc))
+        }
+        [start, end, step] => Ok(Value::Range(start, end, step)),
+        _ => Err(interp.type_error("range() takes 1 to 3 arguments", loc)),
+    }
+}
+
+fn enumerate(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let Some(iterable) = args.first() else {
+        return Err(interp.type_error("enumerate() takes at least 1 argument", loc));
+    };
+    let start = match args.get(1).cloned().or_else(|| kw_arg(&kw_args, "start")) {
+        Some(start) => expect_int(interp, &start, loc)?,
+        None => 0,
+    };
+    let elems = iter(interp, iterable, loc)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, elem)| Value::Tuple(vec![Value::from(start + i as i64), elem].into()))
+        .collect();
+    Ok(Value::list(elems))
+}
+
+fn zip(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let iterables = args
+        .iter()
+        .map(|arg| iter(interp, arg, loc))
+        .collect::<EvalResult<Vec<_>>>()?;
+    let len = iterables.iter().map(Vec::len).min().unwrap_or(0);
+    let elems = (0..len)
+        .map(|i| Value::Tuple(iterables.iter().map(|elems| elems[i].clone()).collect()))
+        .collect();
+    Ok(Value::list(elems))
+}
+
+fn map(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [func, iterable] = take_args(interp, "map", args, loc)?;
+    let mut elems = vec![];
+    for elem in iter(interp, &iterable, loc)? {
+        elems.push(interp.call(&func, vec![elem], vec![], loc)?);
+    }
+    Ok(Value::list(elems))
+}
+
+fn filter(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [func, iterable] = take_args(interp, "filter", args, loc)?;
+    let mut elems = vec![];
+    for elem in iter(interp, &iterable, loc)? {
+        if interp
+            .call(&func, vec![elem.clone()], vec![], loc)?
+            .is_truthy()
+        {
+            elems.push(elem);
+        }
+    }
+    Ok(Value::list(elems))
+}
+
+fn round(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let Some(value) = args.first() else {
+        return Err(interp.type_error("round() takes at least 1 argument", loc));
+    };
+    let Some(f) = value.as_float() else {
+        return Err(interp.type_error(
+            format!("type '{}' doesn't define __round__", value.class_name()),
+            loc,
+        ));
+    };
+    match args.get(1) {
+        Some(digits) => {
+            let scale = 10f64.powi(expect_int(interp, digits, loc)? as i32);
+            Ok(Value::from((f * scale).round_ties_even() / scale))
+        }
+        None => int_value(interp, f.round_ties_even() as i128, loc),
+    }
+}
+
+fn chr(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [code] = take_args(interp, "chr", args, loc)?;
+    let code = expect_int(interp, &code, loc)?;
+    u32::try_from(code)
+        .ok()
+        .and_then(char::from_u32)
+        .map(|c| Value::str(c.to_string()))
+        .ok_or_else(|| interp.error(ErrorKind::ValueError, "chr() arg not in range", loc))
+}
+
+fn ord(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [c] = take_args(interp, "ord", args, loc)?;
+    let s = expect_str(interp, &c, loc)?;
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(Value::from(c as i64)),
+        _ => Err(interp.type_error("ord() expected a character", loc)),
+    }
+}
+
+fn bin(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [i] = take_args(interp, "bin", args, loc)?;
+    let i = expect_int(interp, &i, loc)?;
+    let sign = if i < 0 { "-" } else { "" };
+    Ok(Value::str(format!("{sign}0b{:b}", i.unsigned_abs())))
+}
+
+/// Iterators are not lazy in Dyne, `iter(x)` returns the elements as a list.
+fn iter_func(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [iterable] = take_args(interp, "iter", args, loc)?;
+    Ok(Value::list(iter(interp, &iterable, loc)?))
+}
+
+/// `if` used as a value (e.g. `i = if`). Direct calls are evaluated as special forms.
+fn if_func(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let mut args = args.into_iter();
+    let (Some(cond), Some(then)) = (args.next(), args.next()) else {
+        return Err(interp.type_error("if requires a condition and a block", loc));
+    };
+    if cond.is_truthy() {
+        interp.call(&then, vec![], vec![], loc)
+    } else if let Some(else_) = args.next() {
+        interp.call(&else_, vec![], vec![], loc)
+    } else {
+        Ok(Value::NONE)
+    }
+}
+
+fn for_func(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [iterable, body] = take_args(interp, "for!", args, loc)?;
+    for elem in iter(interp, &iterable, loc)? {
+        interp.call(&body, vec![elem], vec![], loc)?;
+    }
+    Ok(Value::NONE)
+}
+
+fn while_func(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [cond, body] = take_args(interp, "while!", args, loc)?;
+    while interp.call(&cond, vec![], vec![], loc)?.is_truthy() {
+        interp.call(&body, vec![], vec![], loc)?;
+    }
+    Ok(Value::NONE)
+}
+
+fn discard(
+    _interp: &mut Interpreter,
+    _args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    _loc: Location,
+) -> EvalResult<Value> {
+    Ok(Value::NONE)
+}
+
+fn exit(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let code = match args.first() {
+        Some(code) => expect_int(interp, code, loc)? as i32,
+        None => 0,
+    };
+    interp.set_exit_code(code);
+    Err(super::EvalError::system_exit())
+}
+
+fn panic(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let msg = args.first().map(|msg| msg.to_string()).unwrap_or_default();
+    interp.set_exit_code(1);
+    Err(interp.error(ErrorKind::RuntimeError, msg, loc))
+}
+
+fn mut_cell(value: &Value) -> &Rc<std::cell::RefCell<Value>> {
+    let Value::Mut(cell) = value else {
+        unreachable!("the receiver of a mutable method must be a cell")
+    };
+    cell
+}
+
+fn mut_inc(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let cell = mut_cell(&args[0]);
+    let by = args.get(1).cloned().unwrap_or(Value::from(1));
+    let plus = Token::from_str(TokenKind::Plus, "+");
+    let value = binary(interp, &plus, args[0].deref(), by, loc)?;
+    *cell.borrow_mut() = value;
+    Ok(Value::NONE)
+}
+
+fn mut_dec(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let cell = mut_cell(&args[0]);
+    let by = args.get(1).cloned().unwrap_or(Value::from(1));
+    let minus = Token::from_str(TokenKind::Minus, "-");
+    let value = binary(interp, &minus, args[0].deref(), by, loc)?;
+    *cell.borrow_mut() = value;
+    Ok(Value::NONE)
+}
+
+fn mut_update(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [cell, func] = take_args(interp, "update!", args, loc)?;
+    let value = interp.call(&func, vec![cell.deref()], vec![], loc)?;
+    *mut_cell(&cell).borrow_mut() = value;
+    Ok(Value::NONE)
+}
+
+fn mut_str_push(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [cell, s] = take_args(interp, "push!", args, loc)?;
+    let s = expect_str(interp, &s, loc)?;
+    let pushed = format!("{}{s}", cell.deref());
+    *mut_cell(&cell).borrow_mut() = Value::str(pushed);
+    Ok(Value::NONE)
+}
+
+fn mut_invert(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [cell] = take_args(interp, "invert!", args, loc)?;
+    let inverted = !cell.is_truthy();
+    *mut_cell(&cell).borrow_mut() = Value::from(inverted);
+    Ok(Value::NONE)
+}
+
+fn list_of(value: &Value) -> &Rc<std::cell::RefCell<Vec<Value>>> {
+    match value {
+        Value::List(elems) | Value::Set(elems) => elems,
+        _ => unreachable!("the receiver must be a list or a set"),
+    }
+}
+
+fn list_push(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [list, elem] = take_args(interp, "push!", args, loc)?;
+    list_of(&list).borrow_mut().push(elem);
+    Ok(Value::NONE)
+}
+
+/// Resolves a Python-style (possibly negative) index.
+fn index(interp: &Interpreter, len: usize, idx: &Value, loc: Location) -> EvalResult<usize> {
+    let i = expect_int(interp, idx, loc)?;
+    let resolved = if i < 0 { i + len as i64 } else { i };
+    if (0..len as i64).contains(&resolved) {
+        Ok(resolved as usize)
+    } else {
+        Err(interp.error(
+            ErrorKind::IndexError,
+            format!("the index {i} is out of range (the length is {len})"),
+            loc,
+        ))
+    }
+}
+
+fn list_pop(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let elems = list_of(&args[0]);
+    let len = elems.borrow().len();
+    let idx = match args.get(1) {
+        Some(idx) => index(interp, len, idx, loc)?,
+        None if len == 0 => {
+            return Err(interp.error(ErrorKind::IndexError, "pop from empty list", loc))
+        }
+        None => len - 1,
+    };
+    let popped = elems.borrow_mut().remove(idx);
+    Ok(popped)
+}
+
+fn list_extend(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [list, iterable] = take_args(interp, "extend!", args, loc)?;
+    let elems = iter(interp, &iterable, loc)?;
+    list_of(&list).borrow_mut().extend(elems);
+    Ok(Value::NONE)
+}
+
+fn list_insert(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [list, idx, elem] = take_args(interp, "insert!", args, loc)?;
+    let elems = list_of(&list);
+    let len = elems.borrow().len();
+    let idx = expect_int(interp, &idx, loc)?;
+    let idx = if idx < 0 { idx + len as i64 } else { idx }.clamp(0, len as i64);
+    elems.borrow_mut().insert(idx as usize, elem);
+    Ok(Value::NONE)
+}
+
+fn list_remove(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [list, elem] = take_args(interp, "remove!", args, loc)?;
+    let elems = list_of(&list);
+    let pos = elems.borrow().iter().position(|e| e == &elem);
+    match pos {
+        Some(pos) => {
+            elems.borrow_mut().remove(pos);
+            Ok(Value::NONE)
+        }
+        None => Err(interp.error(
+            ErrorKind::ValueError,
+            format!("{} is not in the list", elem.repr()),
+            loc,
+        )),
+    }
+}
+
+fn list_clear(
+    _interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    _loc: Location,
+) -> EvalResult<Value> {
+    list_of(&args[0]).borrow_mut().clear();
+    Ok(Value::NONE)
+}
+
+fn list_reverse(
+    _interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    _loc: Location,
+) -> EvalResult<Value> {
+    list_of(&args[0]).borrow_mut().reverse();
+    Ok(Value::NONE)
+}
+
+fn list_sort(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let elems = list_of(&args[0]);
+    let mut sorted = elems.borrow().clone();
+    sort_values(interp, &mut sorted, &kw_args, loc)?;
+    *elems.borrow_mut() = sorted;
+    Ok(Value::NONE)
+}
+
+fn list_update(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [list, func] = take_args(interp, "update!", args, loc)?;
+    let elems = list_of(&list);
+    let copy = Value::list(elems.borrow().clone());
+    let updated = interp.call(&func, vec![copy], vec![], loc)?;
+    let updated = iter(interp, &updated, loc)?;
+    *elems.borrow_mut() = updated;
+    Ok(Value::NONE)
+}
+
+fn list_setitem(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [list, idx, elem] = take_args(interp, "__setitem__", args, loc)?;
+    let elems = list_of(&list);
+    let idx = index(interp, elems.borrow().len(), &idx, loc)?;
+    elems.borrow_mut()[idx] = elem;
+    Ok(Value::NONE)
+}
+
+fn list_update_nth(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [list, idx, func] = take_args(interp, "update_nth!", args, loc)?;
+    let elems = list_of(&list);
+    let idx = index(interp, elems.borrow().len(), &idx, loc)?;
+    let elem = elems.borrow()[idx].clone();
+    let updated = interp.call(&func, vec![elem], vec![], loc)?;
+    elems.borrow_mut()[idx] = updated;
+    Ok(Value::NONE)
+}
+
+fn list_concat(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [list, other] = take_args(interp, "concat", args, loc)?;
+    let mut elems = list_of(&list).borrow().clone();
+    elems.extend(iter(interp, &other, loc)?);
+    Ok(Value::list(elems))
+}
+
+fn list_from(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [list, nth] = take_args(interp, "from", args, loc)?;
+    let nth = usize::try_from(expect_int(interp, &nth, loc)?).unwrap_or(0);
+    let elems = list_of(&list).borrow();
+    Ok(Value::list(elems.iter().skip(nth).cloned().collect()))
+}
+
+/// The mutable elements are copied (e.g. `[![1]].repeat 2` has two different lists).
+fn list_repeat(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [list, n] = take_args(interp, "repeat", args, loc)?;
+    let n = usize::try_from(expect_int(interp, &n, loc)?).unwrap_or(0);
+    let elems = list_of(&list).borrow();
+    let mut repeated = Vec::with_capacity(elems.len() * n);
+    for _ in 0..n {
+        repeated.extend(elems.iter().map(deep_copy));
+    }
+    Ok(Value::list(repeated))
+}
+
+fn deep_copy(value: &Value) -> Value {
+    match value {
+        Value::Mut(cell) => Value::Mut(Rc::new(cell.borrow().clone().into())),
+        Value::List(elems) => Value::list(elems.borrow().iter().map(deep_copy).collect()),
+        Value::Set(elems) => Value::set(elems.borrow().iter().map(deep_copy).collect()),
+        Value::Dict(kvs) => Value::dict(
+            kvs.borrow()
+                .iter()
+                .map(|(k, v)| (k.clone(), deep_copy(v)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// `xs.dedup()` removes the duplicated elements,
+/// `xs.dedup(same_bucket)` removes the elements `same_bucket(x, next)` holds for (in place).
+fn list_dedup(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let mut args = args.into_iter();
+    let Some(list) = args.next() else {
+        return Err(interp.type_error("dedup() requires a list", loc));
+    };
+    let elems = list_of(&list).borrow().clone();
+    let Some(same_bucket) = args.next() else {
+        let mut deduped: Vec<Value> = Vec::with_capacity(elems.len());
+        for elem in elems {
+            if !deduped.contains(&elem) {
+                deduped.push(elem);
+            }
+        }
+        return Ok(Value::list(deduped));
+    };
+    let mut removes = vec![];
+    for (lhs, rhs) in elems.iter().zip(elems.iter().skip(1)) {
+        let same = interp.call(&same_bucket, vec![lhs.clone(), rhs.clone()], vec![], loc)?;
+        if same.is_truthy() {
+            removes.push(lhs.clone());
+        }
+    }
+    let mut elems = list_of(&list).borrow_mut();
+    for remove in removes {
+        if let Some(pos) = elems.iter().position(|elem| elem == &remove) {
+            elems.remove(pos);
+        }
+    }
+    drop(elems);
+    Ok(list)
+}
+
+fn list_partition(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [list, pred] = take_args(interp, "partition", args, loc)?;
+    let (mut yes, mut no) = (vec![], vec![]);
+    for elem in iter(interp, &list, loc)? {
+        if interp
+            .call(&pred, vec![elem.clone()], vec![], loc)?
+            .is_truthy()
+        {
+            yes.push(elem);
+        } else {
+            no.push(elem);
+        }
+    }
+    Ok(Value::Tuple(vec![Value::list(yes), Value::list(no)].into()))
+}
+
+fn slice(
+    interp: &Interpreter,
+    elems: Vec<Value>,
+    range: &Value,
+    loc: Location,
+) -> EvalResult<Vec<Value>> {
+    let Value::Range(start, end, step) = range.deref() else {
+        unreachable!()
+    };
+    if step <= 0 {
+        return Err(interp.error(ErrorKind::ValueError, "slice step must be positive", loc));
+    }
+    let len = elems.len() as i64;
+    let resolve = |i: i64| if i < 0 { (i + len).max(0) } else { i.min(len) };
+    let (start, end) = (resolve(start), resolve(end));
+    Ok(elems
+        .into_iter()
+        .enumerate()
+        .skip(start as usize)
+        .take((end - start).max(0) as usize)
+        .step_by(step as usize)
+        .map(|(_, elem)| elem)
+        .collect())
+}
+
+fn seq_getitem(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [seq, idx] = take_args(interp, "__getitem__", args, loc)?;
+    let elems = iter(interp, &seq, loc)?;
+    if matches!(idx.deref(), Value::Range(..)) {
+        let sliced = slice(interp, elems, &idx, loc)?;
+        return Ok(match seq {
+            Value::Tuple(_) => Value::Tuple(sliced.into()),
+            _ => Value::list(sliced),
+        });
+    }
+    let idx = index(interp, elems.len(), &idx, loc)?;
+    Ok(elems[idx].clone())
+}
+
+fn seq_index(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [seq, elem] = take_args(interp, "index", args, loc)?;
+    match iter(interp, &seq, loc)?.iter().position(|e| e == &elem) {
+        Some(pos) => Ok(Value::from(pos as i64)),
+        None => Err(interp.error(
+            ErrorKind::ValueError,
+            format!("{} is not in the sequence", elem.repr()),
+            loc,
+        )),
+    }
+}
+
+fn seq_count(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [seq, elem] = take_args(interp, "count", args, loc)?;
+    let count = iter(interp, &seq, loc)?
+        .iter()
+        .filter(|e| *e == &elem)
+        .count();
+    Ok(Value::from(count as i64))
+}
+
+fn iter_map(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [iterable, func] = take_args(interp, "map", args, loc)?;
+    map(interp, vec![func, iterable], vec![], loc)
+}
+
+fn iter_filter(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [iterable, func] = take_args(interp, "filter", args, loc)?;
+    filter(interp, vec![func, iterable], vec![], loc)
+}
+
+fn iter_to_list(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [iterable] = take_args(interp, "to_list", args, loc)?;
+    Ok(Value::list(iter(interp, &iterable, loc)?))
+}
+
+fn collection_copy(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [collection] = take_args(interp, "copy", args, loc)?;
+    Ok(match collection {
+        Value::List(elems) => Value::list(elems.borrow().clone()),
+        Value::Set(elems) => Value::set(elems.borrow().clone()),
+        Value::Dict(kvs) => Value::dict(kvs.borrow().clone()),
+        other => other,
+    })
+}
+
+fn set_add(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [set, elem] = take_args(interp, "add!", args, loc)?;
+    let elems = list_of(&set);
+    if !elems.borrow().contains(&elem) {
+        elems.borrow_mut().push(elem);
+    }
+    Ok(Value::NONE)
+}
+
+fn set_remove(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [set, elem] = take_args(interp, "remove!", args, loc)?;
+    list_of(&set).borrow_mut().retain(|e| e != &elem);
+    Ok(Value::NONE)
+}
+
+fn dict_of(value: &Value) -> &Rc<std::cell::RefCell<Vec<(Value, Value)>>> {
+    let Value::Dict(kvs) = value else {
+        unreachable!("the receiver must be a dict")
+    };
+    kvs
+}
+
+fn dict_getitem(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [dict, key] = take_args(interp, "__getitem__", args, loc)?;
+    let found = dict_of(&dict)
+        .borrow()
+        .iter()
+        .find(|(k, _)| k == &key)
+        .map(|(_, v)| v.clone());
+    found.ok_or_else(|| interp.error(ErrorKind::KeyError, key.repr(), loc))
+}
+
+fn dict_insert(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [dict, key, value] = take_args(interp, "insert!", args, loc)?;
+    let mut kvs = dict_of(&dict).borrow_mut();
+    if let Some((_, v)) = kvs.iter_mut().find(|(k, _)| k == &key) {
+        *v = value;
+    } else {
+        kvs.push((key, value));
+    }
+    Ok(Value::NONE)
+}
+
+fn dict_remove(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [dict, key] = take_args(interp, "remove!", args, loc)?;
+    let mut kvs = dict_of(&dict).borrow_mut();
+    match kvs.iter().position(|(k, _)| k == &key) {
+        Some(pos) => Ok(kvs.remove(pos).1),
+        None => Ok(Value::NONE),
+    }
+}
+
+fn dict_get(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let mut args = args.into_iter();
+    let (Some(dict), Some(key)) = (args.next(), args.next()) else {
+        return Err(interp.type_error("get() takes at least 1 argument", loc));
+    };
+    let default = args.next().unwrap_or(Value::NONE);
+    let found = dict_of(&dict)
+        .borrow()
+        .iter()
+        .find(|(k, _)| k == &key)
+        .map(|(_, v)| v.clone());
+    Ok(found.unwrap_or(default))
+}
+
+fn dict_keys(
+    _interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    _loc: Location,
+) -> EvalResult<Value> {
+    let keys = dict_of(&args[0])
+        .borrow()
+        .iter()
+        .map(|(k, _)| k.clone())
+        .collect();
+    Ok(Value::list(keys))
+}
+
+fn dict_values(
+    _interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    _loc: Location,
+) -> EvalResult<Value> {
+    let values = dict_of(&args[0])
+        .borrow()
+        .iter()
+        .map(|(_, v)| v.clone())
+        .collect();
+    Ok(Value::list(values))
+}
+
+fn dict_items(
+    _interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    _loc: Location,
+) -> EvalResult<Value> {
+    let items = dict_of(&args[0])
+        .borrow()
+        .iter()
+        .map(|(k, v)| Value::Tuple(vec![k.clone(), v.clone()].into()))
+        .collect();
+    Ok(Value::list(items))
+}
+
+fn dict_concat(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [dict, other] = take_args(interp, "concat", args, loc)?;
+    let mut kvs = dict_of(&dict).borrow().clone();
+    let Value::Dict(other) = dict_from(interp, Some(&other), loc)? else {
+        unreachable!()
+    };
+    kvs.extend(other.borrow().iter().cloned());
+    Ok(Value::dict(kvs))
+}
+
+fn dict_diff(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [dict, other] = take_args(interp, "diff", args, loc)?;
+    let other = iter(interp, &other, loc)?;
+    let kvs = dict_of(&dict)
+        .borrow()
+        .iter()
+        .filter(|(k, _)| !other.contains(k))
+        .cloned()
+        .collect();
+    Ok(Value::dict(kvs))
+}
+
+/// `dict.merge!(other, conflict_resolver)`, `dict.update!(pairs, key := value)`
+fn dict_merge(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let mut args = args.into_iter();
+    let Some(dict) = args.next() else {
+        return Err(interp.type_error("merge!() requires a dict", loc));
+    };
+    let Value::Dict(other) = dict_from(interp, args.next().as_ref(), loc)? else {
+        unreachable!()
+    };
+    let resolver = args.next();
+    let pairs = other
+        .borrow()
+        .iter()
+        .cloned()
+        .chain(
+            kw_args
+                .into_iter()
+                .map(|(kw, value)| (Value::from(kw), value)),
+        )
+        .collect::<Vec<_>>();
+    for (key, value) in pairs {
+        let current = dict_of(&dict)
+            .borrow()
+            .iter()
+            .find(|(k, _)| k == &key)
+            .map(|(_, v)| v.clone());
+        let value = match (current, resolver.as_ref()) {
+            (Some(current), Some(resolver)) => {
+                interp.call(resolver, vec![current, value], vec![], loc)?
+            }
+            _ => value,
+        };
+        dict_insert(interp, vec![dict.clone(), key, value], vec![], loc)?;
+    }
+    Ok(Value::NONE)
+}
+
+fn dict_as_record(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [dict] = take_args(interp, "as_record", args, loc)?;
+    let mut fields = vec![];
+    for (key, value) in dict_of(&dict).borrow().iter() {
+        let key = expect_str(interp, key, loc)?;
+        fields.push((key, value.clone()));
+    }
+    Ok(Value::Record(fields.into()))
+}
+
+/// The receiver and the (string) arguments of a `Str` method
+fn str_args<const N: usize>(
+    interp: &Interpreter,
+    name: &str,
+    args: Vec<Value>,
+    loc: Location,
+) -> EvalResult<[Str; N]> {
+    let args = args
+        .iter()
+        .map(|arg| expect_str(interp, arg, loc))
+        .collect::<EvalResult<Vec<_>>>()?;
+    let len = args.len();
+    <[Str; N]>::try_from(args).map_err(|_| {
+        interp.type_error(
+            format!(
+                "{name}() takes {} arguments, but {} were given",
+                N - 1,
+                len - 1
+            ),
+            loc,
+        )
+    })
+}
+
+fn str_getitem(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [s, idx] = take_args(interp, "__getitem__", args, loc)?;
+    let chars = iter(interp, &s, loc)?;
+    if matches!(idx.deref(), Value::Range(..)) {
+        let sliced = slice(interp, chars, &idx, loc)?;
+        return Ok(Value::str(
+            sliced.iter().map(|c| c.to_string()).collect::<String>(),
+        ));
+    }
+    let idx = index(interp, chars.len(), &idx, loc)?;
+    Ok(chars[idx].clone())
+}
+
+fn str_upper(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [s] = str_args(interp, "upper", args, loc)?;
+    Ok(Value::str(s.to_uppercase()))
+}
+
+fn str_lower(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [s] = str_args(interp, "lower", args, loc)?;
+    Ok(Value::str(s.to_lowercase()))
+}
+
+fn str_capitalize(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [s] = str_args(interp, "capitalize", args, loc)?;
+    let mut chars = s.chars();
+    let capitalized = match chars.next() {
+        Some(c) => c
+            .to_uppercase()
+            .chain(chars.as_str().to_lowercase().chars())
+            .collect(),
+        None => String::new(),
+    };
+    Ok(Value::str(capitalized))
+}
+
+fn str_strip(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    if args.len() == 2 {
+        let [s, chars] = str_args(interp, "strip", args, loc)?;
+        return Ok(Value::str(
+            s.trim_matches(|c| chars.contains(c)).to_string(),
+        ));
+    }
+    let [s] = str_args(interp, "strip", args, loc)?;
+    Ok(Value::str(s.trim().to_string()))
+}
+
+fn str_split(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let mut args = args;
+    if let Some(sep) = kw_arg(&kw_args, "sep") {
+        args.push(sep);
+    }
+    let parts: Vec<Value> = if args.len() == 2 {
+        let [s, sep] = str_args(interp, "split", args, loc)?;
+        if sep.is_empty() {
+            return Err(interp.error(ErrorKind::ValueError, "empty separator", loc));
+        }
+        s.split(&sep[..])
+            .map(|s| Value::str(s.to_string()))
+            .collect()
+    } else {
+        let [s] = str_args(interp, "split", args, loc)?;
+        s.split_whitespace()
+            .map(|s| Value::str(s.to_string()))
+            .collect()
+    };
+    Ok(Value::list(parts))
+}
+
+fn str_splitlines(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [s] = str_args(interp, "splitlines", args, loc)?;
+    Ok(Value::list(
+        s.lines().map(|s| Value::str(s.to_string())).collect(),
+    ))
+}
+
+fn str_join(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [sep, iterable] = take_args(interp, "join", args, loc)?;
+    let sep = expect_str(interp, &sep, loc)?;
+    let parts = iter(interp, &iterable, loc)?
+        .iter()
+        .map(|part| expect_str(interp, part, loc).map(|s| s.to_string()))
+        .collect::<EvalResult<Vec<_>>>()?;
+    Ok(Value::str(parts.join(&sep)))
+}
+
+fn str_replace(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [s, from, to] = str_args(interp, "replace", args, loc)?;
+    Ok(Value::str(s.replace(&from[..], &to)))
+}
+
+fn str_startswith(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [s, prefix] = str_args(interp, "startswith", args, loc)?;
+    Ok(Value::from(s.starts_with(&prefix[..])))
+}
+
+fn str_endswith(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [s, suffix] = str_args(interp, "endswith", args, loc)?;
+    Ok(Value::from(s.ends_with(&suffix[..])))
+}
+
+fn str_find(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [s, sub] = str_args(interp, "find", args, loc)?;
+    let pos = s
+        .find(&sub[..])
+        .map_or(-1, |pos| s[..pos].chars().count() as i64);
+    Ok(Value::from(pos))
+}
+
+fn str_count(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [s, sub] = str_args(interp, "count", args, loc)?;
+    Ok(Value::from(s.matches(&sub[..]).count() as i64))
+}
+
+fn str_isdigit(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [s] = str_args(interp, "isdigit", args, loc)?;
+    Ok(Value::from(
+        !s.is_empty() && s.chars().all(|c| c.is_numeric()),
+    ))
+}
+
+fn str_isalpha(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [s] = str_args(interp, "isalpha", args, loc)?;
+    Ok(Value::from(
+        !s.is_empty() && s.chars().all(|c| c.is_alphabetic()),
+    ))
+}
+
+fn str_to_int(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [s] = str_args(interp, "to_int", args, loc)?;
+    match s.trim().parse::<i128>() {
+        Ok(i) => int_value(interp, i, loc),
+        Err(_) => Ok(Value::NONE),
+    }
+}
+
+/// Only positional `{}` placeholders are supported.
+fn str_format(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let mut args = args.into_iter();
+    let template = expect_str(interp, &args.next().unwrap_or(Value::NONE), loc)?;
+    let mut res = String::with_capacity(template.len());
+    let mut parts = template.split("{}");
+    res += parts.next().unwrap_or_default();
+    for part in parts {
+        let Some(arg) = args.next() else {
+            return Err(interp.error(
+                ErrorKind::IndexError,
+                "the number of arguments does not match the placeholders",
+                loc,
+            ));
+        };
+        res += &arg.to_string();
+        res += part;
+    }
+    Ok(Value::str(res))
+}
+
+fn int_times(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [n, proc] = take_args(interp, "times!", args, loc)?;
+    for _ in 0..expect_int(interp, &n, loc)? {
+        interp.call(&proc, vec![], vec![], loc)?;
+    }
+    Ok(Value::NONE)
+}
+
+fn int_succ(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [n] = take_args(interp, "succ", args, loc)?;
+    int_value(interp, expect_int(interp, &n, loc)? as i128 + 1, loc)
+}
+
+fn int_pred(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [n] = take_args(interp, "pred", args, loc)?;
+    int_value(interp, expect_int(interp, &n, loc)? as i128 - 1, loc)
+}
+
+fn int_bit_count(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [n] = take_args(interp, "bit_count", args, loc)?;
+    let n = expect_int(interp, &n, loc)?;
+    Ok(Value::from(n.unsigned_abs().count_ones() as i64))
+}
+
+fn int_bit_length(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [n] = take_args(interp, "bit_length", args, loc)?;
+    let n = expect_int(interp, &n, loc)?;
+    Ok(Value::from(
+        (u64::BITS - n.unsigned_abs().leading_zeros()) as i64,
+    ))
+}
+
+fn float_is_integer(
+    interp: &mut Interpreter,
+    args: Vec<Value>,
+    _kw_args: Vec<(Str, Value)>,
+    loc: Location,
+) -> EvalResult<Value> {
+    let [f] = take_args(interp, "is_integer", args, loc)?;
+    Ok(Value::from(f.as_float().is_some_and(|f| f.fract() == 0.0)))
+}
@@ -0,0 +1,1141 @@
+//! Dyne: an interpreter which evaluates HIR directly, without CPython.
+//!
+//! The core language (numbers, strings, collections, records, classes, closures, `match`, etc.) is supported.
+//! Python modules (including most of the standard library) cannot be imported.
+//! Unlike Python, integers are limited to the ranges `ValueObj` can represent (64-bit `Nat`s and 32-bit negative `Int`s),
+//! and the results out of the ranges are reported as `FeatureError`s.
+pub mod builtins;
+pub mod value;
+
+use std::io::Write;
+use std::mem;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use erg_common::config::ErgConfig;
+use erg_common::dict::Dict;
+use erg_common::error::{ErrorCore, ErrorKind, Location, MultiErrorDisplay, SubMessage};
+use erg_common::io::Input;
+use erg_common::traits::{ExitStatus, Locational, New, Runnable};
+use erg_common::{log, switch_lang, Str};
+
+use erg_parser::ast::ParamPattern;
+use erg_parser::token::{Token, TokenKind};
+
+use crate::artifact::IncompleteArtifact;
+use crate::build_package::PackageBuilder;
+use crate::error::{CompileError, CompileErrors};
+use crate::hir::{
+    Accessor, Args, BinOp, Block, Call, ClassDef, Def, Dict as HirDict, Expr, GuardClause, Lambda,
+    List, Params, ReDef, Set, Signature, Tuple, UnaryOp, HIR,
+};
+use crate::module::SharedCompilerResource;
+use crate::ty::value::{GenTypeObj, ValueObj};
+use crate::ty::HasType;
+
+pub use value::{Class, Closure, Env, Instance, Module, Scope, Value};
+
+pub type EvalError = CompileError;
+pub type EvalErrors = CompileErrors;
+pub type EvalResult<T> = Result<T, EvalError>;
+
+/// Same as Python's default recursion limit
+const MAX_CALL_DEPTH: usize = 1000;
+/// Deep recursion can overflow the Rust stack before reaching `MAX_CALL_DEPTH` (especially in debug builds).
+/// The interpreter is supposed to run on a thread spawned by `exec_new_thread` (the stack size is 8MB or more).
+const MAX_STACK_USAGE: usize = 4 * 1024 * 1024;
+
+#[inline(never)]
+fn stack_addr() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+#[derive(Debug)]
+pub struct Interpreter {
+    shared: SharedCompilerResource,
+    /// the file being evaluated
+    input: Input,
+    globals: Env,
+    modules: Dict<PathBuf, Value>,
+    /// the names of the subroutines being called
+    call_stack: Vec<Str>,
+    /// the stack address when the evaluation started
+    stack_base: usize,
+    /// if `Some`, `print!` writes to this buffer instead of stdout
+    captured: Option<String>,
+    exit_code: i32,
+    /// `f::return x` unwinds the stack (as an error) to the call of `f`
+    returning: Option<(Rc<Closure>, Value)>,
+}
+
+impl Interpreter {
+    pub fn new(cfg: ErgConfig, shared: SharedCompilerResource) -> Self {
+        Self {
+            input: cfg.input,
+            shared,
+            globals: Scope::env(None),
+            modules: Dict::new(),
+            call_stack: vec![],
+            stack_base: 0,
+            captured: None,
+            exit_code: 0,
+            returning: None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.globals = Scope::env(None);
+        self.modules.clear();
+        self.call_stack.clear();
+        self.exit_code = 0;
+        self.returning = None;
+    }
+
+    /// Makes `print!` write to a buffer (see `take_output`).
+    pub fn capture_output(&mut self) {
+        self.captured = Some(String::new());
+    }
+
+    pub fn take_output(&mut self) -> String {
+        self.captured.as_mut().map(mem::take).unwrap_or_default()
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        self.exit_code
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        Scope::get(&self.globals, name)
+    }
+
+    /// Evaluates the module and returns the value of the last expression.
+    pub fn eval_hir(&mut self, hir: HIR) -> EvalResult<Value> {
+        log!(info "the evaluation process has started.");
+        let globals = self.globals.clone();
+        self.stack_base = stack_addr();
+        self.returning = None;
+        self.define_module_attrs(&globals, "__main__");
+        let mut last = Value::NONE;
+        for chunk in hir.module.iter() {
+            last = self.eval_chunk(chunk, &globals)?;
+        }
+        log!(info "the evaluation process has completed.");
+        Ok(last)
+    }
+
+    pub(crate) fn write_output(&mut self, s: &str) {
+        if let Some(captured) = self.captured.as_mut() {
+            captured.push_str(s);
+        } else {
+            let mut stdout = std::io::stdout();
+            let _ = stdout.write_all(s.as_bytes());
+            let _ = stdout.flush();
+        }
+    }
+
+    pub(crate) fn set_exit_code(&mut self, code: i32) {
+        self.exit_code = code;
+    }
+
+    fn caused_by(&self) -> String {
+        self.call_stack
+            .last()
+            .map_or("<module>".to_string(), |name| name.to_string())
+    }
+
+    pub fn error(&self, kind: ErrorKind, msg: impl Into<String>, loc: Location) -> EvalError {
+        EvalError::new(
+            ErrorCore::new(
+                vec![SubMessage::only_loc(loc)],
+                msg,
+                line!() as usize,
+                kind,
+                loc,
+            ),
+            self.input.clone(),
+            self.caused_by(),
+        )
+    }
+
+    pub fn type_error(&self, msg: impl Into<String>, loc: Location) -> EvalError {
+        self.error(ErrorKind::TypeError, msg, loc)
+    }
+
+    fn feature_error(&self, feature: &str, loc: Location) -> EvalError {
+        let msg = switch_lang!(
+            "japanese" => format!("{feature}はDyneでサポートされていません"),
+            "simplified_chinese" => format!("Dyne不支持{feature}"),
+            "traditional_chinese" => format!("Dyne不支援{feature}"),
+            "english" => format!("{feature} is not supported by Dyne"),
+        );
+        self.error(ErrorKind::FeatureError, msg, loc)
+    }
+
+    pub fn eval_block(&mut self, block: &Block, env: &Env) -> EvalResult<Value> {
+        let mut last = Value::NONE;
+        for chunk in block.iter() {
+            last = self.eval_chunk(chunk, env)?;
+        }
+        Ok(last)
+    }
+
+    /// Type ascriptions at the statement level are declarations (e.g. `x: Int`), so they are not evaluated.
+    fn eval_chunk(&mut self, chunk: &Expr, env: &Env) -> EvalResult<Value> {
+        match chunk {
+            Expr::TypeAsc(_) => Ok(Value::NONE),
+            _ => self.eval_expr(chunk, env),
+        }
+    }
+
+    pub fn eval_expr(&mut self, expr: &Expr, env: &Env) -> EvalResult<Value> {
+        self.eval_expr_inner(expr, env)
+            .map_err(|err| Self::locate(err, expr.loc()))
+    }
+
+    /// The names synthesized by desugaring (e.g. `list` of `[x | x <- xs]`) have no location,
+    /// so the errors about them are reported at the nearest enclosing expression which has one.
+    fn locate(mut err: EvalError, loc: Location) -> EvalError {
+        if err.core.loc.is_unknown() && !loc.is_unknown() {
+            err.core.loc = loc;
+            for sub in err.core.sub_messages.iter_mut() {
+                if sub.loc.is_unknown() {
+                    sub.loc = loc;
+                }
+            }
+        }
+        err
+    }
+
+    fn eval_expr_inner(&mut self, expr: &Expr, env: &Env) -> EvalResult<Value> {
+        match expr {
+            Expr::Literal(lit) => Ok(Value::from(lit.value.clone())),
+            Expr::Accessor(acc) => self.eval_acc(acc, env),
+            Expr::List(list) => self.eval_list(list, env),
+            Expr::Tuple(Tuple::Normal(tuple)) => {
+                let elems = self.eval_pos_args(&tuple.elems, env)?;
+                Ok(Value::Tuple(elems.into()))
+            }
+            Expr::Set(set) => match set {
+                Set::Normal(set) => Ok(Value::set(self.eval_pos_args(&set.elems, env)?)),
+                Set::WithLength(set) => Err(self.feature_error("{x; n}", set.loc())),
+            },
+            Expr::Dict(dict) => match dict {
+                HirDict::Normal(dict) => {
+                    let mut kvs = Vec::with_capacity(dict.kvs.len());
+                    for kv in dict.kvs.iter() {
+                        let key = self.eval_expr(&kv.key, env)?;
+                        let value = self.eval_expr(&kv.value, env)?;
+                        kvs.push((key, value));
+                    }
+                    Ok(Value::dict(kvs))
+                }
+                other => Err(self.feature_error("dict comprehension", other.loc())),
+            },
+            Expr::Record(record) => {
+                let mut fields = Vec::with_capacity(record.attrs.len());
+                for attr in record.attrs.iter() {
+                    let value = self.eval_block(&attr.body.block, env)?;
+                    fields.push((attr.sig.inspect().clone(), value));
+                }
+                Ok(Value::Record(fields.into()))
+            }
+            Expr::BinOp(bin) => self.eval_bin(bin, env),
+            Expr::UnaryOp(unary) => self.eval_unary(unary, env),
+            Expr::Call(call) => self.eval_call(call, env),
+            Expr::Lambda(lambda) => Ok(self.closure("<lambda>".into(), lambda, env)),
+            Expr::Def(def) => {
+                self.eval_def(def, env)?;
+                Ok(Value::NONE)
+            }
+            Expr::ClassDef(classdef) => {
+                self.eval_classdef(classdef, env)?;
+                Ok(Value::NONE)
+            }
+            Expr::PatchDef(patchdef) => Err(self.feature_error("Patch", patchdef.loc())),
+            Expr::ReDef(redef) => {
+                self.eval_redef(redef, env)?;
+                Ok(Value::NONE)
+            }
+            Expr::TypeAsc(tasc) => self.eval_expr(&tasc.expr, env),
+            Expr::Code(block) | Expr::Compound(block) => self.eval_block(block, env),
+            // Python modules are not linked
+            Expr::Import(_) => Ok(Value::NONE),
+            Expr::Dummy(dummy) => {
+                for chunk in dummy.iter() {
+                    self.eval_expr(chunk, env)?;
+                }
+                Ok(Value::NONE)
+            }
+        }
+    }
+
+    fn eval_acc(&mut self, acc: &Accessor, env: &Env) -> EvalResult<Value> {
+        match acc {
+            Accessor::Ident(ident) => {
+                let name = ident.inspect();
+                Scope::get(env, name)
+                    .or_else(|| builtins::builtin_value(name))
+                    .ok_or_else(|| {
+                        self.error(
+                            ErrorKind::NameError,
+                            format!("name '{name}' is not defined"),
+                            ident.loc(),
+                        )
+                    })
+            }
+            Accessor::Attr(attr) => {
+                let obj = self.eval_expr(&attr.obj, env)?;
+                self.get_attr(&obj, attr.ident.inspect(), attr.ident.loc())
+            }
+        }
+    }
+
+    pub fn get_attr(&mut self, obj: &Value, name: &str, loc: Location) -> EvalResult<Value> {
+        let found = match obj {
+            Value::Instance(instance) => instance.get_field(name).or_else(|| {
+                instance.class.get_attr(name).map(|attr| match &attr {
+                    Value::Closure(closure) if closure.takes_self() => {
+                        Value::Method(Rc::new((obj.clone(), attr)))
+                    }
+                    _ => attr,
+                })
+            }),
+            Value::Class(class) => class.get_attr(name).or_else(|| {
+                matches!(name, "new" | "__new__" | "__call__")
+                    .then(|| Value::Method(Rc::new((obj.clone(), builtins::constructor()))))
+            }),
+            Value::Record(fields) => fields
+                .iter()
+                .find(|(field, _)| &field[..] == name)
+                .map(|(_, value)| value.clone()),
+            Value::Module(module) => Scope::get(&module.env, name),
+            Value::Obj(ValueObj::Type(_)) => builtins::type_attr(obj, name),
+            _ => None,
+        };
+        if let Some(found) = found {
+            return Ok(found);
+        }
+        if let Some(method) = builtins::method(obj, name) {
+            return Ok(Value::Method(Rc::new((
+                obj.clone(),
+                Value::Builtin(method),
+            ))));
+        }
+        Err(self.error(
+            ErrorKind::AttributeError,
+            format!("'{}' object has no attribute '{name}'", obj.class_name()),
+            loc,
+        ))
+    }
+
+    fn eval_list(&mut self, list: &List, env: &Env) -> EvalResult<Value> {
+        match list {
+            List::Normal(list) => Ok(Value::list(self.eval_pos_args(&list.elems, env)?)),
+            List::WithLength(list) => {
+                let elem = self.eval_expr(&list.elem, env)?;
+                let Some(len) = list.len.as_ref() else {
+                    return Err(self.feature_error("[x; _]", list.loc()));
+                };
+                let len = self.eval_expr(len, env)?;
+                let Some(len) = len.as_int().and_then(|len| usize::try_from(len).ok()) else {
+                    return Err(self.type_error("the length must be a Nat", list.loc()));
+                };
+                Ok(Value::list(vec![elem; len]))
+            }
+            other => Err(self.feature_error("list comprehension", other.loc())),
+        }
+    }
+
+    fn eval_pos_args(&mut self, args: &Args, env: &Env) -> EvalResult<Vec<Value>> {
+        let (pos_args, _) = self.eval_args(args, env)?;
+        Ok(pos_args)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn eval_args(&mut self, args: &Args, env: &Env) -> EvalResult<(Vec<Value>, Vec<(Str, Value)>)> {
+        let mut pos_args = Vec::with_capacity(args.pos_args.len());
+        for arg in args.pos_args.iter() {
+            pos_args.push(self.eval_expr(&arg.expr, env)?);
+        }
+        if let Some(var_args) = args.var_args.as_ref() {
+            let value = self.eval_expr(&var_args.expr, env)?;
+            let Some(elems) = value.iter_values() else {
+                return Err(self.type_error(
+                    format!("'{}' object is not iterable", value.class_name()),
+                    var_args.expr.loc(),
+                ));
+            };
+            pos_args.extend(elems);
+        }
+        let mut kw_args = Vec::with_capacity(args.kw_args.len());
+        for arg in args.kw_args.iter() {
+            let value = self.eval_expr(&arg.expr, env)?;
+            kw_args.push((arg.keyword.content.clone(), value));
+        }
+        if let Some(kw_var) = args.kw_var.as_ref() {
+            let value = self.eval_expr(&kw_var.expr, env)?;
+            let Value::Dict(kvs) = value.deref() else {
+                return Err(self.type_error("the keyword arguments must be a Dict", kw_var.loc()));
+            };
+            for (key, value) in kvs.borrow().iter() {
+                let Some(key) = key.as_str() else {
+                    return Err(self.type_error("keywords must be strings", kw_var.loc()));
+                };
+                kw_args.push((key, value.clone()));
+            }
+        }
+        Ok((pos_args, kw_args))
+    }
+
+    fn closure(&self, name: Str, lambda: &Lambda, env: &Env) -> Value {
+        Value::Closure(Rc::new(Closure {
+            name,
+            params: lambda.params.clone(),
+            body: lambda.body.clone(),
+            env: env.clone(),
+            input: self.input.clone(),
+        }))
+    }
+
+    fn eval_def(&mut self, def: &Def, env: &Env) -> EvalResult<()> {
+        match &def.sig {
+            Signature::Var(var) => {
+                let value = self.eval_block(&def.body.block, env)?;
+                let name = var.ident.inspect();
+                if &name[..] != "_" {
+                    Scope::define(env, name.clone(), value);
+                }
+            }
+            Signature::Subr(subr) => {
+                let closure = Value::Closure(Rc::new(Closure {
+                    name: subr.ident.inspect().clone(),
+                    params: subr.params.clone(),
+                    body: def.body.block.clone(),
+                    env: env.clone(),
+                    input: self.input.clone(),
+                }));
+                Scope::define(env, subr.ident.inspect().clone(), closure);
+            }
+            // {*} = import "foo"
+            Signature::Glob(glob) => {
+                let value = self.eval_block(&def.body.block, env)?;
+                let Value::Module(module) = value else {
+                    return Err(self.type_error("only modules can be glob-imported", glob.loc()));
+                };
+                for (name, value) in Scope::vars(&module.env) {
+                    Scope::define(env, name, value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn eval_classdef(&mut self, classdef: &ClassDef, env: &Env) -> EvalResult<()> {
+        let base = match (classdef.obj.as_ref(), classdef.require_or_sup.as_deref()) {
+            (GenTypeObj::Subclass(_), Some(sup)) => match self.eval_expr(sup, env)? {
+                Value::Class(base) => Some(base),
+                // builtin classes cannot be inherited
+                _ => return Err(self.feature_error("inheriting builtin classes", sup.loc())),
+            },
+            _ => None,
+        };
+        let class = Rc::new(Class {
+            name: classdef.sig.inspect().clone(),
+            base,
+            attrs: Default::default(),
+        });
+        Scope::define(env, class.name.clone(), Value::Class(class.clone()));
+        let class_env = Scope::env(Some(env.clone()));
+        Scope::define(&class_env, "Self".into(), Value::Class(class.clone()));
+        for methods in classdef.methods_list.iter() {
+            for chunk in methods.defs.iter() {
+                let Expr::Def(def) = chunk else {
+                    self.eval_chunk(chunk, &class_env)?;
+                    continue;
+                };
+                let value = match &def.sig {
+                    Signature::Subr(subr) => Value::Closure(Rc::new(Closure {
+                        name: format!("{}.{}", class.name, subr.ident.inspect()).into(),
+                        params: subr.params.clone(),
+                        body: def.body.block.clone(),
+                        env: class_env.clone(),
+                        input: self.input.clone(),
+                    })),
+                    _ => self.eval_block(&def.body.block, &class_env)?,
+                };
+                class
+                    .attrs
+                    .borrow_mut()
+                    .insert(def.sig.inspect().clone(), value);
+            }
+        }
+        Ok(())
+    }
+
+    fn eval_redef(&mut self, redef: &ReDef, env: &Env) -> EvalResult<()> {
+        let value = self.eval_block(&redef.block, env)?;
+        match &redef.attr {
+            Accessor::Ident(ident) => {
+                if !Scope::assign(env, ident.inspect(), value) {
+                    return Err(self.error(
+                        ErrorKind::NameError,
+                        format!("name '{}' is not defined", ident.inspect()),
+                        ident.loc(),
+                    ));
+                }
+            }
+            Accessor::Attr(attr) => {
+                let name = attr.ident.inspect().clone();
+                match self.eval_expr(&attr.obj, env)? {
+                    Value::Instance(instance) => instance.set_field(name, value),
+                    Value::Class(class) => {
+                        class.attrs.borrow_mut().insert(name, value);
+                    }
+                    Value::Module(module) => Scope::define(&module.env, name, value),
+                    other => {
+                        return Err(self.error(
+                            ErrorKind::AttributeError,
+                            format!(
+                                "cannot set attribute '{name}' of '{}' object",
+                                other.class_name()
+                            ),
+                            attr.ident.loc(),
+                        ))
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn eval_call(&mut self, call: &Call, env: &Env) -> EvalResult<Value> {
+        if let Some(attr_name) = call.attr_name.as_ref() {
+            let obj = self.eval_expr(&call.obj, env)?;
+            if &attr_name.inspect()[..] == "return" {
+                // `f::return x`, `self.method::return x`
+                let subr = match &obj {
+                    Value::Method(method) => &method.1,
+                    other => other,
+                };
+                if let Value::Closure(closure) = subr {
+                    return Err(self.early_return(closure.clone(), call, env));
+                }
+            }
+            let method = self.get_attr(&obj, attr_name.inspect(), attr_name.loc())?;
+            let (args, kw_args) = self.eval_args(&call.args, env)?;
+            return self.call(&method, args, kw_args, call.loc());
+        }
+        if let Expr::Accessor(Accessor::Ident(ident)) = call.obj.as_ref() {
+            if Scope::get(env, ident.inspect()).is_none() {
+                if let Some(res) = self.eval_special_call(ident.inspect(), call, env) {
+                    return res;
+                }
+            }
+        }
+        let obj = self.eval_expr(&call.obj, env)?;
+        let (args, kw_args) = self.eval_args(&call.args, env)?;
+        self.call(&obj, args, kw_args, call.loc())
+    }
+
+    /// `f::return x`
+    fn early_return(&mut self, closure: Rc<Closure>, call: &Call, env: &Env) -> EvalError {
+        let value = match self.eval_pos_args(&call.args, env) {
+            Ok(args) => args.into_iter().next().unwrap_or(Value::NONE),
+            Err(err) => return err,
+        };
+        // this error is caught by `call_closure` unless `f` has already returned
+        let err = self.error(
+            ErrorKind::RuntimeError,
+            format!("{0}::return is called outside of {0}", closure.name),
+            call.loc(),
+        );
+        self.returning = Some((closure, value));
+        err
+    }
+
+    /// Control flows and imports. Returns `None` if `name` is not a special form.
+    fn eval_special_call(
+        &mut self,
+        name: &str,
+        call: &Call,
+        env: &Env,
+    ) -> Option<EvalResult<Value>> {
+        let res = match name {
+            "if" | "if!" => self.eval_if(&call.args, env),
+            "for" | "for!" => self.eval_for(call, env),
+            "while" | "while!" => self.eval_while(&call.args, env),
+            "match" | "match!" => self.eval_match(call, env),
+            "assert" => self.eval_assert(call, env),
+            "import" => self.eval_import(call),
+            "pyimport" | "py" | "__import__" | "rsimport" => {
+                let mod_name = call
+                    .args
+                    .get_left_or_key("Path")
+                    .and_then(|arg| match arg {
+                        Expr::Literal(lit) => Some(Value::from(lit.value.clone()).to_string()),
+                        _ => None,
+                    })
+                    .unwrap_or_default();
+                Err(self.py_module_error(&mod_name, call.loc()))
+            }
+            _ => return None,
+        };
+        Some(res)
+    }
+
+    fn call_thunk(&mut self, thunk: &Expr, env: &Env) -> EvalResult<Value> {
+        let thunk = self.eval_expr(thunk, env)?;
+        self.call(&thunk, vec![], vec![], Location::Unknown)
+    }
+
+    fn eval_if(&mut self, args: &Args, env: &Env) -> EvalResult<Value> {
+        let (Some(cond), Some(then)) = (args.get_left_or_key("cond"), args.nth_or_key(1, "then"))
+        else {
+            return Err(self.type_error("if requires a condition and a block", args.loc()));
+        };
+        if self.eval_expr(cond, env)?.is_truthy() {
+            self.call_thunk(then, env)
+        } else if let Some(else_) = args.nth_or_key(2, "else") {
+            self.call_thunk(else_, env)
+        } else {
+            Ok(Value::NONE)
+        }
+    }
+
+    fn eval_for(&mut self, call: &Call, env: &Env) -> EvalResult<Value> {
+        let (Some(iterable), Some(body)) = (
+            call.args.get_left_or_key("iterable"),
+            call.args.nth_or_key(1, "proc!"),
+        ) else {
+            return Err(self.type_error("for! requires an iterable and a block", call.loc()));
+        };
+        let iterable = self.eval_expr(iterable, env)?;
+        let Some(elems) = iterable.iter_values() else {
+            return Err(self.type_error(
+                format!("'{}' object is not iterable", iterable.class_name()),
+                call.loc(),
+            ));
+        };
+        let body = self.eval_expr(body, env)?;
+        for elem in elems {
+            self.call(&body, vec![elem], vec![], call.loc())?;
+        }
+        Ok(Value::NONE)
+    }
+
+    fn eval_while(&mut self, args: &Args, env: &Env) -> EvalResult<Value> {
+        let (Some(cond), Some(body)) = (args.get_left_or_key("cond!"), args.nth_or_key(1, "proc!"))
+        else {
+            return Err(self.type_error("while! requires a condition and a block", args.loc()));
+        };
+        let cond = self.eval_expr(cond, env)?;
+        let body = self.eval_expr(body, env)?;
+        while self.call(&cond, vec![], vec![], args.loc())?.is_truthy() {
+            self.call(&body, vec![], vec![], args.loc())?;
+        }
+        Ok(Value::NONE)
+    }
+
+    fn eval_match(&mut self, call: &Call, env: &Env) -> EvalResult<Value> {
+        let Some((target, arms)) = call.args.pos_args.split_first() else {
+            return Err(self.type_error("match requires a value", call.loc()));
+        };
+        let target = self.eval_expr(&target.expr, env)?;
+        for (i, arm) in arms.iter().enumerate() {
+            let Expr::Lambda(lambda) = &arm.expr else {
+                return Err(self.type_error("the arms of match must be lambdas", arm.loc()));
+            };
+            let is_last_arm = i == arms.len() - 1;
+            let scope = Scope::env(Some(env.clone()));
+            if let Some(param) = lambda.params.non_defaults.first() {
+                self.bind_param(&scope, &param.raw.pat, target.clone(), param.loc())?;
+            }
+            if self.check_guards(&lambda.params, &scope, is_last_arm)? {
+                return self.eval_block(&lambda.body, &scope);
+            }
+        }
+        Err(self.error(
+            ErrorKind::PatternError,
+            format!("no pattern matched {}", target.repr()),
+            call.loc(),
+        ))
+    }
+
+    /// Binds the guard variables and returns whether the conditions are satisfied.
+    /// The conditions of the last arm of `match` are not checked (as the code generator does).
+    fn check_guards(&mut self, params: &Params, scope: &Env, skip_cond: bool) -> EvalResult<bool> {
+        for guard in params.guards.iter() {
+            match guard {
+                GuardClause::Bind(def) => self.eval_def(def, scope)?,
+                GuardClause::Condition(cond) if !skip_cond => {
+                    if !self.eval_expr(cond, scope)?.is_truthy() {
+                        return Ok(false);
+                    }
+                }
+                GuardClause::Condition(_) => {}
+            }
+        }
+        Ok(true)
+    }
+
+    fn eval_assert(&mut self, call: &Call, env: &Env) -> EvalResult<Value> {
+        let Some(test) = call.args.get_left_or_key("test") else {
+            return Err(self.type_error("assert requires a condition", call.loc()));
+        };
+        if self.eval_expr(test, env)?.is_truthy() {
+            return Ok(Value::NONE);
+        }
+        let msg = match call.args.nth_or_key(1, "msg") {
+            Some(msg) => self.eval_expr(msg, env)?.to_string(),
+            None => String::new(),
+        };
+        Err(self.error(ErrorKind::AssertionError, msg, call.loc()))
+    }
+
+    fn py_module_error(&self, mod_name: &str, loc: Location) -> EvalError {
+        let msg = switch_lang!(
+            "japanese" => format!("Pythonモジュール{mod_name}はDyneでインポートできません"),
+            "simplified_chinese" => format!("Dyne无法导入Python模块{mod_name}"),
+            "traditional_chinese" => format!("Dyne無法導入Python模組{mod_name}"),
+            "english" => format!("the Python module {mod_name} cannot be imported by Dyne"),
+        );
+        self.error(ErrorKind::ImportError, msg, loc)
+    }
+
+    /// `__name__` and `__file__`
+    fn define_module_attrs(&self, env: &Env, name: &str) {
+        Scope::define(env, "__name__".into(), Value::str(name.to_string()));
+        let path = self.input.full_path().to_string_lossy().to_string();
+        Scope::define(env, "__file__".into(), Value::str(path));
+    }
+
+    fn eval_import(&mut self, call: &Call) -> EvalResult<Value> {
+        let mod_name = call
+            .args
+            .get_left_or_key("Path")
+            .and_then(|arg| match arg {
+                Expr::Literal(lit) => Some(Value::from(lit.value.clone()).to_string()),
+                _ => None,
+            })
+            .unwrap_or_default();
+        let Some(path) = call.ref_t().module_path() else {
+            return Err(self.py_module_error(&mod_name, call.loc()));
+        };
+        if let Some(module) = self.modules.get(&path) {
+            return Ok(module.clone());
+        }
+        let entry = if call.ref_t().is_erg_module() {
+            self.shared.mod_cache.get(path.as_path()).and_then(|entry| {
+                entry
+                    .hir
+                    .clone()
+                    .map(|hir| (hir, entry.module.context.cfg.input.clone()))
+            })
+        } else {
+            None
+        };
+        let Some((hir, input)) = entry else {
+            return Err(self.py_module_error(&mod_name, call.loc()));
+        };
+        let env = Scope::env(None);
+        let module = Value::Module(Rc::new(Module {
+            name: hir.name.clone(),
+            env: env.clone(),
+        }));
+        // register first for circular imports
+        self.modules.insert(path, module.clone());
+        let prev_input = mem::replace(&mut self.input, input);
+        self.define_module_attrs(&env, &hir.name);
+        let call_stack = mem::take(&mut self.call_stack);
+        let res = hir
+            .module
+            .iter()
+            .try_for_each(|chunk| self.eval_chunk(chunk, &env).map(|_| ()));
+        self.call_stack = call_stack;
+        self.input = prev_input;
+        res.map(|_| module)
+    }
+
+    pub fn call(
+        &mut self,
+        callee: &Value,
+        args: Vec<Value>,
+        kw_args: Vec<(Str, Value)>,
+        loc: Location,
+    ) -> EvalResult<Value> {
+        match callee {
+            Value::Closure(closure) => self.call_closure(closure, args, kw_args, loc),
+            Value::Builtin(builtin) => (builtin.func)(self, args, kw_args, loc),
+            Value::Method(method) => {
+                let (receiver, subr) = method.as_ref();
+                let mut bound = Vec::with_capacity(args.len() + 1);
+                bound.push(receiver.clone());
+                bound.extend(args);
+                self.call(subr, bound, kw_args, loc)
+            }
+            // `C { .x = 1 }` always constructs directly (only `C.new` can be overridden)
+            Value::Class(class) => builtins::construct(self, class.clone(), args, loc),
+            Value::Obj(ValueObj::Type(_)) => builtins::convert(self, callee, args, loc),
+            other => Err(self.type_error(
+                format!("'{}' object is not callable", other.class_name()),
+                loc,
+            )),
+        }
+    }
+
+    fn call_closure(
+        &mut self,
+        closure: &Rc<Closure>,
+        args: Vec<Value>,
+        mut kw_args: Vec<(Str, Value)>,
+        loc: Location,
+    ) -> EvalResult<Value> {
+        if self.call_stack.len() >= MAX_CALL_DEPTH
+            || self.stack_base.abs_diff(stack_addr()) > MAX_STACK_USAGE
+        {
+            return Err(self.error(
+                ErrorKind::RecursionError,
+                "maximum recursion depth exceeded",
+                loc,
+            ));
+        }
+        let scope = Scope::env(Some(closure.env.clone()));
+        let params = &closure.params;
+        let mut args = args.into_iter();
+        let mut take_kw = |name: Option<&Str>| {
+            let name = name?;
+            let idx = kw_args.iter().position(|(kw, _)| kw == name)?;
+            Some(kw_args.remove(idx).1)
+        };
+        for param in params.non_defaults.iter() {
+            let Some(arg) = args.next().or_else(|| take_kw(param.inspect())) else {
+                return Err(self.type_error(
+                    format!(
+                        "{}() missing a required argument: '{}'",
+                        closure.name,
+                        param.inspect().map_or("_", |name| &name[..])
+                    ),
+                    loc,
+                ));
+            };
+            self.bind_param(&scope, &param.raw.pat, arg, param.loc())?;
+        }
+        if let Some(var_params) = params.var_params.as_deref() {
+            let rest = args.by_ref().collect::<Vec<_>>();
+            self.bind_param(
+                &scope,
+                &var_params.raw.pat,
+                Value::list(rest),
+                var_params.loc(),
+            )?;
+        }
+        let mut defaults = vec![];
+        for param in params.defaults.iter() {
+            match args.next().or_else(|| take_kw(param.sig.inspect())) {
+                Some(arg) => self.bind_param(&scope, &param.sig.raw.pat, arg, param.loc())?,
+                None => defaults.push(param),
+            }
+        }
+        let rest = args.count();
+        if rest > 0 {
+            return Err(self.type_error(
+                format!(
+                    "{}() got {rest} too many positional arguments",
+                    closure.name
+                ),
+                loc,
+            ));
+        }
+        if let Some(kw_var_params) = params.kw_var_params.as_deref() {
+            let kvs = mem::take(&mut kw_args)
+                .into_iter()
+                .map(|(kw, value)| (Value::from(kw), value))
+                .collect();
+            self.bind_param(
+                &scope,
+                &kw_var_params.raw.pat,
+                Value::dict(kvs),
+                kw_var_params.loc(),
+            )?;
+        } else if let Some((kw, _)) = kw_args.first() {
+            return Err(self.type_error(
+                format!(
+                    "{}() got an unexpected keyword argument '{kw}'",
+                    closure.name
+                ),
+                loc,
+            ));
+        }
+        let prev_input = mem::replace(&mut self.input, closure.input.clone());
+        self.call_stack.push(closure.name.clone());
+        let res = (|| {
+            // default values are evaluated at the call time
+            for param in defaults {
+                let value = self.eval_expr(&param.default_val, &scope)?;
+                self.bind_param(&scope, &param.sig.raw.pat, value, param.loc())?;
+            }
+            self.check_guards(params, &scope, true)?;
+            self.eval_block(&closure.body, &scope)
+        })();
+        self.call_stack.pop();
+        self.input = prev_input;
+        match res {
+            Err(_)
+                if self
+                    .returning
+                    .as_ref()
+                    .is_some_and(|(target, _)| Rc::ptr_eq(target, closure)) =>
+            {
+                Ok(self
+                    .returning
+                    .take()
+                    .map_or(Value::NONE, |(_, value)| value))
+            }
+            res => res,
+        }
+    }
+
+    fn bind_param(
+        &self,
+        scope: &Env,
+        pat: &ParamPattern,
+        value: Value,
+        loc: Location,
+    ) -> EvalResult<()> {
+        match pat {
+            ParamPattern::VarName(name) | ParamPattern::Ref(name) | ParamPattern::RefMut(name) => {
+                Scope::define(scope, name.inspect().clone(), value);
+                Ok(())
+            }
+            ParamPattern::Discard(_) => Ok(()),
+            _ => Err(self.feature_error("this parameter pattern", loc)),
+        }
+    }
+
+    fn eval_unary(&mut self, unary: &UnaryOp, env: &Env) -> EvalResult<Value> {
+        let value = self.eval_expr(&unary.expr, env)?;
+        let loc = unary.loc();
+        match unary.op.kind {
+            TokenKind::Mutate => Ok(match value {
+                Value::Obj(_) => Value::Mut(Rc::new(value.into())),
+                Value::List(elems) => Value::list(elems.borrow().clone()),
+                Value::Set(elems) => Value::set(elems.borrow().clone()),
+                Value::Dict(kvs) => Value::dict(kvs.borrow().clone()),
+                other => other,
+            }),
+            TokenKind::PrePlus => match value.as_obj() {
+                Some(ValueObj::Int(_) | ValueObj::Nat(_) | ValueObj::Float(_)) => Ok(value.deref()),
+                _ => Err(self.unsupported_unary("+", &value, loc)),
+            },
+            TokenKind::PreMinus => match value.as_obj() {
+                Some(ValueObj::Float(f)) => Ok(Value::from(-*f)),
+                _ => match value.as_int() {
+                    Some(i) => builtins::int_value(self, -(i as i128), loc),
+                    None => Err(self.unsupported_unary("-", &value, loc)),
+                },
+            },
+            TokenKind::PreBitNot => match value.as_int() {
+                Some(i) => builtins::int_value(self, !(i as i128), loc),
+                None => Err(self.unsupported_unary("~", &value, loc)),
+            },
+            TokenKind::RefOp | TokenKind::RefMutOp => Ok(value),
+            _ => Err(self.feature_error(&format!("the operator {}", unary.op.content), loc)),
+        }
+    }
+
+    fn unsupported_unary(&self, op: &str, value: &Value, loc: Location) -> EvalError {
+        self.type_error(
+            format!("bad operand type for unary {op}: '{}'", value.class_name()),
+            loc,
+        )
+    }
+
+    fn eval_bin(&mut self, bin: &BinOp, env: &Env) -> EvalResult<Value> {
+        let loc = bin.loc();
+        let lhs = self.eval_expr(&bin.lhs, env)?;
+        match bin.op.kind {
+            TokenKind::OrOp if bin.lhs.ref_t().is_type() => {
+                let rhs = self.eval_expr(&bin.rhs, env)?;
+                return Ok(Value::union(lhs, rhs));
+            }
+            TokenKind::AndOp if !lhs.is_truthy() => return Ok(lhs),
+            TokenKind::OrOp if lhs.is_truthy() => return Ok(lhs),
+            TokenKind::AndOp | TokenKind::OrOp => return self.eval_expr(&bin.rhs, env),
+            _ => {}
+        }
+        let rhs = self.eval_expr(&bin.rhs, env)?;
+        self.binary_op(&bin.op, lhs, rhs, loc)
+    }
+
+    /// Binary operators. The operators of the instances of user-defined classes are dispatched to the dunder methods (e.g. `__add__`).
+    pub fn binary_op(
+        &mut self,
+        op: &Token,
+        lhs: Value,
+        rhs: Value,
+        loc: Location,
+    ) -> EvalResult<Value> {
+        if let Value::Instance(instance) = &lhs {
+            if let Some(dunder) = builtins::dunder_name(op.kind) {
+                if let Some(method) = instance.class.get_attr(dunder) {
+                    return self.call(&method, vec![lhs, rhs], vec![], loc);
+                }
+                // `x != y` is `not x.__eq__(y)` if `__ne__` is not defined
+                if op.kind == TokenKind::NotEq {
+                    if let Some(method) = instance.class.get_attr("__eq__") {
+                        let eq = self.call(&method, vec![lhs, rhs], vec![], loc)?;
+                        return Ok(Value::from(!eq.is_truthy()));
+                    }
+                }
+            }
+        }
+        match op.kind {
+            TokenKind::DblEq => Ok(Value::from(lhs == rhs)),
+            TokenKind::NotEq => Ok(Value::from(lhs != rhs)),
+            TokenKind::IsOp => Ok(Value::from(builtins::is_same(&lhs, &rhs))),
+            TokenKind::IsNotOp => Ok(Value::from(!builtins::is_same(&lhs, &rhs))),
+            TokenKind::InOp => builtins::contains(self, &rhs, &lhs, loc).map(Value::from),
+            TokenKind::NotInOp => {
+                builtins::contains(self, &rhs, &lhs, loc).map(|b| Value::from(!b))
+            }
+            TokenKind::ContainsOp => builtins::contains(self, &lhs, &rhs, loc).map(Value::from),
+            TokenKind::Closed | TokenKind::RightOpen | TokenKind::LeftOpen | TokenKind::Open => {
+                let (Some(start), Some(end)) = (lhs.as_int(), rhs.as_int()) else {
+                    return Err(self.feature_error("ranges of non-integers", loc));
+                };
+                let (start, end) = match op.kind {
+                    TokenKind::Closed => (start, end + 1),
+                    TokenKind::RightOpen => (start, end),
+                    TokenKind::LeftOpen => (start + 1, end + 1),
+                    _ => (start + 1, end),
+                };
+                Ok(Value::Range(start, end, 1))
+            }
+            _ => builtins::binary(self, op, lhs, rhs, loc),
+        }
+    }
+}
+
+/// Runs Erg programs with Dyne.
+#[derive(Debug)]
+pub struct Dyne {
+    pub cfg: ErgConfig,
+    builder: PackageBuilder,
+    shared: SharedCompilerResource,
+    interpreter: Interpreter,
+}
+
+impl Default for Dyne {
+    fn default() -> Self {
+        Self::new(ErgConfig::default())
+    }
+}
+
+impl New for Dyne {
+    fn new(cfg: ErgConfig) -> Self {
+        let shared = SharedCompilerResource::new(cfg.copy());
+        Self {
+            builder: PackageBuilder::new_with_cache(cfg.copy(), "<module>".into(), shared.clone()),
+            interpreter: Interpreter::new(cfg.copy(), shared.clone()),
+            shared,
+            cfg,
+        }
+    }
+}
+
+impl Runnable for Dyne {
+    type Err = EvalError;
+    type Errs = EvalErrors;
+    const NAME: &'static str = "Erg interpreter (Dyne)";
+
+    #[inline]
+    fn cfg(&self) -> &ErgConfig {
+        &self.cfg
+    }
+    #[inline]
+    fn cfg_mut(&mut self) -> &mut ErgConfig {
+        &mut self.cfg
+    }
+
+    #[inline]
+    fn finish(&mut self) {}
+
+    fn initialize(&mut self) {
+        self.builder.initialize();
+        self.interpreter.clear();
+    }
+
+    fn clear(&mut self) {
+        self.builder.clear();
+        self.interpreter.clear();
+    }
+
+    fn exec(&mut self) -> Result<ExitStatus, Self::Errs> {
+        let src = self.cfg.input.read();
+        let (_, warns) = self.run_src(src, "exec").or_else(|errs| {
+            if errs
+                .iter()
+                .any(|err| err.core.kind == ErrorKind::SystemExit)
+            {
+                Ok((Value::NONE, EvalErrors::empty()))
+            } else {
+                Err(errs)
+            }
+        })?;
+        Ok(ExitStatus::new(
+            self.interpreter.exit_code(),
+            warns.len(),
+            0,
+        ))
+    }
+
+    fn eval(&mut self, src: String) -> Result<String, EvalErrors> {
+        let (value, warns) = self.run_src(src, "eval")?;
+        let mut res = warns.to_string();
+        if !value.is_none() {
+            res += &value.repr();
+        }
+        Ok(res)
+    }
+}
+
+impl Dyne {
+    pub fn new(cfg: ErgConfig) -> Self {
+        New::new(cfg)
+    }
+
+    /// Execute the script specified in the configuration.
+    pub fn exec(&mut self) -> Result<ExitStatus, EvalErrors> {
+        Runnable::exec(self)
+    }
+
+    /// Evaluates code passed as a string.
+    pub fn eval(&mut self, src: String) -> Result<String, EvalErrors> {
+        Runnable::eval(self, src)
+    }
+
+    pub fn interpreter(&mut self) -> &mut Interpreter {
+        &mut self.interpreter
+    }
+
+    pub fn shared(&self) -> &SharedCompilerResource {
+        &self.shared
+    }
+
+    /// Type-checks and evaluates `src`. Returns the value of the last expression and the warnings.
+    pub fn run_src(&mut self, src: String, mode: &str) -> Result<(Value, EvalErrors), EvalErrors> {
+        let artifact = self.builder.build(src, mode).map_err(
+            |IncompleteArtifact { errors, warns, .. }| {
+                warns.write_all_stderr();
+                errors
+            },
+        )?;
+        artifact.warns.write_all_stderr();
+        let value = self
+            .interpreter
+            .eval_hir(artifact.object)
+            .map_err(EvalErrors::from)?;
+        Ok((value, artifact.warns))
+    }
+}
@@ -0,0 +1,563 @@
+//! Runtime values of Dyne.
+//!
+//! Immutable scalars are `ValueObj`s, the other values have reference semantics like Python objects.
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use erg_common::dict::Dict;
+use erg_common::io::Input;
+use erg_common::Str;
+
+use crate::hir::{Block, Params};
+use crate::ty::value::{TypeObj, ValueObj};
+use crate::ty::Type;
+
+use super::builtins::BuiltinFn;
+
+pub type Env = Rc<RefCell<Scope>>;
+
+#[derive(Debug, Default)]
+pub struct Scope {
+    vars: Dict<Str, Value>,
+    parent: Option<Env>,
+}
+
+impl Scope {
+    pub fn env(parent: Option<Env>) -> Env {
+        Rc::new(RefCell::new(Self {
+            vars: Dict::new(),
+            parent,
+        }))
+    }
+
+    pub fn get(env: &Env, name: &str) -> Option<Value> {
+        let scope = env.borrow();
+        if let Some(value) = scope.vars.get(name) {
+            return Some(value.clone());
+        }
+        scope
+            .parent
+            .as_ref()
+            .and_then(|parent| Self::get(parent, name))
+    }
+
+    pub fn define(env: &Env, name: Str, value: Value) {
+        env.borrow_mut().vars.insert(name, value);
+    }
+
+    /// Rebinds the variable in the scope where it is defined.
+    /// Returns `false` if the variable is not defined.
+    pub fn assign(env: &Env, name: &str, value: Value) -> bool {
+        let mut scope = env.borrow_mut();
+        if let Some(var) = scope.vars.get_mut(name) {
+            *var = value;
+            return true;
+        }
+        match scope.parent.as_ref() {
+            Some(parent) => Self::assign(parent, name, value),
+            None => false,
+        }
+    }
+
+    pub fn vars(env: &Env) -> Vec<(Str, Value)> {
+        env.borrow()
+            .vars
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+}
+
+/// A subroutine defined in Erg code. `env` is the scope where it was defined.
+#[derive(Debug)]
+pub struct Closure {
+    pub name: Str,
+    pub params: Params,
+    pub body: Block,
+    pub env: Env,
+    /// the file where the subroutine was defined
+    pub input: Input,
+}
+
+impl Closure {
+    /// Methods whose first parameter is `self` are bound to the receiver.
+    pub fn takes_self(&self) -> bool {
+        self.params
+            .non_defaults
+            .first()
+            .and_then(|param| param.inspect())
+            .is_some_and(|name| &name[..] == "self")
+    }
+}
+
+#[derive(Clone)]
+pub struct Builtin {
+    pub name: Str,
+    pub func: BuiltinFn,
+}
+
+impl fmt::Debug for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<built-in {}>", self.name)
+    }
+}
+
+#[derive(Debug)]
+pub struct Class {
+    pub name: Str,
+    pub base: Option<Rc<Class>>,
+    /// methods and class attributes
+    pub attrs: RefCell<Dict<Str, Value>>,
+}
+
+impl Class {
+    /// Searches the attribute in the class and its base classes.
+    pub fn get_attr(&self, name: &str) -> Option<Value> {
+        if let Some(value) = self.attrs.borrow().get(name) {
+            return Some(value.clone());
+        }
+        self.base.as_ref().and_then(|base| base.get_attr(name))
+    }
+
+    pub fn is_subclass_of(self: &Rc<Self>, other: &Rc<Class>) -> bool {
+        Rc::ptr_eq(self, other)
+            || self
+                .base
+                .as_ref()
+                .is_some_and(|base| base.is_subclass_of(other))
+    }
+}
+
+#[derive(Debug)]
+pub struct Instance {
+    pub class: Rc<Class>,
+    /// in the order of definition
+    pub fields: RefCell<Vec<(Str, Value)>>,
+}
+
+impl Instance {
+    pub fn get_field(&self, name: &str) -> Option<Value> {
+        self.fields
+            .borrow()
+            .iter()
+            .find(|(field, _)| &field[..] == name)
+            .map(|(_, value)| value.clone())
+    }
+
+    pub fn set_field(&self, name: Str, value: Value) {
+        let mut fields = self.fields.borrow_mut();
+        if let Some((_, field)) = fields.iter_mut().find(|(field, _)| field == &name) {
+            *field = value;
+        } else {
+            fields.push((name, value));
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Module {
+    pub name: Str,
+    pub env: Env,
+}
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    /// numbers, strings, `None`, builtin types, etc.
+    Obj(ValueObj),
+    /// a mutable scalar (e.g. `!0`)
+    Mut(Rc<RefCell<Value>>),
+    List(Rc<RefCell<Vec<Value>>>),
+    Tuple(Rc<[Value]>),
+    /// a set or a dict keeps the insertion order (as Python does)
+    Set(Rc<RefCell<Vec<Value>>>),
+    Dict(Rc<RefCell<Vec<(Value, Value)>>>),
+    Record(Rc<[(Str, Value)]>),
+    /// `start..<end` (by `step`)
+    Range(i64, i64, i64),
+    Closure(Rc<Closure>),
+    Builtin(Builtin),
+    /// a method bound to the receiver
+    Method(Rc<(Value, Value)>),
+    Class(Rc<Class>),
+    /// a union type (e.g. `Int or Str`)
+    Union(Rc<[Value]>),
+    Instance(Rc<Instance>),
+    Module(Rc<Module>),
+}
+
+impl From<ValueObj> for Value {
+    fn from(obj: ValueObj) -> Self {
+        match obj {
+            ValueObj::List(elems) => Self::list(elems.iter().cloned().map(Self::from).collect()),
+            ValueObj::Tuple(elems) => Self::Tuple(elems.iter().cloned().map(Self::from).collect()),
+            ValueObj::Set(elems) => Self::set(elems.into_iter().map(Self::from).collect()),
+            ValueObj::Dict(dict) => Self::dict(
+                dict.into_iter()
+                    .map(|(key, value)| (Self::from(key), Self::from(value)))
+                    .collect(),
+            ),
+            ValueObj::Record(fields) => Self::Record(
+                fields
+                    .into_iter()
+                    .map(|(field, value)| (field.symbol, Self::from(value)))
+                    .collect(),
+            ),
+            other => Self::Obj(other),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Self::Obj(ValueObj::Bool(b))
+    }
+}
+
+impl From<Str> for Value {
+    fn from(s: Str) -> Self {
+        Self::Obj(ValueObj::Str(s))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Self {
+        Self::Obj(ValueObj::from(f))
+    }
+}
+
+impl From<i64> for Value {
+    /// Non-negative integers are `Nat`s.
+    fn from(i: i64) -> Self {
+        if let Ok(nat) = u64::try_from(i) {
+            Self::Obj(ValueObj::Nat(nat))
+        } else {
+            Self::Obj(ValueObj::Int(i as i32))
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Mut(l), r) => &*l.borrow() == r,
+            (l, Self::Mut(r)) => l == &*r.borrow(),
+            (Self::Obj(l), Self::Obj(r)) => match l.clone().try_eq(r.clone()) {
+                Some(ValueObj::Bool(b)) => b,
+                _ => l == r,
+            },
+            (Self::List(l), Self::List(r)) => Rc::ptr_eq(l, r) || *l.borrow() == *r.borrow(),
+            (Self::Tuple(l), Self::Tuple(r)) => l == r,
+            (Self::Set(l), Self::Set(r)) => {
+                let (l, r) = (l.borrow(), r.borrow());
+                l.len() == r.len() && l.iter().all(|elem| r.contains(elem))
+            }
+            (Self::Dict(l), Self::Dict(r)) => {
+                let (l, r) = (l.borrow(), r.borrow());
+                l.len() == r.len() && l.iter().all(|kv| r.contains(kv))
+            }
+            (Self::Record(l), Self::Record(r)) => {
+                l.len() == r.len() && l.iter().all(|field| r.contains(field))
+            }
+            (Self::Range(l0, l1, l2), Self::Range(r0, r1, r2)) => (l0, l1, l2) == (r0, r1, r2),
+            (Self::Closure(l), Self::Closure(r)) => Rc::ptr_eq(l, r),
+            (Self::Builtin(l), Self::Builtin(r)) => l.name == r.name,
+            (Self::Method(l), Self::Method(r)) => Rc::ptr_eq(l, r),
+            (Self::Class(l), Self::Class(r)) => Rc::ptr_eq(l, r),
+            (Self::Union(l), Self::Union(r)) => {
+                l.len() == r.len() && l.iter().all(|t| r.contains(t))
+            }
+            (Self::Instance(l), Self::Instance(r)) => Rc::ptr_eq(l, r),
+            (Self::Module(l), Self::Module(r)) => Rc::ptr_eq(l, r),
+            _ => false,
+        }
+    }
+}
+
+/// Same as `str(value)` in Python.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Obj(ValueObj::Str(s)) => write!(f, "{s}"),
+            Self::Mut(value) => write!(f, "{}", value.borrow()),
+            _ => write!(f, "{}", self.repr()),
+        }
+    }
+}
+
+impl Value {
+    pub const NONE: Self = Self::Obj(ValueObj::None);
+
+    pub fn list(elems: Vec<Value>) -> Self {
+        Self::List(Rc::new(RefCell::new(elems)))
+    }
+
+    pub fn set(elems: Vec<Value>) -> Self {
+        let mut set = Vec::with_capacity(elems.len());
+        for elem in elems {
+            if !set.contains(&elem) {
+                set.push(elem);
+            }
+        }
+        Self::Set(Rc::new(RefCell::new(set)))
+    }
+
+    /// `lhs or rhs` (flattened)
+    pub fn union(lhs: Value, rhs: Value) -> Self {
+        let mut types = vec![];
+        for t in [lhs, rhs] {
+            match t {
+                Self::Union(ts) => types.extend(ts.iter().cloned()),
+                other => types.push(other),
+            }
+        }
+        Self::Union(types.into())
+    }
+
+    pub fn is_type(&self) -> bool {
+        matches!(
+            self,
+            Self::Obj(ValueObj::Type(_)) | Self::Class(_) | Self::Union(_)
+        )
+    }
+
+    pub fn dict(kvs: Vec<(Value, Value)>) -> Self {
+        let mut dict: Vec<(Value, Value)> = Vec::with_capacity(kvs.len());
+        for (key, value) in kvs {
+            if let Some((_, v)) = dict.iter_mut().find(|(k, _)| k == &key) {
+                *v = value;
+            } else {
+                dict.push((key, value));
+            }
+        }
+        Self::Dict(Rc::new(RefCell::new(dict)))
+    }
+
+    pub fn str(s: impl Into<Str>) -> Self {
+        Self::Obj(ValueObj::Str(s.into()))
+    }
+
+    pub fn builtin_type(t: Type) -> Self {
+        Self::Obj(ValueObj::builtin_type(t))
+    }
+
+    /// The contents of a mutable scalar, or the value itself.
+    pub fn deref(&self) -> Value {
+        match self {
+            Self::Mut(value) => value.borrow().deref(),
+            _ => self.clone(),
+        }
+    }
+
+    pub fn as_obj(&self) -> Option<ValueObj> {
+        match self.deref() {
+            Self::Obj(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<Str> {
+        match self.as_obj() {
+            Some(ValueObj::Str(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self.as_obj()? {
+            ValueObj::Int(i) => Some(i as i64),
+            ValueObj::Nat(n) => i64::try_from(n).ok(),
+            ValueObj::Bool(b) => Some(b as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        match self.as_obj()? {
+            ValueObj::Float(f) => Some(*f),
+            _ => self.as_int().map(|i| i as f64),
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        matches!(self.deref(), Self::Obj(ValueObj::None))
+    }
+
+    /// Same as `bool(value)` in Python.
+    pub fn is_truthy(&self) -> bool {
+        match self.deref() {
+            Self::Obj(obj) => match obj {
+                ValueObj::Bool(b) => b,
+                ValueObj::Int(i) => i != 0,
+                ValueObj::Nat(n) => n != 0,
+                ValueObj::Float(f) => *f != 0.0,
+                ValueObj::Str(s) => !s.is_empty(),
+                ValueObj::None => false,
+                _ => true,
+            },
+            Self::List(elems) | Self::Set(elems) => !elems.borrow().is_empty(),
+            Self::Tuple(elems) => !elems.is_empty(),
+            Self::Dict(kvs) => !kvs.borrow().is_empty(),
+            Self::Range(start, end, step) => (step > 0 && start < end) || (step < 0 && start > end),
+            _ => true,
+        }
+    }
+
+    /// The name of the class of the value (used in error messages).
+    pub fn class_name(&self) -> Str {
+        match self {
+            Self::Obj(obj) => match obj {
+                ValueObj::Int(_) => "Int".into(),
+                ValueObj::Nat(_) => "Nat".into(),
+                ValueObj::Float(_) => "Float".into(),
+                ValueObj::Str(_) => "Str".into(),
+                ValueObj::Bool(_) => "Bool".into(),
+                ValueObj::None => "NoneType".into(),
+                ValueObj::Type(_) => "Type".into(),
+                _ => "Obj".into(),
+            },
+            Self::Mut(value) => format!("{}!", value.borrow().class_name()).into(),
+            Self::List(_) => "List!".into(),
+            Self::Tuple(_) => "Tuple".into(),
+            Self::Set(_) => "Set!".into(),
+            Self::Dict(_) => "Dict!".into(),
+            Self::Record(_) => "Record".into(),
+            Self::Range(..) => "Range".into(),
+            Self::Closure(_) | Self::Builtin(_) | Self::Method(_) => "Subroutine".into(),
+            Self::Class(_) => "ClassType".into(),
+            Self::Union(_) => "Type".into(),
+            Self::Instance(instance) => instance.class.name.clone(),
+            Self::Module(_) => "Module".into(),
+        }
+    }
+
+    /// Returns the elements if the value is iterable.
+    pub fn iter_values(&self) -> Option<Vec<Value>> {
+        match self.deref() {
+            Self::List(elems) | Self::Set(elems) => Some(elems.borrow().clone()),
+            Self::Tuple(elems) => Some(elems.to_vec()),
+            Self::Dict(kvs) => Some(kvs.borrow().iter().map(|(k, _)| k.clone()).collect()),
+            Self::Range(start, end, step) => {
+                let mut elems = vec![];
+                let mut i = start;
+                while (step > 0 && i < end) || (step < 0 && i > end) {
+                    elems.push(Value::from(i));
+                    i += step;
+                }
+                Some(elems)
+            }
+            Self::Obj(ValueObj::Str(s)) => {
+                Some(s.chars().map(|c| Value::str(c.to_string())).collect())
+            }
+            _ => None,
+        }
+    }
+
+    /// Same as `repr(value)` in Python.
+    pub fn repr(&self) -> String {
+        match self {
+            Self::Obj(obj) => repr_obj(obj),
+            Self::Mut(value) => value.borrow().repr(),
+            Self::List(elems) => format!("[{}]", repr_iter(elems.borrow().iter())),
+            Self::Tuple(elems) if elems.len() == 1 => format!("({},)", elems[0].repr()),
+            Self::Tuple(elems) => format!("({})", repr_iter(elems.iter())),
+            Self::Set(elems) if elems.borrow().is_empty() => "set()".into(),
+            Self::Set(elems) => format!("{{{}}}", repr_iter(elems.borrow().iter())),
+            Self::Dict(kvs) => {
+                let kvs = kvs
+                    .borrow()
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", k.repr(), v.repr()))
+                    .collect::<Vec<_>>();
+                format!("{{{}}}", kvs.join(", "))
+            }
+            Self::Record(fields) => format!("{{{}}}", repr_fields(fields.iter())),
+            Self::Range(start, end, 1) => format!("{start}..<{end}"),
+            Self::Range(start, end, step) => format!("range({start}, {end}, {step})"),
+            Self::Closure(closure) => format!("<function {}>", closure.name),
+            Self::Builtin(builtin) => format!("<built-in function {}>", builtin.name),
+            Self::Method(method) => format!("<bound method {}>", method.1.repr()),
+            Self::Class(class) => format!("<class '{}'>", class.name),
+            Self::Union(types) => types
+                .iter()
+                .map(|t| t.repr())
+                .collect::<Vec<_>>()
+                .join(" or "),
+            Self::Instance(instance) => format!(
+                "{}{{{}}}",
+                instance.class.name,
+                repr_fields(instance.fields.borrow().iter())
+            ),
+            Self::Module(module) => format!("<module '{}'>", module.name),
+        }
+    }
+}
+
+fn repr_iter<'a>(values: impl Iterator<Item = &'a Value>) -> String {
+    values.map(|v| v.repr()).collect::<Vec<_>>().join(", ")
+}
+
+fn repr_fields<'a>(fields: impl Iterator<Item = &'a (Str, Value)>) -> String {
+    fields
+        .map(|(name, value)| format!("{name} = {}", value.repr()))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn repr_obj(obj: &ValueObj) -> String {
+    match obj {
+        ValueObj::Str(s) => repr_str(s),
+        ValueObj::Float(f) => repr_float(**f),
+        ValueObj::Inf => "inf".into(),
+        ValueObj::NegInf => "-inf".into(),
+        ValueObj::Ellipsis => "Ellipsis".into(),
+        ValueObj::Type(TypeObj::Builtin { t, .. }) => format!("<class '{t}'>"),
+        other => other.to_string(),
+    }
+}
+
+/// Python-like float representation (`1.0`, `0.1`, `1e+20`, `inf`)
+pub fn repr_float(f: f64) -> String {
+    if f.is_nan() {
+        "nan".into()
+    } else if f.is_infinite() {
+        if f > 0.0 { "inf" } else { "-inf" }.into()
+    } else if f.fract() == 0.0 && f.abs() < 1e16 {
+        format!("{f:.1}")
+    } else if f.abs() >= 1e16 || f.abs() < 1e-4 {
+        let s = format!("{f:e}");
+        match s.split_once('e') {
+            Some((mantissa, exp)) if !exp.starts_with('-') => format!("{mantissa}e+{exp:0>2}"),
+            Some((mantissa, exp)) => format!("{mantissa}e-{:0>2}", &exp[1..]),
+            None => s,
+        }
+    } else {
+        format!("{f}")
+    }
+}
+
+/// `'a'`, `"it's"`
+pub fn repr_str(s: &str) -> String {
+    let quote = if s.contains('\'') && !s.contains('"') {
+        '"'
+    } else {
+        '\''
+    };
+    let mut repr = String::with_capacity(s.len() + 2);
+    repr.push(quote);
+    for c in s.chars() {
+        match c {
+            '\\' => repr += "\\\\",
+            '\n' => repr += "\\n",
+            '\r' => repr += "\\r",
+            '\t' => repr += "\\t",
+            c if c == quote => {
+                repr.push('\\');
+                repr.push(c);
+            }
+            c => repr.push(c),
+        }
+    }
+    repr.push(quote);
+    repr
+}
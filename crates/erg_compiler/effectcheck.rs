@@ -10,8 +10,8 @@ use erg_parser::token::TokenKind;
 
 use crate::context::Context;
 use crate::error::{EffectError, EffectErrors};
-use crate::hir::{Call, Def, Dict, Expr, List, Params, Set, Signature, Tuple, HIR};
-use crate::ty::{HasType, Visibility};
+use crate::hir::{Accessor, Call, Def, Dict, Expr, List, Params, Set, Signature, Tuple, HIR};
+use crate::ty::{HasType, Type, Visibility};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum BlockKind {
@@ -75,14 +75,11 @@ impl<'c> SideEffectChecker<'c> {
         if self.block_stack.len() == 1 {
             return true;
         }
-        match (
-            self.block_stack.get(self.block_stack.len() - 2).unwrap(),
-            self.block_stack.last().unwrap(),
-        ) {
-            (_, Func | ConstInstant) => false,
-            (_, Proc) => true,
-            (Proc | Module | Instant, Instant) => true,
-            _ => false,
+        // nested instant blocks (e.g. `x = { .a = ... }`) follow the enclosing block
+        match self.block_stack.iter().rev().find(|kind| **kind != Instant) {
+            Some(Proc | Module) | None => true,
+            Some(Func | ConstFunc | ConstInstant) => false,
+            Some(Instant) => unreachable!(),
         }
     }
 
@@ -406,21 +403,31 @@ impl<'c> SideEffectChecker<'c> {
             },
             Expr::Call(call) => {
                 self.constructor_destructor_check(call);
-                self.check_expr(&call.obj);
-                if (call.obj.t().is_procedure()
-                    || call
-                        .attr_name
-                        .as_ref()
-                        .map(|name| name.is_procedural())
-                        .unwrap_or(false))
-                    && !self.in_context_effects_allowed()
-                {
-                    self.errs.push(EffectError::has_effect(
+                if let Some(mut_t) = self.mutated_outer_object(call) {
+                    self.errs.push(EffectError::mutate_error(
                         self.cfg.input.clone(),
                         line!() as usize,
-                        expr,
+                        call.loc(),
+                        mut_t,
                         self.full_path(),
                     ));
+                } else {
+                    self.check_expr(&call.obj);
+                    if (call.obj.t().is_procedure()
+                        || call
+                            .attr_name
+                            .as_ref()
+                            .map(|name| name.is_procedural())
+                            .unwrap_or(false))
+                        && !self.in_context_effects_allowed()
+                    {
+                        self.errs.push(EffectError::has_effect(
+                            self.cfg.input.clone(),
+                            line!() as usize,
+                            expr,
+                            self.full_path(),
+                        ));
+                    }
                 }
                 call.args
                     .pos_args
@@ -490,6 +497,34 @@ impl<'c> SideEffectChecker<'c> {
         }
     }
 
+    /// Returns the type of the receiver if `call` mutates an argument or a captured object in a function.
+    /// e.g. `f x: List!(Int) = x.push! 1`
+    fn mutated_outer_object<'a>(&self, call: &'a Call) -> Option<&'a Type> {
+        if self.in_context_effects_allowed()
+            || !call
+                .attr_name
+                .as_ref()
+                .is_some_and(|name| name.is_procedural())
+        {
+            return None;
+        }
+        let mut_t = call.obj.ref_t();
+        if !mut_t.is_mut_type() && !mut_t.is_refmut() {
+            return None;
+        }
+        let root = match call.obj.as_ref() {
+            Expr::Accessor(Accessor::Ident(_)) => call.obj.as_ref(),
+            Expr::Accessor(acc) => acc.root_obj()?,
+            _ => return None,
+        };
+        let vi = root.var_info()?;
+        if vi.is_parameter() || vi.def_namespace() != &self.full_path() {
+            Some(mut_t)
+        } else {
+            None
+        }
+    }
+
     fn constructor_destructor_check(&mut self, call: &Call) {
         let Some(gen_t) = call.signature_t().and_then(|sig| sig.return_t()) else {
             return;
@@ -537,7 +572,8 @@ impl<'c> SideEffectChecker<'c> {
                         .any(|kwarg| Self::is_impure(&kwarg.expr))
             }
             Expr::BinOp(bin) => Self::is_impure(&bin.lhs) || Self::is_impure(&bin.rhs),
-            Expr::UnaryOp(unary) => Self::is_impure(&unary.expr),
+            // `x?` may exit the current subroutine
+            Expr::UnaryOp(unary) => unary.op.is(TokenKind::Try) || Self::is_impure(&unary.expr),
             Expr::List(lis) => match lis {
                 List::Normal(lis) => lis
                     .elems
@@ -29,6 +29,32 @@ impl EvalError {
         )
     }
 
+    pub fn not_representable_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        value: &str,
+        format: &str,
+        caused_by: String,
+    ) -> Self {
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::only_loc(loc)],
+                switch_lang!(
+                    "japanese" => format!("{value}は{format}で表現できません"),
+                    "simplified_chinese" => format!("{value}无法用{format}表示"),
+                    "traditional_chinese" => format!("{value}無法用{format}表示"),
+                    "english" => format!("{value} cannot be represented in {format}"),
+                ),
+                errno,
+                TypeError,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
+
     pub fn invalid_literal(input: Input, errno: usize, loc: Location, caused_by: String) -> Self {
         Self::new(
             ErrorCore::new(
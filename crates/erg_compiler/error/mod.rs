@@ -18,7 +18,7 @@ pub use crate::error::eval::*;
 pub use crate::error::lower::*;
 pub use crate::error::tycheck::*;
 use crate::hir::Expr;
-use crate::ty::HasType;
+use crate::ty::{HasType, Type};
 
 pub(crate) fn concat_result(l: CompileResult<()>, r: CompileResult<()>) -> CompileResult<()> {
     match (l, r) {
@@ -370,6 +370,68 @@ caused from: {fn_name}"),
         )
     }
 
+    pub fn uncompilable_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        what: &str,
+        target: &str,
+        caused_by: String,
+    ) -> Self {
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::only_loc(loc)],
+                switch_lang!(
+                    "japanese" => format!("{what}は{target}にコンパイルできません"),
+                    "simplified_chinese" => format!("{what}无法编译为{target}"),
+                    "traditional_chinese" => format!("{what}無法編譯為{target}"),
+                    "english" => format!("{what} cannot be compiled to {target}"),
+                ),
+                errno,
+                FeatureError,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
+
+    pub fn py_ffi_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        name: &str,
+        target: &str,
+        caused_by: String,
+    ) -> Self {
+        let name = StyledStr::new(name, Some(ERR), Some(ATTR));
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(
+                    loc,
+                    vec![],
+                    Some(switch_lang!(
+                        "japanese" => "Python側のAPIはcodegenターゲットがPythonの場合のみ使えます",
+                        "simplified_chinese" => "Python的API只能在代码生成目标为Python时使用",
+                        "traditional_chinese" => "Python的API只能在代碼生成目標為Python時使用",
+                        "english" => "Python APIs can only be used when the codegen target is Python",
+                    ).into()),
+                )],
+                switch_lang!(
+                    "japanese" => format!("{name}はPythonに依存しているため、{target}では使えません"),
+                    "simplified_chinese" => format!("{name}依赖于Python，因此不能在{target}中使用"),
+                    "traditional_chinese" => format!("{name}依賴於Python，因此不能在{target}中使用"),
+                    "english" => format!("{name} depends on Python, so it cannot be used in {target}"),
+                ),
+                errno,
+                FFIError,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
+
     pub fn system_exit() -> Self {
         Self::new(
             ErrorCore::new(
@@ -528,6 +590,39 @@ impl EffectError {
             caused_by,
         )
     }
+
+    pub fn mutate_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        mut_t: &Type,
+        caused_by: String,
+    ) -> Self {
+        let hint = switch_lang!(
+            "japanese" => "関数ではなくプロシージャとして定義してください",
+            "simplified_chinese" => "请将其定义为过程而不是函数",
+            "traditional_chinese" => "請將其定義為過程而不是函數",
+            "english" => "define it as a procedure instead of a function",
+        )
+        .to_string();
+        let found = StyledString::new(format!("{mut_t}"), Some(ERR), Some(ATTR));
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, vec![], Some(hint))],
+                switch_lang!(
+                    "japanese" => format!("関数中で外部の可変オブジェクト(: {found})を変更することは出来ません"),
+                    "simplified_chinese" => format!("函数中不能修改外部的可变对象(: {found})"),
+                    "traditional_chinese" => format!("函數中不能修改外部的可變對象(: {found})"),
+                    "english" => format!("cannot mutate an outer mutable object (: {found}) in a function"),
+                ),
+                errno,
+                MutationError,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
 }
 
 pub type OwnershipError = CompileError;
@@ -572,6 +667,62 @@ impl OwnershipError {
             caused_by,
         )
     }
+
+    pub fn cycle_error(
+        input: Input,
+        errno: usize,
+        name: &str,
+        name_loc: Location,
+        referrer_loc: Location,
+        caused_by: String,
+    ) -> Self {
+        let found = StyledString::new(name, Some(ERR), Some(ATTR));
+        let fst = SubMessage::ambiguous_new(
+            name_loc,
+            vec![switch_lang!(
+                "japanese" => "ここで循環参照が作られます",
+                "simplified_chinese" => "此处形成了循环引用",
+                "traditional_chinese" => "此處形成了循環引用",
+                "english" => "the cycle is closed here",
+            )
+            .to_string()],
+            None,
+        );
+        let hint = switch_lang!(
+            "japanese" => "`weakref`モジュールを使って循環を断ち切ることができます",
+            "simplified_chinese" => "可以使用`weakref`模块打破循环",
+            "traditional_chinese" => "可以使用`weakref`模塊打破循環",
+            "english" => "use the `weakref` module to break the cycle",
+        )
+        .to_string();
+        let snd = SubMessage::ambiguous_new(
+            referrer_loc,
+            vec![switch_lang!(
+                "japanese" => "ここで参照されています",
+                "simplified_chinese" => "在此处被引用",
+                "traditional_chinese" => "在此處被引用",
+                "english" => "referred to here",
+            )
+            .to_string()],
+            Some(hint),
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![fst, snd],
+                switch_lang!(
+                    "japanese" => format!("{found}は自身を参照するオブジェクトに格納されます"),
+                    "simplified_chinese" => format!("{found}被存储到引用自身的对象中"),
+                    "traditional_chinese" => format!("{found}被存儲到引用自身的對象中"),
+                    "english" => format!("{found} is stored in an object that refers back to it"),
+                ),
+                errno,
+                CycleError,
+                name_loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1368,6 +1368,38 @@ passed keyword args:    {kw_args_len}"
         )
     }
 
+    pub fn try_operator_error(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        t: &Type,
+    ) -> Self {
+        let t = StyledString::new(format!("{t}"), Some(ERR), Some(ATTR));
+        let hint = switch_lang!(
+            "japanese" => "`?`は`T or E`型(EはBaseExceptionのサブタイプ)の値にのみ適用できます",
+            "simplified_chinese" => "`?`只能用于`T or E`类型(E是BaseException的子类型)的值",
+            "traditional_chinese" => "`?`只能用於`T or E`類型(E是BaseException的子類型)的值",
+            "english" => "`?` can only be applied to a value of type `T or E` (E is a subtype of BaseException)",
+        );
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::ambiguous_new(loc, vec![], Some(hint.into()))],
+                switch_lang!(
+                    "japanese" => format!("{t}型の値はエラーになりえません"),
+                    "simplified_chinese" => format!("{t}类型的值不可能是错误"),
+                    "traditional_chinese" => format!("{t}類型的值不可能是錯誤"),
+                    "english" => format!("a value of type {t} cannot be an error"),
+                ),
+                errno,
+                TypeError,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
+
     pub fn self_type_error(input: Input, errno: usize, loc: Location, caused_by: String) -> Self {
         Self::new(
             ErrorCore::new(
@@ -1389,6 +1421,38 @@ passed keyword args:    {kw_args_len}"
 }
 
 impl TyCheckWarning {
+    pub fn non_exhaustive_match_warning(
+        input: Input,
+        errno: usize,
+        loc: Location,
+        caused_by: String,
+        expr_t: &Type,
+        arm_ts: Vec<Type>,
+    ) -> Self {
+        let arms = arm_ts
+            .into_iter()
+            .enumerate()
+            .fold("".to_string(), |acc, (i, t)| {
+                acc + &format!("{} arm type: {t}\n", ordinal_num(i + 1))
+            });
+        Self::new(
+            ErrorCore::new(
+                vec![SubMessage::only_loc(loc)],
+                switch_lang!(
+                    "japanese" => format!("篩型のパターンが{expr_t}型の全ての値を網羅していない可能性があります\n{arms}"),
+                    "simplified_chinese" => format!("精化类型的模式可能没有涵盖{expr_t}类型的所有值\n{arms}"),
+                    "traditional_chinese" => format!("精化類型的模式可能沒有涵蓋{expr_t}類型的所有值\n{arms}"),
+                    "english" => format!("the refinement patterns may not cover all values of type {expr_t}\n{arms}"),
+                ),
+                errno,
+                TypeWarning,
+                loc,
+            ),
+            input,
+            caused_by,
+        )
+    }
+
     pub fn unnecessary_tyvar_warning(
         input: Input,
         errno: usize,
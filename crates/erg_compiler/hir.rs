@@ -407,6 +407,17 @@ impl Args {
         }
     }
 
+    pub fn nth_or_key(&self, nth: usize, key: &str) -> Option<&Expr> {
+        if let Some(arg) = self.pos_args.get(nth) {
+            Some(&arg.expr)
+        } else {
+            self.kw_args
+                .iter()
+                .find(|arg| &arg.keyword.inspect()[..] == key)
+                .map(|arg| &arg.expr)
+        }
+    }
+
     pub fn get_mut_left_or_key(&mut self, key: &str) -> Option<&mut Expr> {
         if !self.pos_args.is_empty() {
             Some(&mut self.pos_args.get_mut(0)?.expr)
@@ -1488,7 +1499,17 @@ impl NoTypeDisplay for UnaryOp {
 }
 
 impl_display_from_nested!(UnaryOp);
-impl_locational!(UnaryOp, op, expr);
+
+impl Locational for UnaryOp {
+    fn loc(&self) -> Location {
+        // postfix operator (e.g. `x?`)
+        if self.op.is(TokenKind::Try) {
+            Location::concat(self.expr.as_ref(), &self.op)
+        } else {
+            Location::concat(&self.op, self.expr.as_ref())
+        }
+    }
+}
 
 impl UnaryOp {
     pub fn new(op: Token, expr: Expr, info: VarInfo) -> Self {
@@ -13,6 +13,7 @@ mod codegen;
 pub mod context;
 pub mod declare;
 pub mod desugar_hir;
+pub mod dyne;
 pub mod effectcheck;
 pub mod error;
 pub mod hir;
@@ -23,9 +24,12 @@ pub mod lower;
 pub mod module;
 pub mod optimize;
 pub mod ownercheck;
+pub mod peephole;
+pub mod source_map;
 pub mod transpile;
 pub mod ty;
 pub mod varinfo;
+pub mod wasm;
 
 #[allow(unused)]
 use erg_common::config::{ErgConfig, Package};
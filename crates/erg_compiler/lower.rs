@@ -33,8 +33,8 @@ use crate::artifact::{BuildRunnable, Buildable, CompleteArtifact, IncompleteArti
 use crate::build_package::CheckStatus;
 use crate::module::SharedCompilerResource;
 use crate::ty::constructors::{
-    free_var, from_str, func, guard, list_t, mono, poly, proc, refinement, set_t, singleton, ty_tp,
-    unsized_list_t, v_enum,
+    free_var, from_str, func, func1, guard, list_t, mono, poly, proc, refinement, set_t, singleton,
+    ty_tp, unsized_list_t, v_enum,
 };
 use crate::ty::free::Constraint;
 use crate::ty::typaram::TyParam;
@@ -68,6 +68,8 @@ pub struct GenericASTLowerer<ASTBuilder: ASTBuildable = DefaultASTBuilder> {
     pub(crate) errs: LowerErrors,
     pub(crate) warns: LowerWarnings,
     fresh_gen: FreshNameGenerator,
+    /// the error types propagated by `?` in the subroutine being lowered (whose return type is not specified)
+    try_err_t: Type,
     _parser: PhantomData<fn() -> ASTBuilder>,
 }
 
@@ -260,6 +262,7 @@ impl<ASTBuilder: ASTBuildable> GenericASTLowerer<ASTBuilder> {
             errs: LowerErrors::empty(),
             warns: LowerWarnings::empty(),
             fresh_gen: FreshNameGenerator::new("lower"),
+            try_err_t: Type::Never,
             _parser: PhantomData,
         }
     }
@@ -271,6 +274,7 @@ impl<ASTBuilder: ASTBuildable> GenericASTLowerer<ASTBuilder> {
             errs: LowerErrors::empty(),
             warns: LowerWarnings::empty(),
             fresh_gen: FreshNameGenerator::new("lower"),
+            try_err_t: Type::Never,
             _parser: PhantomData,
         }
     }
@@ -533,13 +537,25 @@ impl<A: ASTBuildable> GenericASTLowerer<A> {
         }
     }
 
-    fn lower_tuple(&mut self, tuple: ast::Tuple, expect: Option<&Type>) -> Failable<hir::Tuple> {
+    fn lower_tuple(
+        &mut self,
+        tuple: ast::Tuple,
+        expect: Option<&Type>,
+    ) -> FailableOption<hir::Tuple> {
         log!(info "entered {}({tuple})", fn_name!());
         match tuple {
             ast::Tuple::Normal(tup) => Ok(hir::Tuple::Normal(
                 self.lower_normal_tuple(tup, expect)
-                    .map_err(|(tup, errs)| (hir::Tuple::Normal(tup), errs))?,
+                    .map_err(|(tup, errs)| (Some(hir::Tuple::Normal(tup)), errs))?,
             )),
+            ast::Tuple::Comprehension(tup) => feature_error!(
+                LowerErrors,
+                LowerError,
+                self.module.context,
+                tup.loc(),
+                "tuple comprehension"
+            )
+            .map_err(|es| (None, es)),
         }
     }
 
@@ -1361,6 +1377,9 @@ impl<A: ASTBuildable> GenericASTLowerer<A> {
         expect: Option<&Type>,
     ) -> Failable<hir::UnaryOp> {
         log!(info "entered {}({unary})", fn_name!());
+        if unary.op.is(TokenKind::Try) {
+            return self.lower_try(unary);
+        }
         let mut errors = LowerErrors::empty();
         let mut args = unary.args.into_iter();
         let arg = self
@@ -1397,6 +1416,72 @@ impl<A: ASTBuildable> GenericASTLowerer<A> {
         }
     }
 
+    /// `x?` (x: T or E, E <: BaseException) => `x: T`
+    /// If `x` is an error, it is returned from the current subroutine.
+    fn lower_try(&mut self, unary: ast::UnaryOp) -> Failable<hir::UnaryOp> {
+        log!(info "entered {}({unary})", fn_name!());
+        let mut errors = LowerErrors::empty();
+        let (op, expr) = unary.deconstruct();
+        let expr = self.lower_expr(expr, None).unwrap_or_else(|(expr, errs)| {
+            errors.extend(errs);
+            expr.unwrap_or(hir::Expr::Dummy(hir::Dummy::new(vec![])))
+        });
+        let ctx = &self.module.context;
+        let base_exception = mono("BaseException");
+        let (err_ts, ok_ts) = expr
+            .ref_t()
+            .ors()
+            .into_iter()
+            .partition::<Vec<_>, _>(|t| ctx.subtype_of(t, &base_exception));
+        let union = |ts: Vec<Type>| ts.iter().fold(Type::Never, |acc, t| ctx.union(&acc, t));
+        let (err_t, ok_t) = (union(err_ts), union(ok_ts));
+        if err_t == Type::Never {
+            if !expr.ref_t().is_failure() {
+                errors.push(LowerError::try_operator_error(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    expr.loc(),
+                    ctx.caused_by(),
+                    expr.ref_t(),
+                ));
+            }
+        } else if let Some((name, return_t)) = ctx.current_return_t() {
+            // the return type has not been inferred yet (it will include `err_t`, see `lower_subr_block`)
+            if !return_t.has_no_unbound_var() {
+                self.try_err_t = ctx.union(&self.try_err_t, &err_t);
+            } else if ctx.sub_unify(&err_t, &return_t, &expr, None).is_err() {
+                let hint = switch_lang!(
+                    "japanese" => "`?`で返されるエラーの型は戻り値の型のサブタイプである必要があります",
+                    "simplified_chinese" => "由`?`返回的错误类型必须是返回类型的子类型",
+                    "traditional_chinese" => "由`?`返回的錯誤類型必須是返回類型的子類型",
+                    "english" => "the error type returned by `?` must be a subtype of the return type",
+                );
+                errors.push(LowerError::type_mismatch_error(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    op.loc(),
+                    ctx.caused_by(),
+                    &name,
+                    None,
+                    &return_t,
+                    &err_t,
+                    None,
+                    Some(hint.into()),
+                ));
+            }
+        }
+        let vi = VarInfo {
+            t: func1(expr.t(), ok_t),
+            ..VarInfo::default()
+        };
+        let unary = hir::UnaryOp::new(op, expr, vi);
+        if errors.is_empty() {
+            Ok(unary)
+        } else {
+            Err((unary, errors))
+        }
+    }
+
     fn lower_args(
         &mut self,
         args: ast::Args,
@@ -2087,10 +2172,20 @@ impl<A: ASTBuildable> GenericASTLowerer<A> {
                     .map_or(Type::Obj, |t| t.clone()),
             )
         });
-        let kw_var_params = params
-            .kw_var_params
-            .as_ref()
-            .map(|param| ParamTy::kw(param.name().unwrap().inspect().clone(), param.vi.t.clone()));
+        // `**kwargs: T` is registered as `kwargs: {Str: T}`
+        let kw_var_params = params.kw_var_params.as_ref().map(|param| {
+            let value_t = match param.vi.t.typarams().first() {
+                Some(TyParam::Dict(dict)) => dict
+                    .values()
+                    .next()
+                    .and_then(|tp| self.module.context.convert_tp_into_type(tp.clone()).ok()),
+                _ => None,
+            };
+            ParamTy::kw(
+                param.name().unwrap().inspect().clone(),
+                value_t.unwrap_or(Type::Obj),
+            )
+        });
         let captured_names = mem::take(&mut self.module.context.captured_names);
         if in_statement {
             // For example, `i` in `for i in ...` is a parameter,
@@ -2586,9 +2681,13 @@ impl<A: ASTBuildable> GenericASTLowerer<A> {
             .return_t
             .has_no_unbound_var()
             .then_some(registered_subr_t.return_t.as_ref());
-        match self.lower_block(body.block, return_t) {
+        let outer_try_err_t = mem::replace(&mut self.try_err_t, Type::Never);
+        let res = self.lower_block(body.block, return_t);
+        let try_err_t = mem::replace(&mut self.try_err_t, outer_try_err_t);
+        match res {
             Ok(block) => {
                 let found_body_t = self.module.context.squash_tyvar(block.t());
+                let found_body_t = self.module.context.union(&found_body_t, &try_err_t);
                 let vi = match self.module.context.outer.as_mut().unwrap().assign_subr(
                     &sig,
                     body.id,
@@ -2642,6 +2741,7 @@ impl<A: ASTBuildable> GenericASTLowerer<A> {
             Err((block, errs)) => {
                 errors.extend(errs);
                 let found_body_t = self.module.context.squash_tyvar(block.t());
+                let found_body_t = self.module.context.union(&found_body_t, &try_err_t);
                 let vi = match self.module.context.outer.as_mut().unwrap().assign_subr(
                     &sig,
                     ast::DefId(0),
@@ -3629,7 +3729,7 @@ impl<A: ASTBuildable> GenericASTLowerer<A> {
             ),
             ast::Expr::Tuple(tup) => hir::Expr::Tuple(
                 self.lower_tuple(tup, expect)
-                    .map_err(|(tuple, es)| (Some(hir::Expr::Tuple(tuple)), es))?,
+                    .map_err(|(tuple, es)| (tuple.map(hir::Expr::Tuple), es))?,
             ),
             ast::Expr::Record(rec) => hir::Expr::Record(
                 self.lower_record(rec, expect)
@@ -23,6 +23,8 @@ impl IncRefError {
 pub struct ModuleGraph {
     graph: Graph<NormalizedPathBuf, ()>,
     index: Dict<NormalizedPathBuf, usize>,
+    /// the hashes of the public interfaces (see `ModuleContext::interface_hash`)
+    interface_hashes: Dict<NormalizedPathBuf, usize>,
 }
 
 impl fmt::Display for ModuleGraph {
@@ -53,6 +55,7 @@ impl ModuleGraph {
         Self {
             graph: Graph::new(),
             index: Dict::new(),
+            interface_hashes: Dict::new(),
         }
     }
 
@@ -211,7 +214,11 @@ impl ModuleGraph {
                 .enumerate()
                 .map(|(i, path)| (path, i))
                 .collect();
-            Self { graph, index }
+            Self {
+                graph,
+                index,
+                interface_hashes: self.interface_hashes,
+            }
         })
     }
 
@@ -237,6 +244,7 @@ impl ModuleGraph {
         for node in self.graph.iter_mut() {
             node.depends_on.retain(|p| p != path);
         }
+        self.interface_hashes.remove(path);
     }
 
     /// O(N)
@@ -250,11 +258,30 @@ impl ModuleGraph {
             }
             node.depends_on.retain(|p| p != old);
         }
+        if let Some(hash) = self.interface_hashes.remove(old) {
+            self.interface_hashes.insert(new, hash);
+        }
     }
 
     pub fn initialize(&mut self) {
         self.graph.clear();
         self.index.clear();
+        self.interface_hashes.clear();
+    }
+
+    pub fn interface_hash(&self, path: &NormalizedPathBuf) -> Option<usize> {
+        self.interface_hashes.get(path).copied()
+    }
+
+    /// Records the interface hash of `path`.
+    /// Returns `true` if the interface has changed (or has not been recorded),
+    /// i.e. the modules depending on `path` need to be rechecked.
+    pub fn update_interface_hash(&mut self, path: &NormalizedPathBuf, hash: usize) -> bool {
+        self.interface_hashes.insert(path.clone(), hash) != Some(hash)
+    }
+
+    pub fn remove_interface_hash(&mut self, path: &NormalizedPathBuf) {
+        self.interface_hashes.remove(path);
     }
 
     pub fn display_parents(
@@ -371,6 +398,18 @@ impl SharedModuleGraph {
         self.0.borrow_mut().rename_path(old, new);
     }
 
+    pub fn interface_hash(&self, path: &NormalizedPathBuf) -> Option<usize> {
+        self.0.borrow().interface_hash(path)
+    }
+
+    pub fn update_interface_hash(&self, path: &NormalizedPathBuf, hash: usize) -> bool {
+        self.0.borrow_mut().update_interface_hash(path, hash)
+    }
+
+    pub fn remove_interface_hash(&self, path: &NormalizedPathBuf) {
+        self.0.borrow_mut().remove_interface_hash(path);
+    }
+
     #[allow(clippy::result_unit_err)]
     pub fn sort(&self) -> Result<(), TopoSortError> {
         self.0.borrow_mut().sort()
@@ -111,6 +111,34 @@ impl ModuleIndex {
         });
     }
 
+    /// Returns the members defined in `path` with only the referrers from the other modules.
+    /// Used to keep the references from the dependents which are not rechecked.
+    pub fn external_refs(&self, path: &NormalizedPathBuf) -> Dict<AbsLocation, ModuleIndexValue> {
+        self.members
+            .iter()
+            .filter(|(loc, _)| loc.module.as_deref() == Some(path))
+            .map(|(loc, value)| {
+                let referrers = value
+                    .referrers
+                    .iter()
+                    .filter(|ref_loc| ref_loc.module.as_deref() != Some(path))
+                    .cloned()
+                    .collect();
+                let value = ModuleIndexValue::new(value.name.clone(), value.vi.clone(), referrers);
+                (loc.clone(), value)
+            })
+            .collect()
+    }
+
+    /// Merges the referrers taken by `external_refs` into the members which still exist.
+    pub fn restore_external_refs(&mut self, refs: Dict<AbsLocation, ModuleIndexValue>) {
+        for (loc, value) in refs.into_iter() {
+            if let Some(current) = self.members.get_mut(&loc) {
+                current.referrers.extend(value.referrers);
+            }
+        }
+    }
+
     pub fn rename_path(&mut self, old: &NormalizedPathBuf, new: NormalizedPathBuf) {
         let mut new_members = Dict::new();
         for (loc, mut value) in std::mem::take(&mut self.members) {
@@ -189,4 +217,12 @@ impl SharedModuleIndex {
     pub fn rename_path(&self, old: &NormalizedPathBuf, new: NormalizedPathBuf) {
         self.0.borrow_mut().rename_path(old, new);
     }
+
+    pub fn external_refs(&self, path: &NormalizedPathBuf) -> Dict<AbsLocation, ModuleIndexValue> {
+        self.0.borrow().external_refs(path)
+    }
+
+    pub fn restore_external_refs(&self, refs: Dict<AbsLocation, ModuleIndexValue>) {
+        self.0.borrow_mut().restore_external_refs(refs);
+    }
 }
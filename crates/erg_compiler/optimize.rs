@@ -1,30 +1,459 @@
 use erg_common::config::ErgConfig;
+use erg_common::dict::Dict as HashMap;
+use erg_common::error::Location;
+use erg_common::traits::{Locational, Stream};
+use erg_common::Str;
+use erg_parser::ast::ParamPattern;
+use erg_parser::token::{Token, TokenKind};
 
+use crate::context::{Context, ModuleContext};
 use crate::effectcheck::SideEffectChecker;
 use crate::hir::*;
 use crate::module::SharedCompilerResource;
-// use crate::erg_common::traits::Stream;
+use crate::ty::const_subr::{ConstSubr, ValueArgs};
+use crate::ty::typaram::OpKind;
+use crate::ty::value::ValueObj;
+use crate::ty::HasType;
+use crate::varinfo::AbsLocation;
+
+/// Strings longer than this are not generated by constant folding (to avoid bloating the bytecode).
+const MAX_FOLDED_STR_LEN: usize = 4096;
+/// `len`, `str`, etc. are not included because their const implementations differ from Python's (e.g. `len` counts bytes).
+const FOLDABLE_BUILTIN_FUNCS: [&str; 2] = ["abs", "not"];
+/// Functions whose body has more nodes than this are not inlined.
+const MAX_INLINED_BODY_SIZE: usize = 16;
+
+/// A function that can be inlined.
+/// The body consists only of literals, operators and references to the parameters,
+/// so it has no side effects, does not recurse, and does not depend on the scope where it is called.
+#[derive(Debug)]
+struct InlinableFunc {
+    params: Vec<Str>,
+    body: Expr,
+}
+
+impl InlinableFunc {
+    fn new(def: &Def) -> Option<Self> {
+        let Signature::Subr(sig) = &def.sig else {
+            return None;
+        };
+        let params = &sig.params;
+        if !sig.decorators.is_empty()
+            || params.var_params.is_some()
+            || !params.defaults.is_empty()
+            || params.kw_var_params.is_some()
+            || params
+                .guards
+                .iter()
+                .any(|guard| matches!(guard, GuardClause::Bind(_)))
+            || def.body.block.len() != 1
+        {
+            return None;
+        }
+        let params = params
+            .non_defaults
+            .iter()
+            .map(|param| match &param.raw.pat {
+                ParamPattern::VarName(name) => Some(name.inspect().clone()),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let body = def.body.block.first()?;
+        let size = Self::body_size(&params, body)?;
+        (size <= MAX_INLINED_BODY_SIZE && SideEffectChecker::is_pure(body)).then(|| Self {
+            params,
+            body: body.clone(),
+        })
+    }
+
+    /// `None` if `expr` cannot be inlined
+    fn body_size(params: &[Str], expr: &Expr) -> Option<usize> {
+        match expr {
+            Expr::Literal(_) => Some(1),
+            Expr::Accessor(Accessor::Ident(ident)) => params.contains(ident.inspect()).then_some(1),
+            Expr::BinOp(bin) => {
+                Some(Self::body_size(params, &bin.lhs)? + Self::body_size(params, &bin.rhs)? + 1)
+            }
+            Expr::UnaryOp(unary) => Some(Self::body_size(params, &unary.expr)? + 1),
+            _ => None,
+        }
+    }
+}
 
 /// Optimizes a `HIR`.
 /// This should not be used in the context of sequential execution (e.g. REPL), since it assumes that the given code is all there is.
 /// The optimizer determines the optimization level using `opt_level` in `cfg: ErgConfig`.
 #[derive(Debug)]
-pub struct HIROptimizer {
+pub struct HIROptimizer<'c> {
     cfg: ErgConfig,
     shared: SharedCompilerResource,
+    /// the context of the module to be optimized
+    ctx: Option<&'c Context>,
 }
 
-impl HIROptimizer {
-    pub fn optimize(cfg: ErgConfig, shared: SharedCompilerResource, hir: HIR) -> HIR {
-        let mut optimizer = HIROptimizer { cfg, shared };
+impl<'c> HIROptimizer<'c> {
+    pub fn optimize(
+        cfg: ErgConfig,
+        shared: SharedCompilerResource,
+        mod_ctx: Option<&'c ModuleContext>,
+        hir: HIR,
+    ) -> HIR {
+        let mut optimizer = HIROptimizer {
+            cfg,
+            shared,
+            ctx: mod_ctx.map(|mod_ctx| &mod_ctx.context),
+        };
         if optimizer.cfg.opt_level == 0 || optimizer.cfg.input.is_repl() {
             return hir;
         }
+        let hir = if optimizer.cfg.opt_level >= 2 {
+            optimizer.inline_functions(hir)
+        } else {
+            hir
+        };
+        let hir = optimizer.fold_constants(hir);
         optimizer.eliminate_dead_code(hir)
     }
 
-    fn _fold_constants(&mut self, mut _hir: HIR) -> HIR {
-        todo!()
+    /// ```erg
+    /// X = 2
+    /// F x = x * 3
+    /// print! 1 + 2, "a" + "b", X ** 10, F(X) > 5
+    /// ```
+    /// ↓
+    /// ```erg
+    /// X = 2
+    /// F x = x * 3
+    /// print! 3, "ab", 1024, True
+    /// ```
+    fn fold_constants(&mut self, mut hir: HIR) -> HIR {
+        let Some(ctx) = self.ctx else {
+            return hir;
+        };
+        for chunk in hir.module.iter_mut() {
+            Self::walk_expr(chunk, &mut |expr| Self::fold_expr(ctx, expr));
+        }
+        hir
+    }
+
+    /// ```erg
+    /// f x = x + 1
+    /// print! f(2)
+    /// ```
+    /// ↓
+    /// ```erg
+    /// f x = x + 1
+    /// print! 2 + 1
+    /// ```
+    fn inline_functions(&mut self, mut hir: HIR) -> HIR {
+        let mut funcs = HashMap::new();
+        for chunk in hir.module.iter_mut() {
+            Self::walk_expr(chunk, &mut |expr| Self::inline_call(&funcs, expr));
+            if let Expr::Def(def) = chunk {
+                if let Some(func) = InlinableFunc::new(def) {
+                    funcs.insert(def.sig.ident().vi.def_loc.clone(), func);
+                }
+            }
+        }
+        hir
+    }
+
+    /// Only the calls whose arguments are literals or variables are inlined,
+    /// so that the number of evaluations and the evaluation order of the arguments do not matter.
+    fn inline_call(funcs: &HashMap<AbsLocation, InlinableFunc>, expr: &mut Expr) {
+        let Expr::Call(call) = expr else {
+            return;
+        };
+        let Expr::Accessor(Accessor::Ident(ident)) = call.obj.as_ref() else {
+            return;
+        };
+        let Some(func) = funcs.get(&ident.vi.def_loc) else {
+            return;
+        };
+        let args = &call.args;
+        if call.attr_name.is_some()
+            || args.var_args.is_some()
+            || !args.kw_args.is_empty()
+            || args.kw_var.is_some()
+            || args.pos_args.len() != func.params.len()
+            || args.pos_args.iter().any(|arg| {
+                !matches!(
+                    arg.expr,
+                    Expr::Literal(_) | Expr::Accessor(Accessor::Ident(_))
+                )
+            })
+        {
+            return;
+        }
+        let mut body = func.body.clone();
+        Self::walk_expr(&mut body, &mut |expr| {
+            let Expr::Accessor(Accessor::Ident(ident)) = expr else {
+                return;
+            };
+            if let Some(idx) = func.params.iter().position(|p| p == ident.inspect()) {
+                // The argument is converted to the parameter type as in the function body
+                let param_t = ident.ref_t().clone();
+                let mut arg = args.pos_args[idx].expr.clone();
+                if let Some(t) = arg.ref_mut_t() {
+                    *t = param_t;
+                }
+                *expr = arg;
+            }
+        });
+        *expr = body;
+    }
+
+    fn fold_expr(ctx: &Context, expr: &mut Expr) {
+        let lit = match expr {
+            Expr::BinOp(bin) => Self::fold_binop(ctx, bin),
+            Expr::UnaryOp(unary) => Self::fold_unaryop(ctx, unary),
+            Expr::Call(call) => Self::fold_call(ctx, call),
+            _ => None,
+        };
+        if let Some(lit) = lit {
+            *expr = Expr::Literal(lit);
+        }
+    }
+
+    /// Applies `f` to all sub-expressions of `expr` (innermost first), and then to `expr` itself.
+    fn walk_expr(expr: &mut Expr, f: &mut impl FnMut(&mut Expr)) {
+        match expr {
+            Expr::BinOp(bin) => {
+                Self::walk_expr(&mut bin.lhs, f);
+                Self::walk_expr(&mut bin.rhs, f);
+            }
+            Expr::UnaryOp(unary) => {
+                Self::walk_expr(&mut unary.expr, f);
+            }
+            Expr::Call(call) => {
+                Self::walk_expr(&mut call.obj, f);
+                Self::walk_args(&mut call.args, f);
+            }
+            Expr::Accessor(Accessor::Attr(attr)) => {
+                Self::walk_expr(&mut attr.obj, f);
+            }
+            Expr::List(List::Normal(lis)) => {
+                Self::walk_args(&mut lis.elems, f);
+            }
+            Expr::List(List::WithLength(lis)) => {
+                Self::walk_expr(&mut lis.elem, f);
+                if let Some(len) = lis.len.as_mut() {
+                    Self::walk_expr(len, f);
+                }
+            }
+            Expr::List(List::Comprehension(lis)) => {
+                Self::walk_expr(&mut lis.elem, f);
+                Self::walk_expr(&mut lis.guard, f);
+            }
+            Expr::Tuple(Tuple::Normal(tup)) => {
+                Self::walk_args(&mut tup.elems, f);
+            }
+            Expr::Set(Set::Normal(set)) => {
+                Self::walk_args(&mut set.elems, f);
+            }
+            Expr::Set(Set::WithLength(set)) => {
+                Self::walk_expr(&mut set.elem, f);
+                Self::walk_expr(&mut set.len, f);
+            }
+            Expr::Dict(Dict::Normal(dict)) => {
+                for kv in dict.kvs.iter_mut() {
+                    Self::walk_expr(&mut kv.key, f);
+                    Self::walk_expr(&mut kv.value, f);
+                }
+            }
+            Expr::Dict(Dict::Comprehension(dict)) => {
+                Self::walk_expr(&mut dict.key, f);
+                Self::walk_expr(&mut dict.value, f);
+                Self::walk_expr(&mut dict.guard, f);
+            }
+            Expr::Record(rec) => {
+                for attr in rec.attrs.iter_mut() {
+                    Self::walk_block(&mut attr.body.block, f);
+                }
+            }
+            Expr::Lambda(lambda) => {
+                Self::walk_block(&mut lambda.body, f);
+            }
+            Expr::Def(def) => {
+                Self::walk_block(&mut def.body.block, f);
+            }
+            Expr::ClassDef(class_def) => {
+                if let Some(req_sup) = class_def.require_or_sup.as_mut() {
+                    Self::walk_expr(req_sup, f);
+                }
+                for methods in class_def.methods_list.iter_mut() {
+                    Self::walk_block(&mut methods.defs, f);
+                }
+            }
+            Expr::PatchDef(patch_def) => {
+                Self::walk_block(&mut patch_def.methods, f);
+            }
+            Expr::ReDef(redef) => {
+                Self::walk_block(&mut redef.block, f);
+            }
+            Expr::TypeAsc(tasc) => {
+                Self::walk_expr(&mut tasc.expr, f);
+            }
+            Expr::Code(block) | Expr::Compound(block) => {
+                Self::walk_block(block, f);
+            }
+            Expr::Literal(_)
+            | Expr::Accessor(Accessor::Ident(_))
+            | Expr::Import(_)
+            | Expr::Dummy(_) => {}
+        }
+        f(expr);
+    }
+
+    fn walk_block(block: &mut Block, f: &mut impl FnMut(&mut Expr)) {
+        for chunk in block.iter_mut() {
+            Self::walk_expr(chunk, f);
+        }
+    }
+
+    fn walk_args(args: &mut Args, f: &mut impl FnMut(&mut Expr)) {
+        for arg in args.pos_args.iter_mut() {
+            Self::walk_expr(&mut arg.expr, f);
+        }
+        if let Some(var_args) = args.var_args.as_mut() {
+            Self::walk_expr(&mut var_args.expr, f);
+        }
+        for arg in args.kw_args.iter_mut() {
+            Self::walk_expr(&mut arg.expr, f);
+        }
+        if let Some(kw_var) = args.kw_var.as_mut() {
+            Self::walk_expr(&mut kw_var.expr, f);
+        }
+    }
+
+    fn fold_binop(ctx: &Context, bin: &BinOp) -> Option<Literal> {
+        let lhs = Self::const_value(ctx, &bin.lhs)?;
+        let rhs = Self::const_value(ctx, &bin.rhs)?;
+        let op = ctx.try_get_op_kind_from_token(&bin.op).ok()?;
+        match (op, &lhs, &rhs) {
+            // `and`/`or` of non-Bool values are not boolean operations in Python
+            (OpKind::And | OpKind::Or, ValueObj::Bool(_), ValueObj::Bool(_)) => {}
+            (OpKind::And | OpKind::Or, _, _) => return None,
+            (OpKind::Mul, ValueObj::Str(s), ValueObj::Nat(n))
+                if (s.len() as u64).saturating_mul(*n) > MAX_FOLDED_STR_LEN as u64 =>
+            {
+                return None;
+            }
+            _ => {}
+        }
+        let value = ctx.eval_bin(op, lhs, rhs).ok()?;
+        Self::literal(value, bin.loc())
+    }
+
+    fn fold_unaryop(ctx: &Context, unary: &UnaryOp) -> Option<Literal> {
+        let value = Self::const_value(ctx, &unary.expr)?;
+        let op = ctx.try_get_op_kind_from_token(&unary.op).ok()?;
+        match (op, &value) {
+            (OpKind::Neg, ValueObj::Nat(n)) if *n > i32::MAX as u64 => return None,
+            (OpKind::Neg, ValueObj::Int(i32::MIN)) => return None,
+            (OpKind::Pos | OpKind::Neg, _) => {}
+            // `~True` is `-2` in Python
+            _ => return None,
+        }
+        let value = ctx.eval_unary_val(op, value).ok()?;
+        Self::literal(value, unary.loc())
+    }
+
+    /// Folds a call of a const subroutine with const arguments.
+    /// Only user-defined const subroutines and the builtin functions whose const implementations
+    /// behave the same as Python's ones are folded.
+    fn fold_call(ctx: &Context, call: &Call) -> Option<Literal> {
+        if call.attr_name.is_some() || call.args.var_args.is_some() || call.args.kw_var.is_some() {
+            return None;
+        }
+        let Expr::Accessor(Accessor::Ident(ident)) = call.obj.as_ref() else {
+            return None;
+        };
+        let subr = match Self::get_const(ctx, ident)? {
+            ValueObj::Subr(subr @ ConstSubr::User(_)) => subr,
+            ValueObj::Subr(subr @ ConstSubr::Builtin(_))
+                if FOLDABLE_BUILTIN_FUNCS.contains(&&ident.inspect()[..]) =>
+            {
+                subr
+            }
+            _ => return None,
+        };
+        let pos_args = call
+            .args
+            .pos_args
+            .iter()
+            .map(|arg| Self::const_value(ctx, &arg.expr))
+            .collect::<Option<Vec<_>>>()?;
+        let kw_args = call
+            .args
+            .kw_args
+            .iter()
+            .map(|arg| {
+                Some((
+                    arg.keyword.inspect().clone(),
+                    Self::const_value(ctx, &arg.expr)?,
+                ))
+            })
+            .collect::<Option<HashMap<_, _>>>()?;
+        let tp = ctx
+            .call(subr.clone(), ValueArgs::new(pos_args, kw_args), call.loc())
+            .ok()?;
+        let value = ctx.convert_tp_into_value(tp).ok()?;
+        Self::literal(value, call.loc())
+    }
+
+    /// Returns the value of `expr` if it is a literal or a constant with a primitive value.
+    fn const_value(ctx: &Context, expr: &Expr) -> Option<ValueObj> {
+        let value = match expr {
+            Expr::Literal(lit) => &lit.value,
+            Expr::Accessor(Accessor::Ident(ident)) => Self::get_const(ctx, ident)?,
+            _ => return None,
+        };
+        Self::is_primitive(value).then(|| value.clone())
+    }
+
+    /// Get the value of the module-level (or builtin) constant `ident` refers to.
+    /// `None` if `ident` refers to a local variable that shadows it.
+    fn get_const<'a>(ctx: &'a Context, ident: &Identifier) -> Option<&'a ValueObj> {
+        let name = ident.inspect();
+        for ctx in [Some(ctx), ctx.get_builtins()].into_iter().flatten() {
+            if let Some(vi) = ctx.locals.get(name) {
+                if vi.def_loc != ident.vi.def_loc {
+                    return None;
+                }
+                return ctx.consts.get(name);
+            }
+        }
+        None
+    }
+
+    fn is_primitive(value: &ValueObj) -> bool {
+        match value {
+            ValueObj::Nat(_) | ValueObj::Int(_) | ValueObj::Bool(_) => true,
+            ValueObj::Float(f) => f.is_finite(),
+            ValueObj::Str(s) => s.len() <= MAX_FOLDED_STR_LEN,
+            _ => false,
+        }
+    }
+
+    fn literal(value: ValueObj, loc: Location) -> Option<Literal> {
+        // `Nat` constants are serialized as 32-bit integers
+        if !Self::is_primitive(&value) || matches!(value, ValueObj::Nat(n) if n > i32::MAX as u64) {
+            return None;
+        }
+        let (kind, content) = match &value {
+            ValueObj::Nat(n) => (TokenKind::NatLit, n.to_string()),
+            ValueObj::Int(i) => (TokenKind::IntLit, i.to_string()),
+            ValueObj::Float(f) => (TokenKind::RatioLit, format!("{:?}", **f)),
+            ValueObj::Bool(b) => (
+                TokenKind::BoolLit,
+                if *b { "True" } else { "False" }.to_string(),
+            ),
+            ValueObj::Str(s) => (TokenKind::StrLit, format!("\"{}\"", s.escape())),
+            _ => return None,
+        };
+        let token = Token::new_with_loc(kind, content, loc);
+        Some(Literal::new(value, token))
     }
 
     fn eliminate_unused_variables(&mut self, mut hir: HIR) -> HIR {
@@ -9,11 +9,13 @@ use erg_common::traits::{Locational, Stream};
 use erg_common::Str;
 use erg_common::{impl_display_from_debug, log};
 use erg_parser::ast::{ParamPattern, VarName};
+use erg_parser::token::TokenKind;
 
 use crate::ty::{HasType, Ownership, Visibility};
+use crate::varinfo::AbsLocation;
 
 use crate::error::{OwnershipError, OwnershipErrors};
-use crate::hir::{self, Accessor, Block, Def, Expr, Identifier, List, Signature, Tuple, HIR};
+use crate::hir::{self, Accessor, Block, Call, Def, Expr, Identifier, List, Signature, Tuple, HIR};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum WrapperKind {
@@ -30,15 +32,87 @@ struct LocalVars {
 
 impl_display_from_debug!(LocalVars);
 
+/// A variable and the attributes accessed from it, e.g. `c.xs` (`c`, [`xs`]).
+/// The variable is identified by its definition location.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct AccessPath {
+    var: AbsLocation,
+    attrs: Vec<Str>,
+}
+
+impl AccessPath {
+    /// `c` contains `c` and `c.xs`, but `c.ys` does not contain `c.xs`
+    fn contains(&self, other: &AccessPath) -> bool {
+        self.var == other.var && other.attrs.starts_with(&self.attrs)
+    }
+}
+
+/// A reference from one object (the holder) to another, e.g. `a.push! b` (`a` -> `b`).
+#[derive(Debug, Clone)]
+struct RefEdge {
+    held: AccessPath,
+    /// where the held object is passed (e.g. `b` in `a.push! b`)
+    held_loc: Location,
+}
+
+/// Records which objects hold references to which objects.
+#[derive(Debug, Default)]
+struct RefGraph {
+    edges: Dict<AccessPath, Vec<RefEdge>>,
+    /// `b = a` makes `b` an alias of `a`
+    aliases: Dict<AbsLocation, AbsLocation>,
+}
+
+impl RefGraph {
+    fn resolve<'a>(&'a self, mut var: &'a AbsLocation) -> &'a AbsLocation {
+        while let Some(orig) = self.aliases.get(var) {
+            var = orig;
+        }
+        var
+    }
+
+    /// The references held by `path` or its attributes.
+    fn edges_from<'a>(&'a self, path: &AccessPath) -> impl Iterator<Item = &'a RefEdge> {
+        let path = path.clone();
+        self.edges
+            .iter()
+            .filter(move |(holder, _)| path.contains(holder))
+            .flat_map(|(_, edges)| edges.iter())
+    }
+
+    /// Find the first edge of a path from `from` to (an object containing) `to`.
+    fn find_path(&self, from: &AccessPath, to: &AccessPath) -> Option<&RefEdge> {
+        let mut visited = Set::new();
+        let mut stack = vec![];
+        for edge in self.edges_from(from) {
+            stack.push((edge, edge));
+        }
+        while let Some((first, edge)) = stack.pop() {
+            if edge.held.contains(to) {
+                return Some(first);
+            }
+            if !visited.insert(edge.held.clone()) {
+                continue;
+            }
+            for next in self.edges_from(&edge.held) {
+                stack.push((first, next));
+            }
+        }
+        None
+    }
+}
+
 /// Check code ownership.
 /// for example:
 /// * Check if moved variables are not used again.
 /// * Checks whether a mutable reference method is called in an immutable reference method.
+/// * Check if mutable objects do not refer to each other (reference cycles).
 #[derive(Debug)]
 pub struct OwnershipChecker {
     cfg: ErgConfig,
     path_stack: Vec<Visibility>,
     dict: Dict<Str, LocalVars>,
+    refs: RefGraph,
     errs: OwnershipErrors,
 }
 
@@ -48,6 +122,7 @@ impl OwnershipChecker {
             cfg,
             path_stack: vec![],
             dict: Dict::new(),
+            refs: RefGraph::default(),
             errs: OwnershipErrors::empty(),
         }
     }
@@ -98,6 +173,7 @@ impl OwnershipChecker {
         match expr {
             Expr::Def(def) => {
                 self.define(def);
+                self.check_def_refs(def);
                 let name = match &def.sig {
                     Signature::Var(var) => var.inspect().clone(),
                     Signature::Subr(subr) => subr.ident.inspect().clone(),
@@ -152,6 +228,7 @@ impl OwnershipChecker {
             Expr::Accessor(acc) => self.check_acc(acc, ownership, chunk),
             // TODO: referenced
             Expr::Call(call) => {
+                self.check_call_refs(call);
                 let Some(sig_t) = call.signature_t() else {
                     return;
                 };
@@ -286,6 +363,13 @@ impl OwnershipChecker {
             Expr::TypeAsc(asc) => {
                 self.check_expr(&asc.expr, ownership, chunk);
             }
+            Expr::ReDef(redef) => {
+                if let Some(holder) = Self::access_path(&redef.attr) {
+                    if let Some(expr) = redef.block.last() {
+                        self.add_refs(holder, redef.attr.loc(), expr);
+                    }
+                }
+            }
             _ => {}
         }
     }
@@ -308,6 +392,172 @@ impl OwnershipChecker {
         }
     }
 
+    /// `c.xs.ys` -> (`c`, [`xs`, `ys`])
+    fn access_path(acc: &Accessor) -> Option<(&Identifier, Vec<Str>)> {
+        match acc {
+            Accessor::Ident(ident) if !ident.vi.def_loc.is_unknown() => Some((ident, vec![])),
+            Accessor::Ident(_) => None,
+            Accessor::Attr(attr) => {
+                let Expr::Accessor(obj) = attr.obj.as_ref() else {
+                    return None;
+                };
+                let (root, mut attrs) = Self::access_path(obj)?;
+                attrs.push(attr.ident.inspect().clone());
+                Some((root, attrs))
+            }
+        }
+    }
+
+    /// Collect the objects (accessors) that the value of `expr` will refer to.
+    fn referred_vars<'e>(expr: &'e Expr, vars: &mut Vec<&'e Accessor>) {
+        match expr {
+            // an immutable value (e.g. `c.n: Int`) is copied, not referred
+            Expr::Accessor(_) if expr.ref_t().is_value_class() => {}
+            Expr::Accessor(acc) => vars.push(acc),
+            Expr::List(List::Normal(lis)) => {
+                for arg in lis.elems.pos_args.iter() {
+                    Self::referred_vars(&arg.expr, vars);
+                }
+            }
+            Expr::List(List::WithLength(lis)) => Self::referred_vars(&lis.elem, vars),
+            Expr::Tuple(Tuple::Normal(tup)) => {
+                for arg in tup.elems.pos_args.iter() {
+                    Self::referred_vars(&arg.expr, vars);
+                }
+            }
+            Expr::Set(hir::Set::Normal(set)) => {
+                for arg in set.elems.pos_args.iter() {
+                    Self::referred_vars(&arg.expr, vars);
+                }
+            }
+            Expr::Dict(hir::Dict::Normal(dic)) => {
+                for kv in dic.kvs.iter() {
+                    Self::referred_vars(&kv.key, vars);
+                    Self::referred_vars(&kv.value, vars);
+                }
+            }
+            Expr::Record(rec) => {
+                for def in rec.attrs.iter() {
+                    if let Some(expr) = def.body.block.last() {
+                        Self::referred_vars(expr, vars);
+                    }
+                }
+            }
+            Expr::UnaryOp(unary) if unary.op.is(TokenKind::Mutate) => {
+                Self::referred_vars(&unary.expr, vars);
+            }
+            Expr::TypeAsc(asc) => Self::referred_vars(&asc.expr, vars),
+            // e.g. `C.new { .parent = p }`
+            Expr::Call(call)
+                if call
+                    .attr_name
+                    .as_ref()
+                    .is_some_and(|n| n.inspect() == "new") =>
+            {
+                for arg in call.args.pos_args.iter() {
+                    Self::referred_vars(&arg.expr, vars);
+                }
+                for arg in call.args.kw_args.iter() {
+                    Self::referred_vars(&arg.expr, vars);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// e.g. `x = ![a]` (`x` refers to `a`), `y = x` (`y` is an alias of `x`)
+    fn check_def_refs(&mut self, def: &Def) {
+        let Signature::Var(var) = &def.sig else {
+            return;
+        };
+        let Some(body) = def.body.block.last() else {
+            return;
+        };
+        match body {
+            Expr::Accessor(Accessor::Ident(ident)) if !ident.vi.def_loc.is_unknown() => {
+                let orig = self.refs.resolve(&ident.vi.def_loc).clone();
+                if orig != var.ident.vi.def_loc {
+                    self.refs.aliases.insert(var.ident.vi.def_loc.clone(), orig);
+                }
+            }
+            _ => self.add_refs((&var.ident, vec![]), var.loc(), body),
+        }
+    }
+
+    /// e.g. `a.push! b` (`a` refers to `b`), `n.children.push! n` (`n` refers to itself)
+    fn check_call_refs(&mut self, call: &Call) {
+        if !call.obj.ref_t().is_mut_type()
+            || !call
+                .attr_name
+                .as_ref()
+                .is_some_and(|name| name.inspect().ends_with('!'))
+        {
+            return;
+        }
+        let Expr::Accessor(obj) = call.obj.as_ref() else {
+            return;
+        };
+        let Some(holder) = Self::access_path(obj) else {
+            return;
+        };
+        for arg in call.args.pos_args.iter() {
+            self.add_refs(holder.clone(), call.obj.loc(), &arg.expr);
+        }
+        for arg in call.args.kw_args.iter() {
+            self.add_refs(holder.clone(), call.obj.loc(), &arg.expr);
+        }
+    }
+
+    fn resolve_path(&self, (ident, attrs): (&Identifier, Vec<Str>)) -> AccessPath {
+        AccessPath {
+            var: self.refs.resolve(&ident.vi.def_loc).clone(),
+            attrs,
+        }
+    }
+
+    fn add_refs(&mut self, holder: (&Identifier, Vec<Str>), holder_loc: Location, expr: &Expr) {
+        if holder.0.vi.def_loc.is_unknown() {
+            return;
+        }
+        let holder = self.resolve_path(holder);
+        let mut vars = vec![];
+        Self::referred_vars(expr, &mut vars);
+        for acc in vars {
+            let Some((ident, attrs)) = Self::access_path(acc) else {
+                continue;
+            };
+            let held = self.resolve_path((ident, attrs));
+            // e.g. `n.children.push! n`
+            let cycle_loc = if held.contains(&holder) {
+                Some(holder_loc)
+            } else {
+                self.refs
+                    .find_path(&held, &holder)
+                    .map(|edge| edge.held_loc)
+            };
+            if let Some(cycle_loc) = cycle_loc {
+                self.errs.push(OwnershipError::cycle_error(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    ident.inspect(),
+                    acc.loc(),
+                    cycle_loc,
+                    self.full_path(),
+                ));
+                continue;
+            }
+            let edge = RefEdge {
+                held,
+                held_loc: acc.loc(),
+            };
+            self.refs
+                .edges
+                .entry(holder.clone())
+                .or_default()
+                .push(edge);
+        }
+    }
+
     /// TODO: このメソッドを呼ぶとき、スコープを再帰的に検索する
     #[inline]
     fn current_scope(&mut self) -> &mut LocalVars {
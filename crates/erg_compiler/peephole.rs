@@ -0,0 +1,489 @@
+//! Peephole optimization on the generated bytecode (`CodeObj`).
+use erg_common::config::ErgConfig;
+use erg_common::dict::Dict;
+use erg_common::opcode::CommonOpcode;
+use erg_common::opcode308::Opcode308;
+use erg_common::opcode309::Opcode309;
+use erg_common::opcode310::Opcode310;
+use erg_common::opcode311::Opcode311;
+use erg_common::opcode312::Opcode312;
+use erg_common::opcode313::Opcode313;
+use erg_common::python_util::PythonVersion;
+use erg_common::set::Set;
+
+use crate::ty::codeobj::{CodeObj, ExceptionTableEntry};
+use crate::ty::value::ValueObj;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JumpKind {
+    Absolute,
+    Forward,
+    Backward,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InstrKind {
+    /// `JUMP_FORWARD`, `JUMP_ABSOLUTE`, `JUMP_BACKWARD`
+    Jump(JumpKind),
+    /// e.g. `POP_JUMP_IF_FALSE`
+    CondJump(JumpKind),
+    /// An instruction whose jump target cannot be redirected (e.g. `FOR_ITER`, `SETUP_WITH`)
+    Block(JumpKind),
+    LoadConst,
+    /// `DUP_TOP`, `COPY 1`
+    Dup,
+    PopTop,
+    Nop,
+    Other,
+}
+
+impl InstrKind {
+    const fn jump_kind(&self) -> Option<JumpKind> {
+        match self {
+            Self::Jump(kind) | Self::CondJump(kind) | Self::Block(kind) => Some(*kind),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Instr {
+    /// offset of the first `EXTENDED_ARG` (or of the instruction itself)
+    start: usize,
+    /// offset of the instruction itself
+    offset: usize,
+    /// offset just after the inline cache entries
+    end: usize,
+    op: u8,
+    arg: usize,
+    kind: InstrKind,
+    target: Option<usize>,
+    removed: bool,
+}
+
+impl Instr {
+    /// the number of bytes the argument occupies (`EXTENDED_ARG`s + 1)
+    const fn width(&self) -> usize {
+        (self.offset - self.start) / 2 + 1
+    }
+
+    const fn cache_len(&self) -> usize {
+        self.end - self.offset - 2
+    }
+}
+
+const fn arg_width(arg: usize) -> usize {
+    match arg {
+        0..=0xff => 1,
+        0x100..=0xffff => 2,
+        0x10000..=0xffffff => 3,
+        _ => 4,
+    }
+}
+
+/// Performs peephole optimization on `CodeObj`s (`opt_level >= 2`).
+///
+/// * redundant pairs such as `LOAD_CONST x; POP_TOP` and `NOP`s are removed
+/// * jumps to unconditional jumps are redirected to their final destination
+/// * unnecessary `EXTENDED_ARG`s are removed
+///
+/// If the bytecode contains an instruction that the optimizer does not know, the code is left as it is.
+#[derive(Debug)]
+pub struct PeepholeOptimizer {
+    minor: u8,
+}
+
+impl PeepholeOptimizer {
+    pub fn optimize(cfg: &ErgConfig, py_version: PythonVersion, code: CodeObj) -> CodeObj {
+        if cfg.opt_level < 2 {
+            return code;
+        }
+        match py_version.minor {
+            Some(minor @ 7..=13) => Self { minor }.optimize_code(code),
+            _ => code,
+        }
+    }
+
+    fn optimize_code(&self, mut code: CodeObj) -> CodeObj {
+        code.consts = code
+            .consts
+            .into_iter()
+            .map(|cons| match cons {
+                ValueObj::Code(code) => ValueObj::Code(Box::new(self.optimize_code(*code))),
+                other => other,
+            })
+            .collect();
+        if let Some((bytes, lnotab, exceptiontable)) = self.rewrite(&code) {
+            code.code = bytes;
+            code.lnotab = lnotab;
+            code.exceptiontable = exceptiontable;
+        }
+        code
+    }
+
+    fn extended_arg(&self) -> u8 {
+        if self.minor >= 13 {
+            Opcode313::EXTENDED_ARG as u8
+        } else {
+            CommonOpcode::EXTENDED_ARG as u8
+        }
+    }
+
+    /// `None` if `op` is unknown
+    fn classify(&self, op: u8, arg: usize) -> Option<InstrKind> {
+        use InstrKind::*;
+        use JumpKind::*;
+        let kind = match self.minor {
+            7 | 8 => match Opcode308::try_from(op).ok()? {
+                Opcode308::JUMP_FORWARD => Jump(Forward),
+                Opcode308::JUMP_ABSOLUTE => Jump(Absolute),
+                Opcode308::POP_JUMP_IF_FALSE
+                | Opcode308::POP_JUMP_IF_TRUE
+                | Opcode308::JUMP_IF_FALSE_OR_POP
+                | Opcode308::JUMP_IF_TRUE_OR_POP => CondJump(Absolute),
+                Opcode308::FOR_ITER
+                | Opcode308::SETUP_FINALLY
+                | Opcode308::SETUP_WITH
+                | Opcode308::CALL_FINALLY => Block(Forward),
+                Opcode308::LOAD_CONST => LoadConst,
+                Opcode308::DUP_TOP => Dup,
+                Opcode308::POP_TOP => PopTop,
+                Opcode308::NOP => Nop,
+                _ => Other,
+            },
+            9 => match Opcode309::try_from(op).ok()? {
+                Opcode309::JUMP_FORWARD => Jump(Forward),
+                Opcode309::JUMP_ABSOLUTE => Jump(Absolute),
+                Opcode309::POP_JUMP_IF_FALSE
+                | Opcode309::POP_JUMP_IF_TRUE
+                | Opcode309::JUMP_IF_FALSE_OR_POP
+                | Opcode309::JUMP_IF_TRUE_OR_POP => CondJump(Absolute),
+                Opcode309::FOR_ITER | Opcode309::SETUP_WITH => Block(Forward),
+                Opcode309::LOAD_CONST => LoadConst,
+                Opcode309::DUP_TOP => Dup,
+                Opcode309::POP_TOP => PopTop,
+                Opcode309::NOP => Nop,
+                _ => Other,
+            },
+            10 => match Opcode310::try_from(op).ok()? {
+                Opcode310::JUMP_FORWARD => Jump(Forward),
+                Opcode310::JUMP_ABSOLUTE => Jump(Absolute),
+                Opcode310::POP_JUMP_IF_FALSE
+                | Opcode310::POP_JUMP_IF_TRUE
+                | Opcode310::JUMP_IF_FALSE_OR_POP
+                | Opcode310::JUMP_IF_TRUE_OR_POP => CondJump(Absolute),
+                Opcode310::FOR_ITER | Opcode310::SETUP_WITH => Block(Forward),
+                Opcode310::LOAD_CONST => LoadConst,
+                Opcode310::DUP_TOP => Dup,
+                Opcode310::POP_TOP => PopTop,
+                Opcode310::NOP => Nop,
+                _ => Other,
+            },
+            11 => match Opcode311::try_from(op).ok()? {
+                Opcode311::JUMP_FORWARD => Jump(Forward),
+                Opcode311::JUMP_BACKWARD => Jump(Backward),
+                Opcode311::POP_JUMP_FORWARD_IF_FALSE
+                | Opcode311::POP_JUMP_FORWARD_IF_TRUE
+                | Opcode311::JUMP_IF_FALSE_OR_POP
+                | Opcode311::JUMP_IF_TRUE_OR_POP => CondJump(Forward),
+                Opcode311::POP_JUMP_BACKWARD_IF_FALSE | Opcode311::POP_JUMP_BACKWARD_IF_TRUE => {
+                    CondJump(Backward)
+                }
+                Opcode311::FOR_ITER | Opcode311::SEND => Block(Forward),
+                Opcode311::LOAD_CONST => LoadConst,
+                Opcode311::COPY if arg == 1 => Dup,
+                Opcode311::POP_TOP => PopTop,
+                Opcode311::NOP => Nop,
+                _ => Other,
+            },
+            12 => match Opcode312::try_from(op).ok()? {
+                Opcode312::JUMP_FORWARD => Jump(Forward),
+                Opcode312::JUMP_BACKWARD => Jump(Backward),
+                Opcode312::POP_JUMP_IF_FALSE
+                | Opcode312::POP_JUMP_IF_TRUE
+                | Opcode312::POP_JUMP_IF_NONE
+                | Opcode312::POP_JUMP_IF_NOT_NONE => CondJump(Forward),
+                Opcode312::FOR_ITER | Opcode312::SEND => Block(Forward),
+                Opcode312::JUMP_BACKWARD_NO_INTERRUPT => Block(Backward),
+                Opcode312::LOAD_CONST => LoadConst,
+                Opcode312::COPY if arg == 1 => Dup,
+                Opcode312::POP_TOP => PopTop,
+                Opcode312::NOP => Nop,
+                _ => Other,
+            },
+            13 => match Opcode313::try_from(op).ok()? {
+                Opcode313::JUMP_FORWARD => Jump(Forward),
+                Opcode313::JUMP_BACKWARD => Jump(Backward),
+                Opcode313::POP_JUMP_IF_FALSE
+                | Opcode313::POP_JUMP_IF_TRUE
+                | Opcode313::POP_JUMP_IF_NONE
+                | Opcode313::POP_JUMP_IF_NOT_NONE => CondJump(Forward),
+                Opcode313::FOR_ITER | Opcode313::SEND => Block(Forward),
+                Opcode313::JUMP_BACKWARD_NO_INTERRUPT => Block(Backward),
+                Opcode313::LOAD_CONST => LoadConst,
+                Opcode313::COPY if arg == 1 => Dup,
+                Opcode313::POP_TOP => PopTop,
+                Opcode313::NOP => Nop,
+                _ => Other,
+            },
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    /// Jump arguments are in bytes until 3.9 and in instructions (2 bytes) since 3.10.
+    const fn jump_unit(&self) -> usize {
+        if self.minor >= 10 {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// `end`: the offset just after the jump instruction (relative jumps are based on it)
+    fn jump_target(&self, kind: JumpKind, end: usize, arg: usize) -> Option<usize> {
+        let delta = arg * self.jump_unit();
+        match kind {
+            JumpKind::Absolute => Some(delta),
+            JumpKind::Forward => Some(end + delta),
+            JumpKind::Backward => end.checked_sub(delta),
+        }
+    }
+
+    /// `None` if the jump cannot reach `target`
+    fn jump_arg(&self, kind: JumpKind, end: usize, target: usize) -> Option<usize> {
+        let delta = match kind {
+            JumpKind::Absolute => target,
+            JumpKind::Forward => target.checked_sub(end)?,
+            JumpKind::Backward => end.checked_sub(target)?,
+        };
+        Some(delta / self.jump_unit())
+    }
+
+    fn decode(&self, code: &[u8]) -> Option<Vec<Instr>> {
+        let extended_arg = self.extended_arg();
+        let mut instrs: Vec<Instr> = vec![];
+        let mut idx = 0;
+        let mut start = 0;
+        let mut ext = 0;
+        while idx + 1 < code.len() {
+            let (op, arg) = (code[idx], code[idx + 1]);
+            if op == extended_arg {
+                ext = (ext | arg as usize) << 8;
+                idx += 2;
+                continue;
+            }
+            let arg = ext | arg as usize;
+            ext = 0;
+            let kind = self.classify(op, arg)?;
+            let offset = idx;
+            idx += 2;
+            // `CACHE` (= 0) entries follow the instruction (3.11~)
+            while self.minor >= 11 && code.get(idx) == Some(&0) {
+                idx += 2;
+            }
+            let target = match kind.jump_kind() {
+                Some(jump) => Some(self.jump_target(jump, idx, arg)?),
+                None => None,
+            };
+            instrs.push(Instr {
+                start,
+                offset,
+                end: idx,
+                op,
+                arg,
+                kind,
+                target,
+                removed: false,
+            });
+            start = idx;
+        }
+        (idx == code.len() && start == idx).then_some(instrs)
+    }
+
+    /// Returns the new `(code, lnotab, exceptiontable)`.
+    /// `None` if nothing can be optimized or the code contains something unexpected.
+    fn rewrite(&self, code: &CodeObj) -> Option<(Vec<u8>, Vec<u8>, Vec<u8>)> {
+        let mut instrs = self.decode(&code.code)?;
+        let index = instrs
+            .iter()
+            .enumerate()
+            .map(|(i, instr)| (instr.start, i))
+            .collect::<Dict<_, _>>();
+        let exception_entries = ExceptionTableEntry::table_from_bytes(&code.exceptiontable)?;
+        // all jump targets must be at the beginning of an instruction
+        let jump_targets = instrs.iter().filter_map(|instr| instr.target);
+        let handler_targets = exception_entries.iter().map(|entry| entry.target);
+        if jump_targets
+            .chain(handler_targets)
+            .any(|target| !index.contains_key(&target))
+        {
+            return None;
+        }
+        self.thread_jumps(&mut instrs, &index);
+        let targets = instrs
+            .iter()
+            .filter_map(|instr| instr.target)
+            .chain(exception_entries.iter().map(|entry| entry.target))
+            .collect::<Set<_>>();
+        Self::remove_redundant_instrs(&mut instrs, &targets);
+        Self::remove_jumps_to_next(&mut instrs);
+        // The new size of each instruction is calculated from the old layout.
+        // Since no instruction becomes larger, the distance between two instructions never increases,
+        // so the jump arguments computed in the old layout are upper bounds of the new ones.
+        let mut widths = Vec::with_capacity(instrs.len());
+        for instr in instrs.iter() {
+            let arg = match (instr.kind.jump_kind(), instr.target) {
+                (Some(kind), Some(target)) => self.jump_arg(kind, instr.end, target)?,
+                _ => instr.arg,
+            };
+            widths.push(arg_width(arg).min(instr.width()));
+        }
+        let mut new_starts = Vec::with_capacity(instrs.len() + 1);
+        let mut cur = 0;
+        for (instr, width) in instrs.iter().zip(widths.iter()) {
+            new_starts.push(cur);
+            if !instr.removed {
+                cur += width * 2 + instr.cache_len();
+            }
+        }
+        new_starts.push(cur);
+        // maps an old offset to the new one
+        let remap = |offset: usize| {
+            let idx = instrs.partition_point(|instr| instr.start < offset);
+            new_starts[idx]
+        };
+        let extended_arg = self.extended_arg();
+        let mut bytes = Vec::with_capacity(cur);
+        for ((instr, width), new_start) in instrs.iter().zip(widths).zip(new_starts.iter()) {
+            if instr.removed {
+                continue;
+            }
+            let arg = match (instr.kind.jump_kind(), instr.target) {
+                (Some(kind), Some(target)) => {
+                    let new_end = new_start + width * 2 + instr.cache_len();
+                    self.jump_arg(kind, new_end, remap(target))?
+                }
+                _ => instr.arg,
+            };
+            if arg_width(arg) > width {
+                return None;
+            }
+            for shift in (1..width).rev() {
+                bytes.push(extended_arg);
+                bytes.push((arg >> (shift * 8)) as u8);
+            }
+            bytes.push(instr.op);
+            bytes.push(arg as u8);
+            bytes.extend_from_slice(&code.code[instr.offset + 2..instr.end]);
+        }
+        if bytes == code.code {
+            return None;
+        }
+        let mut line_starts: Vec<(usize, u32)> = vec![];
+        for (offset, line) in CodeObj::line_starts(&code.lnotab, code.firstlineno) {
+            let offset = remap(offset);
+            match line_starts.last_mut() {
+                Some(last) if last.0 == offset => last.1 = line,
+                Some(last) if last.1 == line => {}
+                _ => line_starts.push((offset, line)),
+            }
+        }
+        let lnotab = CodeObj::line_starts_into_lnotab(&line_starts, code.firstlineno);
+        let exception_entries = exception_entries
+            .into_iter()
+            .map(|entry| {
+                ExceptionTableEntry::new(
+                    remap(entry.start),
+                    remap(entry.end),
+                    remap(entry.target),
+                    entry.depth,
+                    entry.lasti,
+                )
+            })
+            .collect::<Vec<_>>();
+        let exceptiontable = ExceptionTableEntry::table_into_bytes(&exception_entries);
+        Some((bytes, lnotab, exceptiontable))
+    }
+
+    /// ```python
+    /// POP_JUMP_IF_FALSE L1
+    /// ...
+    /// L1: JUMP_FORWARD L2
+    /// ```
+    /// ↓
+    /// ```python
+    /// POP_JUMP_IF_FALSE L2
+    /// ...
+    /// L1: JUMP_FORWARD L2
+    /// ```
+    fn thread_jumps(&self, instrs: &mut [Instr], index: &Dict<usize, usize>) {
+        for i in 0..instrs.len() {
+            let (InstrKind::Jump(kind) | InstrKind::CondJump(kind)) = instrs[i].kind else {
+                continue;
+            };
+            let Some(mut target) = instrs[i].target else {
+                continue;
+            };
+            let mut visited = Set::new();
+            while let Some(&next) = index.get(&target) {
+                if !matches!(instrs[next].kind, InstrKind::Jump(_)) || !visited.insert(next) {
+                    break;
+                }
+                let Some(next_target) = instrs[next].target else {
+                    break;
+                };
+                // The jump must be able to reach the new target without becoming larger
+                match self.jump_arg(kind, instrs[i].end, next_target) {
+                    Some(arg) if arg_width(arg) <= instrs[i].width() => {}
+                    _ => break,
+                }
+                target = next_target;
+            }
+            instrs[i].target = Some(target);
+        }
+    }
+
+    /// Removes `LOAD_CONST x; POP_TOP`, `DUP_TOP; POP_TOP` and `NOP`.
+    /// `POP_TOP` cannot be removed if it is a jump target, since the stack states differ.
+    fn remove_redundant_instrs(instrs: &mut [Instr], targets: &Set<usize>) {
+        for i in 0..instrs.len() {
+            match instrs[i].kind {
+                InstrKind::Nop => {
+                    instrs[i].removed = true;
+                }
+                InstrKind::LoadConst | InstrKind::Dup if !instrs[i].removed => {
+                    let Some(next) = instrs.get(i + 1) else {
+                        continue;
+                    };
+                    if next.kind == InstrKind::PopTop && !targets.contains(&next.start) {
+                        instrs[i].removed = true;
+                        instrs[i + 1].removed = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Removes unconditional jumps to the next (non-removed) instruction.
+    fn remove_jumps_to_next(instrs: &mut [Instr]) {
+        for i in 0..instrs.len() {
+            let (InstrKind::Jump(_), Some(target), false) =
+                (instrs[i].kind, instrs[i].target, instrs[i].removed)
+            else {
+                continue;
+            };
+            let mut next = i + 1;
+            while instrs.get(next).is_some_and(|instr| instr.removed) {
+                next += 1;
+            }
+            let next_start = instrs.get(next).map(|instr| instr.start);
+            // `target` is between the end of this jump and the next instruction
+            if next_start.is_some_and(|next_start| instrs[i].end <= target && target <= next_start)
+            {
+                instrs[i].removed = true;
+            }
+        }
+    }
+}
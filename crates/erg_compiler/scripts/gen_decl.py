@@ -0,0 +1,532 @@
+# Generates an Erg declaration file (.d.er) from a Python module.
+# usage: python gen_decl.py <path/to/module.py>
+# The declarations are derived from the AST (the module is not executed),
+# so types which cannot be determined statically are declared as `Obj`.
+import ast
+import sys
+
+SCALARS = {
+    "int": "Int",
+    "float": "Float",
+    "complex": "Complex",
+    "str": "Str",
+    "bool": "Bool",
+    "bytes": "Bytes",
+    "object": "Obj",
+    "Any": "Obj",
+    "None": "NoneType",
+    "NoneType": "NoneType",
+}
+SEQUENCES = {
+    "list": "[{}; _]",
+    "List": "[{}; _]",
+    "set": "{{{}; _}}",
+    "Set": "{{{}; _}}",
+    "frozenset": "{{{}; _}}",
+    "FrozenSet": "{{{}; _}}",
+    "Sequence": "Sequence({})",
+    "MutableSequence": "Sequence({})",
+    "Iterable": "Iterable({})",
+    "Iterator": "Iterable({})",
+    "Generator": "Iterable({})",
+    "Collection": "Iterable({})",
+}
+MAPPINGS = {
+    "dict": "{{{}: {}}}",
+    "Dict": "{{{}: {}}}",
+    "Mapping": "Mapping({}, {})",
+    "MutableMapping": "Mapping({}, {})",
+}
+WRAPPERS = {"Annotated", "Final", "ClassVar", "Required", "NotRequired"}
+# the class decorators which generate `__init__` from the annotated fields (dataclasses, attrs)
+DATACLASSES = {"dataclass", "define", "frozen", "mutable", "attrs", "s"}
+
+
+def const_value(node):
+    if isinstance(node, ast.Constant):
+        return True, node.value
+    # Python 3.7
+    for name, attr in (("Str", "s"), ("Num", "n"), ("Bytes", "s"), ("NameConstant", "value")):
+        cls = getattr(ast, name, None)
+        if cls is not None and isinstance(node, cls):
+            return True, getattr(node, attr)
+    return False, None
+
+
+def base_name(node):
+    if isinstance(node, ast.Name):
+        return node.id
+    if isinstance(node, ast.Attribute):
+        return node.attr
+    return None
+
+
+def subscript_args(node):
+    slice_ = node.slice
+    # Python 3.8
+    if hasattr(ast, "Index") and isinstance(slice_, ast.Index):
+        slice_ = slice_.value
+    if isinstance(slice_, ast.Tuple):
+        return slice_.elts
+    return [slice_]
+
+
+def union(types):
+    result = []
+    for t in types:
+        for part in t.split(" or "):
+            if part not in result:
+                result.append(part)
+    if "Obj" in result or not result:
+        return "Obj"
+    return " or ".join(result)
+
+
+def is_public(name):
+    return not name.startswith("_")
+
+
+class DeclGenerator:
+    def __init__(self, tree):
+        self.tree = tree
+        self.typevars = set()
+        self.used_typevars = []
+        # type variables can only be bound in subroutine signatures (`|T|(x: T) -> T`)
+        self.erase_typevars = True
+        self.classes = {}
+        self.exported = None
+        self.declared = set()
+        self.lines = []
+        self.collect(tree.body)
+
+    # `__all__`, classes and type variables
+    def collect(self, body):
+        for stmt in body:
+            if isinstance(stmt, ast.ClassDef):
+                self.classes.setdefault(stmt.name, stmt)
+            elif isinstance(stmt, (ast.Assign, ast.AugAssign, ast.AnnAssign)):
+                targets = stmt.targets if isinstance(stmt, ast.Assign) else [stmt.target]
+                names = [t.id for t in targets if isinstance(t, ast.Name)]
+                if "__all__" in names and stmt.value is not None:
+                    names = self.str_list(stmt.value)
+                    if names is not None:
+                        if self.exported is None or not isinstance(stmt, ast.AugAssign):
+                            self.exported = []
+                        self.exported.extend(names)
+                elif isinstance(stmt.value, ast.Call) and base_name(stmt.value.func) in (
+                    "TypeVar",
+                    "ParamSpec",
+                    "TypeVarTuple",
+                ):
+                    self.typevars.update(names)
+            elif isinstance(stmt, (ast.If, ast.Try)):
+                self.collect(self.branches(stmt))
+
+    def branches(self, stmt):
+        if isinstance(stmt, ast.If):
+            return stmt.body + stmt.orelse
+        body = stmt.body + stmt.orelse + stmt.finalbody
+        for handler in stmt.handlers:
+            body += handler.body
+        return body
+
+    def str_list(self, node):
+        if not isinstance(node, (ast.List, ast.Tuple)):
+            return None
+        names = []
+        for elt in node.elts:
+            ok, value = const_value(elt)
+            if ok and isinstance(value, str):
+                names.append(value)
+        return names
+
+    def is_exported(self, name):
+        if self.exported is not None:
+            return name in self.exported
+        return is_public(name)
+
+    def class_type(self, name):
+        if name in self.classes and self.is_exported(name):
+            return "." + name
+        return None
+
+    # PEP 484 annotations -> Erg types
+    def conv(self, node):
+        if node is None:
+            return "Obj"
+        ok, value = const_value(node)
+        if ok:
+            if value is None:
+                return "NoneType"
+            if isinstance(value, str):
+                try:
+                    return self.conv(ast.parse(value, mode="eval").body)
+                except SyntaxError:
+                    return "Obj"
+            return "Obj"
+        if isinstance(node, ast.BinOp) and isinstance(node.op, ast.BitOr):
+            return union([self.conv(node.left), self.conv(node.right)])
+        name = base_name(node)
+        if isinstance(node, ast.Subscript):
+            return self.conv_generic(base_name(node.value), subscript_args(node))
+        if name is None:
+            return "Obj"
+        if name in self.typevars:
+            if self.erase_typevars:
+                return "Obj"
+            if name not in self.used_typevars:
+                self.used_typevars.append(name)
+            return name
+        if name in SCALARS:
+            return SCALARS[name]
+        if name in SEQUENCES:
+            return SEQUENCES[name].format("Obj")
+        if name in MAPPINGS:
+            return MAPPINGS[name].format("Obj", "Obj")
+        if name in ("type", "Type"):
+            return "ClassType"
+        return self.class_type(name) or "Obj"
+
+    def conv_generic(self, name, args):
+        if name in SEQUENCES:
+            return SEQUENCES[name].format(self.conv(args[0]))
+        if name in MAPPINGS and len(args) == 2:
+            return MAPPINGS[name].format(self.conv(args[0]), self.conv(args[1]))
+        if name in ("tuple", "Tuple"):
+            if len(args) == 2 and const_value(args[1]) == (True, Ellipsis):
+                return "Sequence({})".format(self.conv(args[0]))
+            return "({})".format(", ".join(self.conv(arg) for arg in args))
+        if name == "Optional":
+            return union([self.conv(args[0]), "NoneType"])
+        if name == "Union":
+            return union([self.conv(arg) for arg in args])
+        if name in WRAPPERS:
+            return self.conv(args[0])
+        if name in ("type", "Type"):
+            return "ClassType"
+        if name == "Literal":
+            values = []
+            for arg in args:
+                ok, value = const_value(arg)
+                if not ok or not isinstance(value, (int, str)) or isinstance(value, bool):
+                    return "Obj"
+                values.append(repr(value).replace("'", '"'))
+            return "{{{}}}".format(", ".join(values))
+        if name == "Callable" and len(args) == 2 and isinstance(args[0], ast.List):
+            params = ", ".join(self.conv(arg) for arg in args[0].elts)
+            return "(({}) -> {})".format(params, self.conv(args[1]))
+        return self.conv(ast.Name(id=name)) if name else "Obj"
+
+    # the type of a value expression
+    def infer(self, node, params=None):
+        if node is None:
+            return "Obj"
+        ok, value = const_value(node)
+        if ok:
+            if value is None:
+                return "NoneType"
+            if isinstance(value, bool):
+                return "Bool"
+            for cls, t in ((int, "Int"), (float, "Float"), (complex, "Complex"), (str, "Str"), (bytes, "Bytes")):
+                if isinstance(value, cls):
+                    return t
+            return "Obj"
+        if isinstance(node, ast.UnaryOp) and isinstance(node.op, (ast.USub, ast.UAdd)):
+            return self.infer(node.operand)
+        if isinstance(node, (ast.List, ast.Set)):
+            elem = self.common_type([self.infer(elt, params) for elt in node.elts])
+            return ("[{}; _]" if isinstance(node, ast.List) else "{{{}; _}}").format(elem)
+        if isinstance(node, ast.Tuple):
+            return "({})".format(", ".join(self.infer(elt, params) for elt in node.elts))
+        if isinstance(node, ast.Dict):
+            key = self.common_type([self.infer(k, params) for k in node.keys if k is not None])
+            value = self.common_type([self.infer(v, params) for v in node.values])
+            return "{{{}: {}}}".format(key, value)
+        if isinstance(node, ast.Call):
+            name = base_name(node.func)
+            if name in SCALARS and name not in ("Any", "None", "NoneType"):
+                return SCALARS[name]
+            return self.class_type(name) or "Obj"
+        if isinstance(node, ast.Name) and params and node.id in params:
+            return params[node.id]
+        return "Obj"
+
+    def common_type(self, types):
+        if not types:
+            return "Obj"
+        first = types[0]
+        return first if all(t == first for t in types) else "Obj"
+
+    # functions
+    def signature(self, func, self_type=None, skip_first=False):
+        self.used_typevars = []
+        self.erase_typevars = False
+        args = func.args
+        positional = list(getattr(args, "posonlyargs", [])) + list(args.args)
+        n_required = len(positional) - len(args.defaults)
+        params = []
+        for i, arg in enumerate(positional[:n_required]):
+            if i == 0 and skip_first:
+                continue
+            if i == 0 and self_type is not None:
+                params.append("{}: {}".format(arg.arg, self_type))
+                continue
+            params.append(self.param(arg, None, False))
+        # Erg has no keyword-only parameters,
+        # so the required ones are declared as (positional or keyword) required parameters.
+        # If they would take the arguments for default or variable-length parameters, `**kwargs` is declared instead
+        kw_required = [arg for arg, default in zip(args.kwonlyargs, args.kw_defaults) if default is None]
+        kw_fallback = bool(kw_required) and (args.vararg is not None or bool(args.defaults))
+        if not kw_fallback:
+            for arg in kw_required:
+                params.append(self.param(arg, None, False))
+        for arg, default in zip(positional[n_required:], args.defaults):
+            params.append(self.param(arg, default, True))
+        if args.vararg is not None:
+            params.append("*{}: {}".format(args.vararg.arg, self.conv(args.vararg.annotation)))
+        for arg, default in zip(args.kwonlyargs, args.kw_defaults):
+            if default is not None:
+                params.append(self.param(arg, default, True))
+        if kw_fallback:
+            name = args.kwarg.arg if args.kwarg is not None else "kwargs"
+            params.append("**{}: Obj".format(name))
+        elif args.kwarg is not None:
+            params.append("**{}: {}".format(args.kwarg.arg, self.conv(args.kwarg.annotation)))
+        if func.returns is None or isinstance(func, ast.AsyncFunctionDef):
+            ret = "Obj"
+        else:
+            ret = self.conv(func.returns)
+        self.erase_typevars = True
+        return params, ret
+
+    def param(self, arg, default, has_default):
+        if arg.annotation is not None:
+            t = self.conv(arg.annotation)
+        else:
+            t = self.infer(default) if has_default else "Obj"
+            if t == "NoneType":
+                t = "Obj"
+        op = " := " if has_default else ": "
+        return "{}{}{}".format(arg.arg, op, t)
+
+    def format_sig(self, params, ret):
+        bounds = ""
+        if self.used_typevars:
+            bounds = "|{}|".format(", ".join(self.used_typevars))
+        return "{}({}) -> {}".format(bounds, ", ".join(params), ret)
+
+    def decorators(self, func):
+        return {base_name(dec.func if isinstance(dec, ast.Call) else dec) for dec in func.decorator_list}
+
+    def func_decl(self, funcs, self_type=None):
+        sigs = []
+        for func in funcs:
+            decs = self.decorators(func)
+            if "property" in decs or "cached_property" in decs:
+                return self.conv(func.returns)
+            if "staticmethod" in decs:
+                params, ret = self.signature(func)
+            elif "classmethod" in decs:
+                params, ret = self.signature(func, skip_first=True)
+            else:
+                params, ret = self.signature(func, self_type)
+            sigs.append(self.format_sig(params, ret))
+        if len(sigs) == 1:
+            return sigs[0]
+        return " and ".join("({})".format(sig) for sig in sigs)
+
+    def overloads(self, body):
+        """Returns the functions grouped by name (`@overload`s are grouped together)."""
+        groups = {}
+        for stmt in body:
+            if not isinstance(stmt, (ast.FunctionDef, ast.AsyncFunctionDef)):
+                continue
+            group = groups.setdefault(stmt.name, [])
+            if "overload" in self.decorators(stmt):
+                group[:] = [f for f in group if "overload" in self.decorators(f)]
+                group.append(stmt)
+            elif not group:
+                group.append(stmt)
+        return groups
+
+    # classes
+    def class_decl(self, cls):
+        qual = "." + cls.name
+        attrs = []
+        declared = set()
+
+        def add(name, t):
+            if name not in declared and is_public(name):
+                declared.add(name)
+                attrs.append("    {}: {}".format(name, t))
+
+        init = self.find_init(cls)
+        if self.is_dataclass(cls) and init not in cls.body:
+            if self.has_external_base(cls):
+                ctor = "(*args: Obj, **kwargs: Obj) -> {}".format(qual)
+            else:
+                ctor = self.dataclass_ctor(cls, qual)
+        elif init is not None:
+            params, _ = self.signature(init, skip_first=True)
+            ctor = self.format_sig(params, qual)
+        elif self.has_external_base(cls) or cls.decorator_list:
+            # the decorators may replace the constructor
+            ctor = "(*args: Obj, **kwargs: Obj) -> {}".format(qual)
+        else:
+            ctor = "() -> {}".format(qual)
+        attrs.append("    __call__: {}".format(ctor))
+        for stmt in cls.body:
+            if isinstance(stmt, ast.AnnAssign) and isinstance(stmt.target, ast.Name):
+                add(stmt.target.id, self.conv(stmt.annotation))
+            elif isinstance(stmt, ast.Assign):
+                for target in stmt.targets:
+                    if isinstance(target, ast.Name):
+                        add(target.id, self.infer(stmt.value))
+        if init is not None:
+            params = {}
+            for arg in init.args.args[1:] + init.args.kwonlyargs:
+                if arg.annotation is not None:
+                    params[arg.arg] = self.conv(arg.annotation)
+            for stmt in ast.walk(init):
+                target, t = None, None
+                if isinstance(stmt, ast.AnnAssign):
+                    target, t = stmt.target, self.conv(stmt.annotation)
+                elif isinstance(stmt, ast.Assign) and len(stmt.targets) == 1:
+                    target, t = stmt.targets[0], self.infer(stmt.value, params)
+                if (
+                    isinstance(target, ast.Attribute)
+                    and isinstance(target.value, ast.Name)
+                    and target.value.id == init.args.args[0].arg
+                ):
+                    add(target.attr, t)
+        for name, funcs in self.overloads(cls.body).items():
+            # dunder methods are skipped (`__call__` of a class means its constructor in Erg)
+            if name.startswith("__") and name.endswith("__"):
+                continue
+            add(name, self.func_decl(funcs, qual))
+        return [qual + "."] + attrs
+
+    def find_init(self, cls):
+        for stmt in cls.body:
+            if isinstance(stmt, ast.FunctionDef) and stmt.name == "__init__" and stmt.args.args:
+                return stmt
+        for base in cls.bases:
+            base = self.classes.get(base_name(base))
+            if base is not None and base is not cls:
+                init = self.find_init(base)
+                if init is not None:
+                    return init
+        return None
+
+    def is_dataclass(self, cls):
+        if self.decorators(cls) & DATACLASSES:
+            return True
+        for base in cls.bases:
+            base = self.classes.get(base_name(base))
+            if base is not None and base is not cls and self.is_dataclass(base):
+                return True
+        return False
+
+    def dataclass_fields(self, cls):
+        """Returns the annotations of the `__init__` parameters generated by the dataclass decorators, in order."""
+        fields = {}
+        for base in cls.bases:
+            base = self.classes.get(base_name(base))
+            if base is not None and base is not cls:
+                fields.update(self.dataclass_fields(base))
+        if not self.decorators(cls) & DATACLASSES:
+            return fields
+        for stmt in cls.body:
+            if not isinstance(stmt, ast.AnnAssign) or not isinstance(stmt.target, ast.Name):
+                continue
+            ann = stmt.annotation
+            if base_name(ann.value if isinstance(ann, ast.Subscript) else ann) == "ClassVar":
+                continue
+            has_default = stmt.value is not None
+            # `field(...)`, `attr.ib(...)`
+            if isinstance(stmt.value, ast.Call) and base_name(stmt.value.func) in ("field", "ib", "attrib"):
+                kwargs = {kw.arg: kw.value for kw in stmt.value.keywords}
+                if const_value(kwargs.get("init")) == (True, False):
+                    continue
+                has_default = any(key in kwargs for key in ("default", "default_factory", "factory"))
+            fields[stmt.target.id] = (ann, has_default)
+        return fields
+
+    def dataclass_ctor(self, cls, qual):
+        self.used_typevars = []
+        self.erase_typevars = False
+        required, defaults = [], []
+        for name, (ann, has_default) in self.dataclass_fields(cls).items():
+            if has_default:
+                defaults.append("{} := {}".format(name, self.conv(ann)))
+            else:
+                required.append("{}: {}".format(name, self.conv(ann)))
+        ctor = self.format_sig(required + defaults, qual)
+        self.erase_typevars = True
+        return ctor
+
+    def has_external_base(self, cls):
+        for base in cls.bases:
+            name = base_name(base)
+            if name == "object":
+                continue
+            if name not in self.classes:
+                return True
+            if self.classes[name] is not cls and self.has_external_base(self.classes[name]):
+                return True
+        return False
+
+    # module
+    def generate(self):
+        classes = [cls for name, cls in self.classes.items() if self.is_exported(name)]
+        for cls in classes:
+            self.lines.append(".{}: ClassType".format(cls.name))
+            self.declared.add(cls.name)
+        for cls in classes:
+            for base in cls.bases:
+                base = self.class_type(base_name(base))
+                if base is not None and base != "." + cls.name:
+                    self.lines.append(".{} <: {}".format(cls.name, base))
+        self.module_body(self.tree.body)
+        for name in self.exported or []:
+            if name not in self.declared:
+                self.declared.add(name)
+                self.lines.append(".{}: Obj".format(name))
+        return "\n".join(self.lines) + "\n"
+
+    def module_body(self, body):
+        funcs = self.overloads(body)
+        for stmt in body:
+            if isinstance(stmt, (ast.FunctionDef, ast.AsyncFunctionDef)):
+                name = stmt.name
+                if self.is_exported(name) and name not in self.declared:
+                    self.declared.add(name)
+                    self.lines.append(".{}: {}".format(name, self.func_decl(funcs[name])))
+            elif isinstance(stmt, ast.ClassDef):
+                if stmt is self.classes.get(stmt.name) and self.is_exported(stmt.name):
+                    self.lines.extend(self.class_decl(stmt))
+            elif isinstance(stmt, ast.AnnAssign) and isinstance(stmt.target, ast.Name):
+                self.var_decl(stmt.target.id, self.conv(stmt.annotation))
+            elif isinstance(stmt, ast.Assign):
+                for target in stmt.targets:
+                    if isinstance(target, ast.Name) and target.id not in self.typevars:
+                        self.var_decl(target.id, self.infer(stmt.value))
+            elif isinstance(stmt, (ast.If, ast.Try)):
+                self.module_body(self.branches(stmt))
+
+    def var_decl(self, name, t):
+        if name.startswith("__") and name.endswith("__"):
+            return
+        if self.is_exported(name) and name not in self.declared:
+            self.declared.add(name)
+            self.lines.append(".{}: {}".format(name, t))
+
+
+def main():
+    with open(sys.argv[1], "rb") as f:
+        tree = ast.parse(f.read(), filename=sys.argv[1])
+    sys.stdout.write(DeclGenerator(tree).generate())
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,348 @@
+//! Source maps for transpiled Python scripts.
+//!
+//! The map file (`<name>.py.map`) follows the [Source Map Revision 3](https://sourcemaps.info/spec.html) format,
+//! so it can also be read by tools other than Erg.
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use erg_common::error::Location;
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn encode_vlq(buf: &mut String, value: i64) {
+    let mut vlq = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    };
+    loop {
+        let mut digit = vlq & 0b11111;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 0b100000;
+        }
+        buf.push(BASE64_CHARS[digit as usize] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+fn decode_vlq(segment: &str) -> Option<Vec<i64>> {
+    let mut values = vec![];
+    let mut value = 0i64;
+    let mut shift = 0;
+    for c in segment.bytes() {
+        let digit = BASE64_CHARS.iter().position(|&b| b == c)? as i64;
+        value += (digit & 0b11111) << shift;
+        if digit & 0b100000 != 0 {
+            shift += 5;
+        } else {
+            let negative = value & 1 == 1;
+            value >>= 1;
+            values.push(if negative { -value } else { value });
+            value = 0;
+            shift = 0;
+        }
+    }
+    Some(values)
+}
+
+fn escape_json_str(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Extracts the first string value of `key` (`"key": "value"` or `"key": ["value", ...]`).
+/// This only understands the maps written by `SourceMap::to_json`.
+fn json_str_field(json: &str, key: &str) -> Option<String> {
+    let start = json.find(&format!("\"{key}\""))? + key.len() + 2;
+    let rest = json[start..].trim_start().strip_prefix(':')?.trim_start();
+    let rest = rest.strip_prefix('[').unwrap_or(rest).trim_start();
+    let mut chars = rest.strip_prefix('"')?.chars();
+    let mut value = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(value),
+            '\\' => value.push(chars.next()?),
+            _ => value.push(c),
+        }
+    }
+    None
+}
+
+/// A position in the transpiled script and the location of the Erg expression it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mapping {
+    /// 1-origin
+    pub py_line: u32,
+    /// 0-origin
+    pub py_col: u32,
+    pub loc: Location,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    /// the transpiled script (e.g. `foo.py`)
+    pub file: String,
+    /// the original Erg script (e.g. `foo.er`)
+    pub source: String,
+    /// sorted by `(py_line, py_col)`
+    mappings: Vec<Mapping>,
+}
+
+impl SourceMap {
+    pub fn new(file: impl Into<String>, source: impl Into<String>) -> Self {
+        Self {
+            file: file.into(),
+            source: source.into(),
+            mappings: vec![],
+        }
+    }
+
+    pub fn mappings(&self) -> &[Mapping] {
+        &self.mappings
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mappings.is_empty()
+    }
+
+    /// `mapping` must not precede the mappings already pushed.
+    pub fn push(&mut self, mapping: Mapping) {
+        if mapping.loc.ln_begin().is_some() {
+            self.mappings.push(mapping);
+        }
+    }
+
+    /// Returns the location of the Erg expression which was transpiled into `py_line`.
+    /// If `py_col` is given, the innermost expression starting before the column is chosen.
+    pub fn lookup(&self, py_line: u32, py_col: Option<u32>) -> Option<Location> {
+        let mut on_line = self.mappings.iter().filter(|m| m.py_line == py_line);
+        let first = on_line.next()?;
+        let Some(py_col) = py_col else {
+            return Some(first.loc);
+        };
+        let found = std::iter::once(first)
+            .chain(on_line)
+            .take_while(|m| m.py_col <= py_col)
+            .last()
+            .unwrap_or(first);
+        Some(found.loc)
+    }
+
+    /// Encodes the mappings into the `mappings` field of a Source Map v3.
+    /// Erg locations only have the beginning positions in the map.
+    pub fn encode_mappings(&self) -> String {
+        let mut buf = String::new();
+        let mut line = 1;
+        let (mut prev_ln, mut prev_col) = (0i64, 0i64);
+        for (i, mapping) in self.mappings.iter().enumerate() {
+            let mut prev_py_col = 0i64;
+            if i > 0 && self.mappings[i - 1].py_line == mapping.py_line {
+                prev_py_col = self.mappings[i - 1].py_col as i64;
+                buf.push(',');
+            }
+            while line < mapping.py_line {
+                buf.push(';');
+                line += 1;
+            }
+            // `ln_begin` is 1-origin, but the source map lines are 0-origin
+            let ln = mapping.loc.ln_begin().unwrap_or(1) as i64 - 1;
+            let col = mapping.loc.col_begin().unwrap_or(0) as i64;
+            encode_vlq(&mut buf, mapping.py_col as i64 - prev_py_col);
+            // there is only one source
+            encode_vlq(&mut buf, 0);
+            encode_vlq(&mut buf, ln - prev_ln);
+            encode_vlq(&mut buf, col - prev_col);
+            (prev_ln, prev_col) = (ln, col);
+        }
+        buf
+    }
+
+    pub fn decode_mappings(&mut self, mappings: &str) -> Option<()> {
+        self.mappings.clear();
+        let (mut ln, mut col) = (0i64, 0i64);
+        for (py_line, segments) in mappings.split(';').enumerate() {
+            let mut py_col = 0i64;
+            for segment in segments.split(',').filter(|s| !s.is_empty()) {
+                let values = decode_vlq(segment)?;
+                let [d_py_col, _source, d_ln, d_col, ..] = values[..] else {
+                    // a segment without the source position
+                    continue;
+                };
+                py_col += d_py_col;
+                ln += d_ln;
+                col += d_col;
+                self.mappings.push(Mapping {
+                    py_line: py_line as u32 + 1,
+                    py_col: py_col as u32,
+                    loc: Location::range(ln as u32 + 1, col as u32, ln as u32 + 1, col as u32),
+                });
+            }
+        }
+        Some(())
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"version\": 3, \"file\": \"{}\", \"sources\": [\"{}\"], \"names\": [], \"mappings\": \"{}\"}}\n",
+            escape_json_str(&self.file),
+            escape_json_str(&self.source),
+            self.encode_mappings()
+        )
+    }
+
+    pub fn from_json(json: &str) -> Option<Self> {
+        let mut map = Self::new(
+            json_str_field(json, "file").unwrap_or_default(),
+            json_str_field(json, "sources")?,
+        );
+        map.decode_mappings(&json_str_field(json, "mappings")?)?;
+        Some(map)
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        Self::from_json(&json)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid source map"))
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+
+    fn is_target_file(&self, path: &str) -> bool {
+        Path::new(path).file_name() == Path::new(&self.file).file_name()
+    }
+
+    /// Rewrites the frames of a Python traceback which point to the transpiled script into the Erg locations.
+    /// ```text
+    /// Traceback (most recent call last):
+    ///   File "foo.py", line 42, in <module>
+    ///     assert (x == Int(2))
+    /// AssertionError
+    /// ```
+    /// becomes
+    /// ```text
+    /// Traceback (most recent call last):
+    ///   File "foo.er", line 3, in <module>
+    ///     assert x == 2
+    /// AssertionError
+    /// ```
+    /// The Python code lines of the rewritten frames are replaced with the Erg code if the source file is readable.
+    pub fn rewrite_traceback(&self, traceback: &str) -> String {
+        let erg_lines = fs::read_to_string(&self.source).ok();
+        let mut rewritten = String::new();
+        let mut in_rewritten_frame = false;
+        for line in traceback.lines() {
+            if in_rewritten_frame && line.starts_with("    ") {
+                // the Python code and the `^^^^` markers of the frame
+                continue;
+            }
+            in_rewritten_frame = false;
+            if let Some((indent, file, py_line, rest)) = Self::parse_frame(line) {
+                if self.is_target_file(file) {
+                    if let Some(ln) = self.lookup(py_line, None).and_then(|loc| loc.ln_begin()) {
+                        let _ = writeln!(
+                            rewritten,
+                            "{indent}File \"{}\", line {ln}{rest}",
+                            self.source
+                        );
+                        if let Some(code) = erg_lines
+                            .as_ref()
+                            .and_then(|lines| lines.lines().nth(ln as usize - 1))
+                        {
+                            let _ = writeln!(rewritten, "{indent}    {}", code.trim());
+                        }
+                        in_rewritten_frame = true;
+                        continue;
+                    }
+                }
+            }
+            rewritten += line;
+            rewritten.push('\n');
+        }
+        if !traceback.ends_with('\n') {
+            rewritten.pop();
+        }
+        rewritten
+    }
+
+    /// `  File "foo.py", line 42, in <module>` => `("  ", "foo.py", 42, ", in <module>")`
+    fn parse_frame(line: &str) -> Option<(&str, &str, u32, &str)> {
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+        let rest = trimmed.strip_prefix("File \"")?;
+        let (file, rest) = rest.split_once("\", line ")?;
+        let end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let py_line = rest[..end].parse().ok()?;
+        Some((indent, file, py_line, &rest[end..]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        let mut map = SourceMap::new("foo.py", "foo.er");
+        map.push(Mapping {
+            py_line: 3,
+            py_col: 0,
+            loc: Location::range(1, 0, 1, 5),
+        });
+        map.push(Mapping {
+            py_line: 3,
+            py_col: 12,
+            loc: Location::range(1, 4, 1, 5),
+        });
+        map.push(Mapping {
+            py_line: 40,
+            py_col: 4,
+            loc: Location::range(2, 4, 2, 9),
+        });
+        map.push(Mapping {
+            py_line: 41,
+            py_col: 0,
+            loc: Location::Unknown,
+        });
+        assert_eq!(
+            map.encode_mappings(),
+            ";;AAAA,YAAI;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;;IACA"
+        );
+        let decoded = SourceMap::from_json(&map.to_json()).unwrap();
+        assert_eq!(decoded.file, "foo.py");
+        assert_eq!(decoded.source, "foo.er");
+        assert_eq!(decoded.lookup(3, None).unwrap().ln_begin(), Some(1));
+        assert_eq!(decoded.lookup(3, Some(20)).unwrap().col_begin(), Some(4));
+        assert_eq!(decoded.lookup(40, None).unwrap().ln_begin(), Some(2));
+        assert_eq!(decoded.lookup(41, None), None);
+    }
+
+    #[test]
+    fn test_rewrite_traceback() {
+        let mut map = SourceMap::new("foo.py", "<nonexistent>/foo.er");
+        map.push(Mapping {
+            py_line: 42,
+            py_col: 0,
+            loc: Location::range(3, 0, 3, 13),
+        });
+        let traceback = "Traceback (most recent call last):
+  File \"/tmp/foo.py\", line 42, in <module>
+    assert (x == Int(2))
+AssertionError
+";
+        assert_eq!(
+            map.rewrite_traceback(traceback),
+            "Traceback (most recent call last):
+  File \"<nonexistent>/foo.er\", line 3, in <module>
+AssertionError
+"
+        );
+    }
+}
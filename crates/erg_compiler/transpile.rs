@@ -1,7 +1,7 @@
 use std::fs::File;
 use std::io::Write;
 
-use erg_common::error::{ErrorDisplay, ErrorKind, MultiErrorDisplay};
+use erg_common::error::{ErrorDisplay, ErrorKind, Location, MultiErrorDisplay};
 use erg_common::log;
 use erg_common::set::Set as HashSet;
 use erg_common::traits::BlockKind;
@@ -23,15 +23,19 @@ use crate::context::{Context, ContextProvider, ModuleContext};
 use crate::desugar_hir::HIRDesugarer;
 use crate::error::{CompileError, CompileErrors, CompileResult};
 use crate::hir::{
-    Accessor, Args, BinOp, Block, Call, ClassDef, Def, Dict, Expr, Identifier, Lambda, List,
-    Literal, Params, PatchDef, ReDef, Record, Set, Signature, Tuple, UnaryOp, HIR,
+    Accessor, Args, BinOp, Block, Call, ClassDef, Def, Dict, Expr, GuardClause, Identifier, Lambda,
+    List, Literal, Module, NonDefaultParamSignature, Params, PatchDef, ReDef, Record, Set,
+    Signature, Tuple, UnaryOp, HIR,
 };
 use crate::link_hir::HIRLinker;
 use crate::module::SharedCompilerResource;
+use crate::optimize::HIROptimizer;
+use crate::source_map::{Mapping, SourceMap};
 use crate::ty::typaram::OpKind;
-use crate::ty::value::ValueObj;
-use crate::ty::{Field, HasType, Type, VisibilityModifier};
+use crate::ty::value::{GenTypeObj, ValueObj};
+use crate::ty::{Field, HasType, TyParam, Type, VisibilityModifier};
 use crate::varinfo::{AbsLocation, VarInfo};
+use crate::wasm::WasmGenerator;
 
 /// patch method -> function
 /// patch attr -> variable
@@ -123,6 +127,10 @@ impl LastLineOperation {
 pub enum TranspiledFile {
     PyScript(PyScript),
     Json(Json),
+    Toml(Toml),
+    Yaml(Yaml),
+    PyStub(PyStub),
+    Wasm(Wasm),
 }
 
 impl TranspiledFile {
@@ -130,6 +138,10 @@ impl TranspiledFile {
         match self {
             Self::PyScript(script) => &script.code,
             Self::Json(json) => &json.code,
+            Self::Toml(toml) => &toml.code,
+            Self::Yaml(yaml) => &yaml.code,
+            Self::PyStub(stub) => &stub.code,
+            Self::Wasm(wasm) => &wasm.code,
         }
     }
 
@@ -137,6 +149,10 @@ impl TranspiledFile {
         match self {
             Self::PyScript(script) => script.code,
             Self::Json(json) => json.code,
+            Self::Toml(toml) => toml.code,
+            Self::Yaml(yaml) => yaml.code,
+            Self::PyStub(stub) => stub.code,
+            Self::Wasm(wasm) => wasm.code,
         }
     }
 
@@ -144,6 +160,10 @@ impl TranspiledFile {
         match self {
             Self::PyScript(script) => &script.filename,
             Self::Json(json) => &json.filename,
+            Self::Toml(toml) => &toml.filename,
+            Self::Yaml(yaml) => &yaml.filename,
+            Self::PyStub(stub) => &stub.filename,
+            Self::Wasm(wasm) => &wasm.filename,
         }
     }
 
@@ -151,6 +171,25 @@ impl TranspiledFile {
         match self {
             Self::PyScript(_) => "py",
             Self::Json(_) => "json",
+            Self::Toml(_) => "toml",
+            Self::Yaml(_) => "yaml",
+            Self::PyStub(_) => "pyi",
+            Self::Wasm(_) => "wat",
+        }
+    }
+
+    /// The binary which is written alongside `code` (e.g. `.wasm` of `.wat`)
+    pub fn binary(&self) -> Option<(&str, &[u8])> {
+        match self {
+            Self::Wasm(wasm) => Some(("wasm", &wasm.binary)),
+            _ => None,
+        }
+    }
+
+    pub fn source_map(&self) -> Option<&SourceMap> {
+        match self {
+            Self::PyScript(script) => Some(&script.source_map),
+            Self::Json(_) | Self::Toml(_) | Self::Yaml(_) | Self::PyStub(_) | Self::Wasm(_) => None,
         }
     }
 }
@@ -159,6 +198,8 @@ impl TranspiledFile {
 pub struct PyScript {
     pub filename: Str,
     pub code: String,
+    /// maps the lines of `code` to the original Erg code
+    pub source_map: SourceMap,
 }
 
 #[derive(Debug, Clone)]
@@ -167,6 +208,35 @@ pub struct Json {
     pub code: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct Toml {
+    pub filename: Str,
+    pub code: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Yaml {
+    pub filename: Str,
+    pub code: String,
+}
+
+/// A Python stub file (`.pyi`) for the transpiled script
+#[derive(Debug, Clone)]
+pub struct PyStub {
+    pub filename: Str,
+    pub code: String,
+}
+
+/// A WebAssembly module
+#[derive(Debug, Clone)]
+pub struct Wasm {
+    pub filename: Str,
+    /// the text format (WAT)
+    pub code: String,
+    /// the binary format
+    pub binary: Vec<u8>,
+}
+
 /// Generates a `PyScript` from an String or other File inputs.
 #[derive(Debug)]
 pub struct Transpiler {
@@ -230,8 +300,19 @@ impl Runnable for Transpiler {
         })?;
         artifact.warns.write_all_stderr();
         path.set_extension(artifact.object.extension());
-        let mut f = File::create(path).unwrap();
+        let mut f = File::create(&path).unwrap();
         f.write_all(artifact.object.code().as_bytes()).unwrap();
+        if let Some((ext, binary)) = artifact.object.binary() {
+            let mut bin_path = path.clone();
+            bin_path.set_extension(ext);
+            let mut f = File::create(&bin_path).unwrap();
+            f.write_all(binary).unwrap();
+        }
+        if let Some(source_map) = artifact.object.source_map() {
+            let mut map_path = path.into_os_string();
+            map_path.push(".map");
+            source_map.write(map_path).unwrap();
+        }
         Ok(ExitStatus::compile_passed(artifact.warns.len()))
     }
 
@@ -372,9 +453,37 @@ impl Transpiler {
                 let mut gen = JsonGenerator::new(self.cfg.copy());
                 Ok(TranspiledFile::Json(gen.transpile(hir)?))
             }
-            _ => Ok(TranspiledFile::PyScript(
-                self.script_generator.transpile(hir),
-            )),
+            Some(TranspileTarget::Toml) => {
+                let mut gen = TomlGenerator::new(self.cfg.copy());
+                Ok(TranspiledFile::Toml(gen.transpile(hir)?))
+            }
+            Some(TranspileTarget::Yaml) => {
+                let mut gen = YamlGenerator::new(self.cfg.copy());
+                Ok(TranspiledFile::Yaml(gen.transpile(hir)?))
+            }
+            Some(TranspileTarget::PyStub) => {
+                let mut gen = PyStubGenerator::new();
+                Ok(TranspiledFile::PyStub(gen.transpile(hir)))
+            }
+            Some(TranspileTarget::Wasm) => {
+                let mut gen = WasmGenerator::new(self.cfg.copy());
+                let module = gen.generate(&hir)?;
+                Ok(TranspiledFile::Wasm(Wasm {
+                    filename: hir.name,
+                    code: module.to_wat(),
+                    binary: module.encode(),
+                }))
+            }
+            _ => {
+                let mut script = self.script_generator.transpile(hir);
+                let mut py_path = self.cfg.dump_path();
+                py_path.set_extension("py");
+                script.source_map.file = py_path
+                    .file_name()
+                    .map_or(String::new(), |f| f.to_string_lossy().to_string());
+                script.source_map.source = self.cfg.input.full_path().display().to_string();
+                Ok(TranspiledFile::PyScript(script))
+            }
         }
     }
 
@@ -396,10 +505,20 @@ impl Transpiler {
         &mut self,
         artifact: CompleteArtifact,
     ) -> Result<CompleteArtifact, ErrorArtifact> {
+        // the WASM generator handles imports and control flows by itself
+        if self.cfg.transpile_target == Some(TranspileTarget::Wasm) {
+            return Ok(artifact);
+        }
         let linker = HIRLinker::new(&self.cfg, &self.shared.mod_cache);
         let hir = linker.link(artifact.object);
         let desugared = HIRDesugarer::desugar(hir);
-        Ok(CompleteArtifact::new(desugared, artifact.warns))
+        let optimized = HIROptimizer::optimize(
+            self.cfg.clone(),
+            self.shared.clone(),
+            self.builder.get_context(),
+            desugared,
+        );
+        Ok(CompleteArtifact::new(optimized, artifact.warns))
     }
 
     pub fn pop_mod_ctx(&mut self) -> Option<ModuleContext> {
@@ -431,7 +550,16 @@ pub struct PyScriptGenerator {
     builtin_types_loaded: bool,
     builtin_control_loaded: bool,
     convertors_loaded: bool,
+    try_op_loaded: bool,
+    /// the depth of the subroutines being transpiled
+    subr_level: usize,
+    /// whether `?` is used in the subroutine being transpiled
+    try_used: bool,
+    /// the level of the class body being transpiled (methods must not be `global`)
+    class_body_level: Option<usize>,
     prelude: String,
+    /// the locations of the expressions marked by `mark_loc`
+    marked_locs: Vec<Location>,
 }
 
 impl PyScriptGenerator {
@@ -447,24 +575,80 @@ impl PyScriptGenerator {
             builtin_types_loaded: false,
             builtin_control_loaded: false,
             convertors_loaded: false,
+            try_op_loaded: false,
+            subr_level: 0,
+            try_used: false,
+            class_body_level: None,
             prelude: String::new(),
+            marked_locs: vec![],
         }
     }
 
     pub fn transpile(&mut self, hir: HIR) -> PyScript {
         let mut code = String::new();
         for chunk in hir.module.into_iter() {
+            let mark = self.mark_loc(chunk.loc());
             let expr = self.transpile_expr(chunk);
             if !expr.is_empty() {
+                code += &mark;
                 code += &expr;
                 code.push('\n');
             }
         }
         code = std::mem::take(&mut self.prelude) + &code;
+        let (code, source_map) = self.resolve_marks(code);
         PyScript {
             filename: hir.name,
             code,
+            source_map,
+        }
+    }
+
+    /// Returns a mark (`\0<id>\0`) to be embedded at the start of the code transpiled from the expression at `loc`.
+    /// Since the generated code may be moved to the prelude, the positions are determined in `resolve_marks`.
+    /// `\0` never appears in the generated code because it is escaped in string literals.
+    fn mark_loc(&mut self, loc: Location) -> String {
+        if loc.ln_begin().is_none() {
+            return String::new();
+        }
+        self.marked_locs.push(loc);
+        format!("\0{}\0", self.marked_locs.len() - 1)
+    }
+
+    /// Removes the marks from `code` and records their positions.
+    fn resolve_marks(&mut self, code: String) -> (String, SourceMap) {
+        let locs = std::mem::take(&mut self.marked_locs);
+        let mut source_map = SourceMap::default();
+        let mut resolved = String::with_capacity(code.len());
+        let (mut py_line, mut py_col) = (1, 0);
+        let mut chars = code.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\0' => {
+                    let id = chars
+                        .by_ref()
+                        .take_while(|c| *c != '\0')
+                        .collect::<String>();
+                    if let Some(loc) = id.parse::<usize>().ok().and_then(|id| locs.get(id)) {
+                        source_map.push(Mapping {
+                            py_line,
+                            py_col,
+                            loc: *loc,
+                        });
+                    }
+                    continue;
+                }
+                '\n' => {
+                    py_line += 1;
+                    py_col = 0;
+                }
+                _ => {
+                    py_col += 1;
+                }
+            }
+            resolved.push(c);
         }
+        (resolved, source_map)
     }
 
     // TODO: more smart way
@@ -528,6 +712,23 @@ impl PyScriptGenerator {
         }
     }
 
+    fn load_try_op_if_not(&mut self) {
+        if !self.try_op_loaded {
+            self.prelude += "class TryReturn__(Exception):\n";
+            self.prelude += "    def __init__(self, value):\n";
+            self.prelude += "        self.value = value\n";
+            self.prelude += "def try_return__(x):\n";
+            self.prelude += "    if isinstance(x, BaseException):\n";
+            self.prelude += "        raise TryReturn__(x)\n";
+            self.prelude += "    return x\n";
+            self.prelude += "def try_raise__(x):\n";
+            self.prelude += "    if isinstance(x, BaseException):\n";
+            self.prelude += "        raise x\n";
+            self.prelude += "    return x\n";
+            self.try_op_loaded = true;
+        }
+    }
+
     fn load_builtin_types_if_not(&mut self) {
         if !self.builtin_types_loaded {
             self.load_builtin_controls_if_not();
@@ -642,8 +843,10 @@ impl PyScriptGenerator {
             Expr::Compound(comp) => {
                 let mut code = "".to_string();
                 for expr in comp.into_iter() {
+                    let mark = self.mark_loc(expr.loc());
                     let expr = self.transpile_expr(expr);
                     if !expr.is_empty() {
+                        code += &mark;
                         code += &expr;
                         code += &format!("\n{}", "    ".repeat(self.level));
                     }
@@ -749,7 +952,17 @@ impl PyScriptGenerator {
 
     fn transpile_unaryop(&mut self, unary: UnaryOp) -> String {
         let mut code = "".to_string();
-        if unary.op.kind == TokenKind::Mutate {
+        if unary.op.kind == TokenKind::Try {
+            self.load_try_op_if_not();
+            // Python has no expression that returns from a function,
+            // so the error is thrown and caught by the enclosing subroutine (see `wrap_try_body`)
+            if self.subr_level == 0 {
+                code += "try_raise__(";
+            } else {
+                self.try_used = true;
+                code += "try_return__(";
+            }
+        } else if unary.op.kind == TokenKind::Mutate {
             self.load_mutate_op_if_not();
             code += "mutate_operator(";
         } else {
@@ -956,7 +1169,22 @@ impl PyScriptGenerator {
                                     .join(" | ");
                                 code += &format!("case ({patterns}) as {param}:\n");
                             } else {
-                                todo!("{other}")
+                                // e.g. `i: {I: Int | I >= 0}` => `case i if contains_operator(Int, i) and i >= 0:`
+                                let conds = arm
+                                    .params
+                                    .guards
+                                    .iter()
+                                    .filter_map(|guard| match guard {
+                                        GuardClause::Condition(cond) => {
+                                            Some(self.transpile_expr(cond.clone()))
+                                        }
+                                        GuardClause::Bind(_) => None,
+                                    })
+                                    .collect::<Vec<_>>();
+                                if conds.is_empty() {
+                                    todo!("{other}")
+                                }
+                                code += &format!("case {param} if {}:\n", conds.join(" and "));
                             }
                         }
                         None => {
@@ -1001,42 +1229,33 @@ impl PyScriptGenerator {
         } else {
             Enclosure::Paren
         };
-        let is_py_api = if let Some(attr) = &call.attr_name {
-            let is_py_api = attr.is_py_api();
-            if let Some(name) = debind(attr) {
-                let name = demangle(&name);
-                return format!(
-                    "{name}({}, {})",
-                    self.transpile_expr(*call.obj),
-                    self.transpile_args(call.args, is_py_api, enc)
-                );
-            }
-            is_py_api
-        } else {
-            call.obj.is_py_api()
-        };
+        if let Some(name) = call.attr_name.as_ref().and_then(debind) {
+            let name = demangle(&name);
+            return format!(
+                "{name}({}, {})",
+                self.transpile_expr(*call.obj),
+                self.transpile_args(call.args, enc)
+            );
+        }
         let mut code = format!("({})", self.transpile_expr(*call.obj));
         if let Some(attr) = call.attr_name {
             code += &format!(".{}", Self::transpile_ident(attr));
         }
-        code += &self.transpile_args(call.args, is_py_api, enc);
+        code += &self.transpile_args(call.args, enc);
         code
     }
 
-    fn transpile_args(&mut self, mut args: Args, is_py_api: bool, enc: Enclosure) -> String {
+    fn transpile_args(&mut self, mut args: Args, enc: Enclosure) -> String {
         let mut code = String::new();
         code.push(enc.open());
         while let Some(arg) = args.try_remove_pos(0) {
             code += &self.transpile_expr(arg.expr);
             code.push(',');
         }
+        // parameter names are not mangled (see `transpile_name`),
+        // and the keywords bound to `**kwargs` become the keys of the dict
         while let Some(arg) = args.try_remove_kw(0) {
-            let escape = if is_py_api { "" } else { "__" };
-            code += &format!(
-                "{}{escape}={},",
-                arg.keyword.content,
-                self.transpile_expr(arg.expr)
-            );
+            code += &format!("{}={},", arg.keyword.content, self.transpile_expr(arg.expr));
         }
         code.push(enc.close());
         code
@@ -1051,7 +1270,8 @@ impl PyScriptGenerator {
             return demangle(py_name);
         }
         let name = replace_non_symbolic(name);
-        if vis.is_public() || &name == "_" {
+        // parameters can be passed as keyword arguments, so they are not mangled
+        if vis.is_public() || &name == "_" || vi.kind.is_parameter() {
             name.to_string()
         } else {
             let def_line = vi.def_loc.loc.ln_begin().unwrap_or(0);
@@ -1085,6 +1305,25 @@ impl PyScriptGenerator {
                 _ => unreachable!(),
             }
         }
+        if let Some(var_params) = params.var_params {
+            match var_params.raw.pat {
+                ParamPattern::VarName(param) => {
+                    code += &format!(
+                        "*{},",
+                        Self::transpile_name(
+                            &VisibilityModifier::Private,
+                            param.inspect(),
+                            &var_params.vi,
+                        )
+                    );
+                }
+                ParamPattern::Discard(_) => {
+                    code += &format!("*_{},", self.fresh_var_n);
+                    self.fresh_var_n += 1;
+                }
+                _ => unreachable!(),
+            }
+        }
         for default in params.defaults {
             match default.sig.raw.pat {
                 ParamPattern::VarName(param) => {
@@ -1106,6 +1345,25 @@ impl PyScriptGenerator {
                 _ => unreachable!(),
             }
         }
+        if let Some(kw_var_params) = params.kw_var_params {
+            match kw_var_params.raw.pat {
+                ParamPattern::VarName(param) => {
+                    code += &format!(
+                        "**{},",
+                        Self::transpile_name(
+                            &VisibilityModifier::Private,
+                            param.inspect(),
+                            &kw_var_params.vi,
+                        )
+                    );
+                }
+                ParamPattern::Discard(_) => {
+                    code += &format!("**_{},", self.fresh_var_n);
+                    self.fresh_var_n += 1;
+                }
+                _ => unreachable!(),
+            }
+        }
         code
     }
 
@@ -1115,6 +1373,8 @@ impl PyScriptGenerator {
         let last = block.len().saturating_sub(1);
         for (i, chunk) in block.into_iter().enumerate() {
             code += &"    ".repeat(self.level);
+            let stmt_start = code.len();
+            let mark = self.mark_loc(chunk.loc());
             if i == last {
                 match last_op {
                     Return => {
@@ -1128,6 +1388,7 @@ impl PyScriptGenerator {
             }
             let expr = self.transpile_expr(chunk);
             if !expr.is_empty() {
+                code.insert_str(stmt_start, &mark);
                 code += &expr;
                 code.push('\n');
             }
@@ -1137,26 +1398,60 @@ impl PyScriptGenerator {
     }
 
     fn transpile_lambda(&mut self, lambda: Lambda) -> String {
-        if lambda.body.len() > 1 {
+        let try_used = std::mem::take(&mut self.try_used);
+        self.subr_level += 1;
+        let params = self.transpile_params(lambda.params);
+        let code = if lambda.body.len() > 1 {
             let name = format!("lambda_{}__", self.fresh_var_n);
             self.fresh_var_n += 1;
-            let mut code = format!("def {name}({}):\n", self.transpile_params(lambda.params));
-            code += &self.transpile_block(lambda.body, Return);
-            self.prelude += &code;
+            let body = self.transpile_block(lambda.body, Return);
+            self.prelude += &format!("def {name}({params}):\n{}", self.wrap_try_body(body));
             name
         } else {
-            let mut code = format!("(lambda {}:", self.transpile_params(lambda.params));
-            code += &self.transpile_block(lambda.body, Discard);
-            code.pop(); // \n
-            code.push(')');
-            code
+            let mut body = self.transpile_block(lambda.body, Discard);
+            body.pop(); // \n
+            if self.try_used {
+                // a lambda cannot catch exceptions, so it is converted to a function
+                let name = format!("lambda_{}__", self.fresh_var_n);
+                self.fresh_var_n += 1;
+                let indent = "    ".repeat(self.level + 1);
+                let body = format!("{indent}return {}\n", body.trim_start());
+                self.prelude += &format!("def {name}({params}):\n{}", self.wrap_try_body(body));
+                name
+            } else {
+                format!("(lambda {params}:{body})")
+            }
+        };
+        self.subr_level -= 1;
+        self.try_used = try_used;
+        code
+    }
+
+    /// If `?` is used in the body, wrap it with `try` so that the propagated error is returned.
+    /// ```python
+    /// try:
+    ///     <body>
+    /// except TryReturn__ as e:
+    ///     return e.value
+    /// ```
+    fn wrap_try_body(&self, body: String) -> String {
+        if !self.try_used {
+            return body;
         }
+        let indent = "    ".repeat(self.level + 1);
+        let mut code = format!("{indent}try:\n");
+        for line in body.lines() {
+            code += &format!("    {line}\n");
+        }
+        code += &format!("{indent}except TryReturn__ as e:\n");
+        code += &format!("{indent}    return e.value\n");
+        code
     }
 
     // TODO: trait definition
     fn transpile_def(&mut self, mut def: Def) -> String {
         // HACK: allow reference to local variables in tmp functions
-        let mut code = if self.level == 0 {
+        let mut code = if self.level == 0 || self.class_body_level == Some(self.level) {
             "".to_string()
         } else {
             let name = Self::transpile_ident(def.sig.ident().clone());
@@ -1167,6 +1462,9 @@ impl PyScriptGenerator {
                 format!("global {name}\n{}", "    ".repeat(self.level))
             }
         };
+        if !code.is_empty() {
+            code += &self.mark_loc(def.loc());
+        }
         match def.sig {
             Signature::Var(var) => {
                 code += &format!("{} = ", Self::transpile_ident(var.ident));
@@ -1189,7 +1487,12 @@ impl PyScriptGenerator {
                     Self::transpile_ident(subr.ident),
                     self.transpile_params(subr.params)
                 );
-                code += &self.transpile_block(def.body.block, Return);
+                let try_used = std::mem::take(&mut self.try_used);
+                self.subr_level += 1;
+                let body = self.transpile_block(def.body.block, Return);
+                code += &self.wrap_try_body(body);
+                self.subr_level -= 1;
+                self.try_used = try_used;
                 code
             }
             Signature::Glob(_) => todo!(),
@@ -1223,7 +1526,9 @@ impl PyScriptGenerator {
             code += &format!("def new(x): return {class_name}.__call__(x)\n");
         }
         let methods = ClassDef::take_all_methods(classdef.methods_list);
+        let class_body_level = self.class_body_level.replace(self.level + 1);
         code += &self.transpile_block(methods, Discard);
+        self.class_body_level = class_body_level;
         code
     }
 
@@ -1261,6 +1566,68 @@ impl PyScriptGenerator {
     }
 }
 
+/// Evaluates a constant expression. `binds` are the values of the constants defined before.
+fn expr_into_value(binds: &HashMap<AbsLocation, ValueObj>, expr: Expr) -> Option<ValueObj> {
+    match expr {
+        Expr::List(List::Normal(lis)) => {
+            let mut vals = vec![];
+            for elem in lis.elems.pos_args {
+                if let Some(val) = expr_into_value(binds, elem.expr) {
+                    vals.push(val);
+                } else {
+                    return None;
+                }
+            }
+            Some(ValueObj::List(vals.into()))
+        }
+        Expr::List(List::WithLength(lis)) => {
+            let len = lis
+                .len
+                .and_then(|len| expr_into_value(binds, *len))
+                .and_then(|v| usize::try_from(&v).ok())?;
+            let vals = vec![expr_into_value(binds, *lis.elem)?; len];
+            Some(ValueObj::List(vals.into()))
+        }
+        Expr::Tuple(Tuple::Normal(tup)) => {
+            let mut vals = vec![];
+            for elem in tup.elems.pos_args {
+                if let Some(val) = expr_into_value(binds, elem.expr) {
+                    vals.push(val);
+                } else {
+                    return None;
+                }
+            }
+            Some(ValueObj::Tuple(vals.into()))
+        }
+        Expr::Dict(Dict::Normal(dic)) => {
+            let mut kvs = dict! {};
+            for kv in dic.kvs {
+                let key = expr_into_value(binds, kv.key)?;
+                let val = expr_into_value(binds, kv.value)?;
+                kvs.insert(key, val);
+            }
+            Some(ValueObj::Dict(kvs))
+        }
+        Expr::Record(rec) => {
+            let mut attrs = dict! {};
+            for mut attr in rec.attrs {
+                let field = Field::from(attr.sig.ident());
+                let val = expr_into_value(binds, attr.body.block.remove(0))?;
+                attrs.insert(field, val);
+            }
+            Some(ValueObj::Record(attrs))
+        }
+        Expr::Literal(lit) => Some(lit.value),
+        Expr::Accessor(acc) => binds.get(&acc.var_info().def_loc).cloned(),
+        Expr::BinOp(bin) => {
+            let lhs = expr_into_value(binds, *bin.lhs)?;
+            let rhs = expr_into_value(binds, *bin.rhs)?;
+            lhs.try_binary(rhs, OpKind::try_from(bin.op.kind).ok()?)
+        }
+        _ => None,
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct JsonGenerator {
     cfg: ErgConfig,
@@ -1299,64 +1666,7 @@ impl JsonGenerator {
     }
 
     fn expr_into_value(&self, expr: Expr) -> Option<ValueObj> {
-        match expr {
-            Expr::List(List::Normal(lis)) => {
-                let mut vals = vec![];
-                for elem in lis.elems.pos_args {
-                    if let Some(val) = self.expr_into_value(elem.expr) {
-                        vals.push(val);
-                    } else {
-                        return None;
-                    }
-                }
-                Some(ValueObj::List(vals.into()))
-            }
-            Expr::List(List::WithLength(lis)) => {
-                let len = lis
-                    .len
-                    .and_then(|len| self.expr_into_value(*len))
-                    .and_then(|v| usize::try_from(&v).ok())?;
-                let vals = vec![self.expr_into_value(*lis.elem)?; len];
-                Some(ValueObj::List(vals.into()))
-            }
-            Expr::Tuple(Tuple::Normal(tup)) => {
-                let mut vals = vec![];
-                for elem in tup.elems.pos_args {
-                    if let Some(val) = self.expr_into_value(elem.expr) {
-                        vals.push(val);
-                    } else {
-                        return None;
-                    }
-                }
-                Some(ValueObj::Tuple(vals.into()))
-            }
-            Expr::Dict(Dict::Normal(dic)) => {
-                let mut kvs = dict! {};
-                for kv in dic.kvs {
-                    let key = self.expr_into_value(kv.key)?;
-                    let val = self.expr_into_value(kv.value)?;
-                    kvs.insert(key, val);
-                }
-                Some(ValueObj::Dict(kvs))
-            }
-            Expr::Record(rec) => {
-                let mut attrs = dict! {};
-                for mut attr in rec.attrs {
-                    let field = Field::from(attr.sig.ident());
-                    let val = self.expr_into_value(attr.body.block.remove(0))?;
-                    attrs.insert(field, val);
-                }
-                Some(ValueObj::Record(attrs))
-            }
-            Expr::Literal(lit) => Some(lit.value),
-            Expr::Accessor(acc) => self.binds.get(&acc.var_info().def_loc).cloned(),
-            Expr::BinOp(bin) => {
-                let lhs = self.expr_into_value(*bin.lhs)?;
-                let rhs = self.expr_into_value(*bin.rhs)?;
-                lhs.try_binary(rhs, OpKind::try_from(bin.op.kind).ok()?)
-            }
-            _ => None,
-        }
+        expr_into_value(&self.binds, expr)
     }
 
     fn transpile_def(&mut self, mut def: Def) -> String {
@@ -1464,3 +1774,827 @@ impl JsonGenerator {
         }
     }
 }
+
+/// Evaluates the public constants of a module for the data formats (TOML, YAML).
+#[derive(Debug, Default)]
+struct ConstEvaluator {
+    cfg: ErgConfig,
+    binds: HashMap<AbsLocation, ValueObj>,
+    errors: CompileErrors,
+}
+
+impl ConstEvaluator {
+    fn new(cfg: ErgConfig) -> Self {
+        Self {
+            cfg,
+            binds: HashMap::new(),
+            errors: CompileErrors::empty(),
+        }
+    }
+
+    /// Returns the public constants in the order of definition.
+    /// `check` returns the (first) value which cannot be represented in `format`.
+    fn eval_module(
+        &mut self,
+        module: Module,
+        format: &str,
+        check: impl Fn(&ValueObj) -> Result<(), ValueObj>,
+    ) -> Vec<(Str, ValueObj)> {
+        let mut consts = vec![];
+        for chunk in module.into_iter() {
+            match chunk {
+                Expr::Def(def) => {
+                    if let Some(entry) = self.eval_def(def, format, &check) {
+                        consts.push(entry);
+                    }
+                }
+                // type definitions, imports, etc. have no values to be written
+                Expr::ClassDef(_) | Expr::PatchDef(_) | Expr::Import(_) | Expr::Dummy(_) => {}
+                other => {
+                    self.errors.push(CompileError::not_const_expr(
+                        self.cfg.input.clone(),
+                        line!() as usize,
+                        other.loc(),
+                        "".into(),
+                    ));
+                }
+            }
+        }
+        consts
+    }
+
+    fn eval_def(
+        &mut self,
+        mut def: Def,
+        format: &str,
+        check: impl Fn(&ValueObj) -> Result<(), ValueObj>,
+    ) -> Option<(Str, ValueObj)> {
+        let loc = def.loc();
+        let value = def
+            .body
+            .block
+            .first()
+            .cloned()
+            .and_then(|expr| expr_into_value(&self.binds, expr));
+        let Some(value) = value else {
+            if def.sig.vis().is_public() {
+                let loc = def.body.block.pop().map_or(loc, |expr| expr.loc());
+                self.errors.push(CompileError::not_const_expr(
+                    self.cfg.input.clone(),
+                    line!() as usize,
+                    loc,
+                    def.sig.inspect().to_string(),
+                ));
+            }
+            return None;
+        };
+        self.binds
+            .insert(def.sig.ident().vi.def_loc.clone(), value.clone());
+        if !def.sig.vis().is_public() {
+            return None;
+        }
+        if let Err(unrepresentable) = check(&value) {
+            self.errors.push(CompileError::not_representable_error(
+                self.cfg.input.clone(),
+                line!() as usize,
+                loc,
+                &unrepresentable.to_string(),
+                format,
+                def.sig.inspect().to_string(),
+            ));
+            return None;
+        }
+        Some((def.sig.inspect().clone(), value))
+    }
+}
+
+/// The key/value pairs of a TOML table.
+type TomlTable = Vec<(Str, ValueObj)>;
+
+/// Converts the public constants of a module into a TOML document.
+/// Records, data class instances and dicts with `Str` keys become tables,
+/// and lists of them become arrays of tables.
+#[derive(Debug, Default)]
+pub struct TomlGenerator {
+    consts: ConstEvaluator,
+}
+
+impl TomlGenerator {
+    pub fn new(cfg: ErgConfig) -> Self {
+        Self {
+            consts: ConstEvaluator::new(cfg),
+        }
+    }
+
+    pub fn transpile(&mut self, hir: HIR) -> CompileResult<Toml> {
+        let root = self
+            .consts
+            .eval_module(hir.module, "TOML", Self::check_representable);
+        let mut code = String::new();
+        Self::write_table(&mut code, "", root);
+        if self.consts.errors.is_empty() {
+            Ok(Toml {
+                filename: hir.name,
+                code,
+            })
+        } else {
+            Err(self.consts.errors.take_all().into())
+        }
+    }
+
+    /// Returns the (first) value which cannot be represented in TOML.
+    fn check_representable(value: &ValueObj) -> Result<(), ValueObj> {
+        match value {
+            ValueObj::Int(_)
+            | ValueObj::Nat(_)
+            | ValueObj::Float(_)
+            | ValueObj::Str(_)
+            | ValueObj::Bool(_)
+            | ValueObj::Inf
+            | ValueObj::NegInf => Ok(()),
+            ValueObj::List(elems) | ValueObj::Tuple(elems) => {
+                elems.iter().try_for_each(Self::check_representable)
+            }
+            ValueObj::Record(_) | ValueObj::DataClass { .. } | ValueObj::Dict(_) => {
+                let Some(table) = Self::as_table(value) else {
+                    return Err(value.clone());
+                };
+                table
+                    .iter()
+                    .try_for_each(|(_, val)| Self::check_representable(val))
+            }
+            _ => Err(value.clone()),
+        }
+    }
+
+    /// Returns `None` if `value` is not a table (or a dict with non-`Str` keys).
+    /// The keys are sorted because records and dicts are unordered.
+    fn as_table(value: &ValueObj) -> Option<TomlTable> {
+        let mut table = match value {
+            ValueObj::Record(fields) | ValueObj::DataClass { fields, .. } => fields
+                .iter()
+                .map(|(field, val)| (field.symbol.clone(), val.clone()))
+                .collect::<TomlTable>(),
+            ValueObj::Dict(dict) => dict
+                .iter()
+                .map(|(key, val)| match key {
+                    ValueObj::Str(key) => Some((key.clone(), val.clone())),
+                    _ => None,
+                })
+                .collect::<Option<TomlTable>>()?,
+            _ => return None,
+        };
+        table.sort_by(|(l, _), (r, _)| l.cmp(r));
+        Some(table)
+    }
+
+    /// `[[foo]]` is used for non-empty lists of tables.
+    fn as_array_of_tables(value: &ValueObj) -> Option<Vec<TomlTable>> {
+        match value {
+            ValueObj::List(elems) | ValueObj::Tuple(elems) if !elems.is_empty() => {
+                elems.iter().map(Self::as_table).collect()
+            }
+            _ => None,
+        }
+    }
+
+    /// The key/value pairs are written first, since the following ones would belong to the sub-tables.
+    fn write_table(code: &mut String, prefix: &str, table: TomlTable) {
+        let mut sub_tables = vec![];
+        for (key, value) in table.into_iter() {
+            if Self::as_table(&value).is_some() || Self::as_array_of_tables(&value).is_some() {
+                sub_tables.push((key, value));
+            } else {
+                *code += &format!("{} = {}\n", Self::key(&key), Self::inline_value(&value));
+            }
+        }
+        for (key, value) in sub_tables.into_iter() {
+            let path = format!("{prefix}{}", Self::key(&key));
+            if !code.is_empty() {
+                code.push('\n');
+            }
+            if let Some(table) = Self::as_table(&value) {
+                *code += &format!("[{path}]\n");
+                Self::write_table(code, &format!("{path}."), table);
+            } else if let Some(tables) = Self::as_array_of_tables(&value) {
+                for (i, table) in tables.into_iter().enumerate() {
+                    if i > 0 {
+                        code.push('\n');
+                    }
+                    *code += &format!("[[{path}]]\n");
+                    Self::write_table(code, &format!("{path}."), table);
+                }
+            }
+        }
+    }
+
+    fn inline_value(value: &ValueObj) -> String {
+        match value {
+            ValueObj::Str(s) => Self::string(s),
+            ValueObj::Bool(b) => b.to_string(),
+            ValueObj::Int(i) => i.to_string(),
+            ValueObj::Nat(n) => n.to_string(),
+            ValueObj::Float(f) if f.is_nan() => "nan".to_string(),
+            ValueObj::Float(f) if f.is_infinite() => {
+                if f.is_sign_positive() { "inf" } else { "-inf" }.to_string()
+            }
+            // `{:?}` does not omit `.0`
+            ValueObj::Float(f) => format!("{:?}", **f),
+            ValueObj::Inf => "inf".to_string(),
+            ValueObj::NegInf => "-inf".to_string(),
+            ValueObj::List(elems) | ValueObj::Tuple(elems) => {
+                let elems = elems.iter().map(Self::inline_value).collect::<Vec<_>>();
+                format!("[{}]", elems.join(", "))
+            }
+            _ => {
+                let table = Self::as_table(value).unwrap_or_default();
+                if table.is_empty() {
+                    return "{}".to_string();
+                }
+                let entries = table
+                    .iter()
+                    .map(|(key, val)| format!("{} = {}", Self::key(key), Self::inline_value(val)))
+                    .collect::<Vec<_>>();
+                format!("{{ {} }}", entries.join(", "))
+            }
+        }
+    }
+
+    /// Bare keys can only contain ASCII letters, ASCII digits, underscores, and dashes.
+    fn key(key: &str) -> String {
+        if !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            key.to_string()
+        } else {
+            Self::string(key)
+        }
+    }
+
+    fn string(s: &str) -> String {
+        let mut escaped = String::with_capacity(s.len() + 2);
+        escaped.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => escaped += "\\\"",
+                '\\' => escaped += "\\\\",
+                '\n' => escaped += "\\n",
+                '\r' => escaped += "\\r",
+                '\t' => escaped += "\\t",
+                '\u{08}' => escaped += "\\b",
+                '\u{0C}' => escaped += "\\f",
+                c if c.is_control() => escaped += &format!("\\u{:04X}", c as u32),
+                c => escaped.push(c),
+            }
+        }
+        escaped.push('"');
+        escaped
+    }
+}
+
+/// Converts the public constants of a module into a YAML document.
+#[derive(Debug, Default)]
+pub struct YamlGenerator {
+    consts: ConstEvaluator,
+}
+
+impl YamlGenerator {
+    pub fn new(cfg: ErgConfig) -> Self {
+        Self {
+            consts: ConstEvaluator::new(cfg),
+        }
+    }
+
+    pub fn transpile(&mut self, hir: HIR) -> CompileResult<Yaml> {
+        let root = self
+            .consts
+            .eval_module(hir.module, "YAML", Self::check_representable)
+            .into_iter()
+            .map(|(key, val)| (ValueObj::Str(key), val))
+            .collect();
+        let mut code = String::new();
+        Self::write_mapping(&mut code, 0, root);
+        if self.consts.errors.is_empty() {
+            Ok(Yaml {
+                filename: hir.name,
+                code,
+            })
+        } else {
+            Err(self.consts.errors.take_all().into())
+        }
+    }
+
+    /// Returns the (first) value which cannot be represented in YAML.
+    fn check_representable(value: &ValueObj) -> Result<(), ValueObj> {
+        match value {
+            ValueObj::Int(_)
+            | ValueObj::Nat(_)
+            | ValueObj::Float(_)
+            | ValueObj::Str(_)
+            | ValueObj::Bool(_)
+            | ValueObj::None
+            | ValueObj::Inf
+            | ValueObj::NegInf => Ok(()),
+            ValueObj::List(elems) | ValueObj::Tuple(elems) => {
+                elems.iter().try_for_each(Self::check_representable)
+            }
+            ValueObj::Record(_) | ValueObj::DataClass { .. } | ValueObj::Dict(_) => {
+                let Some(mapping) = Self::as_mapping(value) else {
+                    return Err(value.clone());
+                };
+                mapping
+                    .iter()
+                    .try_for_each(|(_, val)| Self::check_representable(val))
+            }
+            _ => Err(value.clone()),
+        }
+    }
+
+    /// Returns `None` if `value` is not a mapping (or a dict with non-scalar keys).
+    /// The keys are sorted because records and dicts are unordered.
+    fn as_mapping(value: &ValueObj) -> Option<Vec<(ValueObj, ValueObj)>> {
+        let mut mapping = match value {
+            ValueObj::Record(fields) | ValueObj::DataClass { fields, .. } => fields
+                .iter()
+                .map(|(field, val)| (ValueObj::Str(field.symbol.clone()), val.clone()))
+                .collect::<Vec<_>>(),
+            ValueObj::Dict(dict) => dict
+                .iter()
+                .map(|(key, val)| Self::is_scalar(key).then(|| (key.clone(), val.clone())))
+                .collect::<Option<Vec<_>>>()?,
+            _ => return None,
+        };
+        mapping.sort_by_key(|(key, _)| Self::scalar(key));
+        Some(mapping)
+    }
+
+    fn as_sequence(value: &ValueObj) -> Option<&[ValueObj]> {
+        match value {
+            ValueObj::List(elems) | ValueObj::Tuple(elems) => Some(elems),
+            _ => None,
+        }
+    }
+
+    fn is_scalar(value: &ValueObj) -> bool {
+        matches!(
+            value,
+            ValueObj::Int(_)
+                | ValueObj::Nat(_)
+                | ValueObj::Float(_)
+                | ValueObj::Str(_)
+                | ValueObj::Bool(_)
+                | ValueObj::None
+                | ValueObj::Inf
+                | ValueObj::NegInf
+        )
+    }
+
+    /// Empty collections are written in the flow style (`[]`, `{}`).
+    fn is_block(value: &ValueObj) -> bool {
+        Self::as_sequence(value).is_some_and(|elems| !elems.is_empty())
+            || Self::as_mapping(value).is_some_and(|mapping| !mapping.is_empty())
+    }
+
+    fn write_mapping(code: &mut String, indent: usize, mapping: Vec<(ValueObj, ValueObj)>) {
+        for (key, value) in mapping.into_iter() {
+            *code += &format!("{}{}:", " ".repeat(indent), Self::scalar(&key));
+            Self::write_value(code, indent, value);
+        }
+    }
+
+    fn write_sequence(code: &mut String, indent: usize, elems: &[ValueObj]) {
+        for elem in elems.iter() {
+            if Self::is_block(elem) {
+                // the first line of the nested block follows `- `
+                let mut block = String::new();
+                Self::write_block(&mut block, indent + 2, elem.clone());
+                *code += &format!("{}- {}", " ".repeat(indent), &block[indent + 2..]);
+            } else {
+                *code += &format!("{}- {}\n", " ".repeat(indent), Self::scalar(elem));
+            }
+        }
+    }
+
+    /// Writes the value after `key:`.
+    fn write_value(code: &mut String, indent: usize, value: ValueObj) {
+        if Self::is_block(&value) {
+            code.push('\n');
+            Self::write_block(code, indent + 2, value);
+        } else {
+            *code += &format!(" {}\n", Self::scalar(&value));
+        }
+    }
+
+    fn write_block(code: &mut String, indent: usize, value: ValueObj) {
+        if let Some(elems) = Self::as_sequence(&value) {
+            Self::write_sequence(code, indent, elems);
+        } else if let Some(mapping) = Self::as_mapping(&value) {
+            Self::write_mapping(code, indent, mapping);
+        }
+    }
+
+    fn scalar(value: &ValueObj) -> String {
+        match value {
+            ValueObj::Str(s) => Self::string(s),
+            ValueObj::Bool(b) => b.to_string(),
+            ValueObj::None => "null".to_string(),
+            ValueObj::Int(i) => i.to_string(),
+            ValueObj::Nat(n) => n.to_string(),
+            ValueObj::Float(f) if f.is_nan() => ".nan".to_string(),
+            ValueObj::Float(f) if f.is_infinite() => if f.is_sign_positive() {
+                ".inf"
+            } else {
+                "-.inf"
+            }
+            .to_string(),
+            ValueObj::Float(f) => format!("{:?}", **f),
+            ValueObj::Inf => ".inf".to_string(),
+            ValueObj::NegInf => "-.inf".to_string(),
+            // empty collections
+            ValueObj::List(_) | ValueObj::Tuple(_) => "[]".to_string(),
+            _ => "{}".to_string(),
+        }
+    }
+
+    /// Strings which can be read as other scalars (`true`, `1`, `null`, etc.) are quoted.
+    fn string(s: &str) -> String {
+        let is_plain = s
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '/')
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || "_-./".contains(c))
+            && !matches!(
+                &s.to_ascii_lowercase()[..],
+                "true" | "false" | "yes" | "no" | "on" | "off" | "y" | "n" | "null"
+            );
+        if is_plain {
+            return s.to_string();
+        }
+        let mut escaped = String::with_capacity(s.len() + 2);
+        escaped.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => escaped += "\\\"",
+                '\\' => escaped += "\\\\",
+                '\n' => escaped += "\\n",
+                '\r' => escaped += "\\r",
+                '\t' => escaped += "\\t",
+                c if c.is_control() => escaped += &format!("\\u{:04X}", c as u32),
+                c => escaped.push(c),
+            }
+        }
+        escaped.push('"');
+        escaped
+    }
+}
+
+/// Generates a Python stub file (`.pyi`) for the public APIs of a module.
+/// The names are the same as those in the transpiled script.
+#[derive(Debug, Default)]
+pub struct PyStubGenerator {
+    classes: HashSet<Str>,
+    typing: HashSet<&'static str>,
+    /// type variable name -> (name in Python, declaration (`T = TypeVar("T")`))
+    type_vars: HashMap<Str, (String, String)>,
+}
+
+impl PyStubGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn transpile(&mut self, hir: HIR) -> PyStub {
+        for chunk in hir.module.iter() {
+            if let Expr::ClassDef(classdef) = chunk {
+                if classdef.sig.vis().is_public() {
+                    self.classes.insert(classdef.sig.inspect().clone());
+                }
+            }
+        }
+        let mut body = String::new();
+        for chunk in hir.module.into_iter() {
+            match chunk {
+                Expr::Def(def) if def.sig.vis().is_public() => {
+                    body += &self.gen_def(def.sig, 0);
+                }
+                Expr::ClassDef(classdef) if classdef.sig.vis().is_public() => {
+                    body += &self.gen_classdef(classdef);
+                }
+                _ => {}
+            }
+        }
+        let mut code = String::new();
+        if !self.typing.is_empty() {
+            let mut names = self.typing.iter().copied().collect::<Vec<_>>();
+            names.sort();
+            code += &format!("from typing import {}\n\n", names.join(", "));
+        }
+        if !self.type_vars.is_empty() {
+            let mut decls = self
+                .type_vars
+                .values()
+                .map(|(_, decl)| decl.clone())
+                .collect::<Vec<_>>();
+            decls.sort();
+            code += &format!("{}\n\n", decls.join("\n"));
+        }
+        code += &body;
+        PyStub {
+            filename: hir.name,
+            code,
+        }
+    }
+
+    fn any(&mut self) -> String {
+        self.typing.insert("Any");
+        "Any".to_string()
+    }
+
+    /// Converts an Erg type into a [PEP 484](https://peps.python.org/pep-0484/) type annotation.
+    /// The names imported from `typing` are recorded in `self.typing`.
+    /// Types which cannot be expressed in Python are `Any`.
+    fn type_name(&mut self, t: &Type) -> String {
+        match t {
+            Type::FreeVar(fv) if fv.is_linked() => self.type_name(&fv.crack()),
+            Type::FreeVar(fv) if fv.is_unbound() => {
+                let Some(var) = fv.unbound_name() else {
+                    return self.any();
+                };
+                if let Some((name, _)) = self.type_vars.get(&var) {
+                    return name.clone();
+                }
+                let name = if fv.is_named_unbound() {
+                    var.to_string()
+                } else {
+                    // `?T` -> `_T0`
+                    let n = self
+                        .type_vars
+                        .values()
+                        .filter(|(name, _)| name.starts_with("_T"))
+                        .count();
+                    format!("_T{n}")
+                };
+                self.typing.insert("TypeVar");
+                // registered first, since the bound may refer to the variable itself
+                let decl = format!("{name} = TypeVar(\"{name}\")");
+                self.type_vars.insert(var.clone(), (name.clone(), decl));
+                if let Some(sup) = fv.get_super().filter(|sup| sup != &Type::Obj) {
+                    let bound = self.type_name(&sup);
+                    if bound != "Any" {
+                        let decl = format!("{name} = TypeVar(\"{name}\", bound={bound})");
+                        self.type_vars.insert(var, (name.clone(), decl));
+                    }
+                }
+                name
+            }
+            Type::Quantified(quant) => self.type_name(quant),
+            Type::Refinement(refine) => self.type_name(&refine.t),
+            Type::Ref(t) => self.type_name(t),
+            Type::RefMut { before, .. } => self.type_name(before),
+            Type::Int | Type::Nat => "int".to_string(),
+            Type::Ratio | Type::Float => "float".to_string(),
+            Type::Complex => "complex".to_string(),
+            Type::Bool => "bool".to_string(),
+            Type::Str => "str".to_string(),
+            Type::NoneType => "None".to_string(),
+            Type::Obj => "object".to_string(),
+            Type::Type | Type::ClassType => "type".to_string(),
+            Type::Never => {
+                self.typing.insert("NoReturn");
+                "NoReturn".to_string()
+            }
+            Type::Or(tys) => {
+                let mut names = tys.iter().map(|t| self.type_name(t)).collect::<Vec<_>>();
+                names.sort();
+                names.dedup();
+                names.join(" | ")
+            }
+            Type::Subr(subr) => {
+                self.typing.insert("Callable");
+                let ret = self.type_name(&subr.return_t);
+                if subr.var_params.is_some() || subr.kw_var_params.is_some() {
+                    return format!("Callable[..., {ret}]");
+                }
+                let params = subr
+                    .non_default_params
+                    .iter()
+                    .chain(subr.default_params.iter())
+                    .map(|pt| self.type_name(pt.typ()))
+                    .collect::<Vec<_>>();
+                format!("Callable[[{}], {ret}]", params.join(", "))
+            }
+            Type::Callable { param_ts, return_t } => {
+                self.typing.insert("Callable");
+                let ret = self.type_name(return_t);
+                let params = param_ts
+                    .iter()
+                    .map(|t| self.type_name(t))
+                    .collect::<Vec<_>>();
+                format!("Callable[[{}], {ret}]", params.join(", "))
+            }
+            Type::Poly { name, params } => {
+                let mut type_arg = |tp: &TyParam| {
+                    <&Type>::try_from(tp)
+                        .map(|t| self.type_name(t))
+                        .unwrap_or_else(|_| self.any())
+                };
+                match (name.trim_end_matches('!'), &params[..]) {
+                    ("List", [elem, ..]) => format!("list[{}]", type_arg(elem)),
+                    ("Set", [elem, ..]) => format!("set[{}]", type_arg(elem)),
+                    ("Dict", [kvs]) => match HashMap::<TyParam, TyParam>::try_from(kvs.clone()) {
+                        Ok(kvs) if kvs.len() == 1 => {
+                            let (k, v) = kvs.into_iter().next().unwrap();
+                            format!("dict[{}, {}]", type_arg(&k), type_arg(&v))
+                        }
+                        _ => "dict".to_string(),
+                    },
+                    ("Tuple", [elems]) => match Vec::<TyParam>::try_from(elems.clone()) {
+                        Ok(elems) if elems.is_empty() => "tuple[()]".to_string(),
+                        Ok(elems) => {
+                            let elems = elems.iter().map(&mut type_arg).collect::<Vec<_>>();
+                            format!("tuple[{}]", elems.join(", "))
+                        }
+                        Err(_) => "tuple".to_string(),
+                    },
+                    _ => self.any(),
+                }
+            }
+            Type::Mono(name) => match &name[..] {
+                "Int!" | "Nat!" => "int".to_string(),
+                "Float!" | "Ratio!" => "float".to_string(),
+                "Bool!" => "bool".to_string(),
+                "Str!" => "str".to_string(),
+                "Bytes" => "bytes".to_string(),
+                _ if self.classes.contains(&t.local_name()) => t.local_name().to_string(),
+                _ => self.any(),
+            },
+            _ => self.any(),
+        }
+    }
+
+    fn gen_def(&mut self, sig: Signature, level: usize) -> String {
+        let indent = "    ".repeat(level);
+        let name = replace_non_symbolic(sig.inspect());
+        match sig {
+            Signature::Subr(subr) => {
+                let ret = subr
+                    .ident
+                    .ref_t()
+                    .return_t()
+                    .map(|t| self.type_name(t))
+                    .unwrap_or_else(|| self.type_name(&Type::Failure));
+                let subr_t = subr.ident.ref_t().clone();
+                let params = self.gen_params(subr.params, &subr_t);
+                format!("{indent}def {name}({params}) -> {ret}: ...\n")
+            }
+            Signature::Var(var) => {
+                let t = self.type_name(var.ident.ref_t());
+                format!("{indent}{name}: {t}\n")
+            }
+            Signature::Glob(_) => String::new(),
+        }
+    }
+
+    fn param_name(param: &NonDefaultParamSignature, n: usize) -> String {
+        match &param.raw.pat {
+            ParamPattern::VarName(name) => PyScriptGenerator::transpile_name(
+                &VisibilityModifier::Private,
+                name.inspect(),
+                &param.vi,
+            ),
+            _ => format!("_{n}"),
+        }
+    }
+
+    /// The types of the parameters are taken from the subroutine type `subr_t`
+    /// (the types of the default parameters are not those of the default values).
+    fn gen_params(&mut self, params: Params, subr_t: &Type) -> String {
+        let non_default_ts = subr_t.non_default_params().cloned().unwrap_or_default();
+        let default_ts = subr_t.default_params().cloned().unwrap_or_default();
+        // `self` has been removed from the parameters of methods
+        let offset = non_default_ts
+            .len()
+            .saturating_sub(params.non_defaults.len());
+        let mut code = vec![];
+        for (n, param) in params.non_defaults.iter().enumerate() {
+            let t = non_default_ts
+                .get(n + offset)
+                .map_or(&param.vi.t, |pt| pt.typ());
+            let t = self.type_name(t);
+            code.push(format!("{}: {t}", Self::param_name(param, n)));
+        }
+        if let Some(var_params) = params.var_params.as_deref() {
+            // `*args: T` annotates the elements
+            let t = match var_params.vi.t.typarams().first() {
+                Some(TyParam::Type(elem)) => self.type_name(elem),
+                _ => self.type_name(&Type::Failure),
+            };
+            code.push(format!(
+                "*{}: {t}",
+                Self::param_name(var_params, code.len())
+            ));
+        }
+        for (n, default) in params.defaults.iter().enumerate() {
+            let t = default_ts.get(n).map_or(&default.sig.vi.t, |pt| pt.typ());
+            let t = self.type_name(t);
+            code.push(format!(
+                "{}: {t} = ...",
+                Self::param_name(&default.sig, code.len())
+            ));
+        }
+        if let Some(kw_var_params) = params.kw_var_params.as_deref() {
+            let t = match kw_var_params
+                .vi
+                .t
+                .typarams()
+                .first()
+                .and_then(|kvs| HashMap::<TyParam, TyParam>::try_from(kvs.clone()).ok())
+                .and_then(|kvs| kvs.into_iter().next())
+            {
+                Some((_, TyParam::Type(val))) => self.type_name(&val),
+                _ => self.type_name(&Type::Failure),
+            };
+            code.push(format!(
+                "**{}: {t}",
+                Self::param_name(kw_var_params, code.len())
+            ));
+        }
+        code.join(", ")
+    }
+
+    fn gen_classdef(&mut self, classdef: ClassDef) -> String {
+        let class_name = classdef.sig.inspect().clone();
+        let base = match classdef.obj.as_ref() {
+            GenTypeObj::Subclass(sub) => self.type_name(sub.sup.typ()),
+            _ => String::new(),
+        };
+        let mut code = format!("class {class_name}({base}):\n");
+        if let Some(Type::Record(rec)) = classdef
+            .constructor
+            .non_default_params()
+            .and_then(|params| params.first())
+            .map(|param| param.typ())
+        {
+            let mut fields = rec
+                .iter()
+                .filter(|(field, _)| field.vis.is_public())
+                .collect::<Vec<_>>();
+            fields.sort_by(|(l, _), (r, _)| l.symbol.cmp(&r.symbol));
+            for (field, t) in fields {
+                code += &format!("    {}: {}\n", field.symbol, self.type_name(t));
+            }
+        }
+        let any = self.type_name(&Type::Failure);
+        code += &format!("    def __init__(self, param__: {any}) -> None: ...\n");
+        if classdef.need_to_gen_new {
+            code += "    @staticmethod\n";
+            code += &format!("    def new(x: {any}) -> {class_name}: ...\n");
+        }
+        let methods = ClassDef::take_all_methods(classdef.methods_list);
+        for method in methods.into_iter() {
+            let Expr::Def(def) = method else {
+                continue;
+            };
+            if !def.sig.vis().is_public() {
+                continue;
+            }
+            let is_instance_method = def.sig.params().is_some_and(|params| {
+                params
+                    .non_defaults
+                    .first()
+                    .and_then(|param| param.inspect())
+                    == Some(&Str::ever("self"))
+            });
+            if is_instance_method {
+                let Signature::Subr(mut subr) = def.sig else {
+                    continue;
+                };
+                subr.params.non_defaults.remove(0);
+                let ret = subr
+                    .ident
+                    .ref_t()
+                    .return_t()
+                    .map(|t| self.type_name(t))
+                    .unwrap_or(any.clone());
+                let name = replace_non_symbolic(subr.ident.inspect());
+                let subr_t = subr.ident.ref_t().clone();
+                let params = self.gen_params(subr.params, &subr_t);
+                let params = if params.is_empty() {
+                    "self".to_string()
+                } else {
+                    format!("self, {params}")
+                };
+                code += &format!("    def {name}({params}) -> {ret}: ...\n");
+            } else {
+                if def.sig.is_subr() {
+                    code += "    @staticmethod\n";
+                }
+                code += &self.gen_def(def.sig, 1);
+            }
+        }
+        code
+    }
+}
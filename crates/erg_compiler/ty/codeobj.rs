@@ -14,6 +14,8 @@ use erg_common::opcode308::Opcode308;
 use erg_common::opcode309::Opcode309;
 use erg_common::opcode310::Opcode310;
 use erg_common::opcode311::{BinOpCode, Opcode311};
+use erg_common::opcode312::{Intrinsic1, Opcode312};
+use erg_common::opcode313::Opcode313;
 use erg_common::python_util::{env_magic_number, exec_pyc_code, PythonVersion};
 use erg_common::serialize::*;
 use erg_common::Str;
@@ -55,11 +57,25 @@ pub fn tuple_into_bytes(tup: &[ValueObj], python_ver: PythonVersion) -> Vec<u8>
     bytes
 }
 
+fn compare_op_str(op: usize) -> &'static str {
+    match op {
+        0 => "<",
+        1 => "<=",
+        2 => "==",
+        3 => "!=",
+        4 => ">",
+        5 => ">=",
+        _ => "?",
+    }
+}
+
 pub fn jump_abs_addr(minor_ver: u8, op: u8, idx: usize, arg: usize) -> usize {
     match minor_ver {
         7..=9 => jump_abs_addr_309(Opcode309::try_from(op).unwrap(), idx, arg),
         10 => jump_abs_addr_310(Opcode310::try_from(op).unwrap(), idx, arg),
         11 => jump_abs_addr_311(Opcode311::try_from(op).unwrap(), idx, arg),
+        12 => jump_abs_addr_312(Opcode312::try_from(op).unwrap(), idx, arg),
+        13 => jump_abs_addr_313(Opcode313::try_from(op).unwrap(), idx, arg),
         n => todo!("unsupported version: {n}"),
     }
 }
@@ -100,6 +116,153 @@ fn jump_abs_addr_311(op: Opcode311, idx: usize, arg: usize) -> usize {
     }
 }
 
+/// Since 3.12, backward conditional jumps have been removed and `FOR_ITER` has a cache entry.
+fn jump_abs_addr_312(op: Opcode312, idx: usize, arg: usize) -> usize {
+    match op {
+        Opcode312::POP_JUMP_IF_FALSE
+        | Opcode312::POP_JUMP_IF_TRUE
+        | Opcode312::POP_JUMP_IF_NONE
+        | Opcode312::POP_JUMP_IF_NOT_NONE
+        | Opcode312::JUMP_FORWARD => idx + arg * 2 + 2,
+        Opcode312::FOR_ITER => idx + arg * 2 + 4,
+        Opcode312::JUMP_BACKWARD | Opcode312::JUMP_BACKWARD_NO_INTERRUPT => idx - arg * 2 + 2,
+        _ => unreachable!(),
+    }
+}
+
+/// Since 3.13, conditional jumps and `JUMP_BACKWARD` also have a cache entry.
+fn jump_abs_addr_313(op: Opcode313, idx: usize, arg: usize) -> usize {
+    match op {
+        Opcode313::JUMP_FORWARD => idx + arg * 2 + 2,
+        Opcode313::POP_JUMP_IF_FALSE
+        | Opcode313::POP_JUMP_IF_TRUE
+        | Opcode313::POP_JUMP_IF_NONE
+        | Opcode313::POP_JUMP_IF_NOT_NONE
+        | Opcode313::FOR_ITER => idx + arg * 2 + 4,
+        Opcode313::JUMP_BACKWARD => idx - arg * 2 + 4,
+        Opcode313::JUMP_BACKWARD_NO_INTERRUPT => idx - arg * 2 + 2,
+        _ => unreachable!(),
+    }
+}
+
+/// An entry of `co_exceptiontable` (3.11~).
+/// See `Objects/exception_handling_notes.txt` in CPython for details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExceptionTableEntry {
+    /// byte offset of the first protected instruction
+    pub start: usize,
+    /// byte offset just after the last protected instruction
+    pub end: usize,
+    /// byte offset of the handler
+    pub target: usize,
+    /// stack depth to which the stack is popped before the handler runs
+    pub depth: usize,
+    /// whether the offset of the raising instruction is pushed
+    pub lasti: bool,
+}
+
+impl ExceptionTableEntry {
+    pub const fn new(start: usize, end: usize, target: usize, depth: usize, lasti: bool) -> Self {
+        Self {
+            start,
+            end,
+            target,
+            depth,
+            lasti,
+        }
+    }
+
+    // 6-bit chunks, most significant first, 0x40 = continuation, 0x80 = start of an entry
+    fn write_item(bytes: &mut Vec<u8>, value: usize, mut msb: u8) {
+        for shift in [24, 18, 12, 6] {
+            if value >= 1 << shift {
+                bytes.push(((value >> shift) & 0x3f) as u8 | 0x40 | msb);
+                msb = 0;
+            }
+        }
+        bytes.push((value & 0x3f) as u8 | msb);
+    }
+
+    fn read_item(bytes: &[u8], idx: &mut usize) -> Option<usize> {
+        let mut value = 0;
+        loop {
+            let byte = *bytes.get(*idx)?;
+            *idx += 1;
+            value = (value << 6) | (byte & 0x3f) as usize;
+            if byte & 0x40 == 0 {
+                return Some(value);
+            }
+        }
+    }
+
+    /// The inverse of `table_into_bytes`. `None` if `bytes` is malformed.
+    pub fn table_from_bytes(bytes: &[u8]) -> Option<Vec<Self>> {
+        let mut entries = vec![];
+        let mut idx = 0;
+        while idx < bytes.len() {
+            let start = Self::read_item(bytes, &mut idx)? * 2;
+            let len = Self::read_item(bytes, &mut idx)? * 2;
+            let target = Self::read_item(bytes, &mut idx)? * 2;
+            let depth_lasti = Self::read_item(bytes, &mut idx)?;
+            entries.push(Self::new(
+                start,
+                start + len,
+                target,
+                depth_lasti >> 1,
+                depth_lasti & 1 == 1,
+            ));
+        }
+        Some(entries)
+    }
+
+    /// Entries must not overlap in the table, so nested ranges are split.
+    /// The earlier entry in `entries` takes precedence (inner handlers are registered first).
+    pub fn table_into_bytes(entries: &[Self]) -> Vec<u8> {
+        let mut points = entries
+            .iter()
+            .flat_map(|entry| [entry.start, entry.end])
+            .collect::<Vec<_>>();
+        points.sort_unstable();
+        points.dedup();
+        let mut flattened: Vec<Self> = vec![];
+        for range in points.windows(2) {
+            let (start, end) = (range[0], range[1]);
+            let Some(entry) = entries
+                .iter()
+                .find(|entry| entry.start <= start && end <= entry.end)
+            else {
+                continue;
+            };
+            match flattened.last_mut() {
+                Some(last)
+                    if last.end == start
+                        && (last.target, last.depth, last.lasti)
+                            == (entry.target, entry.depth, entry.lasti) =>
+                {
+                    last.end = end;
+                }
+                _ => flattened.push(Self::new(
+                    start,
+                    end,
+                    entry.target,
+                    entry.depth,
+                    entry.lasti,
+                )),
+            }
+        }
+        flattened.into_iter().flat_map(Self::into_bytes).collect()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        let mut bytes = vec![];
+        Self::write_item(&mut bytes, self.start / 2, 0x80);
+        Self::write_item(&mut bytes, (self.end - self.start) / 2, 0);
+        Self::write_item(&mut bytes, self.target / 2, 0);
+        Self::write_item(&mut bytes, (self.depth << 1) | self.lasti as usize, 0);
+        bytes
+    }
+}
+
 /// Kind can be multiple (e.g. Local + Cell = 0x60)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(u8)]
@@ -393,6 +556,7 @@ impl CodeObj {
     }
 
     pub fn into_bytes(self, python_ver: PythonVersion) -> Vec<u8> {
+        let code_len = self.code.len();
         let mut bytes = vec![DataTypePrefix::Code as u8];
         bytes.append(&mut self.argcount.to_le_bytes().to_vec());
         if python_ver.minor >= Some(8) {
@@ -422,13 +586,100 @@ impl CodeObj {
         }
         bytes.append(&mut self.firstlineno.to_le_bytes().to_vec());
         // lnotab is represented as PyStrObject
-        bytes.append(&mut raw_string_into_bytes(self.lnotab));
+        if python_ver.minor >= Some(11) {
+            let linetable = Self::lnotab_into_linetable(&self.lnotab, self.firstlineno, code_len);
+            bytes.append(&mut raw_string_into_bytes(linetable));
+        } else {
+            bytes.append(&mut raw_string_into_bytes(self.lnotab));
+        }
         if python_ver.minor >= Some(11) {
             bytes.append(&mut raw_string_into_bytes(self.exceptiontable));
         }
         bytes
     }
 
+    /// Returns the offsets at which each line starts (equivalent to `dis.findlinestarts`)
+    pub fn line_starts(lnotab: &[u8], firstlineno: u32) -> Vec<(usize, u32)> {
+        let mut starts = vec![];
+        let mut last_lineno = None;
+        let mut lineno = firstlineno as i64;
+        let mut addr = 0;
+        for pair in lnotab.chunks_exact(2) {
+            let (sdelta, ldelta) = (pair[0], pair[1]);
+            if sdelta != 0 {
+                if last_lineno != Some(lineno) {
+                    starts.push((addr, lineno.max(0) as u32));
+                    last_lineno = Some(lineno);
+                }
+                addr += sdelta as usize;
+            }
+            lineno += ldelta as i8 as i64;
+        }
+        if last_lineno != Some(lineno) {
+            starts.push((addr, lineno.max(0) as u32));
+        }
+        starts
+    }
+
+    /// The inverse of `line_starts`. `starts` must be sorted by offset.
+    pub fn line_starts_into_lnotab(starts: &[(usize, u32)], firstlineno: u32) -> Vec<u8> {
+        let mut lnotab = vec![];
+        let mut addr = 0;
+        let mut lineno = firstlineno as i64;
+        for &(start, line) in starts {
+            let mut sdelta = start - addr;
+            while sdelta > 255 {
+                lnotab.push(255);
+                lnotab.push(0);
+                sdelta -= 255;
+            }
+            let mut ldelta = line as i64 - lineno;
+            while sdelta != 0 || ldelta != 0 {
+                let ld = ldelta.clamp(i8::MIN as i64, i8::MAX as i64);
+                lnotab.push(sdelta as u8);
+                lnotab.push(ld as i8 as u8);
+                sdelta = 0;
+                ldelta -= ld;
+            }
+            addr = start;
+            lineno = line as i64;
+        }
+        lnotab
+    }
+
+    /// Since 3.11, `co_linetable` has a different format from `co_lnotab` (see `Objects/locations.md` in CPython).
+    /// Column information is not recorded.
+    fn lnotab_into_linetable(lnotab: &[u8], firstlineno: u32, code_len: usize) -> Vec<u8> {
+        // PY_CODE_LOCATION_INFO_NO_COLUMNS
+        const NO_COLUMNS: u8 = 13;
+        let mut linetable = vec![];
+        let starts = Self::line_starts(lnotab, firstlineno);
+        let mut prev_line = firstlineno as i64;
+        for (i, (start, line)) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).map_or(code_len, |(next, _)| *next);
+            let mut units = end.saturating_sub(*start) / 2;
+            let mut delta = *line as i64 - prev_line;
+            prev_line = *line as i64;
+            while units > 0 {
+                let len = units.min(8);
+                linetable.push(0x80 | (NO_COLUMNS << 3) | (len as u8 - 1));
+                let mut zigzag = if delta < 0 {
+                    ((-delta) << 1) | 1
+                } else {
+                    delta << 1
+                } as u64;
+                while zigzag >= 64 {
+                    linetable.push(0x40 | (zigzag & 0x3f) as u8);
+                    zigzag >>= 6;
+                }
+                linetable.push(zigzag as u8);
+                delta = 0;
+                units -= len;
+            }
+        }
+        linetable
+    }
+
     fn dump_locals(
         varnames: Vec<Str>,
         freevars: Vec<Str>,
@@ -497,8 +748,11 @@ impl CodeObj {
     pub fn exec(self, cfg: &ErgConfig) -> std::io::Result<ExitStatus> {
         exec_pyc_code(
             &self.into_bytecode(cfg.py_magic_num),
+            cfg.py_command,
             &cfg.runtime_args,
             cfg.output.clone(),
+            // only the tracebacks of Erg scripts can be rewritten
+            cfg.input.is_file(),
         )
     }
 
@@ -582,7 +836,12 @@ impl CodeObj {
                 ldelta = lnotab_iter.next().unwrap_or(&0);
             }
             if let (Some(op), Some(arg)) = (code_iter.next(), code_iter.next()) {
-                let pushed = if CommonOpcode::try_from(*op) == Ok(CommonOpcode::EXTENDED_ARG) {
+                let is_extended_arg = if py_ver.and_then(|pv| pv.minor) >= Some(13) {
+                    Opcode313::try_from(*op) == Ok(Opcode313::EXTENDED_ARG)
+                } else {
+                    CommonOpcode::try_from(*op) == Ok(CommonOpcode::EXTENDED_ARG)
+                };
+                let pushed = if is_extended_arg {
                     extended_arg.push(*arg);
                     true
                 } else {
@@ -609,6 +868,8 @@ impl CodeObj {
                     Some(9) => self.read_instr_309(op, arg, idx, &mut instrs),
                     Some(10) => self.read_instr_310(op, arg, idx, &mut instrs),
                     Some(11) => self.read_instr_311(op, arg, idx, &mut instrs),
+                    Some(12) => self.read_instr_312(op, arg, idx, &mut instrs),
+                    Some(13) => self.read_instr_313(op, arg, idx, &mut instrs),
                     _ => {}
                 }
                 idx += 2;
@@ -815,19 +1076,139 @@ impl CodeObj {
         instrs.push('\n');
     }
 
+    fn read_instr_312(&self, op: &u8, arg: usize, idx: usize, instrs: &mut String) {
+        let op312 = Opcode312::try_from(*op).unwrap();
+        let s_op = op312.to_string();
+        write!(instrs, "{idx:>15} {s_op:<26}").unwrap();
+        match op312 {
+            Opcode312::STORE_NAME
+            | Opcode312::LOAD_NAME
+            | Opcode312::STORE_GLOBAL
+            | Opcode312::STORE_ATTR
+            | Opcode312::IMPORT_NAME
+            | Opcode312::IMPORT_FROM => {
+                write!(instrs, "{arg} ({})", self.names[arg]).unwrap();
+            }
+            Opcode312::LOAD_GLOBAL | Opcode312::LOAD_ATTR => {
+                write!(instrs, "{arg} ({})", self.names[arg >> 1]).unwrap();
+            }
+            Opcode312::STORE_FAST
+            | Opcode312::LOAD_FAST
+            | Opcode312::STORE_DEREF
+            | Opcode312::LOAD_DEREF
+            | Opcode312::MAKE_CELL
+            | Opcode312::LOAD_CLOSURE => {
+                write!(instrs, "{arg} ({})", self.varnames[arg]).unwrap();
+            }
+            Opcode312::LOAD_CONST | Opcode312::KW_NAMES => {
+                write!(instrs, "{arg} ({})", self.consts[arg]).unwrap();
+            }
+            Opcode312::COMPARE_OP => {
+                write!(instrs, "{arg} ({})", compare_op_str(arg >> 4)).unwrap();
+            }
+            Opcode312::CALL_INTRINSIC_1 => {
+                write!(
+                    instrs,
+                    "{arg} ({})",
+                    Intrinsic1::try_from(arg as u8).unwrap()
+                )
+                .unwrap();
+            }
+            Opcode312::BINARY_OP => {
+                write!(
+                    instrs,
+                    "{arg} ({:?})",
+                    BinOpCode::try_from(arg as u8).unwrap()
+                )
+                .unwrap();
+            }
+            Opcode312::POP_JUMP_IF_FALSE
+            | Opcode312::POP_JUMP_IF_TRUE
+            | Opcode312::POP_JUMP_IF_NONE
+            | Opcode312::POP_JUMP_IF_NOT_NONE
+            | Opcode312::JUMP_FORWARD
+            | Opcode312::FOR_ITER
+            | Opcode312::JUMP_BACKWARD
+            | Opcode312::JUMP_BACKWARD_NO_INTERRUPT => {
+                let to = jump_abs_addr_312(op312, idx, arg);
+                write!(instrs, "{arg} (to {to})").unwrap();
+            }
+            _ if op312.take_arg() => {
+                write!(instrs, "{arg}").unwrap();
+            }
+            _ => {}
+        }
+        instrs.push('\n');
+    }
+
+    fn read_instr_313(&self, op: &u8, arg: usize, idx: usize, instrs: &mut String) {
+        let op313 = Opcode313::try_from(*op).unwrap();
+        let s_op = op313.to_string();
+        write!(instrs, "{idx:>15} {s_op:<26}").unwrap();
+        match op313 {
+            Opcode313::STORE_NAME
+            | Opcode313::LOAD_NAME
+            | Opcode313::STORE_GLOBAL
+            | Opcode313::STORE_ATTR
+            | Opcode313::IMPORT_NAME
+            | Opcode313::IMPORT_FROM => {
+                write!(instrs, "{arg} ({})", self.names[arg]).unwrap();
+            }
+            Opcode313::LOAD_GLOBAL | Opcode313::LOAD_ATTR => {
+                write!(instrs, "{arg} ({})", self.names[arg >> 1]).unwrap();
+            }
+            Opcode313::STORE_FAST
+            | Opcode313::LOAD_FAST
+            | Opcode313::STORE_DEREF
+            | Opcode313::LOAD_DEREF
+            | Opcode313::MAKE_CELL => {
+                write!(instrs, "{arg} ({})", self.varnames[arg]).unwrap();
+            }
+            Opcode313::LOAD_CONST => {
+                write!(instrs, "{arg} ({})", self.consts[arg]).unwrap();
+            }
+            Opcode313::COMPARE_OP => {
+                write!(instrs, "{arg} ({})", compare_op_str(arg >> 5)).unwrap();
+            }
+            Opcode313::CALL_INTRINSIC_1 => {
+                write!(
+                    instrs,
+                    "{arg} ({})",
+                    Intrinsic1::try_from(arg as u8).unwrap()
+                )
+                .unwrap();
+            }
+            Opcode313::BINARY_OP => {
+                write!(
+                    instrs,
+                    "{arg} ({:?})",
+                    BinOpCode::try_from(arg as u8).unwrap()
+                )
+                .unwrap();
+            }
+            Opcode313::POP_JUMP_IF_FALSE
+            | Opcode313::POP_JUMP_IF_TRUE
+            | Opcode313::POP_JUMP_IF_NONE
+            | Opcode313::POP_JUMP_IF_NOT_NONE
+            | Opcode313::JUMP_FORWARD
+            | Opcode313::FOR_ITER
+            | Opcode313::JUMP_BACKWARD
+            | Opcode313::JUMP_BACKWARD_NO_INTERRUPT => {
+                let to = jump_abs_addr_313(op313, idx, arg);
+                write!(instrs, "{arg} (to {to})").unwrap();
+            }
+            _ if op313.has_arg() => {
+                write!(instrs, "{arg}").unwrap();
+            }
+            _ => {}
+        }
+        instrs.push('\n');
+    }
+
     fn dump_additional_info(&self, op: CommonOpcode, arg: usize, idx: usize, instrs: &mut String) {
         match op {
             CommonOpcode::COMPARE_OP => {
-                let op = match arg {
-                    0 => "<",
-                    1 => "<=",
-                    2 => "==",
-                    3 => "!=",
-                    4 => ">",
-                    5 => ">=",
-                    _ => "?",
-                };
-                write!(instrs, "{arg} ({op})").unwrap();
+                write!(instrs, "{arg} ({})", compare_op_str(arg)).unwrap();
             }
             CommonOpcode::STORE_NAME
             | CommonOpcode::LOAD_NAME
@@ -12,6 +12,75 @@ use super::typaram::TyParam;
 use super::value::ValueObj;
 use super::{SharedFrees, Type};
 
+/// A set of integers represented as sorted, disjoint closed intervals.
+/// `i128::MIN` and `i128::MAX` stand for negative and positive infinity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntRanges(Vec<(i128, i128)>);
+
+impl IntRanges {
+    pub fn full() -> Self {
+        Self(vec![(i128::MIN, i128::MAX)])
+    }
+
+    pub const fn empty() -> Self {
+        Self(vec![])
+    }
+
+    fn new(mut ranges: Vec<(i128, i128)>) -> Self {
+        ranges.retain(|(l, r)| l <= r);
+        ranges.sort();
+        let mut merged: Vec<(i128, i128)> = vec![];
+        for (l, r) in ranges {
+            match merged.last_mut() {
+                Some((_, last_r)) if l <= last_r.saturating_add(1) => {
+                    *last_r = (*last_r).max(r);
+                }
+                _ => merged.push((l, r)),
+            }
+        }
+        Self(merged)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self::new(self.0.iter().chain(other.0.iter()).cloned().collect())
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = vec![];
+        for (l1, r1) in self.0.iter() {
+            for (l2, r2) in other.0.iter() {
+                ranges.push((*l1.max(l2), *r1.min(r2)));
+            }
+        }
+        Self::new(ranges)
+    }
+
+    pub fn complement(&self) -> Self {
+        let mut ranges = vec![];
+        let mut start = i128::MIN;
+        for (l, r) in self.0.iter() {
+            if *l > start {
+                ranges.push((start, l - 1));
+            }
+            if *r == i128::MAX {
+                return Self::new(ranges);
+            }
+            start = r + 1;
+        }
+        ranges.push((start, i128::MAX));
+        Self::new(ranges)
+    }
+
+    /// `self` ⊆ `other`
+    pub fn is_subset_of(&self, other: &Self) -> bool {
+        self.intersection(&other.complement()).is_empty()
+    }
+}
+
 impl Immutable for Predicate {}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
@@ -875,6 +944,40 @@ impl Predicate {
         }
     }
 
+    /// Returns the set of integers that satisfy the predicate.
+    /// `None` if the predicate is not made of comparisons with integer literals.
+    /// ```erg
+    /// I >= 0 and I != 3 => [0, 2], [4, ∞]
+    /// ```
+    pub fn int_ranges(&self) -> Option<IntRanges> {
+        fn int(tp: &TyParam) -> Option<i128> {
+            match tp {
+                TyParam::Value(ValueObj::Int(i)) => Some(*i as i128),
+                TyParam::Value(ValueObj::Nat(n)) => Some(*n as i128),
+                TyParam::Value(ValueObj::Bool(b)) => Some(*b as i128),
+                _ => None,
+            }
+        }
+        match self {
+            Self::Value(ValueObj::Bool(true)) => Some(IntRanges::full()),
+            Self::Value(ValueObj::Bool(false)) => Some(IntRanges::empty()),
+            Self::Equal { rhs, .. } => int(rhs).map(|i| IntRanges::new(vec![(i, i)])),
+            Self::GreaterEqual { rhs, .. } => {
+                int(rhs).map(|i| IntRanges::new(vec![(i, i128::MAX)]))
+            }
+            Self::LessEqual { rhs, .. } => int(rhs).map(|i| IntRanges::new(vec![(i128::MIN, i)])),
+            Self::NotEqual { rhs, .. } => {
+                int(rhs).map(|i| IntRanges::new(vec![(i, i)]).complement())
+            }
+            Self::And(lhs, rhs) => Some(lhs.int_ranges()?.intersection(&rhs.int_ranges()?)),
+            Self::Or(preds) => preds.iter().try_fold(IntRanges::empty(), |acc, pred| {
+                Some(acc.union(&pred.int_ranges()?))
+            }),
+            Self::Not(pred) => Some(pred.int_ranges()?.complement()),
+            _ => None,
+        }
+    }
+
     pub fn possible_tps(&self) -> Vec<&TyParam> {
         match self {
             Self::Or(preds) => preds.iter().flat_map(|p| p.possible_tps()).collect(),
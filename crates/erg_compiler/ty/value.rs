@@ -1237,6 +1237,35 @@ impl HasType for ValueObj {
     }
 }
 
+/// `l // r` (Python semantics, rounds toward negative infinity)
+fn floordiv_i32(l: i32, r: i32) -> Option<i32> {
+    let q = l.checked_div(r)?;
+    if l % r != 0 && (l < 0) != (r < 0) {
+        Some(q - 1)
+    } else {
+        Some(q)
+    }
+}
+
+/// `l % r` (Python semantics, the sign follows the divisor)
+fn mod_i32(l: i32, r: i32) -> Option<i32> {
+    let m = l.checked_rem(r)?;
+    if m != 0 && (m < 0) != (r < 0) {
+        Some(m + r)
+    } else {
+        Some(m)
+    }
+}
+
+fn mod_f64(l: f64, r: f64) -> f64 {
+    let m = l % r;
+    if m != 0.0 && (m < 0.0) != (r < 0.0) {
+        m + r
+    } else {
+        m
+    }
+}
+
 impl ValueObj {
     pub const fn builtin_class(t: Type) -> Self {
         ValueObj::Type(TypeObj::Builtin {
@@ -1600,15 +1629,15 @@ impl ValueObj {
     // REVIEW: allow_divergenceオプションを付けるべきか?
     pub fn try_add(self, other: Self) -> Option<Self> {
         match (self, other) {
-            (Self::Int(l), Self::Int(r)) => Some(Self::Int(l + r)),
-            (Self::Nat(l), Self::Nat(r)) => Some(Self::Nat(l + r)),
+            (Self::Int(l), Self::Int(r)) => Some(Self::Int(l.checked_add(r)?)),
+            (Self::Nat(l), Self::Nat(r)) => Some(Self::Nat(l.checked_add(r)?)),
             (Self::Float(l), Self::Float(r)) => Some(Self::Float(l + r)),
-            (Self::Int(l), Self::Nat(r)) => Some(Self::from(l + r as i32)),
-            (Self::Nat(l), Self::Int(r)) => Some(Self::Int(l as i32 + r)),
-            (Self::Float(l), Self::Nat(r)) => Some(Self::from(*l - r as f64)),
-            (Self::Int(l), Self::Float(r)) => Some(Self::from(l as f64 - *r)),
-            (Self::Nat(l), Self::Float(r)) => Some(Self::from(l as f64 - *r)),
-            (Self::Float(l), Self::Int(r)) => Some(Self::from(*l - r as f64)),
+            (Self::Int(l), Self::Nat(r)) => Some(Self::from(l.checked_add(r.try_into().ok()?)?)),
+            (Self::Nat(l), Self::Int(r)) => Some(Self::Int(i32::try_from(l).ok()?.checked_add(r)?)),
+            (Self::Float(l), Self::Nat(r)) => Some(Self::from(*l + r as f64)),
+            (Self::Int(l), Self::Float(r)) => Some(Self::from(l as f64 + *r)),
+            (Self::Nat(l), Self::Float(r)) => Some(Self::from(l as f64 + *r)),
+            (Self::Float(l), Self::Int(r)) => Some(Self::from(*l + r as f64)),
             (Self::Str(l), Self::Str(r)) => Some(Self::Str(Str::from(format!("{l}{r}")))),
             (Self::List(l), Self::List(r)) => {
                 let lis = Arc::from([l, r].concat());
@@ -1624,11 +1653,15 @@ impl ValueObj {
 
     pub fn try_sub(self, other: Self) -> Option<Self> {
         match (self, other) {
-            (Self::Int(l), Self::Int(r)) => Some(Self::Int(l - r)),
-            (Self::Nat(l), Self::Nat(r)) => Some(Self::Int(l as i32 - r as i32)),
+            (Self::Int(l), Self::Int(r)) => Some(Self::Int(l.checked_sub(r)?)),
+            (Self::Nat(l), Self::Nat(r)) => Some(Self::Int(
+                i32::try_from(l).ok()?.checked_sub(r.try_into().ok()?)?,
+            )),
             (Self::Float(l), Self::Float(r)) => Some(Self::Float(l - r)),
-            (Self::Int(l), Self::Nat(r)) => Some(Self::from(l - r as i32)),
-            (Self::Nat(l), Self::Int(r)) => Some(Self::from(l as i32 - r)),
+            (Self::Int(l), Self::Nat(r)) => Some(Self::from(l.checked_sub(r.try_into().ok()?)?)),
+            (Self::Nat(l), Self::Int(r)) => {
+                Some(Self::from(i32::try_from(l).ok()?.checked_sub(r)?))
+            }
             (Self::Float(l), Self::Nat(r)) => Some(Self::from(*l - r as f64)),
             (Self::Nat(l), Self::Float(r)) => Some(Self::from(l as f64 - *r)),
             (Self::Float(l), Self::Int(r)) => Some(Self::from(*l - r as f64)),
@@ -1645,11 +1678,11 @@ impl ValueObj {
 
     pub fn try_mul(self, other: Self) -> Option<Self> {
         match (self, other) {
-            (Self::Int(l), Self::Int(r)) => Some(Self::from(l * r)),
-            (Self::Nat(l), Self::Nat(r)) => Some(Self::Nat(l * r)),
+            (Self::Int(l), Self::Int(r)) => Some(Self::from(l.checked_mul(r)?)),
+            (Self::Nat(l), Self::Nat(r)) => Some(Self::Nat(l.checked_mul(r)?)),
             (Self::Float(l), Self::Float(r)) => Some(Self::Float(l * r)),
-            (Self::Int(l), Self::Nat(r)) => Some(Self::Int(l * r as i32)),
-            (Self::Nat(l), Self::Int(r)) => Some(Self::Int(l as i32 * r)),
+            (Self::Int(l), Self::Nat(r)) => Some(Self::Int(l.checked_mul(r.try_into().ok()?)?)),
+            (Self::Nat(l), Self::Int(r)) => Some(Self::Int(i32::try_from(l).ok()?.checked_mul(r)?)),
             (Self::Float(l), Self::Nat(r)) => Some(Self::from(*l * r as f64)),
             (Self::Nat(l), Self::Float(r)) => Some(Self::from(l as f64 * *r)),
             (Self::Float(l), Self::Int(r)) => Some(Self::from(*l * r as f64)),
@@ -1680,11 +1713,11 @@ impl ValueObj {
 
     pub fn try_floordiv(self, other: Self) -> Option<Self> {
         match (self, other) {
-            (Self::Int(l), Self::Int(r)) => Some(Self::Int(l / r)),
-            (Self::Nat(l), Self::Nat(r)) => Some(Self::Nat(l / r)),
+            (Self::Int(l), Self::Int(r)) => Some(Self::Int(floordiv_i32(l, r)?)),
+            (Self::Nat(l), Self::Nat(r)) => Some(Self::Nat(l.checked_div(r)?)),
             (Self::Float(l), Self::Float(r)) => Some(Self::from((l / r).floor())),
-            (Self::Int(l), Self::Nat(r)) => Some(Self::Int(l / r as i32)),
-            (Self::Nat(l), Self::Int(r)) => Some(Self::Int(l as i32 / r)),
+            (Self::Int(l), Self::Nat(r)) => Some(Self::Int(floordiv_i32(l, r.try_into().ok()?)?)),
+            (Self::Nat(l), Self::Int(r)) => Some(Self::Int(floordiv_i32(l.try_into().ok()?, r)?)),
             (Self::Float(l), Self::Nat(r)) => Some(Self::from((*l / r as f64).floor())),
             (Self::Nat(l), Self::Float(r)) => Some(Self::from((l as f64 / *r).floor())),
             (Self::Float(l), Self::Int(r)) => Some(Self::from((*l / r as f64).floor())),
@@ -1696,11 +1729,11 @@ impl ValueObj {
 
     pub fn try_pow(self, other: Self) -> Option<Self> {
         match (self, other) {
-            (Self::Int(l), Self::Int(r)) => Some(Self::Int(l.pow(r.try_into().ok()?))),
-            (Self::Nat(l), Self::Nat(r)) => Some(Self::Nat(l.pow(r.try_into().ok()?))),
+            (Self::Int(l), Self::Int(r)) => Some(Self::Int(l.checked_pow(r.try_into().ok()?)?)),
+            (Self::Nat(l), Self::Nat(r)) => Some(Self::Nat(l.checked_pow(r.try_into().ok()?)?)),
             (Self::Float(l), Self::Float(r)) => Some(Self::from(l.powf(*r))),
-            (Self::Int(l), Self::Nat(r)) => Some(Self::Int(l.pow(r.try_into().ok()?))),
-            (Self::Nat(l), Self::Int(r)) => Some(Self::Nat(l.pow(r.try_into().ok()?))),
+            (Self::Int(l), Self::Nat(r)) => Some(Self::Int(l.checked_pow(r.try_into().ok()?)?)),
+            (Self::Nat(l), Self::Int(r)) => Some(Self::Nat(l.checked_pow(r.try_into().ok()?)?)),
             (Self::Float(l), Self::Nat(r)) => Some(Self::from(l.powf(r as f64))),
             (Self::Nat(l), Self::Float(r)) => Some(Self::from((l as f64).powf(*r))),
             (Self::Float(l), Self::Int(r)) => Some(Self::from(l.powi(r))),
@@ -1711,15 +1744,15 @@ impl ValueObj {
 
     pub fn try_mod(self, other: Self) -> Option<Self> {
         match (self, other) {
-            (Self::Int(l), Self::Int(r)) => Some(Self::Int(l % r)),
-            (Self::Nat(l), Self::Nat(r)) => Some(Self::Nat(l % r)),
-            (Self::Float(l), Self::Float(r)) => Some(Self::Float(l % r)),
-            (Self::Int(l), Self::Nat(r)) => Some(Self::Int(l % r as i32)),
-            (Self::Nat(l), Self::Int(r)) => Some(Self::Int(l as i32 % r)),
-            (Self::Float(l), Self::Nat(r)) => Some(Self::from(*l % r as f64)),
-            (Self::Nat(l), Self::Float(r)) => Some(Self::from(l as f64 % *r)),
-            (Self::Float(l), Self::Int(r)) => Some(Self::from(*l % r as f64)),
-            (Self::Int(l), Self::Float(r)) => Some(Self::from(l as f64 % *r)),
+            (Self::Int(l), Self::Int(r)) => Some(Self::Int(mod_i32(l, r)?)),
+            (Self::Nat(l), Self::Nat(r)) => Some(Self::Nat(l.checked_rem(r)?)),
+            (Self::Float(l), Self::Float(r)) => Some(Self::from(mod_f64(*l, *r))),
+            (Self::Int(l), Self::Nat(r)) => Some(Self::Int(mod_i32(l, r.try_into().ok()?)?)),
+            (Self::Nat(l), Self::Int(r)) => Some(Self::Int(mod_i32(l.try_into().ok()?, r)?)),
+            (Self::Float(l), Self::Nat(r)) => Some(Self::from(mod_f64(*l, r as f64))),
+            (Self::Nat(l), Self::Float(r)) => Some(Self::from(mod_f64(l as f64, *r))),
+            (Self::Float(l), Self::Int(r)) => Some(Self::from(mod_f64(*l, r as f64))),
+            (Self::Int(l), Self::Float(r)) => Some(Self::from(mod_f64(l as f64, *r))),
             _ => None,
         }
     }
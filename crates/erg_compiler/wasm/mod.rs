@@ -0,0 +1,1663 @@
+//! Kayser: generates a WebAssembly module from monomorphic HIR.
+//!
+//! Values are represented as follows:
+//!
+//! * `Int`/`Nat` → `i64`, `Float` → `f64`, `Bool` → `i32`, `NoneType` → (nothing)
+//! * records and lists → `i32` pointers to the linear memory
+//!
+//! Every field of a record and every element of a list occupies an 8-byte slot.
+//! A list points to the header `[len: i32, cap: i32, data: i32]`, so that `push!` can reallocate the data.
+//! Memory is allocated by a bump allocator and never freed.
+//!
+//! Public top-level subroutines are exported with their names, and the other top-level statements are executed when the module is instantiated.
+//! `print!` requires the host to provide `erg.write_i64`, `erg.write_f64`, `erg.write_bool` and `erg.write_char`.
+pub mod module;
+
+use std::fmt;
+
+use erg_common::config::ErgConfig;
+use erg_common::dict::Dict;
+use erg_common::error::Location;
+use erg_common::set::Set;
+use erg_common::traits::{Locational, Stream};
+use erg_common::Str;
+
+use erg_parser::token::TokenKind;
+
+use crate::error::{CompileError, CompileErrors, CompileResult};
+use crate::hir::{
+    Accessor, Args, Attribute, Block, Call, Def, Expr, GuardClause, Identifier, Lambda, List,
+    Literal, Params, Record, Signature, SubrSignature, HIR,
+};
+use crate::ty::value::ValueObj;
+use crate::ty::{HasType, Type};
+
+pub use module::{Func, FuncType, Global, Import, Instr, NumOp, ValType, WasmModule};
+
+const TARGET: &str = "WASM";
+const INIT: &str = "__init";
+const HEAP: &str = "__heap";
+const ALLOC: &str = "__alloc";
+const LIST_NEW: &str = "__list_new";
+const LIST_ADDR: &str = "__list_addr";
+const LIST_PUSH: &str = "__list_push";
+const FLOORDIV: &str = "__floordiv";
+const MOD: &str = "__mod";
+const FMOD: &str = "__fmod";
+const POW: &str = "__pow";
+const ABS: &str = "__abs";
+
+/// The representation of an Erg type in WASM
+#[derive(Debug, Clone, PartialEq)]
+pub enum WasmType {
+    Unit,
+    Int,
+    Float,
+    Bool,
+    /// fields are sorted by name
+    Record(Vec<(Str, WasmType)>),
+    List(Box<WasmType>),
+    /// the type of the code which does not return (e.g. erroneous code)
+    Never,
+}
+
+impl fmt::Display for WasmType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unit => write!(f, "NoneType"),
+            Self::Int => write!(f, "Int"),
+            Self::Float => write!(f, "Float"),
+            Self::Bool => write!(f, "Bool"),
+            Self::Record(fields) => {
+                write!(f, "{{")?;
+                for (i, (name, t)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{name} = {t}")?;
+                }
+                write!(f, "}}")
+            }
+            Self::List(elem) => write!(f, "List({elem})"),
+            Self::Never => write!(f, "Never"),
+        }
+    }
+}
+
+impl WasmType {
+    pub fn from_type(t: &Type) -> Option<Self> {
+        match t {
+            Type::FreeVar(fv) if fv.is_linked() => Self::from_type(&fv.crack()),
+            Type::Refinement(refine) => Self::from_type(&refine.t),
+            Type::Ref(t) | Type::RefMut { before: t, .. } => Self::from_type(t),
+            Type::Int | Type::Nat => Some(Self::Int),
+            Type::Float => Some(Self::Float),
+            Type::Bool | Type::Guard(_) => Some(Self::Bool),
+            Type::NoneType => Some(Self::Unit),
+            Type::Never => Some(Self::Never),
+            Type::Mono(name) => match &name[..] {
+                "Int!" | "Nat!" => Some(Self::Int),
+                "Float!" => Some(Self::Float),
+                "Bool!" => Some(Self::Bool),
+                _ => None,
+            },
+            Type::Record(rec) => {
+                let mut fields = rec
+                    .iter()
+                    .map(|(field, t)| Some((field.symbol.clone(), Self::from_type(t)?)))
+                    .collect::<Option<Vec<_>>>()?;
+                fields.sort_by(|(l, _), (r, _)| l.cmp(r));
+                Some(Self::Record(fields))
+            }
+            Type::Poly { name, params } if &name[..] == "List" || &name[..] == "List!" => {
+                let elem = Self::from_type(params.first()?.as_type()?)?;
+                Some(Self::List(Box::new(elem)))
+            }
+            // e.g. `Nat or Int`
+            Type::Or(tys) => {
+                let mut tys = tys.iter().map(Self::from_type);
+                let first = tys.next()??;
+                tys.all(|t| t.as_ref() == Some(&first)).then_some(first)
+            }
+            _ => None,
+        }
+    }
+
+    pub const fn val_type(&self) -> Option<ValType> {
+        match self {
+            Self::Unit | Self::Never => None,
+            Self::Int => Some(ValType::I64),
+            Self::Float => Some(ValType::F64),
+            Self::Bool | Self::Record(_) | Self::List(_) => Some(ValType::I32),
+        }
+    }
+
+    fn field(&self, name: &str) -> Option<(u32, &WasmType)> {
+        let Self::Record(fields) = self else {
+            return None;
+        };
+        let idx = fields.iter().position(|(field, _)| &field[..] == name)?;
+        Some((idx as u32 * 8, &fields[idx].1))
+    }
+}
+
+/// Instructions and the type of the value they leave on the stack
+type Code = (Vec<Instr>, WasmType);
+
+fn never() -> Code {
+    (vec![Instr::Unreachable], WasmType::Never)
+}
+
+#[derive(Debug, Clone)]
+struct FuncSig {
+    params: Vec<(Str, WasmType)>,
+    result: WasmType,
+    export: Option<Str>,
+}
+
+/// A local variable. `None` index means the variable has no runtime representation (e.g. `None`).
+type Local = (Option<u32>, WasmType);
+
+#[derive(Debug)]
+struct FuncState {
+    name: Str,
+    params: Vec<(Str, ValType)>,
+    locals: Vec<(Str, ValType)>,
+    scopes: Vec<Dict<Str, Local>>,
+}
+
+impl FuncState {
+    fn new(name: Str) -> Self {
+        Self {
+            name,
+            params: vec![],
+            locals: vec![],
+            scopes: vec![Dict::new()],
+        }
+    }
+
+    /// Declares a new local with a unique name (WAT does not allow duplicate names).
+    fn new_local(&mut self, name: &str, t: ValType, is_param: bool) -> u32 {
+        let exists = |name: &str| {
+            self.params
+                .iter()
+                .chain(self.locals.iter())
+                .any(|(n, _)| &n[..] == name)
+        };
+        let mut unique = name.to_string();
+        let mut n = 0;
+        while exists(&unique) {
+            n += 1;
+            unique = format!("{name}%{n}");
+        }
+        let idx = (self.params.len() + self.locals.len()) as u32;
+        if is_param {
+            self.params.push((Str::from(unique), t));
+        } else {
+            self.locals.push((Str::from(unique), t));
+        }
+        idx
+    }
+
+    fn bind(&mut self, name: &str, t: WasmType, is_param: bool) -> Option<u32> {
+        let idx = t.val_type().map(|vt| self.new_local(name, vt, is_param));
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(Str::rc(name), (idx, t));
+        idx
+    }
+
+    fn temp(&mut self, t: ValType) -> u32 {
+        self.new_local("%tmp", t, false)
+    }
+
+    fn get(&self, name: &str) -> Option<&Local> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+}
+
+/// Generates a `WasmModule` from HIR.
+/// Python-dependent code (`pyimport`, Python built-ins) is rejected with `FFIError`s,
+/// and code that cannot be represented in WASM is rejected with `FeatureError`s.
+#[derive(Debug)]
+pub struct WasmGenerator {
+    cfg: ErgConfig,
+    module: WasmModule,
+    funcs: Dict<Str, FuncSig>,
+    globals: Dict<Str, WasmType>,
+    /// names bound to Python objects (already reported)
+    py_names: Set<Str>,
+    helpers: Set<&'static str>,
+    cur: FuncState,
+    errors: CompileErrors,
+}
+
+impl WasmGenerator {
+    pub fn new(cfg: ErgConfig) -> Self {
+        Self {
+            cfg,
+            module: WasmModule {
+                memory_pages: 1,
+                ..Default::default()
+            },
+            funcs: Dict::new(),
+            globals: Dict::new(),
+            py_names: Set::new(),
+            helpers: Set::new(),
+            cur: FuncState::new(Str::ever(INIT)),
+            errors: CompileErrors::empty(),
+        }
+    }
+
+    pub fn generate(&mut self, hir: &HIR) -> CompileResult<WasmModule> {
+        for chunk in hir.module.iter() {
+            if let Expr::Def(def) = chunk {
+                self.register_def(def);
+            }
+        }
+        let mut init = vec![];
+        for chunk in hir.module.iter() {
+            match chunk {
+                Expr::Def(def) => match &def.sig {
+                    Signature::Subr(sig) => self.gen_subr(def, sig),
+                    _ => {
+                        let code = self.global_def(def);
+                        init.extend(code);
+                    }
+                },
+                _ => {
+                    let (code, t) = self.expr(chunk);
+                    init.extend(self.coerce(code, &t, &WasmType::Unit, chunk.loc()));
+                }
+            }
+        }
+        if !init.is_empty() {
+            let state = std::mem::replace(&mut self.cur, FuncState::new(Str::ever(INIT)));
+            self.module.funcs.push(Func {
+                name: state.name,
+                export: None,
+                params: vec![],
+                result: None,
+                locals: state.locals,
+                body: init,
+            });
+            self.module.start = Some(Str::ever(INIT));
+        }
+        self.gen_helpers();
+        if self.errors.is_empty() {
+            Ok(std::mem::take(&mut self.module))
+        } else {
+            Err(self.errors.take_all().into())
+        }
+    }
+
+    fn caused_by(&self) -> String {
+        if &self.cur.name[..] == INIT {
+            "<module>".into()
+        } else {
+            self.cur.name.to_string()
+        }
+    }
+
+    fn unsupported(&mut self, what: &str, loc: Location) -> Code {
+        self.errors.push(CompileError::uncompilable_error(
+            self.cfg.input.clone(),
+            line!() as usize,
+            loc,
+            what,
+            TARGET,
+            self.caused_by(),
+        ));
+        never()
+    }
+
+    fn py_ffi(&mut self, name: &str, loc: Location) -> Code {
+        self.errors.push(CompileError::py_ffi_error(
+            self.cfg.input.clone(),
+            line!() as usize,
+            loc,
+            name,
+            TARGET,
+            self.caused_by(),
+        ));
+        never()
+    }
+
+    fn wasm_type(&mut self, t: &Type, loc: Location) -> Option<WasmType> {
+        let res = WasmType::from_type(t);
+        if res.is_none() {
+            self.unsupported(&format!("the type `{t}`"), loc);
+        }
+        res
+    }
+
+    fn helper(&mut self, name: &'static str) -> Instr {
+        if matches!(name, LIST_NEW | LIST_PUSH) {
+            self.helpers.insert(ALLOC);
+        }
+        self.helpers.insert(name);
+        Instr::Call(Str::ever(name))
+    }
+
+    /// Imports `erg.{field}: param -> ()` from the host.
+    fn host(&mut self, field: &'static str, param: ValType) -> Instr {
+        let name = Str::from(format!("__{field}"));
+        if !self.module.imports.iter().any(|import| import.name == name) {
+            self.module.imports.push(Import {
+                module: Str::ever("erg"),
+                field: Str::ever(field),
+                name: name.clone(),
+                ty: FuncType {
+                    params: vec![param],
+                    result: None,
+                },
+            });
+        }
+        Instr::Call(name)
+    }
+
+    /* definitions */
+
+    fn register_def(&mut self, def: &Def) {
+        let ident = def.sig.ident();
+        match &def.sig {
+            Signature::Subr(sig) => {
+                if let Some(fsig) = self.subr_sig(sig) {
+                    self.funcs.insert(ident.inspect().clone(), fsig);
+                }
+            }
+            Signature::Var(_) => {
+                if ident.vi.t.is_py_module() {
+                    return;
+                }
+                if let Some(t) = WasmType::from_type(&ident.vi.t) {
+                    if let Some(vt) = t.val_type() {
+                        self.module.globals.push(Global {
+                            name: ident.inspect().clone(),
+                            ty: vt,
+                            mutable: true,
+                            init: zero(vt),
+                        });
+                    }
+                    self.globals.insert(ident.inspect().clone(), t);
+                }
+            }
+            Signature::Glob(_) => {}
+        }
+    }
+
+    fn subr_sig(&mut self, sig: &SubrSignature) -> Option<FuncSig> {
+        let t = &sig.ident.vi.t;
+        let loc = sig.ident.loc();
+        if t.is_quantified_subr() || t.has_qvar() {
+            self.unsupported("polymorphic subroutines", loc);
+            return None;
+        }
+        if !sig.params.defaults.is_empty()
+            || sig.params.var_params.is_some()
+            || sig.params.kw_var_params.is_some()
+        {
+            self.unsupported("default and variable-length parameters", loc);
+            return None;
+        }
+        let mut params = vec![];
+        for (i, param) in sig.params.non_defaults.iter().enumerate() {
+            let name = param
+                .inspect()
+                .cloned()
+                .unwrap_or_else(|| Str::from(format!("_{i}")));
+            let pt = self.wasm_type(&param.vi.t, param.loc())?;
+            params.push((name, pt));
+        }
+        let result = self.wasm_type(t.return_t()?, loc)?;
+        let export = sig
+            .ident
+            .vis()
+            .is_public()
+            .then(|| sig.ident.inspect().clone());
+        Some(FuncSig {
+            params,
+            result,
+            export,
+        })
+    }
+
+    fn gen_subr(&mut self, def: &Def, sig: &SubrSignature) {
+        let name = sig.ident.inspect();
+        let Some(fsig) = self.funcs.get(name).cloned() else {
+            return;
+        };
+        let prev = std::mem::replace(&mut self.cur, FuncState::new(name.clone()));
+        for (pname, pt) in fsig.params.iter() {
+            self.cur.bind(pname, pt.clone(), true);
+        }
+        let mut body = self.guards(&sig.params);
+        body.extend(self.block_as(&def.body.block, &fsig.result));
+        let state = std::mem::replace(&mut self.cur, prev);
+        self.module.funcs.push(Func {
+            name: name.clone(),
+            export: fsig.export,
+            params: state.params,
+            result: fsig.result.val_type(),
+            locals: state.locals,
+            body,
+        });
+    }
+
+    /// Binds the variables of destructuring parameters.
+    /// The conditions are type tests, which always hold after type checking.
+    fn guards(&mut self, params: &Params) -> Vec<Instr> {
+        let mut code = vec![];
+        for guard in params.guards.iter() {
+            if let GuardClause::Bind(def) = guard {
+                let (c, _) = self.local_def(def);
+                code.extend(c);
+            }
+        }
+        code
+    }
+
+    fn global_def(&mut self, def: &Def) -> Vec<Instr> {
+        let ident = def.sig.ident();
+        let (mut code, t) = self.block(&def.body.block);
+        if ident.vi.t.is_py_module() || t == WasmType::Never {
+            self.py_names.insert(ident.inspect().clone());
+            return code;
+        }
+        let Some(expected) = self.globals.get(ident.inspect()).cloned() else {
+            self.wasm_type(&ident.vi.t, ident.loc());
+            self.py_names.insert(ident.inspect().clone());
+            return code;
+        };
+        code = self.coerce(code, &t, &expected, def.loc());
+        if expected.val_type().is_some() {
+            code.push(Instr::GlobalSet(ident.inspect().clone()));
+        }
+        code
+    }
+
+    fn local_def(&mut self, def: &Def) -> Code {
+        let ident = match &def.sig {
+            Signature::Var(var) => &var.ident,
+            Signature::Subr(_) => return self.unsupported("nested subroutines", def.loc()),
+            Signature::Glob(_) => return self.unsupported("global declarations", def.loc()),
+        };
+        let (mut code, t) = self.block(&def.body.block);
+        if ident.vi.t.is_py_module() || t == WasmType::Never {
+            self.py_names.insert(ident.inspect().clone());
+            return (code, WasmType::Unit);
+        }
+        let Some(expected) = self.wasm_type(&ident.vi.t, ident.loc()) else {
+            self.py_names.insert(ident.inspect().clone());
+            return (code, WasmType::Unit);
+        };
+        code = self.coerce(code, &t, &expected, def.loc());
+        if let Some(idx) = self.cur.bind(ident.inspect(), expected, false) {
+            code.push(Instr::LocalSet(idx));
+        }
+        (code, WasmType::Unit)
+    }
+
+    /* expressions */
+
+    fn block(&mut self, block: &Block) -> Code {
+        let mut code = vec![];
+        let mut t = WasmType::Unit;
+        for (i, chunk) in block.iter().enumerate() {
+            let (c, ct) = self.expr(chunk);
+            if i + 1 == block.len() {
+                code.extend(c);
+                t = ct;
+            } else {
+                code.extend(self.coerce(c, &ct, &WasmType::Unit, chunk.loc()));
+            }
+        }
+        (code, t)
+    }
+
+    fn block_as(&mut self, block: &Block, expected: &WasmType) -> Vec<Instr> {
+        let (code, t) = self.block(block);
+        self.coerce(code, &t, expected, block.loc())
+    }
+
+    fn expr_as(&mut self, expr: &Expr, expected: &WasmType) -> Vec<Instr> {
+        let (code, t) = self.expr(expr);
+        self.coerce(code, &t, expected, expr.loc())
+    }
+
+    fn coerce(
+        &mut self,
+        mut code: Vec<Instr>,
+        from: &WasmType,
+        to: &WasmType,
+        loc: Location,
+    ) -> Vec<Instr> {
+        match (from, to) {
+            _ if from == to => {}
+            (WasmType::Never, _) => {}
+            (_, WasmType::Unit) => {
+                if from.val_type().is_some() {
+                    code.push(Instr::Drop);
+                }
+            }
+            (WasmType::Int, WasmType::Float) => code.push(Instr::Num(NumOp::F64ConvertI64S)),
+            (WasmType::Bool, WasmType::Int) => code.push(Instr::Num(NumOp::I64ExtendI32U)),
+            (WasmType::Bool, WasmType::Float) => code.extend([
+                Instr::Num(NumOp::I64ExtendI32U),
+                Instr::Num(NumOp::F64ConvertI64S),
+            ]),
+            _ => {
+                return self
+                    .unsupported(&format!("converting `{from}` to `{to}`"), loc)
+                    .0;
+            }
+        }
+        code
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Code {
+        match expr {
+            Expr::Literal(lit) => self.literal(lit),
+            Expr::Accessor(Accessor::Ident(ident)) => self.ident(ident),
+            Expr::Accessor(Accessor::Attr(attr)) => self.attr(attr),
+            Expr::List(List::Normal(list)) => {
+                let elems = list.elems.pos_args.iter().map(|arg| &arg.expr);
+                self.list(elems, list.ref_t(), list.loc())
+            }
+            Expr::Record(record) => self.record(record),
+            Expr::BinOp(bin) => self.binop(&bin.op.kind, &bin.lhs, &bin.rhs, bin.loc()),
+            Expr::UnaryOp(unary) => self.unaryop(&unary.op.kind, &unary.expr, unary.loc()),
+            Expr::Call(call) => self.call(call),
+            Expr::Def(def) => self.local_def(def),
+            Expr::ReDef(redef) => {
+                let Accessor::Ident(ident) = &redef.attr else {
+                    return self.unsupported("reassigning attributes", redef.loc());
+                };
+                let (code, t) = self.block(&redef.block);
+                self.assign(ident, code, &t)
+            }
+            Expr::TypeAsc(tasc) => self.expr(&tasc.expr),
+            Expr::Compound(block) | Expr::Code(block) => self.block(block),
+            Expr::Dummy(dummy) => {
+                let mut code = vec![];
+                for chunk in dummy.iter() {
+                    let (c, t) = self.expr(chunk);
+                    code.extend(self.coerce(c, &t, &WasmType::Unit, chunk.loc()));
+                }
+                (code, WasmType::Unit)
+            }
+            Expr::Lambda(lambda) => self.unsupported("first-class subroutines", lambda.loc()),
+            Expr::ClassDef(_) | Expr::PatchDef(_) => self.unsupported("classes", expr.loc()),
+            Expr::List(_) => self.unsupported("list comprehensions", expr.loc()),
+            Expr::Tuple(_) => self.unsupported("tuples", expr.loc()),
+            Expr::Set(_) => self.unsupported("sets", expr.loc()),
+            Expr::Dict(_) => self.unsupported("dicts", expr.loc()),
+            Expr::Import(_) => self.unsupported("importing modules", expr.loc()),
+        }
+    }
+
+    fn literal(&mut self, lit: &Literal) -> Code {
+        match &lit.value {
+            ValueObj::Int(i) => (vec![Instr::I64Const(*i as i64)], WasmType::Int),
+            ValueObj::Nat(n) => match i64::try_from(*n) {
+                Ok(n) => (vec![Instr::I64Const(n)], WasmType::Int),
+                Err(_) => self.unsupported("integers out of the 64-bit range", lit.loc()),
+            },
+            ValueObj::Float(f) => (vec![Instr::F64Const(**f)], WasmType::Float),
+            ValueObj::Bool(b) => (vec![Instr::I32Const(*b as i32)], WasmType::Bool),
+            ValueObj::None => (vec![], WasmType::Unit),
+            other => self.unsupported(&format!("`{}` values", other.class()), lit.loc()),
+        }
+    }
+
+    fn ident(&mut self, ident: &Identifier) -> Code {
+        let name = ident.inspect();
+        if let Some((idx, t)) = self.cur.get(name) {
+            return (idx.map(Instr::LocalGet).into_iter().collect(), t.clone());
+        }
+        if let Some(t) = self.globals.get(name) {
+            let code = if t.val_type().is_some() {
+                vec![Instr::GlobalGet(name.clone())]
+            } else {
+                vec![]
+            };
+            return (code, t.clone());
+        }
+        if self.py_names.contains(name) {
+            return never();
+        }
+        if self.funcs.contains_key(name) {
+            return self.unsupported("first-class subroutines", ident.loc());
+        }
+        self.py_ffi(name, ident.loc())
+    }
+
+    fn assign(&mut self, ident: &Identifier, code: Vec<Instr>, t: &WasmType) -> Code {
+        let name = ident.inspect();
+        let (set, expected) = if let Some((idx, expected)) = self.cur.get(name) {
+            (idx.map(Instr::LocalSet), expected.clone())
+        } else if let Some(expected) = self.globals.get(name) {
+            let set = expected.val_type().map(|_| Instr::GlobalSet(name.clone()));
+            (set, expected.clone())
+        } else if self.py_names.contains(name) {
+            return never();
+        } else {
+            return self.unsupported("reassigning this variable", ident.loc());
+        };
+        let mut code = self.coerce(code, t, &expected, ident.loc());
+        code.extend(set);
+        (code, WasmType::Unit)
+    }
+
+    fn attr(&mut self, attr: &Attribute) -> Code {
+        let (mut code, t) = self.expr(&attr.obj);
+        if t == WasmType::Never {
+            return never();
+        }
+        let name = attr.ident.inspect();
+        match t.field(name) {
+            Some((offset, ft)) => {
+                match ft.val_type() {
+                    Some(vt) => code.push(Instr::Load(vt, offset)),
+                    None => code.push(Instr::Drop),
+                }
+                (code, ft.clone())
+            }
+            None => self.unsupported(&format!("the attribute `{name}` of `{t}`"), attr.loc()),
+        }
+    }
+
+    fn list<'e>(
+        &mut self,
+        elems: impl ExactSizeIterator<Item = &'e Expr>,
+        t: &Type,
+        loc: Location,
+    ) -> Code {
+        let Some(list_t) = self.wasm_type(t, loc) else {
+            return never();
+        };
+        let WasmType::List(elem_t) = &list_t else {
+            return self.unsupported(&format!("the type `{t}`"), loc);
+        };
+        let tmp = self.cur.temp(ValType::I32);
+        let mut code = vec![
+            Instr::I32Const(elems.len() as i32),
+            self.helper(LIST_NEW),
+            Instr::LocalSet(tmp),
+        ];
+        for (i, elem) in elems.enumerate() {
+            let value = self.expr_as(elem, elem_t);
+            match elem_t.val_type() {
+                Some(vt) => {
+                    code.extend([Instr::LocalGet(tmp), Instr::Load(ValType::I32, 8)]);
+                    code.extend(value);
+                    code.push(Instr::Store(vt, i as u32 * 8));
+                }
+                None => code.extend(value),
+            }
+        }
+        code.push(Instr::LocalGet(tmp));
+        (code, list_t)
+    }
+
+    fn record(&mut self, record: &Record) -> Code {
+        let Some(rec_t) = self.wasm_type(record.ref_t(), record.loc()) else {
+            return never();
+        };
+        let WasmType::Record(fields) = &rec_t else {
+            return self.unsupported(&format!("the type `{}`", record.ref_t()), record.loc());
+        };
+        let tmp = self.cur.temp(ValType::I32);
+        let size = (fields.len().max(1) * 8) as i32;
+        let mut code = vec![
+            Instr::I32Const(size),
+            self.helper(ALLOC),
+            Instr::LocalSet(tmp),
+        ];
+        for def in record.attrs.iter() {
+            let Some((offset, ft)) = rec_t.field(def.sig.inspect()) else {
+                continue;
+            };
+            let ft = ft.clone();
+            let value = self.block_as(&def.body.block, &ft);
+            match ft.val_type() {
+                Some(vt) => {
+                    code.push(Instr::LocalGet(tmp));
+                    code.extend(value);
+                    code.push(Instr::Store(vt, offset));
+                }
+                None => code.extend(value),
+            }
+        }
+        code.push(Instr::LocalGet(tmp));
+        (code, rec_t)
+    }
+
+    /// Returns the operands converted to the common numeric type.
+    fn numeric_operands(
+        &mut self,
+        lhs: &Expr,
+        rhs: &Expr,
+        op: &str,
+        loc: Location,
+    ) -> Option<(Vec<Instr>, Vec<Instr>, WasmType)> {
+        let (lcode, lt) = self.expr(lhs);
+        let (rcode, rt) = self.expr(rhs);
+        let t = match (&lt, &rt) {
+            (WasmType::Never, _) | (_, WasmType::Never) => return None,
+            (WasmType::Float, WasmType::Int | WasmType::Bool | WasmType::Float)
+            | (WasmType::Int | WasmType::Bool, WasmType::Float) => WasmType::Float,
+            (WasmType::Int, WasmType::Int | WasmType::Bool) | (WasmType::Bool, WasmType::Int) => {
+                WasmType::Int
+            }
+            (WasmType::Bool, WasmType::Bool) => WasmType::Bool,
+            _ => {
+                self.unsupported(&format!("`{lt}` {op} `{rt}`"), loc);
+                return None;
+            }
+        };
+        let lcode = self.coerce(lcode, &lt, &t, lhs.loc());
+        let rcode = self.coerce(rcode, &rt, &t, rhs.loc());
+        Some((lcode, rcode, t))
+    }
+
+    fn binop(&mut self, op: &TokenKind, lhs: &Expr, rhs: &Expr, loc: Location) -> Code {
+        use NumOp::*;
+        use WasmType::{Bool, Float, Int};
+        match op {
+            TokenKind::AndOp | TokenKind::OrOp => {
+                let lhs = self.expr_as(lhs, &Bool);
+                let rhs = self.expr_as(rhs, &Bool);
+                let mut code = lhs;
+                code.push(if op == &TokenKind::AndOp {
+                    Instr::If(Some(ValType::I32), rhs, vec![Instr::I32Const(0)])
+                } else {
+                    Instr::If(Some(ValType::I32), vec![Instr::I32Const(1)], rhs)
+                });
+                return (code, Bool);
+            }
+            TokenKind::Closed | TokenKind::RightOpen | TokenKind::LeftOpen | TokenKind::Open => {
+                return self.unsupported("ranges outside of `for!`", loc);
+            }
+            TokenKind::InOp
+            | TokenKind::NotInOp
+            | TokenKind::ContainsOp
+            | TokenKind::IsOp
+            | TokenKind::IsNotOp => {
+                return self.unsupported(&format!("the operator `{}`", op_str(op)), loc);
+            }
+            _ => {}
+        }
+        let Some((mut code, mut rhs, mut t)) = self.numeric_operands(lhs, rhs, op_str(op), loc)
+        else {
+            return never();
+        };
+        let is_bitwise = matches!(op, TokenKind::BitAnd | TokenKind::BitOr | TokenKind::BitXor);
+        let is_eq = matches!(op, TokenKind::DblEq | TokenKind::NotEq);
+        if t == Bool && !is_bitwise && !is_eq {
+            // True + True == 2
+            code = self.coerce(code, &Bool, &Int, loc);
+            rhs = self.coerce(rhs, &Bool, &Int, loc);
+            t = Int;
+        }
+        if op == &TokenKind::Slash && t == Int {
+            code = self.coerce(code, &Int, &Float, loc);
+            rhs = self.coerce(rhs, &Int, &Float, loc);
+            t = Float;
+        }
+        code.extend(rhs);
+        let (instrs, ret_t) = match (op, &t) {
+            (TokenKind::Plus, Int) => (vec![Instr::Num(I64Add)], Int),
+            (TokenKind::Plus, Float) => (vec![Instr::Num(F64Add)], Float),
+            (TokenKind::Minus, Int) => (vec![Instr::Num(I64Sub)], Int),
+            (TokenKind::Minus, Float) => (vec![Instr::Num(F64Sub)], Float),
+            (TokenKind::Star, Int) => (vec![Instr::Num(I64Mul)], Int),
+            (TokenKind::Star, Float) => (vec![Instr::Num(F64Mul)], Float),
+            (TokenKind::Slash, Float) => (vec![Instr::Num(F64Div)], Float),
+            (TokenKind::FloorDiv, Int) => (vec![self.helper(FLOORDIV)], Int),
+            (TokenKind::FloorDiv, Float) => (vec![Instr::Num(F64Div), Instr::Num(F64Floor)], Float),
+            (TokenKind::Mod, Int) => (vec![self.helper(MOD)], Int),
+            (TokenKind::Mod, Float) => (vec![self.helper(FMOD)], Float),
+            (TokenKind::Pow, Int) => (vec![self.helper(POW)], Int),
+            (TokenKind::BitAnd, Int) => (vec![Instr::Num(I64And)], Int),
+            (TokenKind::BitAnd, Bool) => (vec![Instr::Num(I32And)], Bool),
+            (TokenKind::BitOr, Int) => (vec![Instr::Num(I64Or)], Int),
+            (TokenKind::BitOr, Bool) => (vec![Instr::Num(I32Or)], Bool),
+            (TokenKind::BitXor, Int) => (vec![Instr::Num(I64Xor)], Int),
+            (TokenKind::BitXor, Bool) => (vec![Instr::Num(I32Xor)], Bool),
+            (TokenKind::Shl, Int) => (vec![Instr::Num(I64Shl)], Int),
+            (TokenKind::Shr, Int) => (vec![Instr::Num(I64ShrS)], Int),
+            (TokenKind::Less, Int) => (vec![Instr::Num(I64LtS)], Bool),
+            (TokenKind::Less, Float) => (vec![Instr::Num(F64Lt)], Bool),
+            (TokenKind::Gre, Int) => (vec![Instr::Num(I64GtS)], Bool),
+            (TokenKind::Gre, Float) => (vec![Instr::Num(F64Gt)], Bool),
+            (TokenKind::LessEq, Int) => (vec![Instr::Num(I64LeS)], Bool),
+            (TokenKind::LessEq, Float) => (vec![Instr::Num(F64Le)], Bool),
+            (TokenKind::GreEq, Int) => (vec![Instr::Num(I64GeS)], Bool),
+            (TokenKind::GreEq, Float) => (vec![Instr::Num(F64Ge)], Bool),
+            (TokenKind::DblEq, Int) => (vec![Instr::Num(I64Eq)], Bool),
+            (TokenKind::DblEq, Float) => (vec![Instr::Num(F64Eq)], Bool),
+            (TokenKind::DblEq, Bool) => (vec![Instr::Num(I32Eq)], Bool),
+            (TokenKind::NotEq, Int) => (vec![Instr::Num(I64Ne)], Bool),
+            (TokenKind::NotEq, Float) => (vec![Instr::Num(F64Ne)], Bool),
+            (TokenKind::NotEq, Bool) => (vec![Instr::Num(I32Ne)], Bool),
+            _ => {
+                return self.unsupported(&format!("`{t}` {} `{t}`", op_str(op)), loc);
+            }
+        };
+        code.extend(instrs);
+        (code, ret_t)
+    }
+
+    fn unaryop(&mut self, op: &TokenKind, expr: &Expr, loc: Location) -> Code {
+        let (mut code, t) = self.expr(expr);
+        match (op, &t) {
+            (_, WasmType::Never) => return never(),
+            (TokenKind::PrePlus, WasmType::Int | WasmType::Float) => {}
+            (TokenKind::PreMinus, WasmType::Int) => {
+                code.insert(0, Instr::I64Const(0));
+                code.push(Instr::Num(NumOp::I64Sub));
+            }
+            (TokenKind::PreMinus, WasmType::Float) => code.push(Instr::Num(NumOp::F64Neg)),
+            (TokenKind::PreBitNot, WasmType::Int) => {
+                code.extend([Instr::I64Const(-1), Instr::Num(NumOp::I64Xor)]);
+            }
+            // `![1, 2]` creates a new list, but `!l` has to copy `l`
+            (TokenKind::Mutate, WasmType::List(_) | WasmType::Record(_))
+                if !matches!(expr, Expr::List(_) | Expr::Record(_)) =>
+            {
+                return self.unsupported(&format!("copying `{t}`"), loc);
+            }
+            (TokenKind::Mutate | TokenKind::RefOp | TokenKind::RefMutOp, _) => {}
+            _ => {
+                return self.unsupported(&format!("{}`{t}`", op_str(op)), loc);
+            }
+        }
+        (code, t)
+    }
+
+    /* calls */
+
+    fn call(&mut self, call: &Call) -> Code {
+        if let Some(attr_name) = call.attr_name.as_ref() {
+            return self.method_call(call, attr_name);
+        }
+        let Expr::Accessor(Accessor::Ident(ident)) = call.obj.as_ref() else {
+            let (code, t) = self.expr(&call.obj);
+            if t == WasmType::Never {
+                return (code, t);
+            }
+            return self.unsupported("calling this object", call.obj.loc());
+        };
+        let name = ident.inspect();
+        if let Some(fsig) = self.funcs.get(name).cloned() {
+            let mut code = vec![];
+            for (i, (pname, pt)) in fsig.params.iter().enumerate() {
+                let Some(arg) = call.args.nth_or_key(i, pname) else {
+                    return self.unsupported("missing arguments", call.loc());
+                };
+                code.extend(self.expr_as(arg, pt));
+            }
+            code.push(Instr::Call(name.clone()));
+            return (code, fsig.result);
+        }
+        if self.cur.get(name).is_some() || self.globals.contains_key(name) {
+            return self.unsupported("first-class subroutines", ident.loc());
+        }
+        if self.py_names.contains(name) {
+            return never();
+        }
+        let args = &call.args;
+        match &name[..] {
+            "if" | "if!" => self.gen_if(call),
+            "while!" => self.gen_while(call),
+            "for!" => self.gen_for(call),
+            "assert" => {
+                let Some(cond) = args.get_left_or_key("test") else {
+                    return never();
+                };
+                let mut code = self.expr_as(cond, &WasmType::Bool);
+                code.extend([
+                    Instr::Num(NumOp::I32Eqz),
+                    Instr::If(None, vec![Instr::Unreachable], vec![]),
+                ]);
+                (code, WasmType::Unit)
+            }
+            "print!" => self.gen_print(args, call.loc()),
+            "not" => {
+                let Some(x) = args.get_left_or_key("b") else {
+                    return never();
+                };
+                let mut code = self.expr_as(x, &WasmType::Bool);
+                code.push(Instr::Num(NumOp::I32Eqz));
+                (code, WasmType::Bool)
+            }
+            "discard" => {
+                let mut code = vec![];
+                for arg in args.pos_args.iter() {
+                    code.extend(self.expr_as(&arg.expr, &WasmType::Unit));
+                }
+                (code, WasmType::Unit)
+            }
+            "len" | "abs" | "int" | "float" if args.pos_args.len() == 1 => {
+                let arg = &args.pos_args[0].expr;
+                let (mut code, t) = self.expr(arg);
+                let ret_t = match (&name[..], &t) {
+                    (_, WasmType::Never) => return never(),
+                    ("len", WasmType::List(_)) => {
+                        code.extend([
+                            Instr::Load(ValType::I32, 0),
+                            Instr::Num(NumOp::I64ExtendI32U),
+                        ]);
+                        WasmType::Int
+                    }
+                    ("abs", WasmType::Int) => {
+                        code.push(self.helper(ABS));
+                        WasmType::Int
+                    }
+                    ("abs", WasmType::Float) => {
+                        code.push(Instr::Num(NumOp::F64Abs));
+                        WasmType::Float
+                    }
+                    ("int", WasmType::Int | WasmType::Bool) => {
+                        return (
+                            self.coerce(code, &t, &WasmType::Int, arg.loc()),
+                            WasmType::Int,
+                        );
+                    }
+                    ("int", WasmType::Float) => {
+                        code.push(Instr::Num(NumOp::I64TruncF64S));
+                        WasmType::Int
+                    }
+                    ("float", WasmType::Int | WasmType::Bool | WasmType::Float) => {
+                        return (
+                            self.coerce(code, &t, &WasmType::Float, arg.loc()),
+                            WasmType::Float,
+                        );
+                    }
+                    _ => return self.unsupported(&format!("`{name}` of `{t}`"), call.loc()),
+                };
+                (code, ret_t)
+            }
+            "import" => {
+                let (code, t) = self.expr(&call.obj);
+                if t == WasmType::Never {
+                    return (code, t);
+                }
+                self.unsupported("importing modules", call.loc())
+            }
+            "pyimport" | "py" | "__import__" => {
+                let mod_name = args
+                    .get_left_or_key("Path")
+                    .and_then(|arg| match arg {
+                        Expr::Literal(lit) => match &lit.value {
+                            ValueObj::Str(s) => Some(s.clone()),
+                            _ => None,
+                        },
+                        _ => None,
+                    })
+                    .unwrap_or_else(|| name.clone());
+                self.py_ffi(&mod_name, call.loc())
+            }
+            _ => self.py_ffi(name, ident.loc()),
+        }
+    }
+
+    fn method_call(&mut self, call: &Call, attr_name: &Identifier) -> Code {
+        let method = attr_name.inspect();
+        let (mut code, t) = self.expr(&call.obj);
+        let args = &call.args;
+        match (&t, &method[..]) {
+            (WasmType::Never, _) => never(),
+            (WasmType::List(elem_t), "push!") => {
+                let Some(elem) = args.get_left_or_key("elem") else {
+                    return never();
+                };
+                let elem_t = elem_t.as_ref().clone();
+                code.push(self.helper(LIST_PUSH));
+                code.extend(self.expr_as(elem, &elem_t));
+                match elem_t.val_type() {
+                    Some(vt) => code.push(Instr::Store(vt, 0)),
+                    None => code.push(Instr::Drop),
+                }
+                (code, WasmType::Unit)
+            }
+            (WasmType::List(elem_t), "__getitem__") => {
+                let Some(idx) = args.get_left_or_key("index") else {
+                    return never();
+                };
+                let elem_t = elem_t.as_ref().clone();
+                code.extend(self.expr_as(idx, &WasmType::Int));
+                code.push(self.helper(LIST_ADDR));
+                match elem_t.val_type() {
+                    Some(vt) => code.push(Instr::Load(vt, 0)),
+                    None => code.push(Instr::Drop),
+                }
+                (code, elem_t)
+            }
+            (WasmType::Int | WasmType::Float, "inc!" | "dec!" | "update!") => {
+                let Expr::Accessor(Accessor::Ident(ident)) = call.obj.as_ref() else {
+                    return self.unsupported(&format!("`{method}` of this object"), call.loc());
+                };
+                let t = t.clone();
+                let new_value = if &method[..] == "update!" {
+                    let Some(f) = args.get_left_or_key("f") else {
+                        return never();
+                    };
+                    self.inline_lambda(f, vec![(code, t.clone())], &t)
+                } else {
+                    let (one, add, sub) = if t == WasmType::Int {
+                        (Instr::I64Const(1), NumOp::I64Add, NumOp::I64Sub)
+                    } else {
+                        (Instr::F64Const(1.0), NumOp::F64Add, NumOp::F64Sub)
+                    };
+                    match args.get_left_or_key("value") {
+                        Some(value) => code.extend(self.expr_as(value, &t)),
+                        None => code.push(one),
+                    }
+                    code.push(Instr::Num(if &method[..] == "inc!" { add } else { sub }));
+                    code
+                };
+                self.assign(ident, new_value, &t)
+            }
+            _ => self.unsupported(&format!("the method `{method}` of `{t}`"), call.loc()),
+        }
+    }
+
+    /// Inlines a block argument (e.g. `do ...`, `i => ...`) with the given arguments.
+    fn inline_lambda(&mut self, expr: &Expr, args: Vec<Code>, expected: &WasmType) -> Vec<Instr> {
+        let Expr::Lambda(lambda) = expr else {
+            let (code, t) = self.expr(expr);
+            if t == WasmType::Never {
+                return code;
+            }
+            return self.unsupported("first-class subroutines", expr.loc()).0;
+        };
+        self.cur.scopes.push(Dict::new());
+        let code = self.inline_lambda_body(lambda, args, expected);
+        self.cur.scopes.pop();
+        code
+    }
+
+    fn inline_lambda_body(
+        &mut self,
+        lambda: &Lambda,
+        args: Vec<Code>,
+        expected: &WasmType,
+    ) -> Vec<Instr> {
+        let mut code = vec![];
+        for (param, (arg, t)) in lambda.params.non_defaults.iter().zip(args) {
+            code.extend(arg);
+            let name = param.inspect().map_or("_", |name| &name[..]);
+            if let Some(idx) = self.cur.bind(name, t, false) {
+                code.push(Instr::LocalSet(idx));
+            }
+        }
+        code.extend(self.guards(&lambda.params));
+        code.extend(self.block_as(&lambda.body, expected));
+        code
+    }
+
+    fn gen_if(&mut self, call: &Call) -> Code {
+        let args = &call.args;
+        let (Some(cond), Some(then)) = (args.get_left_or_key("cond"), args.nth_or_key(1, "then"))
+        else {
+            return never();
+        };
+        let else_ = args.nth_or_key(2, "else");
+        let t = if else_.is_some() {
+            let Some(t) = self.wasm_type(call.ref_t(), call.loc()) else {
+                return never();
+            };
+            t
+        } else {
+            WasmType::Unit
+        };
+        let mut code = self.expr_as(cond, &WasmType::Bool);
+        let then = self.inline_lambda(then, vec![], &t);
+        let else_ = else_.map_or(vec![], |else_| self.inline_lambda(else_, vec![], &t));
+        code.push(Instr::If(t.val_type(), then, else_));
+        (code, t)
+    }
+
+    fn gen_while(&mut self, call: &Call) -> Code {
+        let args = &call.args;
+        let (Some(cond), Some(body)) = (args.get_left_or_key("cond!"), args.nth_or_key(1, "proc!"))
+        else {
+            return never();
+        };
+        let mut looped = self.inline_lambda(cond, vec![], &WasmType::Bool);
+        looped.extend([Instr::Num(NumOp::I32Eqz), Instr::BrIf(1)]);
+        looped.extend(self.inline_lambda(body, vec![], &WasmType::Unit));
+        looped.push(Instr::Br(0));
+        (
+            vec![Instr::Block(None, vec![Instr::Loop(None, looped)])],
+            WasmType::Unit,
+        )
+    }
+
+    fn gen_for(&mut self, call: &Call) -> Code {
+        let args = &call.args;
+        let (Some(iterable), Some(body)) = (
+            args.get_left_or_key("iterable"),
+            args.nth_or_key(1, "proc!"),
+        ) else {
+            return never();
+        };
+        if let Expr::BinOp(bin) = iterable {
+            if let Some((start_offset, end_offset)) = match bin.op.kind {
+                TokenKind::Closed => Some((0, 1)),
+                TokenKind::RightOpen => Some((0, 0)),
+                TokenKind::LeftOpen => Some((1, 1)),
+                TokenKind::Open => Some((1, 0)),
+                _ => None,
+            } {
+                return self.gen_range_for(
+                    bin.lhs.as_ref(),
+                    bin.rhs.as_ref(),
+                    start_offset,
+                    end_offset,
+                    body,
+                );
+            }
+        }
+        let (list, t) = self.expr(iterable);
+        let elem_t = match t {
+            WasmType::Never => return (list, t),
+            WasmType::List(elem_t) => *elem_t,
+            _ => return self.unsupported(&format!("iterating over `{t}`"), iterable.loc()),
+        };
+        let lst = self.cur.temp(ValType::I32);
+        let idx = self.cur.temp(ValType::I32);
+        let mut code = list;
+        code.extend([
+            Instr::LocalSet(lst),
+            Instr::I32Const(0),
+            Instr::LocalSet(idx),
+        ]);
+        let mut elem = vec![
+            Instr::LocalGet(lst),
+            Instr::Load(ValType::I32, 8),
+            Instr::LocalGet(idx),
+            Instr::I32Const(8),
+            Instr::Num(NumOp::I32Mul),
+            Instr::Num(NumOp::I32Add),
+        ];
+        match elem_t.val_type() {
+            Some(vt) => elem.push(Instr::Load(vt, 0)),
+            None => elem.push(Instr::Drop),
+        }
+        let mut looped = vec![
+            Instr::LocalGet(idx),
+            Instr::LocalGet(lst),
+            Instr::Load(ValType::I32, 0),
+            Instr::Num(NumOp::I32GeS),
+            Instr::BrIf(1),
+        ];
+        looped.extend(self.inline_lambda(body, vec![(elem, elem_t)], &WasmType::Unit));
+        looped.extend([
+            Instr::LocalGet(idx),
+            Instr::I32Const(1),
+            Instr::Num(NumOp::I32Add),
+            Instr::LocalSet(idx),
+            Instr::Br(0),
+        ]);
+        code.push(Instr::Block(None, vec![Instr::Loop(None, looped)]));
+        (code, WasmType::Unit)
+    }
+
+    fn gen_range_for(
+        &mut self,
+        start: &Expr,
+        end: &Expr,
+        start_offset: i64,
+        end_offset: i64,
+        body: &Expr,
+    ) -> Code {
+        let counter = self.cur.temp(ValType::I64);
+        let end_idx = self.cur.temp(ValType::I64);
+        let mut code = self.expr_as(start, &WasmType::Int);
+        if start_offset != 0 {
+            code.extend([Instr::I64Const(start_offset), Instr::Num(NumOp::I64Add)]);
+        }
+        code.push(Instr::LocalSet(counter));
+        code.extend(self.expr_as(end, &WasmType::Int));
+        if end_offset != 0 {
+            code.extend([Instr::I64Const(end_offset), Instr::Num(NumOp::I64Add)]);
+        }
+        code.push(Instr::LocalSet(end_idx));
+        let mut looped = vec![
+            Instr::LocalGet(counter),
+            Instr::LocalGet(end_idx),
+            Instr::Num(NumOp::I64GeS),
+            Instr::BrIf(1),
+        ];
+        let elem = (vec![Instr::LocalGet(counter)], WasmType::Int);
+        looped.extend(self.inline_lambda(body, vec![elem], &WasmType::Unit));
+        looped.extend([
+            Instr::LocalGet(counter),
+            Instr::I64Const(1),
+            Instr::Num(NumOp::I64Add),
+            Instr::LocalSet(counter),
+            Instr::Br(0),
+        ]);
+        code.push(Instr::Block(None, vec![Instr::Loop(None, looped)]));
+        (code, WasmType::Unit)
+    }
+
+    fn gen_print(&mut self, args: &Args, loc: Location) -> Code {
+        if !args.kw_args.is_empty() || args.var_args.is_some() {
+            return self.unsupported("keyword and variable-length arguments of `print!`", loc);
+        }
+        let mut code = vec![];
+        for (i, arg) in args.pos_args.iter().enumerate() {
+            if i > 0 {
+                code.extend([
+                    Instr::I32Const(b' ' as i32),
+                    self.host("write_char", ValType::I32),
+                ]);
+            }
+            let (value, t) = self.expr(&arg.expr);
+            code.extend(value);
+            match t {
+                WasmType::Never => {}
+                WasmType::Int => code.push(self.host("write_i64", ValType::I64)),
+                WasmType::Float => code.push(self.host("write_f64", ValType::F64)),
+                WasmType::Bool => code.push(self.host("write_bool", ValType::I32)),
+                _ => return self.unsupported(&format!("printing `{t}`"), arg.loc()),
+            }
+        }
+        code.extend([
+            Instr::I32Const(b'\n' as i32),
+            self.host("write_char", ValType::I32),
+        ]);
+        (code, WasmType::Unit)
+    }
+
+    /* runtime helpers */
+
+    fn gen_helpers(&mut self) {
+        if self.helpers.contains(ALLOC) {
+            // address 0 is reserved as null
+            self.module.globals.push(Global {
+                name: Str::ever(HEAP),
+                ty: ValType::I32,
+                mutable: true,
+                init: Instr::I32Const(8),
+            });
+        }
+        let mut helpers = self.helpers.iter().copied().collect::<Vec<_>>();
+        helpers.sort();
+        for name in helpers {
+            self.module.funcs.push(helper_func(name));
+        }
+    }
+}
+
+fn zero(t: ValType) -> Instr {
+    match t {
+        ValType::I32 => Instr::I32Const(0),
+        ValType::I64 => Instr::I64Const(0),
+        ValType::F64 => Instr::F64Const(0.0),
+    }
+}
+
+fn op_str(op: &TokenKind) -> &'static str {
+    match op {
+        TokenKind::Plus | TokenKind::PrePlus => "+",
+        TokenKind::Minus | TokenKind::PreMinus => "-",
+        TokenKind::Star => "*",
+        TokenKind::Slash => "/",
+        TokenKind::FloorDiv => "//",
+        TokenKind::Mod => "%",
+        TokenKind::Pow => "**",
+        TokenKind::BitAnd => "&&",
+        TokenKind::BitOr => "||",
+        TokenKind::BitXor => "^^",
+        TokenKind::Shl => "<<",
+        TokenKind::Shr => ">>",
+        TokenKind::Less => "<",
+        TokenKind::Gre => ">",
+        TokenKind::LessEq => "<=",
+        TokenKind::GreEq => ">=",
+        TokenKind::DblEq => "==",
+        TokenKind::NotEq => "!=",
+        TokenKind::InOp => "in",
+        TokenKind::NotInOp => "notin",
+        TokenKind::ContainsOp => "contains",
+        TokenKind::IsOp => "is!",
+        TokenKind::IsNotOp => "isnot!",
+        TokenKind::PreBitNot => "~",
+        TokenKind::Mutate => "!",
+        _ => "?",
+    }
+}
+
+/// The runtime helpers. The parameters are `local 0`, `local 1`, and the locals follow them.
+fn helper_func(name: &'static str) -> Func {
+    use Instr::*;
+    use NumOp::*;
+    use ValType::{F64, I32, I64};
+    let param = |n: &'static str, t: ValType| (Str::ever(n), t);
+    let (params, result, locals, body) = match name {
+        // (size: i32) -> i32
+        ALLOC => (
+            vec![param("size", I32)],
+            Some(I32),
+            vec![param("ptr", I32)],
+            vec![
+                GlobalGet(Str::ever(HEAP)),
+                LocalSet(1),
+                GlobalGet(Str::ever(HEAP)),
+                // align to 8 bytes
+                LocalGet(0),
+                I32Const(7),
+                Num(I32Add),
+                I32Const(-8),
+                Num(I32And),
+                Num(I32Add),
+                GlobalSet(Str::ever(HEAP)),
+                Block(
+                    None,
+                    vec![Loop(
+                        None,
+                        vec![
+                            GlobalGet(Str::ever(HEAP)),
+                            MemorySize,
+                            I32Const(65536),
+                            Num(I32Mul),
+                            Num(I32GtU),
+                            Num(I32Eqz),
+                            BrIf(1),
+                            I32Const(1),
+                            MemoryGrow,
+                            I32Const(-1),
+                            Num(I32Eq),
+                            If(None, vec![Unreachable], vec![]),
+                            Br(0),
+                        ],
+                    )],
+                ),
+                LocalGet(1),
+            ],
+        ),
+        // (len: i32) -> list
+        LIST_NEW => (
+            vec![param("len", I32)],
+            Some(I32),
+            vec![param("list", I32)],
+            vec![
+                I32Const(12),
+                Call(Str::ever(ALLOC)),
+                LocalSet(1),
+                LocalGet(1),
+                LocalGet(0),
+                Store(I32, 0),
+                LocalGet(1),
+                LocalGet(0),
+                Store(I32, 4),
+                LocalGet(1),
+                LocalGet(0),
+                I32Const(8),
+                Num(I32Mul),
+                Call(Str::ever(ALLOC)),
+                Store(I32, 8),
+                LocalGet(1),
+            ],
+        ),
+        // (list, index: i64) -> the address of the element (traps if out of range)
+        LIST_ADDR => (
+            vec![param("list", I32), param("index", I64)],
+            Some(I32),
+            vec![],
+            vec![
+                LocalGet(1),
+                I64Const(0),
+                Num(I64LtS),
+                If(
+                    None,
+                    vec![
+                        LocalGet(1),
+                        LocalGet(0),
+                        Load(I32, 0),
+                        Num(I64ExtendI32U),
+                        Num(I64Add),
+                        LocalSet(1),
+                    ],
+                    vec![],
+                ),
+                LocalGet(1),
+                I64Const(0),
+                Num(I64LtS),
+                LocalGet(1),
+                LocalGet(0),
+                Load(I32, 0),
+                Num(I64ExtendI32U),
+                Num(I64GeS),
+                Num(I32Or),
+                If(None, vec![Unreachable], vec![]),
+                LocalGet(0),
+                Load(I32, 8),
+                LocalGet(1),
+                Num(I32WrapI64),
+                I32Const(8),
+                Num(I32Mul),
+                Num(I32Add),
+            ],
+        ),
+        // (list) -> the address of the new last element
+        LIST_PUSH => (
+            vec![param("list", I32)],
+            Some(I32),
+            vec![param("data", I32), param("i", I32)],
+            vec![
+                LocalGet(0),
+                Load(I32, 0),
+                LocalGet(0),
+                Load(I32, 4),
+                Num(I32Eq),
+                If(
+                    None,
+                    vec![
+                        // cap = cap * 2 + 4
+                        LocalGet(0),
+                        LocalGet(0),
+                        Load(I32, 4),
+                        I32Const(2),
+                        Num(I32Mul),
+                        I32Const(4),
+                        Num(I32Add),
+                        Store(I32, 4),
+                        LocalGet(0),
+                        Load(I32, 4),
+                        I32Const(8),
+                        Num(I32Mul),
+                        Call(Str::ever(ALLOC)),
+                        LocalSet(1),
+                        I32Const(0),
+                        LocalSet(2),
+                        Block(
+                            None,
+                            vec![Loop(
+                                None,
+                                vec![
+                                    LocalGet(2),
+                                    LocalGet(0),
+                                    Load(I32, 0),
+                                    Num(I32GeS),
+                                    BrIf(1),
+                                    LocalGet(1),
+                                    LocalGet(2),
+                                    I32Const(8),
+                                    Num(I32Mul),
+                                    Num(I32Add),
+                                    LocalGet(0),
+                                    Load(I32, 8),
+                                    LocalGet(2),
+                                    I32Const(8),
+                                    Num(I32Mul),
+                                    Num(I32Add),
+                                    Load(I64, 0),
+                                    Store(I64, 0),
+                                    LocalGet(2),
+                                    I32Const(1),
+                                    Num(I32Add),
+                                    LocalSet(2),
+                                    Br(0),
+                                ],
+                            )],
+                        ),
+                        LocalGet(0),
+                        LocalGet(1),
+                        Store(I32, 8),
+                    ],
+                    vec![],
+                ),
+                LocalGet(0),
+                Load(I32, 8),
+                LocalGet(0),
+                Load(I32, 0),
+                I32Const(8),
+                Num(I32Mul),
+                Num(I32Add),
+                LocalGet(0),
+                LocalGet(0),
+                Load(I32, 0),
+                I32Const(1),
+                Num(I32Add),
+                Store(I32, 0),
+            ],
+        ),
+        // rounds toward negative infinity (like Python)
+        FLOORDIV => (
+            vec![param("a", I64), param("b", I64)],
+            Some(I64),
+            vec![],
+            vec![
+                LocalGet(0),
+                LocalGet(1),
+                Num(I64DivS),
+                // subtract 1 if the remainder is not 0 and the signs differ
+                LocalGet(0),
+                LocalGet(1),
+                Num(I64RemS),
+                I64Const(0),
+                Num(I64Ne),
+                LocalGet(0),
+                I64Const(0),
+                Num(I64LtS),
+                LocalGet(1),
+                I64Const(0),
+                Num(I64LtS),
+                Num(I32Xor),
+                Num(I32And),
+                Num(I64ExtendI32U),
+                Num(I64Sub),
+            ],
+        ),
+        // the result has the same sign as the divisor (like Python)
+        MOD => (
+            vec![param("a", I64), param("b", I64)],
+            Some(I64),
+            vec![param("r", I64)],
+            vec![
+                LocalGet(0),
+                LocalGet(1),
+                Num(I64RemS),
+                LocalSet(2),
+                LocalGet(2),
+                LocalGet(1),
+                LocalGet(2),
+                I64Const(0),
+                Num(I64Ne),
+                LocalGet(2),
+                I64Const(0),
+                Num(I64LtS),
+                LocalGet(1),
+                I64Const(0),
+                Num(I64LtS),
+                Num(I32Xor),
+                Num(I32And),
+                Num(I64ExtendI32U),
+                Num(I64Mul),
+                Num(I64Add),
+            ],
+        ),
+        // a - b * floor(a / b)
+        FMOD => (
+            vec![param("a", F64), param("b", F64)],
+            Some(F64),
+            vec![],
+            vec![
+                LocalGet(0),
+                LocalGet(1),
+                LocalGet(0),
+                LocalGet(1),
+                Num(F64Div),
+                Num(F64Floor),
+                Num(F64Mul),
+                Num(F64Sub),
+            ],
+        ),
+        // traps if the exponent is negative
+        POW => (
+            vec![param("base", I64), param("exp", I64)],
+            Some(I64),
+            vec![param("r", I64)],
+            vec![
+                I64Const(1),
+                LocalSet(2),
+                LocalGet(1),
+                I64Const(0),
+                Num(I64LtS),
+                If(None, vec![Unreachable], vec![]),
+                Block(
+                    None,
+                    vec![Loop(
+                        None,
+                        vec![
+                            LocalGet(1),
+                            Num(I64Eqz),
+                            BrIf(1),
+                            LocalGet(2),
+                            LocalGet(0),
+                            Num(I64Mul),
+                            LocalSet(2),
+                            LocalGet(1),
+                            I64Const(1),
+                            Num(I64Sub),
+                            LocalSet(1),
+                            Br(0),
+                        ],
+                    )],
+                ),
+                LocalGet(2),
+            ],
+        ),
+        ABS => (
+            vec![param("x", I64)],
+            Some(I64),
+            vec![],
+            vec![
+                LocalGet(0),
+                I64Const(0),
+                Num(I64LtS),
+                If(
+                    Some(I64),
+                    vec![I64Const(0), LocalGet(0), Num(I64Sub)],
+                    vec![LocalGet(0)],
+                ),
+            ],
+        ),
+        _ => unreachable!("unknown helper: {name}"),
+    };
+    Func {
+        name: Str::ever(name),
+        export: None,
+        params,
+        result,
+        locals,
+        body,
+    }
+}
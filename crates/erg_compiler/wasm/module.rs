@@ -0,0 +1,731 @@
+//! An in-memory WebAssembly module, which can be written in the text format (WAT) or the binary format.
+//!
+//! Functions and globals are referred to by name, and the indices are resolved when encoding.
+use std::fmt::Write as _;
+
+use erg_common::dict::Dict;
+use erg_common::Str;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValType {
+    I32,
+    I64,
+    F64,
+}
+
+impl ValType {
+    pub const fn code(&self) -> u8 {
+        match self {
+            Self::I32 => 0x7f,
+            Self::I64 => 0x7e,
+            Self::F64 => 0x7c,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::F64 => "f64",
+        }
+    }
+
+    const fn load(&self) -> (u8, &'static str) {
+        match self {
+            Self::I32 => (0x28, "i32.load"),
+            Self::I64 => (0x29, "i64.load"),
+            Self::F64 => (0x2b, "f64.load"),
+        }
+    }
+
+    const fn store(&self) -> (u8, &'static str) {
+        match self {
+            Self::I32 => (0x36, "i32.store"),
+            Self::I64 => (0x37, "i64.store"),
+            Self::F64 => (0x39, "f64.store"),
+        }
+    }
+}
+
+/// Numeric instructions without immediates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NumOp {
+    I32Eqz,
+    I32Eq,
+    I32Ne,
+    I32LtS,
+    I32GeS,
+    I32GtU,
+    I32Add,
+    I32Sub,
+    I32Mul,
+    I32And,
+    I32Or,
+    I32Xor,
+    I64Eqz,
+    I64Eq,
+    I64Ne,
+    I64LtS,
+    I64GtS,
+    I64LeS,
+    I64GeS,
+    I64Add,
+    I64Sub,
+    I64Mul,
+    I64DivS,
+    I64RemS,
+    I64And,
+    I64Or,
+    I64Xor,
+    I64Shl,
+    I64ShrS,
+    F64Eq,
+    F64Ne,
+    F64Lt,
+    F64Gt,
+    F64Le,
+    F64Ge,
+    F64Abs,
+    F64Neg,
+    F64Floor,
+    F64Add,
+    F64Sub,
+    F64Mul,
+    F64Div,
+    I32WrapI64,
+    I64TruncF64S,
+    I64ExtendI32U,
+    F64ConvertI64S,
+}
+
+impl NumOp {
+    pub const fn code(&self) -> u8 {
+        match self {
+            Self::I32Eqz => 0x45,
+            Self::I32Eq => 0x46,
+            Self::I32Ne => 0x47,
+            Self::I32LtS => 0x48,
+            Self::I32GeS => 0x4e,
+            Self::I32GtU => 0x4b,
+            Self::I32Add => 0x6a,
+            Self::I32Sub => 0x6b,
+            Self::I32Mul => 0x6c,
+            Self::I32And => 0x71,
+            Self::I32Or => 0x72,
+            Self::I32Xor => 0x73,
+            Self::I64Eqz => 0x50,
+            Self::I64Eq => 0x51,
+            Self::I64Ne => 0x52,
+            Self::I64LtS => 0x53,
+            Self::I64GtS => 0x55,
+            Self::I64LeS => 0x57,
+            Self::I64GeS => 0x59,
+            Self::I64Add => 0x7c,
+            Self::I64Sub => 0x7d,
+            Self::I64Mul => 0x7e,
+            Self::I64DivS => 0x7f,
+            Self::I64RemS => 0x81,
+            Self::I64And => 0x83,
+            Self::I64Or => 0x84,
+            Self::I64Xor => 0x85,
+            Self::I64Shl => 0x86,
+            Self::I64ShrS => 0x87,
+            Self::F64Eq => 0x61,
+            Self::F64Ne => 0x62,
+            Self::F64Lt => 0x63,
+            Self::F64Gt => 0x64,
+            Self::F64Le => 0x65,
+            Self::F64Ge => 0x66,
+            Self::F64Abs => 0x99,
+            Self::F64Neg => 0x9a,
+            Self::F64Floor => 0x9c,
+            Self::F64Add => 0xa0,
+            Self::F64Sub => 0xa1,
+            Self::F64Mul => 0xa2,
+            Self::F64Div => 0xa3,
+            Self::I32WrapI64 => 0xa7,
+            Self::I64TruncF64S => 0xb0,
+            Self::I64ExtendI32U => 0xad,
+            Self::F64ConvertI64S => 0xb9,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::I32Eqz => "i32.eqz",
+            Self::I32Eq => "i32.eq",
+            Self::I32Ne => "i32.ne",
+            Self::I32LtS => "i32.lt_s",
+            Self::I32GeS => "i32.ge_s",
+            Self::I32GtU => "i32.gt_u",
+            Self::I32Add => "i32.add",
+            Self::I32Sub => "i32.sub",
+            Self::I32Mul => "i32.mul",
+            Self::I32And => "i32.and",
+            Self::I32Or => "i32.or",
+            Self::I32Xor => "i32.xor",
+            Self::I64Eqz => "i64.eqz",
+            Self::I64Eq => "i64.eq",
+            Self::I64Ne => "i64.ne",
+            Self::I64LtS => "i64.lt_s",
+            Self::I64GtS => "i64.gt_s",
+            Self::I64LeS => "i64.le_s",
+            Self::I64GeS => "i64.ge_s",
+            Self::I64Add => "i64.add",
+            Self::I64Sub => "i64.sub",
+            Self::I64Mul => "i64.mul",
+            Self::I64DivS => "i64.div_s",
+            Self::I64RemS => "i64.rem_s",
+            Self::I64And => "i64.and",
+            Self::I64Or => "i64.or",
+            Self::I64Xor => "i64.xor",
+            Self::I64Shl => "i64.shl",
+            Self::I64ShrS => "i64.shr_s",
+            Self::F64Eq => "f64.eq",
+            Self::F64Ne => "f64.ne",
+            Self::F64Lt => "f64.lt",
+            Self::F64Gt => "f64.gt",
+            Self::F64Le => "f64.le",
+            Self::F64Ge => "f64.ge",
+            Self::F64Abs => "f64.abs",
+            Self::F64Neg => "f64.neg",
+            Self::F64Floor => "f64.floor",
+            Self::F64Add => "f64.add",
+            Self::F64Sub => "f64.sub",
+            Self::F64Mul => "f64.mul",
+            Self::F64Div => "f64.div",
+            Self::I32WrapI64 => "i32.wrap_i64",
+            Self::I64TruncF64S => "i64.trunc_f64_s",
+            Self::I64ExtendI32U => "i64.extend_i32_u",
+            Self::F64ConvertI64S => "f64.convert_i64_s",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instr {
+    Unreachable,
+    /// `block`, `loop` and `if` take the result type
+    Block(Option<ValType>, Vec<Instr>),
+    Loop(Option<ValType>, Vec<Instr>),
+    If(Option<ValType>, Vec<Instr>, Vec<Instr>),
+    Br(u32),
+    BrIf(u32),
+    Return,
+    Call(Str),
+    Drop,
+    LocalGet(u32),
+    LocalSet(u32),
+    LocalTee(u32),
+    GlobalGet(Str),
+    GlobalSet(Str),
+    /// load/store with the offset (values are 8-byte aligned)
+    Load(ValType, u32),
+    Store(ValType, u32),
+    MemorySize,
+    MemoryGrow,
+    I32Const(i32),
+    I64Const(i64),
+    F64Const(f64),
+    Num(NumOp),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FuncType {
+    pub params: Vec<ValType>,
+    pub result: Option<ValType>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub module: Str,
+    pub field: Str,
+    pub name: Str,
+    pub ty: FuncType,
+}
+
+#[derive(Debug, Clone)]
+pub struct Func {
+    pub name: Str,
+    /// the name exported to the host
+    pub export: Option<Str>,
+    pub params: Vec<(Str, ValType)>,
+    pub result: Option<ValType>,
+    pub locals: Vec<(Str, ValType)>,
+    pub body: Vec<Instr>,
+}
+
+impl Func {
+    pub fn ty(&self) -> FuncType {
+        FuncType {
+            params: self.params.iter().map(|(_, t)| *t).collect(),
+            result: self.result,
+        }
+    }
+
+    fn local_name(&self, idx: u32) -> &str {
+        let idx = idx as usize;
+        match self.params.get(idx) {
+            Some((name, _)) => name,
+            None => &self.locals[idx - self.params.len()].0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Global {
+    pub name: Str,
+    pub ty: ValType,
+    pub mutable: bool,
+    /// the initial value must be a constant
+    pub init: Instr,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WasmModule {
+    pub imports: Vec<Import>,
+    pub funcs: Vec<Func>,
+    pub globals: Vec<Global>,
+    /// the initial number of memory pages (the memory is exported as `memory`)
+    pub memory_pages: u32,
+    /// the function called when the module is instantiated
+    pub start: Option<Str>,
+}
+
+/// `$name` of WAT. Characters not allowed in identifiers are replaced with `_`.
+fn wat_id(name: &str) -> String {
+    let mut id = String::with_capacity(name.len() + 1);
+    id.push('$');
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || "!#$%&'*+-./:<=>?@\\^_`|~".contains(c) {
+            id.push(c);
+        } else {
+            id.push('_');
+        }
+    }
+    id
+}
+
+fn wat_str(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for b in s.bytes() {
+        match b {
+            b'"' | b'\\' => {
+                escaped.push('\\');
+                escaped.push(b as char);
+            }
+            0x20..=0x7e => escaped.push(b as char),
+            _ => {
+                let _ = write!(escaped, "\\{b:02x}");
+            }
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn wat_result(result: Option<ValType>) -> String {
+    result.map_or(String::new(), |t| format!(" (result {})", t.name()))
+}
+
+fn wat_f64(f: f64) -> String {
+    if f.is_nan() {
+        "nan".into()
+    } else if f.is_infinite() {
+        if f > 0.0 { "inf" } else { "-inf" }.into()
+    } else {
+        format!("{f:?}")
+    }
+}
+
+impl WasmModule {
+    /// Writes the module in the WebAssembly text format.
+    pub fn to_wat(&self) -> String {
+        let mut wat = "(module\n".to_string();
+        for import in self.imports.iter() {
+            let params = import
+                .ty
+                .params
+                .iter()
+                .map(|t| format!(" (param {})", t.name()))
+                .collect::<String>();
+            let _ = writeln!(
+                wat,
+                "  (import {} {} (func {}{params}{}))",
+                wat_str(&import.module),
+                wat_str(&import.field),
+                wat_id(&import.name),
+                wat_result(import.ty.result),
+            );
+        }
+        let _ = writeln!(wat, "  (memory (export \"memory\") {})", self.memory_pages);
+        for global in self.globals.iter() {
+            let ty = if global.mutable {
+                format!("(mut {})", global.ty.name())
+            } else {
+                global.ty.name().to_string()
+            };
+            let mut init = String::new();
+            Self::write_instr(&mut init, None, &global.init, 0);
+            let _ = writeln!(
+                wat,
+                "  (global {} {ty} ({}))",
+                wat_id(&global.name),
+                init.trim()
+            );
+        }
+        for func in self.funcs.iter() {
+            let _ = write!(wat, "  (func {}", wat_id(&func.name));
+            if let Some(export) = func.export.as_ref() {
+                let _ = write!(wat, " (export {})", wat_str(export));
+            }
+            for (name, t) in func.params.iter() {
+                let _ = write!(wat, " (param {} {})", wat_id(name), t.name());
+            }
+            wat += &wat_result(func.result);
+            wat.push('\n');
+            for (name, t) in func.locals.iter() {
+                let _ = writeln!(wat, "    (local {} {})", wat_id(name), t.name());
+            }
+            for instr in func.body.iter() {
+                Self::write_instr(&mut wat, Some(func), instr, 2);
+            }
+            wat += "  )\n";
+        }
+        if let Some(start) = self.start.as_ref() {
+            let _ = writeln!(wat, "  (start {})", wat_id(start));
+        }
+        wat += ")\n";
+        wat
+    }
+
+    fn write_instr(wat: &mut String, func: Option<&Func>, instr: &Instr, level: usize) {
+        let indent = "  ".repeat(level);
+        let local = |idx: &u32| func.map_or(idx.to_string(), |f| wat_id(f.local_name(*idx)));
+        let line = match instr {
+            Instr::Block(result, body) | Instr::Loop(result, body) => {
+                let kw = if matches!(instr, Instr::Block(..)) {
+                    "block"
+                } else {
+                    "loop"
+                };
+                let _ = writeln!(wat, "{indent}{kw}{}", wat_result(*result));
+                for instr in body.iter() {
+                    Self::write_instr(wat, func, instr, level + 1);
+                }
+                "end".to_string()
+            }
+            Instr::If(result, then, else_) => {
+                let _ = writeln!(wat, "{indent}if{}", wat_result(*result));
+                for instr in then.iter() {
+                    Self::write_instr(wat, func, instr, level + 1);
+                }
+                if !else_.is_empty() {
+                    let _ = writeln!(wat, "{indent}else");
+                    for instr in else_.iter() {
+                        Self::write_instr(wat, func, instr, level + 1);
+                    }
+                }
+                "end".to_string()
+            }
+            Instr::Unreachable => "unreachable".into(),
+            Instr::Br(depth) => format!("br {depth}"),
+            Instr::BrIf(depth) => format!("br_if {depth}"),
+            Instr::Return => "return".into(),
+            Instr::Call(name) => format!("call {}", wat_id(name)),
+            Instr::Drop => "drop".into(),
+            Instr::LocalGet(idx) => format!("local.get {}", local(idx)),
+            Instr::LocalSet(idx) => format!("local.set {}", local(idx)),
+            Instr::LocalTee(idx) => format!("local.tee {}", local(idx)),
+            Instr::GlobalGet(name) => format!("global.get {}", wat_id(name)),
+            Instr::GlobalSet(name) => format!("global.set {}", wat_id(name)),
+            Instr::Load(t, 0) => t.load().1.to_string(),
+            Instr::Load(t, offset) => format!("{} offset={offset}", t.load().1),
+            Instr::Store(t, 0) => t.store().1.to_string(),
+            Instr::Store(t, offset) => format!("{} offset={offset}", t.store().1),
+            Instr::MemorySize => "memory.size".into(),
+            Instr::MemoryGrow => "memory.grow".into(),
+            Instr::I32Const(i) => format!("i32.const {i}"),
+            Instr::I64Const(i) => format!("i64.const {i}"),
+            Instr::F64Const(f) => format!("f64.const {}", wat_f64(*f)),
+            Instr::Num(op) => op.name().into(),
+        };
+        let _ = writeln!(wat, "{indent}{line}");
+    }
+
+    /// Encodes the module in the WebAssembly binary format.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut types = Vec::<FuncType>::new();
+        let mut type_idx = |ty: FuncType| {
+            if let Some(idx) = types.iter().position(|t| t == &ty) {
+                idx as u32
+            } else {
+                types.push(ty);
+                types.len() as u32 - 1
+            }
+        };
+        let import_types = self
+            .imports
+            .iter()
+            .map(|import| type_idx(import.ty.clone()))
+            .collect::<Vec<_>>();
+        let func_types = self
+            .funcs
+            .iter()
+            .map(|func| type_idx(func.ty()))
+            .collect::<Vec<_>>();
+        let mut indices = Indices::default();
+        for name in self
+            .imports
+            .iter()
+            .map(|import| &import.name)
+            .chain(self.funcs.iter().map(|func| &func.name))
+        {
+            indices
+                .funcs
+                .insert(name.clone(), indices.funcs.len() as u32);
+        }
+        for global in self.globals.iter() {
+            indices
+                .globals
+                .insert(global.name.clone(), indices.globals.len() as u32);
+        }
+
+        let mut wasm = b"\0asm".to_vec();
+        wasm.extend(1u32.to_le_bytes());
+        // type section
+        let mut sec = vec![];
+        uleb(&mut sec, types.len() as u64);
+        for ty in types.iter() {
+            sec.push(0x60);
+            uleb(&mut sec, ty.params.len() as u64);
+            sec.extend(ty.params.iter().map(ValType::code));
+            match ty.result {
+                Some(t) => sec.extend([1, t.code()]),
+                None => sec.push(0),
+            }
+        }
+        section(&mut wasm, 1, sec);
+        // import section
+        if !self.imports.is_empty() {
+            let mut sec = vec![];
+            uleb(&mut sec, self.imports.len() as u64);
+            for (import, ty) in self.imports.iter().zip(import_types) {
+                name(&mut sec, &import.module);
+                name(&mut sec, &import.field);
+                sec.push(0x00);
+                uleb(&mut sec, ty as u64);
+            }
+            section(&mut wasm, 2, sec);
+        }
+        // function section
+        let mut sec = vec![];
+        uleb(&mut sec, func_types.len() as u64);
+        for ty in func_types {
+            uleb(&mut sec, ty as u64);
+        }
+        section(&mut wasm, 3, sec);
+        // memory section
+        let mut sec = vec![1, 0x00];
+        uleb(&mut sec, self.memory_pages as u64);
+        section(&mut wasm, 5, sec);
+        // global section
+        if !self.globals.is_empty() {
+            let mut sec = vec![];
+            uleb(&mut sec, self.globals.len() as u64);
+            for global in self.globals.iter() {
+                sec.extend([global.ty.code(), global.mutable as u8]);
+                indices.encode(&mut sec, &global.init);
+                sec.push(0x0b);
+            }
+            section(&mut wasm, 6, sec);
+        }
+        // export section
+        let exports = self
+            .funcs
+            .iter()
+            .filter_map(|func| Some((func.export.as_ref()?, indices.funcs[&func.name])))
+            .collect::<Vec<_>>();
+        let mut sec = vec![];
+        uleb(&mut sec, exports.len() as u64 + 1);
+        name(&mut sec, "memory");
+        sec.extend([0x02, 0x00]);
+        for (export, idx) in exports {
+            name(&mut sec, export);
+            sec.push(0x00);
+            uleb(&mut sec, idx as u64);
+        }
+        section(&mut wasm, 7, sec);
+        // start section
+        if let Some(start) = self.start.as_ref() {
+            let mut sec = vec![];
+            uleb(&mut sec, indices.funcs[start] as u64);
+            section(&mut wasm, 8, sec);
+        }
+        // code section
+        let mut sec = vec![];
+        uleb(&mut sec, self.funcs.len() as u64);
+        for func in self.funcs.iter() {
+            let mut body = vec![];
+            // consecutive locals of the same type are grouped
+            let mut groups = Vec::<(u32, ValType)>::new();
+            for (_, t) in func.locals.iter() {
+                match groups.last_mut() {
+                    Some((n, last)) if last == t => *n += 1,
+                    _ => groups.push((1, *t)),
+                }
+            }
+            uleb(&mut body, groups.len() as u64);
+            for (n, t) in groups {
+                uleb(&mut body, n as u64);
+                body.push(t.code());
+            }
+            for instr in func.body.iter() {
+                indices.encode(&mut body, instr);
+            }
+            body.push(0x0b);
+            uleb(&mut sec, body.len() as u64);
+            sec.extend(body);
+        }
+        section(&mut wasm, 10, sec);
+        wasm
+    }
+}
+
+#[derive(Default)]
+struct Indices {
+    funcs: Dict<Str, u32>,
+    globals: Dict<Str, u32>,
+}
+
+impl Indices {
+    fn encode(&self, code: &mut Vec<u8>, instr: &Instr) {
+        let block_type = |code: &mut Vec<u8>, result: &Option<ValType>| {
+            code.push(result.map_or(0x40, |t| t.code()));
+        };
+        match instr {
+            Instr::Unreachable => code.push(0x00),
+            Instr::Block(result, body) | Instr::Loop(result, body) => {
+                code.push(if matches!(instr, Instr::Block(..)) {
+                    0x02
+                } else {
+                    0x03
+                });
+                block_type(code, result);
+                for instr in body.iter() {
+                    self.encode(code, instr);
+                }
+                code.push(0x0b);
+            }
+            Instr::If(result, then, else_) => {
+                code.push(0x04);
+                block_type(code, result);
+                for instr in then.iter() {
+                    self.encode(code, instr);
+                }
+                if !else_.is_empty() {
+                    code.push(0x05);
+                    for instr in else_.iter() {
+                        self.encode(code, instr);
+                    }
+                }
+                code.push(0x0b);
+            }
+            Instr::Br(depth) => {
+                code.push(0x0c);
+                uleb(code, *depth as u64);
+            }
+            Instr::BrIf(depth) => {
+                code.push(0x0d);
+                uleb(code, *depth as u64);
+            }
+            Instr::Return => code.push(0x0f),
+            Instr::Call(name) => {
+                code.push(0x10);
+                uleb(code, self.funcs[name] as u64);
+            }
+            Instr::Drop => code.push(0x1a),
+            Instr::LocalGet(idx) | Instr::LocalSet(idx) | Instr::LocalTee(idx) => {
+                code.push(match instr {
+                    Instr::LocalGet(_) => 0x20,
+                    Instr::LocalSet(_) => 0x21,
+                    _ => 0x22,
+                });
+                uleb(code, *idx as u64);
+            }
+            Instr::GlobalGet(name) | Instr::GlobalSet(name) => {
+                code.push(if matches!(instr, Instr::GlobalGet(_)) {
+                    0x23
+                } else {
+                    0x24
+                });
+                uleb(code, self.globals[name] as u64);
+            }
+            Instr::Load(t, offset) | Instr::Store(t, offset) => {
+                let (op, _) = if matches!(instr, Instr::Load(..)) {
+                    t.load()
+                } else {
+                    t.store()
+                };
+                code.push(op);
+                // alignment (log2)
+                uleb(code, if *t == ValType::I32 { 2 } else { 3 });
+                uleb(code, *offset as u64);
+            }
+            Instr::MemorySize => code.extend([0x3f, 0x00]),
+            Instr::MemoryGrow => code.extend([0x40, 0x00]),
+            Instr::I32Const(i) => {
+                code.push(0x41);
+                sleb(code, *i as i64);
+            }
+            Instr::I64Const(i) => {
+                code.push(0x42);
+                sleb(code, *i);
+            }
+            Instr::F64Const(f) => {
+                code.push(0x44);
+                code.extend(f.to_le_bytes());
+            }
+            Instr::Num(op) => code.push(op.code()),
+        }
+    }
+}
+
+fn section(wasm: &mut Vec<u8>, id: u8, contents: Vec<u8>) {
+    wasm.push(id);
+    uleb(wasm, contents.len() as u64);
+    wasm.extend(contents);
+}
+
+fn name(code: &mut Vec<u8>, name: &str) {
+    uleb(code, name.len() as u64);
+    code.extend(name.as_bytes());
+}
+
+/// unsigned LEB128
+fn uleb(code: &mut Vec<u8>, mut n: u64) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            code.push(byte);
+            return;
+        }
+        code.push(byte | 0x80);
+    }
+}
+
+/// signed LEB128
+fn sleb(code: &mut Vec<u8>, mut n: i64) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        let done = (n == 0 && byte & 0x40 == 0) || (n == -1 && byte & 0x40 != 0);
+        if done {
+            code.push(byte);
+            return;
+        }
+        code.push(byte | 0x80);
+    }
+}
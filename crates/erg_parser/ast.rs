@@ -1273,23 +1273,89 @@ impl NormalTuple {
     }
 }
 
+#[pyclass]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TupleComprehension {
+    pub l_paren: Token,
+    pub r_paren: Token,
+    pub layout: Option<Box<Expr>>,
+    pub generators: Vec<(Identifier, Expr)>,
+    pub guard: Option<Box<Expr>>,
+}
+
+impl NestedDisplay for TupleComprehension {
+    fn fmt_nest(&self, f: &mut fmt::Formatter<'_>, _level: usize) -> fmt::Result {
+        let mut generators = String::new();
+        for (name, gen) in self.generators.iter() {
+            write!(generators, "{name} <- {gen}; ")?;
+        }
+        write!(
+            f,
+            "({}{}{})",
+            fmt_option!(self.layout, post " | "),
+            generators,
+            fmt_option!(pre " | ", &self.guard)
+        )
+    }
+}
+
+impl_display_from_nested!(TupleComprehension);
+impl_locational!(TupleComprehension, l_paren, r_paren);
+
+impl Traversable for TupleComprehension {
+    type Target = Expr;
+    fn traverse(&self, f: &mut impl FnMut(&Self::Target)) {
+        if let Some(layout) = &self.layout {
+            f(layout);
+        }
+        for (_, gen) in &self.generators {
+            f(gen);
+        }
+        if let Some(guard) = &self.guard {
+            f(guard);
+        }
+    }
+}
+
+#[pymethods]
+impl TupleComprehension {
+    #[staticmethod]
+    #[pyo3(signature = (l_paren, r_paren, layout, generators, guard=None))]
+    pub fn new(
+        l_paren: Token,
+        r_paren: Token,
+        layout: Option<Expr>,
+        generators: Vec<(Identifier, Expr)>,
+        guard: Option<Expr>,
+    ) -> Self {
+        Self {
+            l_paren,
+            r_paren,
+            layout: layout.map(Box::new),
+            generators,
+            guard: guard.map(Box::new),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Tuple {
     Normal(NormalTuple),
-    // Comprehension(TupleComprehension),
+    Comprehension(TupleComprehension),
 }
 
-impl_nested_display_for_enum!(Tuple; Normal);
-impl_display_for_enum!(Tuple; Normal);
-impl_locational_for_enum!(Tuple; Normal);
-impl_into_py_for_enum!(Tuple; Normal);
-impl_from_py_for_enum!(Tuple; Normal(NormalTuple));
+impl_nested_display_for_enum!(Tuple; Normal, Comprehension);
+impl_display_for_enum!(Tuple; Normal, Comprehension);
+impl_locational_for_enum!(Tuple; Normal, Comprehension);
+impl_into_py_for_enum!(Tuple; Normal, Comprehension);
+impl_from_py_for_enum!(Tuple; Normal(NormalTuple), Comprehension(TupleComprehension));
 
 impl Traversable for Tuple {
     type Target = Expr;
     fn traverse(&self, f: &mut impl FnMut(&Self::Target)) {
         match self {
             Self::Normal(tuple) => tuple.traverse(f),
+            Self::Comprehension(tuple) => tuple.traverse(f),
         }
     }
 }
@@ -1298,6 +1364,7 @@ impl Tuple {
     pub fn paren(&self) -> Option<&(Location, Location)> {
         match self {
             Self::Normal(tuple) => tuple.elems.paren.as_ref(),
+            Self::Comprehension(_) => None,
         }
     }
 }
@@ -2109,7 +2176,12 @@ impl_display_from_nested!(UnaryOp);
 
 impl Locational for UnaryOp {
     fn loc(&self) -> Location {
-        Location::concat(&self.op, self.args[0].as_ref())
+        // postfix operator (e.g. `x?`)
+        if self.op.is(TokenKind::Try) {
+            Location::concat(self.args[0].as_ref(), &self.op)
+        } else {
+            Location::concat(&self.op, self.args[0].as_ref())
+        }
     }
 }
 
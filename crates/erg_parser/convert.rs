@@ -274,6 +274,12 @@ impl Parser {
                 debug_exit_info!(self);
                 Ok(tuple)
             }
+            Tuple::Comprehension(tup) => {
+                let err = ParseError::simple_syntax_error(line!() as usize, tup.loc());
+                self.errs.push(err);
+                debug_exit_info!(self);
+                Err(())
+            }
         }
     }
 
@@ -709,6 +715,12 @@ impl Parser {
                 debug_exit_info!(self);
                 Ok(ParamTuplePattern::new(params))
             }
+            Tuple::Comprehension(tup) => {
+                let err = ParseError::simple_syntax_error(line!() as usize, tup.loc());
+                self.errs.push(err);
+                debug_exit_info!(self);
+                Err(())
+            }
         }
     }
 
@@ -876,6 +888,12 @@ impl Parser {
                 debug_exit_info!(self);
                 Ok(params)
             }
+            Tuple::Comprehension(tup) => {
+                let err = ParseError::simple_syntax_error(line!() as usize, tup.loc());
+                self.errs.push(err);
+                debug_exit_info!(self);
+                Err(())
+            }
         }
     }
 
@@ -19,9 +19,9 @@ use crate::ast::{
     NonDefaultParamSignature, NormalDict, NormalList, NormalRecord, NormalSet, NormalTuple,
     ParamPattern, ParamRecordAttr, ParamTuplePattern, Params, PatchDef, PosArg, ReDef, Record,
     RecordAttrOrIdent, RecordAttrs, RecordTypeSpec, Set as astSet, SetComprehension, SetWithLength,
-    Signature, SubrSignature, Tuple, TupleTypeSpec, TypeAppArgs, TypeAppArgsKind, TypeBoundSpecs,
-    TypeSpec, TypeSpecWithOp, UnaryOp, VarName, VarPattern, VarRecordAttr, VarSignature,
-    VisModifierSpec, AST,
+    Signature, SubrSignature, Tuple, TupleComprehension, TupleTypeSpec, TypeAppArgs,
+    TypeAppArgsKind, TypeBoundSpecs, TypeSpec, TypeSpecWithOp, UnaryOp, VarName, VarPattern,
+    VarRecordAttr, VarSignature, VisModifierSpec, AST,
 };
 use crate::token::{Token, TokenKind, COLON, DOT};
 
@@ -230,6 +230,23 @@ impl Desugarer {
                     let tup = NormalTuple::new(new_tup);
                     Expr::Tuple(Tuple::Normal(tup))
                 }
+                Tuple::Comprehension(tup) => {
+                    let layout = tup.layout.map(|ex| desugar(*ex));
+                    let generators = tup
+                        .generators
+                        .into_iter()
+                        .map(|(ident, gen)| (ident, desugar(gen)))
+                        .collect();
+                    let guard = tup.guard.map(|ex| desugar(*ex));
+                    let tup = TupleComprehension::new(
+                        tup.l_paren,
+                        tup.r_paren,
+                        layout,
+                        generators,
+                        guard,
+                    );
+                    Expr::Tuple(Tuple::Comprehension(tup))
+                }
             },
             Expr::Set(set) => match set {
                 astSet::Normal(set) => {
@@ -1166,7 +1183,8 @@ impl Desugarer {
         Some(GuardClause::Condition(res))
     }
 
-    fn type_guard(param_name: Str, class: &Expr, loc: &impl Locational) -> Option<GuardClause> {
+    /// `x: {I: Int | I >= 0}` => `Int contains x`, `x >= 0`
+    fn type_guard(param_name: Str, class: &Expr, loc: &impl Locational) -> Vec<GuardClause> {
         let name = VarName::new(Token::new_fake(
             TokenKind::Symbol,
             param_name.clone(),
@@ -1174,11 +1192,34 @@ impl Desugarer {
             loc.col_begin().unwrap_or(0),
             loc.col_end().unwrap_or(0),
         ));
+        if let Expr::Set(astSet::Comprehension(SetComprehension {
+            layout: None,
+            generators,
+            guard: Some(pred),
+            ..
+        })) = class
+        {
+            if let [(var, base)] = &generators[..] {
+                let mut guards = Self::type_guard(param_name, base, loc);
+                let pred = Self::rename_var(*pred.clone(), var.inspect(), &name);
+                guards.push(GuardClause::Condition(pred));
+                return guards;
+            }
+        }
         let in_op = class.clone().bin_op(
             Token::dummy(TokenKind::ContainsOp, "contains"),
             Identifier::private_from_varname(name).into(),
         );
-        Some(GuardClause::Condition(in_op.into()))
+        vec![GuardClause::Condition(in_op.into())]
+    }
+
+    fn rename_var(expr: Expr, from: &Str, to: &VarName) -> Expr {
+        match expr {
+            Expr::Accessor(Accessor::Ident(ident)) if ident.inspect() == from => {
+                Expr::from(Identifier::private_from_varname(to.clone()))
+            }
+            expr => Self::perform_desugar(|ex| Self::rename_var(ex, from, to), expr),
+        }
     }
 
     /// ```erg
@@ -1627,6 +1668,24 @@ impl Desugarer {
             }
             */
             ParamPattern::VarName(name) => {
+                let ident = Identifier::new(VisModifierSpec::Private, name.clone());
+                if let Some(t_spec) = sig.t_spec.as_ref().filter(|t_spec| {
+                    matches!(
+                        t_spec.t_spec_as_expr.as_ref(),
+                        Expr::Set(astSet::Comprehension(_))
+                    )
+                }) {
+                    // the predicate of a refinement type refers to `name`, so bind it first
+                    let v = VarSignature::new(VarPattern::Ident(ident), None);
+                    let def = Def::new(Signature::Var(v), body);
+                    guards.push(GuardClause::Bind(def));
+                    guards.extend(Self::type_guard(
+                        name.inspect().clone(),
+                        &t_spec.t_spec_as_expr,
+                        name,
+                    ));
+                    return guards;
+                }
                 if let Some(t_spec) = sig
                     .t_spec
                     .as_ref()
@@ -1634,7 +1693,6 @@ impl Desugarer {
                 {
                     guards.extend(Self::type_guard(name.inspect().clone(), t_spec, name));
                 }
-                let ident = Identifier::new(VisModifierSpec::Private, name.clone());
                 let v = VarSignature::new(VarPattern::Ident(ident), sig.t_spec.clone());
                 let def = Def::new(Signature::Var(v), body);
                 guards.push(GuardClause::Bind(def));
@@ -1783,6 +1841,7 @@ impl Desugarer {
     /// [y | x <- xs] ==> list(map(x -> y, xs))
     /// [(a, b) | x <- xs; y <- ys] ==> list(map(((x, y),) -> (a, b), itertools.product(xs, ys)))
     /// {k: v | x <- xs} ==> dict(map(x -> (k, v), xs))
+    /// (y | x <- xs) ==> tuple(map(x -> y, xs))
     /// {y | x <- xs} ==> set(map(x -> y, xs))
     /// {x <- xs | x <= 10} ==> set(filter(x -> x <= 10, xs))
     /// {x + 1 | x <- xs | x <= 10} ==> set(map(x -> x + 1, filter(x -> x <= 10, xs)))
@@ -1820,6 +1879,17 @@ impl Desugarer {
                 let map = Identifier::private("map".into()).call2(lambda.into(), iter);
                 Identifier::auto("dict".into()).call1(map.into()).into()
             }
+            Expr::Tuple(Tuple::Comprehension(mut comp)) => {
+                debug_power_assert!(comp.generators.len(), >, 0);
+                if comp.generators.len() != 1 {
+                    return Expr::Tuple(Tuple::Comprehension(comp));
+                }
+                let (ident, iter) = comp.generators.remove(0);
+                let iterator = Self::desugar_layout_and_guard(ident, iter, comp.layout, comp.guard);
+                Identifier::auto("tuple".into())
+                    .call1(iterator.into())
+                    .into()
+            }
             Expr::Set(astSet::Comprehension(mut comp)) => {
                 debug_power_assert!(comp.generators.len(), >, 0);
                 if comp.generators.len() != 1 {
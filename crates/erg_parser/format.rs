@@ -0,0 +1,1306 @@
+//! implements `Formatter` (the engine of `erg fmt`).
+//!
+//! The formatter walks the AST (`Module`) and lays out each node:
+//! statements are put on their own lines, and blocks (bodies of definitions and lambdas, arguments after `:`, methods)
+//! are indented according to their nesting rather than to the original indentation.
+//! The tokens of the nodes are printed from the source code (the AST does not keep the spelling of literals),
+//! and the trivia (comments, blank lines, line breaks inside expressions) are re-attached from the token stream:
+//! comments before a node lead it, comments after the last token of a line trail it.
+//! A statement which cannot be laid out (e.g. it has a block in an unexpected position) is kept as it is, only re-indented.
+//! After formatting, the output is re-lexed and re-parsed;
+//! if the tokens, the comments, or the AST differ from the input, the formatting is aborted.
+use std::fmt;
+
+use erg_common::error::Location;
+use erg_common::normalize_newline;
+use erg_common::traits::{DequeStream, Locational, Stream};
+
+use crate::ast::{
+    Accessor, Args, ClassAttr, Def, Dict, Expr, List, Methods, Module, Record, RecordAttrOrIdent,
+    Set, Tuple,
+};
+use crate::error::ParseErrors;
+use crate::lex::Lexer;
+use crate::parse::Parser;
+use crate::token::{Token, TokenCategory, TokenKind, TokenStream};
+
+use TokenKind::*;
+
+const INDENT_WIDTH: usize = 4;
+
+#[derive(Debug)]
+pub enum FormatError {
+    /// The source code has syntax errors
+    Syntax(ParseErrors),
+    /// The formatted code is not equivalent to the source code (a bug of the formatter)
+    Unstable,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(errs) => {
+                write!(f, "failed to parse the source code ({} errors)", errs.len())
+            }
+            Self::Unstable => write!(f, "failed to format the source code"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+pub type FormatResult<T> = Result<T, FormatError>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineKind {
+    Blank,
+    Code,
+    /// Kept as it is (outside of the range to format, inside of multi-line literals, etc.)
+    Fixed,
+}
+
+#[derive(Debug)]
+struct OutLine {
+    kind: LineKind,
+    text: String,
+}
+
+impl OutLine {
+    const fn new(kind: LineKind, text: String) -> Self {
+        Self { kind, text }
+    }
+}
+
+/// Formats Erg source code.
+///
+/// ```
+/// use erg_parser::format::Formatter;
+///
+/// let formatted = Formatter::new("f(x,y:=1)=x+y\n").format().unwrap();
+/// assert_eq!(formatted, "f(x, y := 1) = x + y\n");
+/// let formatted = Formatter::new("C = Class { .x=Int }\n").format().unwrap();
+/// assert_eq!(formatted, "C = Class { .x = Int }\n");
+/// let formatted = Formatter::new("f x =\n    x\n")
+///     .with_indent_width(2)
+///     .format()
+///     .unwrap();
+/// assert_eq!(formatted, "f x =\n  x\n");
+/// ```
+#[derive(Debug)]
+pub struct Formatter {
+    src: String,
+    /// 1-origin, inclusive
+    range: Option<(usize, usize)>,
+    /// the number of spaces per indentation level
+    indent_width: usize,
+}
+
+impl Formatter {
+    pub fn new(src: impl Into<String>) -> Self {
+        Self {
+            src: normalize_newline(&src.into()),
+            range: None,
+            indent_width: INDENT_WIDTH,
+        }
+    }
+
+    /// Indents blocks with `width` spaces (4 by default).
+    /// Tabs cannot be used because they are not allowed for indentation in Erg.
+    pub fn with_indent_width(mut self, width: usize) -> Self {
+        self.indent_width = width.max(1);
+        self
+    }
+
+    /// Only the lines in `ln_begin..=ln_end` (1-origin) are formatted.
+    /// The range is widened to whole top-level chunks (definitions, calls, etc.).
+    pub fn with_range(mut self, ln_begin: usize, ln_end: usize) -> Self {
+        self.range = Some((ln_begin, ln_end));
+        self
+    }
+
+    pub fn format(&self) -> FormatResult<String> {
+        let tokens = Lexer::from_str(self.src.clone())
+            .lex()
+            .map_err(|(_, errs)| FormatError::Syntax(errs))?;
+        let module = Parser::new(tokens.clone())
+            .parse()
+            .map_err(|iart| FormatError::Syntax(iart.errors))?
+            .ast;
+        let lines = self.src.lines().collect::<Vec<_>>();
+        let out = Printer::new(&lines, &tokens, self.range, self.indent_width)
+            .ok_or(FormatError::Unstable)?
+            .module(&module);
+        let formatted = Self::finish(out, self.range.is_none() || self.src.ends_with('\n'));
+        let new_tokens = Lexer::from_str(formatted.clone())
+            .lex()
+            .map_err(|_| FormatError::Unstable)?;
+        let same_ast = Parser::new(new_tokens.clone())
+            .parse()
+            .is_ok_and(|art| art.ast.to_string() == module.to_string());
+        let new_lines = formatted.lines().collect::<Vec<_>>();
+        let same_comments = comments(&lines, &tokens) == comments(&new_lines, &new_tokens);
+        if normalized(&tokens) != normalized(&new_tokens) || !same_ast || !same_comments {
+            return Err(FormatError::Unstable);
+        }
+        Ok(formatted)
+    }
+
+    /// Removes redundant blank lines and joins the lines.
+    fn finish(out: Vec<OutLine>, final_newline: bool) -> String {
+        let mut lines: Vec<OutLine> = Vec::with_capacity(out.len());
+        for line in out {
+            if line.kind == LineKind::Blank
+                && lines.last().is_none_or(|last| last.kind == LineKind::Blank)
+            {
+                continue;
+            }
+            lines.push(line);
+        }
+        while lines
+            .last()
+            .is_some_and(|last| last.kind == LineKind::Blank)
+        {
+            lines.pop();
+        }
+        let mut formatted = lines
+            .into_iter()
+            .map(|line| line.text)
+            .collect::<Vec<_>>()
+            .join("\n");
+        if final_newline && !formatted.is_empty() {
+            formatted.push('\n');
+        }
+        formatted
+    }
+}
+
+/// Formats `src` as a whole.
+pub fn format(src: &str) -> FormatResult<String> {
+    Formatter::new(src).format()
+}
+
+/// Whether any of the lines in `begin..=end` (0-origin) is in `range`.
+fn in_range(range: Option<(usize, usize)>, begin: usize, end: usize) -> bool {
+    begin <= end && range.is_none_or(|(ln_begin, ln_end)| ln_begin <= end + 1 && begin < ln_end)
+}
+
+fn to_chars(lines: &[&str]) -> (Vec<char>, Vec<usize>) {
+    let mut chars = vec![];
+    let mut line_starts = vec![];
+    for line in lines {
+        line_starts.push(chars.len());
+        chars.extend(line.chars());
+        chars.push('\n');
+    }
+    (chars, line_starts)
+}
+
+/// The texts of the comments in the source code.
+fn comments(lines: &[&str], tokens: &TokenStream) -> Option<Vec<String>> {
+    let (chars, _) = to_chars(lines);
+    let mut scanner = Scanner::new(&chars);
+    for token in tokens.iter().filter(|token| !is_layout(token.kind)) {
+        scanner.scan(token)?;
+    }
+    scanner.skip_trivia();
+    let comments = scanner
+        .comments
+        .iter()
+        .map(|&(begin, end)| chars[begin..end].iter().collect::<String>())
+        .map(|comment| comment.trim().to_string())
+        .collect();
+    Some(comments)
+}
+
+/// Layout tokens (newlines, indents) are not compared by their contents,
+/// and a sequence of newlines is regarded as one newline.
+fn normalized(tokens: &TokenStream) -> Vec<(TokenKind, &str)> {
+    let mut res: Vec<(TokenKind, &str)> = vec![];
+    for token in tokens.iter() {
+        match token.kind {
+            Newline if res.last().is_none_or(|(kind, _)| *kind == Newline) => {}
+            Newline | Indent | Dedent => res.push((token.kind, "")),
+            EOF | BOF => {}
+            _ => res.push((token.kind, &token.content[..])),
+        }
+    }
+    if res.last().is_some_and(|(kind, _)| *kind == Newline) {
+        res.pop();
+    }
+    res
+}
+
+const fn is_layout(kind: TokenKind) -> bool {
+    matches!(kind, Newline | Indent | Dedent | EOF | BOF)
+}
+
+const fn is_prefix_op(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        PrePlus | PreMinus | PreBitNot | Mutate | PreStar | PreDblStar
+    )
+}
+
+/// binary operators that are surrounded by spaces
+const fn is_spaced_op(kind: TokenKind) -> bool {
+    match kind {
+        Assign | Walrus | FuncArrow | ProcArrow | Pipe | Inclusion | SubtypeOf | SupertypeOf
+        | As => true,
+        _ => matches!(kind.category(), TokenCategory::BinOp) && !kind.is_range_op(),
+    }
+}
+
+const fn is_operand(kind: TokenKind) -> bool {
+    matches!(
+        kind.category(),
+        TokenCategory::Symbol | TokenCategory::Literal | TokenCategory::UBar
+    ) || matches!(kind, StrInterpRight | RParen | RSqBr | RBrace | Try)
+}
+
+/// `had_space`: whether there was a space between the tokens in the source code.
+/// It is respected where the spacing changes the meaning (e.g. `f(x)` and `f (x)`).
+fn needs_space(prev: &Token, next: &Token, had_space: bool) -> bool {
+    match (prev.kind, next.kind) {
+        (StrInterpLeft | StrInterpMid, _) | (_, StrInterpMid | StrInterpRight) => false,
+        (LBrace, RBrace) => false,
+        // records can be written as `{ .x = Int }`
+        (LBrace, _) | (_, RBrace) => had_space,
+        (_, Comma | Semi | Try | RParen | RSqBr | Colon | DblColon) => false,
+        (LParen | LSqBr | Dot | DblColon | AtSign, _) => false,
+        (Comma | Semi | Colon, _) => true,
+        // e.g. `f .x` (call) and `f.x` (attribute)
+        (_, Dot | VBar) | (VBar, _) => had_space,
+        (RefOp | RefMutOp, LParen) => had_space,
+        (RefOp | RefMutOp, _) => true,
+        (p, _) if is_prefix_op(p) => false,
+        (p, n) if is_prefix_op(n) => !is_prefix_op(p),
+        (p, n) if is_spaced_op(p) || is_spaced_op(n) => true,
+        // e.g. `f(x)` and `f (x)`
+        (p, n) if is_operand(p) && matches!(n, LParen | LSqBr | LBrace) => had_space,
+        (p, n) if is_operand(p) && is_operand(n) => true,
+        _ => had_space,
+    }
+}
+
+/// A token and its extent (char offsets) in the source code.
+///
+/// `Token::content` cannot be used for this because escape sequences in string literals are already decoded.
+struct Span<'t> {
+    token: &'t Token,
+    begin: usize,
+    end: usize,
+}
+
+/// Locates tokens and comments in the source code.
+struct Scanner<'s> {
+    chars: &'s [char],
+    cursor: usize,
+    /// closing quotes of the string literals being interpolated
+    interpolations: Vec<&'static str>,
+    comments: Vec<(usize, usize)>,
+    /// offsets of `\` at the end of lines
+    line_continuations: Vec<usize>,
+}
+
+impl<'s> Scanner<'s> {
+    const fn new(chars: &'s [char]) -> Self {
+        Self {
+            chars,
+            cursor: 0,
+            interpolations: vec![],
+            comments: vec![],
+            line_continuations: vec![],
+        }
+    }
+
+    fn starts_with(&self, pos: usize, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(pos + i) == Some(&c))
+    }
+
+    fn skip_trivia(&mut self) {
+        while let Some(&c) = self.chars.get(self.cursor) {
+            if c.is_whitespace() {
+                self.cursor += 1;
+            } else if self.starts_with(self.cursor, "\\\n") {
+                self.line_continuations.push(self.cursor);
+                self.cursor += 2;
+            } else if self.starts_with(self.cursor, "#[") {
+                let begin = self.cursor;
+                let mut level = 0;
+                while self.cursor < self.chars.len() {
+                    if self.starts_with(self.cursor, "#[") {
+                        level += 1;
+                        self.cursor += 2;
+                    } else if self.starts_with(self.cursor, "]#") {
+                        level -= 1;
+                        self.cursor += 2;
+                        if level == 0 {
+                            break;
+                        }
+                    } else {
+                        self.cursor += 1;
+                    }
+                }
+                self.comments.push((begin, self.cursor));
+            } else if c == '#' {
+                let begin = self.cursor;
+                while self.chars.get(self.cursor).is_some_and(|c| *c != '\n') {
+                    self.cursor += 1;
+                }
+                self.comments.push((begin, self.cursor));
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Scans the body of a string literal from `pos` until `\{` or `closing` appears.
+    fn scan_str(&mut self, mut pos: usize, closing: &[&str]) -> Option<usize> {
+        while pos < self.chars.len() {
+            if self.starts_with(pos, "\\{") {
+                return Some(pos + 2);
+            } else if self.chars[pos] == '\\' {
+                pos += 2;
+            } else if let Some(closing) = closing.iter().find(|q| self.starts_with(pos, q)) {
+                return Some(pos + closing.chars().count());
+            } else {
+                pos += 1;
+            }
+        }
+        None
+    }
+
+    fn scan(&mut self, token: &Token) -> Option<(usize, usize)> {
+        self.skip_trivia();
+        let begin = self.cursor;
+        let end = match token.kind {
+            StrLit | StrInterpLeft | DocComment => {
+                let quote = ["\"\"\"", "'''", "\""]
+                    .into_iter()
+                    .find(|q| self.starts_with(begin, q))?;
+                let quote_len = quote.chars().count();
+                let end = self.scan_str(begin + quote_len, &[quote])?;
+                if token.is(StrInterpLeft) {
+                    // the lexer closes single-line interpolated strings with either quote
+                    self.interpolations
+                        .push(if quote_len == 1 { "\"'" } else { quote });
+                }
+                end
+            }
+            StrInterpMid | StrInterpRight => {
+                let closing = *self.interpolations.last()?;
+                let end = if closing == "\"'" {
+                    self.scan_str(begin + 1, &["\"", "'"])?
+                } else {
+                    self.scan_str(begin + 1, &[closing])?
+                };
+                if token.is(StrInterpRight) {
+                    self.interpolations.pop();
+                }
+                end
+            }
+            _ => {
+                let len = token.content.chars().count();
+                if !self.starts_with(begin, &token.content) {
+                    return None;
+                }
+                begin + len
+            }
+        };
+        self.cursor = end;
+        Some((begin, end))
+    }
+}
+
+/// The locations of some nodes do not cover all of their tokens
+/// (e.g. the location of a binary operation begins at the operator).
+trait Extent {
+    fn extent(&self) -> Location;
+}
+
+impl Extent for Expr {
+    fn extent(&self) -> Location {
+        let first = match self {
+            Self::BinOp(bin) => bin.args[0].extent(),
+            Self::Call(call) => call.obj.extent(),
+            Self::TypeAscription(tasc) => tasc.expr.extent(),
+            Self::Lambda(lambda) => match lambda.sig.params.parens {
+                Some((l_paren, _)) => l_paren,
+                None => return self.loc(),
+            },
+            Self::Accessor(Accessor::Attr(attr)) => attr.obj.extent(),
+            Self::Accessor(Accessor::TupleAttr(attr)) => attr.obj.extent(),
+            Self::Accessor(Accessor::Subscr(subscr)) => subscr.obj.extent(),
+            Self::Accessor(Accessor::TypeApp(app)) => app.obj.extent(),
+            _ => return self.loc(),
+        };
+        let loc = self.loc();
+        if first.ln_begin().is_some()
+            && (first.ln_begin(), first.col_begin()) < (loc.ln_begin(), loc.col_begin())
+        {
+            Location::concat(&first, &loc)
+        } else {
+            loc
+        }
+    }
+}
+
+impl Extent for ClassAttr {
+    fn extent(&self) -> Location {
+        match self {
+            Self::Decl(decl) => Location::concat(&decl.expr.extent(), decl),
+            _ => self.loc(),
+        }
+    }
+}
+
+/// Raised when a node cannot be laid out; the statement containing it is kept as it is.
+struct Verbatim;
+
+type PrintResult = Result<(), Verbatim>;
+
+/// The part of `Printer` that is restored when a statement is kept as it is.
+#[derive(Debug, Clone, Default)]
+struct PrintState {
+    /// the index of the next token to print
+    cursor: usize,
+    /// the index of the next comment to print
+    next_comment: usize,
+    /// the last token printed in the current line
+    last_token: Option<usize>,
+    /// the char offset of the end of the last printed token or comment
+    last_end: usize,
+    /// the source line of `last_end`
+    last_line: usize,
+    line_empty: bool,
+    /// the indentation level of the current line
+    line_level: usize,
+    /// the indentation level of the current statement
+    stmt_level: usize,
+    /// the indentation levels of the lines where the unclosed `(`, `[`, `{` are
+    enclosures: Vec<usize>,
+    /// the indentation level of the line after a block
+    after_block: Option<usize>,
+    /// Blank lines at the beginning of blocks are removed
+    block_start: bool,
+    /// Puts a blank line before the next line
+    force_blank: bool,
+}
+
+/// Prints the AST with the tokens and trivia of the source code.
+struct Printer<'s> {
+    lines: &'s [&'s str],
+    chars: Vec<char>,
+    /// char offset of the beginning of each line
+    line_starts: Vec<usize>,
+    /// all tokens except `BOF` and `EOF` (layout tokens have no extent)
+    spans: Vec<Span<'s>>,
+    comments: Vec<(usize, usize)>,
+    /// whether each line ends with `\`
+    continued: Vec<bool>,
+    /// whether each line begins inside of a multi-line literal or comment
+    fixed: Vec<bool>,
+    range: Option<(usize, usize)>,
+    indent_width: usize,
+    out: Vec<OutLine>,
+    state: PrintState,
+}
+
+impl<'s> Printer<'s> {
+    fn new(
+        lines: &'s [&'s str],
+        tokens: &'s TokenStream,
+        range: Option<(usize, usize)>,
+        indent_width: usize,
+    ) -> Option<Self> {
+        let (chars, line_starts) = to_chars(lines);
+        let mut scanner = Scanner::new(&chars);
+        let mut spans = vec![];
+        for token in tokens
+            .iter()
+            .filter(|token| !token.is(BOF) && !token.is(EOF))
+        {
+            let (begin, end) = if is_layout(token.kind) {
+                (usize::MAX, usize::MAX)
+            } else {
+                scanner.scan(token)?
+            };
+            spans.push(Span { token, begin, end });
+        }
+        scanner.skip_trivia();
+        let comments = scanner.comments;
+        let line_continuations = scanner.line_continuations;
+        let mut printer = Self {
+            lines,
+            chars,
+            line_starts,
+            spans,
+            comments,
+            continued: vec![false; lines.len()],
+            fixed: vec![false; lines.len()],
+            range,
+            indent_width,
+            out: vec![],
+            state: PrintState {
+                line_empty: true,
+                ..Default::default()
+            },
+        };
+        for offset in line_continuations {
+            let line = printer.line_of(offset);
+            printer.continued[line] = true;
+        }
+        let extents = printer
+            .spans
+            .iter()
+            .filter(|span| !is_layout(span.token.kind))
+            .map(|span| (span.begin, span.end))
+            .chain(printer.comments.iter().copied())
+            .collect::<Vec<_>>();
+        for (begin, end) in extents {
+            let (first, last) = (printer.line_of(begin), printer.line_of(end));
+            for fixed in printer.fixed.iter_mut().take(last + 1).skip(first + 1) {
+                *fixed = true;
+            }
+        }
+        Some(printer)
+    }
+
+    fn line_of(&self, offset: usize) -> usize {
+        self.line_starts
+            .partition_point(|start| *start <= offset)
+            .saturating_sub(1)
+    }
+
+    fn line_end(&self, line: usize) -> usize {
+        self.line_starts
+            .get(line + 1)
+            .map_or(self.chars.len(), |next| next - 1)
+    }
+
+    fn text(&self, begin: usize, end: usize) -> String {
+        self.chars[begin..end].iter().collect()
+    }
+
+    fn width(&self, line: usize) -> usize {
+        let text = self.lines[line];
+        text.len() - text.trim_start_matches([' ', '\t']).len()
+    }
+
+    fn indent(&self, level: usize) -> String {
+        " ".repeat(level * self.indent_width)
+    }
+
+    fn is_layout_at(&self, idx: usize) -> bool {
+        is_layout(self.spans[idx].token.kind)
+    }
+
+    fn token_line(&self, idx: usize) -> usize {
+        self.line_of(self.spans[idx].begin)
+    }
+
+    /// the index of the first token at or after (`line`, `col`)
+    fn idx_at(&self, line: u32, col: u32) -> usize {
+        self.spans
+            .partition_point(|span| (span.token.lineno, span.token.col_begin) < (line, col))
+    }
+
+    /// the index of the first token of `node` that has not been printed
+    /// (the parser moves some tokens, e.g. decorators of variables are the callers of the bodies)
+    fn begin_of(&self, node: &impl Locational) -> Option<usize> {
+        let (line, col) = (node.ln_begin()?, node.col_begin()?);
+        let idx = self.idx_at(line, col).max(self.next_idx());
+        // `Dedent`s have the same positions as the next tokens
+        (idx..self.spans.len()).find(|&idx| !self.is_layout_at(idx))
+    }
+
+    /// the index of the token next to `node`
+    fn end_of(&self, node: &impl Locational) -> Option<usize> {
+        Some(self.idx_at(node.ln_end()?, node.col_end()?))
+    }
+
+    /// the index of the next token to print
+    fn next_idx(&self) -> usize {
+        (self.state.cursor..self.spans.len())
+            .find(|&idx| !self.is_layout_at(idx))
+            .unwrap_or(self.spans.len())
+    }
+
+    /// the char offset of the next token to print
+    fn next_offset(&self) -> usize {
+        self.spans
+            .get(self.next_idx())
+            .map_or(usize::MAX, |span| span.begin)
+    }
+
+    fn skip_layout(&mut self) {
+        while self.state.cursor < self.spans.len() && self.is_layout_at(self.state.cursor) {
+            self.state.cursor += 1;
+        }
+    }
+
+    fn push_str(&mut self, s: &str) {
+        if let Some(line) = self.out.last_mut() {
+            line.text.push_str(s);
+        }
+    }
+
+    /// Starts a new line at `level`. `src_line` is the source line of the first token or comment of the line.
+    fn newline(&mut self, level: usize, src_line: usize) {
+        let force_blank = std::mem::take(&mut self.state.force_blank);
+        let block_start = std::mem::take(&mut self.state.block_start);
+        let blank = force_blank
+            || !block_start
+                && (self.state.last_line + 1..src_line)
+                    .any(|line| self.lines[line].trim().is_empty());
+        if blank
+            && self
+                .out
+                .last()
+                .is_some_and(|line| line.kind != LineKind::Blank)
+        {
+            self.out.push(OutLine::new(LineKind::Blank, String::new()));
+        }
+        self.out
+            .push(OutLine::new(LineKind::Code, self.indent(level)));
+        self.state.line_level = level;
+        self.state.line_empty = true;
+        self.state.last_token = None;
+    }
+
+    /// whether there are spaces before `offset` in the source code (after the last token or comment)
+    fn had_space(&self, offset: usize) -> bool {
+        self.state.last_end < offset
+    }
+
+    /// Prints the comment `(begin, end)` in the current line.
+    fn put_comment(&mut self, begin: usize, end: usize) {
+        if !self.state.line_empty && self.had_space(begin) {
+            self.push_str(" ");
+        }
+        let comment = self.text(begin, end);
+        self.push_str(&comment);
+        self.state.last_token = None;
+        self.state.last_end = end;
+        self.state.last_line = self.line_of(end);
+        self.state.line_empty = false;
+        self.state.next_comment += 1;
+    }
+
+    /// Prints the comments before the token at `offset` in their own lines
+    /// (except for the ones in the same line as the token, e.g. `#[comment]# x`).
+    fn put_leading_comments(&mut self, offset: usize, level: usize) {
+        while let Some(&(begin, end)) = self.comments.get(self.state.next_comment) {
+            if begin >= offset || self.line_of(end) == self.line_of(offset) {
+                break;
+            }
+            self.newline(level, self.line_of(begin));
+            self.put_comment(begin, end);
+        }
+    }
+
+    /// Attaches the comments at the end of the current line.
+    fn end_line(&mut self) {
+        if self.state.line_empty {
+            return;
+        }
+        let line = self.line_of(self.state.last_end);
+        let next = self.next_offset();
+        while let Some(&(begin, end)) = self.comments.get(self.state.next_comment) {
+            if begin >= next || self.line_of(begin) != line {
+                break;
+            }
+            self.put_comment(begin, end);
+        }
+    }
+
+    /// Starts a new line for the token `idx` at `level`, unless it was in the same line as the last token
+    /// (e.g. `x = 1; y = 2`).
+    fn start_line(&mut self, idx: usize, level: usize) -> bool {
+        let line = self.token_line(idx);
+        if !self.state.line_empty && self.line_of(self.state.last_end) == line {
+            return false;
+        }
+        self.end_line();
+        let begin = self.spans[idx].begin;
+        self.put_leading_comments(begin, level);
+        let line = self
+            .comments
+            .get(self.state.next_comment)
+            .filter(|(c_begin, _)| *c_begin < begin)
+            .map_or(line, |(c_begin, _)| self.line_of(*c_begin));
+        self.newline(level, line);
+        true
+    }
+
+    /// the indentation level of a line broken inside of an expression
+    fn continuation_level(&self, kind: TokenKind) -> usize {
+        match self.state.enclosures.last() {
+            Some(&level) if matches!(kind, RParen | RSqBr | RBrace) => level,
+            Some(&level) => level + 1,
+            None => self.state.stmt_level + 1,
+        }
+    }
+
+    fn put_token(&mut self, idx: usize) {
+        let Span { token, begin, end } = self.spans[idx];
+        let after_block = self.state.after_block.take();
+        let last_line = self.line_of(self.state.last_end);
+        if !self.state.line_empty && last_line != self.line_of(begin) {
+            // the line break in the source code is kept
+            let level = after_block.unwrap_or_else(|| self.continuation_level(token.kind));
+            if self.continued[last_line] {
+                self.push_str(" \\");
+            }
+            self.end_line();
+            self.put_leading_comments(begin, level);
+            let line = self
+                .comments
+                .get(self.state.next_comment)
+                .filter(|(c_begin, _)| *c_begin < begin)
+                .map_or(begin, |(c_begin, _)| *c_begin);
+            self.newline(level, self.line_of(line));
+        }
+        // e.g. `#[comment]#`
+        while let Some(&(c_begin, c_end)) = self.comments.get(self.state.next_comment) {
+            if c_begin >= begin {
+                break;
+            }
+            self.put_comment(c_begin, c_end);
+        }
+        if !self.state.line_empty {
+            let had_space = self.had_space(begin);
+            let space = match self.state.last_token {
+                Some(last) => needs_space(self.spans[last].token, token, had_space),
+                None => had_space,
+            };
+            if space {
+                self.push_str(" ");
+            }
+        }
+        let text = self.text(begin, end);
+        self.push_str(&text);
+        self.state.last_token = Some(idx);
+        self.state.last_end = end;
+        self.state.last_line = self.line_of(end);
+        self.state.line_empty = false;
+        match token.kind {
+            LParen | LSqBr | LBrace => self.state.enclosures.push(self.state.line_level),
+            RParen | RSqBr | RBrace => {
+                self.state.enclosures.pop();
+            }
+            _ => {}
+        }
+    }
+
+    /// Prints the tokens before `idx` or a block.
+    fn put_tokens_before_block(&mut self, idx: usize) -> PrintResult {
+        let stop = (self.state.cursor..idx)
+            .find(|&idx| self.is_layout_at(idx))
+            .unwrap_or(idx);
+        self.put_tokens_before(stop)
+    }
+
+    /// Prints the tokens before `idx`.
+    /// Layout tokens are allowed only just before `idx` (e.g. before a block), where they are left.
+    fn put_tokens_before(&mut self, idx: usize) -> PrintResult {
+        while self.state.cursor < idx {
+            let cursor = self.state.cursor;
+            if self.is_layout_at(cursor) {
+                if (cursor..idx).all(|i| self.is_layout_at(i)) {
+                    return Ok(());
+                }
+                return Err(Verbatim);
+            }
+            self.put_token(cursor);
+            self.state.cursor += 1;
+        }
+        Ok(())
+    }
+
+    fn snapshot(&self) -> (PrintState, usize, String) {
+        let text = self
+            .out
+            .last()
+            .map_or(String::new(), |line| line.text.clone());
+        (self.state.clone(), self.out.len(), text)
+    }
+
+    fn restore(&mut self, (state, len, text): (PrintState, usize, String)) {
+        self.state = state;
+        self.out.truncate(len);
+        if let Some(line) = self.out.last_mut() {
+            line.text = text;
+        }
+    }
+
+    /// Prints a statement (or a statement-like node, e.g. an argument after `:`) at `level`.
+    /// The tokens before the node (e.g. decorators) are printed in their own lines.
+    fn item(
+        &mut self,
+        loc: Location,
+        level: usize,
+        walk: impl FnOnce(&mut Self) -> PrintResult,
+    ) -> PrintResult {
+        let (Some(begin), Some(end)) = (self.begin_of(&loc), self.end_of(&loc)) else {
+            return walk(self);
+        };
+        // the tokens after the last line break belong to the statement
+        // (e.g. the location of `[x, y] -> x` begins at `x`)
+        let mut begin = begin;
+        while begin > self.state.cursor && !self.is_layout_at(begin - 1) {
+            begin -= 1;
+        }
+        self.state.after_block = None;
+        self.skip_layout();
+        while self.state.cursor < begin {
+            let idx = self.state.cursor;
+            let line = self.token_line(idx);
+            self.start_line(idx, level);
+            while self.state.cursor < begin
+                && !self.is_layout_at(self.state.cursor)
+                && self.token_line(self.state.cursor) == line
+            {
+                self.put_token(self.state.cursor);
+                self.state.cursor += 1;
+            }
+            self.skip_layout();
+        }
+        if self.start_line(begin, level) {
+            self.state.stmt_level = level;
+        }
+        self.state.block_start = false;
+        let snapshot = self.snapshot();
+        let depth = self.state.enclosures.len();
+        let res = walk(self).and_then(|()| self.put_tokens_before(end));
+        // the locations of some nodes (e.g. type specifications) do not include the closing parentheses
+        while res.is_ok()
+            && self.state.enclosures.len() > depth
+            && self.state.cursor < self.spans.len()
+            && !self.is_layout_at(self.state.cursor)
+        {
+            self.put_token(self.state.cursor);
+            self.state.cursor += 1;
+        }
+        if res.is_err() || self.state.enclosures.len() > depth {
+            self.restore(snapshot);
+            self.verbatim(begin, end);
+        }
+        Ok(())
+    }
+
+    /// Prints the tokens in `begin..end` as they are, shifting the indentation of the lines.
+    fn verbatim(&mut self, begin: usize, end: usize) {
+        let Some(last) = (begin..end).rev().find(|&idx| !self.is_layout_at(idx)) else {
+            return;
+        };
+        let (from, to) = (self.spans[begin].begin, self.spans[last].end);
+        let (first_line, last_line) = (self.line_of(from), self.line_of(to));
+        let orig = self.width(first_line);
+        let new = self.state.line_level * self.indent_width;
+        if !self.state.line_empty && self.had_space(from) {
+            self.push_str(" ");
+        }
+        let first_end = if first_line == last_line {
+            to
+        } else {
+            self.line_end(first_line)
+        };
+        let first = self.text(from, first_end);
+        self.push_str(&first);
+        for line in first_line + 1..=last_line {
+            let line_end = if line == last_line {
+                to
+            } else {
+                self.line_end(line)
+            };
+            let text = self.text(self.line_starts[line], line_end);
+            let text = if self.fixed[line] || text.trim().is_empty() {
+                text
+            } else {
+                let width = (self.width(line) + new).saturating_sub(orig);
+                format!("{}{}", " ".repeat(width), text.trim_start())
+            };
+            self.out.push(OutLine::new(LineKind::Fixed, text));
+        }
+        while self
+            .comments
+            .get(self.state.next_comment)
+            .is_some_and(|&(c_begin, _)| c_begin < to)
+        {
+            self.state.next_comment += 1;
+        }
+        self.state.cursor = end;
+        self.state.last_token = Some(last);
+        self.state.last_end = to;
+        self.state.last_line = last_line;
+        self.state.line_empty = false;
+    }
+
+    /// Prints the own-line comments at the end of a block whose statements begin at `col`.
+    fn put_trailing_comments(&mut self, col: usize, level: usize) {
+        self.end_line();
+        let next = self.next_offset();
+        while let Some(&(begin, end)) = self.comments.get(self.state.next_comment) {
+            let line = self.line_of(begin);
+            if begin >= next || begin - self.line_starts[line] < col {
+                break;
+            }
+            self.newline(level, line);
+            self.put_comment(begin, end);
+        }
+    }
+
+    /// Prints the statements of a block, which are indented one level deeper than the line where the block begins.
+    fn block<'a, T: Extent + 'a>(
+        &mut self,
+        stmts: impl IntoIterator<Item = &'a T>,
+        walk: impl Fn(&mut Self, &T) -> PrintResult,
+    ) -> PrintResult {
+        let owner = self.state.line_level;
+        let stmt_level = self.state.stmt_level;
+        // the original indentation of the block (`None` if the block is in the same line, e.g. `f x = x`)
+        let mut col = None;
+        self.state.block_start = true;
+        for (i, stmt) in stmts.into_iter().enumerate() {
+            if i == 0 {
+                col = self
+                    .begin_of(&stmt.extent())
+                    .map(|idx| self.token_line(idx))
+                    .filter(|line| *line != self.line_of(self.state.last_end))
+                    .map(|line| self.width(line));
+            }
+            self.item(stmt.extent(), owner + 1, |printer| walk(printer, stmt))?;
+        }
+        self.state.block_start = false;
+        self.state.stmt_level = stmt_level;
+        if let Some(col) = col {
+            self.put_trailing_comments(col, owner + 1);
+            self.state.after_block = Some(owner);
+        }
+        Ok(())
+    }
+
+    /// Prints the top-level chunks.
+    /// The chunks out of the range (and the lines before them) are kept as they are.
+    fn module(mut self, module: &Module) -> Vec<OutLine> {
+        // chunks sharing lines (e.g. `x = 1; y = 2`) are formatted together
+        let mut groups: Vec<(Vec<&Expr>, usize, usize)> = vec![];
+        for chunk in module.iter() {
+            let loc = chunk.extent();
+            let (Some(begin), Some(end)) = (self.begin_of(&loc), self.end_of(&loc)) else {
+                continue;
+            };
+            let Some(last) = (begin..end).rev().find(|&idx| !self.is_layout_at(idx)) else {
+                continue;
+            };
+            let (first_line, last_line) =
+                (self.token_line(begin), self.line_of(self.spans[last].end));
+            match groups.last_mut() {
+                Some((chunks, _, group_last)) if first_line <= *group_last => {
+                    chunks.push(chunk);
+                    *group_last = (*group_last).max(last_line);
+                }
+                _ => groups.push((vec![chunk], first_line, last_line)),
+            }
+        }
+        let mut next_line = 0;
+        let mut prev_multi_line = None;
+        for (chunks, first_line, last_line) in groups {
+            let multi_line = first_line != last_line;
+            if in_range(self.range, next_line, last_line) {
+                // a multi-line chunk is separated from the others by a blank line
+                self.state.force_blank = prev_multi_line.is_some_and(|prev| prev || multi_line);
+                for chunk in chunks {
+                    let _ = self.item(chunk.extent(), 0, |printer| printer.walk(chunk));
+                }
+                // e.g. `x;`
+                while self.state.cursor < self.spans.len()
+                    && !self.is_layout_at(self.state.cursor)
+                    && self.token_line(self.state.cursor) == self.line_of(self.state.last_end)
+                {
+                    self.put_token(self.state.cursor);
+                    self.state.cursor += 1;
+                }
+                self.end_line();
+            } else {
+                self.keep_lines(next_line, last_line);
+            }
+            prev_multi_line = Some(multi_line);
+            next_line = last_line + 1;
+        }
+        let last_line = self.lines.len().saturating_sub(1);
+        if in_range(self.range, next_line, last_line) {
+            self.put_trailing_comments(0, 0);
+        } else {
+            self.keep_lines(next_line, last_line);
+        }
+        self.out
+    }
+
+    /// Keeps the lines in `begin..=end` as they are.
+    fn keep_lines(&mut self, begin: usize, end: usize) {
+        if begin > end {
+            return;
+        }
+        for line in begin..=end {
+            self.out
+                .push(OutLine::new(LineKind::Fixed, self.lines[line].to_string()));
+        }
+        let end_offset = self.line_end(end);
+        while self.state.cursor < self.spans.len()
+            && (self.is_layout_at(self.state.cursor)
+                || self.spans[self.state.cursor].begin < end_offset)
+        {
+            self.state.cursor += 1;
+        }
+        while self
+            .comments
+            .get(self.state.next_comment)
+            .is_some_and(|&(begin, _)| begin < end_offset)
+        {
+            self.state.next_comment += 1;
+        }
+        self.state.last_token = None;
+        self.state.last_end = end_offset;
+        self.state.last_line = end;
+        self.state.line_empty = true;
+    }
+
+    /// Prints `expr` and its descendants, which begin in the current line.
+    fn walk(&mut self, expr: &Expr) -> PrintResult {
+        match expr {
+            Expr::Literal(_) | Expr::InlineModule(_) => Ok(()),
+            Expr::Accessor(acc) => self.accessor(acc),
+            Expr::List(List::Normal(list)) => self.args(&list.elems),
+            Expr::List(List::WithLength(list)) => {
+                self.child(&list.elem.expr)?;
+                self.child(&list.len)
+            }
+            Expr::List(List::Comprehension(list)) => self.comprehension(
+                list.layout.as_deref(),
+                &list.generators,
+                list.guard.as_deref(),
+            ),
+            Expr::Tuple(Tuple::Normal(tuple)) => self.args(&tuple.elems),
+            Expr::Tuple(Tuple::Comprehension(tuple)) => self.comprehension(
+                tuple.layout.as_deref(),
+                &tuple.generators,
+                tuple.guard.as_deref(),
+            ),
+            Expr::Dict(Dict::Normal(dict)) => {
+                for kv in dict.kvs.iter() {
+                    self.child(&kv.key)?;
+                    self.child(&kv.value)?;
+                }
+                Ok(())
+            }
+            Expr::Dict(Dict::Comprehension(dict)) => {
+                self.child(&dict.kv.key)?;
+                self.child(&dict.kv.value)?;
+                self.comprehension(None, &dict.generators, dict.guard.as_deref())
+            }
+            Expr::Set(Set::Normal(set)) => self.args(&set.elems),
+            Expr::Set(Set::WithLength(set)) => {
+                self.child(&set.elem.expr)?;
+                self.child(&set.len)
+            }
+            Expr::Set(Set::Comprehension(set)) => {
+                self.comprehension(set.layout.as_deref(), &set.generators, set.guard.as_deref())
+            }
+            Expr::Record(record) => self.record(record),
+            Expr::BinOp(bin) => {
+                self.child(&bin.args[0])?;
+                self.child(&bin.args[1])
+            }
+            Expr::UnaryOp(unary) => self.child(&unary.args[0]),
+            Expr::Call(call) => {
+                self.child(&call.obj)?;
+                self.args(&call.args)
+            }
+            Expr::DataPack(pack) => {
+                self.child(&pack.class)?;
+                self.record(&pack.args)
+            }
+            Expr::Lambda(lambda) => {
+                if let Some(begin) = lambda
+                    .body
+                    .first()
+                    .and_then(|expr| self.begin_of(&expr.extent()))
+                {
+                    self.put_tokens_before_block(begin)?;
+                }
+                self.block(lambda.body.iter(), Self::walk)
+            }
+            Expr::TypeAscription(tasc) => self.child(&tasc.expr),
+            Expr::Def(def) => self.def(def),
+            Expr::Methods(methods) => self.methods(methods),
+            Expr::ClassDef(class_def) => {
+                let level = self.state.line_level;
+                self.def(&class_def.def)?;
+                for methods in class_def.methods_list.iter() {
+                    self.item(methods.loc(), level, |printer| printer.methods(methods))?;
+                }
+                Ok(())
+            }
+            Expr::PatchDef(patch_def) => {
+                let level = self.state.line_level;
+                self.def(&patch_def.def)?;
+                for methods in patch_def.methods_list.iter() {
+                    self.item(methods.loc(), level, |printer| printer.methods(methods))?;
+                }
+                Ok(())
+            }
+            Expr::ReDef(redef) => {
+                self.accessor(&redef.attr)?;
+                self.child(&redef.expr)
+            }
+            Expr::Compound(compound) => {
+                let level = self.state.line_level;
+                for expr in compound.iter() {
+                    self.item(expr.extent(), level, |printer| printer.walk(expr))?;
+                }
+                Ok(())
+            }
+            Expr::Dummy(dummy) => {
+                let level = self.state.line_level;
+                for expr in dummy.exprs.iter() {
+                    self.item(expr.extent(), level, |printer| printer.walk(expr))?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Prints the tokens before `expr`, and then `expr`.
+    fn child(&mut self, expr: &Expr) -> PrintResult {
+        let loc = expr.extent();
+        if let Some(begin) = self.begin_of(&loc) {
+            self.put_tokens_before(begin)?;
+        }
+        self.walk(expr)?;
+        if let Some(end) = self.end_of(&loc) {
+            self.put_tokens_before(end)?;
+        }
+        Ok(())
+    }
+
+    fn accessor(&mut self, acc: &Accessor) -> PrintResult {
+        match acc {
+            Accessor::Ident(_) => Ok(()),
+            Accessor::Attr(attr) => self.child(&attr.obj),
+            Accessor::TupleAttr(attr) => self.child(&attr.obj),
+            Accessor::Subscr(subscr) => {
+                self.child(&subscr.obj)?;
+                self.child(&subscr.index)
+            }
+            Accessor::TypeApp(app) => self.child(&app.obj),
+        }
+    }
+
+    /// Arguments after `:` and a line break (e.g. `if x:` + `do: ...`) are printed as a block.
+    fn args(&mut self, args: &Args) -> PrintResult {
+        let mut args = args
+            .pos_args
+            .iter()
+            .map(|arg| (arg.expr.extent(), &arg.expr))
+            .chain(args.var_args.iter().map(|arg| (arg.loc(), &arg.expr)))
+            .chain(args.kw_args.iter().map(|arg| (arg.loc(), &arg.expr)))
+            .chain(args.kw_var_args.iter().map(|arg| (arg.loc(), &arg.expr)))
+            .collect::<Vec<_>>();
+        args.sort_by_key(|(loc, _)| (loc.ln_begin(), loc.col_begin()));
+        let mut block = None;
+        for (loc, expr) in args {
+            let Some(begin) = self.begin_of(&loc) else {
+                self.child(expr)?;
+                continue;
+            };
+            self.put_tokens_before_block(begin)?;
+            if self.state.cursor < begin || block.is_some() {
+                let (owner, stmt_level, _) = *block.get_or_insert_with(|| {
+                    self.state.block_start = true;
+                    let col = self.width(self.token_line(begin));
+                    (self.state.line_level, self.state.stmt_level, col)
+                });
+                self.item(loc, owner + 1, |printer| printer.child(expr))?;
+                self.state.stmt_level = stmt_level;
+            } else {
+                self.child(expr)?;
+            }
+        }
+        if let Some((owner, _, col)) = block {
+            self.state.block_start = false;
+            self.put_trailing_comments(col, owner + 1);
+            self.state.after_block = Some(owner);
+        }
+        Ok(())
+    }
+
+    fn comprehension(
+        &mut self,
+        layout: Option<&Expr>,
+        generators: &[(crate::ast::Identifier, Expr)],
+        guard: Option<&Expr>,
+    ) -> PrintResult {
+        if let Some(layout) = layout {
+            self.child(layout)?;
+        }
+        for (_, iter) in generators {
+            self.child(iter)?;
+        }
+        if let Some(guard) = guard {
+            self.child(guard)?;
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, record: &Record) -> PrintResult {
+        match record {
+            Record::Normal(record) => {
+                for attr in record.attrs.iter() {
+                    self.child_def(attr)?;
+                }
+            }
+            Record::Mixed(record) => {
+                for attr in record.attrs.iter() {
+                    if let RecordAttrOrIdent::Attr(def) = attr {
+                        self.child_def(def)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn child_def(&mut self, def: &Def) -> PrintResult {
+        if let Some(begin) = self.begin_of(def) {
+            self.put_tokens_before(begin)?;
+        }
+        self.def(def)
+    }
+
+    /// The signature is printed as tokens, and the body as a block.
+    fn def(&mut self, def: &Def) -> PrintResult {
+        if let Some(op) = self.begin_of(&def.body.op) {
+            self.put_tokens_before(op + 1)?;
+        }
+        self.block(def.body.block.iter(), Self::walk)
+    }
+
+    fn methods(&mut self, methods: &Methods) -> PrintResult {
+        if let Some(begin) = methods
+            .attrs
+            .first()
+            .and_then(|attr| self.begin_of(&attr.extent()))
+        {
+            self.put_tokens_before_block(begin)?;
+        }
+        self.block(methods.attrs.iter(), |printer, attr| match attr {
+            ClassAttr::Def(def) => printer.def(def),
+            ClassAttr::Decl(decl) => printer.child(&decl.expr),
+            ClassAttr::Doc(_) => Ok(()),
+        })
+    }
+}
@@ -231,9 +231,14 @@ impl Lexer /*<'a>*/ {
         }
     }
 
-    fn emit_multiline_token(&mut self, kind: TokenKind, col_begin: u32, cont: &str) -> Token {
+    fn emit_multiline_token(
+        &mut self,
+        kind: TokenKind,
+        lineno: u32,
+        col_begin: u32,
+        cont: &str,
+    ) -> Token {
         let cont = self.str_cache.get(cont);
-        let lineno = (self.lineno_token_starts + 2).saturating_sub(cont.lines().count() as u32);
         // cannot use String::len() for multi-byte characters
         let cont_len = cont.chars().count();
         let token = Token::new(kind, cont, lineno, col_begin);
@@ -324,6 +329,7 @@ impl Lexer /*<'a>*/ {
             TokenCategory::REnclosure
             | TokenCategory::Literal
             | TokenCategory::StrInterpRight
+            | TokenCategory::PostfixOp
             | TokenCategory::Symbol => match (self.peek_prev_prev_ch(), self.peek_cur_ch()) {
                 (Some(' '), Some(' ')) => Some(OpFix::Infix), // x + 1: bin
                 (Some(' '), Some(_)) => Some(OpFix::Prefix),  // x +1: unary
@@ -951,6 +957,7 @@ impl Lexer /*<'a>*/ {
     }
 
     fn lex_multi_line_str(&mut self, quote: Quote) -> LexResult<Token> {
+        let lineno = self.lineno_token_starts + 1;
         let col_begin = self.col_token_starts;
         let mut s = quote.quotes().to_string();
         while let Some(c) = self.peek_cur_ch() {
@@ -959,7 +966,7 @@ impl Lexer /*<'a>*/ {
                 let next_c = self.peek_cur_ch();
                 let aft_next_c = self.peek_next_ch();
                 if next_c.is_none() {
-                    let token = self.emit_multiline_token(Illegal, col_begin, &s);
+                    let token = self.emit_multiline_token(Illegal, lineno, col_begin, &s);
                     return Err(Self::unclosed_string_error(
                         token,
                         quote.quotes(),
@@ -968,7 +975,7 @@ impl Lexer /*<'a>*/ {
                 }
                 if aft_next_c.is_none() {
                     s.push(self.consume().unwrap());
-                    let token = self.emit_multiline_token(Illegal, col_begin, &s);
+                    let token = self.emit_multiline_token(Illegal, lineno, col_begin, &s);
                     return Err(Self::unclosed_string_error(
                         token,
                         quote.quotes(),
@@ -979,7 +986,8 @@ impl Lexer /*<'a>*/ {
                     self.consume().unwrap();
                     self.consume().unwrap();
                     s.push_str(quote.quotes());
-                    let token = self.emit_multiline_token(quote.token_kind(), col_begin, &s);
+                    let token =
+                        self.emit_multiline_token(quote.token_kind(), lineno, col_begin, &s);
                     return Ok(token);
                 }
                 // else unclosed_string_error
@@ -993,7 +1001,8 @@ impl Lexer /*<'a>*/ {
                             '{' => {
                                 s.push_str("\\{");
                                 self.interpol_stack.push(Interpolation::MultiLine(quote));
-                                let token = self.emit_multiline_token(StrInterpLeft, col_begin, &s);
+                                let token =
+                                    self.emit_multiline_token(StrInterpLeft, lineno, col_begin, &s);
                                 return Ok(token);
                             }
                             '0' => s.push('\0'),
@@ -1011,6 +1020,7 @@ impl Lexer /*<'a>*/ {
                             _ => {
                                 let token = self.emit_multiline_token(
                                     Illegal,
+                                    lineno,
                                     col_begin,
                                     &format!("\\{next_c}"),
                                 );
@@ -1032,7 +1042,7 @@ impl Lexer /*<'a>*/ {
                 }
             }
         }
-        let token = self.emit_multiline_token(Illegal, col_begin, &s);
+        let token = self.emit_multiline_token(Illegal, lineno, col_begin, &s);
         if self.interpol_stack.len() == 1 {
             Err(Self::unclosed_string_error(
                 token,
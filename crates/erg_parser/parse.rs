@@ -901,61 +901,13 @@ impl Parser {
                 debug_exit_info!(self);
                 return Ok(ListInner::Normal(elems));
             }
-            Some(Inclusion) => {
-                self.lpop();
-                let Expr::Accessor(Accessor::Ident(sym)) = elems.remove_pos(0).expr else {
-                    let err = self.skip_and_throw_invalid_seq_err(
-                        caused_by!(),
-                        line!() as usize,
-                        &["identifier"],
-                        Inclusion,
-                    );
-                    self.errs.push(err);
-                    debug_exit_info!(self);
-                    return Err(());
-                };
-                let mut generators = vec![];
-                let expr = self
-                    .try_reduce_expr(false, false, false, false)
+            Some(Inclusion | VBar) => {
+                let first = elems.remove_pos(0).expr;
+                let comp = self
+                    .try_reduce_comprehension(first)
                     .map_err(|_| self.stack_dec(fn_name!()))?;
-                generators.push((sym, expr));
-                let _ = expect_pop!(self, VBar);
-                let guard = self
-                    .try_reduce_expr(false, false, false, false)
-                    .map_err(|_| self.stack_dec(fn_name!()))?;
-                debug_exit_info!(self);
-                return Ok(ListInner::comp(None, generators, Some(guard)));
-            }
-            Some(VBar) => {
-                self.lpop();
-                let elem = elems.remove_pos(0).expr;
-                let mut generators = vec![];
-                loop {
-                    let sym = self
-                        .try_reduce_ident()
-                        .map_err(|_| self.stack_dec(fn_name!()))?;
-                    let _ = expect_pop!(self, Inclusion);
-                    let expr = self
-                        .try_reduce_expr(false, false, false, false)
-                        .map_err(|_| self.stack_dec(fn_name!()))?;
-                    generators.push((sym, expr));
-                    if !self.cur_is(Semi) {
-                        break;
-                    } else {
-                        self.lpop();
-                    }
-                }
-                let guard = if self.cur_is(VBar) {
-                    self.lpop();
-                    let expr = self
-                        .try_reduce_expr(false, false, false, false)
-                        .map_err(|_| self.stack_dec(fn_name!()))?;
-                    Some(expr)
-                } else {
-                    None
-                };
                 debug_exit_info!(self);
-                return Ok(ListInner::comp(Some(elem), generators, guard));
+                return Ok(comp);
             }
             Some(RParen | RSqBr | RBrace | Dedent | Comma) => {}
             Some(_) => {
@@ -1029,6 +981,72 @@ impl Parser {
         Ok(ListInner::Normal(elems))
     }
 
+    /// ```erg
+    /// x <- xs | x <= 10
+    /// x + 1 | x <- xs; y <- ys | x <= 10
+    /// ```
+    /// `first` is the expression before `<-` or `|`
+    fn try_reduce_comprehension(&mut self, first: Expr) -> ParseResult<ListInner> {
+        debug_call_info!(self);
+        match self.peek_kind() {
+            Some(Inclusion) => {
+                self.lpop();
+                let Expr::Accessor(Accessor::Ident(sym)) = first else {
+                    let err = self.skip_and_throw_invalid_seq_err(
+                        caused_by!(),
+                        line!() as usize,
+                        &["identifier"],
+                        Inclusion,
+                    );
+                    self.errs.push(err);
+                    debug_exit_info!(self);
+                    return Err(());
+                };
+                let mut generators = vec![];
+                let expr = self
+                    .try_reduce_expr(false, false, false, false)
+                    .map_err(|_| self.stack_dec(fn_name!()))?;
+                generators.push((sym, expr));
+                let _ = expect_pop!(self, VBar);
+                let guard = self
+                    .try_reduce_expr(false, false, false, false)
+                    .map_err(|_| self.stack_dec(fn_name!()))?;
+                debug_exit_info!(self);
+                Ok(ListInner::comp(None, generators, Some(guard)))
+            }
+            _ => {
+                let _ = expect_pop!(self, fail_next VBar);
+                let mut generators = vec![];
+                loop {
+                    let sym = self
+                        .try_reduce_ident()
+                        .map_err(|_| self.stack_dec(fn_name!()))?;
+                    let _ = expect_pop!(self, Inclusion);
+                    let expr = self
+                        .try_reduce_expr(false, false, false, false)
+                        .map_err(|_| self.stack_dec(fn_name!()))?;
+                    generators.push((sym, expr));
+                    if !self.cur_is(Semi) {
+                        break;
+                    } else {
+                        self.lpop();
+                    }
+                }
+                let guard = if self.cur_is(VBar) {
+                    self.lpop();
+                    let expr = self
+                        .try_reduce_expr(false, false, false, false)
+                        .map_err(|_| self.stack_dec(fn_name!()))?;
+                    Some(expr)
+                } else {
+                    None
+                };
+                debug_exit_info!(self);
+                Ok(ListInner::comp(Some(first), generators, guard))
+            }
+        }
+    }
+
     fn try_reduce_elem(&mut self) -> ParseResult<PosArg> {
         debug_call_info!(self);
         match self.peek() {
@@ -1165,7 +1183,8 @@ impl Parser {
                         args.set_parens((lp.unwrap().loc(), rp.loc()));
                         break;
                     }
-                    if !args.kw_is_empty() {
+                    // e.g. `f(x, y := 1, **kwargs)`
+                    if !args.kw_is_empty() && !self.cur_is(PreDblStar) {
                         args.push_kw(
                             self.try_reduce_kw_arg(in_type_args)
                                 .map_err(|_| self.stack_dec(fn_name!()))?,
@@ -1236,7 +1255,7 @@ impl Parser {
                     break;
                 }
                 Some(_) if style.is_colon() => {
-                    if !args.kw_is_empty() {
+                    if !args.kw_is_empty() && !self.cur_is(PreDblStar) {
                         args.push_kw(
                             self.try_reduce_kw_arg(in_type_args)
                                 .map_err(|_| self.stack_dec(fn_name!()))?,
@@ -2621,6 +2640,31 @@ impl Parser {
                         }
                         self.stack_dec(fn_name!())
                     })?;
+                if self.cur_is(Inclusion) || self.cur_is(VBar) {
+                    let comp = self
+                        .try_reduce_comprehension(expr)
+                        .map_err(|_| self.stack_dec(fn_name!()))?;
+                    if line_break {
+                        while self.cur_is(Newline) {
+                            self.skip();
+                        }
+                        if self.cur_is(Dedent) {
+                            self.skip();
+                        }
+                    }
+                    let rparen = expect_pop!(self, fail_next RParen);
+                    let ListInner::Comprehension {
+                        layout,
+                        generators,
+                        guard,
+                    } = comp
+                    else {
+                        unreachable!()
+                    };
+                    let tup = TupleComprehension::new(lparen, rparen, layout, generators, guard);
+                    debug_exit_info!(self);
+                    return Ok(Expr::Tuple(Tuple::Comprehension(tup)));
+                }
                 if line_break {
                     while self.cur_is(Newline) {
                         self.skip();
@@ -2814,6 +2858,11 @@ impl Parser {
                         }
                     }
                 }
+                // e.g. f(x)?
+                Some(t) if t.is(Try) && obj.col_end() == t.col_begin() => {
+                    let op = self.lpop();
+                    obj = Expr::UnaryOp(UnaryOp::new(op, obj));
+                }
                 // e.g. l[0].0
                 Some(t) if t.is(RatioLit) && obj.col_end() == t.col_begin() => {
                     let mut token = self.lpop();
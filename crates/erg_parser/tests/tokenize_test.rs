@@ -403,6 +403,18 @@ line break\"\"\"",
     Ok(())
 }
 
+#[test]
+fn test_lexer_for_multi_line_str_lineno() -> ParseResult<()> {
+    // escaped line breaks do not shift the line numbers
+    let code = "s = \"\"\"a\\nb\\nc\nd\"\"\"\nt = 1\n";
+    let mut tokens = Lexer::from_str(code.into()).map(|tok| tok.unwrap());
+    let s = tokens.find(|tok| tok.is(StrLit)).unwrap();
+    assert_eq!(s.lineno, 1);
+    let t = tokens.find(|tok| &tok.content[..] == "t").unwrap();
+    assert_eq!(t.lineno, 3);
+    Ok(())
+}
+
 #[test]
 fn for_loop() -> ParseResult<()> {
     let mut lexer = Lexer::new(Input::file(FILE5.into()));
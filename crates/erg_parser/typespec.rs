@@ -121,6 +121,11 @@ impl Parser {
                     let const_tup = ConstTuple::new(elems);
                     Ok(ConstExpr::Tuple(const_tup))
                 }
+                other => Err(ParseError::feature_error(
+                    line!() as usize,
+                    other.loc(),
+                    "const tuple comprehension",
+                )),
             },
             Expr::BinOp(bin) => {
                 let mut args = bin.args.into_iter();
@@ -513,6 +518,10 @@ impl Parser {
                 }
                 Ok(TupleTypeSpec::new(parens, tup_spec))
             }
+            Tuple::Comprehension(tup) => {
+                let err = ParseError::simple_syntax_error(line!() as usize, tup.loc());
+                Err(err)
+            }
         }
     }
 
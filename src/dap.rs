@@ -0,0 +1,567 @@
+//! A debug adapter (`erg dap`) speaking the Debug Adapter Protocol over stdio.
+//!
+//! The program is compiled into a .pyc file and executed under Python's `bdb` (`scripts/dap_runner.py`).
+//! Since the code generator keeps the Erg file names and line numbers, the frames can be mapped directly to the Erg source;
+//! the variable names are demangled.
+use std::env::temp_dir;
+use std::fs::{canonicalize, remove_file};
+use std::io::{stdin, stdout, BufRead, BufReader, Read, Stdout, Write};
+use std::net::{Ipv4Addr, SocketAddrV4, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{sleep, JoinHandle};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use erg_common::config::ErgConfig;
+use erg_common::dict::Dict;
+use erg_common::io::Output;
+use erg_common::python_util::spawn_py_piped;
+use erg_common::spawn::spawn_new_thread;
+use erg_common::style::remove_style;
+use erg_common::traceback::demangle;
+use erg_common::traits::ExitStatus;
+
+use erg_compiler::Compiler;
+
+const THREAD_ID: i64 = 1;
+
+fn find_available_port() -> u16 {
+    let socket = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0);
+    TcpListener::bind(socket)
+        .and_then(|listener| listener.local_addr())
+        .map(|sock_addr| sock_addr.port())
+        .expect("No free port found.")
+}
+
+/// Writes DAP messages (responses and events) to stdout.
+/// This is shared with the threads forwarding the outputs of the debuggee.
+#[derive(Debug)]
+struct DapWriter {
+    seq: AtomicI64,
+    out: Mutex<Stdout>,
+}
+
+impl DapWriter {
+    fn new() -> Self {
+        Self {
+            seq: AtomicI64::new(1),
+            out: Mutex::new(stdout()),
+        }
+    }
+
+    fn send(&self, mut msg: Value) {
+        msg["seq"] = json!(self.seq.fetch_add(1, Ordering::SeqCst));
+        let body = msg.to_string();
+        let mut out = self.out.lock().unwrap();
+        let _ = write!(out, "Content-Length: {}\r\n\r\n{body}", body.len());
+        let _ = out.flush();
+    }
+
+    fn event(&self, event: &str, body: Value) {
+        self.send(json!({ "type": "event", "event": event, "body": body }));
+    }
+
+    fn output(&self, category: &str, output: impl Into<String>) {
+        self.event(
+            "output",
+            json!({ "category": category, "output": output.into() }),
+        );
+    }
+}
+
+/// A Python process running the program (`scripts/dap_runner.py`)
+#[derive(Debug)]
+struct Debuggee {
+    child: Child,
+    stream: TcpStream,
+    pyc: PathBuf,
+    /// replies to the commands (events are handled by the receiver thread)
+    replies: Receiver<Value>,
+    /// `false` while the program is stopped
+    running: Arc<AtomicBool>,
+    /// the frames of the last stop
+    frames: Arc<Mutex<Vec<Value>>>,
+}
+
+impl Debuggee {
+    fn send(&mut self, command: Value) -> Result<(), String> {
+        writeln!(self.stream, "{command}").map_err(|err| err.to_string())
+    }
+
+    fn request(&mut self, command: Value) -> Result<Value, String> {
+        self.send(command)?;
+        self.replies
+            .recv_timeout(Duration::from_secs(10))
+            .map_err(|_| "the debuggee does not respond".to_string())
+    }
+
+    fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    fn resume(&mut self, command: &str) -> Result<(), String> {
+        if self.is_running() {
+            return Err("the program is running".into());
+        }
+        self.running.store(true, Ordering::SeqCst);
+        self.send(json!({ "command": command }))
+    }
+
+    fn terminate(mut self) {
+        if !self.is_running() {
+            let _ = self.send(json!({ "command": "terminate" }));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = remove_file(&self.pyc);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct LaunchArgs {
+    program: PathBuf,
+    args: Vec<String>,
+    stop_on_entry: bool,
+}
+
+/// A debug adapter for Erg programs (`erg dap`)
+#[derive(Debug)]
+pub struct DebugAdapter {
+    cfg: ErgConfig,
+    writer: Arc<DapWriter>,
+    debuggee: Option<Debuggee>,
+    breakpoints: Dict<PathBuf, Vec<u32>>,
+    /// breakpoints set while the program is running (applied at the next stop)
+    pending_breakpoints: Vec<PathBuf>,
+    configured: bool,
+    launch_args: Option<LaunchArgs>,
+}
+
+impl DebugAdapter {
+    pub fn new(cfg: ErgConfig) -> Self {
+        Self {
+            cfg,
+            writer: Arc::new(DapWriter::new()),
+            debuggee: None,
+            breakpoints: Dict::new(),
+            pending_breakpoints: vec![],
+            configured: false,
+            launch_args: None,
+        }
+    }
+
+    pub fn run(cfg: ErgConfig) -> ExitStatus {
+        let mut adapter = Self::new(cfg);
+        let mut reader = BufReader::new(stdin().lock());
+        while let Some(msg) = Self::read_message(&mut reader) {
+            if msg["type"] != "request" {
+                continue;
+            }
+            if !adapter.dispatch(&msg) {
+                break;
+            }
+        }
+        if let Some(debuggee) = adapter.debuggee.take() {
+            debuggee.terminate();
+        }
+        ExitStatus::OK
+    }
+
+    fn read_message(reader: &mut impl BufRead) -> Option<Value> {
+        let mut len = None;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).ok()? == 0 {
+                return None;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length:") {
+                len = value.trim().parse::<usize>().ok();
+            }
+        }
+        let mut buf = vec![0; len?];
+        reader.read_exact(&mut buf).ok()?;
+        serde_json::from_slice(&buf).ok()
+    }
+
+    /// Returns `false` if the session is over.
+    fn dispatch(&mut self, req: &Value) -> bool {
+        let command = req["command"].as_str().unwrap_or_default();
+        let args = &req["arguments"];
+        let res = match command {
+            "initialize" => Ok(json!({
+                "supportsConfigurationDoneRequest": true,
+                "supportsTerminateRequest": true,
+            })),
+            "launch" => self.launch(args),
+            "setBreakpoints" => self.set_breakpoints(args),
+            "configurationDone" => self.configuration_done(),
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })),
+            "stackTrace" => self.stack_trace(),
+            "scopes" => Ok(json!({
+                "scopes": [{
+                    "name": "Locals",
+                    "variablesReference": args["frameId"].as_i64().unwrap_or(0) + 1,
+                    "expensive": false,
+                }]
+            })),
+            "variables" => self.variables(args),
+            "continue" => self
+                .resume("continue")
+                .map(|_| json!({ "allThreadsContinued": true })),
+            "next" | "stepIn" | "stepOut" => self.resume(command).map(|_| json!({})),
+            "disconnect" | "terminate" => {
+                if let Some(debuggee) = self.debuggee.take() {
+                    debuggee.terminate();
+                }
+                Ok(json!({}))
+            }
+            _ => Err(format!("unsupported request: {command}")),
+        };
+        let mut response = json!({
+            "type": "response",
+            "request_seq": req["seq"],
+            "command": command,
+            "success": res.is_ok(),
+        });
+        match res {
+            Ok(body) => response["body"] = body,
+            Err(msg) => response["message"] = json!(msg),
+        }
+        self.writer.send(response);
+        match command {
+            "initialize" => self.writer.event("initialized", json!({})),
+            "terminate" => self.writer.event("terminated", json!({})),
+            "disconnect" => return false,
+            _ => {}
+        }
+        true
+    }
+
+    fn launch(&mut self, args: &Value) -> Result<Value, String> {
+        let program = args["program"]
+            .as_str()
+            .ok_or("`program` is not specified")?;
+        let program = canonicalize(program).map_err(|err| format!("{program}: {err}"))?;
+        let program_args = args["args"]
+            .as_array()
+            .map(|args| {
+                args.iter()
+                    .filter_map(|arg| arg.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let launch_args = LaunchArgs {
+            program,
+            args: program_args,
+            stop_on_entry: args["stopOnEntry"].as_bool().unwrap_or(false),
+        };
+        self.debuggee = Some(self.spawn_debuggee(&launch_args)?);
+        self.launch_args = Some(launch_args);
+        if self.configured {
+            self.start()?;
+        }
+        Ok(json!({}))
+    }
+
+    fn compile(&self, program: &Path) -> Result<PathBuf, String> {
+        let mut cfg = self.cfg.inherit(program.to_path_buf());
+        // stdout is used for the protocol
+        cfg.output = Output::Null;
+        cfg.quiet_repl = true;
+        let src = cfg.input.read();
+        let pyc = temp_dir().join(format!("erg_dap_{}.pyc", std::process::id()));
+        let mut compiler = Compiler::new(cfg);
+        match compiler.compile_and_dump_as_pyc(&pyc, src, "exec") {
+            Ok(warns) => {
+                if !warns.is_empty() {
+                    self.writer
+                        .output("stderr", remove_style(&warns.to_string()));
+                }
+                Ok(pyc)
+            }
+            Err(art) => {
+                self.writer.output(
+                    "stderr",
+                    remove_style(&format!("{}{}", art.warns, art.errors)),
+                );
+                Err(format!("failed to compile {}", program.display()))
+            }
+        }
+    }
+
+    fn spawn_debuggee(&self, launch_args: &LaunchArgs) -> Result<Debuggee, String> {
+        let pyc = self.compile(&launch_args.program)?;
+        let port = find_available_port();
+        let code = include_str!("scripts/dap_runner.py").replace("__PORT__", &port.to_string());
+        let mut py_args = vec![
+            pyc.to_string_lossy().to_string(),
+            launch_args.program.to_string_lossy().to_string(),
+        ];
+        py_args.extend(launch_args.args.iter().cloned());
+        let py_args = py_args.iter().map(|s| &s[..]).collect::<Vec<_>>();
+        let mut child = spawn_py_piped(self.cfg.py_command, &code, &py_args)
+            .map_err(|err| format!("failed to start Python: {err}"))?;
+        let mut forwarders = vec![];
+        for (pipe, category) in [
+            (
+                child
+                    .stdout
+                    .take()
+                    .map(|s| Box::new(s) as Box<dyn Read + Send>),
+                "stdout",
+            ),
+            (
+                child
+                    .stderr
+                    .take()
+                    .map(|s| Box::new(s) as Box<dyn Read + Send>),
+                "stderr",
+            ),
+        ] {
+            let Some(mut pipe) = pipe else {
+                continue;
+            };
+            let writer = self.writer.clone();
+            forwarders.push(spawn_new_thread(
+                move || {
+                    let mut buf = [0; 1024];
+                    while let Ok(n @ 1..) = pipe.read(&mut buf) {
+                        writer.output(category, String::from_utf8_lossy(&buf[..n]));
+                    }
+                },
+                category,
+            ));
+        }
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, port);
+        let mut retry = 0;
+        let stream = loop {
+            match TcpStream::connect(addr) {
+                Ok(stream) => break stream,
+                Err(err) => {
+                    if retry >= 100 || child.try_wait().is_ok_and(|stat| stat.is_some()) {
+                        let _ = child.kill();
+                        let _ = remove_file(&pyc);
+                        return Err(format!("failed to connect to the debuggee: {err}"));
+                    }
+                    retry += 1;
+                    sleep(Duration::from_millis(100));
+                }
+            }
+        };
+        let (sender, replies) = channel();
+        let running = Arc::new(AtomicBool::new(false));
+        let frames = Arc::new(Mutex::new(vec![]));
+        let reader = stream
+            .try_clone()
+            .map_err(|err| format!("failed to connect to the debuggee: {err}"))?;
+        self.start_receiver(reader, sender, running.clone(), frames.clone(), forwarders);
+        Ok(Debuggee {
+            child,
+            stream,
+            pyc,
+            replies,
+            running,
+            frames,
+        })
+    }
+
+    /// Receives messages from the debuggee and converts the events into DAP events.
+    fn start_receiver(
+        &self,
+        reader: TcpStream,
+        replies: Sender<Value>,
+        running: Arc<AtomicBool>,
+        frames: Arc<Mutex<Vec<Value>>>,
+        mut forwarders: Vec<JoinHandle<()>>,
+    ) {
+        let writer = self.writer.clone();
+        spawn_new_thread(
+            move || {
+                for line in BufReader::new(reader).lines() {
+                    let Ok(msg) = line.and_then(|l| Ok(serde_json::from_str::<Value>(&l)?)) else {
+                        break;
+                    };
+                    match msg["event"].as_str() {
+                        Some("stopped") => {
+                            *frames.lock().unwrap() =
+                                msg["frames"].as_array().cloned().unwrap_or_default();
+                            running.store(false, Ordering::SeqCst);
+                            writer.event(
+                                "stopped",
+                                json!({
+                                    "reason": msg["reason"],
+                                    "threadId": THREAD_ID,
+                                    "allThreadsStopped": true,
+                                }),
+                            );
+                        }
+                        Some("exited") => {
+                            // flush the outputs of the program first
+                            for forwarder in forwarders.drain(..) {
+                                let _ = forwarder.join();
+                            }
+                            writer.event("exited", json!({ "exitCode": msg["code"] }));
+                            break;
+                        }
+                        _ => {
+                            let _ = replies.send(msg);
+                        }
+                    }
+                }
+                writer.event("terminated", json!({}));
+            },
+            "dap_receiver",
+        );
+    }
+
+    fn start(&mut self) -> Result<(), String> {
+        let stop_on_entry = self
+            .launch_args
+            .as_ref()
+            .is_some_and(|args| args.stop_on_entry);
+        let Some(debuggee) = self.debuggee.as_mut() else {
+            return Ok(());
+        };
+        for (file, lines) in self.breakpoints.iter() {
+            debuggee.request(json!({
+                "command": "setBreakpoints",
+                "file": file,
+                "lines": lines,
+            }))?;
+        }
+        self.pending_breakpoints.clear();
+        debuggee.running.store(true, Ordering::SeqCst);
+        debuggee.send(json!({ "command": "start", "stopOnEntry": stop_on_entry }))
+    }
+
+    fn configuration_done(&mut self) -> Result<Value, String> {
+        self.configured = true;
+        if self.debuggee.is_some() {
+            self.start()?;
+        }
+        Ok(json!({}))
+    }
+
+    fn set_breakpoints(&mut self, args: &Value) -> Result<Value, String> {
+        let path = args["source"]["path"]
+            .as_str()
+            .ok_or("`source.path` is not specified")?;
+        let path = canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+        let lines = args["breakpoints"]
+            .as_array()
+            .map(|bps| {
+                bps.iter()
+                    .filter_map(|bp| bp["line"].as_u64().map(|line| line as u32))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        self.breakpoints.insert(path.clone(), lines.clone());
+        let verified = match self.debuggee.as_mut() {
+            // the debuggee is waiting for the `start` command or stopped
+            Some(debuggee) if !debuggee.is_running() => {
+                let reply = debuggee.request(json!({
+                    "command": "setBreakpoints",
+                    "file": path,
+                    "lines": lines,
+                }))?;
+                reply["verified"].as_array().cloned().unwrap_or_default()
+            }
+            Some(_) => {
+                self.pending_breakpoints.push(path);
+                vec![json!(true); lines.len()]
+            }
+            None => vec![json!(true); lines.len()],
+        };
+        let breakpoints = lines
+            .iter()
+            .zip(verified)
+            .map(|(line, verified)| json!({ "line": line, "verified": verified }))
+            .collect::<Vec<_>>();
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    /// Applies the breakpoints set while the program was running
+    fn flush_pending_breakpoints(&mut self) -> Result<(), String> {
+        let Some(debuggee) = self.debuggee.as_mut() else {
+            return Ok(());
+        };
+        for file in std::mem::take(&mut self.pending_breakpoints) {
+            let lines = self.breakpoints.get(&file).cloned().unwrap_or_default();
+            debuggee.request(json!({
+                "command": "setBreakpoints",
+                "file": file,
+                "lines": lines,
+            }))?;
+        }
+        Ok(())
+    }
+
+    fn stack_trace(&mut self) -> Result<Value, String> {
+        let debuggee = self.debuggee.as_ref().ok_or("the program is not running")?;
+        let frames = debuggee.frames.lock().unwrap();
+        let stack_frames = frames
+            .iter()
+            .enumerate()
+            .map(|(id, frame)| {
+                let path = PathBuf::from(frame["file"].as_str().unwrap_or_default());
+                let name = frame["name"].as_str().unwrap_or_default();
+                json!({
+                    "id": id,
+                    "name": demangle(name).unwrap_or_else(|| name.to_string()),
+                    "source": {
+                        "name": path.file_name().map(|name| name.to_string_lossy()),
+                        "path": path,
+                    },
+                    "line": frame["line"],
+                    "column": 1,
+                })
+            })
+            .collect::<Vec<_>>();
+        Ok(json!({ "stackFrames": stack_frames, "totalFrames": frames.len() }))
+    }
+
+    fn variables(&mut self, args: &Value) -> Result<Value, String> {
+        let frame = args["variablesReference"].as_i64().unwrap_or(0) - 1;
+        if frame < 0 {
+            return Ok(json!({ "variables": [] }));
+        }
+        self.flush_pending_breakpoints()?;
+        let debuggee = self.debuggee.as_mut().ok_or("the program is not running")?;
+        if debuggee.is_running() {
+            return Err("the program is running".into());
+        }
+        let reply = debuggee.request(json!({ "command": "variables", "frame": frame }))?;
+        let variables = reply["variables"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|var| {
+                let name = demangle(var["name"].as_str()?)?;
+                Some(json!({
+                    "name": name,
+                    "value": var["value"],
+                    "type": var["type"],
+                    "variablesReference": 0,
+                }))
+            })
+            .collect::<Vec<_>>();
+        Ok(json!({ "variables": variables }))
+    }
+
+    fn resume(&mut self, command: &str) -> Result<(), String> {
+        self.flush_pending_breakpoints()?;
+        self.debuggee
+            .as_mut()
+            .ok_or("the program is not running")?
+            .resume(command)
+    }
+}
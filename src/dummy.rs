@@ -1,13 +1,14 @@
 use std::fs::remove_file;
 use std::io::{Read, Write};
 use std::net::{Ipv4Addr, SocketAddrV4, TcpListener, TcpStream};
-use std::process::{self, Command, Stdio};
+use std::process;
 use std::thread::sleep;
 use std::time::Duration;
 
 use erg_common::config::ErgConfig;
 use erg_common::error::{ErrorDisplay, ErrorKind, MultiErrorDisplay};
 use erg_common::python_util::spawn_py;
+use erg_common::traceback::{erg_script_input, PyTraceback, TracebackError, TRACEBACK_HEADER};
 use erg_common::traits::{BlockKind, ExitStatus, New, Runnable};
 
 use erg_compiler::hir::Expr;
@@ -341,7 +342,15 @@ impl Runnable for DummyVM {
                     }
                     Inst::Initialize => {
                         self.compiler.initialize_generator();
-                        String::from_utf8(msg.data.unwrap_or_default())
+                        let data = String::from_utf8(msg.data.unwrap_or_default());
+                        if let Some(errs) = data
+                            .as_ref()
+                            .ok()
+                            .and_then(|data| self.traceback_errors(data))
+                        {
+                            return Err(errs);
+                        }
+                        data
                     }
                     Inst::Print => String::from_utf8(msg.data.unwrap_or_default()),
                     Inst::Exit => err_handle!("Receiving inst {:?} from server", msg.inst),
@@ -421,6 +430,30 @@ impl DummyVM {
         New::new(cfg)
     }
 
+    /// Converts the traceback in the output of the REPL server into Erg errors.
+    /// The output printed before the exception is written out here.
+    fn traceback_errors(&mut self, data: &str) -> Option<EvalErrors> {
+        let idx = data.rfind(TRACEBACK_HEADER)?;
+        let traceback = PyTraceback::parse(&data[idx..])?;
+        let input = self.cfg().input.clone();
+        let err = TracebackError::from_traceback(&traceback, |file| {
+            if file == input.enclosed_name() {
+                Some(input.clone())
+            } else {
+                erg_script_input(file)
+            }
+        })?;
+        let out = data[..idx].trim_end();
+        if !out.is_empty() {
+            writeln!(self.cfg_mut().output, "{out}").ok()?;
+        }
+        Some(EvalErrors::from(EvalError::new(
+            *err.core,
+            err.input,
+            err.caused_by,
+        )))
+    }
+
     /// Execute the script specified in the configuration.
     pub fn exec(&mut self) -> Result<ExitStatus, EvalErrors> {
         Runnable::exec(self)
@@ -431,32 +464,3 @@ impl DummyVM {
         Runnable::eval(self, src)
     }
 }
-
-#[derive(Debug, Default)]
-pub struct PackageManagerRunner {}
-
-impl PackageManagerRunner {
-    pub fn new() -> Self {
-        Self {}
-    }
-
-    pub fn run(cfg: ErgConfig) -> ExitStatus {
-        if Command::new("poise").arg("--version").output().is_err() {
-            eprintln!("Error: poise is not installed. Please install using ergup or manually from the repository (https://github.com/erg-lang/poise).");
-            return ExitStatus::ERR1;
-        }
-        match Command::new("poise")
-            .stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .args(cfg.runtime_args.as_ref())
-            .output()
-        {
-            Ok(out) => ExitStatus::new(out.status.code().unwrap_or(0), 0, 0),
-            Err(err) => {
-                eprintln!("Error: {}", err);
-                ExitStatus::ERR1
-            }
-        }
-    }
-}
@@ -0,0 +1,157 @@
+//! A built-in code formatter (`erg fmt`).
+//!
+//! The formatting itself is implemented in `erg_parser::format`.
+use std::fs;
+use std::io::{stdin, Read};
+use std::path::{Path, PathBuf};
+
+use erg_common::config::ErgConfig;
+use erg_common::error::MultiErrorDisplay;
+use erg_common::io::Input;
+use erg_common::traits::ExitStatus;
+
+use erg_parser::error::ParserRunnerErrors;
+use erg_parser::format::{FormatError, Formatter};
+
+const FMT_HELP: &str = "\
+USAGE:
+    erg fmt [OPTIONS] [FILES]...
+
+ARGS:
+    [FILES]...              files or directories to format (if omitted, read from stdin and write to stdout)
+
+OPTIONS:
+    --check                 do not write the files, exit with 1 if any of them is not formatted";
+
+#[derive(Debug, Default)]
+pub struct FormatterRunner {
+    check: bool,
+    /// the number of files that are not formatted (`--check`) or failed to be formatted
+    failures: usize,
+}
+
+impl FormatterRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn run(cfg: ErgConfig) -> ExitStatus {
+        let mut runner = Self::new();
+        let mut paths = vec![];
+        for &arg in cfg.runtime_args.iter() {
+            match arg {
+                "--check" => runner.check = true,
+                "-h" | "--help" => {
+                    println!("{FMT_HELP}");
+                    return ExitStatus::OK;
+                }
+                other if other.starts_with('-') => {
+                    eprintln!("Error: invalid argument: {other}\n\n{FMT_HELP}");
+                    return ExitStatus::ERR1;
+                }
+                path => paths.push(PathBuf::from(path)),
+            }
+        }
+        if paths.is_empty() {
+            runner.format_stdin();
+        }
+        for path in paths {
+            if path.is_dir() {
+                runner.format_dir(&path);
+            } else {
+                runner.format_file(&path);
+            }
+        }
+        if runner.failures == 0 {
+            ExitStatus::OK
+        } else {
+            ExitStatus::ERR1
+        }
+    }
+
+    fn format(&mut self, input: Input, src: &str) -> Option<String> {
+        match Formatter::new(src).format() {
+            Ok(formatted) => Some(formatted),
+            Err(FormatError::Syntax(errs)) => {
+                ParserRunnerErrors::convert(&input, errs).write_all_stderr();
+                self.failures += 1;
+                None
+            }
+            Err(err) => {
+                eprintln!("Error: {}: {err}", input.filename());
+                self.failures += 1;
+                None
+            }
+        }
+    }
+
+    fn format_stdin(&mut self) {
+        let mut src = String::new();
+        if let Err(err) = stdin().read_to_string(&mut src) {
+            eprintln!("Error: <stdin>: {err}");
+            self.failures += 1;
+            return;
+        }
+        let Some(formatted) = self.format(Input::pipe(src.clone()), &src) else {
+            return;
+        };
+        if self.check {
+            if formatted != src {
+                eprintln!("<stdin> is not formatted");
+                self.failures += 1;
+            }
+        } else {
+            print!("{formatted}");
+        }
+    }
+
+    fn format_dir(&mut self, dir: &Path) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            eprintln!("Error: failed to read {}", dir.display());
+            self.failures += 1;
+            return;
+        };
+        let mut entries = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .collect::<Vec<_>>();
+        entries.sort();
+        for path in entries {
+            let is_hidden = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            if is_hidden {
+                continue;
+            }
+            if path.is_dir() {
+                self.format_dir(&path);
+            } else if path.extension().is_some_and(|ext| ext == "er") {
+                self.format_file(&path);
+            }
+        }
+    }
+
+    fn format_file(&mut self, path: &Path) {
+        let src = match fs::read_to_string(path) {
+            Ok(src) => src,
+            Err(err) => {
+                eprintln!("Error: {}: {err}", path.display());
+                self.failures += 1;
+                return;
+            }
+        };
+        let Some(formatted) = self.format(Input::file(path.to_path_buf()), &src) else {
+            return;
+        };
+        if formatted == src {
+            return;
+        }
+        if self.check {
+            println!("{} is not formatted", path.display());
+            self.failures += 1;
+        } else if let Err(err) = fs::write(path, formatted) {
+            eprintln!("Error: {}: {err}", path.display());
+            self.failures += 1;
+        }
+    }
+}
@@ -1,4 +1,10 @@
 extern crate erg_common;
 extern crate erg_compiler;
+pub mod dap;
 mod dummy;
-pub use dummy::{DummyVM, PackageManagerRunner};
+pub mod fmt;
+pub mod pack;
+pub use dap::DebugAdapter;
+pub use dummy::DummyVM;
+pub use fmt::FormatterRunner;
+pub use pack::PackageManagerRunner;
@@ -11,14 +11,22 @@ use erg_linter::Linter;
 use erg_parser::lex::LexerRunner;
 use erg_parser::ParserRunner;
 
+use erg_compiler::dyne::Dyne;
 use erg_compiler::transpile::Transpiler;
 use erg_compiler::ty::deserialize::Deserializer;
 use erg_compiler::{ASTBuilder, Compiler};
 
-use erg::{DummyVM, PackageManagerRunner};
+use erg::{DummyVM, FormatterRunner, PackageManagerRunner};
 
 fn run() {
-    let cfg = ErgConfig::parse();
+    let mut cfg = ErgConfig::parse();
+    // only the modes that resolve imports need the packages
+    if matches!(
+        cfg.mode,
+        TypeCheck | FullCheck | Compile | Transpile | Execute | Interpret | Lint | DebugAdapter
+    ) {
+        PackageManagerRunner::register_packages(&mut cfg);
+    }
     let stat = match cfg.mode {
         Lex => LexerRunner::run(cfg),
         Parse => ParserRunner::run(cfg),
@@ -28,8 +36,11 @@ fn run() {
         Compile => Compiler::run(cfg),
         Transpile => Transpiler::run(cfg),
         Execute => DummyVM::run(cfg),
+        Interpret => Dyne::run(cfg),
         Read => Deserializer::run(cfg),
         Pack => PackageManagerRunner::run(cfg),
+        Format => FormatterRunner::run(cfg),
+        DebugAdapter => erg::DebugAdapter::run(cfg),
         Lint => Linter::run(cfg),
         LanguageServer => {
             #[cfg(feature = "els")]
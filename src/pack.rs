@@ -0,0 +1,815 @@
+//! A built-in package manager (`erg pack`).
+//!
+//! Reads `package.er`, resolves the dependencies, and writes `package.lock.er`.
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use erg_common::config::{ErgConfig, Package};
+use erg_common::env::erg_pkgs_path;
+use erg_common::io::Input;
+use erg_common::traits::{ExitStatus, Stream};
+use erg_common::{normalize_path, ArcArray};
+
+use erg_compiler::ty::value::ValueObj;
+use erg_compiler::ty::Type;
+use erg_parser::ast::{Def, Dict, Expr, List, Module, Record, RecordAttrOrIdent, Set};
+use erg_parser::token::TokenKind;
+use erg_parser::ParserRunner;
+
+pub const MANIFEST_FILE: &str = "package.er";
+pub const LOCK_FILE: &str = "package.lock.er";
+
+const PACK_HELP: &str = "\
+USAGE:
+    erg pack <COMMAND> [OPTIONS]
+
+COMMANDS:
+    init                    initialize the current directory as a package
+    install | lock          resolve the dependencies and write `package.lock.er`
+    list                    show the locked packages
+
+OPTIONS:
+    --registry <DIR>        use <DIR> as the package registry (default: `$ERG_PATH/lib/pkgs`)";
+
+#[derive(Debug)]
+pub enum PackError {
+    Io(PathBuf, std::io::Error),
+    Parse(String),
+    InvalidManifest(PathBuf, String),
+    NotFound(String),
+    Conflict(String),
+    Git(String),
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            Self::Parse(errs) => write!(f, "{errs}"),
+            Self::InvalidManifest(path, msg) => write!(f, "{}: {msg}", path.display()),
+            Self::NotFound(msg) => write!(f, "{msg}"),
+            Self::Conflict(msg) => write!(f, "{msg}"),
+            Self::Git(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for PackError {}
+
+pub type PackResult<T> = Result<T, PackError>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DependencySource {
+    /// `foo = "foo"` or `foo = { name = "foo"; version = "1.*.*" }`
+    Registry {
+        name: String,
+        version: Option<String>,
+    },
+    /// `foo = { git = "path/to/checkout" }`
+    Git(PathBuf),
+    /// `foo = { path = "path/to/package" }`
+    Path(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    /// the name used in `import`
+    pub as_name: String,
+    pub source: DependencySource,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    pub name: String,
+    pub version: String,
+    pub dependencies: Vec<Dependency>,
+}
+
+impl Manifest {
+    pub fn read(path: &Path) -> PackResult<Self> {
+        let module = parse_file(path)?;
+        let invalid = |msg: &str| PackError::InvalidManifest(path.to_path_buf(), msg.into());
+        let name = module
+            .get_attr("name")
+            .and_then(str_value)
+            .ok_or_else(|| invalid("`.name` must be a string"))?;
+        let version = module
+            .get_attr("version")
+            .and_then(str_value)
+            .ok_or_else(|| invalid("`.version` must be a string"))?;
+        let base = path.parent().unwrap_or(Path::new("."));
+        let mut dependencies = vec![];
+        if let Some(deps) = module.get_attr("dependencies") {
+            let deps = match deps.body.block.first() {
+                Some(Expr::Record(deps)) => record_attrs(deps),
+                // `{}`
+                Some(Expr::Dict(Dict::Normal(dict))) if dict.kvs.is_empty() => {
+                    Box::new(std::iter::empty())
+                }
+                Some(Expr::Set(Set::Normal(set))) if set.elems.is_empty() => {
+                    Box::new(std::iter::empty())
+                }
+                _ => return Err(invalid("`.dependencies` must be a record")),
+            };
+            for dep in deps {
+                let Some(as_name) = dep.sig.ident().map(|id| id.inspect().to_string()) else {
+                    return Err(invalid("invalid dependency name"));
+                };
+                let source = Self::dependency_source(dep, base).ok_or_else(|| {
+                    PackError::InvalidManifest(
+                        path.to_path_buf(),
+                        format!("invalid dependency specification: `{as_name}`"),
+                    )
+                })?;
+                dependencies.push(Dependency { as_name, source });
+            }
+        }
+        Ok(Self {
+            name,
+            version,
+            dependencies,
+        })
+    }
+
+    fn dependency_source(dep: &Def, base: &Path) -> Option<DependencySource> {
+        if let Some(name) = str_value(dep) {
+            return Some(DependencySource::Registry {
+                name,
+                version: None,
+            });
+        }
+        let Some(Expr::Record(rec)) = dep.body.block.first() else {
+            return None;
+        };
+        let get = |name: &str| record_attrs(rec).find(|attr| is_named(attr, name));
+        if let Some(git) = get("git") {
+            Some(DependencySource::Git(base.join(str_value(git)?)))
+        } else if let Some(path) = get("path") {
+            Some(DependencySource::Path(base.join(str_value(path)?)))
+        } else {
+            let name = str_value(get("name")?)?;
+            let version = match get("version") {
+                Some(version) => Some(str_value(version)?),
+                None => None,
+            };
+            Some(DependencySource::Registry { name, version })
+        }
+    }
+}
+
+/// An entry of `package.lock.er`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedPackage {
+    pub name: String,
+    pub as_name: String,
+    pub version: String,
+    /// `None` if the package is in the default registry (`$ERG_PATH/lib/pkgs`)
+    pub path: Option<String>,
+    /// the commit hash (git dependencies only)
+    pub rev: Option<String>,
+}
+
+impl fmt::Display for LockedPackage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{{ name = {:?}; as_name = {:?}; version = {:?}",
+            self.name, self.as_name, self.version
+        )?;
+        if let Some(path) = &self.path {
+            write!(f, "; path = {path:?}")?;
+        }
+        if let Some(rev) = &self.rev {
+            write!(f, "; rev = {rev:?}")?;
+        }
+        write!(f, " }}")
+    }
+}
+
+impl LockedPackage {
+    pub fn root_dir(&self) -> PathBuf {
+        if let Some(path) = &self.path {
+            PathBuf::from(path)
+        } else {
+            erg_pkgs_path().join(&self.name).join(&self.version)
+        }
+    }
+
+    pub fn to_package(&self) -> Package {
+        Package::new(
+            self.name.clone(),
+            self.as_name.clone(),
+            self.version.clone(),
+            self.path.clone(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LockFile {
+    pub packages: Vec<LockedPackage>,
+}
+
+impl fmt::Display for LockFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "# This file is automatically generated by `erg pack`. Do not edit it manually."
+        )?;
+        if self.packages.is_empty() {
+            return writeln!(f, ".packages = []");
+        }
+        writeln!(f, ".packages = [")?;
+        for pkg in self.packages.iter() {
+            writeln!(f, "    {pkg},")?;
+        }
+        writeln!(f, "]")
+    }
+}
+
+impl LockFile {
+    pub fn read(path: &Path) -> PackResult<Self> {
+        let module = parse_file(path)?;
+        let invalid = |msg: &str| PackError::InvalidManifest(path.to_path_buf(), msg.into());
+        let Some(pkgs) = module.get_attr("packages") else {
+            return Err(invalid("`.packages` is not defined"));
+        };
+        let Some(Expr::List(List::Normal(list))) = pkgs.body.block.first() else {
+            return Err(invalid("`.packages` must be a list"));
+        };
+        let mut packages = vec![];
+        for rec in list.iter() {
+            let Expr::Record(rec) = rec else {
+                return Err(invalid("`.packages` must be a list of records"));
+            };
+            let get = |name: &str| {
+                record_attrs(rec)
+                    .find(|attr| is_named(attr, name))
+                    .and_then(str_value)
+            };
+            let (Some(name), Some(as_name), Some(version)) =
+                (get("name"), get("as_name"), get("version"))
+            else {
+                return Err(invalid("`name`, `as_name` and `version` are required"));
+            };
+            packages.push(LockedPackage {
+                name,
+                as_name,
+                version,
+                path: get("path"),
+                rev: get("rev"),
+            });
+        }
+        Ok(Self { packages })
+    }
+
+    pub fn write(&self, path: &Path) -> PackResult<()> {
+        fs::write(path, self.to_string()).map_err(|err| PackError::Io(path.to_path_buf(), err))
+    }
+}
+
+fn parse_file(path: &Path) -> PackResult<Module> {
+    let cfg = ErgConfig {
+        input: Input::file(path.to_path_buf()),
+        ..ErgConfig::default()
+    };
+    let src = fs::read_to_string(path).map_err(|err| PackError::Io(path.to_path_buf(), err))?;
+    let mut parser = ParserRunner::new(cfg);
+    parser
+        .parse(src)
+        .map(|artifact| artifact.ast)
+        .map_err(|iart| PackError::Parse(iart.errors.to_string()))
+}
+
+fn record_attrs(rec: &Record) -> Box<dyn Iterator<Item = &Def> + '_> {
+    match rec {
+        Record::Normal(rec) => Box::new(rec.iter()),
+        Record::Mixed(rec) => Box::new(rec.attrs.iter().filter_map(|attr| match attr {
+            RecordAttrOrIdent::Attr(def) => Some(def),
+            RecordAttrOrIdent::Ident(_) => None,
+        })),
+    }
+}
+
+fn is_named(def: &Def, name: &str) -> bool {
+    def.sig.ident().is_some_and(|id| id.inspect() == name)
+}
+
+fn str_value(def: &Def) -> Option<String> {
+    match def.body.block.first() {
+        Some(Expr::Literal(lit)) if lit.token.is(TokenKind::StrLit) => {
+            match ValueObj::from_str(Type::Str, lit.token.content.clone()) {
+                Some(ValueObj::Str(s)) => Some(s.to_string()),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// `1.2.3-compatible` -> `[1, 2, 3]`
+fn version_numbers(version: &str) -> Vec<u64> {
+    let version = version.split('-').next().unwrap_or(version);
+    version
+        .split('.')
+        .map(|n| n.parse::<u64>().unwrap_or(0))
+        .collect()
+}
+
+/// `1.*.*` matches `1.2.0`, `1.2.0` matches only `1.2.0`.
+pub fn version_matches(version: &str, spec: &str) -> bool {
+    let version = version.split('-').next().unwrap_or(version);
+    let spec = spec.split('-').next().unwrap_or(spec);
+    let mut comps = version.split('.');
+    spec.split('.')
+        .all(|s| comps.next().is_some_and(|v| s == "*" || s == v))
+}
+
+/// normalize a package name (case-insensitive, `-` == `_`)
+fn canonical_name(name: &str) -> String {
+    name.to_lowercase().replace('-', "_")
+}
+
+#[derive(Debug)]
+pub struct Resolver {
+    registry: PathBuf,
+    /// If the registry is not the default one, locked packages have an explicit `path`.
+    custom_registry: bool,
+    resolved: Vec<LockedPackage>,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl Resolver {
+    pub fn new(registry: Option<PathBuf>) -> Self {
+        Self {
+            custom_registry: registry.is_some(),
+            registry: registry.unwrap_or_else(|| erg_pkgs_path().clone()),
+            resolved: vec![],
+        }
+    }
+
+    /// Resolves the dependencies of `manifest` transitively.
+    pub fn resolve(mut self, manifest: &Manifest) -> PackResult<LockFile> {
+        self.resolve_deps(manifest)?;
+        Ok(LockFile {
+            packages: self.resolved,
+        })
+    }
+
+    fn resolve_deps(&mut self, manifest: &Manifest) -> PackResult<()> {
+        for dep in manifest.dependencies.iter() {
+            let locked = self.resolve_dep(dep)?;
+            if let Some(other) = self.resolved.iter().find(|p| p.as_name == locked.as_name) {
+                if other != &locked {
+                    return Err(PackError::Conflict(format!(
+                        "`{}` is required as both {} v{} and {} v{}",
+                        locked.as_name, other.name, other.version, locked.name, locked.version
+                    )));
+                }
+                continue;
+            }
+            let manifest_path = locked.root_dir().join(MANIFEST_FILE);
+            self.resolved.push(locked);
+            if manifest_path.exists() {
+                let manifest = Manifest::read(&manifest_path)?;
+                self.resolve_deps(&manifest)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_dep(&self, dep: &Dependency) -> PackResult<LockedPackage> {
+        match &dep.source {
+            DependencySource::Registry { name, version } => {
+                self.resolve_registry_dep(&dep.as_name, name, version.as_deref())
+            }
+            DependencySource::Path(path) => {
+                let (root, manifest) = Self::read_local_package(&dep.as_name, path)?;
+                Ok(LockedPackage {
+                    name: manifest.name,
+                    as_name: dep.as_name.clone(),
+                    version: manifest.version,
+                    path: Some(root.display().to_string()),
+                    rev: None,
+                })
+            }
+            DependencySource::Git(path) => {
+                let url = path.to_string_lossy();
+                if url.contains("://") || url.contains("git@") {
+                    return Err(PackError::Git(format!(
+                        "`{}`: remote repositories are not supported, clone `{url}` and specify the checkout path",
+                        dep.as_name
+                    )));
+                }
+                let (root, manifest) = Self::read_local_package(&dep.as_name, path)?;
+                let rev = Self::git_rev(&root)?;
+                Ok(LockedPackage {
+                    name: manifest.name,
+                    as_name: dep.as_name.clone(),
+                    version: manifest.version,
+                    path: Some(root.display().to_string()),
+                    rev: Some(rev),
+                })
+            }
+        }
+    }
+
+    fn resolve_registry_dep(
+        &self,
+        as_name: &str,
+        name: &str,
+        spec: Option<&str>,
+    ) -> PackResult<LockedPackage> {
+        let not_found = || {
+            PackError::NotFound(format!(
+                "`{as_name}`: no version of {name} matching `{}` found in {}",
+                spec.unwrap_or("*"),
+                self.registry.display()
+            ))
+        };
+        let pkg_dir = fs::read_dir(&self.registry)
+            .map_err(|_| not_found())?
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| {
+                path.file_name()
+                    .is_some_and(|n| canonical_name(&n.to_string_lossy()) == canonical_name(name))
+            })
+            .ok_or_else(not_found)?;
+        let version = fs::read_dir(&pkg_dir)
+            .map_err(|err| PackError::Io(pkg_dir.clone(), err))?
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|version| spec.is_none_or(|spec| version_matches(version, spec)))
+            .max_by_key(|version| version_numbers(version))
+            .ok_or_else(not_found)?;
+        let path = self.custom_registry.then(|| {
+            let path = pkg_dir.join(&version);
+            normalize_path(path.canonicalize().unwrap_or(path))
+                .display()
+                .to_string()
+        });
+        Ok(LockedPackage {
+            name: pkg_dir
+                .file_name()
+                .map_or(name.to_string(), |n| n.to_string_lossy().to_string()),
+            as_name: as_name.to_string(),
+            version,
+            path,
+            rev: None,
+        })
+    }
+
+    fn read_local_package(as_name: &str, path: &Path) -> PackResult<(PathBuf, Manifest)> {
+        let root = path.canonicalize().map_err(|_| {
+            PackError::NotFound(format!("`{as_name}`: {} not found", path.display()))
+        })?;
+        let manifest = Manifest::read(&root.join(MANIFEST_FILE))?;
+        Ok((normalize_path(root), manifest))
+    }
+
+    fn git_rev(root: &Path) -> PackResult<String> {
+        let out = Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(["rev-parse", "HEAD"])
+            .output()
+            .map_err(|err| PackError::Git(format!("failed to run git: {err}")))?;
+        if !out.status.success() {
+            return Err(PackError::Git(format!(
+                "{} is not a git checkout: {}",
+                root.display(),
+                String::from_utf8_lossy(&out.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PackageManagerRunner {}
+
+impl PackageManagerRunner {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn run(cfg: ErgConfig) -> ExitStatus {
+        let mut args = cfg.runtime_args.iter();
+        let command = args.next().copied();
+        let mut registry = None;
+        while let Some(&arg) = args.next() {
+            match arg {
+                "--registry" => {
+                    let Some(&dir) = args.next() else {
+                        eprintln!("Error: the value of `--registry` is not passed");
+                        return ExitStatus::ERR1;
+                    };
+                    registry = Some(PathBuf::from(dir));
+                }
+                other => {
+                    eprintln!("Error: invalid argument: {other}");
+                    return ExitStatus::ERR1;
+                }
+            }
+        }
+        let res = match command {
+            Some("init") => Self::init(),
+            Some("install" | "lock") => Self::install(registry),
+            Some("list") => Self::list(),
+            Some("-h" | "--help" | "help") => {
+                println!("{PACK_HELP}");
+                Ok(())
+            }
+            Some(other) => {
+                eprintln!("Error: unknown command: {other}\n\n{PACK_HELP}");
+                return ExitStatus::ERR1;
+            }
+            None => {
+                eprintln!("{PACK_HELP}");
+                return ExitStatus::ERR1;
+            }
+        };
+        match res {
+            Ok(()) => ExitStatus::OK,
+            Err(err) => {
+                eprintln!("Error: {err}");
+                ExitStatus::ERR1
+            }
+        }
+    }
+
+    fn init() -> PackResult<()> {
+        let cwd = normalize_path(std::env::current_dir().unwrap_or_default());
+        let manifest = cwd.join(MANIFEST_FILE);
+        if manifest.exists() {
+            return Err(PackError::Conflict(format!(
+                "{} already exists",
+                manifest.display()
+            )));
+        }
+        let name = cwd
+            .file_name()
+            .map_or("package".to_string(), |n| n.to_string_lossy().to_string());
+        let src = format!(
+            ".name = \"{name}\"\n.version = \"0.1.0\"\n.authors = []\n.type = \"app\"\n.dependencies = {{}}\n"
+        );
+        fs::write(&manifest, src).map_err(|err| PackError::Io(manifest.clone(), err))?;
+        let src_dir = cwd.join("src");
+        let main = src_dir.join("main.er");
+        if !main.exists() {
+            fs::create_dir_all(&src_dir).map_err(|err| PackError::Io(src_dir.clone(), err))?;
+            fs::write(&main, "print! \"Hello, world!\"\n")
+                .map_err(|err| PackError::Io(main.clone(), err))?;
+        }
+        println!("Initialized package `{name}`");
+        Ok(())
+    }
+
+    fn install(registry: Option<PathBuf>) -> PackResult<()> {
+        let cwd = normalize_path(std::env::current_dir().unwrap_or_default());
+        let manifest = Manifest::read(&cwd.join(MANIFEST_FILE))?;
+        let lock = Resolver::new(registry).resolve(&manifest)?;
+        lock.write(&cwd.join(LOCK_FILE))?;
+        for pkg in lock.packages.iter() {
+            println!("Locked {} v{} (as {})", pkg.name, pkg.version, pkg.as_name);
+        }
+        Ok(())
+    }
+
+    fn list() -> PackResult<()> {
+        let cwd = normalize_path(std::env::current_dir().unwrap_or_default());
+        let lock = LockFile::read(&cwd.join(LOCK_FILE))?;
+        for pkg in lock.packages.iter() {
+            println!("{} = {} v{}", pkg.as_name, pkg.name, pkg.version);
+        }
+        Ok(())
+    }
+
+    /// Registers the packages of the project that the input belongs to with `cfg.packages`.
+    ///
+    /// Reads `package.lock.er` if exists, otherwise resolves `package.er` (without writing the lock file).
+    /// Packages specified with `--use-package` take precedence.
+    pub fn register_packages(cfg: &mut ErgConfig) {
+        let root = match cfg.input.project_root() {
+            Some(root) => root.clone(),
+            None => normalize_path(std::env::current_dir().unwrap_or_default()),
+        };
+        let lock_file = root.join(LOCK_FILE);
+        let manifest = root.join(MANIFEST_FILE);
+        let lock = if lock_file.exists() {
+            LockFile::read(&lock_file)
+        } else if manifest.exists() {
+            match Manifest::read(&manifest) {
+                // nothing to resolve
+                Ok(manifest) if manifest.dependencies.is_empty() => return,
+                Ok(manifest) => Resolver::default().resolve(&manifest),
+                Err(err) => Err(err),
+            }
+        } else {
+            return;
+        };
+        let lock = match lock {
+            Ok(lock) => lock,
+            Err(err) => {
+                eprintln!("Warning: failed to load the packages: {err}");
+                return;
+            }
+        };
+        if lock.packages.is_empty() {
+            return;
+        }
+        let mut packages = cfg.packages.to_vec();
+        for pkg in lock.packages.iter() {
+            if packages.iter().all(|p| p.as_name != pkg.as_name) {
+                packages.push(pkg.to_package());
+            }
+        }
+        cfg.packages = ArcArray::from(packages);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("erg_pack_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_manifest(dir: &Path, src: &str) -> PathBuf {
+        fs::create_dir_all(dir).unwrap();
+        let path = dir.join(MANIFEST_FILE);
+        fs::write(&path, src).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_version_matches() {
+        assert!(version_matches("1.2.0", "1.*.*"));
+        assert!(version_matches("1.2.0", "1.2.0"));
+        assert!(version_matches("1.2.0-compatible", "1.2.*"));
+        assert!(!version_matches("2.0.0", "1.*.*"));
+        assert!(!version_matches("1.2", "1.2.0"));
+    }
+
+    #[test]
+    fn test_read_manifest() {
+        let dir = temp_dir("manifest");
+        let path = write_manifest(
+            &dir,
+            r#".name = "foo\"bar"
+.version = "0.1.0"
+.dependencies = {
+    a = "a";
+    b = { name = "b-pkg"; version = "1.*.*" };
+    c = { path = "../c" };
+}
+"#,
+        );
+        let manifest = Manifest::read(&path).unwrap();
+        assert_eq!(manifest.name, "foo\"bar");
+        assert_eq!(manifest.version, "0.1.0");
+        assert_eq!(
+            manifest.dependencies,
+            vec![
+                Dependency {
+                    as_name: "a".into(),
+                    source: DependencySource::Registry {
+                        name: "a".into(),
+                        version: None
+                    },
+                },
+                Dependency {
+                    as_name: "b".into(),
+                    source: DependencySource::Registry {
+                        name: "b-pkg".into(),
+                        version: Some("1.*.*".into())
+                    },
+                },
+                Dependency {
+                    as_name: "c".into(),
+                    source: DependencySource::Path(dir.join("../c")),
+                },
+            ]
+        );
+        let empty = write_manifest(
+            &dir,
+            ".name = \"foo\"\n.version = \"0.1.0\"\n.dependencies = {}\n",
+        );
+        assert!(Manifest::read(&empty).unwrap().dependencies.is_empty());
+        let invalid = write_manifest(&dir, ".name = 1\n.version = \"0.1.0\"\n");
+        assert!(matches!(
+            Manifest::read(&invalid),
+            Err(PackError::InvalidManifest(..))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_resolve() {
+        let dir = temp_dir("resolve");
+        let registry = dir.join("registry");
+        for version in ["1.0.0", "1.2.0", "2.0.0"] {
+            fs::create_dir_all(registry.join("b_pkg").join(version)).unwrap();
+        }
+        // `c` depends on `b` v1 as `b1`
+        write_manifest(
+            &dir.join("c"),
+            ".name = \"c\"\n.version = \"0.2.0\"\n.dependencies = { b1 = { name = \"b-pkg\"; version = \"1.*.*\" } }\n",
+        );
+        let manifest = write_manifest(
+            &dir.join("app"),
+            ".name = \"app\"\n.version = \"0.1.0\"\n.dependencies = { b = \"b-pkg\"; c = { path = \"../c\" } }\n",
+        );
+        let manifest = Manifest::read(&manifest).unwrap();
+        let lock = Resolver::new(Some(registry.clone()))
+            .resolve(&manifest)
+            .unwrap();
+        let registry = normalize_path(registry.canonicalize().unwrap());
+        let c = normalize_path(dir.join("c").canonicalize().unwrap());
+        assert_eq!(
+            lock.packages,
+            vec![
+                LockedPackage {
+                    name: "b_pkg".into(),
+                    as_name: "b".into(),
+                    version: "2.0.0".into(),
+                    path: Some(registry.join("b_pkg").join("2.0.0").display().to_string()),
+                    rev: None,
+                },
+                LockedPackage {
+                    name: "c".into(),
+                    as_name: "c".into(),
+                    version: "0.2.0".into(),
+                    path: Some(c.display().to_string()),
+                    rev: None,
+                },
+                LockedPackage {
+                    name: "b_pkg".into(),
+                    as_name: "b1".into(),
+                    version: "1.2.0".into(),
+                    path: Some(registry.join("b_pkg").join("1.2.0").display().to_string()),
+                    rev: None,
+                },
+            ]
+        );
+        let missing = Manifest {
+            name: "app".into(),
+            version: "0.1.0".into(),
+            dependencies: vec![Dependency {
+                as_name: "b".into(),
+                source: DependencySource::Registry {
+                    name: "b-pkg".into(),
+                    version: Some("3.*.*".into()),
+                },
+            }],
+        };
+        assert!(matches!(
+            Resolver::new(Some(registry)).resolve(&missing),
+            Err(PackError::NotFound(_))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_lock_file() {
+        let dir = temp_dir("lock");
+        let path = dir.join(LOCK_FILE);
+        let lock = LockFile {
+            packages: vec![
+                LockedPackage {
+                    name: "a".into(),
+                    as_name: "a".into(),
+                    version: "1.0.0".into(),
+                    path: None,
+                    rev: None,
+                },
+                LockedPackage {
+                    name: "b".into(),
+                    as_name: "b_\"x\"".into(),
+                    version: "0.1.0".into(),
+                    path: Some("C:\\path\\to\\b".into()),
+                    rev: Some("0123abcd".into()),
+                },
+            ],
+        };
+        lock.write(&path).unwrap();
+        assert_eq!(LockFile::read(&path).unwrap(), lock);
+        let empty = LockFile::default();
+        empty.write(&path).unwrap();
+        assert_eq!(LockFile::read(&path).unwrap(), empty);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
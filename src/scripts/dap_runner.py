@@ -0,0 +1,188 @@
+# Runs a compiled Erg program under `bdb` and talks with the debug adapter (`erg dap`).
+# The messages are newline-delimited JSON objects.
+# usage: python -c <this script> <pyc file> <program (.er)> [args]...
+import bdb
+import builtins
+import inspect
+import json
+import marshal
+import os
+import socket
+import sys
+import traceback
+
+MAX_VALUE_LEN = 256
+
+def is_erg_frame(frame):
+    return frame.f_code.co_filename.endswith('.er')
+
+class ErgDebugger(bdb.Bdb):
+    def __init__(self, stream):
+        super().__init__()
+        self.stream = stream
+        self.frames = []
+        self.stop_on_entry = False
+        self.entered = False
+        self.stepping = False
+
+    def send(self, msg):
+        self.stream.write(json.dumps(msg) + '\n')
+        self.stream.flush()
+
+    def recv(self):
+        line = self.stream.readline()
+        if not line:
+            # the adapter has gone
+            raise bdb.BdbQuit
+        return json.loads(line)
+
+    def set_breakpoints(self, file, lines):
+        file = self.canonic(file)
+        self.clear_all_file_breaks(file)
+        verified = []
+        for line in lines:
+            # `set_break` returns an error message if the line does not exist
+            verified.append(self.set_break(file, line) is None)
+        return verified
+
+    def stack_frames(self, frame):
+        frames = []
+        while frame is not None:
+            if is_erg_frame(frame):
+                frames.append(frame)
+            frame = frame.f_back
+        return frames
+
+    def local_names(self, code):
+        return set(code.co_varnames + code.co_cellvars + code.co_freevars)
+
+    def variables(self, frame):
+        prelude = sys.modules.get('_erg_std_prelude')
+        # Erg functions are not `CO_OPTIMIZED`, so their locals are written back to the globals by the tracer
+        if frame.f_code.co_flags & inspect.CO_NEWLOCALS:
+            is_visible = self.local_names(frame.f_code).__contains__
+        else:
+            hidden = set()
+            for f in self.frames:
+                if f.f_code.co_flags & inspect.CO_NEWLOCALS:
+                    hidden |= self.local_names(f.f_code)
+            is_visible = lambda name: name not in hidden
+        variables = []
+        for (name, value) in frame.f_locals.items():
+            if name.startswith('__') and name.endswith('__'):
+                continue
+            if not is_visible(name):
+                continue
+            # names imported from the prelude (`Nat`, `Str`, ...)
+            if prelude is not None and getattr(prelude, name, None) is value:
+                continue
+            try:
+                text = repr(value)
+            except Exception as e:
+                text = '<' + type(e).__name__ + '>'
+            if len(text) > MAX_VALUE_LEN:
+                text = text[:MAX_VALUE_LEN] + '...'
+            variables.append({'name': name, 'value': text, 'type': type(value).__name__})
+        return variables
+
+    def user_line(self, frame):
+        if not is_erg_frame(frame):
+            # keep stepping until we reach the Erg code
+            return
+        if not self.entered:
+            # `bdb` stops at the first line of the program
+            self.entered = True
+            if self.stop_on_entry:
+                reason = 'entry'
+            elif self.get_break(self.canonic(frame.f_code.co_filename), frame.f_lineno):
+                reason = 'breakpoint'
+            else:
+                self.set_continue()
+                return
+        elif self.stepping:
+            reason = 'step'
+        else:
+            reason = 'breakpoint'
+        self.interaction(frame, reason)
+
+    def interaction(self, frame, reason):
+        self.frames = self.stack_frames(frame)
+        self.send({
+            'event': 'stopped',
+            'reason': reason,
+            'frames': [
+                {'name': f.f_code.co_name, 'file': f.f_code.co_filename, 'line': f.f_lineno}
+                for f in self.frames
+            ],
+        })
+        while True:
+            msg = self.recv()
+            command = msg.get('command')
+            if command == 'variables':
+                self.send({'variables': self.variables(self.frames[msg['frame']])})
+            elif command == 'setBreakpoints':
+                self.send({'verified': self.set_breakpoints(msg['file'], msg['lines'])})
+            elif command == 'continue':
+                self.stepping = False
+                self.set_continue()
+                break
+            elif command == 'next':
+                self.stepping = True
+                self.set_next(frame)
+                break
+            elif command == 'stepIn':
+                self.stepping = True
+                self.set_step()
+                break
+            elif command == 'stepOut':
+                self.stepping = True
+                self.set_return(frame)
+                break
+            elif command == 'terminate':
+                self.set_quit()
+                break
+            else:
+                self.send({'error': 'unknown command: ' + str(command)})
+
+server_socket = socket.socket()
+# the debug adapter will replace this __PORT__ with a free port
+server_socket.bind(('127.0.0.1', __PORT__))
+server_socket.listen(1)
+(client_socket, client_address) = server_socket.accept()
+stream = client_socket.makefile('rw', encoding='utf-8')
+
+(pyc, program) = sys.argv[1:3]
+sys.argv = sys.argv[2:]
+sys.path[0] = os.path.dirname(program)
+with open(pyc, 'rb') as f:
+    # skip the header (magic number, flags, timestamp, size)
+    f.read(16)
+    code = marshal.load(f)
+
+debugger = ErgDebugger(stream)
+# receive the breakpoints before starting
+while True:
+    msg = debugger.recv()
+    if msg.get('command') == 'setBreakpoints':
+        debugger.send({'verified': debugger.set_breakpoints(msg['file'], msg['lines'])})
+    elif msg.get('command') == 'start':
+        debugger.stop_on_entry = msg.get('stopOnEntry', False)
+        break
+
+exit_code = 0
+try:
+    debugger.run(code, {'__name__': '__main__', '__file__': program, '__builtins__': builtins})
+except bdb.BdbQuit:
+    exit_code = 1
+except SystemExit as e:
+    exit_code = e.code if isinstance(e.code, int) else int(e.code is not None)
+except BaseException:
+    traceback.print_exc()
+    exit_code = 1
+sys.stdout.flush()
+sys.stderr.flush()
+try:
+    debugger.send({'event': 'exited', 'code': exit_code})
+except OSError:
+    pass
+client_socket.close()
@@ -59,6 +59,37 @@ pub(crate) fn expect_success(file_path: &'static str, num_warns: usize) -> Resul
     }
 }
 
+pub(crate) fn expect_optimized_success(
+    file_path: &'static str,
+    num_warns: usize,
+) -> Result<(), ()> {
+    match exec_optimized_file(file_path) {
+        Ok(stat) if stat.succeed() => {
+            if stat.num_warns == num_warns {
+                Ok(())
+            } else {
+                println!(
+                    "err[{file_path}]: number of warnings should be {num_warns}, but got {}",
+                    stat.num_warns
+                );
+                Err(())
+            }
+        }
+        Ok(stat) => {
+            println!(
+                "err[{file_path}]: should succeed, but end with {}",
+                stat.code
+            );
+            Err(())
+        }
+        Err(errs) => {
+            println!("err[{file_path}]: should succeed, but got compile errors");
+            println!("{errs}");
+            Err(())
+        }
+    }
+}
+
 pub(crate) fn expect_compile_success(file_path: &'static str, num_warns: usize) -> Result<(), ()> {
     match exec_compiler(file_path) {
         Ok(stat) if stat.succeed() => {
@@ -242,6 +273,19 @@ fn _exec_file(file_path: &'static str) -> Result<ExitStatus, CompileErrors> {
     vm.exec()
 }
 
+fn _exec_optimized_file(file_path: &'static str) -> Result<ExitStatus, CompileErrors> {
+    println!("{DEBUG_MAIN}[test] exec (optimized) {file_path}{RESET}");
+    let mut cfg = ErgConfig::with_main_path(PathBuf::from(file_path));
+    cfg.output = if DEBUG_MODE {
+        Output::stdout()
+    } else {
+        Output::Null
+    };
+    cfg.opt_level = 2;
+    let mut vm = DummyVM::new(set_cfg(cfg));
+    vm.exec()
+}
+
 /// WARN: You must quit REPL manually (use `:exit`, `:quit` or call something shutdowns the interpreter)
 pub fn _exec_repl(name: &'static str, lines: Vec<String>) -> Result<ExitStatus, CompileErrors> {
     println!("{DEBUG_MAIN}[test] exec dummy REPL: {lines:?}{RESET}");
@@ -265,6 +309,10 @@ pub(crate) fn exec_file(file_path: &'static str) -> Result<ExitStatus, CompileEr
     exec_new_thread(move || _exec_file(file_path), file_path)
 }
 
+pub(crate) fn exec_optimized_file(file_path: &'static str) -> Result<ExitStatus, CompileErrors> {
+    exec_new_thread(move || _exec_optimized_file(file_path), file_path)
+}
+
 pub(crate) fn exec_repl(
     name: &'static str,
     lines: Vec<String>,
@@ -0,0 +1,180 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+const PROGRAM: &str = "\
+f x: Int =
+    y = x + 1
+    y * 2
+
+z = f 1
+print! z
+";
+
+/// A client talking with `erg dap` through stdio
+struct DapClient {
+    child: Child,
+    stdin: ChildStdin,
+    messages: Receiver<Value>,
+    seq: i64,
+    /// the events received while waiting for the responses
+    events: Vec<Value>,
+}
+
+impl DapClient {
+    fn spawn() -> Self {
+        let mut child = Command::new(env!(concat!("CARGO_BIN_EXE_", env!("CARGO_PKG_NAME"))))
+            .arg("dap")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("failed to spawn the debug adapter");
+        let stdin = child.stdin.take().unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let (sender, messages) = channel();
+        thread::spawn(move || {
+            while let Some(msg) = Self::read_message(&mut stdout) {
+                if sender.send(msg).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            child,
+            stdin,
+            messages,
+            seq: 0,
+            events: vec![],
+        }
+    }
+
+    fn read_message(reader: &mut impl BufRead) -> Option<Value> {
+        let mut len = None;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).ok()? == 0 {
+                return None;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length:") {
+                len = value.trim().parse::<usize>().ok();
+            }
+        }
+        let mut buf = vec![0; len?];
+        reader.read_exact(&mut buf).ok()?;
+        serde_json::from_slice(&buf).ok()
+    }
+
+    fn recv(&self) -> Value {
+        self.messages
+            .recv_timeout(TIMEOUT)
+            .expect("the debug adapter does not respond")
+    }
+
+    fn request(&mut self, command: &str, arguments: Value) -> Value {
+        self.seq += 1;
+        let body = json!({
+            "seq": self.seq,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        })
+        .to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+        self.stdin.flush().unwrap();
+        loop {
+            let msg = self.recv();
+            if msg["type"] == "response" && msg["request_seq"] == self.seq {
+                assert_eq!(msg["success"], true, "{msg}");
+                return msg["body"].clone();
+            }
+            self.events.push(msg);
+        }
+    }
+
+    /// Waits for the event `name` and returns it with the events received before it.
+    fn wait_event(&mut self, name: &str) -> (Value, Vec<Value>) {
+        let mut before = vec![];
+        let mut events = std::mem::take(&mut self.events).into_iter();
+        loop {
+            let msg = events.next().unwrap_or_else(|| self.recv());
+            if msg["type"] == "event" && msg["event"] == name {
+                self.events.extend(events);
+                return (msg, before);
+            }
+            before.push(msg);
+        }
+    }
+}
+
+impl Drop for DapClient {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn is_event(msg: &Value, name: &str) -> bool {
+    msg["type"] == "event" && msg["event"] == name
+}
+
+#[test]
+fn exec_dap_breakpoint() {
+    let program = std::env::temp_dir().join(format!("erg_dap_test_{}.er", std::process::id()));
+    std::fs::write(&program, PROGRAM).unwrap();
+    let program = program.canonicalize().unwrap();
+    let mut client = DapClient::spawn();
+    client.request("initialize", json!({ "adapterID": "erg" }));
+    client.wait_event("initialized");
+    client.request("launch", json!({ "program": program }));
+    let res = client.request(
+        "setBreakpoints",
+        json!({ "source": { "path": program }, "breakpoints": [{ "line": 3 }] }),
+    );
+    assert_eq!(res["breakpoints"][0]["verified"], true, "{res}");
+    client.request("configurationDone", json!({}));
+    let (stopped, _) = client.wait_event("stopped");
+    assert_eq!(stopped["body"]["reason"], "breakpoint");
+    let trace = client.request("stackTrace", json!({ "threadId": 1 }));
+    let top = &trace["stackFrames"][0];
+    assert_eq!(top["line"], 3, "{trace}");
+    assert_eq!(top["name"], "f", "{trace}");
+    let vars = client.request("variables", json!({ "variablesReference": 1 }));
+    let vars = vars["variables"].as_array().unwrap();
+    assert!(
+        vars.iter()
+            .any(|var| var["name"] == "x" && var["value"] == "1"),
+        "{vars:?}"
+    );
+    assert!(
+        vars.iter()
+            .any(|var| var["name"] == "y" && var["value"] == "2"),
+        "{vars:?}"
+    );
+    client.request("continue", json!({ "threadId": 1 }));
+    let (exited, before) = client.wait_event("exited");
+    assert_eq!(exited["body"]["exitCode"], 0);
+    // the definition of `f` (line 1) must not hit the breakpoint in the body
+    assert!(
+        !before.iter().any(|msg| is_event(msg, "stopped")),
+        "{before:?}"
+    );
+    let output = before
+        .iter()
+        .filter(|msg| is_event(msg, "output") && msg["body"]["category"] == "stdout")
+        .filter_map(|msg| msg["body"]["output"].as_str())
+        .collect::<String>();
+    assert_eq!(output.trim(), "4");
+    client.request("disconnect", json!({}));
+    let _ = std::fs::remove_file(&program);
+}
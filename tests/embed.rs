@@ -1,9 +1,16 @@
+use std::env::temp_dir;
+use std::path::PathBuf;
+use std::process::Command;
+
 use erg::DummyVM;
-use erg_common::config::ErgConfig;
-use erg_common::error::MultiErrorDisplay;
+use erg_common::config::{ErgConfig, TranspileTarget};
+use erg_common::error::{ErrorDisplay, ErrorKind, MultiErrorDisplay};
 use erg_common::fn_name;
-use erg_common::python_util::{env_python_version, exec_py_code_with_output};
+use erg_common::python_util::{_opt_which_python, env_python_version, exec_py_code_with_output};
+use erg_common::spawn::exec_new_thread;
+use erg_common::traits::Stream;
 use erg_compiler::artifact::Buildable;
+use erg_compiler::dyne::Dyne;
 use erg_compiler::module::SharedCompilerResource;
 use erg_compiler::HIRBuilder;
 use erg_compiler::Transpiler;
@@ -19,6 +26,159 @@ fn test_vm_embedding() -> Result<(), ()> {
     Ok(())
 }
 
+#[test]
+fn test_vm_runtime_error() -> Result<(), ()> {
+    let mut vm = DummyVM::default();
+    let errs = vm.eval("f x = 1 / x\nf 0".into()).expect_err("should err");
+    let err = errs.first().unwrap();
+    assert_eq!(err.core().kind, ErrorKind::ZeroDivisionError);
+    assert_eq!(err.core().loc.ln_begin(), Some(1));
+    assert_eq!(err.caused_by(), "f");
+    // the VM is still available after the exception
+    let res = vm.eval("1 + 1".into()).map_err(|es| {
+        es.write_all_stderr();
+    })?;
+    assert_eq!(res, "2");
+    Ok(())
+}
+
+#[test]
+fn test_dyne_embedding() -> Result<(), ()> {
+    let mut dyne = Dyne::default();
+    dyne.interpreter().capture_output();
+    let src = "\
+@Inheritable
+C = Class { .x = Int }
+C.
+    get_x self = self.x
+D = Inherit C
+D.
+    twice self = self.get_x() * 2
+adder n = x -> x + n
+f x = match x:
+    1 -> \"one\"
+    (i: Int) -> \"int \" + str(i)
+    _ -> \"other\"
+l = ![1, 2]
+l.push! 3
+r = {.name = \"erg\"; .ver = 1}
+d = D.new { .x = 21 }
+print! d.twice(), adder(1)(2), f(1), f(2), f(\"a\")
+print! l, {\"a\": 1}, {1, 1}, (1, \"a\"), r.name, 7 / 2, -7 // 2
+print! [i * 2 | i <- [1, 2, 3]], {i | i <- [1, 1]}, (i | i <- [1])
+";
+    dyne.eval(src.into()).map_err(|es| {
+        es.write_all_stderr();
+    })?;
+    assert_eq!(
+        dyne.interpreter().take_output(),
+        "42 3 one int 2 other\n[1, 2, 3] {'a': 1} {1} (1, 'a') erg 3.5 -4\n[2, 4, 6] {1} (1,)\n"
+    );
+    // variables are kept between evaluations
+    let res = dyne.eval("d.x + 1".into()).map_err(|es| {
+        es.write_all_stderr();
+    })?;
+    assert_eq!(res, "22");
+    Ok(())
+}
+
+#[test]
+fn test_dyne_runtime_error() -> Result<(), ()> {
+    let mut dyne = Dyne::default();
+    let errs = dyne
+        .eval("f x = 1 / x\nf 0".into())
+        .expect_err("should err");
+    let err = errs.first().unwrap();
+    assert_eq!(err.core().kind, ErrorKind::ZeroDivisionError);
+    assert_eq!(err.core().loc.ln_begin(), Some(1));
+    assert_eq!(err.caused_by(), "f");
+    let errs = dyne
+        .eval("math = pyimport \"math\"".into())
+        .expect_err("should err");
+    assert_eq!(errs.first().unwrap().core().kind, ErrorKind::ImportError);
+    // arbitrary-precision integers are not supported
+    let errs = dyne.eval("big = 2 ** 100".into()).expect_err("should err");
+    assert_eq!(errs.first().unwrap().core().kind, ErrorKind::FeatureError);
+    // errors in desugared expressions are located at the original expression
+    let errs = dyne
+        .eval("n = 0\nl = [1 / i | i <- [n]]".into())
+        .expect_err("should err");
+    let err = errs.first().unwrap();
+    assert_eq!(err.core().kind, ErrorKind::ZeroDivisionError);
+    assert_eq!(err.core().loc.ln_begin(), Some(2));
+    let errs = exec_new_thread(
+        || Dyne::default().eval("f(n: Nat): Nat = f(n + 1)\nf 0".into()),
+        "dyne_recursion",
+    )
+    .expect_err("should err");
+    assert_eq!(errs.first().unwrap().core().kind, ErrorKind::RecursionError);
+    Ok(())
+}
+
+/// `tests/should_ok` files that Dyne cannot run, and the reasons
+const DYNE_UNSUPPORTED: &[(&str, &str)] = &[
+    ("advanced_type_spec.er", "`[x; _]` literals"),
+    ("assert_cast.er", "imports the Python module `json`"),
+    ("associated_types.er", "traits"),
+    ("class.er", "imports the Python module `unittest`"),
+    ("comment.er", "`Del`"),
+    ("context_manager.er", "`with!`"),
+    ("dependent_refinement.er", "imports a Python module"),
+    ("dunder.er", "`module` and `bytes`"),
+    ("index.er", "`bytes`"),
+    ("iterator.er", "imports the Python module `os`"),
+    (
+        "none.er",
+        "does not compile (not a test case of `tests/test.rs`)",
+    ),
+    ("optimize.er", "`with!`"),
+    ("py_decl.er", "imports a Python module"),
+    ("pyimport.er", "imports the Python module `typing`"),
+    ("refinement_class.er", "inheriting builtin classes"),
+    ("structural.er", "`bytes`"),
+    ("try_operator.er", "the `?` operator"),
+    ("use_itertools.er", "imports the Python module `itertools`"),
+];
+
+#[test]
+fn test_dyne_should_ok() -> Result<(), ()> {
+    let mut files = std::fs::read_dir("tests/should_ok")
+        .map_err(|_| ())?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        // declaration files (`.d.er`) are not executable
+        .filter(|name| name.ends_with(".er") && !name.ends_with(".d.er"))
+        .filter(|name| DYNE_UNSUPPORTED.iter().all(|(file, _)| file != name))
+        .collect::<Vec<_>>();
+    files.sort();
+    let mut failed = vec![];
+    for name in files {
+        let file = format!("tests/should_ok/{name}");
+        let path = PathBuf::from(&file);
+        let res = exec_new_thread(
+            move || {
+                let mut dyne = Dyne::new(ErgConfig::with_main_path(path));
+                dyne.interpreter().capture_output();
+                dyne.exec()
+            },
+            &name,
+        );
+        match res {
+            Ok(stat) if stat.succeed() => {}
+            Ok(stat) => failed.push(format!("{file}: end with {}", stat.code)),
+            Err(errs) => {
+                errs.write_all_stderr();
+                failed.push(format!("{file}: got errors"));
+            }
+        }
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        println!("err: {failed:#?}");
+        Err(())
+    }
+}
+
 #[test]
 fn test_transpiler_embedding() -> Result<(), ()> {
     let mut trans = Transpiler::default();
@@ -37,6 +197,35 @@ fn test_transpiler_embedding() -> Result<(), ()> {
     Ok(())
 }
 
+#[test]
+fn test_transpiler_fold_constants() -> Result<(), ()> {
+    let src = "print! 1 + 2, \"a\" + \"b\"".to_string();
+    // constants are not folded in the REPL (the input of `Transpiler::default()`)
+    let cfg = ErgConfig::string(src.clone());
+    let mut trans = Transpiler::new(cfg.copy());
+    let res = trans.transpile(src.clone(), "exec").map_err(|es| {
+        es.errors.write_all_stderr();
+    })?;
+    assert!(res
+        .object
+        .code()
+        .ends_with("(print)(Nat(3),Str(\"ab\"),)\n"));
+    // nor with `-o 0`
+    let cfg = ErgConfig {
+        opt_level: 0,
+        ..cfg
+    };
+    let mut trans = Transpiler::new(cfg);
+    let res = trans.transpile(src, "exec").map_err(|es| {
+        es.errors.write_all_stderr();
+    })?;
+    assert!(res
+        .object
+        .code()
+        .ends_with("(print)((Nat(1) + Nat(2)),(Str(\"a\") + Str(\"b\")),)\n"));
+    Ok(())
+}
+
 #[test]
 fn test_transpiler_embedding2() -> Result<(), ()> {
     let mut trans = Transpiler::default();
@@ -124,6 +313,294 @@ print! z, end:=\"\"
     Ok(())
 }
 
+#[test]
+fn test_transpiler_var_kwargs() -> Result<(), ()> {
+    let mut trans = Transpiler::default();
+    let res = trans
+        .transpile(include_str!("should_ok/var_kwargs.er").into(), "exec")
+        .map_err(|es| {
+            es.errors.write_all_stderr();
+        })?;
+    let res = exec_py_code_with_output(res.object.code(), &[]).map_err(|_| ())?;
+    assert!(res.status.success());
+    let mut trans = Transpiler::default();
+    let res = trans
+        .transpile(
+            "k(**kws: Int) = kws\nprint! k(a:=1), end:=\"\"\n".into(),
+            "exec",
+        )
+        .map_err(|es| {
+            es.errors.write_all_stderr();
+        })?;
+    let res = exec_py_code_with_output(res.object.code(), &[]).map_err(|_| ())?;
+    assert!(res.status.success());
+    assert_eq!(res.stdout, b"{'a': 1}");
+    Ok(())
+}
+
+#[test]
+fn test_transpiler_source_map() -> Result<(), ()> {
+    let mut trans = Transpiler::default();
+    let res = trans
+        .transpile(
+            "f x =
+    assert x == 1
+    x
+print! f(1)
+print! f(2)
+"
+            .into(),
+            "exec",
+        )
+        .map_err(|es| {
+            es.errors.write_all_stderr();
+        })?;
+    let py_file = temp_dir().join(format!("{}.py", fn_name!()));
+    std::fs::write(&py_file, res.object.code()).map_err(|_| ())?;
+    let out = Command::new(_opt_which_python().map_err(|_| ())?)
+        .arg(&py_file)
+        .output()
+        .map_err(|_| ())?;
+    std::fs::remove_file(&py_file).map_err(|_| ())?;
+    assert!(!out.status.success());
+    let mut source_map = res.object.source_map().unwrap().clone();
+    source_map.file = py_file.display().to_string();
+    source_map.source = "test.er".into();
+    let traceback = String::from_utf8_lossy(&out.stderr);
+    let rewritten = source_map.rewrite_traceback(&traceback);
+    assert!(rewritten.contains("File \"test.er\", line 5, in <module>"));
+    assert!(rewritten.contains("File \"test.er\", line 2, in f"));
+    assert!(rewritten.ends_with("AssertionError\n"));
+    Ok(())
+}
+
+#[test]
+fn test_transpiler_toml() -> Result<(), ()> {
+    let cfg = ErgConfig {
+        transpile_target: Some(TranspileTarget::Toml),
+        ..ErgConfig::default()
+    };
+    let mut trans = Transpiler::new(cfg.copy());
+    let res = trans
+        .transpile(
+            "port = 8000
+.title = \"example\"
+.server = { .port = port + 80; .hosts = [\"a\", \"b\"] }
+.users = [{ .name = \"alice\" }, { .name = \"bob\" }]
+"
+            .into(),
+            "exec",
+        )
+        .map_err(|es| {
+            es.errors.write_all_stderr();
+        })?;
+    assert_eq!(
+        res.object.code(),
+        "title = \"example\"
+
+[server]
+hosts = [\"a\", \"b\"]
+port = 8080
+
+[[users]]
+name = \"alice\"
+
+[[users]]
+name = \"bob\"
+"
+    );
+    let mut trans = Transpiler::new(cfg);
+    let errs = trans
+        .transpile(".f x = x\n.n = None\n".into(), "exec")
+        .expect_err("should err")
+        .errors;
+    assert_eq!(errs.len(), 2);
+    assert_eq!(errs.first().unwrap().core.kind, ErrorKind::NotConstExpr);
+    assert_eq!(errs.last().unwrap().core.kind, ErrorKind::TypeError);
+    Ok(())
+}
+
+#[test]
+fn test_transpiler_yaml() -> Result<(), ()> {
+    let cfg = ErgConfig {
+        transpile_target: Some(TranspileTarget::Yaml),
+        ..ErgConfig::default()
+    };
+    let mut trans = Transpiler::new(cfg.copy());
+    let res = trans
+        .transpile(
+            ".title = \"true\"
+.server = { .port = 8080; .hosts = [\"a\", \"b\"]; .tags = [] }
+.users = [{ .name = \"alice\" }, { .name = \"bob\" }]
+.ratio = 0.5
+"
+            .into(),
+            "exec",
+        )
+        .map_err(|es| {
+            es.errors.write_all_stderr();
+        })?;
+    assert_eq!(
+        res.object.code(),
+        "title: \"true\"
+server:
+  hosts:
+    - a
+    - b
+  port: 8080
+  tags: []
+users:
+  - name: alice
+  - name: bob
+ratio: 0.5
+"
+    );
+    let mut trans = Transpiler::new(cfg);
+    let errs = trans
+        .transpile(".d = {(1, 2): \"a\"}\n".into(), "exec")
+        .expect_err("should err")
+        .errors;
+    assert_eq!(errs.len(), 1);
+    assert_eq!(errs.first().unwrap().core.kind, ErrorKind::TypeError);
+    Ok(())
+}
+
+#[test]
+fn test_transpiler_pyi() -> Result<(), ()> {
+    let cfg = ErgConfig {
+        transpile_target: Some(TranspileTarget::PyStub),
+        ..ErgConfig::default()
+    };
+    let mut trans = Transpiler::new(cfg);
+    let res = trans
+        .transpile(
+            ".C = Class { .x = Int; y = Str }
+.C.
+    .get_x self = self.x
+.f(x: Int): List(Str) = [str x]
+.n = None
+private = 1
+print! private
+"
+            .into(),
+            "exec",
+        )
+        .map_err(|es| {
+            es.errors.write_all_stderr();
+        })?;
+    assert_eq!(
+        res.object.code(),
+        "from typing import Any
+
+class C():
+    x: int
+    def __init__(self, param__: Any) -> None: ...
+    @staticmethod
+    def new(x: Any) -> C: ...
+    def get_x(self) -> int: ...
+def f(x: int) -> list[str]: ...
+n: None
+"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_transpiler_pyi_params() -> Result<(), ()> {
+    let cfg = ErgConfig {
+        transpile_target: Some(TranspileTarget::PyStub),
+        ..ErgConfig::default()
+    };
+    let mut trans = Transpiler::new(cfg);
+    let res = trans
+        .transpile(
+            ".g(x: Int, y := \"a\", opt: Int or NoneType := None): Str = y
+.id|T|(x: T): T = x
+.pair|T, U|(x: T, y: U): (T, U) = (x, y)
+.C = Class { .x = Int }
+.C.
+    .m(self, opt: Int or NoneType := None, y := \"a\"): Str = y
+"
+            .into(),
+            "exec",
+        )
+        .map_err(|es| {
+            es.errors.write_all_stderr();
+        })?;
+    assert_eq!(
+        res.object.code(),
+        "from typing import Any, TypeVar
+
+T = TypeVar(\"T\")
+U = TypeVar(\"U\")
+
+def g(x: int, y: str = ..., opt: None | int = ...) -> str: ...
+def id(x: T) -> T: ...
+def pair(x: T, y: U) -> tuple[T, U]: ...
+class C():
+    x: int
+    def __init__(self, param__: Any) -> None: ...
+    @staticmethod
+    def new(x: Any) -> C: ...
+    def m(self, opt: None | int = ..., y: str = ...) -> str: ...
+"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_transpiler_wasm() -> Result<(), ()> {
+    let cfg = ErgConfig {
+        transpile_target: Some(TranspileTarget::Wasm),
+        ..ErgConfig::default()
+    };
+    let mut trans = Transpiler::new(cfg.copy());
+    let res = trans
+        .transpile(".add(x: Int, y: Int): Int = x + y\n".into(), "exec")
+        .map_err(|es| {
+            es.errors.write_all_stderr();
+        })?;
+    assert_eq!(
+        res.object.code(),
+        "(module
+  (memory (export \"memory\") 1)
+  (func $add (export \"add\") (param $x i64) (param $y i64) (result i64)
+    local.get $x
+    local.get $y
+    i64.add
+  )
+)
+"
+    );
+    let (ext, binary) = res.object.binary().unwrap();
+    assert_eq!(ext, "wasm");
+    assert_eq!(
+        binary,
+        b"\0asm\x01\0\0\0\
+        \x01\x07\x01\x60\x02\x7e\x7e\x01\x7e\
+        \x03\x02\x01\x00\
+        \x05\x03\x01\x00\x01\
+        \x07\x10\x02\x06memory\x02\x00\x03add\x00\x00\
+        \x0a\x09\x01\x07\x00\x20\x00\x20\x01\x7c\x0b"
+    );
+    let mut trans = Transpiler::new(cfg);
+    let errs = trans
+        .transpile(
+            "math = pyimport \"math\"
+.f(x: Float): Float = math.sqrt x
+.C = Class { .x = Int }
+"
+            .into(),
+            "exec",
+        )
+        .expect_err("should err")
+        .errors;
+    assert_eq!(errs.len(), 2);
+    assert_eq!(errs.first().unwrap().core.kind, ErrorKind::FFIError);
+    assert_eq!(errs.last().unwrap().core.kind, ErrorKind::FeatureError);
+    Ok(())
+}
+
 #[test]
 fn test_builder() -> Result<(), ()> {
     let mods = ["math", "time"];
@@ -136,3 +613,23 @@ fn test_builder() -> Result<(), ()> {
     let _res = checker.build(src, "exec");
     Ok(())
 }
+
+#[test]
+fn test_builder_mutation_error() {
+    let src = "\
+arr = ![1]
+f x: Int =
+    arr.push! 1
+    x
+g a: List!(Int, _) = a.push! 1
+"
+    .to_string();
+    let cfg = ErgConfig::string(src.clone());
+    let shared = SharedCompilerResource::new(cfg.clone());
+    let mut checker = HIRBuilder::inherit(cfg, shared);
+    let errs = checker.build(src, "exec").expect_err("should err").errors;
+    assert_eq!(errs.len(), 2);
+    assert!(errs
+        .iter()
+        .all(|err| err.core.kind == ErrorKind::MutationError));
+}
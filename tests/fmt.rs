@@ -0,0 +1,75 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use erg_parser::format::{format, FormatError};
+
+fn erg_fmt(args: &[&str], file: &Path) -> Output {
+    Command::new(env!(concat!("CARGO_BIN_EXE_", env!("CARGO_PKG_NAME"))))
+        .arg("fmt")
+        .args(args)
+        .arg(file)
+        .output()
+        .expect("failed to execute `erg fmt`")
+}
+
+fn temp_file(name: &str, code: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("erg_fmt_{name}_{}.er", std::process::id()));
+    fs::write(&path, code).unwrap();
+    path
+}
+
+#[test]
+fn exec_fmt_check() {
+    let unformatted = "f(x,y:=1)=x+y\nC = Class { .x=Int }\n";
+    let file = temp_file("check", unformatted);
+    let out = erg_fmt(&["--check"], &file);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("is not formatted"));
+    // `--check` does not rewrite the file
+    assert_eq!(fs::read_to_string(&file).unwrap(), unformatted);
+
+    let out = erg_fmt(&[], &file);
+    assert_eq!(out.status.code(), Some(0));
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "f(x, y := 1) = x + y\nC = Class { .x = Int }\n"
+    );
+    let out = erg_fmt(&["--check"], &file);
+    assert_eq!(out.status.code(), Some(0));
+    let _ = fs::remove_file(&file);
+}
+
+#[test]
+fn exec_fmt_check_syntax_error() {
+    let file = temp_file("syntax_error", "print! 1 +\n");
+    let out = erg_fmt(&["--check"], &file);
+    assert_eq!(out.status.code(), Some(1));
+    let _ = fs::remove_file(&file);
+}
+
+#[test]
+fn exec_fmt_idempotent() {
+    let mut failed = vec![];
+    for dir in ["tests/should_ok", "examples"] {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|ext| ext != "er") {
+                continue;
+            }
+            let src = fs::read_to_string(&path).unwrap();
+            let once = match format(&src) {
+                Ok(once) => once,
+                Err(FormatError::Syntax(_)) => continue,
+                Err(FormatError::Unstable) => {
+                    failed.push(format!("{}: unstable", path.display()));
+                    continue;
+                }
+            };
+            if format(&once).ok().as_ref() != Some(&once) {
+                failed.push(format!("{}: not idempotent", path.display()));
+            }
+        }
+    }
+    assert!(failed.is_empty(), "{failed:#?}");
+}
@@ -0,0 +1,84 @@
+# The declarations of this module are generated by the compiler (tests/should_ok/py_decl.er)
+from dataclasses import dataclass, field
+from typing import ClassVar, Generic, Optional, TypeVar, overload
+
+__all__ = ["VERSION", "add", "first", "find", "conv", "Point", "Point3D", "Box", "join", "collect", "Item"]
+
+T = TypeVar("T")
+VERSION = "1.0"
+
+
+def add(x: int, y: int = 1) -> int:
+    return x + y
+
+
+def first(xs: "list[T]") -> T:
+    return xs[0]
+
+
+def find(xs: "list[str]", target: str, *rest: str) -> Optional[int]:
+    return xs.index(target) if target in xs else None
+
+
+@overload
+def conv(x: int) -> str: ...
+@overload
+def conv(x: str) -> int: ...
+def conv(x):
+    return str(x) if isinstance(x, int) else int(x)
+
+
+def join(xs: "list[str]", *, sep: str, end: str = "") -> str:
+    return sep.join(xs) + end
+
+
+def collect(a, /, b, *args: int, c: float = 1.0, d: int, **kw) -> int:
+    return a + b + len(args) + d
+
+
+def hidden():
+    pass
+
+
+class Point:
+    dim: int = 2
+
+    def __init__(self, x: float, y: float = 0.0):
+        self.x = x
+        self.y = y
+
+    def norm(self) -> float:
+        return (self.x**2 + self.y**2) ** 0.5
+
+    @property
+    def sum(self) -> float:
+        return self.x + self.y
+
+    @staticmethod
+    def origin() -> "Point":
+        return Point(0.0)
+
+
+class Point3D(Point):
+    def __init__(self, x: float, y: float, z: float):
+        super().__init__(x, y)
+        self.z = z
+
+
+class Box(Generic[T]):
+    def __init__(self, value: T):
+        self.value = value
+
+    def get(self) -> T:
+        return self.value
+
+
+@dataclass
+class Item:
+    name: str
+    price: float = 0.0
+    tags: "list[str]" = field(default_factory=list)
+    count: ClassVar[int] = 0
+
+    def label(self) -> str:
+        return "{}: {}".format(self.name, self.price)
@@ -1,7 +1,7 @@
 mod common;
 use common::{
     expect_compile_failure, expect_compile_success, expect_end_with, expect_error_location_and_msg,
-    expect_success,
+    expect_optimized_success, expect_success,
 };
 use erg_common::error::Location;
 use erg_common::python_util::env_python_version;
@@ -191,6 +191,11 @@ fn exec_helloworld() -> Result<(), ()> {
     }
 }
 
+#[test]
+fn exec_fold_constants() -> Result<(), ()> {
+    expect_success("tests/should_ok/fold_constants.er", 0)
+}
+
 #[test]
 fn exec_if() -> Result<(), ()> {
     expect_success("tests/should_ok/if.er", 0)
@@ -337,6 +342,11 @@ fn exec_never() -> Result<(), ()> {
     expect_success("tests/should_ok/never.er", 0)
 }
 
+#[test]
+fn exec_optimize() -> Result<(), ()> {
+    expect_optimized_success("tests/should_ok/optimize.er", 0)
+}
+
 #[test]
 fn exec_operators() -> Result<(), ()> {
     expect_success("tests/should_ok/operators.er", 0)
@@ -362,6 +372,11 @@ fn exec_poly_type_spec() -> Result<(), ()> {
     expect_success("tests/should_ok/poly_type_spec.er", 0)
 }
 
+#[test]
+fn exec_py_decl() -> Result<(), ()> {
+    expect_success("tests/should_ok/py_decl.er", 0)
+}
+
 #[test]
 fn exec_pyimport_test() -> Result<(), ()> {
     // HACK: When running the test with Windows, the exit code is 1 (the cause is unknown)
@@ -402,6 +417,11 @@ fn exec_recursive_class() -> Result<(), ()> {
     expect_success("tests/should_ok/recursive_class.er", 0)
 }
 
+#[test]
+fn exec_ref_no_cycle() -> Result<(), ()> {
+    expect_success("tests/should_ok/ref_no_cycle.er", 0)
+}
+
 #[test]
 fn exec_refinement() -> Result<(), ()> {
     expect_success("tests/should_ok/refinement.er", 0)
@@ -412,6 +432,11 @@ fn exec_refinement_class() -> Result<(), ()> {
     expect_success("tests/should_ok/refinement_class.er", 0)
 }
 
+#[test]
+fn exec_refinement_match() -> Result<(), ()> {
+    expect_success("tests/should_ok/refinement_match.er", 1)
+}
+
 #[test]
 fn exec_return() -> Result<(), ()> {
     expect_success("tests/should_ok/return.er", 0)
@@ -467,6 +492,11 @@ fn exec_trait() -> Result<(), ()> {
     expect_success("examples/trait.er", 0)
 }
 
+#[test]
+fn exec_try_operator() -> Result<(), ()> {
+    expect_success("tests/should_ok/try_operator.er", 1)
+}
+
 #[test]
 fn exec_tuple() -> Result<(), ()> {
     expect_success("examples/tuple.er", 0)
@@ -715,7 +745,7 @@ fn exec_side_effect() -> Result<(), ()> {
 
 #[test]
 fn exec_side_effect_test() -> Result<(), ()> {
-    expect_compile_failure("tests/should_err/side_effect.er", 6, 5)
+    expect_compile_failure("tests/should_err/side_effect.er", 7, 6)
 }
 
 #[test]
@@ -729,6 +759,11 @@ fn exec_subtyping_err() -> Result<(), ()> {
     expect_compile_failure("tests/should_err/subtyping.er", 3, 15)
 }
 
+#[test]
+fn exec_try_operator_err() -> Result<(), ()> {
+    expect_compile_failure("tests/should_err/try_operator.er", 0, 2)
+}
+
 #[test]
 fn exec_tuple_err() -> Result<(), ()> {
     expect_compile_failure("tests/should_err/tuple.er", 0, 2)
@@ -784,6 +819,11 @@ fn exec_recursive_fn_err() -> Result<(), ()> {
     expect_compile_failure("tests/should_err/recursive_fn.er", 0, 2)
 }
 
+#[test]
+fn exec_ref_cycle_err() -> Result<(), ()> {
+    expect_compile_failure("tests/should_err/ref_cycle.er", 0, 5)
+}
+
 #[test]
 fn exec_refinement_err() -> Result<(), ()> {
     expect_compile_failure("tests/should_err/refinement.er", 0, 10)
@@ -794,6 +834,11 @@ fn exec_refinement_class_err() -> Result<(), ()> {
     expect_compile_failure("tests/should_err/refinement_class.er", 0, 2)
 }
 
+#[test]
+fn exec_refinement_match_err() -> Result<(), ()> {
+    expect_compile_failure("tests/should_err/refinement_match.er", 0, 2)
+}
+
 #[test]
 fn exec_use_itertools_err() -> Result<(), ()> {
     expect_compile_failure("tests/should_err/use_itertools.er", 0, 1)